@@ -3,7 +3,7 @@ const SAMPLES_PER_CHUNK: u32 = SAMPLES_PER_CHUNK_DIM * SAMPLES_PER_CHUNK_DIM * S
 
 struct Params {
     chunk_start : vec3<f32>,
-    _pad : f32,
+    seed : i32,
 };
 
 struct Output {
@@ -6,7 +6,8 @@ const CHUNKS_PER_CLUSTER_DIM: u32 = 5u;
 
 struct Params {
     chunk_start: vec2<f32>,
-    _pad: vec2<f32>,
+    seed: i32,
+    _pad: f32,
 };
 
 struct Output {
@@ -15,7 +16,7 @@ struct Output {
 
 struct ClusterParams {
     cluster_lower_chunk: vec3<i32>,
-    _pad: i32,
+    seed: i32,
 };
 
 struct ClusterOutput {
@@ -25,7 +26,8 @@ struct ClusterOutput {
 // New: batch cluster structures
 struct BatchClusterParams {
     cluster_count: u32,
-    _pad: vec3<u32>,
+    seed: i32,
+    _pad: vec2<u32>,
     cluster_coords: array<vec2<i32>>,
 };
 
@@ -50,31 +52,32 @@ var<storage, read> batch_cluster_params: BatchClusterParams;
 @group(2) @binding(1)
 var<storage, read_write> batch_cluster_output: ClusterOutput;
 
-fn hash(p: vec2<f32>) -> f32 {
-    var p3 = fract(vec3<f32>(p.x, p.y, p.x) * 0.1031);
+// The world seed shifts the hash input so each seed gets its own lattice values
+fn hash(p: vec2<f32>, seed: i32) -> f32 {
+    var p3 = fract(vec3<f32>(p.x, p.y, p.x) * 0.1031 + f32(seed & 0xffff) * 0.0173);
     p3 += dot(p3, vec3<f32>(p3.y, p3.z, p3.x) + 33.33);
     return fract((p3.x + p3.y) * p3.z);
 }
 
-fn noise(p: vec2<f32>) -> f32 {
+fn noise(p: vec2<f32>, seed: i32) -> f32 {
     let i = floor(p);
     let f = fract(p);
     let u = f * f * (3.0 - 2.0 * f);
-    let a = hash(i);
-    let b = hash(i + vec2<f32>(1.0, 0.0));
-    let c = hash(i + vec2<f32>(0.0, 1.0));
-    let d = hash(i + vec2<f32>(1.0, 1.0));
+    let a = hash(i, seed);
+    let b = hash(i + vec2<f32>(1.0, 0.0), seed);
+    let c = hash(i + vec2<f32>(0.0, 1.0), seed);
+    let d = hash(i + vec2<f32>(1.0, 1.0), seed);
     //return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
     return 0.0;
 }
 
-fn fbm(p: vec2<f32>) -> f32 {
+fn fbm(p: vec2<f32>, seed: i32) -> f32 {
     var value = 0.0;
     var amplitude = 0.5;
     var frequency = 1.0;
     var pos = p;
     for (var i = 0; i < 4; i++) {
-        value += amplitude * noise(pos * frequency);
+        value += amplitude * noise(pos * frequency, seed);
         frequency *= 2.0;
         amplitude *= 0.5;
     }
@@ -94,7 +97,7 @@ fn generate_heightmap(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let spacing = f32(SAMPLES_PER_CHUNK_DIM) / f32(NOISE_SAMPLES_DIM - 1);
     let world_pos = single_params.chunk_start + vec2<f32>(f32(x) * spacing, f32(z) * spacing);
     
-    let height = fbm(world_pos * 0.01) * 100.0;
+    let height = fbm(world_pos * 0.01, single_params.seed) * 100.0;
     single_output.heights[sample_index] = height;
 }
 
@@ -116,7 +119,7 @@ fn generate_cluster_heightmap(@builtin(global_invocation_id) global_id: vec3<u32
     let spacing = f32(SAMPLES_PER_CHUNK_DIM) / f32(NOISE_SAMPLES_DIM - 1);
     let world_pos = chunk_start + vec2<f32>(f32(x) * spacing, f32(z) * spacing);
     
-    let height = fbm(world_pos * 0.01) * 100.0;
+    let height = fbm(world_pos * 0.01, cluster_params.seed) * 100.0;
     let output_index = chunk_index * NOISE_SAMPLES_PER_CHUNK + sample_index;
     cluster_output.heights[output_index] = height;
 }
@@ -155,7 +158,7 @@ fn generate_batch_clusters(@builtin(global_invocation_id) global_id: vec3<u32>)
     let world_pos = chunk_start + vec2<f32>(f32(x) * spacing, f32(z) * spacing);
     
     // Generate height
-    let height = fbm(world_pos * 0.01) * 100.0;
+    let height = fbm(world_pos * 0.01, batch_cluster_params.seed) * 100.0;
     
     // Calculate output index
     let chunks_per_cluster = CHUNKS_PER_CLUSTER_DIM * CHUNKS_PER_CLUSTER_DIM;
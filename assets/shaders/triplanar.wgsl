@@ -9,12 +9,20 @@
 @group(3) @binding(103) var base_texture: texture_2d_array<f32>;
 @group(3) @binding(104) var base_sampler: sampler;
 @group(3) @binding(105) var<uniform> scale: f32;
+@group(3) @binding(106) var<uniform> clip_plane: vec4<f32>;
+@group(3) @binding(107) var<uniform> morph_center: vec3<f32>;
+@group(3) @binding(108) var<uniform> dither_fade: f32;
+
+const MORPH_BAND: f32 = 0.3; //fraction of the lod radius, nearest the edge, over which vertices slide to their coarse position
 
 struct Vertex {
     @builtin(instance_index) instance_index: u32,
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) material_id: u32,
+#ifdef LOD_MORPH
+    @location(3) coarse_position: vec4<f32>,
+#endif
 }
 
 struct CustomVertexOutput {
@@ -22,23 +30,89 @@ struct CustomVertexOutput {
     @location(0) world_position: vec4<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) @interpolate(flat) material_id: u32,
+    @location(3) blend_weight: f32,
 }
 
 @vertex
 fn vertex(vertex: Vertex) -> CustomVertexOutput {
     var out: CustomVertexOutput;
     let world_from_local = get_world_from_local(vertex.instance_index);
-    out.world_position = world_from_local * vec4<f32>(vertex.position, 1.0);
-    out.clip_position = mesh_position_local_to_clip(world_from_local, vec4<f32>(vertex.position, 1.0));
+    var position = vertex.position;
+#ifdef LOD_MORPH
+    //geomorphing, w is the radius the next coarser lod takes over at so the swap lands on a vertex already there
+    //normals are left as the fine mesh has them, the silhouette is what pops
+    let end_radius = vertex.coarse_position.w;
+    let fine_distance = distance((world_from_local * vec4<f32>(position, 1.0)).xyz, morph_center);
+    let band = end_radius * MORPH_BAND;
+    let morph = clamp((fine_distance - (end_radius - band)) / band, 0.0, 1.0);
+    position = mix(position, vertex.coarse_position.xyz, morph);
+#endif
+    out.world_position = world_from_local * vec4<f32>(position, 1.0);
+    out.clip_position = mesh_position_local_to_clip(world_from_local, vec4<f32>(position, 1.0));
     out.world_normal = mat3x3<f32>(
         world_from_local[0].xyz,
         world_from_local[1].xyz,
         world_from_local[2].xyz
     ) * vertex.normal;
+    //low byte is the material, blended triangles also pack the secondary material and its weight at this vertex
     out.material_id = vertex.material_id;
+    out.blend_weight = f32((vertex.material_id >> 16u) & 0xFFu) / 255.0;
     return out;
 }
 
+//ordered dither threshold of a pixel, in (0, 1)
+fn bayer4(pixel: vec2<f32>) -> f32 {
+    var matrix = array<f32, 16>(0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0);
+    let p = vec2<u32>(pixel) % vec2<u32>(4u);
+    return (matrix[p.y * 4u + p.x] + 0.5) / 16.0;
+}
+
+//derivatives come in from the caller so this can be called from non uniform control flow
+fn sample_material(
+    id: u32,
+    world_pos: vec3<f32>,
+    blend: vec3<f32>,
+    dpos_dx: vec3<f32>,
+    dpos_dy: vec3<f32>,
+) -> vec3<f32> {
+    var layer = 0;
+    if (id == 2u) {
+        layer = 1;
+    } else if (id == 3u || id == 5u || id == 6u) {
+        layer = 2;
+    }
+    let scale_vec = vec2(scale);
+    let uv_x_raw = world_pos.yz * scale_vec;
+    let uv_y_raw = world_pos.xz * scale_vec;
+    let uv_z_raw = world_pos.xy * scale_vec;
+    let uv_x = fract(uv_x_raw);
+    let uv_y = fract(uv_y_raw);
+    let uv_z = fract(uv_z_raw);
+    let duvdx_x = dpos_dx.yz * scale_vec;
+    let duvdy_x = dpos_dy.yz * scale_vec;
+    let duvdx_y = dpos_dx.xz * scale_vec;
+    let duvdy_y = dpos_dy.xz * scale_vec;
+    let duvdx_z = dpos_dx.xy * scale_vec;
+    let duvdy_z = dpos_dy.xy * scale_vec;
+    let color_x = textureSampleGrad(base_texture, base_sampler, uv_x, layer, duvdx_x, duvdy_x).rgb;
+    let color_y = textureSampleGrad(base_texture, base_sampler, uv_y, layer, duvdx_y, duvdy_y).rgb;
+    let color_z = textureSampleGrad(base_texture, base_sampler, uv_z, layer, duvdx_z, duvdy_z).rgb;
+    var color = color_x * blend.x + color_y * blend.y + color_z * blend.z;
+    if (id == 4u) {
+        //path reuses the dirt layer, packed and bleached
+        color = color * vec3(1.15, 1.05, 0.9);
+    } else if (id == 5u) {
+        //snow reuses the sand layer, desaturated and pushed towards white
+        let luma = dot(color, vec3(0.299, 0.587, 0.114));
+        color = mix(vec3(luma), vec3(0.95, 0.97, 1.0), 0.75);
+    } else if (id == 6u) {
+        //water reuses the sand layer for a little surface detail, mostly a flat blue green
+        let luma = dot(color, vec3(0.299, 0.587, 0.114));
+        color = mix(vec3(0.12, 0.3, 0.38), vec3(luma), 0.15);
+    }
+    return color;
+}
+
 @fragment
 fn fragment(
     in: CustomVertexOutput,
@@ -48,39 +122,41 @@ fn fragment(
     standard_in.position = in.clip_position;
     standard_in.world_position = in.world_position;
     standard_in.world_normal = in.world_normal;
-    var pbr_input = pbr_input_from_standard_material(standard_in, is_front);
     let world_pos = in.world_position.xyz;
+    //debug cutaway, a zero normal means no plane
+    let clipping = any(clip_plane.xyz != vec3(0.0));
+    if (clipping && dot(clip_plane.xyz, world_pos) > clip_plane.w) {
+        discard;
+    }
+    //lod swaps cross fade through a screen door, the incoming and outgoing meshes draw complementary pixels
+    if (dither_fade != 0.0) {
+        let threshold = bayer4(in.clip_position.xy);
+        if ((dither_fade > 0.0 && threshold >= dither_fade) || (dither_fade < 0.0 && threshold < -dither_fade)) {
+            discard;
+        }
+    }
+    var pbr_input = pbr_input_from_standard_material(standard_in, is_front);
     let world_normal = normalize(in.world_normal);
     var blend = abs(world_normal);
     blend = pow(blend, vec3(4.0));
     blend = blend / (blend.x + blend.y + blend.z);
-    let id = i32(in.material_id);
-    var layer = 0;
-    if (id == 2) {
-        layer = 1;
-    } else if (id == 3) {
-        layer = 2;
+    let dpos_dx = dpdx(world_pos);
+    let dpos_dy = dpdy(world_pos);
+    let id = in.material_id & 0xFFu;
+    var final_color = sample_material(id, world_pos, blend, dpos_dx, dpos_dy);
+    if (in.blend_weight > 0.0) {
+        let secondary = (in.material_id >> 8u) & 0xFFu;
+        let secondary_color = sample_material(secondary, world_pos, blend, dpos_dx, dpos_dy);
+        final_color = mix(final_color, secondary_color, in.blend_weight);
     }
-    let scale_vec = vec2(scale);
-    let uv_x_raw = world_pos.yz * scale_vec;
-    let uv_y_raw = world_pos.xz * scale_vec;
-    let uv_z_raw = world_pos.xy * scale_vec;
-    let uv_x = fract(uv_x_raw);
-    let uv_y = fract(uv_y_raw);
-    let uv_z = fract(uv_z_raw);
-    let duvdx_x = dpdx(uv_x_raw);
-    let duvdy_x = dpdy(uv_x_raw);
-    let duvdx_y = dpdx(uv_y_raw);
-    let duvdy_y = dpdy(uv_y_raw);
-    let duvdx_z = dpdx(uv_z_raw);
-    let duvdy_z = dpdy(uv_z_raw);
-    let color_x = textureSampleGrad(base_texture, base_sampler, uv_x, layer, duvdx_x, duvdy_x).rgb;
-    let color_y = textureSampleGrad(base_texture, base_sampler, uv_y, layer, duvdx_y, duvdy_y).rgb;
-    let color_z = textureSampleGrad(base_texture, base_sampler, uv_z, layer, duvdx_z, duvdy_z).rgb;
-    let final_color = color_x * blend.x + color_y * blend.y + color_z * blend.z;
     pbr_input.material.base_color = vec4<f32>(final_color, 1.0);
     pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
     var out: FragmentOutput;
+    if (clipping && !is_front) {
+        //looking through the cut at the inside of a surface, the ground is solid here so draw it flat and dark
+        out.color = vec4<f32>(final_color * 0.3, 1.0);
+        return out;
+    }
     out.color = apply_pbr_lighting(pbr_input);
     out.color = main_pass_post_lighting_processing(pbr_input, out.color);
     return out;
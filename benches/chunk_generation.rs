@@ -1,19 +1,23 @@
 #[path = "bench_util.rs"]
 mod bench_util;
 
+use bevy::math::Vec3;
 use criterion::{Criterion, criterion_group, criterion_main};
 
 use marching_cubes::{
+    chunk_coord::ChunkCoord,
     constants::{
         SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_2D_PADDED, SAMPLES_PER_CHUNK_DIM,
         SAMPLES_PER_CHUNK_PADDED,
     },
     deformable_terrain::{
         chunk_generator::{
-            calculate_chunk_start, chunk_contains_surface, compute_heightmap_gradients, downscale,
-            fast_get_uniformity, fill_voxel_densities, generate_chunk_into_buffers,
-            generate_noise_height_samples, generate_terrain_heights, get_fbm,
+            MaterialCode, calculate_chunk_start, chunk_contains_surface,
+            compute_heightmap_gradients, downscale, fast_get_uniformity, fill_voxel_densities,
+            generate_chunk_into_buffers, generate_noise_height_samples, generate_terrain_heights,
+            get_fbm,
         },
+        density_source::{DensitySource, NoiseTerrain},
         driver::{ChunkBuffers, LodBuffers},
         marching_cubes::mc::mc_mesh_generation,
         plugin::Uniformity,
@@ -24,7 +28,7 @@ use std::{hint::black_box, time::Duration};
 use crate::bench_util::find_chunk_with_surface;
 
 fn benchmark_generate_chunk_into_buffers(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let chunk_coord = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let fbm = get_fbm();
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
@@ -41,7 +45,7 @@ fn benchmark_generate_chunk_into_buffers(c: &mut Criterion) {
         &chunk_buffers.dhdz,
         &chunk_start,
     );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
     assert_eq!(uniformity, Uniformity::NonUniform);
     assert!(chunk_contains_surface(&chunk_buffers.density));
     c.bench_function("generate_chunk_into_buffers", |b| {
@@ -49,6 +53,7 @@ fn benchmark_generate_chunk_into_buffers(c: &mut Criterion) {
             black_box(generate_chunk_into_buffers(
                 black_box(chunk_start),
                 black_box(&mut chunk_buffers),
+                black_box(&fbm),
             ));
         })
     });
@@ -56,7 +61,7 @@ fn benchmark_generate_chunk_into_buffers(c: &mut Criterion) {
 
 fn benchmark_generate_uniform_densities_cpu(c: &mut Criterion) {
     let fbm = get_fbm();
-    let chunk_start = calculate_chunk_start(&(0, 2000, 0));
+    let chunk_start = calculate_chunk_start(&ChunkCoord(0, 2000, 0));
     let mut chunk_buffers = ChunkBuffers::new();
     let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
     generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
@@ -70,13 +75,14 @@ fn benchmark_generate_uniform_densities_cpu(c: &mut Criterion) {
             black_box(generate_chunk_into_buffers(
                 black_box(chunk_start),
                 black_box(&mut chunk_buffers),
+                black_box(&fbm),
             ))
         })
     });
 }
 
 fn benchmark_marching_cubes(c: &mut Criterion) {
-    let chunk = find_chunk_with_surface();
+    let chunk = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let fbm = get_fbm();
     let chunk_start = calculate_chunk_start(&chunk);
     let mut chunk_buffers = ChunkBuffers::new();
@@ -87,7 +93,7 @@ fn benchmark_marching_cubes(c: &mut Criterion) {
         &mut chunk_buffers.dhdz,
         &noise_samples,
     );
-    generate_chunk_into_buffers(chunk_start, black_box(&mut chunk_buffers));
+    generate_chunk_into_buffers(chunk_start, black_box(&mut chunk_buffers), &fbm);
     c.bench_function("marching_cubes", |b| {
         b.iter(|| {
             black_box(mc_mesh_generation(
@@ -102,7 +108,7 @@ fn benchmark_marching_cubes(c: &mut Criterion) {
 }
 
 fn benchmark_compute_heightmap_gradients(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let chunk_coord = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let fbm = get_fbm();
     let chunk_start = calculate_chunk_start(&chunk_coord);
     let height_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
@@ -120,7 +126,7 @@ fn benchmark_compute_heightmap_gradients(c: &mut Criterion) {
 }
 
 fn bench_downscale(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let chunk_coord = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let mut chunk_buffers = ChunkBuffers::new();
     let mut lod_buffers = LodBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
@@ -132,7 +138,7 @@ fn bench_downscale(c: &mut Criterion) {
         &mut chunk_buffers.dhdz,
         &noise_samples,
     );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
     let uniformity = fast_get_uniformity(
         &chunk_buffers.heightmap,
         &chunk_buffers.dhdx,
@@ -215,7 +221,7 @@ fn bench_generate_terrain_heights(c: &mut Criterion) {
 }
 
 fn bench_fill_voxel_densities(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let chunk_coord = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let fbm = get_fbm();
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
@@ -226,7 +232,7 @@ fn bench_fill_voxel_densities(c: &mut Criterion) {
         &mut chunk_buffers.dhdz,
         &noise_samples,
     );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
     let uniformity = fast_get_uniformity(
         &chunk_buffers.heightmap,
         &chunk_buffers.dhdx,
@@ -243,7 +249,7 @@ fn bench_fill_voxel_densities(c: &mut Criterion) {
 }
 
 fn bench_fast_get_uniformity_uniform(c: &mut Criterion) {
-    let chunk_coord = (0, 2000, 0);
+    let chunk_coord = ChunkCoord(0, 2000, 0);
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
     let fbm = get_fbm();
@@ -254,7 +260,7 @@ fn bench_fast_get_uniformity_uniform(c: &mut Criterion) {
         &mut chunk_buffers.dhdz,
         &noise_samples,
     );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
     let uniformity = fast_get_uniformity(
         &chunk_buffers.heightmap,
         &chunk_buffers.dhdx,
@@ -275,7 +281,7 @@ fn bench_fast_get_uniformity_uniform(c: &mut Criterion) {
 }
 
 fn bench_fast_get_uniformity_non_uniform(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let chunk_coord = find_chunk_with_surface(&NoiseTerrain::new(get_fbm()));
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
     let fbm = get_fbm();
@@ -286,7 +292,7 @@ fn bench_fast_get_uniformity_non_uniform(c: &mut Criterion) {
         &mut chunk_buffers.dhdz,
         &noise_samples,
     );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
     let uniformity = fast_get_uniformity(
         &chunk_buffers.heightmap,
         &chunk_buffers.dhdx,
@@ -306,6 +312,48 @@ fn bench_fast_get_uniformity_non_uniform(c: &mut Criterion) {
     });
 }
 
+//only implements sample so the trait's default per sample fill is what gets measured
+struct SampledOnly(NoiseTerrain);
+
+impl DensitySource for SampledOnly {
+    fn sample(&self, world_pos: Vec3) -> (f32, MaterialCode) {
+        self.0.sample(world_pos)
+    }
+}
+
+fn bench_density_source_fill<S: DensitySource>(
+    c: &mut Criterion,
+    name: &str,
+    source: &S,
+    chunk_coord: ChunkCoord,
+) {
+    let mut chunk_buffers = ChunkBuffers::new();
+    let chunk_start = calculate_chunk_start(&chunk_coord);
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            source.fill_chunk(black_box(chunk_start), black_box(&mut chunk_buffers));
+        })
+    });
+}
+
+fn bench_density_source_fill_noise_terrain(c: &mut Criterion) {
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
+    bench_density_source_fill(c, "density_source_fill_noise_terrain", &source, chunk_coord);
+}
+
+fn bench_density_source_fill_per_sample(c: &mut Criterion) {
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source); //the per sample fill is too slow to search with
+    bench_density_source_fill(
+        c,
+        "density_source_fill_per_sample",
+        &SampledOnly(source),
+        chunk_coord,
+    );
+}
+
 criterion_group!(
     benches,
     benchmark_generate_chunk_into_buffers,
@@ -321,6 +369,8 @@ criterion_group!(
     bench_fill_voxel_densities,
     bench_fast_get_uniformity_uniform,
     bench_fast_get_uniformity_non_uniform,
+    bench_density_source_fill_noise_terrain,
+    bench_density_source_fill_per_sample,
 );
 criterion_main!(benches);
 
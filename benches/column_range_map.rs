@@ -1,6 +1,7 @@
 use std::hint::black_box;
 
 use criterion::{Criterion, criterion_group, criterion_main};
+use marching_cubes::chunk_coord::ChunkCoord;
 use marching_cubes::deformable_terrain::{column_range_map::ColumnRangeMap, plugin::Uniformity};
 
 fn benchmark_insert_sequential(c: &mut Criterion) {
@@ -8,7 +9,7 @@ fn benchmark_insert_sequential(c: &mut Criterion) {
         b.iter(|| {
             let mut map = ColumnRangeMap::new();
             for y in -100..100 {
-                black_box(map.insert(black_box((0, y, 0)), black_box(Uniformity::Air)));
+                black_box(map.insert(black_box(ChunkCoord(0, y, 0)), black_box(Uniformity::Air)));
             }
             black_box(map);
         })
@@ -21,7 +22,7 @@ fn benchmark_insert_random_order(c: &mut Criterion) {
         b.iter(|| {
             let mut map = ColumnRangeMap::new();
             for &y in &ys {
-                black_box(map.insert(black_box((0, y, 0)), black_box(Uniformity::Air)));
+                black_box(map.insert(black_box(ChunkCoord(0, y, 0)), black_box(Uniformity::Air)));
             }
             black_box(map);
         })
@@ -35,7 +36,9 @@ fn benchmark_insert_many_columns(c: &mut Criterion) {
             for x in -10..10 {
                 for z in -10..10 {
                     for y in -5..5 {
-                        black_box(map.insert(black_box((x, y, z)), black_box(Uniformity::Air)));
+                        black_box(
+                            map.insert(black_box(ChunkCoord(x, y, z)), black_box(Uniformity::Air)),
+                        );
                     }
                 }
             }
@@ -54,7 +57,7 @@ fn benchmark_insert_interleaved_uniformities(c: &mut Criterion) {
                 } else {
                     Uniformity::Air
                 };
-                black_box(map.insert(black_box((0, y, 0)), black_box(uniformity)));
+                black_box(map.insert(black_box(ChunkCoord(0, y, 0)), black_box(uniformity)));
             }
             black_box(map);
         })
@@ -66,14 +69,14 @@ fn benchmark_contains_hit(c: &mut Criterion) {
     for x in -10..10 {
         for z in -10..10 {
             for y in -1000..100 {
-                map.insert((x, y, z), Uniformity::Air);
+                map.insert(ChunkCoord(x, y, z), Uniformity::Air);
             }
         }
     }
     c.bench_function("contains_hit", |b| {
         b.iter(|| {
             for y in -100..100 {
-                black_box(map.contains(black_box((0, y, 0))));
+                black_box(map.contains(black_box(ChunkCoord(0, y, 0))));
             }
         })
     });
@@ -84,14 +87,14 @@ fn benchmark_contains_miss(c: &mut Criterion) {
     for x in -10..10 {
         for z in -10..10 {
             for y in -1000..100 {
-                map.insert((x, y, z), Uniformity::Air);
+                map.insert(ChunkCoord(x, y, z), Uniformity::Air);
             }
         }
     }
     c.bench_function("contains_miss", |b| {
         b.iter(|| {
             for y in 100..200 {
-                black_box(map.contains(black_box((0, y, 0))));
+                black_box(map.contains(black_box(ChunkCoord(0, y, 0))));
             }
         })
     });
@@ -102,7 +105,7 @@ fn benchmark_contains_many_columns(c: &mut Criterion) {
     for x in -10..10 {
         for z in -10..10 {
             for y in -5..5 {
-                map.insert((x, y, z), Uniformity::Air);
+                map.insert(ChunkCoord(x, y, z), Uniformity::Air);
             }
         }
     }
@@ -111,7 +114,7 @@ fn benchmark_contains_many_columns(c: &mut Criterion) {
             for x in -10..10 {
                 for z in -10..10 {
                     for y in -5..5 {
-                        black_box(map.contains(black_box((x, y, z))));
+                        black_box(map.contains(black_box(ChunkCoord(x, y, z))));
                     }
                 }
             }
@@ -124,9 +127,9 @@ fn benchmark_mixed_operations(c: &mut Criterion) {
         b.iter(|| {
             let mut map = ColumnRangeMap::new();
             for y in -50..50 {
-                black_box(map.insert(black_box((0, y, 0)), black_box(Uniformity::Air)));
+                black_box(map.insert(black_box(ChunkCoord(0, y, 0)), black_box(Uniformity::Air)));
                 if y % 5 == 0 {
-                    black_box(map.contains(black_box((0, y - 10, 0))));
+                    black_box(map.contains(black_box(ChunkCoord(0, y - 10, 0))));
                 }
             }
             black_box(map);
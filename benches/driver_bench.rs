@@ -2,51 +2,40 @@ use crate::bench_util::find_chunk_with_surface;
 use criterion::{Criterion, criterion_group, criterion_main};
 use crossbeam_channel::unbounded;
 use marching_cubes::deformable_terrain::chunk_generator::{
-    calculate_chunk_start, chunk_contains_surface, compute_heightmap_gradients,
-    fast_get_uniformity, generate_chunk_into_buffers, generate_noise_height_samples,
-    generate_terrain_heights, get_fbm,
+    calculate_chunk_start, chunk_contains_surface, get_fbm,
 };
+use marching_cubes::deformable_terrain::density_source::{DensitySource, NoiseTerrain};
 use marching_cubes::deformable_terrain::driver::{
     ChunkBuffers, ChunkSpawnResult, ClusterRequest, FullLodMode, LoadStateTransition, LodBuffers,
     build_full_mesh_and_spawn, lod_resolve_has_surface, try_load_chunk,
 };
-use marching_cubes::deformable_terrain::file_loader::load_chunk_index_map;
+use marching_cubes::deformable_terrain::file_loader::{
+    RegionFiles, RegionMaps, load_chunk_index_map,
+};
 use marching_cubes::deformable_terrain::plugin::Uniformity;
 use parking_lot::RwLock;
 use rustc_hash::FxHashMap;
-#[cfg(windows)]
-use std::fs::File;
-use std::fs::OpenOptions;
 use std::hint::black_box;
+use std::path::Path;
 
 #[path = "bench_util.rs"]
 mod bench_util;
 
 fn benchmark_build_full_mesh_and_spawn_with_collider(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-    let uniformity = fast_get_uniformity(
-        &chunk_buffers.heightmap,
-        &chunk_buffers.dhdx,
-        &chunk_buffers.dhdz,
-        &chunk_start,
-    );
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
+    let uniformity = source.classify(&chunk_start, &mut chunk_buffers);
     let cluster_request = ClusterRequest {
         position: (0, 0, 0),                                //shouldnt matter
         distance_squared: 0.0,                              //shouldnt matter
         load_state_transition: LoadStateTransition::ToFull, //shouldnt matter
         prev_has_entity: None,
         prev_in_simulation_radius: false,
+        queued_secs: 0.0,
     };
     let (chunk_spawn_sender, _chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
     assert_eq!(uniformity, Uniformity::NonUniform);
@@ -67,30 +56,20 @@ fn benchmark_build_full_mesh_and_spawn_with_collider(c: &mut Criterion) {
 }
 
 fn benchmark_build_full_mesh_and_spawn_no_collider(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-    let uniformity = fast_get_uniformity(
-        &chunk_buffers.heightmap,
-        &chunk_buffers.dhdx,
-        &chunk_buffers.dhdz,
-        &chunk_start,
-    );
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
+    let uniformity = source.classify(&chunk_start, &mut chunk_buffers);
     let cluster_request = ClusterRequest {
         position: (0, 0, 0),                                //shouldnt matter
         distance_squared: 0.0,                              //shouldnt matter
         load_state_transition: LoadStateTransition::ToFull, //shouldnt matter
         prev_has_entity: None,
         prev_in_simulation_radius: false,
+        queued_secs: 0.0,
     };
     let (chunk_spawn_sender, _chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
     assert_eq!(uniformity, Uniformity::NonUniform);
@@ -111,25 +90,20 @@ fn benchmark_build_full_mesh_and_spawn_no_collider(c: &mut Criterion) {
 }
 
 fn bench_resolve_has_surface_lod5(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let mut lod_buffers = LodBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    let _uniformity = generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
     let cluster_request = ClusterRequest {
         position: (0, 0, 0),   //shouldnt matter
         distance_squared: 0.0, //shouldnt matter
         load_state_transition: LoadStateTransition::ToLod5,
         prev_has_entity: None, //shouldnt matter
         prev_in_simulation_radius: false,
+        queued_secs: 0.0,
     };
     let (chunk_spawn_sender, _chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
     c.bench_function("resolve_has_surface_lod5", |b| {
@@ -147,25 +121,20 @@ fn bench_resolve_has_surface_lod5(c: &mut Criterion) {
 }
 
 fn bench_resolve_has_surface_lod1(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let mut lod_buffers = LodBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    let _uniformity = generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
     let cluster_request = ClusterRequest {
         position: (0, 0, 0),   //shouldnt matter
         distance_squared: 0.0, //shouldnt matter
         load_state_transition: LoadStateTransition::ToLod1,
         prev_has_entity: None, //shouldnt matter
         prev_in_simulation_radius: false,
+        queued_secs: 0.0,
     };
     let (chunk_spawn_sender, _chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
     c.bench_function("resolve_has_surface_lod1", |b| {
@@ -183,25 +152,20 @@ fn bench_resolve_has_surface_lod1(c: &mut Criterion) {
 }
 
 fn bench_resolve_has_surface_full_collider(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let mut lod_buffers = LodBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    let _uniformity = generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
     let cluster_request = ClusterRequest {
         position: (0, 0, 0),   //shouldnt matter
         distance_squared: 0.0, //shouldnt matter
         load_state_transition: LoadStateTransition::ToFullWithCollider,
         prev_has_entity: None, //shouldnt matter
         prev_in_simulation_radius: false,
+        queued_secs: 0.0,
     };
     let (chunk_spawn_sender, _chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
     c.bench_function("resolve_has_surface_full_collider", |b| {
@@ -219,39 +183,25 @@ fn bench_resolve_has_surface_full_collider(c: &mut Criterion) {
 }
 
 fn bench_try_load_chunk_fail(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-    let uniformity = fast_get_uniformity(
-        &chunk_buffers.heightmap,
-        &chunk_buffers.dhdx,
-        &chunk_buffers.dhdz,
-        &chunk_start,
-    );
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
+    let uniformity = source.classify(&chunk_start, &mut chunk_buffers);
     let index_map_delta = RwLock::new(FxHashMap::default());
     let index_map_read = FxHashMap::default();
-    #[cfg(windows)]
-    let mut chunk_data_file_read = File::open("NUL").unwrap();
-    #[cfg(not(windows))]
-    let mut chunk_data_file_read = File::open("/dev/null").unwrap();
+    let mut region_files_read = RegionFiles::reader(Path::new("benches/bench_data"));
     assert_eq!(uniformity, Uniformity::NonUniform);
     assert!(chunk_contains_surface(&chunk_buffers.density));
     c.bench_function("try_load_chunk_fail", |b| {
         b.iter(|| {
-            black_box(try_load_chunk(
+            let _ = black_box(try_load_chunk(
                 black_box(chunk_coord),
                 black_box(&index_map_read),
                 black_box(&index_map_delta),
-                black_box(&mut chunk_data_file_read),
+                black_box(&mut region_files_read),
                 black_box(&mut chunk_buffers),
             ));
         })
@@ -259,46 +209,45 @@ fn bench_try_load_chunk_fail(c: &mut Criterion) {
 }
 
 fn bench_try_load_chunk_success(c: &mut Criterion) {
-    let chunk_coord = find_chunk_with_surface();
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
     let mut chunk_buffers = ChunkBuffers::new();
     let chunk_start = calculate_chunk_start(&chunk_coord);
-    let fbm = get_fbm();
-    let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
-    generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
-    compute_heightmap_gradients(
-        &mut chunk_buffers.dhdx,
-        &mut chunk_buffers.dhdz,
-        &noise_samples,
-    );
-    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-    let uniformity = fast_get_uniformity(
-        &chunk_buffers.heightmap,
-        &chunk_buffers.dhdx,
-        &chunk_buffers.dhdz,
-        &chunk_start,
-    );
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
+    let uniformity = source.classify(&chunk_start, &mut chunk_buffers);
     let index_map_delta = RwLock::new(FxHashMap::default());
-    let mut chunk_index_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open("benches/bench_data/chunk_index_data.txt")
-        .unwrap();
-    let index_map_read = load_chunk_index_map(&mut chunk_index_file);
-    let mut chunk_data_file_read = File::open("benches/bench_data/chunk_data.txt").unwrap();
+    //bench_data is converted to region files the first time with `migrate region --data benches/bench_data`
+    let bench_data = Path::new("benches/bench_data");
+    let index_map_read = load_chunk_index_map(
+        bench_data,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
     assert_eq!(uniformity, Uniformity::NonUniform);
     assert!(chunk_contains_surface(&chunk_buffers.density));
-    c.bench_function("try_load_chunk_success", |b| {
-        b.iter(|| {
-            black_box(try_load_chunk(
-                black_box(chunk_coord),
-                black_box(&index_map_read),
-                black_box(&index_map_delta),
-                black_box(&mut chunk_data_file_read),
-                black_box(&mut chunk_buffers),
-            ));
-        })
-    });
+    //the same chunk every iteration, so both read from the page cache and only the read path differs
+    let mut group = c.benchmark_group("try_load_chunk_success");
+    for (name, mut region_files_read) in [
+        ("seek_read", RegionFiles::reader(bench_data)),
+        (
+            "mapped",
+            RegionFiles::mapped_reader(bench_data, RegionMaps::default()),
+        ),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let _ = black_box(try_load_chunk(
+                    black_box(chunk_coord),
+                    black_box(&index_map_read),
+                    black_box(&index_map_delta),
+                    black_box(&mut region_files_read),
+                    black_box(&mut chunk_buffers),
+                ));
+            })
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(
@@ -1,3 +1,76 @@
+use crate::bench_util::find_chunk_with_surface;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use marching_cubes::deformable_terrain::chunk_generator::{calculate_chunk_start, get_fbm};
+use marching_cubes::deformable_terrain::density_source::{DensitySource, NoiseTerrain};
+use marching_cubes::deformable_terrain::driver::ChunkBuffers;
+use marching_cubes::deformable_terrain::file_loader::{
+    ChunkCodec, ChunkReadBuffers, read_chunk_at, serialize_chunk_data,
+};
+use std::fs::OpenOptions;
+use std::hint::black_box;
+use std::io::Write;
+
+#[path = "bench_util.rs"]
+mod bench_util;
+
+//load throughput of one surface chunk per codec, read back from a file so the disk read is part of it
+fn benchmark_load_chunk_by_codec(c: &mut Criterion) {
+    let source = NoiseTerrain::new(get_fbm());
+    let chunk_coord = find_chunk_with_surface(&source);
+    let mut chunk_buffers = ChunkBuffers::new();
+    let chunk_start = calculate_chunk_start(&chunk_coord);
+    source.prepare_column(&chunk_start, &mut chunk_buffers);
+    source.fill_chunk(chunk_start, &mut chunk_buffers);
+    let uncompressed_size = chunk_buffers.density.len() * 2 + chunk_buffers.material.len();
+    let mut group = c.benchmark_group("load_chunk");
+    group.throughput(Throughput::Bytes(uncompressed_size as u64));
+    for (name, codec) in [
+        ("raw", ChunkCodec::Raw),
+        ("lz4", ChunkCodec::Lz4),
+        ("run_length", ChunkCodec::RunLength),
+    ] {
+        let path = std::env::temp_dir().join(format!("marching_cubes_file_io_{name}.bin"));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let mut serial_buffer = Vec::new();
+        let blob = serialize_chunk_data(
+            &chunk_buffers.density,
+            &chunk_buffers.material,
+            codec,
+            &mut serial_buffer,
+        );
+        file.write_all(blob).unwrap();
+        println!("{name}: {} of {uncompressed_size} bytes stored", blob.len());
+        let mut read_buffers = ChunkReadBuffers::default();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                read_chunk_at(
+                    black_box(&mut file),
+                    black_box(0),
+                    black_box(&mut read_buffers),
+                    black_box(&mut chunk_buffers.density),
+                    black_box(&mut chunk_buffers.material),
+                )
+                .unwrap();
+            })
+        });
+        let _ = std::fs::remove_file(&path);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_load_chunk_by_codec);
+criterion_main!(benches);
+
+//cargo bench --bench file_io -- load_chunk
+
+//benchmarks below predate the region files and are kept for reference
+
 // use criterion::{Criterion, criterion_group, criterion_main};
 // use marching_cubes::{
 //     constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_DIM},
@@ -223,5 +296,3 @@
 // criterion_main!(benches);
 
 // //cargo bench --bench file_io -- benchmark_read_single_chunk
-
-fn main() {}
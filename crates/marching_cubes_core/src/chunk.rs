@@ -0,0 +1,209 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::{
+    constants::{SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED},
+    sdf_value::SdfValue,
+};
+
+#[repr(u8)]
+#[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MaterialCode {
+    Air = 0,
+    Dirt = 1,
+    Grass = 2,
+    Sand = 3,
+    Path = 4,
+    Snow = 5,
+    Water = 6,
+}
+
+pub const MATERIAL_COUNT: usize = 7;
+
+impl MaterialCode {
+    pub const ALL: [MaterialCode; MATERIAL_COUNT] = [
+        MaterialCode::Air,
+        MaterialCode::Dirt,
+        MaterialCode::Grass,
+        MaterialCode::Sand,
+        MaterialCode::Path,
+        MaterialCode::Snow,
+        MaterialCode::Water,
+    ];
+}
+
+//a stored material byte that names no MaterialCode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnknownMaterial(pub u8);
+
+impl fmt::Display for UnknownMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown material {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownMaterial {}
+
+//for bytes read back from disk, every code is checked against the enum
+impl TryFrom<u8> for MaterialCode {
+    type Error = UnknownMaterial;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        MaterialCode::ALL
+            .get(byte as usize)
+            .copied()
+            .ok_or(UnknownMaterial(byte))
+    }
+}
+
+#[inline(always)]
+pub fn quantize_f32_to_i16(value: f32) -> i16 {
+    SdfValue::from_f32(value).0
+}
+
+#[inline(always)]
+pub fn dequantize_i16_to_f32(q: i16) -> f32 {
+    SdfValue(q).to_f32()
+}
+
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Density = trilinear SDF sample at the output grid point
+// Material = scan the corresponding input block;
+// prefer grass/sand if any exist near the surface,
+// otherwise pick the closest-to-surface solid.
+//always called with full res chunk
+pub fn downscale(
+    densities_in: &[i16],          // (SAMPLES_PER_CHUNK_DIM + 2) **3
+    materials_in: &[MaterialCode], // (SAMPLES_PER_CHUNK) **3
+    densities_out: &mut [i16],
+    materials_out: &mut [MaterialCode],
+    out_dim: usize,
+) {
+    let in_max = SAMPLES_PER_CHUNK_DIM - 1;
+    let out_max = out_dim - 1;
+    let stride = in_max / out_max;
+    for target_z in 0..out_dim {
+        let high_sample_z = 1.0 + (target_z as f32 / out_max as f32) * in_max as f32;
+        let mat_target_z = target_z * stride;
+        let mat_end_z = (mat_target_z + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
+        let z_base = target_z * out_dim;
+        for target_y in 0..out_dim {
+            let high_sample_y = 1.0 + (target_y as f32 / out_max as f32) * in_max as f32;
+            let mat_target_y = target_y * stride;
+            let mat_end_y = (mat_target_y + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
+            let zy_base = (z_base + target_y) * out_dim;
+            for target_x in 0..out_dim {
+                let high_sample_x = 1.0 + (target_x as f32 / out_max as f32) * in_max as f32;
+                let mat_target_x = target_x * stride;
+                let mat_end_x = (mat_target_x + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
+                let new_density = sample_trilinear_density(
+                    densities_in,
+                    SAMPLES_PER_CHUNK_DIM_PADDED,
+                    high_sample_x,
+                    high_sample_y,
+                    high_sample_z,
+                );
+                let out_i = zy_base + target_x;
+                densities_out[out_i] = quantize_f32_to_i16(new_density);
+                let new_material = pick_surface_material(
+                    materials_in,
+                    densities_in,
+                    [
+                        mat_target_x..=mat_end_x,
+                        mat_target_y..=mat_end_y,
+                        mat_target_z..=mat_end_z,
+                    ],
+                );
+                materials_out[out_i] = new_material;
+            }
+        }
+    }
+}
+
+//block is the x, y and z sample range of the input covered by one output sample
+fn pick_surface_material(
+    materials: &[MaterialCode],
+    densities: &[i16],
+    block: [RangeInclusive<usize>; 3],
+) -> MaterialCode {
+    let [xs, ys, zs] = block;
+    let mut best_mat = MaterialCode::Air;
+    let mut best_dist: u16 = u16::MAX;
+    for z in zs {
+        for y in ys.clone() {
+            let mat_base = (z * SAMPLES_PER_CHUNK_DIM + y) * SAMPLES_PER_CHUNK_DIM;
+            let den_base = ((z + 1) * SAMPLES_PER_CHUNK_DIM_PADDED + (y + 1))
+                * SAMPLES_PER_CHUNK_DIM_PADDED
+                + 1;
+            for x in xs.clone() {
+                let density = densities[den_base + x];
+                if density >= 0 {
+                    continue;
+                }
+                let dist = density.unsigned_abs();
+                if dist < best_dist {
+                    best_dist = dist;
+                    best_mat = materials[mat_base + x];
+                }
+            }
+        }
+    }
+    best_mat
+}
+
+pub fn sample_trilinear_density(
+    d: &[i16],
+    dim: usize,
+    high_sample_x: f32,
+    high_sample_y: f32,
+    high_sample_z: f32,
+) -> f32 {
+    let x0 = high_sample_x.floor() as isize;
+    let y0 = high_sample_y.floor() as isize;
+    let z0 = high_sample_z.floor() as isize;
+    let x1 = (x0 + 1).min((dim - 1) as isize);
+    let y1 = (y0 + 1).min((dim - 1) as isize);
+    let z1 = (z0 + 1).min((dim - 1) as isize);
+    let tx = high_sample_x - x0 as f32;
+    let ty = high_sample_y - y0 as f32;
+    let tz = high_sample_z - z0 as f32;
+    let idx = |x: isize, y: isize, z: isize| -> usize {
+        (z as usize * dim + y as usize) * dim + x as usize
+    };
+    let d000 = dequantize_i16_to_f32(d[idx(x0, y0, z0)]);
+    let d100 = dequantize_i16_to_f32(d[idx(x1, y0, z0)]);
+    let d010 = dequantize_i16_to_f32(d[idx(x0, y1, z0)]);
+    let d110 = dequantize_i16_to_f32(d[idx(x1, y1, z0)]);
+    let d001 = dequantize_i16_to_f32(d[idx(x0, y0, z1)]);
+    let d101 = dequantize_i16_to_f32(d[idx(x1, y0, z1)]);
+    let d011 = dequantize_i16_to_f32(d[idx(x0, y1, z1)]);
+    let d111 = dequantize_i16_to_f32(d[idx(x1, y1, z1)]);
+    let c00 = lerp(d000, d100, tx);
+    let c10 = lerp(d010, d110, tx);
+    let c01 = lerp(d001, d101, tx);
+    let c11 = lerp(d011, d111, tx);
+    let c0 = lerp(c00, c10, ty);
+    let c1 = lerp(c01, c11, ty);
+    lerp(c0, c1, tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn material_bytes_round_trip_and_unknown_ones_are_rejected() {
+        for material in MaterialCode::ALL {
+            assert_eq!(MaterialCode::try_from(material as u8), Ok(material));
+        }
+        assert_eq!(
+            MaterialCode::try_from(MATERIAL_COUNT as u8),
+            Err(UnknownMaterial(MATERIAL_COUNT as u8))
+        );
+    }
+}
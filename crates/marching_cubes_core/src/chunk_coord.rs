@@ -0,0 +1,112 @@
+use std::fmt;
+
+use glam::Vec3;
+
+use crate::constants::CHUNK_WORLD_SIZE;
+
+//x, y, z of a chunk in chunks, chunk 0,0,0 is centered on the world origin
+//fields keep tuple order so code that read .0 .1 .2 off the old (i16, i16, i16) still does
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkCoord(pub i16, pub i16, pub i16);
+
+impl ChunkCoord {
+    pub const ORIGIN: ChunkCoord = ChunkCoord(0, 0, 0);
+
+    pub const fn new(x: i16, y: i16, z: i16) -> Self {
+        ChunkCoord(x, y, z)
+    }
+
+    pub fn from_world_pos(world_pos: Vec3) -> Self {
+        let chunk = (world_pos / CHUNK_WORLD_SIZE).round();
+        ChunkCoord(chunk.x as i16, chunk.y as i16, chunk.z as i16)
+    }
+
+    pub fn to_world_center(self) -> Vec3 {
+        Vec3::new(self.0 as f32, self.1 as f32, self.2 as f32) * CHUNK_WORLD_SIZE
+    }
+
+    pub const fn offset(self, x: i16, y: i16, z: i16) -> Self {
+        ChunkCoord(self.0 + x, self.1 + y, self.2 + z)
+    }
+
+    //the six chunks sharing a face with this one, -x +x -y +y -z +z
+    pub const fn neighbors(self) -> [ChunkCoord; 6] {
+        [
+            self.offset(-1, 0, 0),
+            self.offset(1, 0, 0),
+            self.offset(0, -1, 0),
+            self.offset(0, 1, 0),
+            self.offset(0, 0, -1),
+            self.offset(0, 0, 1),
+        ]
+    }
+
+    //in chunks, widened so chunks at opposite world edges do not overflow
+    pub const fn distance_sq_to(self, other: ChunkCoord) -> i32 {
+        let x = self.0 as i32 - other.0 as i32;
+        let y = self.1 as i32 - other.1 as i32;
+        let z = self.2 as i32 - other.2 as i32;
+        x * x + y * y + z * z
+    }
+}
+
+impl From<(i16, i16, i16)> for ChunkCoord {
+    fn from((x, y, z): (i16, i16, i16)) -> Self {
+        ChunkCoord(x, y, z)
+    }
+}
+
+impl From<ChunkCoord> for (i16, i16, i16) {
+    fn from(chunk_coord: ChunkCoord) -> Self {
+        (chunk_coord.0, chunk_coord.1, chunk_coord.2)
+    }
+}
+
+//same text the tuple printed, console output and logs read the same as before
+impl fmt::Display for ChunkCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_center_round_trips() {
+        for chunk_coord in [
+            ChunkCoord::ORIGIN,
+            ChunkCoord(-3, 7, 120),
+            ChunkCoord(4095, -4095, 1),
+        ] {
+            assert_eq!(
+                ChunkCoord::from_world_pos(chunk_coord.to_world_center()),
+                chunk_coord
+            );
+            let near_face = chunk_coord.to_world_center() + Vec3::splat(CHUNK_WORLD_SIZE * 0.49);
+            assert_eq!(ChunkCoord::from_world_pos(near_face), chunk_coord);
+        }
+    }
+
+    #[test]
+    fn neighbors_are_one_chunk_away() {
+        let center = ChunkCoord(5, -3, 7);
+        let neighbors = center.neighbors();
+        assert!(neighbors.iter().all(|n| n.distance_sq_to(center) == 1));
+        assert_eq!(neighbors[1], ChunkCoord(6, -3, 7));
+        assert_eq!(
+            ChunkCoord(-20000, 0, 0).distance_sq_to(ChunkCoord(20000, 0, 0)),
+            40000 * 40000
+        );
+    }
+
+    #[test]
+    fn tuple_conversions_keep_axis_order() {
+        let chunk_coord: ChunkCoord = (1, 2, 3).into();
+        assert_eq!(chunk_coord, ChunkCoord::new(1, 2, 3));
+        assert_eq!(<(i16, i16, i16)>::from(chunk_coord), (1, 2, 3));
+        assert_eq!(chunk_coord.to_string(), "(1, 2, 3)");
+    }
+}
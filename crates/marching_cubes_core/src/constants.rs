@@ -1,4 +1,4 @@
-use bevy::math::Vec3;
+use glam::Vec3;
 
 pub const SIMULATION_RADIUS: f32 = 80.0; //in world units. Distance where everything is loaded at all times and physically simulated.
 pub const CHUNK_WORLD_SIZE: f32 = 12.0; //in world units, required by noise to be an integer and even
@@ -9,7 +9,10 @@ pub const REDUCED_LOD_2_RADIUS: f32 = REDUCED_LOD_1_RADIUS * 2.0; //cyan
 pub const REDUCED_LOD_3_RADIUS: f32 = REDUCED_LOD_1_RADIUS * 4.0; //blue
 pub const REDUCED_LOD_4_RADIUS: f32 = REDUCED_LOD_1_RADIUS * 8.0; //yellow
 pub const REDUCED_LOD_5_RADIUS: f32 = REDUCED_LOD_1_RADIUS * 16.0; //purple
-pub const WORLD_SEED: i32 = 111;
+pub const MERGED_LOD_RADIUS: f32 = REDUCED_LOD_5_RADIUS * 1.5; //past lod5, whole clusters are one mesh
+pub const DEFAULT_WORLD_SEED: i32 = 111; //worlds pick their own seed, see world_gen
+pub const DEFAULT_WORLD_RADIUS: i16 = 512; //clusters from the origin to the world edge along each axis
+pub const MAX_WORLD_RADIUS: i16 = 4096; //chunk coords at the edge of a wider world would not fit an i16
 pub const NOISE_FREQUENCY: f32 = 0.0005; // Frequency of the noise
 pub const NOISE_AMPLITUDE: f32 = 300.0; // Amplitude of the noise
 pub const PLAYER_SPAWN: Vec3 = Vec3::new(0., 0., 0.);
@@ -32,3 +35,4 @@ pub const REDUCED_LOD_2_RADIUS_SQUARED: f32 = REDUCED_LOD_2_RADIUS * REDUCED_LOD
 pub const REDUCED_LOD_3_RADIUS_SQUARED: f32 = REDUCED_LOD_3_RADIUS * REDUCED_LOD_3_RADIUS;
 pub const REDUCED_LOD_4_RADIUS_SQUARED: f32 = REDUCED_LOD_4_RADIUS * REDUCED_LOD_4_RADIUS;
 pub const REDUCED_LOD_5_RADIUS_SQUARED: f32 = REDUCED_LOD_5_RADIUS * REDUCED_LOD_5_RADIUS;
+pub const MERGED_LOD_RADIUS_SQUARED: f32 = MERGED_LOD_RADIUS * MERGED_LOD_RADIUS;
@@ -1,29 +1,25 @@
-use bevy::math::Vec3;
+use glam::{IVec3, UVec3, Vec3};
 
-use crate::constants::{
-    CHUNK_WORLD_SIZE, CHUNKS_PER_CLUSTER_DIM, CLUSTER_WORLD_LENGTH, HALF_CHUNK, VOXEL_WORLD_SIZE,
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::{
+        CHUNK_WORLD_SIZE, CHUNKS_PER_CLUSTER_DIM, CLUSTER_WORLD_LENGTH, HALF_CHUNK,
+        SAMPLES_PER_CHUNK_DIM, VOXEL_WORLD_SIZE,
+    },
 };
 
-pub fn chunk_coord_to_world_pos(chunk_coord: &(i16, i16, i16)) -> Vec3 {
-    Vec3::new(
-        chunk_coord.0 as f32 * CHUNK_WORLD_SIZE,
-        chunk_coord.1 as f32 * CHUNK_WORLD_SIZE,
-        chunk_coord.2 as f32 * CHUNK_WORLD_SIZE,
-    )
+const SAMPLE_STRIDE: i32 = SAMPLES_PER_CHUNK_DIM as i32 - 1; //neighboring chunks share their border samples
+
+pub fn chunk_coord_to_world_pos(chunk_coord: &ChunkCoord) -> Vec3 {
+    chunk_coord.to_world_center()
 }
 
-pub fn world_pos_to_chunk_coord(world_pos: &Vec3) -> (i16, i16, i16) {
-    let chunk_x = (world_pos.x / CHUNK_WORLD_SIZE).round() as i16;
-    let chunk_y = (world_pos.y / CHUNK_WORLD_SIZE).round() as i16;
-    let chunk_z = (world_pos.z / CHUNK_WORLD_SIZE).round() as i16;
-    (chunk_x, chunk_y, chunk_z)
+pub fn world_pos_to_chunk_coord(world_pos: &Vec3) -> ChunkCoord {
+    ChunkCoord::from_world_pos(*world_pos)
 }
 
-pub fn world_pos_to_voxel_index(
-    world_pos: &Vec3,
-    chunk_coord: &(i16, i16, i16),
-) -> (u32, u32, u32) {
-    let chunk_world_center = chunk_coord_to_world_pos(&chunk_coord);
+pub fn world_pos_to_voxel_index(world_pos: &Vec3, chunk_coord: &ChunkCoord) -> (u32, u32, u32) {
+    let chunk_world_center = chunk_coord.to_world_center();
     let chunk_world_min = chunk_world_center - Vec3::splat(HALF_CHUNK);
     let relative_pos = world_pos - chunk_world_min;
     let voxel_x = (relative_pos.x / VOXEL_WORLD_SIZE).floor() as u32;
@@ -32,11 +28,43 @@ pub fn world_pos_to_voxel_index(
     (voxel_x, voxel_y, voxel_z)
 }
 
+//samples of every chunk on one grid, a chunk's first interior sample sits on its min corner
+pub fn world_pos_to_sample(world_pos: Vec3) -> IVec3 {
+    ((world_pos + HALF_CHUNK) / VOXEL_WORLD_SIZE)
+        .round()
+        .as_ivec3()
+}
+
+pub fn sample_to_world_pos(sample: IVec3) -> Vec3 {
+    sample.as_vec3() * VOXEL_WORLD_SIZE - HALF_CHUNK
+}
+
+//the global sample at index 0 of the chunk's padded grid
+pub fn chunk_coord_to_first_padded_sample(chunk_coord: &ChunkCoord) -> IVec3 {
+    IVec3::new(
+        chunk_coord.0 as i32,
+        chunk_coord.1 as i32,
+        chunk_coord.2 as i32,
+    ) * SAMPLE_STRIDE
+        - IVec3::ONE
+}
+
+//a chunk whose interior holds the sample and the sample's index in that chunk's padded grid
+//border samples belong to two chunks with the same value, the one on the max side is returned
+pub fn sample_to_padded_index(sample: IVec3) -> (ChunkCoord, UVec3) {
+    let chunk = sample.div_euclid(IVec3::splat(SAMPLE_STRIDE));
+    let local = sample.rem_euclid(IVec3::splat(SAMPLE_STRIDE)) + IVec3::ONE;
+    (
+        ChunkCoord(chunk.x as i16, chunk.y as i16, chunk.z as i16),
+        local.as_uvec3(),
+    )
+}
+
 pub fn flatten_index(x: u32, y: u32, z: u32, dimension_size: usize) -> u32 {
     z * dimension_size as u32 * dimension_size as u32 + y * dimension_size as u32 + x
 }
 
-pub fn chunk_coord_to_cluster_coord(chunk_coord: &(i16, i16, i16)) -> (i16, i16, i16) {
+pub fn chunk_coord_to_cluster_coord(chunk_coord: &ChunkCoord) -> (i16, i16, i16) {
     (
         chunk_coord.0.div_euclid(CHUNKS_PER_CLUSTER_DIM as i16),
         chunk_coord.1.div_euclid(CHUNKS_PER_CLUSTER_DIM as i16),
@@ -65,8 +93,8 @@ pub fn world_pos_to_cluster_coord(world_pos: &Vec3) -> (i16, i16, i16) {
     (cluster_x, cluster_y, cluster_z)
 }
 
-pub fn cluster_coord_to_min_chunk_coord(cluster_coord: (i16, i16, i16)) -> (i16, i16, i16) {
-    (
+pub fn cluster_coord_to_min_chunk_coord(cluster_coord: (i16, i16, i16)) -> ChunkCoord {
+    ChunkCoord(
         cluster_coord.0 * CHUNKS_PER_CLUSTER_DIM as i16,
         cluster_coord.1 * CHUNKS_PER_CLUSTER_DIM as i16,
         cluster_coord.2 * CHUNKS_PER_CLUSTER_DIM as i16,
@@ -0,0 +1,9 @@
+//chunk layout, the sdf sample type and the meshers, with no bevy and no file io
+//the game crate re-exports these under its old module paths
+
+pub mod chunk;
+pub mod chunk_coord;
+pub mod constants;
+pub mod conversions;
+pub mod meshing;
+pub mod sdf_value;
@@ -0,0 +1,247 @@
+use glam::Vec3;
+
+use crate::{
+    chunk::MaterialCode,
+    constants::{CHUNK_WORLD_SIZE, HALF_CHUNK},
+    meshing::{surface_nets::pick_cell_material, tables::CORNER_OFFSETS},
+};
+
+//blocky mesher for the same density and material data, a cell between 8 samples is a cube when its mean density is solid
+//exposed cube faces are merged into the largest same material rectangles per slice, normals are the face axis
+//cells tile the chunk exactly so neighbors never overlap, the padded apron decides the faces on the chunk border
+//unpadded lod grids have no apron and always close their border, those faces end up inside the neighbor's cubes
+pub fn greedy_cubes_mesh_generation(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    samples_per_chunk_dim: usize,
+    densities_padded: bool,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    let cells_per_chunk_dim = samples_per_chunk_dim - 1;
+    let voxel_size = CHUNK_WORLD_SIZE / cells_per_chunk_dim as f32;
+    let grid = classify_cells(
+        densities,
+        materials,
+        samples_per_chunk_dim,
+        densities_padded,
+    );
+    let grid_dim = cells_per_chunk_dim + 2;
+    let cell = |c: [usize; 3]| grid[(c[2] * grid_dim + c[1]) * grid_dim + c[0]];
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut material_ids = Vec::new();
+    let mut indices = Vec::new();
+    let mut mask = vec![MaterialCode::Air; cells_per_chunk_dim * cells_per_chunk_dim];
+    for axis in 0..3 {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        for positive in [true, false] {
+            let mut normal = Vec3::ZERO;
+            normal[axis] = if positive { 1.0 } else { -1.0 };
+            for layer in 1..=cells_per_chunk_dim {
+                //faces of this layer's cubes that look into air along the normal
+                for v in 0..cells_per_chunk_dim {
+                    for u in 0..cells_per_chunk_dim {
+                        let mut here = [0; 3];
+                        here[axis] = layer;
+                        here[u_axis] = u + 1;
+                        here[v_axis] = v + 1;
+                        let mut there = here;
+                        there[axis] = if positive { layer + 1 } else { layer - 1 };
+                        let material = cell(here);
+                        mask[v * cells_per_chunk_dim + u] =
+                            if material != MaterialCode::Air && cell(there) == MaterialCode::Air {
+                                material
+                            } else {
+                                MaterialCode::Air
+                            };
+                    }
+                }
+                let plane = if positive { layer } else { layer - 1 };
+                for v in 0..cells_per_chunk_dim {
+                    let mut u = 0;
+                    while u < cells_per_chunk_dim {
+                        let material = mask[v * cells_per_chunk_dim + u];
+                        if material == MaterialCode::Air {
+                            u += 1;
+                            continue;
+                        }
+                        let mut width = 1;
+                        while u + width < cells_per_chunk_dim
+                            && mask[v * cells_per_chunk_dim + u + width] == material
+                        {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        while v + height < cells_per_chunk_dim
+                            && mask[(v + height) * cells_per_chunk_dim + u
+                                ..(v + height) * cells_per_chunk_dim + u + width]
+                                .iter()
+                                .all(|m| *m == material)
+                        {
+                            height += 1;
+                        }
+                        for row in v..v + height {
+                            mask[row * cells_per_chunk_dim + u
+                                ..row * cells_per_chunk_dim + u + width]
+                                .fill(MaterialCode::Air);
+                        }
+                        let mut origin = Vec3::splat(-HALF_CHUNK);
+                        origin[axis] += plane as f32 * voxel_size;
+                        origin[u_axis] += u as f32 * voxel_size;
+                        origin[v_axis] += v as f32 * voxel_size;
+                        let mut du = Vec3::ZERO;
+                        du[u_axis] = width as f32 * voxel_size;
+                        let mut dv = Vec3::ZERO;
+                        dv[v_axis] = height as f32 * voxel_size;
+                        let base = vertices.len() as u32;
+                        vertices.extend_from_slice(&[
+                            origin,
+                            origin + du,
+                            origin + du + dv,
+                            origin + dv,
+                        ]);
+                        normals.extend_from_slice(&[normal; 4]);
+                        material_ids.extend_from_slice(&[material as u32; 4]);
+                        //u cross v is the positive axis, so the winding flips for the negative faces
+                        if positive {
+                            indices.extend_from_slice(&[
+                                base,
+                                base + 1,
+                                base + 2,
+                                base,
+                                base + 2,
+                                base + 3,
+                            ]);
+                        } else {
+                            indices.extend_from_slice(&[
+                                base,
+                                base + 2,
+                                base + 1,
+                                base,
+                                base + 3,
+                                base + 2,
+                            ]);
+                        }
+                        u += width;
+                    }
+                }
+            }
+        }
+    }
+    (vertices, normals, material_ids, indices)
+}
+
+//cell materials with a one cell border around the chunk, Air is empty
+//border cells come from the apron when there is one and are empty otherwise, only their occupancy is used
+fn classify_cells(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    samples_per_chunk_dim: usize,
+    densities_padded: bool,
+) -> Vec<MaterialCode> {
+    let cells_per_chunk_dim = samples_per_chunk_dim - 1;
+    let grid_dim = cells_per_chunk_dim + 2;
+    let density_dim = if densities_padded {
+        samples_per_chunk_dim + 2
+    } else {
+        samples_per_chunk_dim
+    };
+    let stride = density_dim * density_dim;
+    let mat_stride = samples_per_chunk_dim * samples_per_chunk_dim;
+    let mut grid = vec![MaterialCode::Air; grid_dim * grid_dim * grid_dim];
+    for gz in 0..grid_dim {
+        for gy in 0..grid_dim {
+            for gx in 0..grid_dim {
+                let inside = [gx, gy, gz]
+                    .iter()
+                    .all(|g| (1..=cells_per_chunk_dim).contains(g));
+                if !inside && !densities_padded {
+                    continue;
+                }
+                //grid index is one past the cell, the padded density index is one past the sample
+                let (sx, sy, sz) = if densities_padded {
+                    (gx, gy, gz)
+                } else {
+                    (gx - 1, gy - 1, gz - 1)
+                };
+                let sample_idx = sz * stride + sy * density_dim + sx;
+                let corner_densities: [f32; 8] = std::array::from_fn(|corner| {
+                    let offset = CORNER_OFFSETS[corner];
+                    densities[sample_idx
+                        + offset.z as usize * stride
+                        + offset.y as usize * density_dim
+                        + offset.x as usize] as f32
+                });
+                if corner_densities.iter().sum::<f32>() >= 0.0 {
+                    continue;
+                }
+                grid[(gz * grid_dim + gy) * grid_dim + gx] = if inside {
+                    let mat_idx = (gz - 1) * mat_stride + (gy - 1) * samples_per_chunk_dim + gx - 1;
+                    pick_cell_material(materials, &corner_densities, |corner| {
+                        let offset = CORNER_OFFSETS[corner];
+                        mat_idx
+                            + offset.z as usize * mat_stride
+                            + offset.y as usize * samples_per_chunk_dim
+                            + offset.x as usize
+                    })
+                } else {
+                    MaterialCode::Dirt
+                };
+            }
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_ground_is_one_merged_quad() {
+        //4 cells per side, the lower two layers solid and the apron solid beside and below them
+        let samples_per_chunk_dim: usize = 5;
+        let padded_dim = samples_per_chunk_dim + 2;
+        let voxel_size = CHUNK_WORLD_SIZE / (samples_per_chunk_dim - 1) as f32;
+        let mut densities = Vec::with_capacity(padded_dim.pow(3));
+        for _z in 0..padded_dim {
+            for y in 0..padded_dim {
+                for _x in 0..padded_dim {
+                    let local_y = -HALF_CHUNK + (y as f32 - 1.0) * voxel_size;
+                    densities.push((local_y * 100.0) as i16);
+                }
+            }
+        }
+        let materials = vec![MaterialCode::Grass; samples_per_chunk_dim.pow(3)];
+        let (vertices, normals, material_ids, indices) =
+            greedy_cubes_mesh_generation(&densities, &materials, samples_per_chunk_dim, true);
+        assert_eq!(indices.len(), 6);
+        assert!(vertices.iter().all(|p| p.y.abs() < 1e-5));
+        assert!(normals.iter().all(|n| *n == Vec3::Y));
+        assert!(
+            material_ids
+                .iter()
+                .all(|m| *m == MaterialCode::Grass as u32)
+        );
+        for tri in indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize]);
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+    }
+
+    #[test]
+    fn unpadded_solid_chunk_closes_every_face() {
+        let samples_per_chunk_dim: usize = 5;
+        let densities = vec![-100i16; samples_per_chunk_dim.pow(3)];
+        let materials = vec![MaterialCode::Dirt; samples_per_chunk_dim.pow(3)];
+        let (vertices, normals, _, indices) =
+            greedy_cubes_mesh_generation(&densities, &materials, samples_per_chunk_dim, false);
+        //one merged quad per side of the chunk
+        assert_eq!(indices.len(), 6 * 6);
+        for tri in indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize]);
+            let face_normal = (b - a).cross(c - a);
+            assert!(face_normal.dot(normals[tri[0] as usize]) > 0.0);
+            assert!(face_normal.dot((a + b + c) / 3.0) > 0.0); //pointing out of the chunk
+        }
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::hash_map::Entry;
+
+use glam::Vec3;
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{
+    chunk::{MaterialCode, sample_trilinear_density},
+    constants::{
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    },
+    meshing::tables::{CORNER_OFFSETS, EDGE_ID_OFFSETS, EDGE_VERTICES, TRIANGLE_TABLE},
+    sdf_value::SdfValue,
+};
+
+type EdgeKey = u64;
+
+//packed material id layout used by BlendWeights, plain ids only ever use the low byte
+pub const MATERIAL_ID_MASK: u32 = 0xFF;
+pub const BLEND_SECONDARY_SHIFT: u32 = 8; //material blended towards
+pub const BLEND_WEIGHT_SHIFT: u32 = 16; //0..=255 weight of the secondary material at this vertex
+
+//how a vertex on a surface crossing edge and a triangle spanning several materials get their material
+//every mode except Priority takes the edge material from its solid end, dug samples keep their old material
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MaterialResolution {
+    //surface materials win over dirt on either end of the edge, smears grass down dug cliffs
+    Priority,
+    //mixed triangles take the material most of their corners have, no extra vertices
+    DominantCorner,
+    //mixed triangles are cut along the boundary with duplicated vertices
+    #[default]
+    HardSplit,
+    //two material triangles get packed ids the shader blends across, three material triangles are split
+    BlendWeights,
+}
+
+//owned by each chunk loader thread and reused for every chunk it meshes
+//the edge map keeps its buckets and the output vecs their capacity, so steady state meshing does not allocate
+//the mesh asset has to own its buffers, copy_mesh copies them out at exact size and leaves the capacity here
+#[derive(Default)]
+pub struct MeshingScratch {
+    edge_to_vertex: HashMap<EdgeKey, u32>,
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub material_ids: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshingScratch {
+    pub fn new() -> Self {
+        MeshingScratch::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.edge_to_vertex.clear();
+        self.vertices.clear();
+        self.normals.clear();
+        self.material_ids.clear();
+        self.indices.clear();
+    }
+
+    pub fn copy_mesh(&self) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+        (
+            self.vertices.clone(),
+            self.normals.clone(),
+            self.material_ids.clone(),
+            self.indices.clone(),
+        )
+    }
+
+    pub fn into_mesh(self) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+        (self.vertices, self.normals, self.material_ids, self.indices)
+    }
+}
+
+#[inline(always)]
+fn make_edge_key(x: u16, y: u16, z: u16, dir: u8) -> u64 {
+    (x as u64) | ((y as u64) << 16) | ((z as u64) << 32) | ((dir as u64) << 48)
+}
+
+#[inline(always)]
+fn sample_full_res(
+    densities_full_res: &[i16],
+    x: usize,
+    y: usize,
+    z: usize,
+    padded_dim: usize,
+) -> f32 {
+    let stride = padded_dim * padded_dim;
+    densities_full_res[z * stride + y * padded_dim + x] as f32
+}
+
+pub(super) fn sample_full_res_trilinear(densities_full_res: &[i16], local_pos: Vec3) -> f32 {
+    let inv_voxel = (SAMPLES_PER_CHUNK_DIM - 1) as f32 / CHUNK_WORLD_SIZE;
+    let fx = (local_pos.x + HALF_CHUNK) * inv_voxel + 1.0;
+    let fy = (local_pos.y + HALF_CHUNK) * inv_voxel + 1.0;
+    let fz = (local_pos.z + HALF_CHUNK) * inv_voxel + 1.0;
+    let fx = fx.clamp(0.0, (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32);
+    let fy = fy.clamp(0.0, (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32);
+    let fz = fz.clamp(0.0, (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32);
+    let ix = fx as usize;
+    let iy = fy as usize;
+    let iz = fz as usize;
+    let tx = fx - ix as f32;
+    let ty = fy - iy as f32;
+    let tz = fz - iz as f32;
+    let ix1 = (ix + 1).min(SAMPLES_PER_CHUNK_DIM_PADDED - 1);
+    let iy1 = (iy + 1).min(SAMPLES_PER_CHUNK_DIM_PADDED - 1);
+    let iz1 = (iz + 1).min(SAMPLES_PER_CHUNK_DIM_PADDED - 1);
+    let d000 = sample_full_res(densities_full_res, ix, iy, iz, SAMPLES_PER_CHUNK_DIM_PADDED);
+    let d100 = sample_full_res(
+        densities_full_res,
+        ix1,
+        iy,
+        iz,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d010 = sample_full_res(
+        densities_full_res,
+        ix,
+        iy1,
+        iz,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d110 = sample_full_res(
+        densities_full_res,
+        ix1,
+        iy1,
+        iz,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d001 = sample_full_res(
+        densities_full_res,
+        ix,
+        iy,
+        iz1,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d101 = sample_full_res(
+        densities_full_res,
+        ix1,
+        iy,
+        iz1,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d011 = sample_full_res(
+        densities_full_res,
+        ix,
+        iy1,
+        iz1,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let d111 = sample_full_res(
+        densities_full_res,
+        ix1,
+        iy1,
+        iz1,
+        SAMPLES_PER_CHUNK_DIM_PADDED,
+    );
+    let c00 = d000 + tx * (d100 - d000);
+    let c10 = d010 + tx * (d110 - d010);
+    let c01 = d001 + tx * (d101 - d001);
+    let c11 = d011 + tx * (d111 - d011);
+    let c0 = c00 + ty * (c10 - c00);
+    let c1 = c01 + ty * (c11 - c01);
+    c0 + tz * (c1 - c0)
+}
+
+//where a mesh takes its normals and lod morph targets from
+//the padded full res chunk when there is one, far lods generated straight at their resolution only have the unpadded grid they mesh
+#[derive(Clone, Copy)]
+pub enum DensityField<'a> {
+    FullRes(&'a [i16]),
+    Downsampled(&'a [i16], usize), //samples per chunk dim
+}
+
+impl DensityField<'_> {
+    //density at a chunk local position in stored sample units, the same scale the meshers compare against zero
+    #[inline(always)]
+    pub(super) fn sample(&self, local_pos: Vec3) -> f32 {
+        match *self {
+            DensityField::FullRes(densities_full_res) => {
+                sample_full_res_trilinear(densities_full_res, local_pos)
+            }
+            DensityField::Downsampled(densities, samples_per_chunk_dim) => {
+                let max = (samples_per_chunk_dim - 1) as f32;
+                let f = ((local_pos + Vec3::splat(HALF_CHUNK)) * max / CHUNK_WORLD_SIZE)
+                    .clamp(Vec3::ZERO, Vec3::splat(max));
+                sample_trilinear_density(densities, samples_per_chunk_dim, f.x, f.y, f.z)
+                    * SdfValue::SCALE
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn gradient(&self, local_pos: Vec3) -> Vec3 {
+        match *self {
+            DensityField::FullRes(densities_full_res) => {
+                compute_full_res_gradient(densities_full_res, local_pos)
+            }
+            DensityField::Downsampled(densities, samples_per_chunk_dim) => {
+                compute_downsampled_gradient(densities, samples_per_chunk_dim, local_pos)
+            }
+        }
+    }
+}
+
+#[inline(always)]
+pub(super) fn compute_full_res_gradient(densities_full_res: &[i16], local_pos: Vec3) -> Vec3 {
+    let h = CHUNK_WORLD_SIZE / (SAMPLES_PER_CHUNK_DIM - 1) as f32 * 0.5;
+    let dx = sample_full_res_trilinear(densities_full_res, local_pos + Vec3::new(h, 0.0, 0.0))
+        - sample_full_res_trilinear(densities_full_res, local_pos - Vec3::new(h, 0.0, 0.0));
+    let dy = sample_full_res_trilinear(densities_full_res, local_pos + Vec3::new(0.0, h, 0.0))
+        - sample_full_res_trilinear(densities_full_res, local_pos - Vec3::new(0.0, h, 0.0));
+    let dz = sample_full_res_trilinear(densities_full_res, local_pos + Vec3::new(0.0, 0.0, h))
+        - sample_full_res_trilinear(densities_full_res, local_pos - Vec3::new(0.0, 0.0, h));
+    Vec3::new(dx, dy, dz)
+}
+
+//no apron, so on the chunk faces this is one sided and the neighbor can disagree slightly, far enough away not to show
+fn compute_downsampled_gradient(
+    densities: &[i16],
+    samples_per_chunk_dim: usize,
+    local_pos: Vec3,
+) -> Vec3 {
+    let max = (samples_per_chunk_dim - 1) as f32;
+    let inv_voxel = max / CHUNK_WORLD_SIZE;
+    let sample = |p: Vec3| {
+        let f = ((p + Vec3::splat(HALF_CHUNK)) * inv_voxel).clamp(Vec3::ZERO, Vec3::splat(max));
+        sample_trilinear_density(densities, samples_per_chunk_dim, f.x, f.y, f.z)
+    };
+    let h = 0.5 / inv_voxel;
+    Vec3::new(
+        sample(local_pos + Vec3::new(h, 0.0, 0.0)) - sample(local_pos - Vec3::new(h, 0.0, 0.0)),
+        sample(local_pos + Vec3::new(0.0, h, 0.0)) - sample(local_pos - Vec3::new(0.0, h, 0.0)),
+        sample(local_pos + Vec3::new(0.0, 0.0, h)) - sample(local_pos - Vec3::new(0.0, 0.0, h)),
+    )
+}
+
+//densities_full_res is always the padded full res chunk, its 1 voxel apron holds the neighbors' samples
+//normals are the gradient of that field rather than accumulated from triangles, so a vertex on a chunk face
+//gets the same normal from both chunks as long as the aprons are kept in sync
+pub fn mc_mesh_generation(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    samples_per_chunk_dim: usize,
+    densities_padded: bool,
+    densities_full_res: &[i16],
+    material_resolution: MaterialResolution,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    let mut scratch = MeshingScratch::new();
+    mc_mesh_generation_into(
+        densities,
+        materials,
+        samples_per_chunk_dim,
+        densities_padded,
+        DensityField::FullRes(densities_full_res),
+        material_resolution,
+        &mut scratch,
+    );
+    scratch.into_mesh()
+}
+
+//per chunk inputs every cube of one mesh reads
+struct CubeGrid<'a> {
+    materials: &'a [MaterialCode],
+    samples_per_chunk_dim: usize,
+    mat_stride: usize,
+    voxel_size: f32,
+    normal_field: DensityField<'a>,
+    material_resolution: MaterialResolution,
+}
+
+//a cube the surface crosses, corners already read out of the density grid
+struct Cube {
+    x_idx: usize,
+    y_idx: usize,
+    z_idx: usize,
+    world_pos: Vec3,
+    mat_voxel_idx: usize,
+    corner_densities: [f32; 8],
+}
+
+//same as mc_mesh_generation but the mesh is left in the scratch buffers, which are cleared first
+pub fn mc_mesh_generation_into(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    samples_per_chunk_dim: usize,
+    densities_padded: bool,
+    normal_field: DensityField,
+    material_resolution: MaterialResolution,
+    scratch: &mut MeshingScratch,
+) {
+    scratch.clear();
+    let cubes_per_chunk_dim = samples_per_chunk_dim - 1;
+    let density_dim = if densities_padded {
+        samples_per_chunk_dim + 2
+    } else {
+        samples_per_chunk_dim
+    };
+    let stride = density_dim * density_dim;
+    let density_offset = if densities_padded {
+        stride + density_dim + 1
+    } else {
+        0
+    };
+    let grid = CubeGrid {
+        materials,
+        samples_per_chunk_dim,
+        mat_stride: samples_per_chunk_dim * samples_per_chunk_dim,
+        voxel_size: CHUNK_WORLD_SIZE / (samples_per_chunk_dim - 1) as f32,
+        normal_field,
+        material_resolution,
+    };
+    for z_idx in 0..cubes_per_chunk_dim {
+        let cube_z_world_pos = -HALF_CHUNK + z_idx as f32 * grid.voxel_size;
+        let z_base = z_idx * stride;
+        let mat_z_base = z_idx * grid.mat_stride;
+        for y_idx in 0..cubes_per_chunk_dim {
+            let cube_y_world_pos = -HALF_CHUNK + y_idx as f32 * grid.voxel_size;
+            let yz_base = z_base + y_idx * density_dim;
+            let mat_yz_base = mat_z_base + y_idx * samples_per_chunk_dim;
+            for x_idx in 0..cubes_per_chunk_dim {
+                let voxel_idx = density_offset + yz_base + x_idx;
+                let corner_densities =
+                    sample_cube_corner_densities(densities, density_dim, stride, voxel_idx);
+                let sdf_sign_mask = compute_sdf_sign_mask(&corner_densities);
+                if sdf_sign_mask == 0 || sdf_sign_mask == 255 {
+                    continue;
+                }
+                let cube = Cube {
+                    x_idx,
+                    y_idx,
+                    z_idx,
+                    world_pos: Vec3::new(
+                        -HALF_CHUNK + x_idx as f32 * grid.voxel_size,
+                        cube_y_world_pos,
+                        cube_z_world_pos,
+                    ),
+                    mat_voxel_idx: mat_yz_base + x_idx,
+                    corner_densities,
+                };
+                let edge_table = &TRIANGLE_TABLE[sdf_sign_mask as usize];
+                triangulate_cube_with_cache(&cube, &grid, edge_table, scratch);
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn compute_sdf_sign_mask(values: &[f32; 8]) -> u8 {
+    values.iter().enumerate().fold(0u8, |mask, (i, value)| {
+        mask | (((value.to_bits() >> 31) ^ 1) as u8) << i
+    })
+}
+
+fn triangulate_cube_with_cache(
+    cube: &Cube,
+    grid: &CubeGrid,
+    edge_table: &[i32; 16],
+    scratch: &mut MeshingScratch,
+) {
+    for edges in edge_table
+        .chunks_exact(3)
+        .take_while(|edges| edges[0] != -1)
+    {
+        let [v1, v2, v3] =
+            [0, 1, 2].map(|k| get_or_create_edge_vertex(edges[k] as usize, cube, grid, scratch));
+        let m1 = scratch.material_ids[v1 as usize];
+        let m2 = scratch.material_ids[v2 as usize];
+        let m3 = scratch.material_ids[v3 as usize];
+        if m1 == m2 && m2 == m3 {
+            scratch.indices.extend_from_slice(&[v1, v2, v3]);
+            continue;
+        }
+        match grid.material_resolution {
+            MaterialResolution::Priority | MaterialResolution::HardSplit => {
+                split_mixed_triangle([v1, v2, v3], [m1, m2, m3], scratch);
+            }
+            //the shader reads the material flat from the first vertex, so rotate the majority to the front
+            MaterialResolution::DominantCorner => {
+                if m2 == m3 {
+                    scratch.indices.extend_from_slice(&[v2, v3, v1]);
+                } else {
+                    scratch.indices.extend_from_slice(&[v1, v2, v3]);
+                }
+            }
+            MaterialResolution::BlendWeights => {
+                if m1 != m2 && m2 != m3 && m1 != m3 {
+                    split_mixed_triangle([v1, v2, v3], [m1, m2, m3], scratch);
+                } else {
+                    push_blended_triangle([v1, v2, v3], scratch);
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn get_or_create_edge_vertex(
+    edge_index: usize,
+    cube: &Cube,
+    grid: &CubeGrid,
+    scratch: &mut MeshingScratch,
+) -> u32 {
+    let (dx, dy, dz, dir) = EDGE_ID_OFFSETS[edge_index];
+    let edge_id = make_edge_key(
+        cube.x_idx as u16 + dx,
+        cube.y_idx as u16 + dy,
+        cube.z_idx as u16 + dz,
+        dir,
+    );
+    match scratch.edge_to_vertex.entry(edge_id) {
+        Entry::Occupied(e) => *e.get(),
+        Entry::Vacant(e) => {
+            let (v1_idx, v2_idx) = EDGE_VERTICES[edge_index];
+            let d1 = CORNER_OFFSETS[v1_idx];
+            let d2 = CORNER_OFFSETS[v2_idx];
+            let v1 = d1 * grid.voxel_size + cube.world_pos;
+            let v2 = d2 * grid.voxel_size + cube.world_pos;
+            let position =
+                interpolate_edge_from_base(v1_idx, v2_idx, &cube.corner_densities, v1, v2);
+            let material_at = |d: Vec3| {
+                grid.materials[cube.mat_voxel_idx
+                    + d.z as usize * grid.mat_stride
+                    + d.y as usize * grid.samples_per_chunk_dim
+                    + d.x as usize]
+            };
+            let material1 = material_at(d1);
+            let material2 = material_at(d2);
+            let material = match grid.material_resolution {
+                MaterialResolution::Priority => {
+                    if material1 == MaterialCode::Path || material2 == MaterialCode::Path {
+                        MaterialCode::Path
+                    } else if material1 == MaterialCode::Grass || material2 == MaterialCode::Grass {
+                        MaterialCode::Grass
+                    } else if material1 == MaterialCode::Snow || material2 == MaterialCode::Snow {
+                        MaterialCode::Snow
+                    } else if material1 == MaterialCode::Sand || material2 == MaterialCode::Sand {
+                        MaterialCode::Sand
+                    } else if material1 == MaterialCode::Water || material2 == MaterialCode::Water {
+                        MaterialCode::Water
+                    } else if material1 != MaterialCode::Air {
+                        material1
+                    } else {
+                        material2
+                    }
+                }
+                _ => {
+                    let solid = if cube.corner_densities[v1_idx] <= cube.corner_densities[v2_idx] {
+                        material1
+                    } else {
+                        material2
+                    };
+                    if solid == MaterialCode::Air {
+                        MaterialCode::Dirt
+                    } else {
+                        solid
+                    }
+                }
+            };
+            let gradient = grid.normal_field.gradient(position);
+            let normal = if gradient.length_squared() > 0.0001 {
+                gradient.normalize()
+            } else {
+                Vec3::Y
+            };
+            let idx = scratch.vertices.len() as u32;
+            scratch.vertices.push(position);
+            scratch.normals.push(normal);
+            scratch.material_ids.push(material as u32);
+            e.insert(idx);
+            idx
+        }
+    }
+}
+
+#[inline(always)]
+pub(super) fn interpolate_edge_from_base(
+    v1_idx: usize,
+    v2_idx: usize,
+    cube_corner_densities: &[f32; 8],
+    v1: Vec3,
+    v2: Vec3,
+) -> Vec3 {
+    let val1 = cube_corner_densities[v1_idx];
+    let val2 = cube_corner_densities[v2_idx];
+    let denom = val2 - val1;
+    let t = if denom.abs() < 0.0001 {
+        0.5
+    } else {
+        (-val1 / denom).clamp(0.0, 1.0)
+    };
+    v1 + t * (v2 - v1)
+}
+
+#[inline(always)]
+fn sample_cube_corner_densities(
+    densities: &[i16],
+    samples_per_chunk_dim: usize,
+    stride: usize,
+    voxel_idx: usize,
+) -> [f32; 8] {
+    [
+        densities[voxel_idx] as f32,
+        densities[voxel_idx + 1] as f32,
+        densities[voxel_idx + samples_per_chunk_dim + 1] as f32,
+        densities[voxel_idx + samples_per_chunk_dim] as f32,
+        densities[voxel_idx + stride] as f32,
+        densities[voxel_idx + stride + 1] as f32,
+        densities[voxel_idx + stride + samples_per_chunk_dim + 1] as f32,
+        densities[voxel_idx + stride + samples_per_chunk_dim] as f32,
+    ]
+}
+
+fn interp(vertices: &[Vec3], normals: &[Vec3], a: usize, b: usize) -> (Vec3, Vec3) {
+    let pos = (vertices[a] + vertices[b]) * 0.5;
+    let raw_n = (normals[a] + normals[b]) * 0.5;
+    let len = raw_n.length();
+    let norm = if len > 0.0001 { raw_n / len } else { Vec3::Y };
+    (pos, norm)
+}
+
+fn make_seam(
+    scratch: &mut MeshingScratch,
+    pos: Vec3,
+    norm: Vec3,
+    mat_near: u32,
+    mat_far: u32,
+) -> (u32, u32) {
+    let near = scratch.vertices.len() as u32;
+    scratch.vertices.push(pos);
+    scratch.normals.push(norm);
+    scratch.material_ids.push(mat_near);
+    let far = scratch.vertices.len() as u32;
+    scratch.vertices.push(pos);
+    scratch.normals.push(norm);
+    scratch.material_ids.push(mat_far);
+    (near, far)
+}
+
+//triangle with exactly two materials, gets its own vertices all carrying the same material pair
+//so the flat pair and the interpolated weight agree across the whole triangle
+fn push_blended_triangle(corners: [u32; 3], scratch: &mut MeshingScratch) {
+    let primary = scratch.material_ids[corners[0] as usize];
+    let secondary = corners
+        .iter()
+        .map(|&v| scratch.material_ids[v as usize])
+        .find(|&m| m != primary)
+        .unwrap();
+    for v in corners {
+        let weight = if scratch.material_ids[v as usize] == secondary {
+            255
+        } else {
+            0
+        };
+        scratch.indices.push(scratch.vertices.len() as u32);
+        scratch.vertices.push(scratch.vertices[v as usize]);
+        scratch.normals.push(scratch.normals[v as usize]);
+        scratch
+            .material_ids
+            .push(primary | secondary << BLEND_SECONDARY_SHIFT | weight << BLEND_WEIGHT_SHIFT);
+    }
+}
+
+fn split_mixed_triangle(
+    corners: [u32; 3],
+    corner_materials: [u32; 3],
+    scratch: &mut MeshingScratch,
+) {
+    let [v1, v2, v3] = corners;
+    let [m1, m2, m3] = corner_materials;
+    let midpoint = |scratch: &MeshingScratch, a: u32, b: u32| {
+        interp(&scratch.vertices, &scratch.normals, a as usize, b as usize)
+    };
+    if m1 == m2 && m1 != m3 {
+        let (p13, n13) = midpoint(scratch, v1, v3);
+        let (p23, n23) = midpoint(scratch, v2, v3);
+        let (s13_top, s13_bot) = make_seam(scratch, p13, n13, m1, m3);
+        let (s23_top, s23_bot) = make_seam(scratch, p23, n23, m1, m3);
+        scratch
+            .indices
+            .extend_from_slice(&[v1, v2, s23_top, v1, s23_top, s13_top, s13_bot, s23_bot, v3]);
+    } else if m1 == m3 && m1 != m2 {
+        let (p12, n12) = midpoint(scratch, v1, v2);
+        let (p23, n23) = midpoint(scratch, v2, v3);
+        let (s12_top, s12_bot) = make_seam(scratch, p12, n12, m1, m2);
+        let (s23_top, s23_bot) = make_seam(scratch, p23, n23, m1, m2);
+        scratch
+            .indices
+            .extend_from_slice(&[v1, s12_top, s23_top, v1, s23_top, v3, s12_bot, v2, s23_bot]);
+    } else if m2 == m3 && m2 != m1 {
+        let (p12, n12) = midpoint(scratch, v1, v2);
+        let (p13, n13) = midpoint(scratch, v1, v3);
+        let (s12_top, s12_bot) = make_seam(scratch, p12, n12, m1, m2);
+        let (s13_top, s13_bot) = make_seam(scratch, p13, n13, m1, m3);
+        scratch
+            .indices
+            .extend_from_slice(&[v1, s12_top, s13_top, s12_bot, v2, v3, s12_bot, v3, s13_bot]);
+    } else {
+        scratch.indices.extend_from_slice(&[v1, v2, v3]);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod greedy_cubes;
+pub mod mc;
+pub mod simplify;
+pub mod surface_nets;
+mod tables;
+pub mod transvoxel;
@@ -0,0 +1,395 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use glam::{DVec3, Vec3};
+use rustc_hash::FxHashMap;
+
+use crate::constants::HALF_CHUNK;
+
+const BORDER_EPSILON: f32 = 1e-4;
+
+//cost bits, keep, remove, and the stamps both had when the cost was computed, cheapest first
+type CollapseCandidate = Reverse<(u64, u32, u32, u32, u32)>;
+
+//post process for lod meshes, both steps are skipped when their setting is zero
+//vertices on the chunk faces never move so seams with neighbors stay closed
+#[derive(Debug, Clone, Copy)]
+pub struct MeshSimplification {
+    pub weld_tolerance: f32, //world units, vertices closer than this are merged
+    pub target_ratio: f32,   //fraction of triangles to keep, 1.0 disables edge collapses
+    pub max_error: f32,      //world units, no collapse may move the surface further than this
+}
+
+impl Default for MeshSimplification {
+    fn default() -> Self {
+        MeshSimplification {
+            weld_tolerance: 0.0,
+            target_ratio: 1.0,
+            max_error: 0.0,
+        }
+    }
+}
+
+#[inline(always)]
+fn on_chunk_border(p: Vec3) -> bool {
+    p.abs().max_element() >= HALF_CHUNK - BORDER_EPSILON
+}
+
+pub fn simplify_mesh(
+    vertices: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    material_ids: Vec<u32>,
+    indices: Vec<u32>,
+    settings: &MeshSimplification,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    let mut indices = indices;
+    if settings.weld_tolerance > 0.0 {
+        weld_vertices(&vertices, &mut indices, settings.weld_tolerance);
+    }
+    if settings.target_ratio < 1.0 && settings.max_error > 0.0 {
+        collapse_edges(&vertices, &material_ids, &mut indices, settings);
+    }
+    compact(vertices, normals, material_ids, indices)
+}
+
+//snaps interior vertices onto the first vertex found in the same tolerance sized cell
+//border vertices are kept as they are, the neighbor chunk welds its own side independently
+fn weld_vertices(vertices: &[Vec3], indices: &mut Vec<u32>, tolerance: f32) {
+    let mut cells: FxHashMap<(i32, i32, i32), u32> = FxHashMap::default();
+    let remap: Vec<u32> = vertices
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            if on_chunk_border(p) {
+                return i as u32;
+            }
+            let cell = (p / tolerance).floor().as_ivec3();
+            *cells.entry((cell.x, cell.y, cell.z)).or_insert(i as u32)
+        })
+        .collect();
+    let mut welded = Vec::with_capacity(indices.len());
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [
+            remap[tri[0] as usize],
+            remap[tri[1] as usize],
+            remap[tri[2] as usize],
+        ];
+        if a != b && b != c && a != c {
+            welded.extend_from_slice(&[a, b, c]);
+        }
+    }
+    *indices = welded;
+}
+
+//symmetric 4x4 plane quadric, upper triangle row major
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(n: DVec3, d: f64) -> Self {
+        Quadric([
+            n.x * n.x,
+            n.x * n.y,
+            n.x * n.z,
+            n.x * d,
+            n.y * n.y,
+            n.y * n.z,
+            n.y * d,
+            n.z * n.z,
+            n.z * d,
+            d * d,
+        ])
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+    }
+
+    //sum of squared distances from p to every plane folded into the quadric
+    fn error(&self, p: DVec3) -> f64 {
+        let q = &self.0;
+        q[0] * p.x * p.x
+            + 2.0 * q[1] * p.x * p.y
+            + 2.0 * q[2] * p.x * p.z
+            + 2.0 * q[3] * p.x
+            + q[4] * p.y * p.y
+            + 2.0 * q[5] * p.y * p.z
+            + 2.0 * q[6] * p.y
+            + q[7] * p.z * p.z
+            + 2.0 * q[8] * p.z
+            + q[9]
+    }
+}
+
+//garland heckbert edge collapse, the removed vertex always snaps onto the kept one
+//so no new positions are invented and the gradient normals computed by the mesher stay valid
+fn collapse_edges(
+    vertices: &[Vec3],
+    material_ids: &[u32],
+    indices: &mut Vec<u32>,
+    settings: &MeshSimplification,
+) {
+    let mut triangles: Vec<[u32; 3]> = indices
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .collect();
+    let mut alive = vec![true; triangles.len()];
+    let mut alive_count = triangles.len();
+    let target_count = (triangles.len() as f32 * settings.target_ratio.max(0.0)) as usize;
+    let max_error = (settings.max_error as f64).powi(2);
+    let mut vertex_triangles: Vec<Vec<u32>> = vec![Vec::new(); vertices.len()];
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    for (t, tri) in triangles.iter().enumerate() {
+        let [a, b, c] = tri.map(|i| vertices[i as usize].as_dvec3());
+        let n = (b - a).cross(c - a);
+        if n.length_squared() > 0.0 {
+            let n = n.normalize();
+            let plane = Quadric::from_plane(n, -n.dot(a));
+            for &v in tri {
+                quadrics[v as usize].add(&plane);
+            }
+        }
+        for &v in tri {
+            vertex_triangles[v as usize].push(t as u32);
+        }
+    }
+    let locked: Vec<bool> = vertices.iter().map(|&p| on_chunk_border(p)).collect();
+    let mut stamps = vec![0u32; vertices.len()];
+    let mut heap: BinaryHeap<CollapseCandidate> = BinaryHeap::new();
+    let push_edge =
+        |heap: &mut BinaryHeap<_>, quadrics: &[Quadric], stamps: &[u32], u: u32, v: u32| {
+            let (u_idx, v_idx) = (u as usize, v as usize);
+            if material_ids[u_idx] != material_ids[v_idx] || (locked[u_idx] && locked[v_idx]) {
+                return;
+            }
+            let mut q = quadrics[u_idx];
+            q.add(&quadrics[v_idx]);
+            let cost_at = |i: usize| q.error(vertices[i].as_dvec3()).abs(); //rounding can dip below zero
+            let (keep, remove, cost) = if locked[u_idx] {
+                (u, v, cost_at(u_idx))
+            } else if locked[v_idx] {
+                (v, u, cost_at(v_idx))
+            } else if cost_at(u_idx) <= cost_at(v_idx) {
+                (u, v, cost_at(u_idx))
+            } else {
+                (v, u, cost_at(v_idx))
+            };
+            if cost <= max_error {
+                heap.push(Reverse((
+                    cost.to_bits(),
+                    keep,
+                    remove,
+                    stamps[keep as usize],
+                    stamps[remove as usize],
+                )));
+            }
+        };
+    for tri in triangles.iter() {
+        for (u, v) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            if u < v {
+                push_edge(&mut heap, &quadrics, &stamps, u, v);
+            }
+        }
+    }
+    let mut neighbors_a = Vec::new();
+    let mut neighbors_b = Vec::new();
+    while alive_count > target_count {
+        let Some(Reverse((_, keep, remove, keep_stamp, remove_stamp))) = heap.pop() else {
+            break;
+        };
+        let (keep_idx, remove_idx) = (keep as usize, remove as usize);
+        if stamps[keep_idx] != keep_stamp || stamps[remove_idx] != remove_stamp {
+            continue;
+        }
+        //link condition, the edge must be shared by exactly as many triangles as common neighbors
+        //otherwise collapsing it pinches the surface into a non manifold shape
+        collect_neighbors(
+            keep,
+            &vertex_triangles[keep_idx],
+            &triangles,
+            &alive,
+            &mut neighbors_a,
+        );
+        collect_neighbors(
+            remove,
+            &vertex_triangles[remove_idx],
+            &triangles,
+            &alive,
+            &mut neighbors_b,
+        );
+        if !neighbors_a.contains(&remove) {
+            continue;
+        }
+        let common = neighbors_a
+            .iter()
+            .filter(|n| neighbors_b.contains(n))
+            .count();
+        let shared = vertex_triangles[remove_idx]
+            .iter()
+            .filter(|&&t| alive[t as usize] && triangles[t as usize].contains(&keep))
+            .count();
+        if common != shared {
+            continue;
+        }
+        //reject collapses that flip or flatten a triangle around the removed vertex
+        let target = vertices[keep_idx];
+        let flips = vertex_triangles[remove_idx].iter().any(|&t| {
+            let tri = triangles[t as usize];
+            if !alive[t as usize] || tri.contains(&keep) {
+                return false;
+            }
+            let corner = |i: u32| vertices[i as usize];
+            let before = (corner(tri[1]) - corner(tri[0])).cross(corner(tri[2]) - corner(tri[0]));
+            let moved = tri.map(|i| if i == remove { target } else { corner(i) });
+            let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+            after.dot(before) <= 0.0 || after.length_squared() <= f32::EPSILON
+        });
+        if flips {
+            continue;
+        }
+        let removed_triangles = std::mem::take(&mut vertex_triangles[remove_idx]);
+        for &t in removed_triangles.iter() {
+            let t_idx = t as usize;
+            if !alive[t_idx] {
+                continue;
+            }
+            if triangles[t_idx].contains(&keep) {
+                alive[t_idx] = false;
+                alive_count -= 1;
+            } else {
+                for v in triangles[t_idx].iter_mut() {
+                    if *v == remove {
+                        *v = keep;
+                    }
+                }
+                vertex_triangles[keep_idx].push(t);
+            }
+        }
+        vertex_triangles[keep_idx].retain(|&t| alive[t as usize]);
+        let removed_quadric = quadrics[remove_idx];
+        quadrics[keep_idx].add(&removed_quadric);
+        stamps[keep_idx] += 1;
+        stamps[remove_idx] += 1;
+        //only edges touching the kept vertex changed cost, everything else in the heap is still valid
+        collect_neighbors(
+            keep,
+            &vertex_triangles[keep_idx],
+            &triangles,
+            &alive,
+            &mut neighbors_a,
+        );
+        for &n in neighbors_a.iter() {
+            push_edge(&mut heap, &quadrics, &stamps, keep, n);
+        }
+    }
+    indices.clear();
+    for (tri, _) in triangles.iter().zip(alive.iter()).filter(|(_, a)| **a) {
+        indices.extend_from_slice(tri);
+    }
+}
+
+fn collect_neighbors(
+    vertex: u32,
+    vertex_triangles: &[u32],
+    triangles: &[[u32; 3]],
+    alive: &[bool],
+    out: &mut Vec<u32>,
+) {
+    out.clear();
+    for &t in vertex_triangles {
+        if !alive[t as usize] {
+            continue;
+        }
+        for &v in triangles[t as usize].iter() {
+            if v != vertex && !out.contains(&v) {
+                out.push(v);
+            }
+        }
+    }
+}
+
+//drops vertices no triangle references anymore
+fn compact(
+    vertices: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    material_ids: Vec<u32>,
+    mut indices: Vec<u32>,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    let mut remap = vec![u32::MAX; vertices.len()];
+    let mut out_vertices = Vec::new();
+    let mut out_normals = Vec::new();
+    let mut out_material_ids = Vec::new();
+    for i in indices.iter_mut() {
+        let old = *i as usize;
+        if remap[old] == u32::MAX {
+            remap[old] = out_vertices.len() as u32;
+            out_vertices.push(vertices[old]);
+            out_normals.push(normals[old]);
+            out_material_ids.push(material_ids[old]);
+        }
+        *i = remap[old];
+    }
+    (out_vertices, out_normals, out_material_ids, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //flat square covering the chunk's xz face at height y, n vertices per side, facing up
+    fn flat_grid(n: usize, y: f32) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+        let step = 2.0 * HALF_CHUNK / (n - 1) as f32;
+        let mut vertices = Vec::new();
+        for z in 0..n {
+            for x in 0..n {
+                vertices.push(Vec3::new(
+                    -HALF_CHUNK + x as f32 * step,
+                    y,
+                    -HALF_CHUNK + z as f32 * step,
+                ));
+            }
+        }
+        let mut indices = Vec::new();
+        for z in 0..n as u32 - 1 {
+            for x in 0..n as u32 - 1 {
+                let i = z * n as u32 + x;
+                let row = n as u32;
+                indices.extend_from_slice(&[i, i + row, i + 1, i + 1, i + row, i + row + 1]);
+            }
+        }
+        let count = vertices.len();
+        (vertices, vec![Vec3::Y; count], vec![0; count], indices)
+    }
+
+    #[test]
+    fn border_vertices_survive_simplification() {
+        let (vertices, normals, material_ids, indices) = flat_grid(9, 0.5);
+        let triangles_before = indices.len() / 3;
+        let border_before: Vec<Vec3> = vertices
+            .iter()
+            .copied()
+            .filter(|&p| on_chunk_border(p))
+            .collect();
+        let settings = MeshSimplification {
+            weld_tolerance: 0.1,
+            target_ratio: 0.25,
+            max_error: 0.01,
+        };
+        let (out_vertices, out_normals, _, out_indices) =
+            simplify_mesh(vertices.clone(), normals, material_ids, indices, &settings);
+        assert!(out_indices.len() / 3 < triangles_before);
+        assert_eq!(out_vertices.len(), out_normals.len());
+        //the border is untouched, nothing else moved either, collapses only snap onto kept vertices
+        for p in &border_before {
+            assert!(out_vertices.contains(p), "border vertex {p} was removed");
+        }
+        assert!(out_vertices.iter().all(|p| vertices.contains(p)));
+        //and the surface still faces up
+        for tri in out_indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| out_vertices[i as usize]);
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use glam::Vec3;
+
+use crate::{
+    chunk::MaterialCode,
+    constants::{CHUNK_WORLD_SIZE, HALF_CHUNK},
+    meshing::{
+        mc::{DensityField, interpolate_edge_from_base},
+        tables::{CORNER_OFFSETS, CUBE_FACE_EDGES, EDGE_VERTICES},
+    },
+};
+
+const NO_VERTEX: u32 = u32::MAX;
+
+//naive surface nets for far lods, one vertex per cell at the mean of its edge crossings and one quad per crossed edge
+//roughly a third of the triangles marching cubes makes on the same grid and no table lookups
+//densities and materials are the unpadded downscaled grid, normals come from normal_field
+//cells on the chunk border only average the crossings on their border faces, those are shared with the neighbor
+//so both chunks place the same vertices on the seam and same lod neighbors meet without a crack
+pub fn surface_nets_mesh_generation(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    samples_per_chunk_dim: usize,
+    normal_field: DensityField,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    let cells_per_chunk_dim = samples_per_chunk_dim - 1;
+    let voxel_size = CHUNK_WORLD_SIZE / cells_per_chunk_dim as f32;
+    let stride = samples_per_chunk_dim * samples_per_chunk_dim;
+    let cell_stride = cells_per_chunk_dim * cells_per_chunk_dim;
+    let mut cell_to_vertex = vec![NO_VERTEX; cell_stride * cells_per_chunk_dim];
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut material_ids = Vec::new();
+    let mut indices = Vec::new();
+    for z_idx in 0..cells_per_chunk_dim {
+        for y_idx in 0..cells_per_chunk_dim {
+            for x_idx in 0..cells_per_chunk_dim {
+                let sample_idx = z_idx * stride + y_idx * samples_per_chunk_dim + x_idx;
+                let corner_idx = |corner: usize| {
+                    let offset = CORNER_OFFSETS[corner];
+                    sample_idx
+                        + offset.z as usize * stride
+                        + offset.y as usize * samples_per_chunk_dim
+                        + offset.x as usize
+                };
+                let corner_densities: [f32; 8] =
+                    std::array::from_fn(|corner| densities[corner_idx(corner)] as f32);
+                let solid_corners = corner_densities.iter().filter(|d| **d < 0.0).count();
+                if solid_corners == 0 || solid_corners == 8 {
+                    continue;
+                }
+                let cell_world_pos = Vec3::new(
+                    -HALF_CHUNK + x_idx as f32 * voxel_size,
+                    -HALF_CHUNK + y_idx as f32 * voxel_size,
+                    -HALF_CHUNK + z_idx as f32 * voxel_size,
+                );
+                let boundary_faces = [
+                    x_idx == 0,
+                    x_idx == cells_per_chunk_dim - 1,
+                    y_idx == 0,
+                    y_idx == cells_per_chunk_dim - 1,
+                    z_idx == 0,
+                    z_idx == cells_per_chunk_dim - 1,
+                ];
+                let mut boundary_edges = 0u16;
+                for (face, edges) in CUBE_FACE_EDGES.iter().enumerate() {
+                    if boundary_faces[face] {
+                        for &edge in edges {
+                            boundary_edges |= 1 << edge;
+                        }
+                    }
+                }
+                let mut sum = Vec3::ZERO;
+                let mut count = 0;
+                let mut boundary_sum = Vec3::ZERO;
+                let mut boundary_count = 0;
+                for (edge, &(v1_idx, v2_idx)) in EDGE_VERTICES.iter().enumerate() {
+                    if (corner_densities[v1_idx] < 0.0) == (corner_densities[v2_idx] < 0.0) {
+                        continue;
+                    }
+                    let crossing = interpolate_edge_from_base(
+                        v1_idx,
+                        v2_idx,
+                        &corner_densities,
+                        CORNER_OFFSETS[v1_idx] * voxel_size + cell_world_pos,
+                        CORNER_OFFSETS[v2_idx] * voxel_size + cell_world_pos,
+                    );
+                    sum += crossing;
+                    count += 1;
+                    if boundary_edges & (1 << edge) != 0 {
+                        boundary_sum += crossing;
+                        boundary_count += 1;
+                    }
+                }
+                let position = if boundary_count > 0 {
+                    boundary_sum / boundary_count as f32
+                } else {
+                    sum / count as f32
+                };
+                let gradient = normal_field.gradient(position);
+                let normal = if gradient.length_squared() > 0.0001 {
+                    gradient.normalize()
+                } else {
+                    Vec3::Y
+                };
+                let material = pick_cell_material(materials, &corner_densities, corner_idx);
+                cell_to_vertex[z_idx * cell_stride + y_idx * cells_per_chunk_dim + x_idx] =
+                    vertices.len() as u32;
+                vertices.push(position);
+                normals.push(normal);
+                material_ids.push(material as u32);
+            }
+        }
+    }
+    //a sample edge crossing the surface gets a quad from the four cells around it
+    //edges on the border of the chunk only have some of those cells here and are closed by the seam vertices instead
+    let cell_vertex = |x: usize, y: usize, z: usize| {
+        cell_to_vertex[z * cell_stride + y * cells_per_chunk_dim + x]
+    };
+    for z in 0..samples_per_chunk_dim {
+        for y in 0..samples_per_chunk_dim {
+            for x in 0..samples_per_chunk_dim {
+                let here = densities[z * stride + y * samples_per_chunk_dim + x] < 0;
+                if x + 1 < samples_per_chunk_dim
+                    && y > 0
+                    && z > 0
+                    && y < samples_per_chunk_dim - 1
+                    && z < samples_per_chunk_dim - 1
+                {
+                    let there = densities[z * stride + y * samples_per_chunk_dim + x + 1] < 0;
+                    if here != there {
+                        push_quad(
+                            [
+                                cell_vertex(x, y - 1, z - 1),
+                                cell_vertex(x, y, z - 1),
+                                cell_vertex(x, y, z),
+                                cell_vertex(x, y - 1, z),
+                            ],
+                            here,
+                            &mut indices,
+                        );
+                    }
+                }
+                if y + 1 < samples_per_chunk_dim
+                    && x > 0
+                    && z > 0
+                    && x < samples_per_chunk_dim - 1
+                    && z < samples_per_chunk_dim - 1
+                {
+                    let there = densities[z * stride + (y + 1) * samples_per_chunk_dim + x] < 0;
+                    if here != there {
+                        push_quad(
+                            [
+                                cell_vertex(x - 1, y, z - 1),
+                                cell_vertex(x - 1, y, z),
+                                cell_vertex(x, y, z),
+                                cell_vertex(x, y, z - 1),
+                            ],
+                            here,
+                            &mut indices,
+                        );
+                    }
+                }
+                if z + 1 < samples_per_chunk_dim
+                    && x > 0
+                    && y > 0
+                    && x < samples_per_chunk_dim - 1
+                    && y < samples_per_chunk_dim - 1
+                {
+                    let there = densities[(z + 1) * stride + y * samples_per_chunk_dim + x] < 0;
+                    if here != there {
+                        push_quad(
+                            [
+                                cell_vertex(x - 1, y - 1, z),
+                                cell_vertex(x, y - 1, z),
+                                cell_vertex(x, y, z),
+                                cell_vertex(x - 1, y, z),
+                            ],
+                            here,
+                            &mut indices,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    (vertices, normals, material_ids, indices)
+}
+
+//corners are ordered counter clockwise seen from +axis, which faces air when the lower sample is solid
+#[inline(always)]
+fn push_quad(corners: [u32; 4], lower_is_solid: bool, indices: &mut Vec<u32>) {
+    let [a, b, c, d] = corners;
+    if lower_is_solid {
+        indices.extend_from_slice(&[a, b, c, a, c, d]);
+    } else {
+        indices.extend_from_slice(&[a, c, b, a, d, c]);
+    }
+}
+
+//same priority as the marching cubes edge rule, surface materials win over dirt
+#[inline(always)]
+pub(super) fn pick_cell_material(
+    materials: &[MaterialCode],
+    corner_densities: &[f32; 8],
+    corner_idx: impl Fn(usize) -> usize,
+) -> MaterialCode {
+    let mut picked = MaterialCode::Air;
+    for corner in 0..8 {
+        if corner_densities[corner] >= 0.0 {
+            continue;
+        }
+        let material = materials[corner_idx(corner)];
+        let rank = |m: MaterialCode| match m {
+            MaterialCode::Path => 6,
+            MaterialCode::Grass => 5,
+            MaterialCode::Snow => 4,
+            MaterialCode::Sand => 3,
+            MaterialCode::Water => 2, //below the land materials so shorelines read as land
+            MaterialCode::Dirt => 1,
+            MaterialCode::Air => 0,
+        };
+        if rank(material) > rank(picked) {
+            picked = material;
+        }
+    }
+    if picked == MaterialCode::Air {
+        MaterialCode::Dirt
+    } else {
+        picked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_surface_gets_a_quad_per_crossed_interior_edge() {
+        let samples_per_chunk_dim: usize = 9;
+        let voxel_size = CHUNK_WORLD_SIZE / (samples_per_chunk_dim - 1) as f32;
+        let surface_y = 0.3;
+        let mut densities = Vec::with_capacity(samples_per_chunk_dim.pow(3));
+        for _z in 0..samples_per_chunk_dim {
+            for y in 0..samples_per_chunk_dim {
+                for _x in 0..samples_per_chunk_dim {
+                    let local_y = -HALF_CHUNK + y as f32 * voxel_size;
+                    densities.push(((local_y - surface_y) * 1000.0) as i16);
+                }
+            }
+        }
+        let materials = vec![MaterialCode::Dirt; samples_per_chunk_dim.pow(3)];
+        let (vertices, normals, _, indices) = surface_nets_mesh_generation(
+            &densities,
+            &materials,
+            samples_per_chunk_dim,
+            DensityField::Downsampled(&densities, samples_per_chunk_dim),
+        );
+        let cells_per_chunk_dim = samples_per_chunk_dim - 1;
+        //one vertex per crossed cell, quads only for vertical edges with all four cells in the chunk
+        assert_eq!(vertices.len(), cells_per_chunk_dim.pow(2));
+        assert_eq!(indices.len(), (cells_per_chunk_dim - 1).pow(2) * 6);
+        assert!(vertices.iter().all(|p| (p.y - surface_y).abs() < 0.01));
+        assert!(normals.iter().all(|n| n.dot(Vec3::Y) > 0.99));
+        for tri in indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize]);
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use bevy::math::Vec3;
+use glam::Vec3;
 
 pub(crate) const EDGE_VERTICES: [(usize, usize); 12] = [
     (0, 1),
@@ -41,6 +41,16 @@ pub const EDGE_ID_OFFSETS: [(u16, u16, u16, u8); 12] = [
     (0, 1, 0, 2),
 ];
 
+//the 4 cube edges lying on each cube face, faces ordered -x, +x, -y, +y, -z, +z
+pub(crate) const CUBE_FACE_EDGES: [[usize; 4]; 6] = [
+    [3, 7, 8, 11],
+    [1, 5, 9, 10],
+    [0, 4, 8, 9],
+    [2, 6, 10, 11],
+    [0, 1, 2, 3],
+    [4, 5, 6, 7],
+];
+
 pub const TRIANGLE_TABLE: [[i32; 16]; 256] = [
     [
         -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
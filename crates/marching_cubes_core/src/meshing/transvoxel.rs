@@ -0,0 +1,828 @@
+use std::array::from_fn;
+
+use glam::{IVec3, Vec3};
+
+use crate::{
+    chunk::MaterialCode,
+    constants::{CHUNK_WORLD_SIZE, HALF_CHUNK},
+    meshing::{
+        mc::{
+            DensityField, MaterialResolution, MeshingScratch, interpolate_edge_from_base,
+            mc_mesh_generation_into,
+        },
+        tables::{CORNER_OFFSETS, CUBE_FACE_EDGES, EDGE_VERTICES, TRIANGLE_TABLE},
+    },
+};
+
+//fraction of a regular cell the boundary layer gives up to the transition strip, half of it to each layer
+const TRANSITION_CELL_WIDTH: f32 = 0.5;
+//contour points are matched by position, anything closer than 1/4096 of a world unit is the same point
+const POSITION_KEY_SCALE: f32 = 4096.0;
+
+//everything mc_mesh_generation_with_transitions meshes, mc_mesh_generation_into's inputs plus the neighbors
+pub struct TransitionMeshInput<'a> {
+    pub densities: &'a [i16],
+    pub materials: &'a [MaterialCode],
+    pub samples_per_chunk_dim: usize,
+    pub densities_padded: bool,
+    pub normal_field: DensityField<'a>,
+    //sampled on transition faces at each neighbor's resolution, the field the finer neighbors meshed from
+    pub fine_field: DensityField<'a>,
+    pub neighbor_samples_per_chunk_dim: [usize; 6], //-x, +x, -y, +y, -z, +z
+    pub material_resolution: MaterialResolution,
+}
+
+struct DensityGrid<'a> {
+    densities: &'a [i16],
+    dim: usize,
+    stride: usize,
+    offset: usize,
+}
+
+impl DensityGrid<'_> {
+    fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.densities[self.offset + z * self.stride + y * self.dim + x] as f32
+    }
+}
+
+//a point on a transition cell contour, keyed so both cells and faces that share a point agree on it
+#[derive(Clone, Copy)]
+struct ContourPoint {
+    key: IVec3,
+    position: Vec3,
+}
+
+impl ContourPoint {
+    fn new(position: Vec3) -> Self {
+        Self {
+            key: (position * POSITION_KEY_SCALE).round().as_ivec3(),
+            position,
+        }
+    }
+}
+
+//one chunk face that gets a transition strip, cut along the neighbor's sample lines and this chunk's
+//line positions are in steps of 1 / (neighbor cells * cells) of the chunk so lines from both sets compare exactly
+struct StripFace {
+    axis: usize,
+    u_axis: usize,
+    v_axis: usize,
+    is_max: bool,
+    corners: [[usize; 2]; 2], //cube corners on the face indexed by their (u, v) offset
+    edges: [usize; 4],
+    side_edges: [usize; 4], //cube edge on each side of the face, -u, +u, -v, +v
+    neighbor_cells: usize,
+    steps: usize,
+    lines: Vec<usize>,  //every line of either set, ascending
+    fine: Vec<usize>,   //index in lines of each of the neighbor's sample lines
+    coarse: Vec<usize>, //index in lines of each of this chunk's sample lines
+    values: Vec<f32>, //fine_field on the chunk face where two lines cross, rounded to stored units like the neighbor's samples
+}
+
+impl StripFace {
+    fn new(face: usize, cells: usize, neighbor_cells: usize, fine_field: DensityField) -> Self {
+        let axis = face / 2;
+        let is_max = face % 2 == 1;
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let local = if is_max { 1.0 } else { 0.0 };
+        let mut corners = [[0usize; 2]; 2];
+        for (corner, offset) in CORNER_OFFSETS.iter().enumerate() {
+            if offset[axis] == local {
+                corners[offset[u_axis] as usize][offset[v_axis] as usize] = corner;
+            }
+        }
+        let edges = CUBE_FACE_EDGES[face];
+        let side_edges = [
+            (corners[0][0], corners[0][1]),
+            (corners[1][0], corners[1][1]),
+            (corners[0][0], corners[1][0]),
+            (corners[0][1], corners[1][1]),
+        ]
+        .map(|(a, b)| {
+            *edges
+                .iter()
+                .find(|&&e| EDGE_VERTICES[e] == (a, b) || EDGE_VERTICES[e] == (b, a))
+                .unwrap()
+        });
+        let mut lines: Vec<usize> = (0..=neighbor_cells)
+            .map(|k| k * cells)
+            .chain((0..=cells).map(|j| j * neighbor_cells))
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        let index_of = |t: usize| lines.binary_search(&t).unwrap();
+        let fine = (0..=neighbor_cells).map(|k| index_of(k * cells)).collect();
+        let coarse = (0..=cells).map(|j| index_of(j * neighbor_cells)).collect();
+        let mut face = StripFace {
+            axis,
+            u_axis,
+            v_axis,
+            is_max,
+            corners,
+            edges,
+            side_edges,
+            neighbor_cells,
+            steps: cells * neighbor_cells,
+            lines,
+            fine,
+            coarse,
+            values: Vec::new(),
+        };
+        let n = face.lines.len();
+        face.values = (0..n * n)
+            .map(|i| fine_field.sample(face.position(0.0, i % n, i / n)).round())
+            .collect();
+        face
+    }
+
+    //where two lines cross on the plane depth inside the chunk face
+    fn position(&self, depth: f32, u: usize, v: usize) -> Vec3 {
+        let step = CHUNK_WORLD_SIZE / self.steps as f32;
+        let mut p = Vec3::ZERO;
+        p[self.axis] = if self.is_max {
+            HALF_CHUNK - depth
+        } else {
+            depth - HALF_CHUNK
+        };
+        p[self.u_axis] = -HALF_CHUNK + self.lines[u] as f32 * step;
+        p[self.v_axis] = -HALF_CHUNK + self.lines[v] as f32 * step;
+        p
+    }
+
+    fn value(&self, (u, v): (usize, usize)) -> f32 {
+        self.values[v * self.lines.len() + u]
+    }
+
+    fn solid(&self, at: (usize, usize)) -> bool {
+        self.value(at) < 0.0
+    }
+
+    //always interpolated from the lower end so every square and side sharing the edge agrees
+    fn plane_crossing(&self, depth: f32, a: (usize, usize), b: (usize, usize)) -> ContourPoint {
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        ContourPoint::new(crossing(
+            self.value(a),
+            self.value(b),
+            self.position(depth, a.0, a.1),
+            self.position(depth, b.0, b.1),
+        ))
+    }
+
+    //marching squares over one rectangle between lines, saddles resolved by the center average
+    fn square_segments(
+        &self,
+        depth: f32,
+        ring: [(usize, usize); 4],
+        segments: &mut Vec<(ContourPoint, ContourPoint)>,
+    ) {
+        let solid = ring.map(|at| self.solid(at));
+        let edge_point = |edge: usize| self.plane_crossing(depth, ring[edge], ring[(edge + 1) % 4]);
+        let crossed: Vec<usize> = (0..4).filter(|&e| solid[e] != solid[(e + 1) % 4]).collect();
+        match crossed.len() {
+            2 => segments.push((edge_point(crossed[0]), edge_point(crossed[1]))),
+            4 => {
+                let center = ring.iter().map(|&at| self.value(at)).sum::<f32>() * 0.25;
+                if (center < 0.0) == solid[0] {
+                    segments.push((edge_point(0), edge_point(1)));
+                    segments.push((edge_point(2), edge_point(3)));
+                } else {
+                    segments.push((edge_point(3), edge_point(0)));
+                    segments.push((edge_point(1), edge_point(2)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    //every rectangle between lines in [u0, u1] x [v0, v1]
+    fn area_segments(
+        &self,
+        depth: f32,
+        [u0, u1]: [usize; 2],
+        [v0, v1]: [usize; 2],
+        segments: &mut Vec<(ContourPoint, ContourPoint)>,
+    ) {
+        for u in u0..u1 {
+            for v in v0..v1 {
+                let ring = [(u, v), (u + 1, v), (u + 1, v + 1), (u, v + 1)];
+                self.square_segments(depth, ring, segments);
+            }
+        }
+    }
+}
+
+//one of the four sides of a strip cell, -u, +u, -v, +v
+struct CellSide {
+    along_v: bool,
+    far: usize,       //which corner of the cell along the fixed axis
+    fixed: usize,     //line the side lies on
+    ends: [usize; 2], //lines it runs between
+}
+
+impl CellSide {
+    fn all([u0, u1]: [usize; 2], [v0, v1]: [usize; 2]) -> [CellSide; 4] {
+        [(true, 0, u0), (true, 1, u1), (false, 0, v0), (false, 1, v1)].map(
+            |(along_v, far, fixed)| CellSide {
+                along_v,
+                far,
+                fixed,
+                ends: if along_v { [v0, v1] } else { [u0, u1] },
+            },
+        )
+    }
+
+    fn at(&self, i: usize) -> (usize, usize) {
+        if self.along_v {
+            (self.fixed, i)
+        } else {
+            (i, self.fixed)
+        }
+    }
+
+    //the (u, v) corner of the cell at one end
+    fn corner(&self, end: usize) -> (usize, usize) {
+        if self.along_v {
+            (self.far, end)
+        } else {
+            (end, self.far)
+        }
+    }
+}
+
+//crossings in walk order, paired up consecutively so both cells walking the same side get the same segments
+fn pair_crossings(crossings: &[ContourPoint], segments: &mut Vec<(ContourPoint, ContourPoint)>) {
+    for pair in crossings.chunks_exact(2) {
+        segments.push((pair[0], pair[1]));
+    }
+}
+
+//what every transition cell of a chunk reads
+struct TransitionStrip<'a> {
+    input: &'a TransitionMeshInput<'a>,
+    grid: DensityGrid<'a>,
+    cells: usize,
+    voxel_size: f32,
+    transition_faces: [bool; 6],
+}
+
+//same as mc_mesh_generation_into but faces whose neighbor is sampled finer than this chunk get a transition strip
+//lod grids don't nest 2:1 (63 full res cells to 31 at lod1) so Lengyel's transition cell tables don't apply,
+//instead the boundary layer of regular cells on those faces is squeezed inward and the gap is filled with two layers:
+//the outer one has the neighbor's own face cells so the seam vertices are the neighbor's, the inner one has this chunk's
+//cells so it meets the squeezed regular vertices, and the plane between them is cut along both sets of lines
+//every cell is closed by walking the contour around its boundary and fanning it
+//saddles on the seam use the center average where the neighbor uses its case table, vertices match either way
+//where two transition faces meet the strips overlap and leave a gap one strip wide along the chunk edge
+pub fn mc_mesh_generation_with_transitions(
+    input: &TransitionMeshInput,
+    scratch: &mut MeshingScratch,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>) {
+    mc_mesh_generation_into(
+        input.densities,
+        input.materials,
+        input.samples_per_chunk_dim,
+        input.densities_padded,
+        input.normal_field,
+        input.material_resolution,
+        scratch,
+    );
+    let (mut vertices, mut normals, mut material_ids, mut indices) = scratch.copy_mesh();
+    let transition_faces: [bool; 6] =
+        from_fn(|face| input.neighbor_samples_per_chunk_dim[face] > input.samples_per_chunk_dim);
+    if !transition_faces.contains(&true) {
+        return (vertices, normals, material_ids, indices);
+    }
+    let cells = input.samples_per_chunk_dim - 1;
+    let voxel_size = CHUNK_WORLD_SIZE / cells as f32;
+    for vertex in vertices.iter_mut() {
+        *vertex = shrink_boundary_layer(*vertex, &transition_faces, voxel_size);
+    }
+    let dim = if input.densities_padded {
+        input.samples_per_chunk_dim + 2
+    } else {
+        input.samples_per_chunk_dim
+    };
+    let stride = dim * dim;
+    let strip = TransitionStrip {
+        input,
+        grid: DensityGrid {
+            densities: input.densities,
+            dim,
+            stride,
+            offset: if input.densities_padded {
+                stride + dim + 1
+            } else {
+                0
+            },
+        },
+        cells,
+        voxel_size,
+        transition_faces,
+    };
+    let mut segments = Vec::new();
+    let mut emit = |segments: &[(ContourPoint, ContourPoint)], material| {
+        triangulate_contour(
+            segments,
+            material,
+            input.normal_field,
+            &mut vertices,
+            &mut normals,
+            &mut material_ids,
+            &mut indices,
+        );
+    };
+    for (face, _) in transition_faces.iter().enumerate().filter(|(_, t)| **t) {
+        let neighbor_cells = input.neighbor_samples_per_chunk_dim[face] - 1;
+        let face = StripFace::new(face, cells, neighbor_cells, input.fine_field);
+        for fine_u in 0..neighbor_cells {
+            for fine_v in 0..neighbor_cells {
+                segments.clear();
+                if let Some(material) =
+                    strip.outer_cell_contour(&face, [fine_u, fine_v], &mut segments)
+                {
+                    emit(&segments, material);
+                }
+            }
+        }
+        for cell_u in 0..cells {
+            for cell_v in 0..cells {
+                segments.clear();
+                if let Some(material) =
+                    strip.inner_cell_contour(&face, [cell_u, cell_v], &mut segments)
+                {
+                    emit(&segments, material);
+                }
+            }
+        }
+    }
+    (vertices, normals, material_ids, indices)
+}
+
+//maps the outermost cell layer [0, voxel] to [w, voxel] along every transition face so the strip has room
+//a pure function of position so vertices shared between cells move together
+fn shrink_boundary_layer(
+    mut position: Vec3,
+    transition_faces: &[bool; 6],
+    voxel_size: f32,
+) -> Vec3 {
+    let width = TRANSITION_CELL_WIDTH * voxel_size;
+    for (face, _) in transition_faces.iter().enumerate().filter(|(_, t)| **t) {
+        let axis = face / 2;
+        let is_max = face % 2 == 1;
+        let depth = if is_max {
+            HALF_CHUNK - position[axis]
+        } else {
+            position[axis] + HALF_CHUNK
+        };
+        if depth < voxel_size {
+            let squeezed = width + depth * (voxel_size - width) / voxel_size;
+            position[axis] = if is_max {
+                HALF_CHUNK - squeezed
+            } else {
+                squeezed - HALF_CHUNK
+            };
+        }
+    }
+    position
+}
+
+#[inline(always)]
+fn crossing(a: f32, b: f32, pa: Vec3, pb: Vec3) -> Vec3 {
+    let denom = b - a;
+    let t = if denom.abs() < 0.0001 {
+        0.5
+    } else {
+        (-a / denom).clamp(0.0, 1.0)
+    };
+    pa + t * (pb - pa)
+}
+
+impl TransitionStrip<'_> {
+    fn middle_depth(&self) -> f32 {
+        TRANSITION_CELL_WIDTH * self.voxel_size * 0.5
+    }
+
+    //the regular boundary cell behind a strip cell
+    fn boundary_cell(&self, face: &StripFace, [cell_u, cell_v]: [usize; 2]) -> [usize; 3] {
+        let mut cell = [0usize; 3];
+        cell[face.axis] = if face.is_max { self.cells - 1 } else { 0 };
+        cell[face.u_axis] = cell_u;
+        cell[face.v_axis] = cell_v;
+        cell
+    }
+
+    //the outer layer cell under one of the neighbor's face cells, from the chunk face to the middle plane
+    //returns None if the surface misses the cell
+    fn outer_cell_contour(
+        &self,
+        face: &StripFace,
+        [fine_u, fine_v]: [usize; 2],
+        segments: &mut Vec<(ContourPoint, ContourPoint)>,
+    ) -> Option<MaterialCode> {
+        let us = [face.fine[fine_u], face.fine[fine_u + 1]];
+        let vs = [face.fine[fine_v], face.fine[fine_v + 1]];
+        let first_solid = face.solid((us[0], vs[0]));
+        if (us[0]..=us[1]).all(|u| (vs[0]..=vs[1]).all(|v| face.solid((u, v)) == first_solid)) {
+            return None;
+        }
+        let middle = self.middle_depth();
+        //the neighbor's face cell as it is, then the middle plane cut along both sets of lines
+        let ring = [
+            (us[0], vs[0]),
+            (us[1], vs[0]),
+            (us[1], vs[1]),
+            (us[0], vs[1]),
+        ];
+        face.square_segments(0.0, ring, segments);
+        face.area_segments(middle, us, vs, segments);
+        //sides walk the outer edge then the middle edge back, both planes share values so the depth edges never cross
+        let mut crossings = Vec::new();
+        for side in CellSide::all(us, vs) {
+            crossings.clear();
+            let [i0, i1] = side.ends;
+            if face.solid(side.at(i0)) != face.solid(side.at(i1)) {
+                crossings.push(face.plane_crossing(0.0, side.at(i0), side.at(i1)));
+            }
+            for i in (i0..i1).rev() {
+                if face.solid(side.at(i)) != face.solid(side.at(i + 1)) {
+                    crossings.push(face.plane_crossing(middle, side.at(i), side.at(i + 1)));
+                }
+            }
+            pair_crossings(&crossings, segments);
+        }
+        //takes the material of the regular cell it sits in front of
+        let coarse_cell = |[i0, i1]: [usize; 2]| {
+            ((face.lines[i0] + face.lines[i1]) / 2 / face.neighbor_cells).min(self.cells - 1)
+        };
+        Some(self.cell_material(
+            face,
+            self.boundary_cell(face, [coarse_cell(us), coarse_cell(vs)]),
+        ))
+    }
+
+    //the inner layer cell in front of one regular boundary cell, from the middle plane to the squeezed regular cell
+    //returns None if the surface misses the cell
+    fn inner_cell_contour(
+        &self,
+        face: &StripFace,
+        cell_uv: [usize; 2],
+        segments: &mut Vec<(ContourPoint, ContourPoint)>,
+    ) -> Option<MaterialCode> {
+        let voxel_size = self.voxel_size;
+        let cell = self.boundary_cell(face, cell_uv);
+        //identical to the regular pass so inner plane points land exactly on the squeezed regular vertices
+        let cube_world_pos = Vec3::new(
+            -HALF_CHUNK + cell[0] as f32 * voxel_size,
+            -HALF_CHUNK + cell[1] as f32 * voxel_size,
+            -HALF_CHUNK + cell[2] as f32 * voxel_size,
+        );
+        let corner_densities: [f32; 8] = from_fn(|corner| {
+            let offset = CORNER_OFFSETS[corner];
+            self.grid.get(
+                cell[0] + offset.x as usize,
+                cell[1] + offset.y as usize,
+                cell[2] + offset.z as usize,
+            )
+        });
+        let us = [face.coarse[cell_uv[0]], face.coarse[cell_uv[0] + 1]];
+        let vs = [face.coarse[cell_uv[1]], face.coarse[cell_uv[1] + 1]];
+        let corner_density = |(cu, cv): (usize, usize)| corner_densities[face.corners[cu][cv]];
+        let first_solid = corner_density((0, 0)) < 0.0;
+        let inner_uniform = [(1, 0), (1, 1), (0, 1)]
+            .into_iter()
+            .all(|corner| (corner_density(corner) < 0.0) == first_solid);
+        if inner_uniform
+            && (us[0]..=us[1]).all(|u| (vs[0]..=vs[1]).all(|v| face.solid((u, v)) == first_solid))
+        {
+            return None;
+        }
+        let middle = self.middle_depth();
+        face.area_segments(middle, us, vs, segments);
+        //inner plane points are the squeezed regular vertices on the boundary face edges
+        let squeeze =
+            |position| shrink_boundary_layer(position, &self.transition_faces, voxel_size);
+        let inner_point = |edge: usize| {
+            let (v1_idx, v2_idx) = EDGE_VERTICES[edge];
+            let v1 = CORNER_OFFSETS[v1_idx] * voxel_size + cube_world_pos;
+            let v2 = CORNER_OFFSETS[v2_idx] * voxel_size + cube_world_pos;
+            let position = interpolate_edge_from_base(v1_idx, v2_idx, &corner_densities, v1, v2);
+            ContourPoint::new(squeeze(position))
+        };
+        //inner plane follows the regular cell's own triangles so saddles resolve the same way on both sides
+        let mask = corner_densities
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| **d >= 0.0)
+            .fold(0usize, |mask, (corner, _)| mask | 1 << corner);
+        for triangle in TRIANGLE_TABLE[mask]
+            .chunks_exact(3)
+            .take_while(|triangle| triangle[0] != -1)
+        {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|e| e as usize);
+            for (a, b) in [(a, b), (b, c), (c, a)] {
+                if face.edges.contains(&a) && face.edges.contains(&b) {
+                    segments.push((inner_point(a), inner_point(b)));
+                }
+            }
+        }
+        //sides walk the middle edge, down to the inner plane, the inner edge back and up again
+        let depth_crossing = |at: (usize, usize), corner: (usize, usize)| {
+            let (m, d) = (face.value(at), corner_density(corner));
+            ((m < 0.0) != (d < 0.0)).then(|| {
+                let corner_position =
+                    CORNER_OFFSETS[face.corners[corner.0][corner.1]] * voxel_size + cube_world_pos;
+                ContourPoint::new(crossing(
+                    m,
+                    d,
+                    face.position(middle, at.0, at.1),
+                    squeeze(corner_position),
+                ))
+            })
+        };
+        let mut crossings = Vec::new();
+        for (side, edge) in CellSide::all(us, vs).iter().zip(face.side_edges) {
+            crossings.clear();
+            let [i0, i1] = side.ends;
+            for i in i0..i1 {
+                if face.solid(side.at(i)) != face.solid(side.at(i + 1)) {
+                    crossings.push(face.plane_crossing(middle, side.at(i), side.at(i + 1)));
+                }
+            }
+            crossings.extend(depth_crossing(side.at(i1), side.corner(1)));
+            if (corner_density(side.corner(0)) < 0.0) != (corner_density(side.corner(1)) < 0.0) {
+                crossings.push(inner_point(edge));
+            }
+            crossings.extend(depth_crossing(side.at(i0), side.corner(0)));
+            pair_crossings(&crossings, segments);
+        }
+        Some(self.cell_material(face, cell))
+    }
+
+    //the material of the regular cell's face corners, same preference as regular edge vertices
+    fn cell_material(&self, face: &StripFace, cell: [usize; 3]) -> MaterialCode {
+        let samples_per_chunk_dim = self.input.samples_per_chunk_dim;
+        let material_at = |corner: usize| {
+            let offset = CORNER_OFFSETS[corner];
+            let x = cell[0] + offset.x as usize;
+            let y = cell[1] + offset.y as usize;
+            let z = cell[2] + offset.z as usize;
+            self.input.materials[(z * samples_per_chunk_dim + y) * samples_per_chunk_dim + x]
+        };
+        let face_materials = [
+            material_at(face.corners[0][0]),
+            material_at(face.corners[1][0]),
+            material_at(face.corners[1][1]),
+            material_at(face.corners[0][1]),
+        ];
+        [
+            MaterialCode::Path,
+            MaterialCode::Grass,
+            MaterialCode::Snow,
+            MaterialCode::Sand,
+            MaterialCode::Water,
+        ]
+        .into_iter()
+        .find(|m| face_materials.contains(m))
+        .or_else(|| face_materials.into_iter().find(|&m| m != MaterialCode::Air))
+        .unwrap_or(MaterialCode::Air)
+    }
+}
+
+//chains segments into closed loops and fans each loop around its centroid
+//winding is chosen so the face normal points up the density gradient, matching the regular cells
+fn triangulate_contour(
+    segments: &[(ContourPoint, ContourPoint)],
+    material: MaterialCode,
+    normal_field: DensityField,
+    vertices: &mut Vec<Vec3>,
+    normals: &mut Vec<Vec3>,
+    material_ids: &mut Vec<u32>,
+    indices: &mut Vec<u32>,
+) {
+    let mut used = vec![false; segments.len()];
+    let mut emit_vertex = |position: Vec3, vertices: &mut Vec<Vec3>| {
+        let gradient = normal_field.gradient(position);
+        let normal = if gradient.length_squared() > 0.0001 {
+            gradient.normalize()
+        } else {
+            Vec3::Y
+        };
+        let idx = vertices.len() as u32;
+        vertices.push(position);
+        normals.push(normal);
+        material_ids.push(material as u32);
+        idx
+    };
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut loop_points = vec![segments[start].0, segments[start].1];
+        let mut closed = false;
+        while let Some(&tail) = loop_points.last() {
+            if tail.key == loop_points[0].key && loop_points.len() > 1 {
+                loop_points.pop();
+                closed = true;
+                break;
+            }
+            let next = (0..segments.len()).find(|&s| {
+                !used[s] && (segments[s].0.key == tail.key || segments[s].1.key == tail.key)
+            });
+            let Some(next) = next else {
+                break;
+            };
+            used[next] = true;
+            let (a, b) = segments[next];
+            loop_points.push(if a.key == tail.key { b } else { a });
+        }
+        //an open chain means a point failed to match, skip it rather than emit a broken fan
+        if !closed || loop_points.len() < 3 {
+            continue;
+        }
+        let centroid =
+            loop_points.iter().map(|p| p.position).sum::<Vec3>() / loop_points.len() as f32;
+        let up = normal_field.gradient(centroid);
+        let center_idx = emit_vertex(centroid, vertices);
+        let ring: Vec<u32> = loop_points
+            .iter()
+            .map(|p| emit_vertex(p.position, vertices))
+            .collect();
+        for k in 0..ring.len() {
+            let a = ring[k];
+            let b = ring[(k + 1) % ring.len()];
+            let pa = vertices[a as usize];
+            let pb = vertices[b as usize];
+            if (pa - centroid).cross(pb - centroid).dot(up) >= 0.0 {
+                indices.extend_from_slice(&[center_idx, a, b]);
+            } else {
+                indices.extend_from_slice(&[center_idx, b, a]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::quantize_f32_to_i16, constants::SAMPLES_PER_CHUNK_DIM};
+
+    const COARSE_DIM: usize = 9;
+    const FINE_DIM: usize = 2 * COARSE_DIM - 1; //twice the cells, every other sample is a coarse one
+
+    //unpadded samples of sloped rolling ground over the chunk whose center is offset_x along x
+    fn ground(samples_per_chunk_dim: usize, offset_x: f32) -> Vec<i16> {
+        let voxel_size = CHUNK_WORLD_SIZE / (samples_per_chunk_dim - 1) as f32;
+        let mut densities = Vec::with_capacity(samples_per_chunk_dim.pow(3));
+        for z in 0..samples_per_chunk_dim {
+            for y in 0..samples_per_chunk_dim {
+                for x in 0..samples_per_chunk_dim {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * voxel_size
+                        - Vec3::splat(HALF_CHUNK);
+                    let height = 0.3 * (p.x + offset_x) + 0.2 * p.z + 0.3 * (0.4 * p.z).sin();
+                    densities.push(quantize_f32_to_i16(p.y - height - 0.35));
+                }
+            }
+        }
+        densities
+    }
+
+    //triangle edges with both ends on the plane x = face_x, ends in a fixed order
+    fn edges_on_face(vertices: &[Vec3], indices: &[u32], face_x: f32) -> Vec<(Vec3, Vec3)> {
+        let on_face = |p: Vec3| (p.x - face_x).abs() < 1e-4;
+        let mut edges = Vec::new();
+        for tri in indices.chunks_exact(3) {
+            for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+                let (a, b) = (vertices[a as usize], vertices[b as usize]);
+                if on_face(a) && on_face(b) {
+                    let ordered = (a.y, a.z) < (b.y, b.z);
+                    edges.push(if ordered { (a, b) } else { (b, a) });
+                }
+            }
+        }
+        edges
+    }
+
+    fn has_partner(edge: &(Vec3, Vec3), others: &[(Vec3, Vec3)]) -> bool {
+        others
+            .iter()
+            .any(|other| other.0.distance(edge.0) < 1e-3 && other.1.distance(edge.1) < 1e-3)
+    }
+
+    fn vertices_on_face(vertices: &[Vec3], face_x: f32) -> Vec<Vec3> {
+        vertices
+            .iter()
+            .copied()
+            .filter(|p| (p.x - face_x).abs() < 1e-4)
+            .collect()
+    }
+
+    fn has_vertex(vertex: Vec3, others: &[Vec3]) -> bool {
+        others.iter().any(|other| other.distance(vertex) < 1e-3)
+    }
+
+    //a coarse chunk with a finer neighbor on +x, both meshed on their own and the neighbor moved next to it
+    //returns the edges on the shared face of the coarse mesh, the neighbor mesh and plain marching cubes on the coarse grid
+    //and the vertices on it of the first two
+    #[allow(clippy::type_complexity)]
+    fn seam(coarse_dim: usize, fine_dim: usize) -> ([Vec<(Vec3, Vec3)>; 3], [Vec<Vec3>; 2]) {
+        let fine_field = ground(fine_dim, 0.0);
+        let densities = ground(coarse_dim, 0.0);
+        let materials = vec![MaterialCode::Dirt; coarse_dim.pow(3)];
+        let mut neighbor_samples_per_chunk_dim = [coarse_dim; 6];
+        neighbor_samples_per_chunk_dim[1] = fine_dim;
+        let mut scratch = MeshingScratch::new();
+        let (coarse_vertices, _, _, coarse_indices) = mc_mesh_generation_with_transitions(
+            &TransitionMeshInput {
+                densities: &densities,
+                materials: &materials,
+                samples_per_chunk_dim: coarse_dim,
+                densities_padded: false,
+                normal_field: DensityField::Downsampled(&densities, coarse_dim),
+                fine_field: DensityField::Downsampled(&fine_field, fine_dim),
+                neighbor_samples_per_chunk_dim,
+                material_resolution: MaterialResolution::default(),
+            },
+            &mut scratch,
+        );
+        let neighbor = ground(fine_dim, CHUNK_WORLD_SIZE);
+        mc_mesh_generation_into(
+            &neighbor,
+            &vec![MaterialCode::Dirt; fine_dim.pow(3)],
+            fine_dim,
+            false,
+            DensityField::Downsampled(&neighbor, fine_dim),
+            MaterialResolution::default(),
+            &mut scratch,
+        );
+        let (neighbor_vertices, _, _, neighbor_indices) = scratch.copy_mesh();
+        let neighbor_vertices: Vec<Vec3> = neighbor_vertices
+            .iter()
+            .map(|p| *p + Vec3::X * CHUNK_WORLD_SIZE)
+            .collect();
+        mc_mesh_generation_into(
+            &densities,
+            &materials,
+            coarse_dim,
+            false,
+            DensityField::Downsampled(&densities, coarse_dim),
+            MaterialResolution::default(),
+            &mut scratch,
+        );
+        let (plain_vertices, _, _, plain_indices) = scratch.copy_mesh();
+        (
+            [
+                edges_on_face(&coarse_vertices, &coarse_indices, HALF_CHUNK),
+                edges_on_face(&neighbor_vertices, &neighbor_indices, HALF_CHUNK),
+                edges_on_face(&plain_vertices, &plain_indices, HALF_CHUNK),
+            ],
+            [
+                vertices_on_face(&coarse_vertices, HALF_CHUNK),
+                vertices_on_face(&neighbor_vertices, HALF_CHUNK),
+            ],
+        )
+    }
+
+    fn assert_seam_closed(coarse_dim: usize, fine_dim: usize) {
+        let ([coarse_edges, neighbor_edges, plain_edges], [coarse_vertices, neighbor_vertices]) =
+            seam(coarse_dim, fine_dim);
+        assert!(!neighbor_edges.is_empty());
+        //every vertex and edge on the shared face is the other chunk's too, none are left open on either side
+        for vertex in &coarse_vertices {
+            assert!(
+                has_vertex(*vertex, &neighbor_vertices),
+                "stray vertex {vertex:?}"
+            );
+        }
+        for vertex in &neighbor_vertices {
+            assert!(
+                has_vertex(*vertex, &coarse_vertices),
+                "unmatched vertex {vertex:?}"
+            );
+        }
+        for edge in &coarse_edges {
+            assert!(has_partner(edge, &neighbor_edges), "open edge {edge:?}");
+        }
+        for edge in &neighbor_edges {
+            assert!(has_partner(edge, &coarse_edges), "open edge {edge:?}");
+        }
+        //plain marching cubes on the coarse grid leaves the seam open
+        assert!(
+            neighbor_edges
+                .iter()
+                .any(|edge| !has_partner(edge, &plain_edges))
+        );
+    }
+
+    #[test]
+    fn half_res_chunk_closes_the_seam_with_a_full_res_neighbor() {
+        assert_seam_closed(COARSE_DIM, FINE_DIM);
+    }
+
+    //in game the grids don't nest, 63 full res cells meet 31 lod1 cells and 31 meet 15 lod2 ones
+    #[test]
+    fn lod_chunks_close_the_seam_at_in_game_resolutions() {
+        assert_seam_closed(SAMPLES_PER_CHUNK_DIM / 2, SAMPLES_PER_CHUNK_DIM);
+        assert_seam_closed(SAMPLES_PER_CHUNK_DIM / 4, SAMPLES_PER_CHUNK_DIM / 2);
+    }
+}
@@ -0,0 +1,105 @@
+//one quantized sample of the signed distance field, negative is solid
+//generation, digging and the chunk files all go through this so they agree on the representable range
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SdfValue(pub i16);
+
+impl SdfValue {
+    pub const RANGE: f32 = 10.0; //world units, distances past this are clamped
+    pub const SCALE: f32 = i16::MAX as f32 / Self::RANGE;
+    pub const SOLID: SdfValue = SdfValue(-i16::MAX); //symmetric with AIR so negating never overflows
+    pub const AIR: SdfValue = SdfValue(i16::MAX);
+    pub const SURFACE: SdfValue = SdfValue(0);
+
+    #[inline(always)]
+    pub fn from_f32(distance: f32) -> Self {
+        let clamped = distance.clamp(-Self::RANGE, Self::RANGE);
+        SdfValue((clamped * Self::SCALE).round() as i16)
+    }
+
+    //raw values from older files or uniform fills may be i16::MIN, pulled back into range here
+    #[inline(always)]
+    pub fn from_raw(raw: i16) -> Self {
+        SdfValue(raw.max(Self::SOLID.0))
+    }
+
+    #[inline(always)]
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::SCALE
+    }
+
+    #[inline(always)]
+    pub fn is_solid(self) -> bool {
+        self.0 < 0
+    }
+
+    //adds a brush strength in world units, positive removes material, stays in range instead of wrapping
+    #[inline(always)]
+    pub fn saturating_add(self, amount: f32) -> Self {
+        Self::from_f32(self.to_f32() + amount)
+    }
+
+    //brush strength that moves a sample from self to target
+    #[inline(always)]
+    pub fn strength_to(self, target: SdfValue) -> f32 {
+        target.to_f32() - self.to_f32()
+    }
+}
+
+impl From<f32> for SdfValue {
+    fn from(distance: f32) -> Self {
+        SdfValue::from_f32(distance)
+    }
+}
+
+impl From<SdfValue> for f32 {
+    fn from(value: SdfValue) -> Self {
+        value.to_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_stays_within_one_step() {
+        let step = 1.0 / SdfValue::SCALE;
+        for i in -200..=200 {
+            let distance = i as f32 * 0.05;
+            let back = SdfValue::from_f32(distance).to_f32();
+            assert!(
+                (back - distance).abs() <= step,
+                "{distance} came back as {back}"
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_clamps_symmetrically() {
+        assert_eq!(SdfValue::from_f32(25.0), SdfValue::AIR);
+        assert_eq!(SdfValue::from_f32(-25.0), SdfValue::SOLID);
+        assert_eq!(SdfValue::from_f32(f32::NEG_INFINITY), SdfValue::SOLID);
+        assert_eq!(SdfValue::from_raw(i16::MIN), SdfValue::SOLID);
+        assert_eq!(SdfValue::AIR.to_f32(), SdfValue::RANGE);
+        assert_eq!(SdfValue::SOLID.to_f32(), -SdfValue::RANGE);
+    }
+
+    #[test]
+    fn brush_saturates_and_keeps_sign() {
+        assert_eq!(SdfValue::SOLID.saturating_add(-5.0), SdfValue::SOLID);
+        assert_eq!(SdfValue::AIR.saturating_add(5.0), SdfValue::AIR);
+        assert_eq!(SdfValue::SOLID.saturating_add(100.0), SdfValue::AIR);
+        let dug = SdfValue::from_f32(-1.0).saturating_add(1.5);
+        assert!(!dug.is_solid());
+        assert!((dug.to_f32() - 0.5).abs() <= 1.0 / SdfValue::SCALE);
+    }
+
+    #[test]
+    fn strength_to_reaches_target() {
+        let start = SdfValue::from_f32(-3.0);
+        let target = SdfValue::from_f32(2.0);
+        assert_eq!(start.saturating_add(start.strength_to(target)), target);
+    }
+}
@@ -0,0 +1,83 @@
+//bakes a grayscale png or tiff heightmap into the empty default world
+//cargo run -r --bin import_heightmap -- <image> [--meters-per-pixel <f>] [--height-scale <f>] [--base-height <f>] [--origin <x> <z>] [--data <dir>]
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bevy::math::Vec2;
+use marching_cubes::deformable_terrain::heightmap_import::{
+    HeightmapImportSettings, HeightmapTerrain, import_heightmap,
+};
+use marching_cubes::deformable_terrain::storage_paths::StoragePaths;
+
+fn usage() -> ExitCode {
+    eprintln!(
+        "usage: import_heightmap <image> [--meters-per-pixel <f>] [--height-scale <f>] [--base-height <f>] [--origin <x> <z>] [--data <dir>]"
+    );
+    eprintln!("black maps to base-height and white to base-height + height-scale");
+    ExitCode::FAILURE
+}
+
+fn next_f32(args: &mut impl Iterator<Item = String>) -> Option<f32> {
+    args.next().and_then(|v| v.parse().ok())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut image = None;
+    let mut data_dir = StoragePaths::default().world_dir();
+    let mut settings = HeightmapImportSettings::default();
+    while let Some(arg) = args.next() {
+        let parsed = match arg.as_str() {
+            "--meters-per-pixel" => next_f32(&mut args).map(|v| settings.meters_per_pixel = v),
+            "--height-scale" => next_f32(&mut args).map(|v| settings.height_scale = v),
+            "--base-height" => next_f32(&mut args).map(|v| settings.base_height = v),
+            "--origin" => next_f32(&mut args)
+                .zip(next_f32(&mut args))
+                .map(|(x, z)| settings.origin = Vec2::new(x, z)),
+            "--data" => args.next().map(|v| data_dir = PathBuf::from(v)),
+            _ if image.is_none() && !arg.starts_with("--") => {
+                image = Some(PathBuf::from(&arg));
+                Some(())
+            }
+            _ => None,
+        };
+        if parsed.is_none() {
+            return usage();
+        }
+    }
+    let Some(image) = image else {
+        return usage();
+    };
+    if settings.meters_per_pixel <= 0.0 {
+        return usage();
+    }
+    let terrain = match HeightmapTerrain::load(&image, settings) {
+        Ok(terrain) => terrain,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (min, max) = terrain.footprint();
+    let (low, high) = terrain.height_range();
+    println!(
+        "importing {} covering {min} to {max}, heights {low:.1} to {high:.1}",
+        image.display()
+    );
+    match import_heightmap(&terrain, &data_dir) {
+        Ok(report) => {
+            println!(
+                "wrote {} surface chunks, {} air chunks and {} dirt chunks to {}",
+                report.surface_chunks,
+                report.air_chunks,
+                report.dirt_chunks,
+                data_dir.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("import failed: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
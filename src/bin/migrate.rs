@@ -0,0 +1,88 @@
+//converts the saved default world between storage formats, or packs its region files
+//cargo run -r --bin migrate -- [from] <to> [--data <dir>]
+//cargo run -r --bin migrate -- compact [--data <dir>]
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use marching_cubes::deformable_terrain::file_loader::compact_region_files;
+use marching_cubes::deformable_terrain::migrate::{StorageFormat, migrate_world};
+use marching_cubes::deformable_terrain::storage_paths::StoragePaths;
+
+fn usage() -> ExitCode {
+    let names: Vec<&str> = StorageFormat::ALL.iter().map(|f| f.name()).collect();
+    eprintln!("usage: migrate [from] <to> [--data <dir>]");
+    eprintln!("       migrate compact [--data <dir>]");
+    eprintln!("formats: {}", names.join(", "));
+    eprintln!("from is detected from the saved files when omitted");
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut data_dir = StoragePaths::default().world_dir();
+    if let Some(i) = args.iter().position(|a| a == "--data") {
+        if i + 1 >= args.len() {
+            return usage();
+        }
+        data_dir = PathBuf::from(args.remove(i + 1));
+        args.remove(i);
+    }
+    if args == ["compact"] {
+        return compact(&data_dir);
+    }
+    let formats: Option<Vec<StorageFormat>> =
+        args.iter().map(|a| StorageFormat::from_name(a)).collect();
+    let (from, to) = match formats.as_deref() {
+        Some([to]) => match StorageFormat::detect(&data_dir) {
+            Some(from) => (from, *to),
+            None => {
+                eprintln!("could not detect the format of {}", data_dir.display());
+                return usage();
+            }
+        },
+        Some([from, to]) => (*from, *to),
+        _ => return usage(),
+    };
+    println!(
+        "migrating {} from {} to {}",
+        data_dir.display(),
+        from.name(),
+        to.name()
+    );
+    match migrate_world(&data_dir, from, to) {
+        Ok(report) => {
+            println!(
+                "migrated {} chunks, {:.2} MB -> {:.2} MB, originals kept in {}",
+                report.chunks,
+                report.source_bytes as f64 / 1_000_000.0,
+                report.destination_bytes as f64 / 1_000_000.0,
+                report.backup_dir
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("migration failed, world left untouched: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+//packs every region with a gap, the game only does it on startup for regions past its waste threshold
+fn compact(data_dir: &Path) -> ExitCode {
+    match compact_region_files(data_dir, 0.0) {
+        Ok(report) => {
+            println!(
+                "compacted {} regions holding {} chunks, {:.2} MB -> {:.2} MB",
+                report.regions,
+                report.chunks,
+                report.bytes_before as f64 / 1_000_000.0,
+                report.bytes_after as f64 / 1_000_000.0
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("compaction failed, regions packed so far are kept: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
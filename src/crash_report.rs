@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::log::BoxedLayer;
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::{Event, Subscriber};
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::prelude::*;
+use parking_lot::Mutex;
+
+use crate::deformable_terrain::chunk_budget::{
+    TERRAIN_MAP_BYTES, TERRAIN_MAP_CHUNKS, TERRAIN_MAP_EVICTIONS,
+};
+use crate::deformable_terrain::driver::{INITIAL_CHUNKS_LOADED, QUEUE_SIZE, RENDER_RADIUS_SQUARED};
+#[cfg(feature = "debug")]
+use crate::deformable_terrain::driver_debug_ui::{
+    CHUNK_SPAWN_RECEIVER_QUEUE_SIZE, CLUSTERS_PROCESSED, INTERNAL_QUEUE_SIZES,
+};
+use crate::deformable_terrain::file_loader::disk_size;
+use crate::deformable_terrain::migrate::StorageFormat;
+use crate::deformable_terrain::plugin::MoveableCenter;
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::world_stats::SAVE_FILES;
+
+const LOG_TAIL_LINES: usize = 200;
+
+//the panic hook cant reach the ecs so everything it reports is mirrored into statics
+static LOG_TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static PLAYER_POSITION: Mutex<Option<Vec3>> = Mutex::new(None);
+
+//keeps the last few formatted log events around for the crash bundle, passed to LogPlugin::custom_layer
+pub fn crash_log_layer(_app: &mut App) -> Option<BoxedLayer> {
+    Some(Box::new(CrashLogLayer))
+}
+
+struct CrashLogLayer;
+
+impl<S: Subscriber> Layer<S> for CrashLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!("{} {}:", metadata.level(), metadata.target());
+        event.record(&mut LineVisitor(&mut line));
+        let mut tail = LOG_TAIL.lock();
+        if tail.len() == LOG_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
+
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+pub fn record_crash_context(moveable_center: Res<MoveableCenter>) {
+    *PLAYER_POSITION.lock() = Some(moveable_center.read());
+}
+
+//chains onto the existing hook so the usual message still reaches stderr, then writes the bundle
+//installed before the app is built so panics during startup and on worker threads are covered too
+//storage is the same one the terrain plugin is given, the hook keeps its own copy
+pub fn install_crash_reporter(storage: StoragePaths) {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+        write_crash_bundle(info, &storage);
+    }));
+}
+
+//a panic inside the bundle writer would abort the process, every step here is best effort
+fn write_crash_bundle(info: &panic::PanicHookInfo, storage: &StoragePaths) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let dir = storage.crash_dir().join(format!("crash_{seconds}"));
+    if std::fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let thread = std::thread::current();
+    let mut report = format!(
+        "thread: {}\n{info}\n\n{}\n",
+        thread.name().unwrap_or("unnamed"),
+        std::backtrace::Backtrace::force_capture()
+    );
+    //the panicking thread may hold either lock, try_lock so a crash in the log layer cant deadlock the hook
+    match PLAYER_POSITION.try_lock().as_deref() {
+        Some(Some(position)) => {
+            let _ = writeln!(
+                report,
+                "player position: ({:.2}, {:.2}, {:.2})",
+                position.x, position.y, position.z
+            );
+        }
+        Some(None) => report.push_str("player position: not spawned yet\n"),
+        None => report.push_str("player position: unavailable\n"),
+    }
+    let _ = writeln!(report, "{}", streaming_stats());
+    let _ = std::fs::write(dir.join("report.txt"), report);
+    let _ = std::fs::write(
+        dir.join("manifest.txt"),
+        world_manifest(&storage.world_dir()),
+    );
+    if let Some(tail) = LOG_TAIL.try_lock() {
+        let log: Vec<&str> = tail.iter().map(String::as_str).collect();
+        let _ = std::fs::write(dir.join("log.txt"), log.join("\n"));
+    }
+    eprintln!("crash report written to {}", dir.display());
+}
+
+fn streaming_stats() -> String {
+    let mut stats = String::new();
+    let _ = write!(
+        stats,
+        "initial chunks loaded: {}\nload queue: {}\nrender radius: {:.0}",
+        INITIAL_CHUNKS_LOADED.load(Ordering::Relaxed),
+        QUEUE_SIZE.load(Ordering::Relaxed),
+        f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)).sqrt()
+    );
+    let _ = write!(
+        stats,
+        "\nterrain map: {:.1} MB in {} chunks, {} evicted",
+        TERRAIN_MAP_BYTES.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        TERRAIN_MAP_CHUNKS.load(Ordering::Relaxed),
+        TERRAIN_MAP_EVICTIONS.load(Ordering::Relaxed)
+    );
+    #[cfg(feature = "debug")]
+    {
+        let _ = write!(
+            stats,
+            "\nspawn receiver queue: {}\nclusters processed: {}",
+            CHUNK_SPAWN_RECEIVER_QUEUE_SIZE.load(Ordering::Relaxed),
+            CLUSTERS_PROCESSED.load(Ordering::Relaxed)
+        );
+        if let Some(sizes) = INTERNAL_QUEUE_SIZES.get() {
+            let sizes: Vec<usize> = sizes.iter().map(|s| s.load(Ordering::Relaxed)).collect();
+            let _ = write!(stats, "\ncompute threads busy: {sizes:?}");
+        }
+    }
+    stats
+}
+
+//what is on disk and which layout it is in, enough to tell a corrupt store from an old one
+fn world_manifest(data_dir: &Path) -> String {
+    let mut manifest = format!(
+        "world: {}\nstorage format: {}\n",
+        data_dir.display(),
+        StorageFormat::detect(data_dir).map_or("unknown", |format| format.name())
+    );
+    for (name, path) in SAVE_FILES {
+        match disk_size(&data_dir.join(path)) {
+            Some(size) => {
+                let _ = writeln!(manifest, "{name}: {path}, {size} bytes");
+            }
+            None => {
+                let _ = writeln!(manifest, "{name}: {path}, missing");
+            }
+        }
+    }
+    manifest
+}
@@ -0,0 +1,134 @@
+use std::fs::{copy, create_dir_all, read_dir};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED};
+use crate::deformable_terrain::chunk_generator::MaterialCode;
+use crate::deformable_terrain::chunk_history::ChunkModifiedTimes;
+use crate::deformable_terrain::file_loader::{
+    RegionFiles, load_chunk, load_chunk_index_map, unix_millis_now, write_chunk,
+};
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::world_header::WORLD_HEADER_FILE;
+use crate::player::spawn_points::SPAWN_POINTS_FILE;
+use crate::ui::console::{Console, ConsoleCommand};
+
+const BACKUP_DIR: &str = "backups"; //inside the world, each world keeps its own
+const COPIED_FILES: [&str; 5] = [
+    "air_compression_data.txt",
+    "dirt_compression_data.txt",
+    "player_data.txt",
+    WORLD_HEADER_FILE,
+    SPAWN_POINTS_FILE,
+]; //small enough to copy whole every time
+
+#[derive(Debug)]
+pub struct BackupReport {
+    pub chunks: usize,
+    pub dir: PathBuf,
+}
+
+//backups are named by the unix millis they were taken at, 0 when there are none yet
+fn last_backup(backup_root: &Path) -> u64 {
+    let Ok(entries) = read_dir(backup_root) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+//writes every chunk changed since the newest backup into a new backup as region files, next to copies of the small files
+//the first backup holds everything, restoring replays them oldest first so later chunks win
+pub fn incremental_backup(
+    data_dir: &Path,
+    chunk_modified_times: &ChunkModifiedTimes,
+) -> Result<BackupReport, String> {
+    let backup_root = data_dir.join(BACKUP_DIR);
+    let since = last_backup(&backup_root);
+    let taken = unix_millis_now(); //before collecting, anything written from here on is picked up next time
+    let changed: Vec<(ChunkCoord, u64)> = chunk_modified_times
+        .iter_chunks_modified_since(since)
+        .collect();
+    let dir = backup_root.join(taken.to_string());
+    create_dir_all(&dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    //headers are written before the times are recorded, so every changed chunk is in this map
+    let mut summaries = FxHashMap::default();
+    let index_map = load_chunk_index_map(&data_dir, &mut summaries, &mut FxHashMap::default());
+    let mut source = RegionFiles::reader(&data_dir);
+    let mut destination = RegionFiles::writer(&dir)
+        .map_err(|e| format!("failed to open backup region files: {e}"))?;
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+    let mut serial_buffer = Vec::new();
+    let mut chunks = 0;
+    for (chunk_coord, modified) in changed {
+        let (Some(&offset), Some(summary)) =
+            (index_map.get(&chunk_coord), summaries.get(&chunk_coord))
+        else {
+            continue;
+        };
+        load_chunk(
+            &mut source,
+            &chunk_coord,
+            offset,
+            &mut densities,
+            &mut materials,
+        )
+        .map_err(|e| format!("failed to read chunk {chunk_coord}: {e}"))?;
+        write_chunk(
+            &densities,
+            &materials,
+            summary,
+            modified,
+            &chunk_coord,
+            &mut FxHashMap::default(),
+            &mut destination,
+            &mut serial_buffer,
+        )
+        .map_err(|e| format!("failed to back up chunk {chunk_coord}: {e}"))?;
+        chunks += 1;
+    }
+    for name in COPIED_FILES {
+        let path = data_dir.join(name);
+        if path.exists() {
+            copy(&path, dir.join(name)).map_err(|e| format!("failed to copy {name}: {e}"))?;
+        }
+    }
+    Ok(BackupReport { chunks, dir })
+}
+
+//`backup` writes an incremental backup on a background thread and prints where it went
+pub fn backup_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    storage: Res<StoragePaths>,
+    chunk_modified_times: Res<ChunkModifiedTimes>,
+) {
+    for command in command_reader.read() {
+        if command.name != "backup" {
+            continue;
+        }
+        console.print("backing up changed chunks...");
+        let output = console.sender();
+        let chunk_modified_times = chunk_modified_times.clone();
+        let data_dir = storage.world_dir();
+        thread::spawn(move || {
+            let line = match incremental_backup(&data_dir, &chunk_modified_times) {
+                Ok(report) => format!(
+                    "backed up {} chunks to {}",
+                    report.chunks,
+                    report.dir.display()
+                ),
+                Err(e) => format!("backup failed: {e}"),
+            };
+            let _ = output.send(line);
+        });
+    }
+}
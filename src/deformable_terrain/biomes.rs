@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+use fastnoise2::{
+    SafeNode,
+    generator::{Generator, GeneratorWrapper, simplex::opensimplex2},
+};
+
+use crate::{
+    constants::{HALF_CHUNK, SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE},
+    deformable_terrain::{
+        chunk_generator::{MaterialCode, quantize_f32_to_i16},
+        world_gen::channel_seed,
+    },
+};
+
+const CLIMATE_FREQUENCY: f32 = 0.0008; //temperature and humidity, roughly one biome per 1200 world units
+const CONTINENTALNESS_FREQUENCY: f32 = 0.0003;
+const CONTINENTAL_RELIEF: f32 = 40.0; //world units the whole landscape rises or sinks with continentalness
+const BIOME_BLEND: f32 = 0.15; //climate distance over which neighboring biomes blend their height curves
+const COLD_BELOW: f32 = -0.2;
+const HOT_ABOVE: f32 = 0.2;
+const DRY_BELOW: f32 = 0.0;
+pub const BIOME_COUNT: usize = 3;
+
+thread_local! {
+    static CLIMATE_NOISE: GeneratorWrapper<SafeNode> = opensimplex2().build();
+}
+
+//ids are stored in chunk summaries, only append
+#[repr(u8)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Biome {
+    #[default]
+    Plains = 0,
+    Desert = 1,
+    SnowPeaks = 2,
+}
+
+//raw heights are scaled and then offset, in world units
+#[derive(Debug, Clone, Copy)]
+pub struct HeightProfile {
+    pub scale: f32,
+    pub offset: f32,
+}
+
+//top material for the first `depth` world units below the surface, dirt under that
+//underwater replaces the top material below sea level, roads always win over the stack
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceStack {
+    pub top: MaterialCode,
+    pub underwater: MaterialCode,
+    pub depth: f32,
+}
+
+impl Biome {
+    pub const ALL: [Biome; BIOME_COUNT] = [Biome::Plains, Biome::Desert, Biome::SnowPeaks];
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Plains => "plains",
+            Biome::Desert => "desert",
+            Biome::SnowPeaks => "snow peaks",
+        }
+    }
+
+    pub fn height_profile(&self) -> HeightProfile {
+        match self {
+            Biome::Plains => HeightProfile {
+                scale: 1.0,
+                offset: 0.0,
+            },
+            Biome::Desert => HeightProfile {
+                scale: 0.35,
+                offset: 15.0,
+            },
+            Biome::SnowPeaks => HeightProfile {
+                scale: 1.6,
+                offset: 60.0,
+            },
+        }
+    }
+
+    pub fn surface_stack(&self) -> SurfaceStack {
+        match self {
+            Biome::Plains => SurfaceStack {
+                top: MaterialCode::Grass,
+                underwater: MaterialCode::Sand,
+                depth: 1.0,
+            },
+            Biome::Desert => SurfaceStack {
+                top: MaterialCode::Sand,
+                underwater: MaterialCode::Sand,
+                depth: 4.0,
+            },
+            Biome::SnowPeaks => SurfaceStack {
+                top: MaterialCode::Snow,
+                underwater: MaterialCode::Sand,
+                depth: 2.0,
+            },
+        }
+    }
+
+    //same as the surface stack depth, quantized for comparing against densities
+    pub fn stack_threshold(&self) -> i16 {
+        quantize_f32_to_i16(-self.surface_stack().depth)
+    }
+}
+
+//the three low frequency channels biomes are picked from, each roughly in -1..1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+    pub continentalness: f32,
+}
+
+impl Climate {
+    //weights sum to 1 and change smoothly with the climate so blended heights never step at a biome border
+    pub fn biome_weights(&self) -> [f32; BIOME_COUNT] {
+        let cold = smoothstep(
+            COLD_BELOW + BIOME_BLEND,
+            COLD_BELOW - BIOME_BLEND,
+            self.temperature,
+        );
+        let hot = smoothstep(
+            HOT_ABOVE - BIOME_BLEND,
+            HOT_ABOVE + BIOME_BLEND,
+            self.temperature,
+        );
+        let dry = smoothstep(
+            DRY_BELOW + BIOME_BLEND,
+            DRY_BELOW - BIOME_BLEND,
+            self.humidity,
+        );
+        //cold and hot never overlap as long as the blend is under half the gap between them
+        let snow = cold;
+        let desert = hot * dry;
+        [1.0 - snow - desert, desert, snow]
+    }
+
+    pub fn biome(&self) -> Biome {
+        let weights = self.biome_weights();
+        let dominant = (0..BIOME_COUNT)
+            .max_by(|&a, &b| weights[a].total_cmp(&weights[b]))
+            .unwrap();
+        Biome::ALL[dominant]
+    }
+
+    //applies the blended height curves to a raw fbm height
+    pub fn shape_height(&self, raw_height: f32) -> f32 {
+        let weights = self.biome_weights();
+        let mut scale = 0.0;
+        let mut offset = 0.0;
+        for (biome, weight) in Biome::ALL.iter().zip(weights) {
+            let profile = biome.height_profile();
+            scale += profile.scale * weight;
+            offset += profile.offset * weight;
+        }
+        raw_height * scale + offset + self.continentalness * CONTINENTAL_RELIEF
+    }
+}
+
+pub fn climate_at(x: f32, z: f32) -> Climate {
+    CLIMATE_NOISE.with(|noise| Climate {
+        temperature: noise.gen_single_2d(
+            x * CLIMATE_FREQUENCY,
+            z * CLIMATE_FREQUENCY,
+            channel_seed(10),
+        ),
+        humidity: noise.gen_single_2d(
+            x * CLIMATE_FREQUENCY,
+            z * CLIMATE_FREQUENCY,
+            channel_seed(11),
+        ),
+        continentalness: noise.gen_single_2d(
+            x * CONTINENTALNESS_FREQUENCY,
+            z * CONTINENTALNESS_FREQUENCY,
+            channel_seed(12),
+        ),
+    })
+}
+
+//query for gameplay code, only x and z matter
+pub fn biome_at(world_pos: Vec3) -> Biome {
+    climate_at(world_pos.x, world_pos.z).biome()
+}
+
+//climate on the same lattice as generate_noise_height_grid, x_start and z_start are in units of spacing
+pub fn climate_grid(
+    x_start: i32,
+    z_start: i32,
+    width: usize,
+    depth: usize,
+    spacing: f32,
+) -> Vec<Climate> {
+    let mut temperature = vec![0.0; width * depth];
+    let mut humidity = vec![0.0; width * depth];
+    let mut continentalness = vec![0.0; width * depth];
+    CLIMATE_NOISE.with(|noise| {
+        for (out, frequency, seed) in [
+            (&mut temperature, CLIMATE_FREQUENCY, channel_seed(10)),
+            (&mut humidity, CLIMATE_FREQUENCY, channel_seed(11)),
+            (
+                &mut continentalness,
+                CONTINENTALNESS_FREQUENCY,
+                channel_seed(12),
+            ),
+        ] {
+            noise.gen_uniform_grid_2d(
+                out,
+                x_start,
+                z_start,
+                width as i32,
+                depth as i32,
+                frequency * spacing,
+                seed,
+            );
+        }
+    });
+    (0..width * depth)
+        .map(|i| Climate {
+            temperature: temperature[i],
+            humidity: humidity[i],
+            continentalness: continentalness[i],
+        })
+        .collect()
+}
+
+//reshapes raw fbm heights laid out like generate_noise_height_grid by the biome height curves
+pub fn shape_heights(
+    heights: &mut [f32],
+    x_start: i32,
+    z_start: i32,
+    width: usize,
+    depth: usize,
+    spacing: f32,
+) {
+    let climates = climate_grid(x_start, z_start, width, depth, spacing);
+    for (height, climate) in heights.iter_mut().zip(climates) {
+        *height = climate.shape_height(*height);
+    }
+}
+
+//dominant biome per padded heightmap column, climate comes from the 5x5 lattice the chunk heights are sampled on
+//and is bilinearly interpolated so borders follow the climate instead of the lattice
+pub fn fill_biome_columns(biomes: &mut [Biome], chunk_start: &Vec3) {
+    let x_start = ((chunk_start.x - HALF_CHUNK) / HALF_CHUNK) as i32;
+    let z_start = ((chunk_start.z - HALF_CHUNK) / HALF_CHUNK) as i32;
+    let climates = climate_grid(x_start, z_start, 5, 5, HALF_CHUNK);
+    let lattice_coord = |p: usize| {
+        let g = 1.0 + (p as f32 - 1.0) * VOXEL_WORLD_SIZE / HALF_CHUNK;
+        let g0 = (g as usize).min(3);
+        (g0, g - g0 as f32)
+    };
+    let lerp_climate = |a: Climate, b: Climate, t: f32| Climate {
+        temperature: a.temperature + (b.temperature - a.temperature) * t,
+        humidity: a.humidity + (b.humidity - a.humidity) * t,
+        continentalness: a.continentalness + (b.continentalness - a.continentalness) * t,
+    };
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        let (z0, tz) = lattice_coord(z);
+        for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let (x0, tx) = lattice_coord(x);
+            let near = lerp_climate(climates[z0 * 5 + x0], climates[z0 * 5 + x0 + 1], tx);
+            let far = lerp_climate(
+                climates[(z0 + 1) * 5 + x0],
+                climates[(z0 + 1) * 5 + x0 + 1],
+                tx,
+            );
+            biomes[z * SAMPLES_PER_CHUNK_DIM_PADDED + x] = lerp_climate(near, far, tz).biome();
+        }
+    }
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
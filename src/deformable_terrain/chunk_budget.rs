@@ -0,0 +1,375 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::prelude::*;
+use crossbeam_channel::Sender;
+use parking_lot::RwLock;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::CHUNK_WORLD_SIZE,
+    conversions::chunk_coord_to_world_pos,
+    deformable_terrain::{
+        chunk_generator::{MaterialCode, calculate_chunk_start},
+        chunk_pool::ChunkBufferPool,
+        chunk_store::ChunkStore,
+        density_source::DensitySource,
+        driver::{ChunkBuffers, MEMORY_BUDGET, WriteCmd, try_load_chunk},
+        file_loader::RegionFiles,
+        heightmap_cache::HeightmapCache,
+        plugin::Uniformity,
+        terrain::{NonUniformTerrainChunk, TerrainChunk},
+        terrain_error::TerrainError,
+    },
+};
+
+pub static TERRAIN_MAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+pub static TERRAIN_MAP_CHUNKS: AtomicUsize = AtomicUsize::new(0); //non uniform chunks, the ones a chunk budget counts
+pub static TERRAIN_MAP_EVICTIONS: AtomicUsize = AtomicUsize::new(0); //since startup
+
+const RESIDENT_RADIUS: f32 = 3.0 * CHUNK_WORLD_SIZE; //evicted chunks this close to the center are loaded back, well past digging reach
+const EVICTION_MIN_RADIUS: f32 = RESIDENT_RADIUS + CHUNK_WORLD_SIZE; //gap so a chunk at the edge doesnt flip between evicted and loaded
+const EVICT_DOWN_TO: f32 = 0.9; //of the budget, so eviction doesnt run again on the next insert
+
+//caps what the terrain chunk map keeps in memory, only non uniform chunks are evicted since uniform ones are a few bytes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TerrainMemoryBudget {
+    #[default]
+    Unlimited,
+    Bytes(usize),
+    Chunks(usize), //non uniform chunks
+}
+
+impl TerrainMemoryBudget {
+    fn exceeded(&self, bytes: usize, chunks: usize, fraction: f32) -> bool {
+        match *self {
+            TerrainMemoryBudget::Unlimited => false,
+            TerrainMemoryBudget::Bytes(limit) => bytes as f32 > limit as f32 * fraction,
+            TerrainMemoryBudget::Chunks(limit) => chunks as f32 > limit as f32 * fraction,
+        }
+    }
+
+    //each shard of the chunk store is held to an equal part of the budget
+    fn share(&self, shards: usize) -> TerrainMemoryBudget {
+        match *self {
+            TerrainMemoryBudget::Unlimited => TerrainMemoryBudget::Unlimited,
+            TerrainMemoryBudget::Bytes(limit) => TerrainMemoryBudget::Bytes(limit.div_ceil(shards)),
+            TerrainMemoryBudget::Chunks(limit) => {
+                TerrainMemoryBudget::Chunks(limit.div_ceil(shards))
+            }
+        }
+    }
+}
+
+struct ChunkEntry {
+    chunk: TerrainChunk,
+    last_used: u64,
+    edited: bool, //dug since it was loaded
+}
+
+//one shard of the chunk store with the last use of each chunk for eviction
+//chunks evicted for the budget are remembered so the svo manager can load them back when the center comes near
+#[derive(Default)]
+pub(crate) struct TerrainChunks {
+    entries: FxHashMap<ChunkCoord, ChunkEntry>,
+    evicted: FxHashSet<ChunkCoord>,
+    clock: u64,
+    bytes: usize,
+    non_uniform: usize,
+}
+
+fn chunk_bytes(chunk: &TerrainChunk) -> usize {
+    let samples = match chunk {
+        TerrainChunk::NonUniformTerrainChunk(chunk) => {
+            chunk.densities.len() * size_of::<i16>()
+                + chunk.materials.len() * size_of::<MaterialCode>()
+        }
+        TerrainChunk::UniformAir | TerrainChunk::UniformDirt => 0,
+    };
+    size_of::<(ChunkCoord, ChunkEntry)>() + samples
+}
+
+impl TerrainChunks {
+    pub(crate) fn get(&self, chunk_coord: &ChunkCoord) -> Option<&TerrainChunk> {
+        self.entries.get(chunk_coord).map(|entry| &entry.chunk)
+    }
+
+    //like get but counts as a use, for reads on the way to an edit
+    pub(crate) fn touch(&mut self, chunk_coord: &ChunkCoord) -> Option<&TerrainChunk> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(chunk_coord)?;
+        entry.last_used = self.clock;
+        Some(&entry.chunk)
+    }
+
+    pub(crate) fn contains_key(&self, chunk_coord: &ChunkCoord) -> bool {
+        self.entries.contains_key(chunk_coord)
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &ChunkCoord> {
+        self.entries.keys()
+    }
+
+    pub(crate) fn evicted(&self) -> impl Iterator<Item = &ChunkCoord> {
+        self.evicted.iter()
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub(crate) fn non_uniform(&self) -> usize {
+        self.non_uniform
+    }
+
+    //as loaded or generated, the same as what the loader would produce again
+    pub(crate) fn insert(
+        &mut self,
+        chunk_coord: ChunkCoord,
+        chunk: TerrainChunk,
+    ) -> Option<TerrainChunk> {
+        self.insert_entry(chunk_coord, chunk, false)
+    }
+
+    //dug this session, written back again if it is evicted
+    pub(crate) fn insert_edited(
+        &mut self,
+        chunk_coord: ChunkCoord,
+        chunk: TerrainChunk,
+    ) -> Option<TerrainChunk> {
+        self.insert_entry(chunk_coord, chunk, true)
+    }
+
+    //returns the chunk it replaced
+    fn insert_entry(
+        &mut self,
+        chunk_coord: ChunkCoord,
+        chunk: TerrainChunk,
+        edited: bool,
+    ) -> Option<TerrainChunk> {
+        self.clock += 1;
+        self.evicted.remove(&chunk_coord);
+        self.bytes += chunk_bytes(&chunk);
+        if matches!(chunk, TerrainChunk::NonUniformTerrainChunk(_)) {
+            self.non_uniform += 1;
+        }
+        let entry = ChunkEntry {
+            chunk,
+            last_used: self.clock,
+            edited,
+        };
+        let old = self.entries.insert(chunk_coord, entry)?;
+        self.forget_entry(&old);
+        Some(old.chunk)
+    }
+
+    pub(crate) fn remove(&mut self, chunk_coord: &ChunkCoord) -> Option<TerrainChunk> {
+        self.evicted.remove(chunk_coord);
+        let old = self.entries.remove(chunk_coord)?;
+        self.forget_entry(&old);
+        Some(old.chunk)
+    }
+
+    fn forget_entry(&mut self, entry: &ChunkEntry) {
+        self.bytes -= chunk_bytes(&entry.chunk);
+        if matches!(entry.chunk, TerrainChunk::NonUniformTerrainChunk(_)) {
+            self.non_uniform -= 1;
+        }
+    }
+
+    //drops the least recently used non uniform chunks away from the centers until the shard is back under its budget
+    //chunks within EVICTION_MIN_RADIUS of any center are kept even if that leaves it over, on_evict gets each chunk and whether it was edited
+    pub(crate) fn evict(
+        &mut self,
+        centers: &[Vec3],
+        budget: TerrainMemoryBudget,
+        mut on_evict: impl FnMut(ChunkCoord, NonUniformTerrainChunk, bool),
+    ) {
+        if !budget.exceeded(self.bytes, self.non_uniform, 1.0) {
+            return;
+        }
+        let min_distance_squared = EVICTION_MIN_RADIUS * EVICTION_MIN_RADIUS;
+        let mut candidates: Vec<(u64, ChunkCoord)> = self
+            .entries
+            .iter()
+            .filter(|(chunk_coord, entry)| {
+                let chunk_pos = chunk_coord_to_world_pos(chunk_coord);
+                matches!(entry.chunk, TerrainChunk::NonUniformTerrainChunk(_))
+                    && centers
+                        .iter()
+                        .all(|center| chunk_pos.distance_squared(*center) > min_distance_squared)
+            })
+            .map(|(chunk_coord, entry)| (entry.last_used, *chunk_coord))
+            .collect();
+        candidates.sort_unstable();
+        for (_, chunk_coord) in candidates {
+            if !budget.exceeded(self.bytes, self.non_uniform, EVICT_DOWN_TO) {
+                break;
+            }
+            let entry = self.entries.remove(&chunk_coord).unwrap();
+            self.forget_entry(&entry);
+            self.evicted.insert(chunk_coord);
+            TERRAIN_MAP_EVICTIONS.fetch_add(1, Ordering::Relaxed);
+            if let TerrainChunk::NonUniformTerrainChunk(chunk) = entry.chunk {
+                on_evict(chunk_coord, chunk, entry.edited);
+            }
+        }
+    }
+}
+
+//evicts from and loads back into the chunk store, runs on the svo manager thread
+//disk reads and regeneration happen with no shard locked
+pub(crate) struct ChunkPager {
+    pub(crate) write_sender: Sender<WriteCmd>,
+    pub(crate) index_map_read: Arc<FxHashMap<ChunkCoord, u64>>,
+    pub(crate) index_map_delta: Arc<RwLock<FxHashMap<ChunkCoord, u64>>>,
+    pub(crate) region_files_read: RegionFiles,
+    pub(crate) source: Arc<dyn DensitySource>,
+    pub(crate) heightmap_cache: Arc<HeightmapCache>,
+    pub(crate) chunk_buffers: Box<ChunkBuffers>,
+    pub(crate) chunk_pool: ChunkBufferPool,
+    pub(crate) unsynced: bool, //edited chunks were sent to the write thread since the last sync
+}
+
+impl ChunkPager {
+    //centers are every observer's position, chunks near any of them stay resident
+    pub(crate) fn evict(&mut self, store: &ChunkStore, centers: &[Vec3]) {
+        let budget = MEMORY_BUDGET.read().share(store.shard_count());
+        let (mut bytes, mut non_uniform) = (0, 0);
+        store.for_each_shard(|chunks| {
+            chunks.evict(centers, budget, |chunk_coord, chunk, edited| {
+                //digs are written through already, this makes sure the stored copy matches what gets dropped
+                if edited {
+                    let _ = self.write_sender.send(WriteCmd::UpdateNonUniform {
+                        densities: chunk.densities,
+                        materials: chunk.materials,
+                        chunk_coord,
+                    });
+                    self.unsynced = true;
+                } else {
+                    self.chunk_pool
+                        .recycle(TerrainChunk::NonUniformTerrainChunk(chunk));
+                }
+            });
+            bytes += chunks.bytes();
+            non_uniform += chunks.non_uniform();
+        });
+        TERRAIN_MAP_BYTES.store(bytes, Ordering::Relaxed);
+        TERRAIN_MAP_CHUNKS.store(non_uniform, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reload_near(&mut self, store: &ChunkStore, centers: &[Vec3]) {
+        let resident_squared = RESIDENT_RADIUS * RESIDENT_RADIUS;
+        let mut near = Vec::new();
+        store.for_each_shard(|chunks| {
+            near.extend(chunks.evicted().copied().filter(|chunk_coord| {
+                let chunk_pos = chunk_coord_to_world_pos(chunk_coord);
+                centers
+                    .iter()
+                    .any(|center| chunk_pos.distance_squared(*center) <= resident_squared)
+            }));
+        });
+        for chunk_coord in near {
+            //left evicted on a failed read, the next pass tries it again
+            let chunk = match self.load(chunk_coord) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    error!("failed to page chunk {chunk_coord} back in: {e}");
+                    continue;
+                }
+            };
+            if let Some(replaced) = store.insert(chunk_coord, chunk) {
+                self.chunk_pool.recycle(replaced);
+            }
+        }
+    }
+
+    //from disk if it was ever written, otherwise generated again the way the loader did
+    fn load(&mut self, chunk_coord: ChunkCoord) -> Result<TerrainChunk, TerrainError> {
+        if self.unsynced {
+            let (done_sender, done_receiver) = crossbeam_channel::bounded(1);
+            let _ = self.write_sender.send(WriteCmd::Sync {
+                done: Some(done_sender),
+            });
+            let _ = done_receiver.recv();
+            self.unsynced = false;
+        }
+        let uniformity = try_load_chunk(
+            chunk_coord,
+            &self.index_map_read,
+            &self.index_map_delta,
+            &mut self.region_files_read,
+            &mut self.chunk_buffers,
+        )?;
+        match uniformity {
+            Uniformity::Air => return Ok(TerrainChunk::UniformAir),
+            Uniformity::Dirt => return Ok(TerrainChunk::UniformDirt),
+            Uniformity::NonUniform => {}
+            Uniformity::Unknown => {
+                //only non uniform chunks are evicted, so the source fills it without classifying
+                let chunk_start = calculate_chunk_start(&chunk_coord);
+                self.heightmap_cache.prepare_column(
+                    &*self.source,
+                    (chunk_coord.0, chunk_coord.2),
+                    &chunk_start,
+                    &mut self.chunk_buffers,
+                );
+                self.source.fill_chunk(chunk_start, &mut self.chunk_buffers);
+            }
+        }
+        Ok(TerrainChunk::NonUniformTerrainChunk(
+            self.chunk_pool
+                .chunk_from(&self.chunk_buffers.density, &self.chunk_buffers.material),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn non_uniform() -> TerrainChunk {
+        TerrainChunk::NonUniformTerrainChunk(NonUniformTerrainChunk {
+            densities: Arc::from(vec![0i16; 8]),
+            materials: Arc::from(vec![MaterialCode::Dirt; 8]),
+        })
+    }
+
+    #[test]
+    fn least_recently_used_far_chunks_are_evicted_first() {
+        let mut chunks = TerrainChunks::default();
+        chunks.insert(ChunkCoord(0, 0, 0), non_uniform()); //under the center, never evicted
+        chunks.insert(ChunkCoord(10, 0, 0), non_uniform());
+        chunks.insert_edited(ChunkCoord(11, 0, 0), non_uniform());
+        chunks.insert(ChunkCoord(12, 0, 0), non_uniform());
+        chunks.insert(ChunkCoord(13, 0, 0), TerrainChunk::UniformAir);
+        chunks.touch(&ChunkCoord(10, 0, 0));
+        let mut evicted = Vec::new();
+        chunks.evict(
+            &[Vec3::ZERO],
+            TerrainMemoryBudget::Chunks(3),
+            |chunk_coord, _, edited| evicted.push((chunk_coord, edited)),
+        );
+        //3 would fit the budget but not 0.9 of it, so a second one goes
+        assert_eq!(
+            evicted,
+            [(ChunkCoord(11, 0, 0), true), (ChunkCoord(12, 0, 0), false)]
+        );
+        assert_eq!(chunks.non_uniform(), 2);
+        assert!(
+            chunks.contains_key(&ChunkCoord(0, 0, 0)) && chunks.contains_key(&ChunkCoord(13, 0, 0))
+        );
+        assert_eq!(chunks.evicted().count(), 2);
+        chunks.insert(ChunkCoord(12, 0, 0), non_uniform());
+        chunks.remove(&ChunkCoord(11, 0, 0));
+        assert_eq!(chunks.evicted().count(), 0);
+        chunks.remove(&ChunkCoord(12, 0, 0));
+        let left = 2 * chunk_bytes(&non_uniform()) + chunk_bytes(&TerrainChunk::UniformAir);
+        assert_eq!(chunks.bytes(), left);
+        assert_eq!(
+            TerrainMemoryBudget::Chunks(100).share(16),
+            TerrainMemoryBudget::Chunks(7)
+        );
+    }
+}
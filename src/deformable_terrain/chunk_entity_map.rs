@@ -1,16 +1,18 @@
 use bevy::prelude::*;
 use rustc_hash::FxHashMap;
 
+use crate::chunk_coord::ChunkCoord;
+
 //store mesh handle to be able to replace mesh without the entity being spawned to avoid crash NotSpawned(ValidButNotSpawned(EntityValidButNotSpawnedError
 #[derive(Resource)]
-pub struct ChunkEntityMap(FxHashMap<(i16, i16, i16), (Entity, Handle<Mesh>)>);
+pub struct ChunkEntityMap(FxHashMap<ChunkCoord, (Entity, Handle<Mesh>)>);
 
 impl ChunkEntityMap {
     pub(crate) fn new() -> ChunkEntityMap {
         ChunkEntityMap(FxHashMap::default())
     }
 
-    pub(crate) fn insert(&mut self, chunk_coord: (i16, i16, i16), entity: (Entity, Handle<Mesh>)) {
+    pub(crate) fn insert(&mut self, chunk_coord: ChunkCoord, entity: (Entity, Handle<Mesh>)) {
         #[cfg(feature = "debug")]
         {
             assert!(
@@ -26,14 +28,14 @@ impl ChunkEntityMap {
 
     pub(crate) fn replace_mesh_handle(
         &mut self,
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
         new_mesh_handle: Handle<Mesh>,
     ) {
         let (_, mesh_handle) = self.0.get_mut(&chunk_coord).unwrap();
         *mesh_handle = new_mesh_handle;
     }
 
-    pub fn get(&self, chunk_coord: (i16, i16, i16)) -> (Entity, Handle<Mesh>) {
+    pub fn get(&self, chunk_coord: ChunkCoord) -> (Entity, Handle<Mesh>) {
         #[cfg(feature = "debug")]
         {
             let result = self.0.get(&chunk_coord);
@@ -49,11 +51,11 @@ impl ChunkEntityMap {
         }
     }
 
-    pub fn get_option(&self, chunk_coord: (i16, i16, i16)) -> Option<&(Entity, Handle<Mesh>)> {
+    pub fn get_option(&self, chunk_coord: ChunkCoord) -> Option<&(Entity, Handle<Mesh>)> {
         self.0.get(&chunk_coord)
     }
 
-    pub fn remove(&mut self, chunk_coord: (i16, i16, i16)) -> (Entity, Handle<Mesh>) {
+    pub fn remove(&mut self, chunk_coord: ChunkCoord) -> (Entity, Handle<Mesh>) {
         #[cfg(feature = "debug")]
         {
             let result = self.0.remove(&chunk_coord);
@@ -0,0 +1,105 @@
+use bevy::{ecs::system::SystemParam, math::bounding::Aabb3d, prelude::*};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::{HALF_CHUNK, VOXEL_WORLD_SIZE},
+    deformable_terrain::driver::DirtyRange,
+};
+
+//a chunk got an entity, from streaming or from an edit that gave an empty chunk a surface
+//always followed by a ChunkMeshed for the same chunk in the same frame
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ChunkLoaded {
+    pub coord: ChunkCoord,
+    pub entity: Entity,
+}
+
+//a chunk's entity has a new mesh, on spawn, on every lod change and once the remesh after an edit lands
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ChunkMeshed {
+    pub coord: ChunkCoord,
+    pub entity: Entity,
+}
+
+//a chunk's entity was despawned, from streaming or from an edit that left the chunk without a surface
+//the entity is already queued for despawn when this is read, only use it as a key
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ChunkUnloaded {
+    pub coord: ChunkCoord,
+    pub entity: Entity,
+}
+
+//the samples of a chunk inside bounds were changed in the chunk map, the frame the edit is applied
+//bounds is in world space and may be a little larger than what the edit actually changed
+//the new mesh follows a frame or so later as a ChunkMeshed
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ChunkEdited {
+    pub coord: ChunkCoord,
+    pub bounds: Aabb3d,
+}
+
+//the chunk lifecycle writers, shared by the spawn receiver and the terrain editor
+#[derive(SystemParam)]
+pub struct ChunkEventWriters<'w> {
+    loaded_writer: MessageWriter<'w, ChunkLoaded>,
+    meshed_writer: MessageWriter<'w, ChunkMeshed>,
+    unloaded_writer: MessageWriter<'w, ChunkUnloaded>,
+    edited_writer: MessageWriter<'w, ChunkEdited>,
+}
+
+impl ChunkEventWriters<'_> {
+    pub(crate) fn spawned(&mut self, coord: ChunkCoord, entity: Entity) {
+        self.loaded_writer.write(ChunkLoaded { coord, entity });
+        self.meshed_writer.write(ChunkMeshed { coord, entity });
+    }
+
+    pub(crate) fn meshed(&mut self, coord: ChunkCoord, entity: Entity) {
+        self.meshed_writer.write(ChunkMeshed { coord, entity });
+    }
+
+    pub(crate) fn despawned(&mut self, coord: ChunkCoord, entity: Entity) {
+        self.unloaded_writer.write(ChunkUnloaded { coord, entity });
+    }
+
+    pub(crate) fn edited(&mut self, coord: ChunkCoord, dirty: &DirtyRange) {
+        self.edited_writer.write(ChunkEdited {
+            coord,
+            bounds: dirty_world_bounds(coord, dirty),
+        });
+    }
+}
+
+//the world space box over the padded samples of the dirty range, padding included
+fn dirty_world_bounds(coord: ChunkCoord, dirty: &DirtyRange) -> Aabb3d {
+    let padded_origin = coord.to_world_center() - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+    let to_world = |(x, y, z): (usize, usize, usize)| {
+        padded_origin + Vec3::new(x as f32, y as f32, z as f32) * VOXEL_WORLD_SIZE
+    };
+    Aabb3d {
+        min: to_world(dirty.min).into(),
+        max: to_world(dirty.max).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SAMPLES_PER_CHUNK_DIM_PADDED;
+
+    #[test]
+    fn dirty_bounds_cover_the_chunk_and_its_padding() {
+        let last = SAMPLES_PER_CHUNK_DIM_PADDED - 1;
+        let coord = ChunkCoord(1, -2, 0);
+        let bounds = dirty_world_bounds(
+            coord,
+            &DirtyRange {
+                min: (0, 0, 0),
+                max: (last, last, last),
+            },
+        );
+        let padding = Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+        assert!(Vec3::from(bounds.min).abs_diff_eq(coord.to_world_center() - padding, 1e-4));
+        //padded samples run one voxel past the far face too
+        assert!(Vec3::from(bounds.max).abs_diff_eq(coord.to_world_center() + padding, 1e-4));
+    }
+}
@@ -6,24 +6,37 @@ use fastnoise2::{
 };
 
 use crate::{
+    chunk_coord::ChunkCoord,
     constants::{
-        CHUNK_WORLD_SIZE, HALF_CHUNK, NOISE_AMPLITUDE, NOISE_FREQUENCY, SAMPLES_PER_CHUNK_2D,
-        SAMPLES_PER_CHUNK_2D_PADDED, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
-        VOXEL_WORLD_SIZE, WORLD_SEED,
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_2D_PADDED,
+        SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
     },
-    deformable_terrain::{driver::ChunkBuffers, plugin::Uniformity},
+    deformable_terrain::{
+        biomes::{Biome, climate_at, fill_biome_columns, shape_heights},
+        driver::ChunkBuffers,
+        hydrology::{channel_depth_at, fill_water},
+        plugin::Uniformity,
+        sdf_value::SdfValue,
+        structures::{stamp_structures, stamp_structures_downsampled},
+        world_gen::{CaveSettings, channel_seed, world_gen_config},
+    },
+};
+
+pub use marching_cubes_core::chunk::{
+    MATERIAL_COUNT, MaterialCode, UnknownMaterial, dequantize_i16_to_f32, downscale,
+    quantize_f32_to_i16, sample_trilinear_density,
 };
 
-const SCALE: f32 = 32767.0 / 10.0; // Map [-10, 10] to [-32767, 32767]
-const SCALE_INV: f32 = 1.0 / SCALE;
+//the cave field is sampled every CAVE_LATTICE_STEP voxels on a grid aligned across chunks and trilinearly upsampled
+//63 cells per chunk is a multiple of the step so neighboring chunks sample the same lattice points on their shared face
+const CAVE_LATTICE_STEP: usize = 3;
+const CAVE_LATTICE_DIM: usize = (SAMPLES_PER_CHUNK_DIM_PADDED + 1) / CAVE_LATTICE_STEP + 2; //one past the last padded sample
+pub const CAVE_LATTICE_LEN: usize = CAVE_LATTICE_DIM.pow(3);
+const CAVE_NOISE_GRADIENT: f32 = 2.0; //rough slope of opensimplex2 per unit of noise space, turns noise into distance
+const CAVERN_THRESHOLD: f32 = 0.6; //how much of the cavern channel opens up, higher is rarer
 
-#[repr(u8)]
-#[derive(PartialEq, Copy, Clone, Debug)]
-pub enum MaterialCode {
-    Air = 0,
-    Dirt = 1,
-    Grass = 2,
-    Sand = 3,
+thread_local! {
+    static CAVE_NOISE: GeneratorWrapper<SafeNode> = opensimplex2().build();
 }
 
 pub fn get_fbm() -> GeneratorWrapper<SafeNode> {
@@ -32,9 +45,108 @@ pub fn get_fbm() -> GeneratorWrapper<SafeNode> {
 }
 
 //assumed to only be called on full res buffers
-pub fn generate_chunk_into_buffers(chunk_start: Vec3, chunk_buffers: &mut ChunkBuffers) {
+pub fn generate_chunk_into_buffers(
+    chunk_start: Vec3,
+    chunk_buffers: &mut ChunkBuffers,
+    fbm: &GeneratorWrapper<SafeNode>,
+) {
     fill_voxel_densities(chunk_buffers, &chunk_start);
-    //place trees
+    fill_water(chunk_buffers, &chunk_start);
+    carve_caves(chunk_buffers, &chunk_start);
+    stamp_structures(chunk_buffers, &chunk_start, fbm);
+}
+
+//far lod version of generate_chunk_into_buffers, samples the terrain at out_dim^3 points spread over the chunk
+//the same points downscale takes, so the mesh matches what downscaling the full res chunk would give
+//columns are interpolated from the full res heightmap prepare_column made, the cave lattice is sampled as usual
+//densities_out and materials_out are unpadded
+pub fn generate_downsampled_into(
+    chunk_start: Vec3,
+    chunk_buffers: &mut ChunkBuffers,
+    fbm: &GeneratorWrapper<SafeNode>,
+    out_dim: usize,
+    densities_out: &mut [i16],
+    materials_out: &mut [MaterialCode],
+) {
+    let solid_threshold = quantize_f32_to_i16(-1.0);
+    let step = (SAMPLES_PER_CHUNK_DIM - 1) as f32 / (out_dim - 1) as f32; //in full res samples
+    let spacing = step * VOXEL_WORLD_SIZE;
+    fill_biome_columns(&mut chunk_buffers.biome, &chunk_start);
+    let carves = fill_cave_lattice(chunk_buffers, &chunk_start) > -10.0;
+    for z in 0..out_dim {
+        let padded_z = 1.0 + z as f32 * step;
+        for x in 0..out_dim {
+            let padded_x = 1.0 + x as f32 * step;
+            let terrain_height = sample_column(&chunk_buffers.heightmap, padded_x, padded_z);
+            let gx = sample_column(&chunk_buffers.dhdx, padded_x, padded_z);
+            let gz = sample_column(&chunk_buffers.dhdz, padded_x, padded_z);
+            let nearest = padded_z.round() as usize * SAMPLES_PER_CHUNK_DIM_PADDED
+                + padded_x.round() as usize;
+            let biome = chunk_buffers.biome[nearest];
+            let on_path = chunk_buffers.path_mask[nearest];
+            let water_level = chunk_buffers.water_level[nearest];
+            for y in 0..out_dim {
+                let world_y = chunk_start.y + y as f32 * spacing;
+                let vertical_dist = world_y - terrain_height;
+                let mut q = quantize_f32_to_i16(surface_distance(vertical_dist, gx, gz));
+                //a full res chunk has samples between these, the solid one nearest the surface decides the material
+                //so look as far up as the next coarse sample would, clamped to still being solid
+                let nearest_solid = quantize_f32_to_i16(surface_distance(
+                    vertical_dist + spacing - VOXEL_WORLD_SIZE,
+                    gx,
+                    gz,
+                ))
+                .min(-1);
+                let underwater = world_y < 0.0 || world_y < water_level;
+                let mut material = if q >= 0 {
+                    MaterialCode::Air
+                } else {
+                    voxel_material(nearest_solid, solid_threshold, biome, underwater, on_path)
+                };
+                if water_level != f32::NEG_INFINITY {
+                    let water = quantize_f32_to_i16(world_y - water_level);
+                    if water < q {
+                        if q >= 0 && water < 0 {
+                            material = MaterialCode::Water;
+                        }
+                        q = water;
+                    }
+                }
+                if carves {
+                    let carve = quantize_f32_to_i16(cave_carve_at(
+                        &chunk_buffers.cave[0],
+                        padded_x,
+                        1.0 + y as f32 * step,
+                        padded_z,
+                    ));
+                    if carve > q {
+                        q = carve;
+                        if carve >= 0 {
+                            material = MaterialCode::Air;
+                        }
+                    }
+                }
+                let idx = (z * out_dim + y) * out_dim + x;
+                densities_out[idx] = q;
+                materials_out[idx] = material;
+            }
+        }
+    }
+    stamp_structures_downsampled(densities_out, materials_out, out_dim, &chunk_start, fbm);
+}
+
+//bilinear between the padded heightmap columns around a fractional padded sample
+fn sample_column(values: &[f32], padded_x: f32, padded_z: f32) -> f32 {
+    let max = SAMPLES_PER_CHUNK_DIM_PADDED - 1;
+    let (x0, z0) = (padded_x as usize, padded_z as usize);
+    let (x1, z1) = ((x0 + 1).min(max), (z0 + 1).min(max));
+    let (tx, tz) = (padded_x - x0 as f32, padded_z - z0 as f32);
+    let at = |x: usize, z: usize| values[z * SAMPLES_PER_CHUNK_DIM_PADDED + x];
+    lerp(
+        lerp(at(x0, z0), at(x1, z0), tx),
+        lerp(at(x0, z1), at(x1, z1), tx),
+        tz,
+    )
 }
 
 pub fn generate_noise_height_samples(
@@ -45,22 +157,61 @@ pub fn generate_noise_height_samples(
     let mut noise_grid = [0.0; 25];
     let x_start = ((chunk_start_x - HALF_CHUNK) / HALF_CHUNK) as i32;
     let z_start = ((chunk_start_z - HALF_CHUNK) / HALF_CHUNK) as i32;
+    let config = world_gen_config();
     fbm.gen_uniform_grid_2d(
         &mut noise_grid,
         x_start,
         z_start,
         5,
         5,
-        NOISE_FREQUENCY * HALF_CHUNK,
-        WORLD_SEED,
+        config.noise_frequency * HALF_CHUNK,
+        config.seed,
     );
     for v in &mut noise_grid {
-        *v *= NOISE_AMPLITUDE;
+        *v *= config.noise_amplitude;
     }
+    shape_heights(&mut noise_grid, x_start, z_start, 5, 5, HALF_CHUNK);
     noise_grid
 }
 
-pub fn calculate_chunk_start(chunk_coord: &(i16, i16, i16)) -> Vec3 {
+//samples the same height field as generate_noise_height_samples on an arbitrary grid
+//x_start and z_start are in units of spacing, spacing must be an integer for the samples to line up with the terrain
+pub fn generate_noise_height_grid(
+    x_start: i32,
+    z_start: i32,
+    width: usize,
+    depth: usize,
+    spacing: f32,
+    fbm: &GeneratorWrapper<SafeNode>,
+) -> Vec<f32> {
+    let mut noise_grid = vec![0.0; width * depth];
+    let config = world_gen_config();
+    fbm.gen_uniform_grid_2d(
+        &mut noise_grid,
+        x_start,
+        z_start,
+        width as i32,
+        depth as i32,
+        config.noise_frequency * spacing,
+        config.seed,
+    );
+    for v in &mut noise_grid {
+        *v *= config.noise_amplitude;
+    }
+    shape_heights(&mut noise_grid, x_start, z_start, width, depth, spacing);
+    noise_grid
+}
+
+//single point version of the height samples, skips the bicubic pass so it can be off by a little between samples
+pub fn sample_terrain_height(x: f32, z: f32, fbm: &GeneratorWrapper<SafeNode>) -> f32 {
+    let config = world_gen_config();
+    let frequency = config.noise_frequency;
+    let raw_height =
+        fbm.gen_single_2d(x * frequency, z * frequency, config.seed) * config.noise_amplitude;
+    climate_at(x, z).shape_height(raw_height) - channel_depth_at(x, z)
+}
+
+pub fn calculate_chunk_start(chunk_coord: &ChunkCoord) -> Vec3 {
     Vec3::new(
         chunk_coord.0 as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK,
         chunk_coord.1 as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK,
@@ -94,6 +245,30 @@ pub fn padded_chunk_contains_surface(
     false
 }
 
+//the one definition of a uniform chunk, used for generated and imported chunks and for dug chunks on the write thread
+//uniform chunks are stored as a single slot and come back as all AIR or all SOLID dirt, so only the interior is checked
+//air ignores materials, nothing under air can be dug back into view
+//solid needs every material to be dirt, stone or sand with no surface has nothing to mesh but must survive being dug into
+pub fn chunk_uniformity(
+    density_buffer: &[i16],           // (SAMPLES_PER_CHUNK_DIM+2) **3
+    material_buffer: &[MaterialCode], // SAMPLES_PER_CHUNK_DIM **3
+) -> Uniformity {
+    if padded_chunk_contains_surface(density_buffer) {
+        return Uniformity::NonUniform;
+    }
+    let first_interior = (SAMPLES_PER_CHUNK_DIM_PADDED + 1) * SAMPLES_PER_CHUNK_DIM_PADDED + 1;
+    if !SdfValue::from_raw(density_buffer[first_interior]).is_solid() {
+        Uniformity::Air
+    } else if material_buffer
+        .iter()
+        .all(|material| *material == MaterialCode::Dirt)
+    {
+        Uniformity::Dirt
+    } else {
+        Uniformity::NonUniform
+    }
+}
+
 //ignore padding
 //when called on padded buffer, a false positive is unlikely but possible
 //a false positive is non-fatal
@@ -194,6 +369,7 @@ pub fn generate_terrain_heights(
 //only called with non-uniform chunks due to earlier uniform cull
 pub fn fill_voxel_densities(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3) {
     let solid_threshold = quantize_f32_to_i16(-1.0);
+    fill_biome_columns(&mut chunk_buffers.biome, chunk_start);
     for z in [0, SAMPLES_PER_CHUNK_DIM_PADDED - 1] {
         let height_base = z * SAMPLES_PER_CHUNK_DIM_PADDED;
         let z_base = z * SAMPLES_PER_CHUNK_2D_PADDED;
@@ -249,14 +425,19 @@ pub fn fill_voxel_densities(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3
                 let gx = chunk_buffers.dhdx[height_base_plus_x];
                 let gz = chunk_buffers.dhdz[height_base_plus_x];
                 let q = quantize_f32_to_i16(surface_distance(vertical_dist, gx, gz));
+                let biome = chunk_buffers.biome[height_base_plus_x];
+                let underwater =
+                    below_sea || world_y < chunk_buffers.water_level[height_base_plus_x];
                 let mat = if q >= 0 {
                     MaterialCode::Air
-                } else if q < solid_threshold {
-                    MaterialCode::Dirt
-                } else if below_sea {
-                    MaterialCode::Sand
                 } else {
-                    MaterialCode::Grass
+                    voxel_material(
+                        q,
+                        solid_threshold,
+                        biome,
+                        underwater,
+                        chunk_buffers.path_mask[height_base_plus_x],
+                    )
                 };
                 chunk_buffers.density[base_rolling + x] = q;
                 chunk_buffers.material[mat_base + (x - 1)] = mat;
@@ -265,6 +446,154 @@ pub fn fill_voxel_densities(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3
     }
 }
 
+//material of a solid sample q below the surface of its column
+#[inline(always)]
+fn voxel_material(
+    q: i16,
+    solid_threshold: i16,
+    biome: Biome,
+    underwater: bool,
+    on_path: bool,
+) -> MaterialCode {
+    if q < biome.stack_threshold() {
+        MaterialCode::Dirt
+    } else if q >= solid_threshold && on_path {
+        MaterialCode::Path
+    } else if underwater {
+        biome.surface_stack().underwater
+    } else {
+        biome.surface_stack().top
+    }
+}
+
+//fills chunk_buffers.cave[0] for the chunk, positive is inside a cave in world units
+//two channels crossing zero together make the tunnels, a lower frequency channel above a threshold makes the caverns
+//carving fades out linearly above min_depth so caves never break the surface near the top of the terrain
+//returns the largest lattice value, anything at or below -10 cant change the clamped densities
+fn fill_cave_lattice(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3) -> f32 {
+    let settings = world_gen_config().caves;
+    if settings.frequency <= 0.0 {
+        return f32::MIN;
+    }
+    //lattice point g sits on padded sample 3g - 2, chunk starts are 63 voxels apart so 21 lattice points apart
+    let chunk_coord = ((*chunk_start + HALF_CHUNK) / CHUNK_WORLD_SIZE)
+        .round()
+        .as_ivec3();
+    let lattice_start = chunk_coord * ((SAMPLES_PER_CHUNK_DIM - 1) / CAVE_LATTICE_STEP) as i32 - 1;
+    let lattice_spacing = CAVE_LATTICE_STEP as f32 * VOXEL_WORLD_SIZE;
+    let [cave, tunnel_a, tunnel_b] = &mut chunk_buffers.cave;
+    CAVE_NOISE.with(|noise| {
+        let gen_channel = |out: &mut [f32], frequency: f32, seed: i32| {
+            noise.gen_uniform_grid_3d(
+                out,
+                lattice_start.x,
+                lattice_start.y,
+                lattice_start.z,
+                CAVE_LATTICE_DIM as i32,
+                CAVE_LATTICE_DIM as i32,
+                CAVE_LATTICE_DIM as i32,
+                frequency * lattice_spacing,
+                seed,
+            );
+        };
+        gen_channel(&mut tunnel_a[..], settings.frequency, channel_seed(1));
+        gen_channel(&mut tunnel_b[..], settings.frequency, channel_seed(2));
+        gen_channel(&mut cave[..], settings.frequency * 0.5, channel_seed(3));
+    });
+    let CaveSettings {
+        frequency,
+        radius,
+        min_depth,
+    } = settings;
+    let tunnel_scale = 1.0 / (frequency * CAVE_NOISE_GRADIENT);
+    let cavern_scale = 2.0 * tunnel_scale;
+    let mut max_carve = f32::MIN;
+    for gz in 0..CAVE_LATTICE_DIM {
+        let pz = (gz * CAVE_LATTICE_STEP).clamp(2, SAMPLES_PER_CHUNK_DIM_PADDED + 1) - 2;
+        for gy in 0..CAVE_LATTICE_DIM {
+            let world_y = chunk_start.y + (gy as f32 - 1.0) * lattice_spacing;
+            for gx in 0..CAVE_LATTICE_DIM {
+                let px = (gx * CAVE_LATTICE_STEP).clamp(2, SAMPLES_PER_CHUNK_DIM_PADDED + 1) - 2;
+                let idx = (gz * CAVE_LATTICE_DIM + gy) * CAVE_LATTICE_DIM + gx;
+                let depth =
+                    chunk_buffers.heightmap[pz * SAMPLES_PER_CHUNK_DIM_PADDED + px] - world_y;
+                let tunnel = radius - tunnel_a[idx].abs().max(tunnel_b[idx].abs()) * tunnel_scale;
+                let cavern = (cave[idx] - CAVERN_THRESHOLD) * cavern_scale;
+                let carve = tunnel.max(cavern) - (min_depth - depth).max(0.0);
+                cave[idx] = carve;
+                max_carve = max_carve.max(carve);
+            }
+        }
+    }
+    max_carve
+}
+
+//used to promote chunks fast_get_uniformity called solid dirt, only the cave field is computed
+//expects the heightmap to already be filled
+pub fn caves_reach_chunk(chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) -> bool {
+    fill_cave_lattice(chunk_buffers, chunk_start) > 0.0
+}
+
+//union of the terrain and the cave field, runs after fill_voxel_densities
+//carved samples become air, the solid walls keep the material the surface pass gave them
+fn carve_caves(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3) {
+    if fill_cave_lattice(chunk_buffers, chunk_start) <= -10.0 {
+        return;
+    }
+    //padded sample p is at lattice coordinate (p + 2) / 3
+    let lattice_coord = |p: usize| {
+        (
+            (p + 2) / CAVE_LATTICE_STEP,
+            ((p + 2) % CAVE_LATTICE_STEP) as f32 / CAVE_LATTICE_STEP as f32,
+        )
+    };
+    let cave = &chunk_buffers.cave[0];
+    let at = |x: usize, y: usize, z: usize| cave[(z * CAVE_LATTICE_DIM + y) * CAVE_LATTICE_DIM + x];
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        let (z0, tz) = lattice_coord(z);
+        for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let (y0, ty) = lattice_coord(y);
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let (x0, tx) = lattice_coord(x);
+                let c00 = lerp(at(x0, y0, z0), at(x0 + 1, y0, z0), tx);
+                let c10 = lerp(at(x0, y0 + 1, z0), at(x0 + 1, y0 + 1, z0), tx);
+                let c01 = lerp(at(x0, y0, z0 + 1), at(x0 + 1, y0, z0 + 1), tx);
+                let c11 = lerp(at(x0, y0 + 1, z0 + 1), at(x0 + 1, y0 + 1, z0 + 1), tx);
+                let carve = lerp(lerp(c00, c10, ty), lerp(c01, c11, ty), tz);
+                if carve <= -10.0 {
+                    continue;
+                }
+                let idx = (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                let q = quantize_f32_to_i16(carve);
+                if q <= chunk_buffers.density[idx] {
+                    continue;
+                }
+                chunk_buffers.density[idx] = q;
+                let interior = [x, y, z]
+                    .iter()
+                    .all(|p| (1..=SAMPLES_PER_CHUNK_DIM).contains(p));
+                if interior && q >= 0 {
+                    let mat_idx = (z - 1) * SAMPLES_PER_CHUNK_2D + (y - 1) * SAMPLES_PER_CHUNK_DIM;
+                    chunk_buffers.material[mat_idx + x - 1] = MaterialCode::Air;
+                }
+            }
+        }
+    }
+}
+
+//carve_caves at a fractional padded sample, for samples that are not on the full res grid
+fn cave_carve_at(cave: &[f32], padded_x: f32, padded_y: f32, padded_z: f32) -> f32 {
+    let lattice = (Vec3::new(padded_x, padded_y, padded_z) + 2.0) / CAVE_LATTICE_STEP as f32;
+    let (x0, y0, z0) = (lattice.x as usize, lattice.y as usize, lattice.z as usize);
+    let t = lattice - Vec3::new(x0 as f32, y0 as f32, z0 as f32);
+    let at = |x: usize, y: usize, z: usize| cave[(z * CAVE_LATTICE_DIM + y) * CAVE_LATTICE_DIM + x];
+    let c00 = lerp(at(x0, y0, z0), at(x0 + 1, y0, z0), t.x);
+    let c10 = lerp(at(x0, y0 + 1, z0), at(x0 + 1, y0 + 1, z0), t.x);
+    let c01 = lerp(at(x0, y0, z0 + 1), at(x0 + 1, y0, z0 + 1), t.x);
+    let c11 = lerp(at(x0, y0 + 1, z0 + 1), at(x0 + 1, y0 + 1, z0 + 1), t.x);
+    lerp(lerp(c00, c10, t.y), lerp(c01, c11, t.y), t.z)
+}
+
 //stripped version of fill_voxel_densities designed to quickly return uniformity without touching buffers
 //first do a math check against the heightmap gradients to check if its really far away from a surface
 //otherwise calculate (but dont store) the full chunk
@@ -434,145 +763,11 @@ fn surface_distance(vertical_dist: f32, gx: f32, gz: f32) -> f32 {
     (vertical_dist * inv_sqrt).clamp(-10.0, 10.0)
 }
 
-#[inline(always)]
-pub fn quantize_f32_to_i16(value: f32) -> i16 {
-    (value * SCALE).round() as i16
-}
-
-#[inline(always)]
-pub fn dequantize_i16_to_f32(q: i16) -> f32 {
-    q as f32 * SCALE_INV
-}
-
 #[inline(always)]
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + (b - a) * t
 }
 
-// Density = trilinear SDF sample at the output grid point
-// Material = scan the corresponding input block;
-// prefer grass/sand if any exist near the surface,
-// otherwise pick the closest-to-surface solid.
-//always called with full res chunk
-pub fn downscale(
-    densities_in: &[i16],          // (SAMPLES_PER_CHUNK_DIM + 2) **3
-    materials_in: &[MaterialCode], // (SAMPLES_PER_CHUNK) **3
-    densities_out: &mut [i16],
-    materials_out: &mut [MaterialCode],
-    out_dim: usize,
-) {
-    let in_max = SAMPLES_PER_CHUNK_DIM - 1;
-    let out_max = out_dim - 1;
-    let stride = in_max / out_max;
-    for target_z in 0..out_dim {
-        let high_sample_z = 1.0 + (target_z as f32 / out_max as f32) * in_max as f32;
-        let mat_target_z = target_z * stride;
-        let mat_end_z = (mat_target_z + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
-        let z_base = target_z * out_dim;
-        for target_y in 0..out_dim {
-            let high_sample_y = 1.0 + (target_y as f32 / out_max as f32) * in_max as f32;
-            let mat_target_y = target_y * stride;
-            let mat_end_y = (mat_target_y + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
-            let zy_base = (z_base + target_y) * out_dim;
-            for target_x in 0..out_dim {
-                let high_sample_x = 1.0 + (target_x as f32 / out_max as f32) * in_max as f32;
-                let mat_target_x = target_x * stride;
-                let mat_end_x = (mat_target_x + stride).min(SAMPLES_PER_CHUNK_DIM - 1);
-                let new_density = sample_trilinear_density(
-                    densities_in,
-                    SAMPLES_PER_CHUNK_DIM_PADDED,
-                    high_sample_x,
-                    high_sample_y,
-                    high_sample_z,
-                );
-                let out_i = zy_base + target_x;
-                densities_out[out_i] = quantize_f32_to_i16(new_density);
-                let new_material = pick_surface_material(
-                    materials_in,
-                    densities_in,
-                    mat_target_x,
-                    mat_target_y,
-                    mat_target_z,
-                    mat_end_x,
-                    mat_end_y,
-                    mat_end_z,
-                );
-                materials_out[out_i] = new_material;
-            }
-        }
-    }
-}
-
-fn pick_surface_material(
-    materials: &[MaterialCode],
-    densities: &[i16],
-    start_x: usize,
-    start_y: usize,
-    start_z: usize,
-    end_x: usize,
-    end_y: usize,
-    end_z: usize,
-) -> MaterialCode {
-    let mut best_mat = MaterialCode::Air;
-    let mut best_dist: u16 = u16::MAX;
-    for z in start_z..=end_z {
-        for y in start_y..=end_y {
-            let mat_base = (z * SAMPLES_PER_CHUNK_DIM + y) * SAMPLES_PER_CHUNK_DIM;
-            let den_base = ((z + 1) * SAMPLES_PER_CHUNK_DIM_PADDED + (y + 1))
-                * SAMPLES_PER_CHUNK_DIM_PADDED
-                + 1;
-            for x in start_x..=end_x {
-                let density = densities[den_base + x];
-                if density >= 0 {
-                    continue;
-                }
-                let dist = density.unsigned_abs();
-                if dist < best_dist {
-                    best_dist = dist;
-                    best_mat = materials[mat_base + x];
-                }
-            }
-        }
-    }
-    best_mat
-}
-
-fn sample_trilinear_density(
-    d: &[i16],
-    dim: usize,
-    high_sample_x: f32,
-    high_sample_y: f32,
-    high_sample_z: f32,
-) -> f32 {
-    let x0 = high_sample_x.floor() as isize;
-    let y0 = high_sample_y.floor() as isize;
-    let z0 = high_sample_z.floor() as isize;
-    let x1 = (x0 + 1).min((dim - 1) as isize);
-    let y1 = (y0 + 1).min((dim - 1) as isize);
-    let z1 = (z0 + 1).min((dim - 1) as isize);
-    let tx = high_sample_x - x0 as f32;
-    let ty = high_sample_y - y0 as f32;
-    let tz = high_sample_z - z0 as f32;
-    let idx = |x: isize, y: isize, z: isize| -> usize {
-        (z as usize * dim + y as usize) * dim + x as usize
-    };
-    let d000 = dequantize_i16_to_f32(d[idx(x0, y0, z0)]);
-    let d100 = dequantize_i16_to_f32(d[idx(x1, y0, z0)]);
-    let d010 = dequantize_i16_to_f32(d[idx(x0, y1, z0)]);
-    let d110 = dequantize_i16_to_f32(d[idx(x1, y1, z0)]);
-    let d001 = dequantize_i16_to_f32(d[idx(x0, y0, z1)]);
-    let d101 = dequantize_i16_to_f32(d[idx(x1, y0, z1)]);
-    let d011 = dequantize_i16_to_f32(d[idx(x0, y1, z1)]);
-    let d111 = dequantize_i16_to_f32(d[idx(x1, y1, z1)]);
-    let c00 = lerp(d000, d100, tx);
-    let c10 = lerp(d010, d110, tx);
-    let c01 = lerp(d001, d101, tx);
-    let c11 = lerp(d011, d111, tx);
-    let c0 = lerp(c00, c10, ty);
-    let c1 = lerp(c01, c11, ty);
-    lerp(c0, c1, tz)
-}
-
 pub fn compute_heightmap_gradients(
     dhdx: &mut [f32], //(SAMPLES_PER_CHUNK_DIM + 2) * (SAMPLES_PER_CHUNK_DIM + 2)
     dhdz: &mut [f32], //(SAMPLES_PER_CHUNK_DIM + 2) * (SAMPLES_PER_CHUNK_DIM + 2)
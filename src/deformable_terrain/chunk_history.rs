@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::prelude::*;
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+
+//when each stored chunk was last written in unix millis, loaded from the region headers and kept current by the write thread
+//0 means the chunk was stored before times were recorded
+#[derive(Resource, Clone, Default)]
+pub struct ChunkModifiedTimes {
+    times: Arc<RwLock<FxHashMap<ChunkCoord, u64>>>,
+    latest: Arc<AtomicU64>, //newest time in the map, read every frame by the save indicator
+}
+
+impl ChunkModifiedTimes {
+    pub(crate) fn new(times: FxHashMap<ChunkCoord, u64>) -> Self {
+        let latest = times.values().copied().max().unwrap_or(0);
+        ChunkModifiedTimes {
+            times: Arc::new(RwLock::new(times)),
+            latest: Arc::new(AtomicU64::new(latest)),
+        }
+    }
+
+    pub(crate) fn record(&self, chunk_coord: ChunkCoord, modified: u64) {
+        self.times.write().insert(chunk_coord, modified);
+        self.latest.fetch_max(modified, Ordering::Relaxed);
+    }
+
+    pub fn latest(&self) -> u64 {
+        self.latest.load(Ordering::Relaxed)
+    }
+
+    //chunks written at or after since, collected up front so the write thread is not held up by the caller
+    pub fn iter_chunks_modified_since(
+        &self,
+        since: u64,
+    ) -> impl Iterator<Item = (ChunkCoord, u64)> + use<> {
+        let changed: Vec<(ChunkCoord, u64)> = self
+            .times
+            .read()
+            .iter()
+            .filter(|(_, modified)| **modified >= since)
+            .map(|(chunk_coord, modified)| (*chunk_coord, *modified))
+            .collect();
+        changed.into_iter()
+    }
+}
@@ -0,0 +1,43 @@
+use crate::chunk_coord::ChunkCoord;
+
+//chunk coords over common shapes, radii are in chunks and measured between chunk centers
+
+//bounds inclusive, z outermost and x innermost like the sample buffers
+pub fn chunks_in_box(min: ChunkCoord, max: ChunkCoord) -> impl Iterator<Item = ChunkCoord> {
+    (min.2..=max.2).flat_map(move |z| {
+        (min.1..=max.1).flat_map(move |y| (min.0..=max.0).map(move |x| ChunkCoord(x, y, z)))
+    })
+}
+
+fn bounding_box(center: ChunkCoord, radius: i16) -> (ChunkCoord, ChunkCoord) {
+    (
+        center.offset(-radius, -radius, -radius),
+        center.offset(radius, radius, radius),
+    )
+}
+
+pub fn chunks_in_sphere(center: ChunkCoord, radius: i16) -> impl Iterator<Item = ChunkCoord> {
+    let radius_squared = radius as i32 * radius as i32;
+    let (min, max) = bounding_box(center, radius);
+    chunks_in_box(min, max).filter(move |c| c.distance_sq_to(center) <= radius_squared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+
+    #[test]
+    fn shapes_cover_what_they_claim() {
+        let boxed: Vec<_> = chunks_in_box(ChunkCoord(-1, 0, 2), ChunkCoord(1, 1, 3)).collect();
+        assert_eq!(boxed.len(), 3 * 2 * 2);
+        assert_eq!(boxed[..2], [ChunkCoord(-1, 0, 2), ChunkCoord(0, 0, 2)]);
+        assert_eq!(*boxed.last().unwrap(), ChunkCoord(1, 1, 3));
+        let center = ChunkCoord(5, -3, 7);
+        assert_eq!(chunks_in_sphere(center, 0).collect::<Vec<_>>(), [center]);
+        assert_eq!(chunks_in_sphere(center, 1).count(), 7);
+        let sphere: FxHashSet<_> = chunks_in_sphere(center, 6).collect();
+        assert!(sphere.contains(&center.offset(6, 0, 0)));
+        assert!(!sphere.contains(&center.offset(5, 4, 0)));
+    }
+}
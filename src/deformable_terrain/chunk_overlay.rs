@@ -0,0 +1,147 @@
+use bevy::{camera::primitives::Aabb, prelude::*};
+use bevy_rapier3d::prelude::Collider;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::CHUNK_WORLD_SIZE,
+    conversions::{chunk_coord_to_world_pos, world_pos_to_chunk_coord},
+    deformable_terrain::{
+        chunk_entity_map::ChunkEntityMap, chunk_iter::chunks_in_sphere, driver::TerrainChunkMap,
+        plugin::ChunkTag,
+    },
+    player::player::{MainCameraTag, PlayerTag},
+    ui::configurable_settings::ConfigurableSettings,
+};
+
+const OVERLAY_RADIUS: i16 = 4; //in chunks around the player, keeps the per frame cost bounded
+const MAX_LABELS: usize = 48; //nearest chunks get a label, the rest only get heatmap boxes
+const HEATMAP_FADE_SECS: f32 = 30.0; //age at which a chunk reaches the stale color
+const LABEL_FONT_SIZE: f32 = 12.0;
+const FRESH_COLOR: Color = Color::srgb(1.0, 0.1, 0.0);
+const STALE_COLOR: Color = Color::srgb(0.0, 0.3, 1.0);
+
+//elapsed seconds at which each chunk last got a mesh, written on spawn and on every lod swap
+#[derive(Resource, Default)]
+pub struct ChunkLoadTimes(FxHashMap<ChunkCoord, f32>);
+
+#[derive(Component)]
+pub struct ChunkLabel;
+
+pub fn setup_chunk_overlay(mut commands: Commands) {
+    commands.insert_resource(ChunkLoadTimes::default());
+    for _ in 0..MAX_LABELS {
+        commands.spawn((
+            ChunkLabel,
+            Text::new(""),
+            TextFont {
+                font_size: LABEL_FONT_SIZE,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Visibility::Hidden,
+        ));
+    }
+}
+
+//the spawn receiver inserts a fresh aabb whenever a mesh lands so that doubles as the load signal
+pub fn record_chunk_load_times(
+    time: Res<Time>,
+    loaded: Query<&Transform, (With<ChunkTag>, Changed<Aabb>)>,
+    mut unloaded: RemovedComponents<ChunkTag>,
+    chunk_entity_map: Res<ChunkEntityMap>,
+    mut load_times: ResMut<ChunkLoadTimes>,
+) {
+    let now = time.elapsed_secs();
+    for transform in loaded.iter() {
+        load_times
+            .0
+            .insert(world_pos_to_chunk_coord(&transform.translation), now);
+    }
+    if !unloaded.is_empty() {
+        unloaded.clear();
+        load_times
+            .0
+            .retain(|chunk_coord, _| chunk_entity_map.get_option(*chunk_coord).is_some());
+    }
+}
+
+pub fn draw_chunk_overlay(
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    settings: Res<ConfigurableSettings>,
+    player_transform_query: Query<&Transform, With<PlayerTag>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
+    chunks: Query<Has<Collider>, With<ChunkTag>>,
+    mut labels: Query<(&mut Text, &mut Node, &mut Visibility), With<ChunkLabel>>,
+    chunk_entity_map: Res<ChunkEntityMap>,
+    terrain_chunk_map: Res<TerrainChunkMap>,
+    load_times: Res<ChunkLoadTimes>,
+) {
+    let mut labels = labels.iter_mut();
+    if settings.chunk_heatmap || settings.chunk_labels {
+        let player_pos = player_transform_query.iter().next().unwrap().translation;
+        let center = world_pos_to_chunk_coord(&player_pos);
+        let now = time.elapsed_secs();
+        let mut nearby = Vec::new();
+        for chunk_coord in chunks_in_sphere(center, OVERLAY_RADIUS) {
+            let Some((entity, _)) = chunk_entity_map.get_option(chunk_coord) else {
+                continue;
+            };
+            let Ok(has_collider) = chunks.get(*entity) else {
+                continue;
+            };
+            let age = load_times.0.get(&chunk_coord).map(|t| now - t);
+            nearby.push((
+                chunk_coord.distance_sq_to(center),
+                chunk_coord,
+                has_collider,
+                age,
+            ));
+        }
+        if settings.chunk_heatmap {
+            for (_, chunk_coord, _, age) in &nearby {
+                let t = (age.unwrap_or(HEATMAP_FADE_SECS) / HEATMAP_FADE_SECS).min(1.0);
+                gizmos.cube(
+                    Transform::from_translation(chunk_coord_to_world_pos(chunk_coord))
+                        .with_scale(Vec3::splat(CHUNK_WORLD_SIZE * 0.98)),
+                    FRESH_COLOR.mix(&STALE_COLOR, t),
+                );
+            }
+        }
+        if let (true, Ok((camera, camera_transform))) =
+            (settings.chunk_labels, camera_query.single())
+        {
+            nearby.sort_unstable_by_key(|(dist_sq, ..)| *dist_sq);
+            for (_, chunk_coord, has_collider, age) in &nearby {
+                let world_pos = chunk_coord_to_world_pos(chunk_coord);
+                let Ok(screen_pos) = camera.world_to_viewport(camera_transform, world_pos) else {
+                    continue;
+                };
+                let Some((mut text, mut node, mut visibility)) = labels.next() else {
+                    break;
+                };
+                let status = match (*has_collider, terrain_chunk_map.0.contains_key(chunk_coord)) {
+                    (true, _) => "collider",
+                    (false, true) => "mesh+sdf",
+                    (false, false) => "mesh",
+                };
+                let loaded = match age {
+                    Some(age) => format!("{age:.1}s ago"),
+                    None => "unknown".to_string(),
+                };
+                text.0 = format!("{chunk_coord}\n{status}\n{loaded}");
+                node.left = Val::Px(screen_pos.x);
+                node.top = Val::Px(screen_pos.y);
+                *visibility = Visibility::Visible;
+            }
+        }
+    }
+    for (_, _, mut visibility) in labels {
+        *visibility = Visibility::Hidden;
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::{
+    constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED},
+    deformable_terrain::{
+        chunk_generator::MaterialCode,
+        terrain::{NonUniformTerrainChunk, TerrainChunk},
+    },
+};
+
+const CHUNK_POOL_CAPACITY: usize = 32; //chunks, about 840kb each
+
+#[derive(Default)]
+struct PooledSamples {
+    densities: Vec<Arc<[i16]>>,
+    materials: Vec<Arc<[MaterialCode]>>,
+}
+
+//sample arrays of chunks that left the chunk map, handed out again to the io and loader threads
+//streaming frees about as many chunks as it loads, so once warm most loads copy into an old allocation instead of making one
+#[derive(Clone, Default)]
+pub(crate) struct ChunkBufferPool(Arc<Mutex<PooledSamples>>);
+
+impl ChunkBufferPool {
+    //a copy of a full res chunk, density padded and material not
+    pub(crate) fn chunk_from(
+        &self,
+        density: &[i16],
+        material: &[MaterialCode],
+    ) -> NonUniformTerrainChunk {
+        let (densities, materials) = {
+            let mut pooled = self.0.lock();
+            (pooled.densities.pop(), pooled.materials.pop())
+        };
+        NonUniformTerrainChunk {
+            densities: copy_into(densities, density),
+            materials: copy_into(materials, material),
+        }
+    }
+
+    //samples still shared, with the write thread or a dig in progress, are dropped as usual
+    pub(crate) fn recycle(&self, chunk: TerrainChunk) {
+        let TerrainChunk::NonUniformTerrainChunk(mut chunk) = chunk else {
+            return;
+        };
+        let mut pooled = self.0.lock();
+        if pooled.densities.len() < CHUNK_POOL_CAPACITY
+            && chunk.densities.len() == SAMPLES_PER_CHUNK_PADDED
+            && Arc::get_mut(&mut chunk.densities).is_some()
+        {
+            pooled.densities.push(chunk.densities);
+        }
+        if pooled.materials.len() < CHUNK_POOL_CAPACITY
+            && chunk.materials.len() == SAMPLES_PER_CHUNK
+            && Arc::get_mut(&mut chunk.materials).is_some()
+        {
+            pooled.materials.push(chunk.materials);
+        }
+    }
+}
+
+fn copy_into<T: Copy>(pooled: Option<Arc<[T]>>, samples: &[T]) -> Arc<[T]> {
+    match pooled {
+        Some(mut pooled) => {
+            //only unshared arrays go into the pool and nothing clones them while they sit there
+            Arc::get_mut(&mut pooled).unwrap().copy_from_slice(samples);
+            pooled
+        }
+        None => Arc::from(samples),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_chunks_are_reused_and_shared_ones_are_not() {
+        let pool = ChunkBufferPool::default();
+        let density = vec![3i16; SAMPLES_PER_CHUNK_PADDED];
+        let material = vec![MaterialCode::Dirt; SAMPLES_PER_CHUNK];
+        let first = pool.chunk_from(&density, &material);
+        let first_ptr = Arc::as_ptr(&first.densities);
+        pool.recycle(TerrainChunk::NonUniformTerrainChunk(first));
+        let second = pool.chunk_from(&[7i16; SAMPLES_PER_CHUNK_PADDED], &material);
+        assert_eq!(Arc::as_ptr(&second.densities), first_ptr);
+        assert!(second.densities.iter().all(|d| *d == 7));
+        let held = Arc::clone(&second.densities);
+        pool.recycle(TerrainChunk::NonUniformTerrainChunk(second));
+        assert_eq!(pool.0.lock().densities.len(), 0);
+        assert_eq!(pool.0.lock().materials.len(), 1);
+        drop(held);
+    }
+}
@@ -0,0 +1,125 @@
+use std::hash::BuildHasher;
+
+use parking_lot::{Mutex, MutexGuard};
+use rustc_hash::FxBuildHasher;
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    deformable_terrain::{chunk_budget::TerrainChunks, terrain::TerrainChunk},
+};
+
+const SHARD_COUNT: usize = 16; //power of two so the shard is a mask of the hash
+
+//the terrain chunk map split over shards that lock on their own
+//digging, raycasts, the debug views and the svo manager only wait on each other when they touch the same shard
+//chunks come out as clones, the samples are behind arcs so that is a couple of refcount bumps and no lock is held after
+pub(crate) struct ChunkStore {
+    shards: Box<[Mutex<TerrainChunks>]>,
+}
+
+impl Default for ChunkStore {
+    fn default() -> Self {
+        ChunkStore {
+            shards: (0..SHARD_COUNT)
+                .map(|_| Mutex::new(TerrainChunks::default()))
+                .collect(),
+        }
+    }
+}
+
+impl ChunkStore {
+    fn shard(&self, chunk_coord: &ChunkCoord) -> MutexGuard<'_, TerrainChunks> {
+        let hash = FxBuildHasher.hash_one(chunk_coord);
+        self.shards[hash as usize & (SHARD_COUNT - 1)].lock()
+    }
+
+    pub(crate) fn get(&self, chunk_coord: &ChunkCoord) -> Option<TerrainChunk> {
+        self.shard(chunk_coord).get(chunk_coord).cloned()
+    }
+
+    //like get but counts as a use for eviction
+    pub(crate) fn touch(&self, chunk_coord: &ChunkCoord) -> Option<TerrainChunk> {
+        self.shard(chunk_coord).touch(chunk_coord).cloned()
+    }
+
+    pub(crate) fn contains_key(&self, chunk_coord: &ChunkCoord) -> bool {
+        self.shard(chunk_coord).contains_key(chunk_coord)
+    }
+
+    //insert and remove hand back what left the store so its samples can go back to the pool
+    pub(crate) fn insert(
+        &self,
+        chunk_coord: ChunkCoord,
+        chunk: TerrainChunk,
+    ) -> Option<TerrainChunk> {
+        self.shard(&chunk_coord).insert(chunk_coord, chunk)
+    }
+
+    pub(crate) fn insert_edited(
+        &self,
+        chunk_coord: ChunkCoord,
+        chunk: TerrainChunk,
+    ) -> Option<TerrainChunk> {
+        self.shard(&chunk_coord).insert_edited(chunk_coord, chunk)
+    }
+
+    pub(crate) fn remove(&self, chunk_coord: &ChunkCoord) -> Option<TerrainChunk> {
+        self.shard(chunk_coord).remove(chunk_coord)
+    }
+
+    //a snapshot, shards are locked one after another so chunks may come and go while it is taken
+    pub(crate) fn coords(&self) -> Vec<ChunkCoord> {
+        let mut coords = Vec::new();
+        for shard in self.shards.iter() {
+            coords.extend(shard.lock().keys().copied());
+        }
+        coords
+    }
+
+    //for passes over every chunk, each shard is locked only while f runs on it
+    pub(crate) fn for_each_shard(&self, mut f: impl FnMut(&mut TerrainChunks)) {
+        for shard in self.shards.iter() {
+            f(&mut shard.lock());
+        }
+    }
+
+    pub(crate) fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deformable_terrain::chunk_iter::chunks_in_box;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn threads_see_each_others_chunks() {
+        let store = Arc::new(ChunkStore::default());
+        let workers: Vec<_> = (0..4i16)
+            .map(|x| {
+                let store = Arc::clone(&store);
+                thread::spawn(move || {
+                    for chunk_coord in chunks_in_box(ChunkCoord(x, -4, -4), ChunkCoord(x, 3, 3)) {
+                        store.insert(chunk_coord, TerrainChunk::UniformDirt);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(store.coords().len(), 4 * 8 * 8);
+        let mut per_shard = Vec::new();
+        store.for_each_shard(|shard| per_shard.push(shard.keys().count()));
+        assert!(per_shard.iter().all(|count| *count > 0), "{per_shard:?}");
+        assert!(matches!(
+            store.get(&ChunkCoord(3, 3, 3)),
+            Some(TerrainChunk::UniformDirt)
+        ));
+        store.remove(&ChunkCoord(3, 3, 3));
+        assert!(!store.contains_key(&ChunkCoord(3, 3, 3)));
+    }
+}
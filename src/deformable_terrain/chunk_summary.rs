@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED};
+use crate::conversions::{chunk_coord_to_world_pos, flatten_index};
+use crate::deformable_terrain::biomes::{Biome, biome_at};
+use crate::deformable_terrain::chunk_generator::{MATERIAL_COUNT, MaterialCode, UnknownMaterial};
+
+pub(crate) const CHUNK_SUMMARY_SERIALIZED_SIZE: usize = 2 + MATERIAL_COUNT; //dominant material, coverage bytes, biome id
+
+//tiny per chunk digest so gameplay can ask what a chunk looks like without touching voxel data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkSummary {
+    pub dominant_material: MaterialCode,
+    pub surface_coverage: [u8; MATERIAL_COUNT], //fraction of surface columns per MaterialCode, 255 = every column
+    pub biome_id: u8,                           //biome at the chunk center, see Biome::from_id
+}
+
+impl ChunkSummary {
+    pub fn coverage(&self, material: MaterialCode) -> f32 {
+        self.surface_coverage[material as usize] as f32 / 255.0
+    }
+
+    pub fn biome(&self) -> Option<Biome> {
+        Biome::from_id(self.biome_id)
+    }
+
+    pub(crate) fn to_bytes(&self) -> [u8; CHUNK_SUMMARY_SERIALIZED_SIZE] {
+        let mut bytes = [0u8; CHUNK_SUMMARY_SERIALIZED_SIZE];
+        bytes[0] = self.dominant_material as u8;
+        bytes[1..1 + MATERIAL_COUNT].copy_from_slice(&self.surface_coverage);
+        bytes[1 + MATERIAL_COUNT] = self.biome_id;
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, UnknownMaterial> {
+        let mut surface_coverage = [0u8; MATERIAL_COUNT];
+        surface_coverage.copy_from_slice(&bytes[1..1 + MATERIAL_COUNT]);
+        Ok(ChunkSummary {
+            dominant_material: MaterialCode::try_from(bytes[0])?,
+            surface_coverage,
+            biome_id: bytes[1 + MATERIAL_COUNT],
+        })
+    }
+}
+
+//shared between loader threads, the write thread and the main world
+#[derive(Resource, Clone, Default)]
+pub struct ChunkSummaries(pub(crate) Arc<RwLock<FxHashMap<ChunkCoord, ChunkSummary>>>);
+
+//scans each column top down for the first solid sample and tallies its material
+//columns that are fully air or fully solid inside the chunk dont count as surface
+pub fn compute_chunk_summary(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    chunk_coord: ChunkCoord,
+) -> ChunkSummary {
+    let mut counts = [0u32; MATERIAL_COUNT];
+    let mut surface_columns = 0;
+    for z in 0..SAMPLES_PER_CHUNK_DIM {
+        for x in 0..SAMPLES_PER_CHUNK_DIM {
+            let mut above_is_air = false;
+            for y in (0..SAMPLES_PER_CHUNK_DIM).rev() {
+                let density = densities[flatten_index(
+                    x as u32 + 1,
+                    y as u32 + 1,
+                    z as u32 + 1,
+                    SAMPLES_PER_CHUNK_DIM_PADDED,
+                ) as usize];
+                if density >= 0 {
+                    above_is_air = true;
+                    continue;
+                }
+                if above_is_air {
+                    let material = materials[flatten_index(
+                        x as u32,
+                        y as u32,
+                        z as u32,
+                        SAMPLES_PER_CHUNK_DIM,
+                    ) as usize];
+                    counts[material as usize] += 1;
+                    surface_columns += 1;
+                }
+                break;
+            }
+        }
+    }
+    let mut surface_coverage = [0u8; MATERIAL_COUNT];
+    if surface_columns > 0 {
+        for (coverage, count) in surface_coverage.iter_mut().zip(counts) {
+            *coverage = (count * 255 / surface_columns) as u8;
+        }
+    }
+    let dominant = (1..counts.len()).max_by_key(|&i| counts[i]).unwrap();
+    let dominant_material = if counts[dominant] == 0 {
+        MaterialCode::Dirt //no exposed surface, whatever is here is buried
+    } else {
+        MaterialCode::ALL[dominant]
+    };
+    ChunkSummary {
+        dominant_material,
+        surface_coverage,
+        biome_id: biome_at(chunk_coord_to_world_pos(&chunk_coord)).id(),
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs::{File, read, rename};
+use std::hash::BuildHasher;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use parking_lot::Mutex;
+use rustc_hash::{FxBuildHasher, FxHashMap};
+
+use crate::constants::CHUNKS_PER_CLUSTER;
+use crate::deformable_terrain::driver::{ClusterRequest, LoadState, MESHING_MODE};
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::world_gen::world_gen_config;
+
+pub(crate) const CLUSTER_OCCUPANCY_FILE: &str = "cluster_occupancy.bin";
+const CLUSTER_OCCUPANCY_VERSION: u8 = 1;
+const HEADER_LEN: usize = 9; //version and fingerprint
+const ENTRY_LEN: usize = 7; //cluster coord and load state
+
+//clusters a loader found without any surface at a reduced lod, kept across sessions
+//the svo manager marks them loaded without queueing them when they come up at that lod again, so the empty sky and
+//deep ground past the simulation radius costs nothing after the first visit. uniform chunks already have their own files,
+//this covers the non-uniform ones whose surface the reduction dropped. full res states are never kept, those clusters
+//fill the chunk map and every edit happens in one of them, so a cluster that was dug is recorded again when it next loads.
+//merged clusters are left out too, their chunks never have entities so has_entity says nothing about the merged mesh
+#[derive(Default)]
+pub(crate) struct ClusterOccupancy {
+    empty: FxHashMap<(i16, i16, i16), LoadState>,
+    dirty: bool, //changed since loaded or saved
+}
+
+impl ClusterOccupancy {
+    //every loader result goes through here so the map always holds what the cluster last loaded as
+    pub(crate) fn record(
+        &mut self,
+        cluster_coord: (i16, i16, i16),
+        has_entity: &[bool; CHUNKS_PER_CLUSTER],
+        load_state: LoadState,
+    ) {
+        let changed = if is_reduced(load_state) && !has_entity.contains(&true) {
+            self.empty.insert(cluster_coord, load_state) != Some(load_state)
+        } else {
+            self.empty.remove(&cluster_coord).is_some()
+        };
+        self.dirty |= changed;
+    }
+
+    //a request that would load nothing and has nothing on screen to replace
+    pub(crate) fn is_known_empty(&self, request: &ClusterRequest, load_state: LoadState) -> bool {
+        !request.prev_merged
+            && request
+                .prev_has_entity
+                .is_none_or(|has_entity| !has_entity.contains(&true))
+            && self.empty.get(&request.position) == Some(&load_state)
+    }
+
+    //an unreadable file or one written with other meshing or cave settings starts over empty
+    pub(crate) fn load(data_dir: &Path) -> Self {
+        let Ok(bytes) = read(data_dir.join(CLUSTER_OCCUPANCY_FILE)) else {
+            return ClusterOccupancy::default();
+        };
+        if bytes.len() < HEADER_LEN
+            || bytes[0] != CLUSTER_OCCUPANCY_VERSION
+            || bytes[1..HEADER_LEN] != generation_fingerprint().to_le_bytes()
+        {
+            return ClusterOccupancy::default();
+        }
+        let mut empty = FxHashMap::default();
+        for entry in bytes[HEADER_LEN..].chunks_exact(ENTRY_LEN) {
+            let cluster_coord = (
+                i16::from_le_bytes([entry[0], entry[1]]),
+                i16::from_le_bytes([entry[2], entry[3]]),
+                i16::from_le_bytes([entry[4], entry[5]]),
+            );
+            if let Some(load_state) = reduced_state_from_u8(entry[6]) {
+                empty.insert(cluster_coord, load_state);
+            }
+        }
+        ClusterOccupancy {
+            empty,
+            dirty: false,
+        }
+    }
+
+    //written beside and renamed over so a crash never leaves half a file
+    pub(crate) fn save(&mut self, data_dir: &Path) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.empty.len() * ENTRY_LEN);
+        bytes.push(CLUSTER_OCCUPANCY_VERSION);
+        bytes.extend_from_slice(&generation_fingerprint().to_le_bytes());
+        for (cluster_coord, load_state) in &self.empty {
+            bytes.extend_from_slice(&cluster_coord.0.to_le_bytes());
+            bytes.extend_from_slice(&cluster_coord.1.to_le_bytes());
+            bytes.extend_from_slice(&cluster_coord.2.to_le_bytes());
+            bytes.push(*load_state as u8);
+        }
+        let path = data_dir.join(CLUSTER_OCCUPANCY_FILE);
+        let tmp_path = path.with_extension("bin.tmp");
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&bytes)?;
+                file.sync_all()
+            })
+            .and_then(|_| rename(&tmp_path, &path))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+fn is_reduced(load_state: LoadState) -> bool {
+    reduced_state_from_u8(load_state as u8).is_some()
+}
+
+fn reduced_state_from_u8(byte: u8) -> Option<LoadState> {
+    [
+        LoadState::Lod1,
+        LoadState::Lod2,
+        LoadState::Lod3,
+        LoadState::Lod4,
+        LoadState::Lod5,
+    ]
+    .into_iter()
+    .find(|load_state| *load_state as u8 == byte)
+}
+
+//settings that change what a cluster meshes to, fx hashing is the same on every run
+fn generation_fingerprint() -> u64 {
+    let settings = format!("{:?} {:?}", *MESHING_MODE.read(), world_gen_config());
+    FxBuildHasher.hash_one(settings.as_str())
+}
+
+//shared by the svo manager, which records into it, and the exit system that saves it
+#[derive(Resource, Clone, Default)]
+pub struct SharedClusterOccupancy(pub(crate) Arc<Mutex<ClusterOccupancy>>);
+
+impl SharedClusterOccupancy {
+    pub(crate) fn load(data_dir: &Path) -> Self {
+        SharedClusterOccupancy(Arc::new(Mutex::new(ClusterOccupancy::load(data_dir))))
+    }
+}
+
+pub fn save_cluster_occupancy_on_exit(
+    mut exit_reader: MessageReader<AppExit>,
+    occupancy: Option<Res<SharedClusterOccupancy>>,
+    storage: Res<StoragePaths>,
+) {
+    if exit_reader.read().last().is_none() {
+        return;
+    }
+    let Some(occupancy) = occupancy else {
+        return;
+    };
+    if let Err(e) = occupancy.0.lock().save(&storage.world_dir()) {
+        warn!("Failed to save {CLUSTER_OCCUPANCY_FILE}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deformable_terrain::driver::{LoadStateTransition, RF3_SAMPLES_PER_CHUNK_DIM};
+
+    fn request(position: (i16, i16, i16)) -> ClusterRequest {
+        ClusterRequest {
+            position,
+            distance_squared: 0.0,
+            load_state_transition: LoadStateTransition::ToLod3,
+            prev_has_entity: None,
+            prev_in_simulation_radius: false,
+            prev_merged: false,
+            queued_secs: 0.0,
+            neighbor_samples_per_chunk_dim: [RF3_SAMPLES_PER_CHUNK_DIM; 6],
+        }
+    }
+
+    #[test]
+    fn empty_reduced_clusters_survive_a_save() {
+        let dir = std::env::temp_dir().join(format!("cluster_occupancy_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut occupancy = ClusterOccupancy::default();
+        let mut has_entity = [false; CHUNKS_PER_CLUSTER];
+        occupancy.record((1, 2, 3), &has_entity, LoadState::Lod3);
+        occupancy.record((4, 5, 6), &has_entity, LoadState::FullWithCollider);
+        has_entity[7] = true;
+        occupancy.record((-7, 8, -9), &has_entity, LoadState::Lod3);
+        occupancy.save(&dir).unwrap();
+        let loaded = ClusterOccupancy::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(loaded.is_known_empty(&request((1, 2, 3)), LoadState::Lod3));
+        assert!(!loaded.is_known_empty(&request((1, 2, 3)), LoadState::Lod4));
+        assert!(!loaded.is_known_empty(&request((4, 5, 6)), LoadState::Lod3));
+        assert!(!loaded.is_known_empty(&request((-7, 8, -9)), LoadState::Lod3));
+        let mut shown = request((1, 2, 3));
+        shown.prev_has_entity = Some(has_entity);
+        assert!(!loaded.is_known_empty(&shown, LoadState::Lod3));
+    }
+}
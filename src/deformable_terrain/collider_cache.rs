@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Collider;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    deformable_terrain::{
+        driver::{MESHING_MODE, trimesh_collider},
+        heightfield_collider::heightfield_collider,
+        lru_cache::LruCache,
+        plugin::MeshingMode,
+        terrain_error::TerrainError,
+    },
+};
+
+const COLLIDER_CACHE_CAPACITY: usize = 256; //colliders, the ones still on a chunk entity share their shape with it
+
+pub static COLLIDER_CACHE_HITS: AtomicUsize = AtomicUsize::new(0); //since startup
+pub static COLLIDER_CACHE_MISSES: AtomicUsize = AtomicUsize::new(0);
+pub static HEIGHTFIELD_COLLIDERS: AtomicUsize = AtomicUsize::new(0); //misses built as a heightfield instead of a trimesh
+
+//shared by every loader thread, like MESHING_MODE the meshes it stands for are the same for the whole process
+static COLLIDER_CACHE: LazyLock<ColliderCache> =
+    LazyLock::new(|| ColliderCache::new(COLLIDER_CACHE_CAPACITY));
+
+//the samples a collider was built from, a 64 bit fast hash would let two chunks share a key
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DensityDigest {
+    digest: [u8; 16], //first 128 bits of the blake3 hash of the samples
+    len: usize,
+}
+
+type ColliderKey = (MeshingMode, DensityDigest);
+
+//chunk colliders keyed on the padded densities they were built from, so a chunk that unloads and comes back
+//or flips out of full lod and back in gets its old collider instead of rapier building the same one again
+//colliders are in chunk local space, two chunks with the same samples share one
+//two threads missing on the same samples both build it, the colliders are identical so the second insert is harmless
+type ColliderCache = LruCache<ColliderKey, Collider>;
+
+//one pass over the padded samples, cheap next to the trimesh build
+pub fn density_digest(densities: &[i16]) -> DensityDigest {
+    let hash = blake3::hash(bytemuck::cast_slice(densities));
+    let mut digest = [0u8; 16];
+    digest.copy_from_slice(&hash.as_bytes()[..16]);
+    DensityDigest {
+        digest,
+        len: densities.len(),
+    }
+}
+
+//a heightfield where the surface allows one and a trimesh of mesh otherwise, through the cache
+//mesh has to be the one meshed from densities with the current meshing mode
+pub(crate) fn cached_chunk_collider(
+    chunk_coord: ChunkCoord,
+    densities: &[i16],
+    mesh: &Mesh,
+) -> Result<Collider, TerrainError> {
+    let key = (*MESHING_MODE.read(), density_digest(densities));
+    if let Some(collider) = COLLIDER_CACHE.get(&key) {
+        COLLIDER_CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(collider);
+    }
+    COLLIDER_CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
+    //greedy cubes meshes are stepped, a heightfield through the crossings would smooth the steps away
+    let heightfield = match key.0 {
+        MeshingMode::MarchingCubes => heightfield_collider(densities),
+        MeshingMode::GreedyCubes => None,
+    };
+    let collider = match heightfield {
+        Some(collider) => {
+            HEIGHTFIELD_COLLIDERS.fetch_add(1, Ordering::Relaxed);
+            collider
+        }
+        None => trimesh_collider(chunk_coord, mesh)?,
+    };
+    Ok(COLLIDER_CACHE.insert(key, collider))
+}
+
+//hits over lookups since startup, 0 before the first lookup
+pub fn collider_cache_hit_rate() -> f32 {
+    let hits = COLLIDER_CACHE_HITS.load(Ordering::Relaxed);
+    let misses = COLLIDER_CACHE_MISSES.load(Ordering::Relaxed);
+    if hits + misses == 0 {
+        0.0
+    } else {
+        hits as f32 / (hits + misses) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_collider_is_evicted() {
+        let cache = ColliderCache::new(2);
+        let key = |n: i16| (MeshingMode::MarchingCubes, density_digest(&[n; 8]));
+        cache.insert(key(1), Collider::ball(1.0));
+        cache.insert(key(2), Collider::ball(2.0));
+        assert!(cache.get(&key(1)).is_some()); //2 is now the oldest
+        cache.insert(key(3), Collider::ball(3.0));
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+        assert!(cache.get(&(MeshingMode::GreedyCubes, key(1).1)).is_none());
+    }
+
+    #[test]
+    fn other_samples_are_a_miss() {
+        let cache = ColliderCache::new(2);
+        let mut densities = vec![0i16; 512];
+        let built_from = density_digest(&densities);
+        cache.insert(
+            (MeshingMode::MarchingCubes, built_from),
+            Collider::ball(1.0),
+        );
+        densities[300] = -1;
+        let key = |densities: &[i16]| (MeshingMode::MarchingCubes, density_digest(densities));
+        assert!(cache.get(&key(&densities)).is_none());
+        assert!(cache.get(&key(&densities[..256])).is_none());
+        assert!(
+            cache
+                .get(&(MeshingMode::MarchingCubes, built_from))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn density_digest_follows_content() {
+        let mut densities = vec![0i16; 512];
+        let before = density_digest(&densities);
+        assert_eq!(before, density_digest(&densities.clone()));
+        densities[300] = -1;
+        assert_ne!(before, density_digest(&densities));
+        assert_ne!(before, density_digest(&densities[..256]));
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Collider;
+
+use crate::constants::HALF_CHUNK;
+use crate::deformable_terrain::plugin::{ChunkTag, MoveableCenter};
+use crate::ui::configurable_settings::ConfigurableSettings;
+
+const RESTORE_MARGIN: f32 = HALF_CHUNK; //colliders come back a little inside the radius they left at so a chunk on the edge doesnt flicker
+
+//collider set aside while its chunk is past the physics radius, put back as is instead of rebuilding the trimesh
+//the driver still decides which chunks get a collider at all, anything it adds or removes clears this
+#[derive(Component)]
+pub struct StashedCollider(Collider);
+
+//the driver builds colliders out to SIMULATION_RADIUS, past the physics radius they only cost rapier broadphase time
+//meshes are left alone so the render radius stays independent of how much is simulated
+pub fn cull_far_colliders(
+    mut commands: Commands,
+    moveable_center: Res<MoveableCenter>,
+    settings: Res<ConfigurableSettings>,
+    colliders: Query<(Entity, &Transform, &Collider), (With<ChunkTag>, Without<StashedCollider>)>,
+    stashed: Query<(Entity, &Transform, &StashedCollider), (With<ChunkTag>, Without<Collider>)>,
+) {
+    let center = moveable_center.read();
+    let cull_radius_squared = settings.physics_radius * settings.physics_radius;
+    let restore_radius = (settings.physics_radius - RESTORE_MARGIN).max(0.0);
+    for (entity, transform, collider) in colliders.iter() {
+        if transform.translation.distance_squared(center) > cull_radius_squared {
+            commands
+                .entity(entity)
+                .remove::<Collider>()
+                .insert(StashedCollider(collider.clone()));
+        }
+    }
+    for (entity, transform, StashedCollider(collider)) in stashed.iter() {
+        if transform.translation.distance_squared(center) <= restore_radius * restore_radius {
+            commands
+                .entity(entity)
+                .remove::<StashedCollider>()
+                .insert(collider.clone());
+        }
+    }
+}
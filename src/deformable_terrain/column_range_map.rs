@@ -1,5 +1,6 @@
 use rustc_hash::FxHashMap;
 
+use crate::chunk_coord::ChunkCoord;
 use crate::deformable_terrain::plugin::Uniformity;
 
 //store ranges of chunk columns that match uniformity instead of storing individial chunks
@@ -18,7 +19,7 @@ impl ColumnRangeMap {
 
     //given a chunk coordinate, return whether it is contained in any of the column ranges and if so its uniformity
     #[inline(always)]
-    pub fn contains(&self, chunk_coord: (i16, i16, i16)) -> Uniformity {
+    pub fn contains(&self, chunk_coord: ChunkCoord) -> Uniformity {
         self.map
             .get(&pack_xz(chunk_coord.0, chunk_coord.2))
             .and_then(|ranges| {
@@ -43,7 +44,7 @@ impl ColumnRangeMap {
     //search column ranges for either a containing range or the two ranges who could potentially combine
     //check if the range neighbors the new chunk and has the same uniformity, if so combine them
     //assume impossible for a chunk to be in the middle of an existing range but have different uniformity
-    pub fn insert(&mut self, chunk_coord: (i16, i16, i16), uniformity: Uniformity) {
+    pub fn insert(&mut self, chunk_coord: ChunkCoord, uniformity: Uniformity) {
         let xz = pack_xz(chunk_coord.0, chunk_coord.2);
         let column_ranges = self.map.entry(xz).or_default();
         let mut new_low = chunk_coord.1;
@@ -71,7 +72,7 @@ impl ColumnRangeMap {
 
     //remove a chunk coordinate from the map, splitting ranges if necessary
     //panics if the chunk does not exist or has a different uniformity
-    pub fn remove(&mut self, chunk_coord: (i16, i16, i16), uniformity: Uniformity) {
+    pub fn remove(&mut self, chunk_coord: ChunkCoord, uniformity: Uniformity) {
         let xz = pack_xz(chunk_coord.0, chunk_coord.2);
         let column_ranges = self.map.get_mut(&xz).unwrap();
         let y = chunk_coord.1;
@@ -165,47 +166,47 @@ mod tests {
     #[test]
     fn merges_contiguous_into_one_range() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 1, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
         for y in 0..=2 {
-            assert_eq!(m.contains((0, y, 0)), Uniformity::Air);
+            assert_eq!(m.contains(ChunkCoord(0, y, 0)), Uniformity::Air);
         }
-        assert_eq!(m.contains((0, 3, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 3, 0)), Uniformity::Unknown);
     }
 
     #[test]
     fn keeps_gaps_separate() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Unknown);
-        assert_eq!(m.contains((0, 2, 0)), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 2, 0)), Uniformity::Air);
     }
 
     #[test]
     fn bridging_insert_merges_two_ranges() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        m.insert((0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
         for y in 0..=2 {
-            assert_eq!(m.contains((0, y, 0)), Uniformity::Air);
+            assert_eq!(m.contains(ChunkCoord(0, y, 0)), Uniformity::Air);
         }
     }
 
     #[test]
     fn mixed_uniformity_stays_disjoint() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, -1, 0), Uniformity::Dirt);
-        m.insert((0, 0, 0), Uniformity::Dirt);
-        m.insert((0, 1, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, -1, 0)), Uniformity::Dirt);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Dirt);
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 2, 0)), Uniformity::Air);
+        m.insert(ChunkCoord(0, -1, 0), Uniformity::Dirt);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Dirt);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, -1, 0)), Uniformity::Dirt);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Dirt);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 2, 0)), Uniformity::Air);
     }
 
     #[test]
@@ -214,14 +215,14 @@ mod tests {
         for x in -4..=4 {
             for z in -4..=4 {
                 for y in -2..=2 {
-                    m.insert((x, y, z), Uniformity::Air);
+                    m.insert(ChunkCoord(x, y, z), Uniformity::Air);
                 }
             }
         }
         for x in -4..=4 {
             for z in -4..=4 {
                 for y in -2..=2 {
-                    assert_eq!(m.contains((x, y, z)), Uniformity::Air);
+                    assert_eq!(m.contains(ChunkCoord(x, y, z)), Uniformity::Air);
                 }
             }
         }
@@ -235,13 +236,18 @@ mod tests {
             -12,
         ];
         for &y in &ys {
-            m.insert((0, y, 0), Uniformity::Air);
+            m.insert(ChunkCoord(0, y, 0), Uniformity::Air);
         }
         for y in -12..=12 {
-            assert_eq!(m.contains((0, y, 0)), Uniformity::Air, "missing y={}", y);
+            assert_eq!(
+                m.contains(ChunkCoord(0, y, 0)),
+                Uniformity::Air,
+                "missing y={}",
+                y
+            );
         }
-        assert_eq!(m.contains((0, -13, 0)), Uniformity::Unknown);
-        assert_eq!(m.contains((0, 13, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, -13, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 13, 0)), Uniformity::Unknown);
     }
 
     #[test]
@@ -250,12 +256,12 @@ mod tests {
         let dirt_ys: [i16; 10] = [-10, -1, -9, -2, -8, -3, -7, -4, -6, -5];
         let air_ys: [i16; 10] = [10, 1, 9, 2, 8, 3, 7, 4, 6, 5];
         for i in 0..10 {
-            m.insert((0, dirt_ys[i], 0), Uniformity::Dirt);
-            m.insert((0, air_ys[i], 0), Uniformity::Air);
+            m.insert(ChunkCoord(0, dirt_ys[i], 0), Uniformity::Dirt);
+            m.insert(ChunkCoord(0, air_ys[i], 0), Uniformity::Air);
         }
         for y in -10..=-1 {
             assert_eq!(
-                m.contains((0, y, 0)),
+                m.contains(ChunkCoord(0, y, 0)),
                 Uniformity::Dirt,
                 "missing dirt y={}",
                 y
@@ -263,26 +269,31 @@ mod tests {
         }
         for y in 1..=10 {
             assert_eq!(
-                m.contains((0, y, 0)),
+                m.contains(ChunkCoord(0, y, 0)),
                 Uniformity::Air,
                 "missing air y={}",
                 y
             );
         }
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Unknown);
     }
 
     #[test]
     fn many_disjoint_ranges_then_bridged_in_randomish_order() {
         let mut m = ColumnRangeMap::new();
         for y in (-40..=40).step_by(2) {
-            m.insert((0, y, 0), Uniformity::Air);
+            m.insert(ChunkCoord(0, y, 0), Uniformity::Air);
         }
         for y in (-39..=39).rev().step_by(2) {
-            m.insert((0, y, 0), Uniformity::Air);
+            m.insert(ChunkCoord(0, y, 0), Uniformity::Air);
         }
         for y in -40..=40 {
-            assert_eq!(m.contains((0, y, 0)), Uniformity::Air, "missing y={}", y);
+            assert_eq!(
+                m.contains(ChunkCoord(0, y, 0)),
+                Uniformity::Air,
+                "missing y={}",
+                y
+            );
         }
     }
 
@@ -290,71 +301,71 @@ mod tests {
     fn repro_missing_air_1_smaller() {
         let mut m = ColumnRangeMap::new();
         for &y in &[-6, -1, -5, -2, -4, -3] {
-            m.insert((0, y, 0), Uniformity::Dirt);
+            m.insert(ChunkCoord(0, y, 0), Uniformity::Dirt);
         }
         for &y in &[6, 1, 5, 2, 4, 3] {
-            m.insert((0, y, 0), Uniformity::Air);
+            m.insert(ChunkCoord(0, y, 0), Uniformity::Air);
         }
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Air);
     }
 
     #[test]
     fn remove_single_chunk_range() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.remove((0, 0, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Unknown);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 0, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Unknown);
     }
 
     #[test]
     fn remove_from_middle_splits_range() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 1, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        m.remove((0, 1, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Unknown);
-        assert_eq!(m.contains((0, 2, 0)), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 1, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 2, 0)), Uniformity::Air);
     }
 
     #[test]
     fn remove_from_start_of_range() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 1, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        m.remove((0, 0, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Unknown);
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 2, 0)), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 0, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Unknown);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 2, 0)), Uniformity::Air);
     }
 
     #[test]
     fn remove_from_end_of_range() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.insert((0, 1, 0), Uniformity::Air);
-        m.insert((0, 2, 0), Uniformity::Air);
-        m.remove((0, 2, 0), Uniformity::Air);
-        assert_eq!(m.contains((0, 0, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 1, 0)), Uniformity::Air);
-        assert_eq!(m.contains((0, 2, 0)), Uniformity::Unknown);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 1, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 2, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 2, 0), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 0, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 1, 0)), Uniformity::Air);
+        assert_eq!(m.contains(ChunkCoord(0, 2, 0)), Uniformity::Unknown);
     }
 
     #[test]
     #[should_panic(expected = "chunk coordinate not found in map")]
     fn remove_nonexistent_panics() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.remove((0, 5, 0), Uniformity::Air);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 5, 0), Uniformity::Air);
     }
 
     #[test]
     #[should_panic(expected = "uniformity mismatch")]
     fn remove_wrong_uniformity_panics() {
         let mut m = ColumnRangeMap::new();
-        m.insert((0, 0, 0), Uniformity::Air);
-        m.remove((0, 0, 0), Uniformity::Dirt);
+        m.insert(ChunkCoord(0, 0, 0), Uniformity::Air);
+        m.remove(ChunkCoord(0, 0, 0), Uniformity::Dirt);
     }
 }
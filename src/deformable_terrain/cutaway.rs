@@ -0,0 +1,136 @@
+use bevy::pbr::ExtendedMaterial;
+use bevy::prelude::*;
+use bevy::render::render_resource::Face;
+
+use crate::deformable_terrain::terrain::TerrainMaterialHandle;
+use crate::deformable_terrain::terrain_material::TerrainMaterialExtension;
+use crate::player::player::MainCameraTag;
+use crate::ui::console::{Console, ConsoleCommand};
+
+const DEFAULT_CUTAWAY_DISTANCE: f32 = 8.0; //world units in front of the camera a fresh plane is placed
+const CUTAWAY_NUDGE_SPEED: f32 = 6.0; //world units per second while a nudge key is held
+const NUDGE_CLOSER_KEY: KeyCode = KeyCode::BracketLeft;
+const NUDGE_FURTHER_KEY: KeyCode = KeyCode::BracketRight;
+
+//clip plane for the terrain material, everything on the positive side of the normal is discarded
+//back faces are drawn flat while it is active so solid ground reads as a filled cross section
+#[derive(Resource, Default)]
+pub struct Cutaway {
+    plane: Option<(Vec3, f32)>,
+}
+
+impl Cutaway {
+    pub fn is_active(&self) -> bool {
+        self.plane.is_some()
+    }
+}
+
+//cutaway [distance] clips everything between the camera and a plane that far ahead
+//cutaway x|y|z <coordinate> clips everything past an axis aligned plane, cutaway again or cutaway off clears it
+pub fn cutaway_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    camera: Query<&GlobalTransform, With<MainCameraTag>>,
+    mut cutaway: ResMut<Cutaway>,
+) {
+    for command in command_reader.read() {
+        if command.name != "cutaway" {
+            continue;
+        }
+        let off = command.args.first().is_some_and(|a| a == "off");
+        if cutaway.is_active() && (off || command.args.is_empty()) {
+            cutaway.plane = None;
+            console.print("cutaway off");
+            continue;
+        }
+        if off {
+            console.print("cutaway is not active");
+            continue;
+        }
+        let axis = match command.args.first().map(String::as_str) {
+            Some("x") => Some(Vec3::X),
+            Some("y") => Some(Vec3::Y),
+            Some("z") => Some(Vec3::Z),
+            _ => None,
+        };
+        let plane = if let Some(axis) = axis {
+            command
+                .args
+                .get(1)
+                .and_then(|a| a.parse::<f32>().ok())
+                .map(|coordinate| (axis, coordinate))
+        } else {
+            let distance = command
+                .args
+                .first()
+                .map_or(Some(DEFAULT_CUTAWAY_DISTANCE), |a| {
+                    a.parse::<f32>().ok().filter(|v| *v > 0.0)
+                });
+            match (distance, camera.single()) {
+                (Some(distance), Ok(camera)) => {
+                    //normal points back at the camera so the near side is what gets discarded
+                    let normal = -camera.forward().as_vec3();
+                    let point = camera.translation() - normal * distance;
+                    Some((normal, normal.dot(point)))
+                }
+                _ => None,
+            }
+        };
+        let Some((normal, offset)) = plane else {
+            console.print("usage: cutaway [distance] | cutaway x|y|z <coordinate> | cutaway off");
+            continue;
+        };
+        cutaway.plane = Some((normal, offset));
+        console.print(format!(
+            "cutaway at n=({:.2}, {:.2}, {:.2}) d={offset:.1}, hold [ and ] to move it",
+            normal.x, normal.y, normal.z
+        ));
+    }
+}
+
+//] moves the plane deeper into the terrain and [ backs it out, skipped while typing in the console
+pub fn nudge_cutaway(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    console: Res<Console>,
+    mut cutaway: ResMut<Cutaway>,
+) {
+    if console.is_open || !cutaway.is_active() {
+        return;
+    }
+    let direction = keyboard.pressed(NUDGE_FURTHER_KEY) as i32 as f32
+        - keyboard.pressed(NUDGE_CLOSER_KEY) as i32 as f32;
+    if direction == 0.0 {
+        return;
+    }
+    if let Some((_, offset)) = cutaway.plane.as_mut() {
+        *offset -= direction * CUTAWAY_NUDGE_SPEED * time.delta_secs();
+    }
+}
+
+//pushes the plane into the shared terrain material, every chunk uses the same handle so this is one write
+pub fn apply_cutaway(
+    cutaway: Res<Cutaway>,
+    material_handle: Option<Res<TerrainMaterialHandle>>,
+    mut materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>>>,
+) {
+    let Some(material_handle) = material_handle else {
+        return;
+    };
+    if !cutaway.is_changed() && !material_handle.is_added() {
+        return;
+    }
+    let Some(material) = materials.get_mut(&material_handle.0) else {
+        return;
+    };
+    match cutaway.plane {
+        Some((normal, offset)) => {
+            material.extension.clip_plane = normal.extend(offset);
+            material.base.cull_mode = None;
+        }
+        None => {
+            material.extension.clip_plane = Vec4::ZERO;
+            material.base.cull_mode = Some(Face::Back);
+        }
+    }
+}
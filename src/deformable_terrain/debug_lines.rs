@@ -5,15 +5,18 @@ use bevy_rapier3d::prelude::Collider;
 
 use crate::{
     constants::{
-        CHUNK_WORLD_SIZE, CLUSTER_WORLD_LENGTH, HALF_CHUNK, REDUCED_LOD_1_RADIUS,
-        REDUCED_LOD_2_RADIUS, REDUCED_LOD_3_RADIUS, REDUCED_LOD_4_RADIUS, REDUCED_LOD_5_RADIUS,
-        SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
+        CHUNK_WORLD_SIZE, CLUSTER_WORLD_LENGTH, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM,
+        SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
     },
     conversions::{
         chunk_coord_to_cluster_coord, chunk_coord_to_world_pos, cluster_coord_to_world_center,
         world_pos_to_chunk_coord,
     },
-    deformable_terrain::{driver::TerrainChunkMap, plugin::ChunkTag, terrain::TerrainChunk},
+    deformable_terrain::{
+        driver::TerrainChunkMap,
+        plugin::{ChunkTag, StreamingConfig},
+        terrain::TerrainChunk,
+    },
     player::player::PlayerTag,
     ui::configurable_settings::ConfigurableSettings,
 };
@@ -26,22 +29,24 @@ pub fn draw_lod_debug(
     mut gizmos: Gizmos,
     player_transform_query: Query<&Transform, With<PlayerTag>>,
     settings: Res<ConfigurableSettings>,
+    streaming: Res<StreamingConfig>,
 ) {
     let pos = player_transform_query.iter().next().unwrap().translation;
+    let [lod_1, lod_2, lod_3, lod_4, lod_5] = streaming.lod_radii;
     if settings.debug_lod_1 {
-        gizmos.sphere(pos, REDUCED_LOD_1_RADIUS, Color::srgb(1.0, 0.0, 0.0));
+        gizmos.sphere(pos, lod_1, Color::srgb(1.0, 0.0, 0.0));
     }
     if settings.debug_lod_2 {
-        gizmos.sphere(pos, REDUCED_LOD_2_RADIUS, Color::srgb(0.0, 1.0, 1.0));
+        gizmos.sphere(pos, lod_2, Color::srgb(0.0, 1.0, 1.0));
     }
     if settings.debug_lod_3 {
-        gizmos.sphere(pos, REDUCED_LOD_3_RADIUS, Color::srgb(0.0, 0.0, 1.0));
+        gizmos.sphere(pos, lod_3, Color::srgb(0.0, 0.0, 1.0));
     }
     if settings.debug_lod_4 {
-        gizmos.sphere(pos, REDUCED_LOD_4_RADIUS, Color::srgb(1.0, 1.0, 0.0));
+        gizmos.sphere(pos, lod_4, Color::srgb(1.0, 1.0, 0.0));
     }
     if settings.debug_lod_5 {
-        gizmos.sphere(pos, REDUCED_LOD_5_RADIUS, Color::srgb(1.0, 0.0, 1.0));
+        gizmos.sphere(pos, lod_5, Color::srgb(1.0, 0.0, 1.0));
     }
 }
 
@@ -61,9 +66,8 @@ pub fn draw_collider_debug(
             CHUNKS_WITH_COLLIDER_COLOR,
         );
     }
-    let terrain_chunk_map_lock = terrain_chunk_map.0.lock().unwrap();
-    for chunk_coord in terrain_chunk_map_lock.keys() {
-        let chunk_world_pos = chunk_coord_to_world_pos(chunk_coord);
+    for chunk_coord in terrain_chunk_map.0.coords() {
+        let chunk_world_pos = chunk_coord_to_world_pos(&chunk_coord);
         gizmos.cube(
             Transform::from_translation(chunk_world_pos)
                 .with_scale(Vec3::splat(CHUNK_WORLD_SIZE as f32)),
@@ -106,8 +110,8 @@ pub fn draw_voxel_surface_debug(
     }
     let player_pos = player_transform_query.iter().next().unwrap().translation;
     let chunk_coord = world_pos_to_chunk_coord(&player_pos);
-    let map = terrain_chunk_map.0.lock().unwrap();
-    let Some(TerrainChunk::NonUniformTerrainChunk(chunk)) = map.get(&chunk_coord) else {
+    let Some(TerrainChunk::NonUniformTerrainChunk(chunk)) = terrain_chunk_map.0.get(&chunk_coord)
+    else {
         return;
     };
     let densities = &chunk.densities;
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use fastnoise2::{SafeNode, generator::GeneratorWrapper};
+
+use crate::{
+    constants::{
+        CHUNK_WORLD_SIZE, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_DIM,
+        SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
+    },
+    deformable_terrain::{
+        chunk_generator::{
+            MaterialCode, caves_reach_chunk, compute_heightmap_gradients, fast_get_uniformity,
+            generate_chunk_into_buffers, generate_downsampled_into, generate_noise_height_samples,
+            generate_terrain_heights, quantize_f32_to_i16, sample_terrain_height,
+        },
+        driver::ChunkBuffers,
+        hydrology::apply_hydrology,
+        plugin::Uniformity,
+        roads::apply_roads,
+        structures::structures_reach_chunk,
+    },
+};
+
+//where the loader threads get terrain from when a chunk is not on disk
+//sdf is in world units, negative is solid, anything past +-10 is clamped when quantized
+//a chunk the source fills without a surface is saved as uniform air or dirt, so solid material other than dirt only survives next to a surface
+//any loader thread may get any chunk, in any order, with buffers left over from whatever it did last
+//so everything written has to follow from chunk_start and the world seed alone, randomness comes from chunk_rng
+pub trait DensitySource: Send + Sync + 'static {
+    fn sample(&self, world_pos: Vec3) -> (f32, MaterialCode);
+
+    //runs once per chunk column before any chunk of it is classified or filled, for work shared along y
+    //the loader caches the result across threads, so it may only write heightmap, dhdx, dhdz, path_mask and water_level
+    fn prepare_column(&self, _chunk_start: &Vec3, _chunk_buffers: &mut ChunkBuffers) {}
+
+    //cheap check to skip filling chunks that are obviously uniform, Unknown makes the loader fill the chunk to find out
+    fn classify(&self, _chunk_start: &Vec3, _chunk_buffers: &mut ChunkBuffers) -> Uniformity {
+        Uniformity::Unknown
+    }
+
+    //fills the padded densities and the unpadded materials, chunk_start is the world position of the first unpadded sample
+    fn fill_chunk(&self, chunk_start: Vec3, chunk_buffers: &mut ChunkBuffers) {
+        let padded_start = chunk_start - Vec3::splat(VOXEL_WORLD_SIZE);
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                    let world_pos =
+                        padded_start + Vec3::new(x as f32, y as f32, z as f32) * VOXEL_WORLD_SIZE;
+                    let (sdf, material) = self.sample(world_pos);
+                    let idx =
+                        (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                    chunk_buffers.density[idx] = quantize_f32_to_i16(sdf);
+                    let interior = [x, y, z]
+                        .iter()
+                        .all(|p| (1..=SAMPLES_PER_CHUNK_DIM).contains(p));
+                    if interior {
+                        let mat_idx =
+                            (z - 1) * SAMPLES_PER_CHUNK_2D + (y - 1) * SAMPLES_PER_CHUNK_DIM;
+                        chunk_buffers.material[mat_idx + x - 1] = material;
+                    }
+                }
+            }
+        }
+    }
+
+    //far lods skip the full res chunk, out_dim^3 samples spread evenly over the chunk with the outer ones on its faces
+    //unpadded densities and materials, only called after classify said Unknown and never for chunks on disk
+    fn fill_chunk_downsampled(
+        &self,
+        chunk_start: Vec3,
+        _chunk_buffers: &mut ChunkBuffers,
+        out_dim: usize,
+        densities_out: &mut [i16],
+        materials_out: &mut [MaterialCode],
+    ) {
+        let spacing = CHUNK_WORLD_SIZE / (out_dim - 1) as f32;
+        for z in 0..out_dim {
+            for y in 0..out_dim {
+                for x in 0..out_dim {
+                    let world_pos = chunk_start + Vec3::new(x as f32, y as f32, z as f32) * spacing;
+                    let (sdf, material) = self.sample(world_pos);
+                    let idx = (z * out_dim + y) * out_dim + x;
+                    densities_out[idx] = quantize_f32_to_i16(sdf);
+                    materials_out[idx] = material;
+                }
+            }
+        }
+    }
+}
+
+//the built in heightmap terrain with hydrology, roads, caves and structures
+#[derive(Clone)]
+pub struct NoiseTerrain {
+    pub fbm: GeneratorWrapper<SafeNode>,
+}
+
+impl NoiseTerrain {
+    pub fn new(fbm: GeneratorWrapper<SafeNode>) -> Self {
+        NoiseTerrain { fbm }
+    }
+}
+
+impl DensitySource for NoiseTerrain {
+    //single point version, ignores slope, caves, structures and the biome surface stack
+    fn sample(&self, world_pos: Vec3) -> (f32, MaterialCode) {
+        let sdf = world_pos.y - sample_terrain_height(world_pos.x, world_pos.z, &self.fbm);
+        let material = if sdf < 0.0 {
+            MaterialCode::Dirt
+        } else {
+            MaterialCode::Air
+        };
+        (sdf, material)
+    }
+
+    fn prepare_column(&self, chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) {
+        let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &self.fbm);
+        generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
+        compute_heightmap_gradients(
+            &mut chunk_buffers.dhdx,
+            &mut chunk_buffers.dhdz,
+            &noise_samples,
+        );
+        apply_hydrology(chunk_start, chunk_buffers);
+        apply_roads(chunk_start, chunk_buffers, &self.fbm);
+    }
+
+    fn classify(&self, chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) -> Uniformity {
+        let uniformity = fast_get_uniformity(
+            &chunk_buffers.heightmap,
+            &chunk_buffers.dhdx,
+            &chunk_buffers.dhdz,
+            chunk_start,
+        );
+        if uniformity == Uniformity::Dirt && caves_reach_chunk(chunk_start, chunk_buffers) {
+            return Uniformity::NonUniform;
+        }
+        if uniformity == Uniformity::Air && structures_reach_chunk(chunk_start, &self.fbm) {
+            return Uniformity::NonUniform;
+        }
+        uniformity
+    }
+
+    fn fill_chunk(&self, chunk_start: Vec3, chunk_buffers: &mut ChunkBuffers) {
+        generate_chunk_into_buffers(chunk_start, chunk_buffers, &self.fbm);
+    }
+
+    fn fill_chunk_downsampled(
+        &self,
+        chunk_start: Vec3,
+        chunk_buffers: &mut ChunkBuffers,
+        out_dim: usize,
+        densities_out: &mut [i16],
+        materials_out: &mut [MaterialCode],
+    ) {
+        generate_downsampled_into(
+            chunk_start,
+            chunk_buffers,
+            &self.fbm,
+            out_dim,
+            densities_out,
+            materials_out,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deformable_terrain::{
+        chunk_generator::downscale, driver::RF2_SAMPLES_PER_CHUNK_DIM,
+    };
+
+    struct TiltedPlane;
+
+    impl DensitySource for TiltedPlane {
+        fn sample(&self, world_pos: Vec3) -> (f32, MaterialCode) {
+            let sdf = world_pos.y - 0.4 * world_pos.x - 0.2 * world_pos.z - 3.0;
+            let material = if sdf < 0.0 {
+                MaterialCode::Dirt
+            } else {
+                MaterialCode::Air
+            };
+            (sdf, material)
+        }
+    }
+
+    #[test]
+    fn downsampled_fill_takes_the_samples_downscale_would() {
+        let chunk_start = Vec3::new(-6.0, -2.0, 6.0);
+        let mut chunk_buffers = ChunkBuffers::new();
+        TiltedPlane.fill_chunk(chunk_start, &mut chunk_buffers);
+        let len = RF2_SAMPLES_PER_CHUNK_DIM.pow(3);
+        let (mut downscaled, mut downscaled_materials) =
+            (vec![0i16; len], vec![MaterialCode::Air; len]);
+        downscale(
+            &chunk_buffers.density,
+            &chunk_buffers.material,
+            &mut downscaled,
+            &mut downscaled_materials,
+            RF2_SAMPLES_PER_CHUNK_DIM,
+        );
+        let (mut generated, mut generated_materials) =
+            (vec![0i16; len], vec![MaterialCode::Air; len]);
+        TiltedPlane.fill_chunk_downsampled(
+            chunk_start,
+            &mut chunk_buffers,
+            RF2_SAMPLES_PER_CHUNK_DIM,
+            &mut generated,
+            &mut generated_materials,
+        );
+        //a plane is linear so the trilinear downscale lands on it up to quantization
+        for (d, g) in downscaled.iter().zip(&generated) {
+            assert!((d - g).abs() <= 1, "{d} {g}");
+        }
+        assert!(generated.iter().any(|d| *d < 0) && generated.iter().any(|d| *d > 0));
+    }
+}
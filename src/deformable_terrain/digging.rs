@@ -1,59 +1,467 @@
 use std::sync::Arc;
 
-use bevy::{camera::primitives::MeshAabb, ecs::system::SystemParam, prelude::*};
-use bevy_rapier3d::prelude::{Collider, ComputedColliderShape, TriMeshFlags};
+use bevy::{
+    diagnostic::FrameCount, ecs::system::SystemParam, input::mouse::MouseWheel, prelude::*,
+};
+use bevy_rapier3d::prelude::Collider;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
+    chunk_coord::ChunkCoord,
     constants::{
         CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM,
         SAMPLES_PER_CHUNK_DIM_PADDED, SAMPLES_PER_CHUNK_PADDED, VOXEL_WORLD_SIZE,
     },
     conversions::{
-        chunk_coord_to_world_pos, flatten_index, world_pos_to_chunk_coord, world_pos_to_voxel_index,
+        chunk_coord_to_first_padded_sample, chunk_coord_to_world_pos, flatten_index,
+        sample_to_padded_index, world_pos_to_chunk_coord, world_pos_to_voxel_index,
     },
     deformable_terrain::{
         chunk_entity_map::ChunkEntityMap,
-        chunk_generator::{MaterialCode, dequantize_i16_to_f32, quantize_f32_to_i16},
-        driver::{TerrainChunkMap, WriteCmd, WriteCmdSender},
-        marching_cubes::mc::mc_mesh_generation,
+        chunk_events::ChunkEventWriters,
+        chunk_generator::{MaterialCode, chunk_uniformity},
+        chunk_iter::chunks_in_box,
+        driver::{
+            DirtyRange, RemeshQueue, RemeshResult, TerrainChunkMap, TerrainSvo, WriteCmd,
+            WriteCmdSender,
+        },
         plugin::{ChunkTag, Uniformity},
+        protected_regions::{EditRejected, ProtectedRegions},
+        sdf_value::SdfValue,
         sparse_voxel_octree::sphere_intersects_aabb,
-        terrain::{
-            NonUniformTerrainChunk, TerrainChunk, TerrainMaterialHandle, generate_bevy_mesh,
-        },
+        terrain::{NonUniformTerrainChunk, TerrainChunk, TerrainMaterialHandle},
+    },
+    player::player::{KeyBindings, MainCameraTag},
+    ui::{
+        console::{Console, ConsoleCommand},
+        menu::MenuRoot,
     },
-    player::player::MainCameraTag,
-    ui::menu::MenuRoot,
 };
 
-const DIG_STRENGTH: f32 = 0.5;
+#[cfg(feature = "debug")]
+use crate::deformable_terrain::driver_debug_ui::EMPTY_MESHES_SUPPRESSED;
+#[cfg(feature = "debug")]
+use std::sync::atomic::Ordering;
+
 const DIG_TIMER: f32 = 0.004; // seconds
-const DIG_RADIUS: f32 = 2.0; // world space
-const DIG_RADIUS_SQUARED: f32 = DIG_RADIUS * DIG_RADIUS;
+const DEFAULT_BRUSH_RADIUS: f32 = 2.0; // world space
+const DEFAULT_BRUSH_STRENGTH: f32 = 0.5; //world units a stroke moves the surface at the center, a dig and a build cancel out
+const MIN_BRUSH_RADIUS: f32 = 0.5;
+const MAX_BRUSH_RADIUS: f32 = 8.0;
+const BRUSH_RADIUS_STEP: f32 = 0.25; //per scroll notch
+const MIN_BRUSH_STRENGTH: f32 = 0.05;
+const MAX_BRUSH_STRENGTH: f32 = 2.0;
+const BRUSH_STRENGTH_STEP: f32 = 1.25; //factor per key press
+pub const PAINT_PALETTE: [MaterialCode; 5] = [
+    MaterialCode::Dirt,
+    MaterialCode::Grass,
+    MaterialCode::Sand,
+    MaterialCode::Path,
+    MaterialCode::Snow,
+]; //picked with the number keys while painting
+const PALETTE_KEYS: [KeyCode; PAINT_PALETTE.len()] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+//a chunk copied out of the chunk map with an edit applied, and what it was before the edit
+pub(crate) type ModifiedChunk = (
+    ChunkCoord,
+    Arc<[i16]>,
+    Arc<[MaterialCode]>,
+    Uniformity,
+    DirtyRange,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushShape {
+    Sphere,
+    Cube,    //as wide as the sphere
+    Capsule, //upright, twice as tall as it is wide
+    Flatten, //moves the surface inside a sphere toward the level of the first hit of a click
+    Smooth,  //pulls every sample inside a sphere toward the average of its neighbors
+}
+
+impl BrushShape {
+    const ALL: [BrushShape; 5] = [
+        BrushShape::Sphere,
+        BrushShape::Cube,
+        BrushShape::Capsule,
+        BrushShape::Flatten,
+        BrushShape::Smooth,
+    ];
+
+    fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|shape| *shape == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    //flatten and smooth reshape what is there, the rest dig on left click and build on right click
+    fn adds_or_removes(self) -> bool {
+        matches!(
+            self,
+            BrushShape::Sphere | BrushShape::Cube | BrushShape::Capsule
+        )
+    }
+
+    pub(crate) fn reaches(self, center: Vec3, radius: f32, min: &Vec3, max: &Vec3) -> bool {
+        match self {
+            BrushShape::Sphere | BrushShape::Flatten | BrushShape::Smooth => {
+                sphere_intersects_aabb(&center, radius * radius, min, max)
+            }
+            BrushShape::Cube | BrushShape::Capsule => {
+                let half_extents = self.half_extents(radius);
+                (center - half_extents).cmple(*max).all()
+                    && (center + half_extents).cmpge(*min).all()
+            }
+        }
+    }
+
+    pub(crate) fn half_extents(self, radius: f32) -> Vec3 {
+        match self {
+            BrushShape::Capsule => Vec3::new(radius, 2.0 * radius, radius),
+            _ => Vec3::splat(radius),
+        }
+    }
+
+    //1 at the center down to 0 at the edge, 0 outside
+    fn falloff(self, center: Vec3, radius: f32, point: Vec3) -> f32 {
+        let offset = point - center;
+        let distance_squared = match self {
+            BrushShape::Sphere | BrushShape::Flatten | BrushShape::Smooth => {
+                offset.length_squared()
+            }
+            BrushShape::Cube => offset.abs().max_element().powi(2),
+            BrushShape::Capsule => {
+                Vec3::new(offset.x, (offset.y.abs() - radius).max(0.0), offset.z).length_squared()
+            }
+        };
+        (1.0 - distance_squared / (radius * radius)).max(0.0)
+    }
+}
+
+//the brush both mouse buttons edit with, changed with the brush hotkeys or the `brush` console command
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct TerrainBrush {
+    pub shape: BrushShape,
+    pub radius: f32,            //world space
+    pub strength: f32,          //world units per stroke at the center
+    pub material: MaterialCode, //given to samples a stroke makes solid
+    pub painting: bool, //clicks recolor the solid samples inside the brush instead of changing the surface
+}
+
+impl Default for TerrainBrush {
+    fn default() -> Self {
+        TerrainBrush {
+            shape: BrushShape::Sphere,
+            radius: DEFAULT_BRUSH_RADIUS,
+            strength: DEFAULT_BRUSH_STRENGTH,
+            material: MaterialCode::Dirt,
+            painting: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BrushAction {
+    Dig,
+    Build,
+}
+
+//every dig the player makes, for terrain kept outside the chunk map such as fine zones
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TerrainDug {
+    pub center: Vec3,
+    pub radius: f32,
+    pub strength: f32,
+    pub shape: BrushShape,
+}
 
 #[derive(SystemParam)]
 pub struct TerrainIo<'w> {
-    pub terrain_chunk_map: ResMut<'w, TerrainChunkMap>,
+    pub terrain_chunk_map: Res<'w, TerrainChunkMap>,
     pub chunk_entity_map: ResMut<'w, ChunkEntityMap>,
+    pub terrain_svo: Res<'w, TerrainSvo>,
+}
+
+//hold the resize key and scroll to change the radius, the stronger and weaker keys scale the strength
+//the paint key switches between sculpting and painting, while painting the number keys pick from the palette
+pub fn adjust_brush(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut scroll_events: MessageReader<MouseWheel>,
+    mut brush: ResMut<TerrainBrush>,
+    menu_root_query: Query<&MenuRoot>,
+) {
+    let resizing = keyboard.pressed(key_bindings.brush_resize);
+    let scrolled: f32 = scroll_events.read().map(|event| event.y.signum()).sum();
+    if !menu_root_query.is_empty() {
+        return;
+    }
+    if resizing && scrolled != 0.0 {
+        brush.radius =
+            (brush.radius + scrolled * BRUSH_RADIUS_STEP).clamp(MIN_BRUSH_RADIUS, MAX_BRUSH_RADIUS);
+    }
+    if keyboard.just_pressed(key_bindings.brush_stronger) {
+        brush.strength =
+            (brush.strength * BRUSH_STRENGTH_STEP).clamp(MIN_BRUSH_STRENGTH, MAX_BRUSH_STRENGTH);
+    }
+    if keyboard.just_pressed(key_bindings.brush_weaker) {
+        brush.strength =
+            (brush.strength / BRUSH_STRENGTH_STEP).clamp(MIN_BRUSH_STRENGTH, MAX_BRUSH_STRENGTH);
+    }
+    if keyboard.just_pressed(key_bindings.cycle_brush) {
+        brush.shape = brush.shape.next();
+    }
+    if keyboard.just_pressed(key_bindings.toggle_paint) {
+        brush.painting = !brush.painting;
+    }
+    if brush.painting
+        && let Some(i) = PALETTE_KEYS
+            .iter()
+            .position(|key| keyboard.just_pressed(*key))
+    {
+        brush.material = PAINT_PALETTE[i];
+    }
+}
+
+//everything needed to put edited chunk copies back, shared by every tool that edits the chunk map
+#[derive(SystemParam)]
+pub struct TerrainEditor<'w, 's> {
+    pub terrain_io: TerrainIo<'w>,
+    commands: Commands<'w, 's>,
+    material_handle: Res<'w, TerrainMaterialHandle>,
+    solid_chunk_query: Query<'w, 's, &'static mut Mesh3d, With<ChunkTag>>,
+    mesh_handles: ResMut<'w, Assets<Mesh>>,
+    write_cmd_sender: Res<'w, WriteCmdSender>,
+    remesh_queue: ResMut<'w, RemeshQueue>,
+    frame_count: Res<'w, FrameCount>,
+    pub protected_regions: Res<'w, ProtectedRegions>,
+    rejected_writer: MessageWriter<'w, EditRejected>,
+    chunk_events: ChunkEventWriters<'w>,
+}
+
+impl TerrainEditor<'_, '_> {
+    //tells the ui when an edit around center reaches into a protected region, true if it does
+    pub(crate) fn reject_protected(&mut self, center: Vec3, min: Vec3, max: Vec3) -> bool {
+        let Some(region) = self.protected_regions.overlapping(min, max) else {
+            return false;
+        };
+        self.rejected_writer.write(EditRejected {
+            center,
+            region: region.name.clone(),
+        });
+        true
+    }
+
+    //sends the edited chunks to the write thread, swaps them into the chunk map and queues their remesh
+    //every density and material the edit changed has to lie inside the chunk's dirty range, that is all a patch rewrites
+    pub(crate) fn apply(&mut self, modified_chunks: Vec<ModifiedChunk>) {
+        {
+            let mut svo = self.terrain_io.terrain_svo.0.write();
+            for (chunk_coord, ..) in &modified_chunks {
+                svo.mark_edited(*chunk_coord, self.frame_count.0);
+            }
+        }
+        let edited: FxHashSet<ChunkCoord> = modified_chunks
+            .iter()
+            .map(|(chunk_coord, ..)| *chunk_coord)
+            .collect();
+        let mut border_neighbors = FxHashSet::default();
+        for (chunk_coord, .., dirty) in &modified_chunks {
+            for (x, y, z) in dirty.touched_faces() {
+                let neighbor = chunk_coord.offset(x, y, z);
+                if !edited.contains(&neighbor) {
+                    border_neighbors.insert(neighbor);
+                }
+            }
+        }
+        for (chunk_coord, densities, materials, uniformity, dirty) in modified_chunks {
+            self.chunk_events.edited(chunk_coord, &dirty);
+            match uniformity {
+                Uniformity::Air | Uniformity::Dirt => {
+                    let _ = self.write_cmd_sender.0.send(WriteCmd::UpdateNonUniform {
+                        densities: Arc::clone(&densities),
+                        materials: Arc::clone(&materials),
+                        chunk_coord,
+                    });
+                    if uniformity == Uniformity::Air {
+                        let _ = self
+                            .write_cmd_sender
+                            .0
+                            .send(WriteCmd::RemoveUniformAir { chunk_coord });
+                    } else {
+                        let _ = self
+                            .write_cmd_sender
+                            .0
+                            .send(WriteCmd::RemoveUniformDirt { chunk_coord });
+                    }
+                }
+                Uniformity::NonUniform => {
+                    let _ = self.write_cmd_sender.0.send(WriteCmd::PatchNonUniform {
+                        densities: Arc::clone(&densities),
+                        materials: Arc::clone(&materials),
+                        chunk_coord,
+                        dirty,
+                    });
+                }
+                Uniformity::Unknown => unreachable!(),
+            }
+            self.remesh_queue
+                .request(chunk_coord, Arc::clone(&densities), Arc::clone(&materials));
+            //replace chunks in chunk map, one the edit left without a surface goes back to uniform
+            //the same way the write thread moves it to the air or dirt file and frees its region sectors
+            let terrain_chunk = match chunk_uniformity(&densities, &materials) {
+                Uniformity::Air => TerrainChunk::UniformAir,
+                Uniformity::Dirt => TerrainChunk::UniformDirt,
+                _ => TerrainChunk::NonUniformTerrainChunk(NonUniformTerrainChunk {
+                    densities,
+                    materials,
+                }),
+            };
+            self.terrain_io
+                .terrain_chunk_map
+                .0
+                .insert_edited(chunk_coord, terrain_chunk);
+        }
+        //the chunk across a face the edit touched shares the surface there even when none of its own samples changed,
+        //it is queued behind the edited chunks so its side of the seam is rebuilt from what is in the chunk map now
+        //one without an entity has no surface to crack, it gets one once an edit reaches its own samples
+        for neighbor in border_neighbors {
+            if self
+                .terrain_io
+                .chunk_entity_map
+                .get_option(neighbor)
+                .is_some()
+                && let Some(TerrainChunk::NonUniformTerrainChunk(chunk)) =
+                    self.terrain_io.terrain_chunk_map.0.get(&neighbor)
+            {
+                self.remesh_queue
+                    .request(neighbor, chunk.densities, chunk.materials);
+            }
+        }
+    }
+
+    fn apply_remeshed(&mut self, remeshed: RemeshResult) {
+        let RemeshResult { chunk_coord, mesh } = remeshed;
+        let stale = self.remesh_queue.finish(chunk_coord);
+        let mesh = match self.terrain_io.terrain_chunk_map.0.get(&chunk_coord) {
+            //paged out while it was being meshed, its entity went with it
+            None => return,
+            Some(TerrainChunk::NonUniformTerrainChunk(chunk)) => {
+                //the mesh is still shown, it is one edit behind and the next one is already on its way
+                if stale {
+                    self.remesh_queue
+                        .request(chunk_coord, chunk.densities, chunk.materials);
+                }
+                mesh
+            }
+            //a later edit left it uniform, there is no surface whatever the mesh in flight shows
+            Some(TerrainChunk::UniformAir | TerrainChunk::UniformDirt) => None,
+        };
+        let entity = self.terrain_io.chunk_entity_map.get_option(chunk_coord);
+        match mesh {
+            Some((prepared, collider)) => match entity {
+                //entity already existed, update it
+                Some((entity, mesh_handle)) => {
+                    let entity = *entity;
+                    let mut mesh = self.solid_chunk_query.get_mut(entity).unwrap();
+                    //a mesh rapier refused leaves the chunk without a collider until its next edit
+                    match collider {
+                        Some(collider) => self.commands.entity(entity).insert(collider),
+                        None => self.commands.entity(entity).remove::<Collider>(),
+                    };
+                    self.mesh_handles.remove(mesh_handle);
+                    self.commands.entity(entity).insert(prepared.aabb);
+                    let new_mesh_handle = self.mesh_handles.add(prepared.mesh);
+                    *mesh = Mesh3d(new_mesh_handle.clone());
+                    self.terrain_io
+                        .chunk_entity_map
+                        .replace_mesh_handle(chunk_coord, new_mesh_handle);
+                    self.chunk_events.meshed(chunk_coord, entity);
+                }
+                //entity did not already exist
+                None => {
+                    let new_mesh_handle = self.mesh_handles.add(prepared.mesh);
+                    let mut new_entity = self.commands.spawn((
+                        Mesh3d(new_mesh_handle.clone()),
+                        prepared.aabb,
+                        MeshMaterial3d(self.material_handle.0.clone()),
+                        ChunkTag,
+                        Transform::from_translation(chunk_coord_to_world_pos(&chunk_coord)),
+                    ));
+                    if let Some(collider) = collider {
+                        new_entity.insert(collider);
+                    }
+                    let new_entity = new_entity.id();
+                    self.terrain_io
+                        .chunk_entity_map
+                        .insert(chunk_coord, (new_entity, new_mesh_handle));
+                    self.terrain_io
+                        .terrain_svo
+                        .0
+                        .write()
+                        .set_has_entity(chunk_coord, true);
+                    self.chunk_events.spawned(chunk_coord, new_entity);
+                }
+            },
+            None => {
+                //no geometry, remove existing entity if it exists
+                #[cfg(feature = "debug")]
+                EMPTY_MESHES_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+                if let Some((entity, mesh_handle)) = entity {
+                    self.chunk_events.despawned(chunk_coord, *entity);
+                    self.commands.entity(*entity).despawn();
+                    self.mesh_handles.remove(mesh_handle);
+                    self.terrain_io.chunk_entity_map.remove(chunk_coord);
+                    self.terrain_io
+                        .terrain_svo
+                        .0
+                        .write()
+                        .set_has_entity(chunk_coord, false);
+                }
+            }
+        }
+    }
 }
+
+//swaps in the meshes the loader threads made for edited chunks, a frame or so after the edit
+pub fn apply_remesh_results(mut terrain_editor: TerrainEditor) {
+    while let Some(remeshed) = terrain_editor.remesh_queue.try_recv() {
+        terrain_editor.apply_remeshed(remeshed);
+    }
+}
+
+//left click digs and right click builds with the terrain brush, flatten and smooth do the same on either
 pub fn handle_digging_input(
     mouse_input: Res<ButtonInput<MouseButton>>,
     camera: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
     window: Query<&Window>,
-    mut commands: Commands,
     mut dig_timer: Local<f32>,
     time: Res<Time>,
-    material_handle: Res<TerrainMaterialHandle>,
-    mut solid_chunk_query: Query<(&mut Collider, &mut Mesh3d), With<ChunkTag>>,
-    mut mesh_handles: ResMut<Assets<Mesh>>,
-    mut terrain_io: TerrainIo,
-    write_cmd_sender: Res<WriteCmdSender>,
+    mut terrain_editor: TerrainEditor,
     menu_root_query: Query<&MenuRoot>,
+    mut dug_writer: MessageWriter<TerrainDug>,
+    brush: Res<TerrainBrush>,
+    mut flatten_height: Local<Option<f32>>,
 ) {
     if !menu_root_query.is_empty() {
         return;
     }
-    let should_dig = if mouse_input.pressed(MouseButton::Left) {
+    let action = if mouse_input.pressed(MouseButton::Left) {
+        Some(BrushAction::Dig)
+    } else if mouse_input.pressed(MouseButton::Right) {
+        Some(BrushAction::Build)
+    } else {
+        None
+    };
+    if action.is_none() {
+        *flatten_height = None;
+    }
+    let should_dig = if action.is_some() {
         *dig_timer += time.delta_secs();
         if *dig_timer >= DIG_TIMER {
             *dig_timer = 0.0;
@@ -65,241 +473,425 @@ pub fn handle_digging_input(
         *dig_timer = 0.0;
         false
     };
-    if should_dig {
+    if should_dig && let Some(action) = action {
         if let Some(cursor_pos) = window.iter().next().unwrap().cursor_position() {
             let (camera, camera_transform) = camera.iter().next().unwrap();
             if let Some(world_pos) = screen_to_world_ray(
                 cursor_pos,
                 camera,
                 camera_transform,
-                &terrain_io.terrain_chunk_map,
+                &terrain_editor.terrain_io.terrain_chunk_map,
+                &terrain_editor.terrain_io.terrain_svo,
             ) {
-                let modified_chunks = dig_sphere(
+                //the plane stays where the click first hit so holding the button levels the ground to it
+                let plane_height = *flatten_height.get_or_insert(world_pos.y);
+                //fine zones only follow digs, other strokes inside one show on the coarse chunk once it is unrefined
+                if action == BrushAction::Dig && brush.shape.adds_or_removes() && !brush.painting {
+                    dug_writer.write(TerrainDug {
+                        center: world_pos,
+                        radius: brush.radius,
+                        strength: brush.strength,
+                        shape: brush.shape,
+                    });
+                }
+                let half_extents = brush.shape.half_extents(brush.radius);
+                terrain_editor.reject_protected(
                     world_pos,
-                    DIG_RADIUS,
-                    DIG_RADIUS_SQUARED,
-                    DIG_STRENGTH,
-                    &mut terrain_io.terrain_chunk_map,
+                    world_pos - half_extents,
+                    world_pos + half_extents,
                 );
-                for (chunk_coord, densities, materials, uniformity) in modified_chunks {
-                    let entity = terrain_io.chunk_entity_map.get_option(chunk_coord);
-                    let (vertices, normals, material_ids, indices) = mc_mesh_generation(
-                        &densities,
-                        &materials,
-                        SAMPLES_PER_CHUNK_DIM,
-                        true,
-                        &densities,
-                    );
-                    match uniformity {
-                        Uniformity::Air | Uniformity::Dirt => {
-                            let _ = write_cmd_sender.0.send(WriteCmd::UpdateNonUniform {
-                                densities: Arc::clone(&densities),
-                                materials: Arc::clone(&materials),
-                                chunk_coord,
-                            });
-                            if uniformity == Uniformity::Air {
-                                let _ = write_cmd_sender
-                                    .0
-                                    .send(WriteCmd::RemoveUniformAir { chunk_coord });
-                            } else {
-                                let _ = write_cmd_sender
-                                    .0
-                                    .send(WriteCmd::RemoveUniformDirt { chunk_coord });
-                            }
-                        }
-                        Uniformity::NonUniform => {
-                            let _ = write_cmd_sender.0.send(WriteCmd::UpdateNonUniform {
-                                densities: Arc::clone(&densities),
-                                materials: Arc::clone(&materials),
-                                chunk_coord,
-                            });
+                let modified_chunks = brush_stroke(
+                    world_pos,
+                    *brush,
+                    action,
+                    plane_height,
+                    &terrain_editor.terrain_io.terrain_chunk_map,
+                    &terrain_editor.protected_regions,
+                );
+                terrain_editor.apply(modified_chunks);
+            }
+        }
+    }
+}
+
+//copies the chunks the brush reaches and applies one stroke to them, chunks it left unchanged are dropped
+//uniform chunks come back expanded to non uniform, TerrainEditor::apply turns the ones left without a surface
+//back into uniform air or dirt
+fn brush_stroke(
+    center: Vec3,
+    brush: TerrainBrush,
+    action: BrushAction,
+    plane_height: f32,
+    terrain_chunk_map: &TerrainChunkMap,
+    protected_regions: &ProtectedRegions,
+) -> Vec<ModifiedChunk> {
+    let mut modified_chunks = copy_chunks_in_brush(center, brush, terrain_chunk_map);
+    let snapshot = (brush.shape == BrushShape::Smooth).then(|| StrokeSnapshot {
+        densities: modified_chunks
+            .iter()
+            .map(|(chunk_coord, densities, ..)| (*chunk_coord, Arc::clone(densities)))
+            .collect(),
+    });
+    let reach = brush.strength;
+    modified_chunks.retain_mut(|(chunk_coord, densities, materials, _, _)| {
+        let padded_origin =
+            chunk_coord_to_world_pos(chunk_coord) - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+        let first_sample = chunk_coord_to_first_padded_sample(chunk_coord);
+        let densities = Arc::make_mut(densities);
+        match (brush.shape, action) {
+            //the surface stays where it is, only the solid samples inside the brush are recolored
+            _ if brush.painting => {
+                let mut painted = false;
+                apply_brush(
+                    densities,
+                    padded_origin,
+                    VOXEL_WORLD_SIZE,
+                    center,
+                    brush.radius,
+                    brush.shape,
+                    protected_regions,
+                    |padded, _, current, _| {
+                        if current.is_solid() {
+                            painted |= paint_sample(materials, padded, brush.material, true);
                         }
-                        Uniformity::Unknown => unreachable!(),
+                        current
+                    },
+                );
+                painted
+            }
+            (BrushShape::Flatten, _) => apply_brush(
+                densities,
+                padded_origin,
+                VOXEL_WORLD_SIZE,
+                center,
+                brush.radius,
+                brush.shape,
+                protected_regions,
+                |padded, world, current, falloff| {
+                    let target = SdfValue::from_f32(world.y - plane_height);
+                    let step = current
+                        .strength_to(target)
+                        .clamp(-reach * falloff, reach * falloff);
+                    let flattened = current.saturating_add(step);
+                    if flattened.is_solid() && !current.is_solid() {
+                        paint_sample(materials, padded, brush.material, false);
                     }
-                    let new_mesh = generate_bevy_mesh(vertices, normals, material_ids, indices);
-                    if new_mesh.count_vertices() > 0 {
-                        let collider = Collider::from_bevy_mesh(
-                            &new_mesh,
-                            &ComputedColliderShape::TriMesh(TriMeshFlags::default()),
-                        )
-                        .unwrap();
-                        match entity {
-                            //entity already existed, update it
-                            Some((entity, mesh_handle)) => {
-                                let (mut collider_component, mut mesh) =
-                                    solid_chunk_query.get_mut(*entity).unwrap();
-                                *collider_component = collider;
-                                mesh_handles.remove(mesh_handle);
-                                if let Some(aabb) = new_mesh.compute_aabb() {
-                                    commands.entity(*entity).insert(aabb);
-                                }
-                                let new_mesh_handle = mesh_handles.add(new_mesh);
-                                *mesh = Mesh3d(new_mesh_handle.clone());
-                                terrain_io
-                                    .chunk_entity_map
-                                    .replace_mesh_handle(chunk_coord, new_mesh_handle);
-                            }
-                            //entity did not already exist
-                            None => {
-                                let new_mesh_handle = mesh_handles.add(new_mesh);
-                                let new_entity = commands
-                                    .spawn((
-                                        collider,
-                                        Mesh3d(new_mesh_handle.clone()),
-                                        MeshMaterial3d(material_handle.0.clone()),
-                                        ChunkTag,
-                                        Transform::from_translation(chunk_coord_to_world_pos(
-                                            &chunk_coord,
-                                        )),
-                                    ))
-                                    .id();
-                                terrain_io
-                                    .chunk_entity_map
-                                    .insert(chunk_coord, (new_entity, new_mesh_handle));
-                            }
-                        }
-                    } else {
-                        //no geometry, remove existing entity if it exists
-                        if let Some((entity, mesh_handle)) = entity {
-                            commands.entity(*entity).despawn();
-                            mesh_handles.remove(mesh_handle);
-                            terrain_io.chunk_entity_map.remove(chunk_coord);
+                    flattened
+                },
+            ),
+            (BrushShape::Smooth, _) => {
+                let snapshot = snapshot.as_ref().unwrap();
+                apply_brush(
+                    densities,
+                    padded_origin,
+                    VOXEL_WORLD_SIZE,
+                    center,
+                    brush.radius,
+                    brush.shape,
+                    protected_regions,
+                    |padded, _, current, falloff| {
+                        let sample = first_sample + padded.as_ivec3();
+                        let average = IVec3::AXES
+                            .iter()
+                            .flat_map(|axis| [sample + *axis, sample - *axis])
+                            .map(|neighbor| snapshot.sample(neighbor).unwrap_or(current).to_f32())
+                            .sum::<f32>()
+                            / 6.0;
+                        let t = (reach * falloff).min(1.0);
+                        let smoothed =
+                            SdfValue::from_f32(current.to_f32() + (average - current.to_f32()) * t);
+                        if smoothed.is_solid() && !current.is_solid() {
+                            paint_sample(materials, padded, brush.material, false);
                         }
-                    }
-                    //replace chunks in chunk map
-                    let mut terrain_chunk_map_lock = terrain_io.terrain_chunk_map.0.lock().unwrap();
-                    terrain_chunk_map_lock.insert(
-                        chunk_coord,
-                        TerrainChunk::NonUniformTerrainChunk(NonUniformTerrainChunk {
-                            densities,
-                            materials,
-                        }),
-                    );
-                }
+                        smoothed
+                    },
+                )
             }
+            (_, BrushAction::Dig) => apply_brush(
+                densities,
+                padded_origin,
+                VOXEL_WORLD_SIZE,
+                center,
+                brush.radius,
+                brush.shape,
+                protected_regions,
+                dig_sample(reach),
+            ),
+            (_, BrushAction::Build) => apply_brush(
+                densities,
+                padded_origin,
+                VOXEL_WORLD_SIZE,
+                center,
+                brush.radius,
+                brush.shape,
+                protected_regions,
+                |padded, _, current, falloff| {
+                    let filled = current.saturating_add(-reach * falloff);
+                    if filled.is_solid() && !current.is_solid() {
+                        paint_sample(materials, padded, brush.material, true);
+                    }
+                    filled
+                },
+            ),
+        }
+    });
+    modified_chunks
+}
+
+//removes material, only solid samples are dug so a stroke never pushes air further from the surface
+pub(crate) fn dig_sample(strength: f32) -> impl FnMut(UVec3, Vec3, SdfValue, f32) -> SdfValue {
+    move |_, _, current, falloff| {
+        if current.is_solid() {
+            current.saturating_add(strength * falloff)
+        } else {
+            current
         }
     }
 }
 
-fn dig_sphere(
+//materials have no padding, a sample turned solid in the padding gets its material from the neighbor that owns it
+//samples dug out keep their material, reshaping brushes only paint over what has none
+pub(crate) fn paint_sample(
+    materials: &mut Arc<[MaterialCode]>,
+    padded: UVec3,
+    material: MaterialCode,
+    overwrite: bool,
+) -> bool {
+    let interior = 1..=SAMPLES_PER_CHUNK_DIM as u32;
+    if !(interior.contains(&padded.x)
+        && interior.contains(&padded.y)
+        && interior.contains(&padded.z))
+    {
+        return false;
+    }
+    let index = flatten_index(
+        padded.x - 1,
+        padded.y - 1,
+        padded.z - 1,
+        SAMPLES_PER_CHUNK_DIM,
+    ) as usize;
+    if materials[index] == material || !overwrite && materials[index] != MaterialCode::Air {
+        return false;
+    }
+    Arc::make_mut(materials)[index] = material;
+    true
+}
+
+//the densities of the chunks a stroke reaches as they were before it, read by global sample index
+//every copy of a shared padding sample smooths from the same neighbors this way, so chunk faces stay seamless
+struct StrokeSnapshot {
+    densities: FxHashMap<ChunkCoord, Arc<[i16]>>,
+}
+
+impl StrokeSnapshot {
+    fn sample(&self, sample: IVec3) -> Option<SdfValue> {
+        let (chunk_coord, padded) = sample_to_padded_index(sample);
+        let densities = self.densities.get(&chunk_coord)?;
+        let index = flatten_index(padded.x, padded.y, padded.z, SAMPLES_PER_CHUNK_DIM_PADDED);
+        Some(SdfValue::from_raw(densities[index as usize]))
+    }
+}
+
+//copies of every loaded chunk whose padded box the brush reaches
+fn copy_chunks_in_brush(
     center: Vec3,
-    radius: f32,
-    radius_squared: f32,
-    strength: f32,
-    terrain_chunk_map: &mut TerrainChunkMap,
-) -> Vec<((i16, i16, i16), Arc<[i16]>, Arc<[MaterialCode]>, Uniformity)> {
+    brush: TerrainBrush,
+    terrain_chunk_map: &TerrainChunkMap,
+) -> Vec<ModifiedChunk> {
+    let half_extents = brush.shape.half_extents(brush.radius);
+    copy_chunks_in_box(
+        center - half_extents,
+        center + half_extents,
+        terrain_chunk_map,
+        |node_min, node_max| {
+            brush
+                .shape
+                .reaches(center, brush.radius, node_min, node_max)
+        },
+    )
+}
+
+//copies of every loaded chunk whose padded box overlaps min..max and passes reaches, each is only locked while it is cloned
+//uniform chunks are expanded to full buffers so the edit can be applied to them like any other
+pub(crate) fn copy_chunks_in_box(
+    min: Vec3,
+    max: Vec3,
+    terrain_chunk_map: &TerrainChunkMap,
+    reaches: impl Fn(&Vec3, &Vec3) -> bool,
+) -> Vec<ModifiedChunk> {
     let mut modified_chunks = Vec::new();
-    let min_world = center - Vec3::splat(radius);
-    let max_world = center + Vec3::splat(radius);
-    let min_chunk = world_pos_to_chunk_coord(&min_world);
-    let max_chunk = world_pos_to_chunk_coord(&max_world);
-    let inv_radius_sq = 1.0 / radius_squared;
-    //collect copies of all modified chunks
-    let mut terrain_chunk_map_lock = terrain_chunk_map.0.lock().unwrap();
-    for chunk_x in min_chunk.0..=max_chunk.0 {
-        for chunk_y in min_chunk.1..=max_chunk.1 {
-            for chunk_z in min_chunk.2..=max_chunk.2 {
-                let chunk_coord = (chunk_x, chunk_y, chunk_z);
-                let chunk_center = chunk_coord_to_world_pos(&chunk_coord);
-                let node_min = Vec3::new(
-                    chunk_center.x - HALF_CHUNK,
-                    chunk_center.y - HALF_CHUNK,
-                    chunk_center.z - HALF_CHUNK,
-                );
-                let node_max = node_min + Vec3::splat(CHUNK_WORLD_SIZE);
-                if sphere_intersects_aabb(&center, radius_squared, &node_min, &node_max) {
-                    let terrain_chunk = terrain_chunk_map_lock.get_mut(&chunk_coord).expect(
-                        "During dig, tried to modify a chunk that does not exist in the terrain chunk map!",
-                    );
-                    let (densities, materials, uniformity): (
-                        Arc<[i16]>,
-                        Arc<[MaterialCode]>,
-                        Uniformity,
-                    ) = match terrain_chunk {
-                        TerrainChunk::UniformAir => (
-                            Arc::new([i16::MAX; SAMPLES_PER_CHUNK_PADDED]),
-                            Arc::new([MaterialCode::Air; SAMPLES_PER_CHUNK]),
-                            Uniformity::Air,
-                        ),
-                        TerrainChunk::UniformDirt => (
-                            Arc::new([i16::MIN; SAMPLES_PER_CHUNK_PADDED]),
-                            Arc::new([MaterialCode::Dirt; SAMPLES_PER_CHUNK]),
-                            Uniformity::Dirt,
-                        ),
-                        TerrainChunk::NonUniformTerrainChunk(chunk) => (
-                            Arc::clone(&chunk.densities),
-                            Arc::clone(&chunk.materials),
-                            Uniformity::NonUniform,
-                        ),
-                    };
-                    modified_chunks.push((chunk_coord, densities, materials, uniformity));
-                }
-            }
+    //reach one voxel further so chunks whose padding overlaps the box are collected too
+    let min_chunk = world_pos_to_chunk_coord(&(min - VOXEL_WORLD_SIZE));
+    let max_chunk = world_pos_to_chunk_coord(&(max + VOXEL_WORLD_SIZE));
+    for chunk_coord in chunks_in_box(min_chunk, max_chunk) {
+        let chunk_center = chunk_coord_to_world_pos(&chunk_coord);
+        let node_min = Vec3::new(
+            chunk_center.x - HALF_CHUNK - VOXEL_WORLD_SIZE,
+            chunk_center.y - HALF_CHUNK - VOXEL_WORLD_SIZE,
+            chunk_center.z - HALF_CHUNK - VOXEL_WORLD_SIZE,
+        );
+        let node_max = node_min + Vec3::splat(CHUNK_WORLD_SIZE + 2.0 * VOXEL_WORLD_SIZE);
+        if reaches(&node_min, &node_max) {
+            let Some(terrain_chunk) = terrain_chunk_map.0.touch(&chunk_coord) else {
+                continue; //not loaded yet or evicted, the svo manager loads evicted chunks back before the player gets near
+            };
+            let (densities, materials, uniformity): (Arc<[i16]>, Arc<[MaterialCode]>, Uniformity) =
+                match terrain_chunk {
+                    TerrainChunk::UniformAir => (
+                        Arc::new([SdfValue::AIR.0; SAMPLES_PER_CHUNK_PADDED]),
+                        Arc::new([MaterialCode::Air; SAMPLES_PER_CHUNK]),
+                        Uniformity::Air,
+                    ),
+                    TerrainChunk::UniformDirt => (
+                        Arc::new([SdfValue::SOLID.0; SAMPLES_PER_CHUNK_PADDED]),
+                        Arc::new([MaterialCode::Dirt; SAMPLES_PER_CHUNK]),
+                        Uniformity::Dirt,
+                    ),
+                    TerrainChunk::NonUniformTerrainChunk(chunk) => (
+                        Arc::clone(&chunk.densities),
+                        Arc::clone(&chunk.materials),
+                        Uniformity::NonUniform,
+                    ),
+                };
+            let padded_origin = chunk_center - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+            let dirty = box_dirty_range(padded_origin, min, max);
+            modified_chunks.push((chunk_coord, densities, materials, uniformity, dirty));
         }
     }
-    drop(terrain_chunk_map_lock);
-    modified_chunks.retain_mut(|(chunk_coord, densities, _, _)| {
-        let dens_mut: &mut [i16] = Arc::make_mut(densities);
-        modify_chunk_voxels(
-            dens_mut,
-            chunk_coord,
-            center,
-            radius_squared,
-            strength,
-            inv_radius_sq,
-        )
-    });
     modified_chunks
 }
 
-//syncing the neighboring paddings is not necessary because definitionally if padding is touched so were the non padded neighboring densities which get remeshed anyway.
-fn modify_chunk_voxels(
+//padded samples of the chunk at padded_origin that the box covers
+fn box_dirty_range(padded_origin: Vec3, min: Vec3, max: Vec3) -> DirtyRange {
+    let last = (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32;
+    let min = ((min - padded_origin) / VOXEL_WORLD_SIZE)
+        .floor()
+        .clamp(Vec3::ZERO, Vec3::splat(last));
+    let max = ((max - padded_origin) / VOXEL_WORLD_SIZE)
+        .ceil()
+        .clamp(Vec3::ZERO, Vec3::splat(last));
+    DirtyRange {
+        min: (min.x as usize, min.y as usize, min.z as usize),
+        max: (max.x as usize, max.y as usize, max.z as usize),
+    }
+}
+
+//the padding is the apron the mesher takes normals from, it has to stay equal to the neighbor's border samples
+//otherwise the gradient on each side of a chunk face differs and lighting seams there
+//brush_stroke collects every chunk whose padded box the brush reaches so both copies are always edited together
+//padded_origin is the world position of the first padding sample, voxel_size is smaller for fine zones
+//edit gets each sample inside the brush with its padded index, world position and falloff and returns its new value
+//samples inside a protected region are never passed to it
+pub(crate) fn apply_brush(
     densities: &mut [i16],
-    chunk_coord: &(i16, i16, i16),
-    dig_center: Vec3,
-    radius_squared: f32,
-    strength: f32,
-    inv_radius_sq: f32,
+    padded_origin: Vec3,
+    voxel_size: f32,
+    center: Vec3,
+    radius: f32,
+    shape: BrushShape,
+    protected_regions: &ProtectedRegions,
+    mut edit: impl FnMut(UVec3, Vec3, SdfValue, f32) -> SdfValue,
 ) -> bool {
-    let chunk_center = chunk_coord_to_world_pos(&chunk_coord);
-    let padded_origin = Vec3::new(
-        chunk_center.x - HALF_CHUNK - VOXEL_WORLD_SIZE,
-        chunk_center.y - HALF_CHUNK - VOXEL_WORLD_SIZE,
-        chunk_center.z - HALF_CHUNK - VOXEL_WORLD_SIZE,
-    );
     let mut chunk_modified = false;
     for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
-        let world_z = padded_origin.z + z as f32 * VOXEL_WORLD_SIZE;
+        let world_z = padded_origin.z + z as f32 * voxel_size;
         for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
-            let world_y = padded_origin.y + y as f32 * VOXEL_WORLD_SIZE;
+            let world_y = padded_origin.y + y as f32 * voxel_size;
             for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
-                let world_x = padded_origin.x + x as f32 * VOXEL_WORLD_SIZE;
+                let world_x = padded_origin.x + x as f32 * voxel_size;
                 let voxel_world_pos = Vec3::new(world_x, world_y, world_z);
-                let distance_squared = voxel_world_pos.distance_squared(dig_center);
-                if distance_squared <= radius_squared {
-                    let falloff = 1.0 - distance_squared * inv_radius_sq;
-                    let dig_amount = strength * falloff;
-                    let flat_index =
-                        flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
-                    let current_density = &mut densities[flat_index as usize];
-                    if *current_density < 0 {
-                        let sdf_f32 = dequantize_i16_to_f32(*current_density);
-                        let new_sdf = (sdf_f32 + dig_amount).clamp(-10.0, 10.0);
-                        *current_density = quantize_f32_to_i16(new_sdf);
-                        chunk_modified = true;
-                    }
+                let falloff = shape.falloff(center, radius, voxel_world_pos);
+                if falloff <= 0.0 || protected_regions.contains(voxel_world_pos) {
+                    continue;
                 }
+                let flat_index =
+                    flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
+                let current_density = &mut densities[flat_index as usize];
+                let current = SdfValue::from_raw(*current_density);
+                let edited = edit(
+                    UVec3::new(x as u32, y as u32, z as u32),
+                    voxel_world_pos,
+                    current,
+                    falloff,
+                );
+                if edited != current {
+                    *current_density = edited.0;
+                    chunk_modified = true;
+                }
+            }
+        }
+    }
+    chunk_modified
+}
+
+//`brush [sphere|cube|capsule|flatten|smooth] [paint|sculpt] [dirt|grass|sand|path|snow] [radius] [strength]`
+//the numbers are taken in that order, anything left out keeps its current value
+pub fn brush_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    mut terrain_brush: ResMut<TerrainBrush>,
+) {
+    for command in command_reader.read() {
+        if command.name != "brush" {
+            continue;
+        }
+        let mut brush = *terrain_brush;
+        let mut numbers = 0;
+        let mut valid = true;
+        for arg in &command.args {
+            match arg.as_str() {
+                "sphere" => brush.shape = BrushShape::Sphere,
+                "cube" => brush.shape = BrushShape::Cube,
+                "capsule" => brush.shape = BrushShape::Capsule,
+                "flatten" => brush.shape = BrushShape::Flatten,
+                "smooth" => brush.shape = BrushShape::Smooth,
+                "paint" => brush.painting = true,
+                "sculpt" => brush.painting = false,
+                "dirt" => brush.material = MaterialCode::Dirt,
+                "grass" => brush.material = MaterialCode::Grass,
+                "sand" => brush.material = MaterialCode::Sand,
+                "path" => brush.material = MaterialCode::Path,
+                "snow" => brush.material = MaterialCode::Snow,
+                number => match (numbers, number.parse::<f32>()) {
+                    (0, Ok(radius)) if (MIN_BRUSH_RADIUS..=MAX_BRUSH_RADIUS).contains(&radius) => {
+                        brush.radius = radius;
+                        numbers += 1;
+                    }
+                    (1, Ok(strength))
+                        if (MIN_BRUSH_STRENGTH..=MAX_BRUSH_STRENGTH).contains(&strength) =>
+                    {
+                        brush.strength = strength;
+                        numbers += 1;
+                    }
+                    _ => valid = false,
+                },
             }
         }
+        if !valid {
+            console.print(format!(
+                "usage: brush [sphere|cube|capsule|flatten|smooth] [paint|sculpt] [dirt|grass|sand|path|snow] \
+                 [radius {MIN_BRUSH_RADIUS}-{MAX_BRUSH_RADIUS}] [strength {MIN_BRUSH_STRENGTH}-{MAX_BRUSH_STRENGTH}]"
+            ));
+            continue;
+        }
+        *terrain_brush = brush;
+        console.print(format!(
+            "{:?} brush {} {:?}, radius {:.2} strength {:.2}",
+            brush.shape,
+            if brush.painting { "painting" } else { "of" },
+            brush.material,
+            brush.radius,
+            brush.strength
+        ));
     }
-    return chunk_modified;
 }
 
-fn screen_to_world_ray(
+pub(crate) fn screen_to_world_ray(
     cursor_pos: Vec2,
     camera: &Camera,
     camera_transform: &GlobalTransform,
     terrain_chunk_map: &TerrainChunkMap,
+    terrain_svo: &TerrainSvo,
 ) -> Option<Vec3> {
     let ray = camera
         .viewport_to_world(camera_transform, cursor_pos)
@@ -307,16 +899,29 @@ fn screen_to_world_ray(
     let ray_origin = ray.origin;
     let max_distance = 8.0;
     let step_size = 0.05;
-    let mut distance_traveled = 0.0;
+    //chunks before the first one with a surface cant stop the ray, it starts stepping from that one
+    let (_, skipped) = terrain_svo
+        .0
+        .read()
+        .raycast(ray_origin, *ray.direction, max_distance)?;
+    let mut distance_traveled = (skipped / step_size).floor() * step_size;
+    //the ray crosses a handful of chunks in 160 steps, so each is fetched once when the ray enters it
+    let mut fetched_coord = None;
+    let mut current_chunk = None;
     while distance_traveled < max_distance {
         let current_pos = ray_origin + ray.direction * distance_traveled;
+        distance_traveled += step_size;
         let chunk_coord = world_pos_to_chunk_coord(&current_pos);
-        if let Some(chunk_data) = terrain_chunk_map.0.lock().unwrap().get(&chunk_coord) {
+        if fetched_coord != Some(chunk_coord) {
+            current_chunk = terrain_chunk_map.0.get(&chunk_coord);
+            fetched_coord = Some(chunk_coord);
+        }
+        //a chunk that isnt loaded is passed through rather than stopping the ray
+        if let Some(chunk_data) = &current_chunk {
             let voxel_idx = world_pos_to_voxel_index(&current_pos, &chunk_coord);
             if chunk_data.is_solid(voxel_idx.0, voxel_idx.1, voxel_idx.2) {
                 return Some(current_pos);
             }
-            distance_traveled += step_size;
         }
     }
     None
@@ -1,37 +1,78 @@
+use crate::chunk_coord::ChunkCoord;
 use crate::constants::SAMPLES_PER_CHUNK_PADDED;
 use crate::conversions::{chunk_coord_to_cluster_coord, cluster_coord_to_world_center};
+use crate::deformable_terrain::biomes::Biome;
+use crate::deformable_terrain::chunk_budget::{ChunkPager, TerrainMemoryBudget};
 use crate::deformable_terrain::chunk_entity_map::ChunkEntityMap;
+use crate::deformable_terrain::chunk_events::ChunkEventWriters;
 use crate::deformable_terrain::chunk_generator::{
-    MaterialCode, calculate_chunk_start, chunk_contains_surface, compute_heightmap_gradients,
-    downscale, fast_get_uniformity, generate_chunk_into_buffers, generate_noise_height_samples,
-    generate_terrain_heights, get_fbm, padded_chunk_contains_surface,
+    CAVE_LATTICE_LEN, MaterialCode, calculate_chunk_start, chunk_contains_surface,
+    chunk_uniformity, downscale, get_fbm, padded_chunk_contains_surface,
 };
+use crate::deformable_terrain::chunk_history::ChunkModifiedTimes;
+use crate::deformable_terrain::chunk_pool::ChunkBufferPool;
+use crate::deformable_terrain::chunk_store::ChunkStore;
+use crate::deformable_terrain::chunk_summary::{ChunkSummaries, compute_chunk_summary};
+use crate::deformable_terrain::cluster_occupancy::SharedClusterOccupancy;
+use crate::deformable_terrain::collider_cache::cached_chunk_collider;
+use crate::deformable_terrain::collider_culling::StashedCollider;
 use crate::deformable_terrain::column_range_map::ColumnRangeMap;
+use crate::deformable_terrain::density_source::{DensitySource, NoiseTerrain};
 #[cfg(feature = "debug")]
 use crate::deformable_terrain::driver_debug_ui::{
-    CHUNK_SPAWN_RECEIVER_QUEUE_SIZE, CLUSTERS_PROCESSED, INTERNAL_QUEUE_SIZES,
+    CHUNK_SPAWN_RECEIVER_QUEUE_SIZE, CLUSTERS_PROCESSED, EMPTY_MESHES_SPAWNED,
+    EMPTY_MESHES_SUPPRESSED, INTERNAL_QUEUE_SIZES,
 };
 use crate::deformable_terrain::file_loader::{
-    CHUNK_SERIALIZED_SIZE, get_project_root, load_chunk, load_chunk_index_map, load_uniform_chunks,
-    remove_uniform_chunk, update_chunk, write_chunk, write_uniform_chunk,
+    CHUNK_CODEC, ChunkCodec, RegionFiles, RegionMaps, compact_region_files, load_chunk,
+    load_chunk_index_map, load_uniform_chunks, patch_chunk, recompress_chunk, remove_chunk,
+    remove_uniform_chunk, removed_as, replace_file_contents, unix_millis_now, update_chunk,
+    write_chunk, write_uniform_chunk,
 };
-use crate::deformable_terrain::marching_cubes::mc::mc_mesh_generation;
-use crate::deformable_terrain::plugin::{ChunkTag, MoveableCenter, Uniformity};
-use crate::deformable_terrain::sparse_voxel_octree::SvoNode;
+use crate::deformable_terrain::heightmap_cache::HeightmapCache;
+use crate::deformable_terrain::horizon::HorizonCuller;
+use crate::deformable_terrain::load_history::{ChunkLoadEvent, ChunkLoadHistory, LoadStage};
+use crate::deformable_terrain::lod_fade::LodFadeMaterials;
+use crate::deformable_terrain::marching_cubes::geomorph::coarse_positions;
+use crate::deformable_terrain::marching_cubes::greedy_cubes::greedy_cubes_mesh_generation;
+use crate::deformable_terrain::marching_cubes::mc::{
+    DensityField, MaterialResolution, MeshingScratch, mc_mesh_generation_into,
+};
+use crate::deformable_terrain::marching_cubes::simplify::{MeshSimplification, simplify_mesh};
+use crate::deformable_terrain::marching_cubes::surface_nets::surface_nets_mesh_generation;
+use crate::deformable_terrain::marching_cubes::transvoxel::{
+    TransitionMeshInput, mc_mesh_generation_with_transitions,
+};
+use crate::deformable_terrain::merged_clusters::{
+    MergedClusterMap, MergedClusterMesh, MergedClusterTag,
+};
+use crate::deformable_terrain::migrate::{StorageFormat, migrate_world};
+use crate::deformable_terrain::observers::{
+    TerrainObserver, TerrainObservers, nearest_distance_squared, observers_changed,
+};
+use crate::deformable_terrain::plugin::{
+    ChunkTag, MeshingMode, MoveableCenter, StreamingConfig, Uniformity,
+};
+use crate::deformable_terrain::sparse_voxel_octree::{ClusterVisitMask, Svo};
+use crate::deformable_terrain::storage_paths::StoragePaths;
 use crate::deformable_terrain::terrain::{
-    NonUniformTerrainChunk, TerrainChunk, TerrainMaterialHandle, generate_bevy_mesh,
+    NonUniformTerrainChunk, PreparedMesh, TerrainChunk, TerrainMaterialHandle, prepare_bevy_mesh,
+    with_coarse_positions,
 };
+use crate::deformable_terrain::terrain_error::TerrainError;
+use crate::deformable_terrain::world_gen::{WorldGenConfig, world_seed};
+use crate::deformable_terrain::write_journal::{WriteJournal, replay_write_journal};
 
 use crate::{
     constants::{
         CHUNKS_PER_CLUSTER, CHUNKS_PER_CLUSTER_DIM, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_2D_PADDED,
-        SAMPLES_PER_CHUNK_DIM, SIMULATION_RADIUS_SQUARED,
+        SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED, SIMULATION_RADIUS_SQUARED,
     },
     conversions::cluster_coord_to_min_chunk_coord,
 };
-use bevy::{camera::primitives::MeshAabb, prelude::*};
+use bevy::prelude::*;
 use bevy_rapier3d::prelude::{Collider, ComputedColliderShape, TriMeshFlags};
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::{Receiver, Select, Sender, TryRecvError, unbounded};
 use fastnoise2::{SafeNode, generator::GeneratorWrapper};
 use parking_lot::RwLock;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -39,7 +80,8 @@ use std::cmp::Ordering::Equal;
 use std::sync::atomic::{AtomicU32, AtomicUsize};
 use std::{
     collections::{BinaryHeap, VecDeque},
-    fs::{File, OpenOptions},
+    fs::File,
+    path::Path,
     sync::{
         Arc, Condvar, Mutex,
         atomic::{AtomicBool, Ordering},
@@ -60,13 +102,36 @@ pub(crate) const RF2_SAMPLES_PER_CHUNK_DIM: usize = SAMPLES_PER_CHUNK_DIM / RF2;
 pub(crate) const RF3_SAMPLES_PER_CHUNK_DIM: usize = SAMPLES_PER_CHUNK_DIM / RF3;
 pub(crate) const RF4_SAMPLES_PER_CHUNK_DIM: usize = SAMPLES_PER_CHUNK_DIM / RF4;
 pub(crate) const RF5_SAMPLES_PER_CHUNK_DIM: usize = SAMPLES_PER_CHUNK_DIM / RF5;
+const SURFACE_NETS_MAX_SAMPLES_PER_CHUNK_DIM: usize = RF3_SAMPLES_PER_CHUNK_DIM; //lod3 and coarser mesh with surface nets
 const PRIORITY_QUEUE_MAX_SIZE: usize = 10000;
-const INTERNAL_WORKER_QUEUE_SIZE: usize = 64;
+const CHUNK_IO_THREADS: usize = 2; //reads are mostly waiting on the disk, a couple keep the compute threads fed
+const LOADED_CLUSTERS_PER_COMPUTE_THREAD: usize = 2; //read ahead, small so the priority queue still decides what loads next
+const SVO_CENTER_MOVE_THRESHOLD: f32 = 2.0; //world units the center must move before the svo manager redoes a pass on its own
+const SVO_IDLE_POLL_INTERVAL: Duration = Duration::from_millis(16); //the center is a plain mutex so movement is polled
+const REQUEST_AGING_RATE: f32 = 16.0; //world units of distance a queued request makes up per second it waits
+const PREFETCH_LOOKAHEAD: f32 = 1.5; //seconds of travel the load order leads the center by
+const TRAVEL_SMOOTHING_SECS: f32 = 0.5; //how long a change of direction takes to move the prefetch
+const MAX_TRAVEL_SPEED: f32 = 150.0; //world units per second, anything faster between passes is a teleport
+const VIEW_CONE_COS: f32 = 0.5; //cosine of the half angle of the cone around the view direction that loads first
+const VIEW_PRIORITY_SCALE: f32 = 0.5; //squared distance multiplier in the view cone, clusters in view load as if ~0.7 as far
+const COMPACT_WASTE_RATIO: f32 = 0.25; //regions with more of their file unused than this are packed at startup
+const WRITE_ATTEMPTS: u32 = 3; //tries per write command before it is left to the write journal
+const WRITE_RETRY_DELAY: Duration = Duration::from_millis(50); //doubled after every failed attempt
+const READ_ATTEMPTS: u32 = 3; //tries per read before the error is handed back, the retries wait like writes do
+const FAILED_READ_RETRY_SECS: f32 = 5.0; //a cluster whose chunks could not be read is requested again after this
 
 //I dont like this but, block player movement until first chunk load happens
 pub static INITIAL_CHUNKS_LOADED: AtomicBool = AtomicBool::new(false);
 pub static QUEUE_SIZE: AtomicUsize = AtomicUsize::new(0);
+pub static INITIAL_LOAD_TOTAL: AtomicUsize = AtomicUsize::new(0); //chunks in the simulation radius at startup, 0 until the svo manager requested them
+pub static INITIAL_LOAD_LOADED: AtomicUsize = AtomicUsize::new(0); //of those, chunks whose cluster came back from a loader
 pub static RENDER_RADIUS_SQUARED: AtomicU32 = AtomicU32::new(0);
+pub static VERTICAL_RENDER_RADIUS_SQUARED: AtomicU32 = AtomicU32::new(0); //f32 bits like the render radius, 0 keeps the render distance a sphere
+pub static MESH_SIMPLIFICATION: RwLock<Option<MeshSimplification>> = RwLock::new(None); //lod meshes only, None skips the pass
+pub static LOD_MORPHING: AtomicBool = AtomicBool::new(false); //set by the plugin, marching cubes meshes carry coarse positions for the shader
+pub static MESHING_MODE: RwLock<MeshingMode> = RwLock::new(MeshingMode::MarchingCubes); //set by the plugin before any chunk is meshed
+pub static MEMORY_BUDGET: RwLock<TerrainMemoryBudget> = RwLock::new(TerrainMemoryBudget::Unlimited); //the svo manager evicts down to it every pass
+pub static STREAMING_CONFIG: RwLock<StreamingConfig> = RwLock::new(StreamingConfig::DEFAULT); //copied from the resource, the svo manager does a pass when it changes
 
 #[repr(u8)]
 pub enum FullLodMode {
@@ -77,8 +142,8 @@ pub enum FullLodMode {
 
 #[repr(u8)]
 pub(crate) enum TerrainChunkMapModification {
-    Insert((i16, i16, i16), TerrainChunk),
-    Remove((i16, i16, i16)),
+    Insert(ChunkCoord, TerrainChunk),
+    Remove(ChunkCoord),
 }
 
 pub struct ChunkBuffers {
@@ -87,12 +152,18 @@ pub struct ChunkBuffers {
     pub heightmap: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
     pub dhdx: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
     pub dhdz: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
+    pub path_mask: [bool; SAMPLES_PER_CHUNK_2D_PADDED], //columns covered by a road surface
+    pub biome: [Biome; SAMPLES_PER_CHUNK_2D_PADDED],    //filled by fill_voxel_densities
+    pub water_level: [f32; SAMPLES_PER_CHUNK_2D_PADDED], //river and lake surface per column, -inf when dry
+    pub cave: [f32; CAVE_LATTICE_LEN], //coarse cave field, scratch for carve_caves
 }
 
 impl ChunkBuffers {
     pub fn new() -> Box<Self> {
         //boxed at the struct level for better cache locality
-        unsafe { Box::new_zeroed().assume_init() } //unsafe to avoid stack overflow on debug builds 
+        let mut buffers: Box<Self> = unsafe { Box::new_zeroed().assume_init() }; //unsafe to avoid stack overflow on debug builds
+        buffers.water_level.fill(f32::NEG_INFINITY); //dry until apply_hydrology says otherwise
+        buffers
     }
 }
 
@@ -114,20 +185,103 @@ impl LodBuffers {
         //boxed at the struct level for better cache locality
         unsafe { Box::new_zeroed().assume_init() } //unsafe to avoid stack overflow on debug builds 
     }
+
+    //the buffers a transition to a reduced lod meshes from and their samples per dim, None for the full res transitions
+    fn reduced(
+        &mut self,
+        transition: LoadStateTransition,
+    ) -> Option<(&mut [i16], &mut [MaterialCode], usize)> {
+        match transition {
+            LoadStateTransition::ToLod1 => Some((
+                &mut self.density_r1,
+                &mut self.material_r1,
+                RF1_SAMPLES_PER_CHUNK_DIM,
+            )),
+            LoadStateTransition::ToLod2 => Some((
+                &mut self.density_r2,
+                &mut self.material_r2,
+                RF2_SAMPLES_PER_CHUNK_DIM,
+            )),
+            LoadStateTransition::ToLod3 => Some((
+                &mut self.density_r3,
+                &mut self.material_r3,
+                RF3_SAMPLES_PER_CHUNK_DIM,
+            )),
+            LoadStateTransition::ToLod4 => Some((
+                &mut self.density_r4,
+                &mut self.material_r4,
+                RF4_SAMPLES_PER_CHUNK_DIM,
+            )),
+            LoadStateTransition::ToLod5 | LoadStateTransition::ToMerged => Some((
+                &mut self.density_r5,
+                &mut self.material_r5,
+                RF5_SAMPLES_PER_CHUNK_DIM,
+            )),
+            LoadStateTransition::ToFull
+            | LoadStateTransition::ToFullWithCollider
+            | LoadStateTransition::NoChangeAddCollider => None,
+        }
+    }
 }
 
 #[derive(Resource)]
 pub struct FrameStart(pub Instant);
 
+//the startup load of everything in the simulation radius around the spawn, in chunks
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct InitialLoadProgress {
+    pub loaded: usize,
+    pub total: usize,   //0 until the requests are made
+    pub complete: bool, //every chunk is in the chunk map and spawned with its collider
+}
+
+impl InitialLoadProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.complete {
+            1.0
+        } else if self.total == 0 {
+            0.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+//written once, the frame the initial load completes
+#[derive(Message, Clone, Copy, Debug)]
+pub struct InitialLoadComplete;
+
 #[derive(Resource)]
 pub struct LogicalProcesors(pub usize);
 
 #[derive(Resource)]
 pub struct NoiseGenerator(pub GeneratorWrapper<SafeNode>);
 
-//stores the data for all chunks in Z0 radius on the bevy thread. Chunk loader can write to the mutex and bevy can modify it for digging operations.
+//stores the data for all chunks in Z0 radius. The svo manager fills it from the loaders and bevy modifies it for digging operations.
+//chunks away from the center may be evicted to stay under MEMORY_BUDGET, they come back before the center gets close
 #[derive(Resource)]
-pub struct TerrainChunkMap(pub(crate) Arc<Mutex<FxHashMap<(i16, i16, i16), TerrainChunk>>>);
+pub struct TerrainChunkMap(pub(crate) Arc<ChunkStore>);
+
+//the svo manager's record of every loaded cluster, shared so rays can skip the empty space in it
+//the manager takes the write lock only to apply results and drop clusters, its passes over the tree only read
+#[derive(Resource, Clone)]
+pub struct TerrainSvo(pub(crate) Arc<RwLock<Svo>>);
+
+impl TerrainSvo {
+    //chunks dug at or after frame since in the clusters from min to max inclusive, with the frame of their last dig
+    pub fn chunks_edited_since(
+        &self,
+        since: u32,
+        min_cluster: (i16, i16, i16),
+        max_cluster: (i16, i16, i16),
+    ) -> Vec<(ChunkCoord, u32)> {
+        let mut edited = Vec::new();
+        self.0
+            .read()
+            .chunks_edited_since(since, min_cluster, max_cluster, &mut edited);
+        edited
+    }
+}
 
 #[repr(u8)]
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -140,6 +294,7 @@ pub enum LoadStateTransition {
     ToLod5,              //transition to Lod5 with no collider
     ToFullWithCollider,  //transition to full LOD and collider is needed
     NoChangeAddCollider, //LOD did not change but collider is needed
+    ToMerged,            //transition to one mesh for the whole cluster at Lod5 resolution
 }
 
 impl LoadStateTransition {
@@ -153,6 +308,7 @@ impl LoadStateTransition {
             LoadStateTransition::ToLod5 => LoadState::Lod5,
             LoadStateTransition::NoChangeAddCollider => LoadState::FullWithCollider,
             LoadStateTransition::ToFullWithCollider => LoadState::FullWithCollider,
+            LoadStateTransition::ToMerged => LoadState::Merged,
         }
     }
 }
@@ -167,49 +323,176 @@ pub enum LoadState {
     Lod3,
     Lod4,
     Lod5,
+    Merged, //no chunk has an entity, the cluster has one
+}
+
+impl LoadState {
+    //samples per chunk dim the cluster's chunks are meshed at, merged clusters are meshed at lod5
+    pub(crate) fn samples_per_chunk_dim(&self) -> usize {
+        match self {
+            LoadState::FullWithCollider | LoadState::Full => SAMPLES_PER_CHUNK_DIM,
+            LoadState::Lod1 => RF1_SAMPLES_PER_CHUNK_DIM,
+            LoadState::Lod2 => RF2_SAMPLES_PER_CHUNK_DIM,
+            LoadState::Lod3 => RF3_SAMPLES_PER_CHUNK_DIM,
+            LoadState::Lod4 => RF4_SAMPLES_PER_CHUNK_DIM,
+            LoadState::Lod5 | LoadState::Merged => RF5_SAMPLES_PER_CHUNK_DIM,
+        }
+    }
 }
 
 pub enum ChunkSpawnResult {
-    ToSpawn(((i16, i16, i16), Mesh)), //when a chunk is spawned without a collider
-    ToSpawnWithCollider(((i16, i16, i16), Collider, Mesh)), //when a chunk is spawned with a collider
-    ToDespawn((i16, i16, i16)),
-    ToGiveCollider(((i16, i16, i16), Collider)), //same lod but now needs a collider
-    ToChangeLod(((i16, i16, i16), Mesh)), //change mesh, assume it has no collider and doesnt need one
-    ToChangeLodAddCollider(((i16, i16, i16), Mesh, Collider)), //when its both changing LOD and now needs a collider
-    ToChangeLodRemoveCollider(((i16, i16, i16), Mesh)), //had collider and becoming lod therefor no longer needs collider
-    ToRemoveCollider((i16, i16, i16)), //was full, still full except no longer needs collider
+    ToSpawn((ChunkCoord, PreparedMesh)), //when a chunk is spawned without a collider
+    ToSpawnWithCollider((ChunkCoord, Collider, PreparedMesh)), //when a chunk is spawned with a collider
+    ToDespawn(ChunkCoord),
+    ToGiveCollider((ChunkCoord, Collider)), //same lod but now needs a collider
+    ToChangeLod((ChunkCoord, PreparedMesh)), //change mesh, assume it has no collider and doesnt need one
+    ToChangeLodAddCollider((ChunkCoord, PreparedMesh, Collider)), //when its both changing LOD and now needs a collider
+    ToChangeLodRemoveCollider((ChunkCoord, PreparedMesh)), //had collider and becoming lod therefor no longer needs collider
+    ToRemoveCollider(ChunkCoord), //was full, still full except no longer needs collider
+    ToSpawnMerged(((i16, i16, i16), PreparedMesh)), //keyed by cluster coord, spawns the merged entity or replaces its mesh
+    ToDespawnMerged((i16, i16, i16)),               //keyed by cluster coord
+    InitialLoadDone, //sent after the last startup cluster, everything of the initial load before it in the channel
+}
+
+impl ChunkSpawnResult {
+    #[cfg(feature = "debug")]
+    fn prepared_mesh(&self) -> Option<&PreparedMesh> {
+        match self {
+            ChunkSpawnResult::ToSpawn((_, prepared))
+            | ChunkSpawnResult::ToSpawnWithCollider((_, _, prepared))
+            | ChunkSpawnResult::ToChangeLod((_, prepared))
+            | ChunkSpawnResult::ToChangeLodAddCollider((_, prepared, _))
+            | ChunkSpawnResult::ToChangeLodRemoveCollider((_, prepared))
+            | ChunkSpawnResult::ToSpawnMerged((_, prepared)) => Some(prepared),
+            ChunkSpawnResult::ToDespawn(_)
+            | ChunkSpawnResult::ToGiveCollider(_)
+            | ChunkSpawnResult::ToRemoveCollider(_)
+            | ChunkSpawnResult::ToDespawnMerged(_)
+            | ChunkSpawnResult::InitialLoadDone => None,
+        }
+    }
 }
 
 pub enum WriteCmd {
     UpdateNonUniform {
         densities: Arc<[i16]>,
         materials: Arc<[MaterialCode]>,
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
+    },
+    //an edit to a chunk that is already stored, only the samples inside dirty are rewritten when it can be patched
+    PatchNonUniform {
+        densities: Arc<[i16]>,
+        materials: Arc<[MaterialCode]>,
+        chunk_coord: ChunkCoord,
+        dirty: DirtyRange,
     },
     WriteUniformAir {
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
     },
     WriteUniformDirt {
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
     },
     RemoveUniformAir {
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
     },
     RemoveUniformDirt {
-        chunk_coord: (i16, i16, i16),
+        chunk_coord: ChunkCoord,
+    },
+    //a small file kept next to the chunks, rewritten whole and synced here so a slow disk never holds up the frame
+    //name is only for the warning when it fails, a failed write is not retried, the next save brings it up to date
+    ReplaceFile {
+        name: &'static str,
+        file: Arc<File>,
+        contents: Vec<u8>,
+    },
+    //everything sent before this is on the disk once done is signalled, sent by autosave and on exit
+    Sync {
+        done: Option<Sender<()>>,
     },
 }
 
+impl WriteCmd {
+    //None for Sync
+    pub fn chunk_coord(&self) -> Option<ChunkCoord> {
+        match self {
+            WriteCmd::UpdateNonUniform { chunk_coord, .. }
+            | WriteCmd::PatchNonUniform { chunk_coord, .. }
+            | WriteCmd::WriteUniformAir { chunk_coord }
+            | WriteCmd::WriteUniformDirt { chunk_coord }
+            | WriteCmd::RemoveUniformAir { chunk_coord }
+            | WriteCmd::RemoveUniformDirt { chunk_coord } => Some(*chunk_coord),
+            WriteCmd::ReplaceFile { .. } | WriteCmd::Sync { .. } => None,
+        }
+    }
+}
+
+//an edited chunk for the loader threads to mesh, they take these ahead of the next cluster
+pub struct RemeshRequest {
+    pub chunk_coord: ChunkCoord,
+    pub densities: Arc<[i16]>,
+    pub materials: Arc<[MaterialCode]>,
+}
+
+pub struct RemeshResult {
+    pub(crate) chunk_coord: ChunkCoord,
+    pub(crate) mesh: Option<(PreparedMesh, Option<Collider>)>, //None when the edit left no surface
+}
+
+//trimesh collider for a chunk mesh, meshes are never empty by here but rapier can still refuse one
+pub(crate) fn trimesh_collider(
+    chunk_coord: ChunkCoord,
+    mesh: &Mesh,
+) -> Result<Collider, TerrainError> {
+    Collider::from_bevy_mesh(
+        mesh,
+        &ComputedColliderShape::TriMesh(TriMeshFlags::default()),
+    )
+    .ok_or(TerrainError::Collider(chunk_coord))
+}
+
+//padded sample bounds an edit may have touched, inclusive on both ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirtyRange {
+    pub min: (usize, usize, usize),
+    pub max: (usize, usize, usize),
+}
+
+impl DirtyRange {
+    //offsets to the face neighbors that also hold samples inside the range
+    //the last three padded layers on each side are the first three of the neighbor's, padding included
+    pub fn touched_faces(&self) -> Vec<(i16, i16, i16)> {
+        const SHARED_LAYERS: usize = 3;
+        let min = [self.min.0, self.min.1, self.min.2];
+        let max = [self.max.0, self.max.1, self.max.2];
+        let mut faces = Vec::new();
+        for (axis, (min, max)) in min.into_iter().zip(max).enumerate() {
+            let mut offset = [0i16; 3];
+            if min < SHARED_LAYERS {
+                offset[axis] = -1;
+                faces.push((offset[0], offset[1], offset[2]));
+            }
+            if max >= SAMPLES_PER_CHUNK_DIM_PADDED - SHARED_LAYERS {
+                offset[axis] = 1;
+                faces.push((offset[0], offset[1], offset[2]));
+            }
+        }
+        faces
+    }
+}
+
 #[derive(Resource)]
 pub struct ChunkSpawnReciever(Receiver<ChunkSpawnResult>);
 
 #[derive(Debug)]
 pub struct ClusterRequest {
     pub position: (i16, i16, i16),
-    pub distance_squared: f32, //distance to cluster center in world units
+    pub distance_squared: f32, //distance to cluster center in world units, shortened ahead of travel, only orders loads
     pub load_state_transition: LoadStateTransition,
     pub prev_has_entity: Option<[bool; CHUNKS_PER_CLUSTER]>,
     pub prev_in_simulation_radius: bool, //if in sim radius and had entity, it also had a collider
+    pub prev_merged: bool, //the cluster has a merged entity that goes once its chunks are meshed on their own
+    pub queued_secs: f32,  //svo manager clock when pushed to the queue, stamped on push
+    pub neighbor_samples_per_chunk_dim: [usize; 6], //resolution of the face neighbor clusters, -x, +x, -y, +y, -z, +z
 }
 
 impl PartialEq for ClusterRequest {
@@ -222,11 +505,13 @@ impl PartialEq for ClusterRequest {
 
 impl Eq for ClusterRequest {}
 
+//nearest first, but every request pushed later is pushed back by how long the earlier one has waited
+//the key only depends on fields fixed at push time so the heap stays valid while requests age
 impl Ord for ClusterRequest {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         other
-            .distance_squared
-            .partial_cmp(&self.distance_squared)
+            .aged_priority()
+            .partial_cmp(&self.aged_priority())
             .unwrap_or(std::cmp::Ordering::Equal)
     }
 }
@@ -241,25 +526,126 @@ impl ClusterRequest {
     fn had_entity(&self, idx: usize) -> bool {
         self.prev_has_entity.map_or(false, |a| a[idx])
     }
+
+    //resolution across each face of a chunk, the neighbor cluster's on the cluster boundary and the cluster's own inside it
+    fn chunk_neighbor_samples_per_chunk_dim(&self, chunk_coord: ChunkCoord) -> [usize; 6] {
+        let own = self
+            .load_state_transition
+            .to_state()
+            .samples_per_chunk_dim();
+        let min_chunk = cluster_coord_to_min_chunk_coord(self.position);
+        let local = [
+            chunk_coord.0 - min_chunk.0,
+            chunk_coord.1 - min_chunk.1,
+            chunk_coord.2 - min_chunk.2,
+        ];
+        std::array::from_fn(|face| {
+            let on_boundary = if face % 2 == 1 {
+                local[face / 2] == CHUNKS_PER_CLUSTER_DIM as i16 - 1
+            } else {
+                local[face / 2] == 0
+            };
+            if on_boundary {
+                self.neighbor_samples_per_chunk_dim[face]
+            } else {
+                own
+            }
+        })
+    }
+
+    //lower loads first. every loader pops the same heap, so once a request has waited its distance over REQUEST_AGING_RATE
+    //seconds nothing pushed after it can go ahead of it and it loads once the requests queued before it have
+    fn aged_priority(&self) -> f32 {
+        self.distance_squared.sqrt() + self.queued_secs * REQUEST_AGING_RATE
+    }
+}
+
+//a cluster request with the chunks of it that were found on disk, handed from the io threads to the compute threads
+pub struct LoadedCluster {
+    request: ClusterRequest,
+    chunks_on_disk: Vec<DiskChunk>,
+    buried: [bool; CHUNKS_PER_CLUSTER], //walled in by uniform dirt on all six sides, nothing outside can see their surface
+}
+
+struct DiskChunk {
+    chunk_coord: ChunkCoord,
+    stored: Option<NonUniformTerrainChunk>, //pooled samples, None when the chunk turned uniform this session
+    removed_as: Uniformity,                 //Air or Dirt when stored is None
+    read_time: Duration,
 }
 
 struct ChunkResult {
     has_entity: [bool; CHUNKS_PER_CLUSTER],
     cluster_coord: (i16, i16, i16),
     load_state: LoadState,
+    read_failed: bool, //a stored chunk could not be read, nothing of the cluster was loaded
 }
 
 #[derive(Resource)]
 pub struct WriteCmdSender(pub Sender<WriteCmd>);
 
+//edited chunks out on the loader threads. an edit to a chunk already out is not sent again,
+//the chunk is marked stale and remeshed from the chunk map once the mesh in flight lands
+#[derive(Resource)]
+pub struct RemeshQueue {
+    sender: Sender<RemeshRequest>,
+    result_receiver: Receiver<RemeshResult>,
+    in_flight: FxHashSet<ChunkCoord>,
+    stale: FxHashSet<ChunkCoord>,
+}
+
+impl RemeshQueue {
+    pub(crate) fn request(
+        &mut self,
+        chunk_coord: ChunkCoord,
+        densities: Arc<[i16]>,
+        materials: Arc<[MaterialCode]>,
+    ) {
+        if !self.in_flight.insert(chunk_coord) {
+            self.stale.insert(chunk_coord);
+            return;
+        }
+        let _ = self.sender.send(RemeshRequest {
+            chunk_coord,
+            densities,
+            materials,
+        });
+    }
+
+    //the chunk is no longer in flight, true if it was edited again while it was
+    pub(crate) fn finish(&mut self, chunk_coord: ChunkCoord) -> bool {
+        self.in_flight.remove(&chunk_coord);
+        self.stale.remove(&chunk_coord)
+    }
+
+    pub(crate) fn try_recv(&self) -> Option<RemeshResult> {
+        self.result_receiver.try_recv().ok()
+    }
+
+    //every edit so far has its new mesh in place
+    pub(crate) fn is_idle(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+}
+
 #[derive(Resource)]
 pub(crate) struct Lods(pub(crate) bool);
 
+//insert before startup to generate terrain from something other than the built in noise
+#[derive(Resource)]
+pub struct TerrainDensitySource(pub Arc<dyn DensitySource>);
+
+//the world files are read with the same retries as chunks, one that still fails is returned to the app's error handler
+//the driver does not start without them, guessing would have chunks generated over saved ones
 pub(crate) fn setup_chunk_driver(
     mut commands: Commands,
     moveable_center: Res<MoveableCenter>,
     lods: Res<Lods>,
-) {
+    density_source: Option<Res<TerrainDensitySource>>,
+    world_gen: Res<WorldGenConfig>,
+    terrain_observers: Res<TerrainObservers>,
+    storage: Res<StoragePaths>,
+) -> Result {
     let lods: bool = lods.0;
     commands.remove_resource::<Lods>();
     #[cfg(feature = "timers")]
@@ -270,43 +656,57 @@ pub(crate) fn setup_chunk_driver(
     let num_processors = thread::available_parallelism().unwrap().get();
     info!("Number of Available Processors: {}", num_processors);
     commands.insert_resource(LogicalProcesors(num_processors));
+    //a small ci runner still gets one loader, nothing would ever load without it
+    let num_compute_threads = num_processors.saturating_sub(4).max(1);
     #[cfg(feature = "debug")]
     INTERNAL_QUEUE_SIZES.get_or_init(|| {
-        (0..num_processors.saturating_sub(4))
+        (0..num_compute_threads)
             .map(|_| AtomicUsize::new(0))
             .collect()
     });
     let moveable_center_arc = Arc::clone(&moveable_center.center_mutex);
+    let view_direction_arc = Arc::clone(&moveable_center.view_mutex);
+    let terrain_observers = terrain_observers.clone();
     let (chunk_spawn_sender, chunk_spawn_reciever) = unbounded::<ChunkSpawnResult>();
-    let terrain_chunk_map = Arc::new(Mutex::new(FxHashMap::default()));
+    let terrain_chunk_map = Arc::new(ChunkStore::default());
     let (res_tx, res_rx) = unbounded::<ChunkResult>();
-    let svo = SvoNode::world_root();
+    let svo = TerrainSvo(Arc::new(RwLock::new(Svo::world_root(
+        world_gen.world_radius_clusters(),
+    ))));
+    commands.insert_resource(svo.clone());
     commands.insert_resource(ChunkSpawnReciever(chunk_spawn_reciever));
-    let root = get_project_root();
-    let mut air_compression_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(root.join("data/air_compression_data.txt"))
-        .unwrap();
-    let mut dirt_compression_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(root.join("data/dirt_compression_data.txt"))
-        .unwrap();
+    let data_dir = storage.world_dir();
+    migrate_to_region_files(&data_dir)?;
+    with_retries("replay the write journal", || {
+        replay_write_journal(&data_dir)
+    })?;
+    compact_fragmented_regions(&data_dir);
+    let open = |name: &str| {
+        with_retries(format_args!("open {name}"), || {
+            storage
+                .open(name)
+                .map_err(|e| TerrainError::Open(data_dir.join(name), e))
+        })
+    };
+    let mut air_compression_file = open("air_compression_data.txt")?;
+    let mut dirt_compression_file = open("dirt_compression_data.txt")?;
     let t0 = Instant::now();
     let mut column_range_map = ColumnRangeMap::new();
-    let empty_air_offsets = load_uniform_chunks(
-        &mut air_compression_file,
-        Uniformity::Air,
-        &mut column_range_map,
-    );
-    let empty_dirt_offsets = load_uniform_chunks(
-        &mut dirt_compression_file,
-        Uniformity::Dirt,
-        &mut column_range_map,
-    );
+    //startup runs on the main thread, a world whose files cant be read stops here with the reason instead of in a worker
+    let empty_air_offsets = with_retries("load uniform air chunks", || {
+        load_uniform_chunks(
+            &mut air_compression_file,
+            Uniformity::Air,
+            &mut column_range_map,
+        )
+    })?;
+    let empty_dirt_offsets = with_retries("load uniform dirt chunks", || {
+        load_uniform_chunks(
+            &mut dirt_compression_file,
+            Uniformity::Dirt,
+            &mut column_range_map,
+        )
+    })?;
     info!(
         "Loaded ColumnRangeMap with {} bytes in {} ms.",
         column_range_map.size_in_bytes(),
@@ -315,23 +715,31 @@ pub(crate) fn setup_chunk_driver(
     let column_range_map = Arc::new(column_range_map);
     let fbm = get_fbm();
     commands.insert_resource(NoiseGenerator(fbm.clone()));
+    let source = density_source.map_or_else(
+        || Arc::new(NoiseTerrain::new(fbm)) as Arc<dyn DensitySource>,
+        |source| Arc::clone(&source.0),
+    );
     let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let (remesh_tx, remesh_rx) = unbounded::<RemeshRequest>();
+    let (remesh_result_tx, remesh_result_rx) = unbounded::<RemeshResult>();
     let index_map_delta_arc = Arc::clone(&index_map_delta);
-    let data_file_write = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(root.join("data/chunk_data.txt"))
-        .unwrap();
-    let mut chunk_index_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(root.join("data/chunk_index_data.txt"))
-        .unwrap();
+    let region_files_write = with_retries("open region files for writing", || {
+        RegionFiles::writer(&data_dir)
+    })?;
+    let write_journal = with_retries("open the write journal", || WriteJournal::open(&data_dir))?;
     let t0 = Instant::now();
-    let index_map_read = Arc::new(load_chunk_index_map(&mut chunk_index_file));
+    let mut summaries = FxHashMap::default();
+    let mut modified_times = FxHashMap::default();
+    let index_map_read = Arc::new(load_chunk_index_map(
+        &data_dir,
+        &mut summaries,
+        &mut modified_times,
+    ));
     let index_map_read_arc = Arc::clone(&index_map_read);
+    let chunk_summaries = ChunkSummaries(Arc::new(RwLock::new(summaries)));
+    let chunk_summaries_write = chunk_summaries.clone();
+    let chunk_modified_times = ChunkModifiedTimes::new(modified_times);
+    let chunk_modified_times_write = chunk_modified_times.clone();
     let (terrain_chunk_map_modification_sender, terrain_chunk_map_modification_reciever) =
         crossbeam_channel::unbounded();
     info!(
@@ -343,32 +751,66 @@ pub(crate) fn setup_chunk_driver(
         dedicated_write_thread(
             write_rx,
             index_map_delta_arc,
-            data_file_write,
-            chunk_index_file,
+            region_files_write,
             air_compression_file,
             dirt_compression_file,
             empty_air_offsets,
             empty_dirt_offsets,
             index_map_read_arc,
+            chunk_summaries_write,
+            chunk_modified_times_write,
+            Some(write_journal),
         );
     });
     let priority_queue = Arc::new((Mutex::new(BinaryHeap::new()), Condvar::new()));
-    for thread_idx in 0..num_processors.saturating_sub(4) {
-        //leave one processor free for main thread and one for svo manager <- might be wrong
+    let heightmap_cache = Arc::new(HeightmapCache::default());
+    let load_history = ChunkLoadHistory::default();
+    let (loaded_cluster_sender, loaded_cluster_receiver) =
+        crossbeam_channel::bounded(num_compute_threads * LOADED_CLUSTERS_PER_COMPUTE_THREAD);
+    let region_maps = RegionMaps::default();
+    let chunk_pool = ChunkBufferPool::default();
+    for thread_idx in 0..CHUNK_IO_THREADS {
         let index_map_read = Arc::clone(&index_map_read);
         let index_map_delta = Arc::clone(&index_map_delta);
-        let chunk_data_file_read = OpenOptions::new()
-            .read(true)
-            .open(root.join("data/chunk_data.txt"))
-            .unwrap();
+        let region_files_read = RegionFiles::mapped_reader(&data_dir, region_maps.clone());
+        let column_range_map_read_only = Arc::clone(&column_range_map);
+        let priority_queue_arc = Arc::clone(&priority_queue);
+        let loaded_cluster_sender = loaded_cluster_sender.clone();
+        let failed_read_sender = res_tx.clone();
+        let chunk_pool_clone = chunk_pool.clone();
+        let _handle = thread::Builder::new()
+            .name(format!("chunk_io_{thread_idx}"))
+            .spawn(move || {
+                chunk_io_thread(
+                    index_map_read,
+                    index_map_delta,
+                    region_files_read,
+                    column_range_map_read_only,
+                    priority_queue_arc,
+                    loaded_cluster_sender,
+                    failed_read_sender,
+                    chunk_pool_clone,
+                );
+            })
+            .expect("failed to spawn chunk io thread");
+    }
+    drop(loaded_cluster_sender);
+    for thread_idx in 0..num_compute_threads {
+        //leave one processor free for main thread and one for svo manager <- might be wrong
+        let loaded_cluster_receiver = loaded_cluster_receiver.clone();
         let res_tx_clone = res_tx.clone();
         let chunk_spawn_channel = chunk_spawn_sender.clone();
-        let fbm_clone = fbm.clone();
+        let source_clone = Arc::clone(&source);
+        let heightmap_cache_clone = Arc::clone(&heightmap_cache);
         let column_range_map_read_only = Arc::clone(&column_range_map);
         let write_sender_clone = write_tx.clone();
-        let priority_queue_arc = Arc::clone(&priority_queue);
         let terrain_chunk_map_modification_sender_clone =
             terrain_chunk_map_modification_sender.clone();
+        let chunk_summaries_clone = chunk_summaries.clone();
+        let load_history_clone = load_history.clone();
+        let chunk_pool_clone = chunk_pool.clone();
+        let remesh_receiver = remesh_rx.clone();
+        let remesh_result_sender = remesh_result_tx.clone();
         let _handle = thread::Builder::new()
             .name(format!("chunk_loader_{thread_idx}"))
             .spawn(move || {
@@ -376,173 +818,712 @@ pub(crate) fn setup_chunk_driver(
                     lod_chunk_loader_thread(
                         thread_idx,
                         res_tx_clone,
-                        index_map_read,
-                        index_map_delta,
-                        chunk_data_file_read,
+                        loaded_cluster_receiver,
+                        remesh_receiver,
+                        remesh_result_sender,
                         chunk_spawn_channel,
-                        fbm_clone,
+                        source_clone,
+                        heightmap_cache_clone,
                         column_range_map_read_only,
                         write_sender_clone,
-                        priority_queue_arc,
                         terrain_chunk_map_modification_sender_clone,
+                        chunk_summaries_clone,
+                        load_history_clone,
+                        chunk_pool_clone,
                     );
                 } else {
                     chunk_loader_thread(
                         thread_idx,
                         res_tx_clone,
-                        index_map_read,
-                        index_map_delta,
-                        chunk_data_file_read,
+                        loaded_cluster_receiver,
+                        remesh_receiver,
+                        remesh_result_sender,
                         chunk_spawn_channel,
-                        fbm_clone,
+                        source_clone,
+                        heightmap_cache_clone,
                         column_range_map_read_only,
                         write_sender_clone,
-                        priority_queue_arc,
                         terrain_chunk_map_modification_sender_clone,
+                        chunk_summaries_clone,
+                        load_history_clone,
+                        chunk_pool_clone,
                     );
                 }
             })
             .expect("failed to spawn chunk loader thread");
     }
     let terrain_chunk_map_arc = Arc::clone(&terrain_chunk_map);
+    let chunk_pager = ChunkPager {
+        write_sender: write_tx.clone(),
+        index_map_read,
+        index_map_delta,
+        region_files_read: RegionFiles::mapped_reader(&data_dir, region_maps),
+        source,
+        heightmap_cache,
+        chunk_buffers: ChunkBuffers::new(),
+        chunk_pool,
+        unsynced: false,
+    };
+    //only lod clusters are ever recorded, without lods there is nothing to save
+    let cluster_occupancy = lods.then(|| SharedClusterOccupancy::load(&data_dir));
+    if let Some(cluster_occupancy) = &cluster_occupancy {
+        commands.insert_resource(cluster_occupancy.clone());
+    }
     thread::spawn(move || {
         svo_manager_thread(
             res_rx,
             moveable_center_arc,
+            view_direction_arc,
+            terrain_observers,
             chunk_spawn_sender,
             svo,
             priority_queue,
             terrain_chunk_map_arc,
             terrain_chunk_map_modification_reciever,
             terrain_chunk_map_modification_sender,
+            chunk_pager,
+            cluster_occupancy,
             lods,
         );
     });
     commands.insert_resource(WriteCmdSender(write_tx));
+    commands.insert_resource(RemeshQueue {
+        sender: remesh_tx,
+        result_receiver: remesh_result_rx,
+        in_flight: FxHashSet::default(),
+        stale: FxHashSet::default(),
+    });
+    commands.insert_resource(chunk_summaries);
+    commands.insert_resource(chunk_modified_times);
+    commands.insert_resource(load_history);
     commands.insert_resource(TerrainChunkMap(terrain_chunk_map));
+    Ok(())
+}
+
+//worlds saved before region files are converted once on startup, the flat files are kept in a backup dir
+//a failed migration leaves the old files alone and clears its staging dir on the next try
+fn migrate_to_region_files(data_dir: &Path) -> Result<(), TerrainError> {
+    let Some(format) = StorageFormat::detect(data_dir) else {
+        return Ok(());
+    };
+    if format == StorageFormat::Region {
+        return Ok(());
+    }
+    let what = format!("migrate {} world to region files", format.name());
+    let report = with_retries(what, || {
+        migrate_world(data_dir, format, StorageFormat::Region).map_err(TerrainError::Migration)
+    })?;
+    info!(
+        "Migrated {} chunks from {} to region files, originals kept in {}.",
+        report.chunks,
+        format.name(),
+        report.backup_dir
+    );
+    Ok(())
+}
+
+//runs before anything opens the region files, the index map is loaded from the packed headers right after
+fn compact_fragmented_regions(data_dir: &Path) {
+    let t0 = Instant::now();
+    match compact_region_files(data_dir, COMPACT_WASTE_RATIO) {
+        Ok(report) if report.regions > 0 => info!(
+            "Compacted {} regions holding {} chunks, {:.2} MB -> {:.2} MB in {} ms.",
+            report.regions,
+            report.chunks,
+            report.bytes_before as f64 / 1_000_000.0,
+            report.bytes_after as f64 / 1_000_000.0,
+            t0.elapsed().as_millis()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("region compaction stopped, regions packed so far are kept: {e}"),
+    }
 }
 
 //assume duplicate writes are impossible otherwise something went wrong
-fn dedicated_write_thread(
+//exits once every WriteCmdSender is dropped, everything sent before that is on disk
+//a chunk write that keeps failing is given up on and retried at every sync, the journal is cut down to those chunks
+//when it would be cleared so the next start replays them and nothing that already landed
+pub fn dedicated_write_thread(
     rx: Receiver<WriteCmd>,
-    index_map_delta: Arc<RwLock<FxHashMap<(i16, i16, i16), u64>>>,
-    mut chunk_data_file: File,
-    mut chunk_index_file: File,
-    mut air_file: File,
-    mut dirt_file: File,
-    mut air_empty_offsets: VecDeque<u64>,
-    mut dirt_empty_offsets: VecDeque<u64>,
-    chunk_index_map_read: Arc<FxHashMap<(i16, i16, i16), u64>>,
+    index_map_delta: Arc<RwLock<FxHashMap<ChunkCoord, u64>>>,
+    region_files: RegionFiles,
+    air_file: File,
+    dirt_file: File,
+    air_empty_offsets: VecDeque<u64>,
+    dirt_empty_offsets: VecDeque<u64>,
+    chunk_index_map_read: Arc<FxHashMap<ChunkCoord, u64>>,
+    chunk_summaries: ChunkSummaries,
+    chunk_modified_times: ChunkModifiedTimes,
+    mut journal: Option<WriteJournal>,
 ) {
-    let mut chunk_write_reuse = Vec::with_capacity(14); //sizeof (i16, i16, i16, u64)
-    let mut serial_buffer = [0; CHUNK_SERIALIZED_SIZE];
+    let mut target = WriteTarget {
+        index_map_delta,
+        region_files,
+        air_file,
+        dirt_file,
+        air_empty_offsets,
+        dirt_empty_offsets,
+        chunk_index_map_read,
+        chunk_summaries,
+        chunk_modified_times,
+        serial_buffer: Vec::new(),
+        hot_chunks: FxHashSet::default(),
+    };
+    let mut batch = Vec::new();
+    let mut unwritten = FxHashMap::default(); //latest content of every chunk whose write was given up on
     while let Ok(cmd) = rx.recv() {
-        match cmd {
+        batch.push(cmd);
+        batch.extend(rx.try_iter());
+        //the batch is still written, it just has nothing to finish it if the game dies part way through
+        if let Some(journal) = journal.as_mut()
+            && let Err(e) = journal.append(&batch, &mut target.serial_buffer)
+        {
+            error!("failed to append to the write journal, this batch is not journaled: {e}");
+        }
+        let batch_len = batch.len();
+        for (i, cmd) in batch.drain(..).enumerate() {
+            if let WriteCmd::ReplaceFile {
+                name,
+                file,
+                contents,
+            } = &cmd
+            {
+                if let Err(e) = replace_file_contents(file, contents) {
+                    warn!("failed to save {name}: {e}");
+                }
+                continue;
+            }
+            if let WriteCmd::Sync { done } = cmd {
+                target.retry_unwritten(&mut unwritten);
+                //the loaders keep the channel open for the life of the app so this is where raw chunks get compressed
+                target.settle_hot_chunks();
+                let synced = target.sync();
+                //the rest of the batch is journaled but not applied yet
+                if synced
+                    && i + 1 == batch_len
+                    && let Some(journal) = journal.as_mut()
+                {
+                    reset_journal(journal, &unwritten, &mut target.serial_buffer);
+                }
+                if let Some(done) = done {
+                    let _ = done.send(());
+                }
+                continue;
+            }
+            let applied = target.apply_with_retries(&cmd);
+            track_unwritten(&mut unwritten, cmd, applied);
+        }
+        if journal.as_ref().is_some_and(WriteJournal::needs_checkpoint) {
+            target.retry_unwritten(&mut unwritten);
+            if target.sync() {
+                reset_journal(
+                    journal.as_mut().unwrap(),
+                    &unwritten,
+                    &mut target.serial_buffer,
+                );
+            }
+        }
+    }
+    //the sender is gone when the app exits, only chunks that still could not be written are left for the journal
+    target.retry_unwritten(&mut unwritten);
+    target.settle_hot_chunks();
+    if target.sync()
+        && let Some(journal) = journal.as_mut()
+    {
+        reset_journal(journal, &unwritten, &mut target.serial_buffer);
+    }
+}
+
+//a later full write of the chunk that lands replaces the one given up on, anything else leaves it waiting
+//with the newest content, a patch only rewrites part of a chunk whose stored copy is already stale
+fn track_unwritten(unwritten: &mut FxHashMap<ChunkCoord, WriteCmd>, cmd: WriteCmd, applied: bool) {
+    match cmd {
+        WriteCmd::UpdateNonUniform { chunk_coord, .. }
+        | WriteCmd::WriteUniformAir { chunk_coord }
+        | WriteCmd::WriteUniformDirt { chunk_coord }
+            if applied =>
+        {
+            unwritten.remove(&chunk_coord);
+        }
+        WriteCmd::PatchNonUniform { chunk_coord, .. }
+            if applied && !unwritten.contains_key(&chunk_coord) => {}
+        WriteCmd::UpdateNonUniform {
+            densities,
+            materials,
+            chunk_coord,
+        }
+        | WriteCmd::PatchNonUniform {
+            densities,
+            materials,
+            chunk_coord,
+            ..
+        } => {
+            unwritten.insert(
+                chunk_coord,
+                WriteCmd::UpdateNonUniform {
+                    densities,
+                    materials,
+                    chunk_coord,
+                },
+            );
+        }
+        //uniform writes are not journaled, apply_with_retries already reported it
+        _ => {}
+    }
+}
+
+//only once everything applied so far is synced, the journal keeps just the chunks that were never written
+//a failed clear leaves the old records to be replayed, which only rewrites what already landed
+fn reset_journal(
+    journal: &mut WriteJournal,
+    unwritten: &FxHashMap<ChunkCoord, WriteCmd>,
+    serial_buffer: &mut Vec<u8>,
+) {
+    if let Err(e) = journal
+        .clear()
+        .and_then(|_| journal.append(unwritten.values(), serial_buffer))
+    {
+        warn!("failed to reset the write journal: {e}");
+    }
+}
+
+//everything the write thread writes to, applying a command again after it failed partway leaves the files consistent
+struct WriteTarget {
+    index_map_delta: Arc<RwLock<FxHashMap<ChunkCoord, u64>>>,
+    region_files: RegionFiles,
+    air_file: File,
+    dirt_file: File,
+    air_empty_offsets: VecDeque<u64>,
+    dirt_empty_offsets: VecDeque<u64>,
+    chunk_index_map_read: Arc<FxHashMap<ChunkCoord, u64>>,
+    chunk_summaries: ChunkSummaries,
+    chunk_modified_times: ChunkModifiedTimes,
+    serial_buffer: Vec<u8>,
+    hot_chunks: FxHashSet<ChunkCoord>, //stored raw by patches this session
+}
+
+impl WriteTarget {
+    //one more try for each chunk given up on, they stay in unwritten until one lands
+    fn retry_unwritten(&mut self, unwritten: &mut FxHashMap<ChunkCoord, WriteCmd>) {
+        unwritten.retain(|chunk_coord, cmd| {
+            let result = self.apply(cmd);
+            if let Err(e) = &result {
+                warn!("chunk {chunk_coord} still could not be written: {e}");
+            }
+            result.is_err()
+        });
+    }
+
+    //most io errors pass, a disk being cleared or a file held by another process, so a command is tried a few times
+    //false once every attempt failed
+    fn apply_with_retries(&mut self, cmd: &WriteCmd) -> bool {
+        let mut delay = WRITE_RETRY_DELAY;
+        for attempt in 1..=WRITE_ATTEMPTS {
+            let Err(e) = self.apply(cmd) else {
+                return true;
+            };
+            let chunk_coord = cmd.chunk_coord().unwrap_or_default(); //syncs never get here
+            if attempt == WRITE_ATTEMPTS {
+                error!("gave up writing chunk {chunk_coord}: {e}");
+                return false;
+            }
+            warn!(
+                "failed to write chunk {chunk_coord}, attempt {attempt} of {WRITE_ATTEMPTS}: {e}"
+            );
+            thread::sleep(delay);
+            delay *= 2;
+        }
+        false
+    }
+
+    fn apply(&mut self, cmd: &WriteCmd) -> Result<(), TerrainError> {
+        let (densities, materials, chunk_coord, dirty) = match cmd {
             WriteCmd::UpdateNonUniform {
                 densities,
                 materials,
                 chunk_coord,
-            } => {
-                //offset lookup must be async to avoid situation where we try to update a chunk that isnt written
-                //because the channel is ordered, the write should always process before the update
-                let offset = chunk_index_map_read
-                    .get(&chunk_coord)
-                    .cloned()
-                    .or_else(|| index_map_delta.read().get(&chunk_coord).cloned());
-                match offset {
-                    Some(offset) => {
-                        update_chunk(
-                            offset,
-                            &densities,
-                            &materials,
-                            &mut chunk_data_file,
-                            &mut serial_buffer,
-                        );
-                    }
-                    None => {
-                        let mut index_map = index_map_delta.write();
-                        write_chunk(
-                            &densities,
-                            &materials,
-                            &chunk_coord,
-                            &mut index_map,
-                            &mut chunk_data_file,
-                            &mut chunk_index_file,
-                            &mut chunk_write_reuse,
-                            &mut serial_buffer,
-                        );
-                    }
-                }
-            }
+            } => (densities, materials, *chunk_coord, None),
+            WriteCmd::PatchNonUniform {
+                densities,
+                materials,
+                chunk_coord,
+                dirty,
+            } => (densities, materials, *chunk_coord, Some(*dirty)),
             WriteCmd::WriteUniformAir { chunk_coord } => {
-                write_uniform_chunk(&chunk_coord, &mut air_file, &mut air_empty_offsets);
+                return write_uniform_chunk(
+                    chunk_coord,
+                    &mut self.air_file,
+                    &mut self.air_empty_offsets,
+                );
             }
             WriteCmd::WriteUniformDirt { chunk_coord } => {
-                write_uniform_chunk(&chunk_coord, &mut dirt_file, &mut dirt_empty_offsets);
+                return write_uniform_chunk(
+                    chunk_coord,
+                    &mut self.dirt_file,
+                    &mut self.dirt_empty_offsets,
+                );
             }
             WriteCmd::RemoveUniformAir { chunk_coord } => {
-                remove_uniform_chunk(&chunk_coord, &mut air_file, &mut air_empty_offsets);
+                return remove_uniform_chunk(
+                    chunk_coord,
+                    &mut self.air_file,
+                    &mut self.air_empty_offsets,
+                );
             }
             WriteCmd::RemoveUniformDirt { chunk_coord } => {
-                remove_uniform_chunk(&chunk_coord, &mut dirt_file, &mut dirt_empty_offsets);
+                return remove_uniform_chunk(
+                    chunk_coord,
+                    &mut self.dirt_file,
+                    &mut self.dirt_empty_offsets,
+                );
             }
+            WriteCmd::ReplaceFile { .. } | WriteCmd::Sync { .. } => return Ok(()), //handled by the write loop
+        };
+        //offset lookup must be async to avoid situation where we try to update a chunk that isnt written
+        //because the channel is ordered, the write should always process before the update
+        //the delta goes first, a chunk that moved to new sectors this session is only correct there
+        let stored = self
+            .index_map_delta
+            .read()
+            .get(&chunk_coord)
+            .cloned()
+            .or_else(|| self.chunk_index_map_read.get(&chunk_coord).cloned());
+        let offset = stored.filter(|offset| removed_as(*offset).is_none());
+        //it turned uniform earlier this session and is edited again, the uniform file entry would win next session
+        match stored.and_then(removed_as) {
+            Some(Uniformity::Air) => remove_uniform_chunk(
+                &chunk_coord,
+                &mut self.air_file,
+                &mut self.air_empty_offsets,
+            )?,
+            Some(Uniformity::Dirt) => remove_uniform_chunk(
+                &chunk_coord,
+                &mut self.dirt_file,
+                &mut self.dirt_empty_offsets,
+            )?,
+            _ => {}
+        }
+        //a dug chunk with no solid sample left or a built one with no air left goes back to its uniform file
+        //and gives up its region sectors
+        let uniformity = chunk_uniformity(densities, materials);
+        if matches!(uniformity, Uniformity::Air | Uniformity::Dirt) {
+            if stored.is_some() {
+                remove_chunk(
+                    &chunk_coord,
+                    uniformity,
+                    &mut self.index_map_delta.write(),
+                    &mut self.region_files,
+                )?;
+            }
+            self.hot_chunks.remove(&chunk_coord);
+            self.chunk_summaries.0.write().remove(&chunk_coord);
+            return if uniformity == Uniformity::Air {
+                write_uniform_chunk(
+                    &chunk_coord,
+                    &mut self.air_file,
+                    &mut self.air_empty_offsets,
+                )
+            } else {
+                write_uniform_chunk(
+                    &chunk_coord,
+                    &mut self.dirt_file,
+                    &mut self.dirt_empty_offsets,
+                )
+            };
+        }
+        let summary = compute_chunk_summary(densities, materials, chunk_coord);
+        self.chunk_summaries.0.write().insert(chunk_coord, summary);
+        let modified = unix_millis_now();
+        match offset {
+            Some(offset) => {
+                let patched = match dirty {
+                    Some(dirty) => patch_chunk(
+                        &chunk_coord,
+                        offset,
+                        densities,
+                        materials,
+                        &dirty,
+                        &summary,
+                        modified,
+                        &mut self.region_files,
+                    )?,
+                    None => false,
+                };
+                if !patched {
+                    //a chunk being dug is kept raw so the next edits can patch it, it is compressed again at exit
+                    let codec = if dirty.is_some() {
+                        self.hot_chunks.insert(chunk_coord);
+                        ChunkCodec::Raw
+                    } else {
+                        self.hot_chunks.remove(&chunk_coord);
+                        CHUNK_CODEC
+                    };
+                    let new_offset = update_chunk(
+                        &chunk_coord,
+                        offset,
+                        densities,
+                        materials,
+                        &summary,
+                        modified,
+                        codec,
+                        &mut self.region_files,
+                        &mut self.serial_buffer,
+                    )?;
+                    if new_offset != offset {
+                        self.index_map_delta.write().insert(chunk_coord, new_offset);
+                    }
+                }
+            }
+            None => {
+                let mut index_map = self.index_map_delta.write();
+                write_chunk(
+                    densities,
+                    materials,
+                    &summary,
+                    modified,
+                    &chunk_coord,
+                    &mut index_map,
+                    &mut self.region_files,
+                    &mut self.serial_buffer,
+                )?;
+            }
+        }
+        self.chunk_modified_times.record(chunk_coord, modified);
+        Ok(())
+    }
+
+    //compresses the chunks patches left raw, they only shrink so each stays in its sectors
+    //one that fails stays raw, it reads back the same either way
+    fn settle_hot_chunks(&mut self) {
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+        for chunk_coord in self.hot_chunks.drain() {
+            let Some(offset) = self
+                .index_map_delta
+                .read()
+                .get(&chunk_coord)
+                .or_else(|| self.chunk_index_map_read.get(&chunk_coord))
+                .cloned()
+                .filter(|offset| removed_as(*offset).is_none())
+            else {
+                continue;
+            };
+            if let Err(e) = recompress_chunk(
+                &chunk_coord,
+                offset,
+                &mut densities,
+                &mut materials,
+                &mut self.region_files,
+                &mut self.serial_buffer,
+            ) {
+                warn!("failed to compress chunk {chunk_coord}, it is left raw: {e}");
+            }
+        }
+    }
+
+    //false when something may not have reached the disk, the journal has to stay until a later sync succeeds
+    fn sync(&self) -> bool {
+        let synced = self
+            .region_files
+            .sync_all()
+            .and_then(|_| Ok(self.air_file.sync_all()?))
+            .and_then(|_| Ok(self.dirt_file.sync_all()?));
+        if let Err(e) = &synced {
+            warn!("failed to sync terrain files, the write journal is kept: {e}");
         }
+        synced.is_ok()
     }
 }
 
 //compute thread for loading or generating chunks
 //recieves chunk load requests from svo_manager_thread and returns the data
 //uses a fast uniformity check to skip most of the chunk calculation on uniform chunks
-fn lod_chunk_loader_thread(
-    #[cfg_attr(not(feature = "timers"), allow(unused_variables))] thread_idx: usize,
+fn lod_chunk_loader_thread<S: DensitySource + ?Sized>(
+    thread_idx: usize,
     res_tx: Sender<ChunkResult>,
-    index_map_read: Arc<FxHashMap<(i16, i16, i16), u64>>,
-    index_map_delta: Arc<RwLock<FxHashMap<(i16, i16, i16), u64>>>,
-    mut chunk_data_file_read: File,
+    loaded_cluster_receiver: Receiver<LoadedCluster>,
+    remesh_receiver: Receiver<RemeshRequest>,
+    remesh_result_sender: Sender<RemeshResult>,
     chunk_spawn_channel: Sender<ChunkSpawnResult>,
-    fbm: GeneratorWrapper<SafeNode>,
+    source: Arc<S>,
+    heightmap_cache: Arc<HeightmapCache>,
     column_range_map_read_only: Arc<ColumnRangeMap>,
     write_sender: Sender<WriteCmd>,
-    priority_queue: Arc<(Mutex<BinaryHeap<ClusterRequest>>, Condvar)>,
     terrain_chunk_map_modification_sender: Sender<TerrainChunkMapModification>,
+    chunk_summaries: ChunkSummaries,
+    load_history: ChunkLoadHistory,
+    chunk_pool: ChunkBufferPool,
 ) {
     let mut lod_buffers = LodBuffers::new();
+    let mut finer_neighbor_density = vec![0i16; SAMPLES_PER_CHUNK]; //see finer_neighbor_field
+    let mut finer_neighbor_material = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
     let mut chunk_buffers = ChunkBuffers::new();
-    let mut internal_queue = Vec::with_capacity(INTERNAL_WORKER_QUEUE_SIZE);
+    let mut meshing_scratch = MeshingScratch::new();
     loop {
-        let (binary_heap_lock, condvar) = &*priority_queue;
-        let mut binary_heap = binary_heap_lock.lock().unwrap();
-        while binary_heap.is_empty() {
-            binary_heap = condvar.wait(binary_heap).unwrap();
-        }
-        let num_to_pop = binary_heap.len().min(INTERNAL_WORKER_QUEUE_SIZE);
-        for _ in 0..num_to_pop {
-            internal_queue.push(binary_heap.pop().unwrap());
-        }
-        QUEUE_SIZE.store(binary_heap.len(), Ordering::Relaxed);
-        drop(binary_heap);
         #[cfg(feature = "debug")]
-        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx]
-            .store(internal_queue.len(), Ordering::Relaxed);
-        for cluster_request in internal_queue.drain(..) {
-            #[cfg(feature = "debug")]
-            INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].fetch_sub(1, Ordering::Relaxed);
-            let mut has_entity_buffer = [false; CHUNKS_PER_CLUSTER];
-            let mut rolling = 0;
-            let in_simulation_range = matches!(
-                cluster_request.load_state_transition,
-                LoadStateTransition::ToFullWithCollider | LoadStateTransition::NoChangeAddCollider
-            );
-            let min_chunk = cluster_coord_to_min_chunk_coord(cluster_request.position);
-            for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                    let mut has_heightmap_been_calculated = false;
-                    let column_cache = column_range_map_read_only.get_column(chunk_x, chunk_z);
-                    for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                        let chunk_coord = (chunk_x, chunk_y, chunk_z);
-                        let mut uniformity = column_cache.uniformity_at_y(chunk_y);
-                        if uniformity == Uniformity::Air {
-                            //cache hit
+        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].store(0, Ordering::Relaxed);
+        let Some(LoadedCluster {
+            request: cluster_request,
+            mut chunks_on_disk,
+            buried,
+        }) = next_loaded_cluster(
+            &loaded_cluster_receiver,
+            &remesh_receiver,
+            &remesh_result_sender,
+            &mut meshing_scratch,
+        )
+        else {
+            return; //io threads are gone
+        };
+        #[cfg(feature = "debug")]
+        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].store(1, Ordering::Relaxed);
+        let mut has_entity_buffer = [false; CHUNKS_PER_CLUSTER];
+        let mut rolling = 0;
+        let in_simulation_range = matches!(
+            cluster_request.load_state_transition,
+            LoadStateTransition::ToFullWithCollider | LoadStateTransition::NoChangeAddCollider
+        );
+        let mut merged = (cluster_request.load_state_transition == LoadStateTransition::ToMerged)
+            .then(|| MergedClusterMesh::new(cluster_request.position));
+        let min_chunk = cluster_coord_to_min_chunk_coord(cluster_request.position);
+        for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+            for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                let mut has_column_been_prepared = false;
+                let column_cache = column_range_map_read_only.get_column(chunk_x, chunk_z);
+                for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                    let chunk_coord = ChunkCoord(chunk_x, chunk_y, chunk_z);
+                    let mut uniformity = column_cache.uniformity_at_y(chunk_y);
+                    if uniformity == Uniformity::Air {
+                        //cache hit
+                        if in_simulation_range {
+                            let _ = terrain_chunk_map_modification_sender.send(
+                                TerrainChunkMapModification::Insert(
+                                    chunk_coord,
+                                    TerrainChunk::UniformAir,
+                                ),
+                            );
+                        }
+                        rolling += 1;
+                        continue;
+                    } else if uniformity == Uniformity::Dirt {
+                        //cache hit
+                        if in_simulation_range {
+                            let _ = terrain_chunk_map_modification_sender.send(
+                                TerrainChunkMapModification::Insert(
+                                    chunk_coord,
+                                    TerrainChunk::UniformDirt,
+                                ),
+                            );
+                        }
+                        rolling += 1;
+                        continue;
+                    }
+                    let mut buffers_filled = false; //loaded from disk or filled to classify the chunk
+                    let skip_mesh = buried[rolling] && !cluster_request.had_entity(rolling); //an entity it had is replaced as usual
+                    let mut stages = [Duration::ZERO; 3];
+                    if uniformity == Uniformity::Unknown {
+                        uniformity = take_chunk_from_disk(
+                            &mut chunks_on_disk,
+                            chunk_coord,
+                            &mut chunk_buffers,
+                            &mut stages[LoadStage::Disk as usize],
+                            &chunk_pool,
+                        );
+                        if uniformity == Uniformity::NonUniform {
+                            buffers_filled = true;
+                        }
+                    }
+                    let chunk_start = calculate_chunk_start(&chunk_coord);
+                    let generate_start = Instant::now();
+                    if uniformity == Uniformity::Unknown {
+                        if !has_column_been_prepared {
+                            heightmap_cache.prepare_column(
+                                &*source,
+                                (chunk_x, chunk_z),
+                                &chunk_start,
+                                &mut chunk_buffers,
+                            );
+                            has_column_been_prepared = true;
+                        }
+                        uniformity = source.classify(&chunk_start, &mut chunk_buffers);
+                        //outside the simulation radius nothing needs a buried chunk's samples either
+                        if uniformity == Uniformity::Unknown && skip_mesh && !in_simulation_range {
+                            rolling += 1;
+                            continue;
+                        }
+                        if uniformity == Uniformity::Unknown
+                            && let Some((reduced_density, reduced_material, out_dim)) =
+                                lod_buffers.reduced(cluster_request.load_state_transition)
+                        {
+                            //far lods are generated at their own resolution and never saved or summarized,
+                            //the full res chunk is generated the first time it loads closer
+                            source.fill_chunk_downsampled(
+                                chunk_start,
+                                &mut chunk_buffers,
+                                out_dim,
+                                reduced_density,
+                                reduced_material,
+                            );
+                            stages[LoadStage::Generate as usize] = generate_start.elapsed();
+                            let mesh_start = Instant::now();
+                            let normal_field = DensityField::Downsampled(reduced_density, out_dim);
+                            match merged.as_mut() {
+                                Some(merged) => {
+                                    if let Some(mesh) = reduced_lod_mesh(
+                                        reduced_density,
+                                        reduced_material,
+                                        normal_field,
+                                        &mut meshing_scratch,
+                                        out_dim,
+                                        None,
+                                    ) {
+                                        merged.append(chunk_coord, mesh);
+                                    }
+                                }
+                                None => {
+                                    let neighbors = finer_neighbor_field(
+                                        &*source,
+                                        chunk_start,
+                                        &mut chunk_buffers,
+                                        out_dim,
+                                        cluster_request
+                                            .chunk_neighbor_samples_per_chunk_dim(chunk_coord),
+                                        &mut finer_neighbor_density,
+                                        &mut finer_neighbor_material,
+                                    );
+                                    has_entity_buffer[rolling] = mesh_reduced_lod(
+                                        reduced_density,
+                                        reduced_material,
+                                        normal_field,
+                                        &chunk_spawn_channel,
+                                        &mut meshing_scratch,
+                                        chunk_coord,
+                                        out_dim,
+                                        neighbors,
+                                        cluster_request.had_entity(rolling),
+                                        cluster_request.prev_in_simulation_radius,
+                                    );
+                                }
+                            }
+                            stages[LoadStage::Mesh as usize] = mesh_start.elapsed();
+                            load_history.record(ChunkLoadEvent {
+                                chunk_coord,
+                                stages,
+                                thread_idx,
+                            });
+                            rolling += 1;
+                            continue;
+                        }
+                        if uniformity == Uniformity::Unknown {
+                            source.fill_chunk(chunk_start, &mut chunk_buffers);
+                            uniformity =
+                                chunk_uniformity(&chunk_buffers.density, &chunk_buffers.material);
+                            buffers_filled = true;
+                        }
+                    }
+                    match uniformity {
+                        Uniformity::Air => {
+                            let _ = write_sender.send(WriteCmd::WriteUniformAir { chunk_coord });
                             if in_simulation_range {
                                 let _ = terrain_chunk_map_modification_sender.send(
                                     TerrainChunkMapModification::Insert(
@@ -551,10 +1532,9 @@ fn lod_chunk_loader_thread(
                                     ),
                                 );
                             }
-                            rolling += 1;
-                            continue;
-                        } else if uniformity == Uniformity::Dirt {
-                            //cache hit
+                        }
+                        Uniformity::Dirt => {
+                            let _ = write_sender.send(WriteCmd::WriteUniformDirt { chunk_coord });
                             if in_simulation_range {
                                 let _ = terrain_chunk_map_modification_sender.send(
                                     TerrainChunkMapModification::Insert(
@@ -563,172 +1543,273 @@ fn lod_chunk_loader_thread(
                                     ),
                                 );
                             }
-                            rolling += 1;
-                            continue;
-                        }
-                        let mut loaded_from_disk = false;
-                        if uniformity == Uniformity::Unknown {
-                            uniformity = try_load_chunk(
-                                chunk_coord,
-                                &index_map_read,
-                                &index_map_delta,
-                                &mut chunk_data_file_read,
-                                &mut chunk_buffers,
-                            );
-                            if uniformity == Uniformity::NonUniform {
-                                loaded_from_disk = true;
-                            }
-                        }
-                        let chunk_start = calculate_chunk_start(&chunk_coord);
-                        if uniformity == Uniformity::Unknown {
-                            if !has_heightmap_been_calculated {
-                                let noise_samples = generate_noise_height_samples(
-                                    chunk_start.x,
-                                    chunk_start.z,
-                                    &fbm,
-                                );
-                                generate_terrain_heights(
-                                    &mut chunk_buffers.heightmap,
-                                    &noise_samples,
-                                );
-                                compute_heightmap_gradients(
-                                    &mut chunk_buffers.dhdx,
-                                    &mut chunk_buffers.dhdz,
-                                    &noise_samples,
-                                );
-                                has_heightmap_been_calculated = true;
-                            }
-                            uniformity = fast_get_uniformity(
-                                &chunk_buffers.heightmap,
-                                &chunk_buffers.dhdx,
-                                &chunk_buffers.dhdz,
-                                &chunk_start,
-                            );
                         }
-                        match uniformity {
-                            Uniformity::Air => {
-                                let _ =
-                                    write_sender.send(WriteCmd::WriteUniformAir { chunk_coord });
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::UniformAir,
-                                        ),
-                                    );
-                                }
+                        Uniformity::NonUniform => {
+                            if !buffers_filled {
+                                source.fill_chunk(chunk_start, &mut chunk_buffers);
                             }
-                            Uniformity::Dirt => {
-                                let _ =
-                                    write_sender.send(WriteCmd::WriteUniformDirt { chunk_coord });
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::UniformDirt,
-                                        ),
+                            stages[LoadStage::Generate as usize] = generate_start.elapsed();
+                            record_chunk_summary(&chunk_summaries, chunk_coord, &chunk_buffers);
+                            let mesh_start = Instant::now();
+                            let has_surface = match merged.as_mut() {
+                                _ if skip_mesh => false,
+                                Some(merged) => {
+                                    merge_chunk(
+                                        &chunk_buffers,
+                                        &mut lod_buffers,
+                                        &mut meshing_scratch,
+                                        chunk_coord,
+                                        merged,
                                     );
+                                    false
                                 }
-                            }
-                            Uniformity::NonUniform => {
-                                if !loaded_from_disk {
-                                    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-                                }
-                                let has_surface = lod_resolve_has_surface(
+                                None => lod_resolve_has_surface(
                                     &cluster_request,
                                     &chunk_buffers,
                                     &mut lod_buffers,
+                                    &mut meshing_scratch,
                                     chunk_coord,
                                     rolling,
                                     &chunk_spawn_channel,
-                                );
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::NonUniformTerrainChunk(
-                                                NonUniformTerrainChunk {
-                                                    //allocation here
-                                                    densities: Arc::from(
-                                                        &chunk_buffers.density[..],
-                                                    ),
-                                                    materials: Arc::from(
-                                                        &chunk_buffers.material[..],
-                                                    ),
-                                                },
+                                ),
+                            };
+                            stages[LoadStage::Mesh as usize] = mesh_start.elapsed();
+                            load_history.record(ChunkLoadEvent {
+                                chunk_coord,
+                                stages,
+                                thread_idx,
+                            });
+                            if in_simulation_range {
+                                let _ = terrain_chunk_map_modification_sender.send(
+                                    TerrainChunkMapModification::Insert(
+                                        chunk_coord,
+                                        TerrainChunk::NonUniformTerrainChunk(
+                                            chunk_pool.chunk_from(
+                                                &chunk_buffers.density,
+                                                &chunk_buffers.material,
                                             ),
                                         ),
-                                    );
-                                }
-                                has_entity_buffer[rolling] = has_surface;
+                                    ),
+                                );
                             }
-                            Uniformity::Unknown => unreachable!(),
-                        };
-                        rolling += 1;
-                    }
+                            has_entity_buffer[rolling] = has_surface;
+                        }
+                        Uniformity::Unknown => unreachable!(),
+                    };
+                    rolling += 1;
                 }
             }
-            let new_state = cluster_request.load_state_transition.to_state();
-            let _ = res_tx.send(ChunkResult {
-                has_entity: has_entity_buffer,
-                cluster_coord: cluster_request.position,
-                load_state: new_state,
-            });
-            #[cfg(feature = "debug")]
-            CLUSTERS_PROCESSED.fetch_add(1, Ordering::Relaxed);
         }
+        //the merged entity is sent before the chunk entities it replaces are despawned and the other way around on a split,
+        //so the cluster is never drawn with neither
+        if let Some(merged) = merged {
+            if let Some(prepared) = merged.into_prepared() {
+                let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToSpawnMerged((
+                    cluster_request.position,
+                    prepared,
+                )));
+            }
+            despawn_merged_chunks(&cluster_request, &chunk_spawn_channel);
+        } else if cluster_request.prev_merged {
+            let _ = chunk_spawn_channel
+                .send(ChunkSpawnResult::ToDespawnMerged(cluster_request.position));
+        }
+        let new_state = cluster_request.load_state_transition.to_state();
+        let _ = res_tx.send(ChunkResult {
+            has_entity: has_entity_buffer,
+            cluster_coord: cluster_request.position,
+            load_state: new_state,
+            read_failed: false,
+        });
+        #[cfg(feature = "debug")]
+        CLUSTERS_PROCESSED.fetch_add(1, Ordering::Relaxed);
     }
 }
 
-fn chunk_loader_thread(
-    #[cfg_attr(not(feature = "timers"), allow(unused_variables))] thread_idx: usize,
+//edited chunks are meshed first, the player is looking at them and each one is far cheaper than a cluster
+//None once the io threads are gone
+fn next_loaded_cluster(
+    loaded_cluster_receiver: &Receiver<LoadedCluster>,
+    remesh_receiver: &Receiver<RemeshRequest>,
+    remesh_result_sender: &Sender<RemeshResult>,
+    meshing_scratch: &mut MeshingScratch,
+) -> Option<LoadedCluster> {
+    loop {
+        loop {
+            match remesh_receiver.try_recv() {
+                Ok(request) => remesh_edited_chunk(request, remesh_result_sender, meshing_scratch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return loaded_cluster_receiver.recv().ok(),
+            }
+        }
+        match loaded_cluster_receiver.try_recv() {
+            Ok(loaded_cluster) => return Some(loaded_cluster),
+            Err(TryRecvError::Disconnected) => return None,
+            Err(TryRecvError::Empty) => {}
+        }
+        let mut select = Select::new();
+        select.recv(loaded_cluster_receiver);
+        select.recv(remesh_receiver);
+        let _ = select.ready();
+    }
+}
+
+fn remesh_edited_chunk(
+    request: RemeshRequest,
+    remesh_result_sender: &Sender<RemeshResult>,
+    meshing_scratch: &mut MeshingScratch,
+) {
+    let RemeshRequest {
+        chunk_coord,
+        densities,
+        materials,
+    } = request;
+    let (vertices, normals, material_ids, indices) = match *MESHING_MODE.read() {
+        MeshingMode::MarchingCubes => {
+            mc_mesh_generation_into(
+                &densities,
+                &materials,
+                SAMPLES_PER_CHUNK_DIM,
+                true,
+                DensityField::FullRes(&densities),
+                MaterialResolution::default(),
+                meshing_scratch,
+            );
+            meshing_scratch.copy_mesh()
+        }
+        MeshingMode::GreedyCubes => {
+            greedy_cubes_mesh_generation(&densities, &materials, SAMPLES_PER_CHUNK_DIM, true)
+        }
+    };
+    let mesh = (!indices.is_empty()).then(|| {
+        let mesh = prepare_bevy_mesh(vertices, normals, material_ids, indices);
+        //the edit still shows without a collider, the next edit to the chunk tries again
+        let collider = cached_chunk_collider(chunk_coord, &densities, &mesh.mesh)
+            .inspect_err(|e| warn!("{e}, the edit is left without one"))
+            .ok();
+        (mesh, collider)
+    });
+    let _ = remesh_result_sender.send(RemeshResult { chunk_coord, mesh });
+}
+
+fn chunk_loader_thread<S: DensitySource + ?Sized>(
+    thread_idx: usize,
     res_tx: Sender<ChunkResult>,
-    index_map_read: Arc<FxHashMap<(i16, i16, i16), u64>>,
-    index_map_delta: Arc<RwLock<FxHashMap<(i16, i16, i16), u64>>>,
-    mut chunk_data_file_read: File,
+    loaded_cluster_receiver: Receiver<LoadedCluster>,
+    remesh_receiver: Receiver<RemeshRequest>,
+    remesh_result_sender: Sender<RemeshResult>,
     chunk_spawn_channel: Sender<ChunkSpawnResult>,
-    fbm: GeneratorWrapper<SafeNode>,
+    source: Arc<S>,
+    heightmap_cache: Arc<HeightmapCache>,
     column_range_map_read_only: Arc<ColumnRangeMap>,
     write_sender: Sender<WriteCmd>,
-    priority_queue: Arc<(Mutex<BinaryHeap<ClusterRequest>>, Condvar)>,
     terrain_chunk_map_modification_sender: Sender<TerrainChunkMapModification>,
+    chunk_summaries: ChunkSummaries,
+    load_history: ChunkLoadHistory,
+    chunk_pool: ChunkBufferPool,
 ) {
     let mut chunk_buffers = ChunkBuffers::new();
-    let mut internal_queue = Vec::with_capacity(INTERNAL_WORKER_QUEUE_SIZE);
+    let mut meshing_scratch = MeshingScratch::new();
     loop {
-        let (binary_heap_lock, condvar) = &*priority_queue;
-        let mut binary_heap = binary_heap_lock.lock().unwrap();
-        while binary_heap.is_empty() {
-            binary_heap = condvar.wait(binary_heap).unwrap();
-        }
-        let num_to_pop = binary_heap.len().min(INTERNAL_WORKER_QUEUE_SIZE);
-        for _ in 0..num_to_pop {
-            internal_queue.push(binary_heap.pop().unwrap());
-        }
-        QUEUE_SIZE.store(binary_heap.len(), Ordering::Relaxed);
-        drop(binary_heap);
         #[cfg(feature = "debug")]
-        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx]
-            .store(internal_queue.len(), Ordering::Relaxed);
-        for cluster_request in internal_queue.drain(..) {
-            #[cfg(feature = "debug")]
-            INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].fetch_sub(1, Ordering::Relaxed);
-            let mut has_entity_buffer = [false; CHUNKS_PER_CLUSTER];
-            let mut rolling = 0;
-            let in_simulation_range = matches!(
-                cluster_request.load_state_transition,
-                LoadStateTransition::ToFullWithCollider | LoadStateTransition::NoChangeAddCollider
-            );
-            let min_chunk = cluster_coord_to_min_chunk_coord(cluster_request.position);
-            for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                    let mut has_heightmap_been_calculated = false;
-                    let column_cache = column_range_map_read_only.get_column(chunk_x, chunk_z);
-                    for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                        let chunk_coord = (chunk_x, chunk_y, chunk_z);
-                        let mut uniformity = column_cache.uniformity_at_y(chunk_y);
-                        if uniformity == Uniformity::Air {
-                            //cache hit
+        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].store(0, Ordering::Relaxed);
+        let Some(LoadedCluster {
+            request: cluster_request,
+            mut chunks_on_disk,
+            buried,
+        }) = next_loaded_cluster(
+            &loaded_cluster_receiver,
+            &remesh_receiver,
+            &remesh_result_sender,
+            &mut meshing_scratch,
+        )
+        else {
+            return; //io threads are gone
+        };
+        #[cfg(feature = "debug")]
+        INTERNAL_QUEUE_SIZES.get().unwrap()[thread_idx].store(1, Ordering::Relaxed);
+        let mut has_entity_buffer = [false; CHUNKS_PER_CLUSTER];
+        let mut rolling = 0;
+        let in_simulation_range = matches!(
+            cluster_request.load_state_transition,
+            LoadStateTransition::ToFullWithCollider | LoadStateTransition::NoChangeAddCollider
+        );
+        let min_chunk = cluster_coord_to_min_chunk_coord(cluster_request.position);
+        for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+            for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                let mut has_column_been_prepared = false;
+                let column_cache = column_range_map_read_only.get_column(chunk_x, chunk_z);
+                for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                    let chunk_coord = ChunkCoord(chunk_x, chunk_y, chunk_z);
+                    let mut uniformity = column_cache.uniformity_at_y(chunk_y);
+                    if uniformity == Uniformity::Air {
+                        //cache hit
+                        if in_simulation_range {
+                            let _ = terrain_chunk_map_modification_sender.send(
+                                TerrainChunkMapModification::Insert(
+                                    chunk_coord,
+                                    TerrainChunk::UniformAir,
+                                ),
+                            );
+                        }
+                        rolling += 1;
+                        continue;
+                    } else if uniformity == Uniformity::Dirt {
+                        //cache hit
+                        if in_simulation_range {
+                            let _ = terrain_chunk_map_modification_sender.send(
+                                TerrainChunkMapModification::Insert(
+                                    chunk_coord,
+                                    TerrainChunk::UniformDirt,
+                                ),
+                            );
+                        }
+                        rolling += 1;
+                        continue;
+                    }
+                    let mut buffers_filled = false; //loaded from disk or filled to classify the chunk
+                    let skip_mesh = buried[rolling] && !cluster_request.had_entity(rolling); //an entity it had is replaced as usual
+                    let mut stages = [Duration::ZERO; 3];
+                    if uniformity == Uniformity::Unknown {
+                        uniformity = take_chunk_from_disk(
+                            &mut chunks_on_disk,
+                            chunk_coord,
+                            &mut chunk_buffers,
+                            &mut stages[LoadStage::Disk as usize],
+                            &chunk_pool,
+                        );
+                        if uniformity == Uniformity::NonUniform {
+                            buffers_filled = true;
+                        }
+                    }
+                    let chunk_start = calculate_chunk_start(&chunk_coord);
+                    let generate_start = Instant::now();
+                    if uniformity == Uniformity::Unknown {
+                        if !has_column_been_prepared {
+                            heightmap_cache.prepare_column(
+                                &*source,
+                                (chunk_x, chunk_z),
+                                &chunk_start,
+                                &mut chunk_buffers,
+                            );
+                            has_column_been_prepared = true;
+                        }
+                        uniformity = source.classify(&chunk_start, &mut chunk_buffers);
+                        //outside the simulation radius nothing needs a buried chunk's samples either
+                        if uniformity == Uniformity::Unknown && skip_mesh && !in_simulation_range {
+                            rolling += 1;
+                            continue;
+                        }
+                        if uniformity == Uniformity::Unknown {
+                            source.fill_chunk(chunk_start, &mut chunk_buffers);
+                            uniformity =
+                                chunk_uniformity(&chunk_buffers.density, &chunk_buffers.material);
+                            buffers_filled = true;
+                        }
+                    }
+                    match uniformity {
+                        Uniformity::Air => {
+                            let _ = write_sender.send(WriteCmd::WriteUniformAir { chunk_coord });
                             if in_simulation_range {
                                 let _ = terrain_chunk_map_modification_sender.send(
                                     TerrainChunkMapModification::Insert(
@@ -737,10 +1818,9 @@ fn chunk_loader_thread(
                                     ),
                                 );
                             }
-                            rolling += 1;
-                            continue;
-                        } else if uniformity == Uniformity::Dirt {
-                            //cache hit
+                        }
+                        Uniformity::Dirt => {
+                            let _ = write_sender.send(WriteCmd::WriteUniformDirt { chunk_coord });
                             if in_simulation_range {
                                 let _ = terrain_chunk_map_modification_sender.send(
                                     TerrainChunkMapModification::Insert(
@@ -749,135 +1829,126 @@ fn chunk_loader_thread(
                                     ),
                                 );
                             }
-                            rolling += 1;
-                            continue;
-                        }
-                        let mut loaded_from_disk = false;
-                        if uniformity == Uniformity::Unknown {
-                            uniformity = try_load_chunk(
-                                chunk_coord,
-                                &index_map_read,
-                                &index_map_delta,
-                                &mut chunk_data_file_read,
-                                &mut chunk_buffers,
-                            );
-                            if uniformity == Uniformity::NonUniform {
-                                loaded_from_disk = true;
-                            }
-                        }
-                        let chunk_start = calculate_chunk_start(&chunk_coord);
-                        if uniformity == Uniformity::Unknown {
-                            if !has_heightmap_been_calculated {
-                                let noise_samples = generate_noise_height_samples(
-                                    chunk_start.x,
-                                    chunk_start.z,
-                                    &fbm,
-                                );
-                                generate_terrain_heights(
-                                    &mut chunk_buffers.heightmap,
-                                    &noise_samples,
-                                );
-                                compute_heightmap_gradients(
-                                    &mut chunk_buffers.dhdx,
-                                    &mut chunk_buffers.dhdz,
-                                    &noise_samples,
-                                );
-                                has_heightmap_been_calculated = true;
-                            }
-                            uniformity = fast_get_uniformity(
-                                &chunk_buffers.heightmap,
-                                &chunk_buffers.dhdx,
-                                &chunk_buffers.dhdz,
-                                &chunk_start,
-                            );
                         }
-                        match uniformity {
-                            Uniformity::Air => {
-                                let _ =
-                                    write_sender.send(WriteCmd::WriteUniformAir { chunk_coord });
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::UniformAir,
-                                        ),
-                                    );
-                                }
-                            }
-                            Uniformity::Dirt => {
-                                let _ =
-                                    write_sender.send(WriteCmd::WriteUniformDirt { chunk_coord });
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::UniformDirt,
-                                        ),
-                                    );
-                                }
+                        Uniformity::NonUniform => {
+                            if !buffers_filled {
+                                source.fill_chunk(chunk_start, &mut chunk_buffers);
                             }
-                            Uniformity::NonUniform => {
-                                if !loaded_from_disk {
-                                    generate_chunk_into_buffers(chunk_start, &mut chunk_buffers);
-                                }
-                                let has_surface = resolve_has_surface(
+                            stages[LoadStage::Generate as usize] = generate_start.elapsed();
+                            record_chunk_summary(&chunk_summaries, chunk_coord, &chunk_buffers);
+                            let mesh_start = Instant::now();
+                            let has_surface = !skip_mesh
+                                && resolve_has_surface(
                                     &cluster_request,
                                     &chunk_buffers,
+                                    &mut meshing_scratch,
                                     chunk_coord,
                                     rolling,
                                     &chunk_spawn_channel,
                                 );
-                                if in_simulation_range {
-                                    let _ = terrain_chunk_map_modification_sender.send(
-                                        TerrainChunkMapModification::Insert(
-                                            chunk_coord,
-                                            TerrainChunk::NonUniformTerrainChunk(
-                                                NonUniformTerrainChunk {
-                                                    //allocation here
-                                                    densities: Arc::from(
-                                                        &chunk_buffers.density[..],
-                                                    ),
-                                                    materials: Arc::from(
-                                                        &chunk_buffers.material[..],
-                                                    ),
-                                                },
+                            stages[LoadStage::Mesh as usize] = mesh_start.elapsed();
+                            load_history.record(ChunkLoadEvent {
+                                chunk_coord,
+                                stages,
+                                thread_idx,
+                            });
+                            if in_simulation_range {
+                                let _ = terrain_chunk_map_modification_sender.send(
+                                    TerrainChunkMapModification::Insert(
+                                        chunk_coord,
+                                        TerrainChunk::NonUniformTerrainChunk(
+                                            chunk_pool.chunk_from(
+                                                &chunk_buffers.density,
+                                                &chunk_buffers.material,
                                             ),
                                         ),
-                                    );
-                                }
-                                has_entity_buffer[rolling] = has_surface;
+                                    ),
+                                );
                             }
-                            Uniformity::Unknown => unreachable!(),
-                        };
-                        rolling += 1;
-                    }
+                            has_entity_buffer[rolling] = has_surface;
+                        }
+                        Uniformity::Unknown => unreachable!(),
+                    };
+                    rolling += 1;
                 }
             }
-            let new_state = cluster_request.load_state_transition.to_state();
-            let _ = res_tx.send(ChunkResult {
-                has_entity: has_entity_buffer,
-                cluster_coord: cluster_request.position,
-                load_state: new_state,
-            });
-            #[cfg(feature = "debug")]
-            CLUSTERS_PROCESSED.fetch_add(1, Ordering::Relaxed);
+        }
+        let new_state = cluster_request.load_state_transition.to_state();
+        let _ = res_tx.send(ChunkResult {
+            has_entity: has_entity_buffer,
+            cluster_coord: cluster_request.position,
+            load_state: new_state,
+            read_failed: false,
+        });
+        #[cfg(feature = "debug")]
+        CLUSTERS_PROCESSED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+//velocity of the center between two passes blended into the running estimate, a teleport starts over from rest
+fn smoothed_travel(travel: Vec3, moved: Vec3, elapsed_secs: f32) -> Vec3 {
+    if elapsed_secs <= 0.0 {
+        return travel;
+    }
+    let velocity = moved / elapsed_secs;
+    if velocity.length_squared() > MAX_TRAVEL_SPEED * MAX_TRAVEL_SPEED {
+        return Vec3::ZERO;
+    }
+    travel.lerp(velocity, (elapsed_secs / TRAVEL_SMOOTHING_SECS).min(1.0))
+}
+
+//clusters near where the center will be PREFETCH_LOOKAHEAD seconds from now load as if they were that close to it
+//the nearer of the two distances wins, so nothing around the center is pushed back behind the prefetch
+fn lead_requests_along_travel(request_buffer: &mut [ClusterRequest], center: Vec3, travel: Vec3) {
+    if travel == Vec3::ZERO {
+        return;
+    }
+    let predicted = center + travel * PREFETCH_LOOKAHEAD;
+    for request in request_buffer.iter_mut() {
+        let predicted_distance_squared =
+            predicted.distance_squared(cluster_coord_to_world_center(&request.position));
+        request.distance_squared = request.distance_squared.min(predicted_distance_squared);
+    }
+}
+
+//terrain in front of the camera loads before terrain behind it, the simulation radius keeps plain distance order
+//since the ground under the player matters whichever way they look. only new requests are scored, queued ones keep theirs
+fn favor_requests_in_view(
+    request_buffer: &mut [ClusterRequest],
+    center: Vec3,
+    view_direction: Vec3,
+) {
+    if view_direction == Vec3::ZERO {
+        return;
+    }
+    for request in request_buffer.iter_mut() {
+        let offset = cluster_coord_to_world_center(&request.position) - center;
+        let distance_squared = offset.length_squared();
+        if distance_squared > SIMULATION_RADIUS_SQUARED
+            && offset.dot(view_direction) > VIEW_CONE_COS * distance_squared.sqrt()
+        {
+            request.distance_squared *= VIEW_PRIORITY_SCALE;
         }
     }
 }
 
-//owns the main svo
+//owns the main svo, digging only flips chunk flags in it
 //recieves and handles modification requests
-//produces chunk load requests for chunk_loader_thread and recieves the data
+//produces chunk load requests for chunk_loader_thread around the player and every other observer and recieves the data
 //sends chunks to be spawned to main thread
 fn svo_manager_thread(
     results_channel: Receiver<ChunkResult>,
     moveable_center: Arc<Mutex<Vec3>>,
+    view_direction: Arc<Mutex<Vec3>>,
+    terrain_observers: TerrainObservers,
     chunk_spawn_channel: Sender<ChunkSpawnResult>,
-    mut svo: SvoNode,
+    svo: TerrainSvo,
     priority_queue: Arc<(Mutex<BinaryHeap<ClusterRequest>>, Condvar)>,
-    terrain_chunk_map: Arc<Mutex<FxHashMap<(i16, i16, i16), TerrainChunk>>>,
+    terrain_chunk_map: Arc<ChunkStore>,
     terrain_chunk_map_modification_reciever: Receiver<TerrainChunkMapModification>,
     terrain_chunk_map_modification_sender: Sender<TerrainChunkMapModification>,
+    mut chunk_pager: ChunkPager,
+    cluster_occupancy: Option<SharedClusterOccupancy>,
     lods: bool,
 ) {
     #[cfg(feature = "timers")]
@@ -886,21 +1957,33 @@ fn svo_manager_thread(
     let mut first_completion_printed = false;
     let mut request_buffer = Vec::new();
     let mut chunks_being_loaded = FxHashSet::default();
+    let mut visited = ClusterVisitMask::default();
+    let queue_clock = Instant::now();
     let moveable_center_lock = moveable_center.lock().unwrap();
     let initial_moveable_center = *moveable_center_lock;
     drop(moveable_center_lock);
+    let mut horizon = HorizonCuller::new(initial_moveable_center);
+    let mut last_pass_streaming = *STREAMING_CONFIG.read();
+    //the first load only covers the player, other observers are picked up by the passes after it
+    let initial_observers = [TerrainObserver {
+        position: initial_moveable_center,
+        render_radius_squared: SIMULATION_RADIUS_SQUARED,
+        vertical_render_radius_squared: 0.0,
+    }];
     if lods {
-        svo.lod_fill_missing_chunks_in_radius(
-            &initial_moveable_center,
-            SIMULATION_RADIUS_SQUARED,
+        svo.0.read().lod_fill_missing_chunks(
+            &initial_observers,
             &chunks_being_loaded,
+            &mut visited,
             &mut request_buffer,
+            &mut horizon,
+            &last_pass_streaming,
         );
     } else {
-        svo.fill_missing_chunks_in_radius(
-            &initial_moveable_center,
-            SIMULATION_RADIUS_SQUARED,
+        svo.0.read().fill_missing_chunks(
+            &initial_observers,
             &chunks_being_loaded,
+            &mut visited,
             &mut request_buffer,
         );
     }
@@ -913,62 +1996,174 @@ fn svo_manager_thread(
     for request in &request_buffer {
         chunks_being_loaded.insert(request.position);
     }
+    //the initial load is exactly these clusters, done once each has a result and its chunks are in the map
+    let mut initial_pending = chunks_being_loaded.clone();
+    let mut initial_results_in = initial_pending.is_empty();
+    INITIAL_LOAD_TOTAL.store(
+        initial_pending.len() * CHUNKS_PER_CLUSTER,
+        Ordering::Relaxed,
+    );
     let (binary_heap_lock, condvar) = &*priority_queue;
     {
         let mut binary_heap = binary_heap_lock.lock().unwrap();
-        for request in request_buffer.drain(..) {
+        let queued_secs = queue_clock.elapsed().as_secs_f32();
+        for mut request in request_buffer.drain(..) {
+            request.queued_secs = queued_secs;
             binary_heap.push(request);
         }
         QUEUE_SIZE.store(binary_heap.len(), Ordering::Relaxed);
     }
     condvar.notify_all();
     let mut clusters_to_deallocate = Vec::new();
+    let mut last_pass_center = initial_moveable_center;
+    let mut last_pass_secs = 0.0;
+    let mut travel = Vec3::ZERO; //smoothed velocity of the center
+    let mut observers = Vec::new(); //the player first, then everything in terrain_observers
+    let mut observer_centers = Vec::new();
+    let mut last_pass_observers = Vec::new();
+    let mut pass_pending = true; //the startup fill only covered the simulation radius
+    let mut failed_reads: Vec<((i16, i16, i16), f32)> = Vec::new(); //clusters held back from requests until the secs
     loop {
         let moveable_center_lock = moveable_center.lock().unwrap();
         let moveable_center = *moveable_center_lock;
         drop(moveable_center_lock);
-        let mut terrain_map_lock = terrain_chunk_map.lock().unwrap();
+        let now_secs = queue_clock.elapsed().as_secs_f32();
+        failed_reads.retain(|&(cluster_coord, retry_secs)| {
+            if retry_secs > now_secs {
+                return true;
+            }
+            chunks_being_loaded.remove(&cluster_coord);
+            pass_pending = true;
+            false
+        });
+        observers.clear();
+        observers.push(TerrainObserver {
+            position: moveable_center,
+            render_radius_squared: f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)),
+            vertical_render_radius_squared: f32::from_bits(
+                VERTICAL_RENDER_RADIUS_SQUARED.load(Ordering::Relaxed),
+            ),
+        });
+        terrain_observers.copy_into(&mut observers);
+        let streaming = *STREAMING_CONFIG.read();
+        //a pass only does work when results came in, the map changed, an observer moved or a radius changed
+        //otherwise sleep until one of the channels has something or it is time to poll the observers again
+        if !pass_pending
+            && streaming == last_pass_streaming
+            && !observers_changed(&last_pass_observers, &observers, SVO_CENTER_MOVE_THRESHOLD)
+            && results_channel.is_empty()
+            && terrain_chunk_map_modification_reciever.is_empty()
+        {
+            let mut select = Select::new();
+            select.recv(&results_channel);
+            select.recv(&terrain_chunk_map_modification_reciever);
+            let _ = select.ready_timeout(SVO_IDLE_POLL_INTERVAL);
+            continue;
+        }
+        pass_pending = false;
+        let pass_secs = queue_clock.elapsed().as_secs_f32();
+        travel = smoothed_travel(
+            travel,
+            moveable_center - last_pass_center,
+            pass_secs - last_pass_secs,
+        );
+        last_pass_center = moveable_center;
+        last_pass_secs = pass_secs;
+        last_pass_observers.clone_from(&observers);
+        last_pass_streaming = streaming;
+        observer_centers.clear();
+        observer_centers.extend(observers.iter().map(|observer| observer.position));
         while let Ok(modification) = terrain_chunk_map_modification_reciever.try_recv() {
-            match modification {
+            let replaced = match modification {
                 TerrainChunkMapModification::Insert(chunk_coord, terrain_chunk) => {
-                    terrain_map_lock.insert(chunk_coord, terrain_chunk);
+                    terrain_chunk_map.insert(chunk_coord, terrain_chunk)
                 }
                 TerrainChunkMapModification::Remove(chunk_coord) => {
-                    terrain_map_lock.remove(&chunk_coord);
+                    terrain_chunk_map.remove(&chunk_coord)
                 }
+            };
+            if let Some(replaced) = replaced {
+                chunk_pager.chunk_pool.recycle(replaced);
             }
         }
-        for chunk_coord in terrain_map_lock.keys() {
-            let lower_cluster_coord = chunk_coord_to_cluster_coord(chunk_coord);
-            let distance_squared = moveable_center
-                .distance_squared(cluster_coord_to_world_center(&lower_cluster_coord));
-            if distance_squared > SIMULATION_RADIUS_SQUARED {
-                let _ = terrain_chunk_map_modification_sender
-                    .send(TerrainChunkMapModification::Remove(*chunk_coord));
-            }
+        //loaders send a cluster's chunks before its result, so the last ones were applied just above
+        if initial_results_in {
+            let _ = chunk_spawn_channel.send(ChunkSpawnResult::InitialLoadDone);
+            initial_results_in = false;
         }
-        drop(terrain_map_lock);
+        //evicted chunks leaving the simulation radius are forgotten too, the loader brings them back with their cluster
+        //chunks stay while any observer is close enough for their cluster to be simulated
+        terrain_chunk_map.for_each_shard(|chunks| {
+            for chunk_coord in chunks.keys().chain(chunks.evicted()) {
+                let lower_cluster_coord = chunk_coord_to_cluster_coord(chunk_coord);
+                let distance_squared = nearest_distance_squared(
+                    &observers,
+                    cluster_coord_to_world_center(&lower_cluster_coord),
+                );
+                if distance_squared > SIMULATION_RADIUS_SQUARED {
+                    let _ = terrain_chunk_map_modification_sender
+                        .send(TerrainChunkMapModification::Remove(*chunk_coord));
+                }
+            }
+        });
+        chunk_pager.reload_near(&terrain_chunk_map, &observer_centers);
+        chunk_pager.evict(&terrain_chunk_map, &observer_centers);
+        let mut occupancy = cluster_occupancy
+            .as_ref()
+            .map(|occupancy| occupancy.0.lock());
         while let Ok(result) = results_channel.try_recv() {
-            svo.insert(result.cluster_coord, result.has_entity, result.load_state);
+            //stays out of the svo and in chunks_being_loaded, so whatever lod it had is kept until the retry loads
+            if result.read_failed {
+                failed_reads.push((result.cluster_coord, pass_secs + FAILED_READ_RETRY_SECS));
+                continue;
+            }
+            if let Some(occupancy) = occupancy.as_mut() {
+                occupancy.record(result.cluster_coord, &result.has_entity, result.load_state);
+            }
+            if let Err(e) =
+                svo.0
+                    .write()
+                    .insert(result.cluster_coord, result.has_entity, result.load_state)
+            {
+                warn!("Dropped a cluster result: {e:?}");
+            }
+            horizon.record_cluster(result.cluster_coord, &result.has_entity);
             chunks_being_loaded.remove(&result.cluster_coord);
+            if initial_pending.remove(&result.cluster_coord) {
+                INITIAL_LOAD_LOADED.fetch_add(CHUNKS_PER_CLUSTER, Ordering::Relaxed);
+                if initial_pending.is_empty() {
+                    initial_results_in = true;
+                    pass_pending = true;
+                }
+            }
         }
-        svo.query_chunks_outside_sphere(&moveable_center, &mut clusters_to_deallocate);
-        for (chunk_coord, _) in &clusters_to_deallocate {
-            svo.delete(*chunk_coord);
+        drop(occupancy);
+        svo.0
+            .read()
+            .query_chunks_outside_observers(&observers, &mut clusters_to_deallocate);
+        if !clusters_to_deallocate.is_empty() {
+            let mut svo = svo.0.write();
+            for (chunk_coord, _, _) in &clusters_to_deallocate {
+                svo.delete(*chunk_coord);
+            }
         }
         let mut roller = 0;
-        let mut terrain_map_lock = terrain_chunk_map.lock().unwrap();
-        for (cluster_coord, has_entity) in clusters_to_deallocate.drain(..) {
+        for (cluster_coord, has_entity, load_state) in clusters_to_deallocate.drain(..) {
+            if load_state == LoadState::Merged {
+                let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToDespawnMerged(cluster_coord));
+            }
             let min_chunk = cluster_coord_to_min_chunk_coord(cluster_coord);
             for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
                 for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
                     for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
-                        let chunk_coord = (chunk_x, chunk_y, chunk_z);
+                        let chunk_coord = ChunkCoord(chunk_x, chunk_y, chunk_z);
                         if has_entity[roller] {
                             let _ =
                                 chunk_spawn_channel.send(ChunkSpawnResult::ToDespawn(chunk_coord));
                         }
-                        terrain_map_lock.remove(&chunk_coord);
+                        if let Some(chunk) = terrain_chunk_map.remove(&chunk_coord) {
+                            chunk_pager.chunk_pool.recycle(chunk);
+                        }
                         roller += 1;
                     }
                 }
@@ -976,23 +2171,48 @@ fn svo_manager_thread(
             chunks_being_loaded.remove(&cluster_coord);
             roller = 0;
         }
-        drop(terrain_map_lock);
         if QUEUE_SIZE.load(Ordering::Relaxed) < PRIORITY_QUEUE_MAX_SIZE {
             if lods {
-                svo.lod_fill_missing_chunks_in_radius(
-                    &moveable_center,
-                    f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)),
+                horizon.begin_pass(moveable_center, observers[0].render_radius_squared);
+                svo.0.read().lod_fill_missing_chunks(
+                    &observers,
                     &chunks_being_loaded,
+                    &mut visited,
                     &mut request_buffer,
+                    &mut horizon,
+                    &streaming,
                 );
             } else {
-                svo.fill_missing_chunks_in_radius(
-                    &moveable_center,
-                    f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)),
+                svo.0.read().fill_missing_chunks(
+                    &observers,
                     &chunks_being_loaded,
+                    &mut visited,
                     &mut request_buffer,
                 );
             }
+            //clusters known to load without a surface at their lod go straight into the svo instead of the queue
+            if let Some(cluster_occupancy) = &cluster_occupancy {
+                let occupancy = cluster_occupancy.0.lock();
+                request_buffer.retain(|request| {
+                    let load_state = request.load_state_transition.to_state();
+                    if !occupancy.is_known_empty(request, load_state) {
+                        return true;
+                    }
+                    let has_entity = [false; CHUNKS_PER_CLUSTER];
+                    if let Err(e) = svo
+                        .0
+                        .write()
+                        .insert(request.position, has_entity, load_state)
+                    {
+                        warn!("Dropped a known empty cluster: {e:?}");
+                    }
+                    horizon.record_cluster(request.position, &has_entity);
+                    false
+                });
+            }
+            lead_requests_along_travel(&mut request_buffer, moveable_center, travel);
+            let view_direction = *view_direction.lock().unwrap();
+            favor_requests_in_view(&mut request_buffer, moveable_center, view_direction);
             request_buffer.sort_unstable_by(|a, b| {
                 a.distance_squared
                     .partial_cmp(&b.distance_squared)
@@ -1006,7 +2226,9 @@ fn svo_manager_thread(
             let (binary_heap_lock, condvar) = &*priority_queue;
             {
                 let mut binary_heap = binary_heap_lock.lock().unwrap();
-                for request in request_buffer.drain(..) {
+                let queued_secs = queue_clock.elapsed().as_secs_f32();
+                for mut request in request_buffer.drain(..) {
+                    request.queued_secs = queued_secs;
                     binary_heap.push(request);
                 }
                 QUEUE_SIZE.store(binary_heap.len(), Ordering::Relaxed);
@@ -1024,6 +2246,14 @@ fn svo_manager_thread(
     }
 }
 
+pub fn update_initial_load_progress(mut initial_load: ResMut<InitialLoadProgress>) {
+    if initial_load.complete {
+        return;
+    }
+    initial_load.total = INITIAL_LOAD_TOTAL.load(Ordering::Relaxed);
+    initial_load.loaded = INITIAL_LOAD_LOADED.load(Ordering::Relaxed);
+}
+
 //recieves chunks that were loaded and need spawning from the manager
 pub fn chunk_spawn_reciever(
     mut commands: Commands,
@@ -1031,33 +2261,56 @@ pub fn chunk_spawn_reciever(
     mut mesh_handles: ResMut<Assets<Mesh>>,
     req_rx: Res<ChunkSpawnReciever>,
     mut chunk_entity_map: ResMut<ChunkEntityMap>,
+    mut merged_cluster_map: ResMut<MergedClusterMap>,
+    lod_fade: Option<Res<LodFadeMaterials>>,
+    time: Res<Time>,
     frame_start: Res<FrameStart>,
+    mut initial_load: ResMut<InitialLoadProgress>,
+    mut initial_load_writer: MessageWriter<InitialLoadComplete>,
+    mut chunk_events: ChunkEventWriters,
 ) {
     const TARGET_FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 90);
+    const MAX_MESH_UPLOADS_PER_FRAME: usize = 48;
+    let mut mesh_uploads = 0;
     while let Ok(request) = req_rx.0.try_recv() {
+        #[cfg(feature = "debug")]
+        if request
+            .prepared_mesh()
+            .is_some_and(|prepared| prepared.mesh.count_vertices() == 0)
+        {
+            EMPTY_MESHES_SPAWNED.fetch_add(1, Ordering::Relaxed); //should stay at zero
+        }
         match request {
-            ChunkSpawnResult::ToSpawn((chunk_coord, mesh)) => {
+            ChunkSpawnResult::ToSpawn((chunk_coord, prepared)) => {
                 //use option in case a chunk is spawned, despawned, and spawned again but the second spawn comes before the despawn
                 if chunk_entity_map.get_option(chunk_coord).is_none() {
-                    let mesh_handle = mesh_handles.add(mesh);
+                    let mesh_handle = mesh_handles.add(prepared.mesh);
+                    mesh_uploads += 1;
                     let entity = commands
                         .spawn((
                             Mesh3d(mesh_handle.clone()),
+                            prepared.aabb,
                             ChunkTag,
                             Transform::from_translation(chunk_coord_to_world_pos(&chunk_coord)),
                             MeshMaterial3d(standard_material.0.clone()),
                         ))
                         .id();
                     chunk_entity_map.insert(chunk_coord, (entity, mesh_handle));
+                    chunk_events.spawned(chunk_coord, entity);
                 }
             }
             ChunkSpawnResult::ToGiveCollider((chunk_coord, collider)) => {
                 let (entity, _) = chunk_entity_map.get(chunk_coord);
-                commands.entity(entity).insert(collider);
+                commands
+                    .entity(entity)
+                    .remove::<StashedCollider>()
+                    .insert(collider);
             }
             ChunkSpawnResult::ToRemoveCollider(chunk_coord) => {
                 let (entity, _) = chunk_entity_map.get(chunk_coord);
-                commands.entity(entity).remove::<Collider>();
+                commands
+                    .entity(entity)
+                    .remove::<(Collider, StashedCollider)>();
             }
             ChunkSpawnResult::ToDespawn(chunk_coord) => {
                 //use option in case the corresponding ToSpawn was skipped due to a duplicate, leaving nothing to remove
@@ -1067,42 +2320,81 @@ pub fn chunk_spawn_reciever(
                     chunk_entity_map.remove(chunk_coord);
                     mesh_handles.remove(&mesh_handle);
                     commands.entity(entity).despawn();
+                    chunk_events.despawned(chunk_coord, entity);
                 }
             }
-            ChunkSpawnResult::ToChangeLodAddCollider((chunk_coord, new_mesh, new_collider)) => {
+            ChunkSpawnResult::ToChangeLodAddCollider((chunk_coord, prepared, new_collider)) => {
                 //use option to handle the case where the chunk was despawned while the LOD change was in flight
                 if let Some((entity, mesh_handle)) = chunk_entity_map.get_option(chunk_coord) {
-                    if let Some(aabb) = new_mesh.compute_aabb() {
-                        commands.entity(*entity).insert(aabb);
-                    }
-                    mesh_handles.insert(mesh_handle, new_mesh).unwrap();
-                    commands.entity(*entity).insert(new_collider);
+                    let (entity, mesh_handle) = (*entity, mesh_handle.clone());
+                    commands
+                        .entity(entity)
+                        .remove::<StashedCollider>()
+                        .insert((prepared.aabb, new_collider));
+                    swap_lod_mesh(
+                        &mut commands,
+                        &mut mesh_handles,
+                        &mut chunk_entity_map,
+                        lod_fade.as_deref(),
+                        time.elapsed_secs(),
+                        chunk_coord,
+                        entity,
+                        mesh_handle,
+                        prepared.mesh,
+                    );
+                    mesh_uploads += 1;
+                    chunk_events.meshed(chunk_coord, entity);
                 }
             }
-            ChunkSpawnResult::ToChangeLod((chunk_coord, new_mesh)) => {
+            ChunkSpawnResult::ToChangeLod((chunk_coord, prepared)) => {
                 //use option to handle the case where the chunk was despawned while the LOD change was in flight
                 if let Some((entity, mesh_handle)) = chunk_entity_map.get_option(chunk_coord) {
-                    if let Some(aabb) = new_mesh.compute_aabb() {
-                        commands.entity(*entity).insert(aabb);
-                    }
-                    mesh_handles.insert(mesh_handle, new_mesh).unwrap();
+                    let (entity, mesh_handle) = (*entity, mesh_handle.clone());
+                    commands.entity(entity).insert(prepared.aabb);
+                    swap_lod_mesh(
+                        &mut commands,
+                        &mut mesh_handles,
+                        &mut chunk_entity_map,
+                        lod_fade.as_deref(),
+                        time.elapsed_secs(),
+                        chunk_coord,
+                        entity,
+                        mesh_handle,
+                        prepared.mesh,
+                    );
+                    mesh_uploads += 1;
+                    chunk_events.meshed(chunk_coord, entity);
                 }
             }
-            ChunkSpawnResult::ToChangeLodRemoveCollider((chunk_coord, new_mesh)) => {
+            ChunkSpawnResult::ToChangeLodRemoveCollider((chunk_coord, prepared)) => {
                 let (entity, mesh_handle) = chunk_entity_map.get(chunk_coord);
-                if let Some(aabb) = new_mesh.compute_aabb() {
-                    commands.entity(entity).insert(aabb);
-                }
-                mesh_handles.insert(&mesh_handle, new_mesh).unwrap();
-                commands.entity(entity).remove::<Collider>();
+                commands.entity(entity).insert(prepared.aabb);
+                swap_lod_mesh(
+                    &mut commands,
+                    &mut mesh_handles,
+                    &mut chunk_entity_map,
+                    lod_fade.as_deref(),
+                    time.elapsed_secs(),
+                    chunk_coord,
+                    entity,
+                    mesh_handle,
+                    prepared.mesh,
+                );
+                mesh_uploads += 1;
+                chunk_events.meshed(chunk_coord, entity);
+                commands
+                    .entity(entity)
+                    .remove::<(Collider, StashedCollider)>();
             }
-            ChunkSpawnResult::ToSpawnWithCollider((chunk_coord, collider, mesh)) => {
+            ChunkSpawnResult::ToSpawnWithCollider((chunk_coord, collider, prepared)) => {
                 //use option in case a chunk is spawned, despawned, and spawned again but the second spawn comes before the despawn
                 if chunk_entity_map.get_option(chunk_coord).is_none() {
-                    let mesh_handle = mesh_handles.add(mesh);
+                    let mesh_handle = mesh_handles.add(prepared.mesh);
+                    mesh_uploads += 1;
                     let entity = commands
                         .spawn((
                             Mesh3d(mesh_handle.clone()),
+                            prepared.aabb,
                             collider,
                             ChunkTag,
                             Transform::from_translation(chunk_coord_to_world_pos(&chunk_coord)),
@@ -1110,17 +2402,91 @@ pub fn chunk_spawn_reciever(
                         ))
                         .id();
                     chunk_entity_map.insert(chunk_coord, (entity, mesh_handle));
+                    chunk_events.spawned(chunk_coord, entity);
+                }
+            }
+            ChunkSpawnResult::ToSpawnMerged((cluster_coord, prepared)) => {
+                match merged_cluster_map.0.get(&cluster_coord) {
+                    Some((entity, mesh_handle)) => {
+                        commands.entity(*entity).insert(prepared.aabb);
+                        mesh_handles.insert(mesh_handle, prepared.mesh).unwrap();
+                    }
+                    None => {
+                        let mesh_handle = mesh_handles.add(prepared.mesh);
+                        let entity = commands
+                            .spawn((
+                                Mesh3d(mesh_handle.clone()),
+                                prepared.aabb,
+                                MergedClusterTag,
+                                Transform::from_translation(cluster_coord_to_world_center(
+                                    &cluster_coord,
+                                )),
+                                MeshMaterial3d(standard_material.0.clone()),
+                            ))
+                            .id();
+                        merged_cluster_map
+                            .0
+                            .insert(cluster_coord, (entity, mesh_handle));
+                    }
+                }
+                mesh_uploads += 1;
+            }
+            ChunkSpawnResult::ToDespawnMerged(cluster_coord) => {
+                if let Some((entity, mesh_handle)) = merged_cluster_map.0.remove(&cluster_coord) {
+                    mesh_handles.remove(&mesh_handle);
+                    commands.entity(entity).despawn();
                 }
             }
+            ChunkSpawnResult::InitialLoadDone => {
+                initial_load.complete = true;
+                initial_load_writer.write(InitialLoadComplete);
+                //the ground under the player has its collider now if there is any in range, so nothing is left to wait for
+                INITIAL_CHUNKS_LOADED.store(true, Ordering::Relaxed);
+                info!("Initial load of {} chunks complete.", initial_load.total);
+            }
         }
-        if frame_start.0.elapsed() >= TARGET_FRAME_TIME {
-            return; //if this fn would cause fps to drop below a certain threshold, wait until next frame to continue processing requests
+        //every added mesh is another buffer for the render extract to copy, cap them so a burst of results cant stall a frame
+        if mesh_uploads >= MAX_MESH_UPLOADS_PER_FRAME
+            || frame_start.0.elapsed() >= TARGET_FRAME_TIME
+        {
+            break; //if this fn would cause fps to drop below a certain threshold, wait until next frame to continue processing requests
         }
     }
     #[cfg(feature = "debug")]
     CHUNK_SPAWN_RECEIVER_QUEUE_SIZE.store(req_rx.0.len(), Ordering::Relaxed);
 }
 
+//the new lod mesh goes in place of the old one, or through a cross fade when lod fading is on
+fn swap_lod_mesh(
+    commands: &mut Commands,
+    mesh_handles: &mut Assets<Mesh>,
+    chunk_entity_map: &mut ChunkEntityMap,
+    lod_fade: Option<&LodFadeMaterials>,
+    now: f32,
+    chunk_coord: ChunkCoord,
+    entity: Entity,
+    mesh_handle: Handle<Mesh>,
+    mesh: Mesh,
+) {
+    match lod_fade {
+        Some(lod_fade) => {
+            let new_mesh_handle = mesh_handles.add(mesh);
+            chunk_entity_map.replace_mesh_handle(chunk_coord, new_mesh_handle.clone());
+            lod_fade.begin(
+                commands,
+                now,
+                entity,
+                chunk_coord,
+                mesh_handle,
+                new_mesh_handle,
+            );
+        }
+        None => {
+            mesh_handles.insert(&mesh_handle, mesh).unwrap();
+        }
+    }
+}
+
 //downscales to new resolution from full resolution
 //searches downscaled densities for surface
 //if has surface runs marching cubes, generates mesh, and sends either a spawn command or a change lod command based on if it was previously loaded or not
@@ -1130,10 +2496,12 @@ fn process_lod(
     density_buffer: &[i16],
     material_buffer: &[MaterialCode],
     chunk_spawn_channel: &Sender<ChunkSpawnResult>,
-    chunk_coord: (i16, i16, i16),
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
     reduced_density_buffer: &mut [i16],
     reduced_material_buffer: &mut [MaterialCode],
     out_samples_per_chunk_dim: usize,
+    neighbor_samples_per_chunk_dim: [usize; 6],
     had_entity: bool,
     prev_in_simulation_radius: bool,
 ) -> bool {
@@ -1144,21 +2512,56 @@ fn process_lod(
         reduced_material_buffer,
         out_samples_per_chunk_dim,
     );
-    //must recheck surface incase the reduction eliminated the surface. Additionally filters out the false positive state from calling chunk_contains_surface on a padded buffer preventing empty geometry.
-    if !chunk_contains_surface(reduced_density_buffer) {
+    mesh_reduced_lod(
+        reduced_density_buffer,
+        reduced_material_buffer,
+        DensityField::FullRes(density_buffer),
+        chunk_spawn_channel,
+        meshing_scratch,
+        chunk_coord,
+        out_samples_per_chunk_dim,
+        Some(LodNeighbors {
+            fine_field: DensityField::FullRes(density_buffer),
+            samples_per_chunk_dim: neighbor_samples_per_chunk_dim,
+        }),
+        had_entity,
+        prev_in_simulation_radius,
+    )
+}
+
+//meshes a reduced chunk whether it was downscaled or generated at its resolution, sends the spawn or change lod command
+//returns if it has a surface
+fn mesh_reduced_lod(
+    reduced_density_buffer: &[i16],
+    reduced_material_buffer: &[MaterialCode],
+    normal_field: DensityField,
+    chunk_spawn_channel: &Sender<ChunkSpawnResult>,
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
+    out_samples_per_chunk_dim: usize,
+    neighbors: Option<LodNeighbors>,
+    had_entity: bool,
+    prev_in_simulation_radius: bool,
+) -> bool {
+    let Some((vertices, normals, material_ids, indices)) = reduced_lod_mesh(
+        reduced_density_buffer,
+        reduced_material_buffer,
+        normal_field,
+        meshing_scratch,
+        out_samples_per_chunk_dim,
+        neighbors,
+    ) else {
         if had_entity {
             let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToDespawn(chunk_coord));
         }
         return false;
-    }
-    let (vertices, normals, material_ids, indices) = mc_mesh_generation(
-        reduced_density_buffer,
-        reduced_material_buffer,
-        out_samples_per_chunk_dim,
-        false,
-        &density_buffer,
-    );
-    let mesh = generate_bevy_mesh(vertices, normals, material_ids, indices);
+    };
+    let coarse_positions = lod_morph_targets(&vertices, normal_field, out_samples_per_chunk_dim);
+    let mesh = prepare_bevy_mesh(vertices, normals, material_ids, indices);
+    let mesh = match coarse_positions {
+        Some(coarse_positions) => with_coarse_positions(mesh, coarse_positions),
+        None => mesh,
+    };
     if had_entity {
         if prev_in_simulation_radius {
             let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToChangeLodRemoveCollider((
@@ -1174,29 +2577,424 @@ fn process_lod(
     true
 }
 
+//what a reduced chunk needs to stitch transition strips onto faces whose neighbor is meshed finer
+//only marching cubes lods use it, a neighbor changing lod later leaves the strip as it was until the chunk is meshed again
+#[derive(Clone, Copy)]
+struct LodNeighbors<'a> {
+    fine_field: DensityField<'a>, //sampled at each finer neighbor's resolution on its face
+    samples_per_chunk_dim: [usize; 6],
+}
+
+//a chunk generated at its own lod has no full res samples to stitch a transition strip from,
+//so when a face neighbor is meshed finer the chunk is generated again at the finest neighbor's resolution.
+//only cluster boundary chunks next to a finer cluster pay for it, None when no strip would be meshed
+fn finer_neighbor_field<'a, S: DensitySource + ?Sized>(
+    source: &S,
+    chunk_start: Vec3,
+    chunk_buffers: &mut ChunkBuffers,
+    out_samples_per_chunk_dim: usize,
+    neighbor_samples_per_chunk_dim: [usize; 6],
+    density: &'a mut [i16],
+    material: &mut [MaterialCode],
+) -> Option<LodNeighbors<'a>> {
+    let finest = neighbor_samples_per_chunk_dim.into_iter().max().unwrap();
+    if finest <= out_samples_per_chunk_dim
+        || out_samples_per_chunk_dim <= SURFACE_NETS_MAX_SAMPLES_PER_CHUNK_DIM
+        || *MESHING_MODE.read() != MeshingMode::MarchingCubes
+    {
+        return None;
+    }
+    let len = finest.pow(3);
+    source.fill_chunk_downsampled(
+        chunk_start,
+        chunk_buffers,
+        finest,
+        &mut density[..len],
+        &mut material[..len],
+    );
+    Some(LodNeighbors {
+        fine_field: DensityField::Downsampled(&density[..len], finest),
+        samples_per_chunk_dim: neighbor_samples_per_chunk_dim,
+    })
+}
+
+//the simplified mesh of a reduced chunk in chunk local space, None when it has no surface
+fn reduced_lod_mesh(
+    reduced_density_buffer: &[i16],
+    reduced_material_buffer: &[MaterialCode],
+    normal_field: DensityField,
+    meshing_scratch: &mut MeshingScratch,
+    out_samples_per_chunk_dim: usize,
+    neighbors: Option<LodNeighbors>,
+) -> Option<(Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>)> {
+    //must recheck surface incase the reduction eliminated the surface. Additionally filters out the false positive state from calling chunk_contains_surface on a padded buffer preventing empty geometry.
+    if !chunk_contains_surface(reduced_density_buffer) {
+        return None;
+    }
+    let (vertices, normals, material_ids, indices) =
+        if *MESHING_MODE.read() == MeshingMode::GreedyCubes {
+            greedy_cubes_mesh_generation(
+                reduced_density_buffer,
+                reduced_material_buffer,
+                out_samples_per_chunk_dim,
+                false,
+            )
+        } else if out_samples_per_chunk_dim <= SURFACE_NETS_MAX_SAMPLES_PER_CHUNK_DIM {
+            surface_nets_mesh_generation(
+                reduced_density_buffer,
+                reduced_material_buffer,
+                out_samples_per_chunk_dim,
+                normal_field,
+            )
+        } else if let Some(neighbors) = neighbors {
+            mc_mesh_generation_with_transitions(
+                &TransitionMeshInput {
+                    densities: reduced_density_buffer,
+                    materials: reduced_material_buffer,
+                    samples_per_chunk_dim: out_samples_per_chunk_dim,
+                    densities_padded: false,
+                    normal_field,
+                    fine_field: neighbors.fine_field,
+                    neighbor_samples_per_chunk_dim: neighbors.samples_per_chunk_dim,
+                    material_resolution: MaterialResolution::default(),
+                },
+                meshing_scratch,
+            )
+        } else {
+            mc_mesh_generation_into(
+                reduced_density_buffer,
+                reduced_material_buffer,
+                out_samples_per_chunk_dim,
+                false,
+                normal_field,
+                MaterialResolution::default(),
+                meshing_scratch,
+            );
+            meshing_scratch.copy_mesh()
+        };
+    if indices.is_empty() {
+        #[cfg(feature = "debug")]
+        EMPTY_MESHES_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return None;
+    }
+    Some(match *MESH_SIMPLIFICATION.read() {
+        Some(settings) => simplify_mesh(vertices, normals, material_ids, indices, &settings),
+        None => (vertices, normals, material_ids, indices),
+    })
+}
+
+//a full res chunk of a cluster being merged, downscaled to lod5 and added to the cluster's mesh
+fn merge_chunk(
+    chunk_buffers: &ChunkBuffers,
+    lod_buffers: &mut LodBuffers,
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
+    merged: &mut MergedClusterMesh,
+) {
+    if !chunk_contains_surface(&chunk_buffers.density) {
+        return;
+    }
+    downscale(
+        &chunk_buffers.density,
+        &chunk_buffers.material,
+        &mut lod_buffers.density_r5,
+        &mut lod_buffers.material_r5,
+        RF5_SAMPLES_PER_CHUNK_DIM,
+    );
+    if let Some(mesh) = reduced_lod_mesh(
+        &lod_buffers.density_r5,
+        &lod_buffers.material_r5,
+        DensityField::FullRes(&chunk_buffers.density),
+        meshing_scratch,
+        RF5_SAMPLES_PER_CHUNK_DIM,
+        None,
+    ) {
+        merged.append(chunk_coord, mesh);
+    }
+}
+
+//chunks of a cluster that just merged lose the entities they had on their own
+fn despawn_merged_chunks(
+    cluster_request: &ClusterRequest,
+    chunk_spawn_channel: &Sender<ChunkSpawnResult>,
+) {
+    let mut rolling = 0;
+    let min_chunk = cluster_coord_to_min_chunk_coord(cluster_request.position);
+    for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+        for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+            for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                if cluster_request.had_entity(rolling) {
+                    let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToDespawn(ChunkCoord(
+                        chunk_x, chunk_y, chunk_z,
+                    )));
+                }
+                rolling += 1;
+            }
+        }
+    }
+}
+
+//None unless lod morphing is on, greedy cubes are blocky on purpose and are never morphed
+fn lod_morph_targets(
+    vertices: &[Vec3],
+    field: DensityField,
+    samples_per_chunk_dim: usize,
+) -> Option<Vec<[f32; 4]>> {
+    if !LOD_MORPHING.load(Ordering::Relaxed) || *MESHING_MODE.read() != MeshingMode::MarchingCubes {
+        return None;
+    }
+    coarse_positions(vertices, field, samples_per_chunk_dim)
+}
+
+//a surface check can pass and meshing still produce no triangles, from the padding false positive,
+//a downscale or a thresholded thin surface. those chunks are treated as surface-less before any mesh is built,
+//whatever was spawned for them is despawned and nothing new is sent
+fn suppress_empty_mesh(
+    indices: &[u32],
+    had_entity: bool,
+    chunk_coord: ChunkCoord,
+    chunk_spawn_channel: &Sender<ChunkSpawnResult>,
+) -> bool {
+    if !indices.is_empty() {
+        return false;
+    }
+    if had_entity {
+        let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToDespawn(chunk_coord));
+    }
+    #[cfg(feature = "debug")]
+    EMPTY_MESHES_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+    true
+}
+
+//summaries of stored chunks come from the index, generated chunks are summarized once per session
+fn record_chunk_summary(
+    chunk_summaries: &ChunkSummaries,
+    chunk_coord: ChunkCoord,
+    chunk_buffers: &ChunkBuffers,
+) {
+    if chunk_summaries.0.read().contains_key(&chunk_coord) {
+        return;
+    }
+    let summary =
+        compute_chunk_summary(&chunk_buffers.density, &chunk_buffers.material, chunk_coord);
+    chunk_summaries.0.write().insert(chunk_coord, summary);
+}
+
 //try to get file offset from index map or index map delta (delta requiring read lock)
 //if offset found, load chunk from file and return uniformity
-pub fn try_load_chunk(
-    chunk_coord: (i16, i16, i16),
-    index_map_read: &FxHashMap<(i16, i16, i16), u64>,
-    index_map_delta: &RwLock<FxHashMap<(i16, i16, i16), u64>>,
-    chunk_data_file_read: &mut File,
+//pops requests in priority order and reads the chunks of each cluster that are on disk
+//keeps blocking file reads off the compute threads so a slow disk does not leave the cpu idle
+//a cluster with a chunk that cant be read goes straight back to the svo manager as failed, none of it is loaded
+fn chunk_io_thread(
+    index_map_read: Arc<FxHashMap<ChunkCoord, u64>>,
+    index_map_delta: Arc<RwLock<FxHashMap<ChunkCoord, u64>>>,
+    mut region_files_read: RegionFiles,
+    column_range_map_read_only: Arc<ColumnRangeMap>,
+    priority_queue: Arc<(Mutex<BinaryHeap<ClusterRequest>>, Condvar)>,
+    loaded_cluster_sender: Sender<LoadedCluster>,
+    failed_read_sender: Sender<ChunkResult>,
+    chunk_pool: ChunkBufferPool,
+) {
+    let mut chunk_buffers = ChunkBuffers::new();
+    loop {
+        let (binary_heap_lock, condvar) = &*priority_queue;
+        let mut binary_heap = binary_heap_lock.lock().unwrap();
+        while binary_heap.is_empty() {
+            binary_heap = condvar.wait(binary_heap).unwrap();
+        }
+        let request = binary_heap.pop().unwrap();
+        QUEUE_SIZE.store(binary_heap.len(), Ordering::Relaxed);
+        drop(binary_heap);
+        let mut chunks_on_disk = Vec::new();
+        let mut buried = [false; CHUNKS_PER_CLUSTER];
+        let mut rolling = 0;
+        let mut read_failed = false;
+        let min_chunk = cluster_coord_to_min_chunk_coord(request.position);
+        'cluster: for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+            for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                let column_cache = column_range_map_read_only.get_column(chunk_x, chunk_z);
+                for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                    rolling += 1;
+                    if column_cache.uniformity_at_y(chunk_y) != Uniformity::Unknown {
+                        continue; //uniform chunks never touch the data file
+                    }
+                    let chunk_coord = ChunkCoord(chunk_x, chunk_y, chunk_z);
+                    buried[rolling - 1] = walled_in_by_dirt(
+                        chunk_coord,
+                        &column_range_map_read_only,
+                        &index_map_read,
+                        &index_map_delta,
+                    );
+                    let read_start = Instant::now();
+                    let uniformity = match try_load_chunk(
+                        chunk_coord,
+                        &index_map_read,
+                        &index_map_delta,
+                        &mut region_files_read,
+                        &mut chunk_buffers,
+                    ) {
+                        Ok(uniformity) => uniformity,
+                        Err(e) => {
+                            error!(
+                                "failed to read chunk {chunk_coord}, cluster {:?} is requested again later: {e}",
+                                request.position
+                            );
+                            read_failed = true;
+                            break 'cluster;
+                        }
+                    };
+                    match uniformity {
+                        Uniformity::NonUniform => chunks_on_disk.push(DiskChunk {
+                            chunk_coord,
+                            stored: Some(
+                                chunk_pool
+                                    .chunk_from(&chunk_buffers.density, &chunk_buffers.material),
+                            ),
+                            removed_as: Uniformity::NonUniform,
+                            read_time: read_start.elapsed(),
+                        }),
+                        Uniformity::Air | Uniformity::Dirt => chunks_on_disk.push(DiskChunk {
+                            chunk_coord,
+                            stored: None,
+                            removed_as: uniformity,
+                            read_time: read_start.elapsed(),
+                        }),
+                        Uniformity::Unknown => {}
+                    }
+                }
+            }
+        }
+        if read_failed {
+            for disk_chunk in chunks_on_disk {
+                if let Some(stored) = disk_chunk.stored {
+                    chunk_pool.recycle(TerrainChunk::NonUniformTerrainChunk(stored));
+                }
+            }
+            let result = ChunkResult {
+                has_entity: [false; CHUNKS_PER_CLUSTER],
+                cluster_coord: request.position,
+                load_state: request.load_state_transition.to_state(),
+                read_failed: true,
+            };
+            if failed_read_sender.send(result).is_err() {
+                return;
+            }
+            continue;
+        }
+        let loaded_cluster = LoadedCluster {
+            request,
+            chunks_on_disk,
+            buried,
+        };
+        if loaded_cluster_sender.send(loaded_cluster).is_err() {
+            return;
+        }
+    }
+}
+
+//all six neighbors are uniform dirt that was never dug, so any surface in the chunk is a sealed pocket
+//the column range map only knows chunks classified in earlier sessions and a neighbor dug since has an index map entry,
+//so a chunk is only ever wrongly left unburied. digging into one remeshes it like any chunk the dig touches
+fn walled_in_by_dirt(
+    chunk_coord: ChunkCoord,
+    column_range_map: &ColumnRangeMap,
+    index_map_read: &FxHashMap<ChunkCoord, u64>,
+    index_map_delta: &RwLock<FxHashMap<ChunkCoord, u64>>,
+) -> bool {
+    chunk_coord.neighbors().into_iter().all(|neighbor| {
+        column_range_map.contains(neighbor) == Uniformity::Dirt
+            && !index_map_read.contains_key(&neighbor)
+            && !index_map_delta.read().contains_key(&neighbor)
+    })
+}
+
+//NonUniform with the buffers filled if the io thread found the chunk on disk, Air or Dirt if it was removed from disk
+//read_time is how long the io thread spent on it
+fn take_chunk_from_disk(
+    chunks_on_disk: &mut Vec<DiskChunk>,
+    chunk_coord: ChunkCoord,
     chunk_buffers: &mut ChunkBuffers,
+    read_time: &mut Duration,
+    chunk_pool: &ChunkBufferPool,
 ) -> Uniformity {
-    let file_offset = index_map_read
+    let Some(i) = chunks_on_disk
+        .iter()
+        .position(|chunk| chunk.chunk_coord == chunk_coord)
+    else {
+        return Uniformity::Unknown;
+    };
+    let disk_chunk = chunks_on_disk.swap_remove(i);
+    *read_time = disk_chunk.read_time;
+    let Some(stored) = disk_chunk.stored else {
+        return disk_chunk.removed_as;
+    };
+    chunk_buffers.density.copy_from_slice(&stored.densities);
+    chunk_buffers.material.copy_from_slice(&stored.materials);
+    chunk_pool.recycle(TerrainChunk::NonUniformTerrainChunk(stored));
+    Uniformity::NonUniform
+}
+
+//Air or Dirt for a chunk turned uniform and removed this session, the column range map only learns about it next session
+//Unknown when it was never stored, or stored corrupt so generating it again is the only way to get it back
+//an error is a read that kept failing, the stored chunk may be fine so the caller has to leave it unloaded and try again later.
+//generating it instead would put noise where the player's edits are and the next edit would write it over them
+pub fn try_load_chunk(
+    chunk_coord: ChunkCoord,
+    index_map_read: &FxHashMap<ChunkCoord, u64>,
+    index_map_delta: &RwLock<FxHashMap<ChunkCoord, u64>>,
+    region_files_read: &mut RegionFiles,
+    chunk_buffers: &mut ChunkBuffers,
+) -> Result<Uniformity, TerrainError> {
+    let file_offset = index_map_delta
+        .read()
         .get(&chunk_coord)
         .copied()
-        .or_else(|| index_map_delta.read().get(&chunk_coord).copied());
-    if let Some(offset) = file_offset {
+        .or_else(|| index_map_read.get(&chunk_coord).copied());
+    if let Some(uniformity) = file_offset.and_then(removed_as) {
+        return Ok(uniformity);
+    }
+    let Some(offset) = file_offset else {
+        return Ok(Uniformity::Unknown);
+    };
+    let read = with_retries(format_args!("read chunk {chunk_coord}"), || {
         load_chunk(
-            chunk_data_file_read,
+            region_files_read,
+            &chunk_coord,
             offset,
             &mut chunk_buffers.density,
             &mut chunk_buffers.material,
-        );
-        return Uniformity::NonUniform;
+        )
+    });
+    match read {
+        Ok(()) => Ok(Uniformity::NonUniform),
+        Err(e @ TerrainError::CorruptChunk(_)) => {
+            error!("chunk {chunk_coord} is corrupt on disk, it is generated again: {e}");
+            Ok(Uniformity::Unknown)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+//runs file work up to READ_ATTEMPTS times with the write retry backoff between tries, the last error is handed back
+//chunk reads and everything startup reads go through it, what finishes "failed to ..." in the log
+pub(crate) fn with_retries<T>(
+    what: impl std::fmt::Display,
+    mut op: impl FnMut() -> Result<T, TerrainError>,
+) -> Result<T, TerrainError> {
+    let mut delay = WRITE_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < READ_ATTEMPTS => {
+                warn!("failed to {what}, attempt {attempt} of {READ_ATTEMPTS}: {e}");
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
     }
-    Uniformity::Unknown
 }
 
 //run fast surface check for early exit
@@ -1206,7 +3004,8 @@ pub fn lod_resolve_has_surface(
     cluster_request: &ClusterRequest,
     chunk_buffers: &ChunkBuffers,
     lod_buffers: &mut LodBuffers,
-    chunk_coord: (i16, i16, i16),
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
     rolling: usize,
     chunk_spawn_channel: &Sender<ChunkSpawnResult>,
 ) -> bool {
@@ -1230,10 +3029,12 @@ pub fn lod_resolve_has_surface(
                 &chunk_buffers.density,
                 &chunk_buffers.material,
                 &chunk_spawn_channel,
+                meshing_scratch,
                 chunk_coord,
                 &mut lod_buffers.density_r5,
                 &mut lod_buffers.material_r5,
                 RF5_SAMPLES_PER_CHUNK_DIM,
+                cluster_request.chunk_neighbor_samples_per_chunk_dim(chunk_coord),
                 had_entity,
                 cluster_request.prev_in_simulation_radius,
             )
@@ -1244,10 +3045,12 @@ pub fn lod_resolve_has_surface(
                 &chunk_buffers.density,
                 &chunk_buffers.material,
                 &chunk_spawn_channel,
+                meshing_scratch,
                 chunk_coord,
                 &mut lod_buffers.density_r4,
                 &mut lod_buffers.material_r4,
                 RF4_SAMPLES_PER_CHUNK_DIM,
+                cluster_request.chunk_neighbor_samples_per_chunk_dim(chunk_coord),
                 had_entity,
                 cluster_request.prev_in_simulation_radius,
             )
@@ -1258,10 +3061,12 @@ pub fn lod_resolve_has_surface(
                 &chunk_buffers.density,
                 &chunk_buffers.material,
                 &chunk_spawn_channel,
+                meshing_scratch,
                 chunk_coord,
                 &mut lod_buffers.density_r3,
                 &mut lod_buffers.material_r3,
                 RF3_SAMPLES_PER_CHUNK_DIM,
+                cluster_request.chunk_neighbor_samples_per_chunk_dim(chunk_coord),
                 had_entity,
                 cluster_request.prev_in_simulation_radius,
             )
@@ -1272,10 +3077,12 @@ pub fn lod_resolve_has_surface(
                 &chunk_buffers.density,
                 &chunk_buffers.material,
                 &chunk_spawn_channel,
+                meshing_scratch,
                 chunk_coord,
                 &mut lod_buffers.density_r2,
                 &mut lod_buffers.material_r2,
                 RF2_SAMPLES_PER_CHUNK_DIM,
+                cluster_request.chunk_neighbor_samples_per_chunk_dim(chunk_coord),
                 had_entity,
                 cluster_request.prev_in_simulation_radius,
             )
@@ -1286,10 +3093,12 @@ pub fn lod_resolve_has_surface(
                 &chunk_buffers.density,
                 &chunk_buffers.material,
                 &chunk_spawn_channel,
+                meshing_scratch,
                 chunk_coord,
                 &mut lod_buffers.density_r1,
                 &mut lod_buffers.material_r1,
                 RF1_SAMPLES_PER_CHUNK_DIM,
+                cluster_request.chunk_neighbor_samples_per_chunk_dim(chunk_coord),
                 had_entity,
                 cluster_request.prev_in_simulation_radius,
             )
@@ -1297,6 +3106,7 @@ pub fn lod_resolve_has_surface(
         LoadStateTransition::ToFull => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
@@ -1306,6 +3116,7 @@ pub fn lod_resolve_has_surface(
         LoadStateTransition::ToFullWithCollider => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
@@ -1315,19 +3126,22 @@ pub fn lod_resolve_has_surface(
         LoadStateTransition::NoChangeAddCollider => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
             chunk_spawn_channel,
             FullLodMode::AddColliderToExisting,
         ),
+        LoadStateTransition::ToMerged => unreachable!(), //merged clusters go through merge_chunk
     }
 }
 
 pub fn resolve_has_surface(
     cluster_request: &ClusterRequest,
     chunk_buffers: &ChunkBuffers,
-    chunk_coord: (i16, i16, i16),
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
     rolling: usize,
     chunk_spawn_channel: &Sender<ChunkSpawnResult>,
 ) -> bool {
@@ -1348,6 +3162,7 @@ pub fn resolve_has_surface(
         LoadStateTransition::ToFull => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
@@ -1357,6 +3172,7 @@ pub fn resolve_has_surface(
         LoadStateTransition::ToFullWithCollider => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
@@ -1366,6 +3182,7 @@ pub fn resolve_has_surface(
         LoadStateTransition::NoChangeAddCollider => build_full_mesh_and_spawn(
             &chunk_buffers.density,
             &chunk_buffers.material,
+            meshing_scratch,
             chunk_coord,
             cluster_request,
             rolling,
@@ -1387,7 +3204,8 @@ pub fn resolve_has_surface(
 pub fn build_full_mesh_and_spawn(
     density_buffer: &[i16],
     material_buffer: &[MaterialCode],
-    chunk_coord: (i16, i16, i16),
+    meshing_scratch: &mut MeshingScratch,
+    chunk_coord: ChunkCoord,
     cluster_request: &ClusterRequest,
     rolling: usize,
     chunk_spawn_channel: &Sender<ChunkSpawnResult>,
@@ -1395,29 +3213,68 @@ pub fn build_full_mesh_and_spawn(
 ) -> bool {
     //slower surface check to eliminate false possitive state to prevent empty geometry.
     padded_chunk_contains_surface(density_buffer) && {
-        let (vertices, normals, material_ids, indices) = mc_mesh_generation(
-            density_buffer,
-            material_buffer,
-            SAMPLES_PER_CHUNK_DIM,
-            true,
-            density_buffer,
-        );
+        let (vertices, normals, material_ids, indices) = match *MESHING_MODE.read() {
+            MeshingMode::MarchingCubes => {
+                mc_mesh_generation_into(
+                    density_buffer,
+                    material_buffer,
+                    SAMPLES_PER_CHUNK_DIM,
+                    true,
+                    DensityField::FullRes(density_buffer),
+                    MaterialResolution::default(),
+                    meshing_scratch,
+                );
+                meshing_scratch.copy_mesh()
+            }
+            MeshingMode::GreedyCubes => greedy_cubes_mesh_generation(
+                density_buffer,
+                material_buffer,
+                SAMPLES_PER_CHUNK_DIM,
+                true,
+            ),
+        };
+        //thresholding can drop a thin surface, only marching cubes is guaranteed geometry here
         #[cfg(feature = "debug")]
         assert!(
-            !vertices.is_empty(),
+            *MESHING_MODE.read() != MeshingMode::MarchingCubes || !vertices.is_empty(),
             "padded_chunk_contains_surface returned true but MC produced no geometry for {:?}",
             chunk_coord
         );
         #[cfg(feature = "debug")]
         assert!(
-            !indices.is_empty(),
+            vertices.is_empty() || !indices.is_empty(),
             "MC produced vertices but empty indices for {:?}",
             chunk_coord
         );
-        let mesh = generate_bevy_mesh(vertices, normals, material_ids, indices);
         let had_entity = cluster_request.had_entity(rolling);
-        match mode {
-            FullLodMode::NoCollider => {
+        if suppress_empty_mesh(&indices, had_entity, chunk_coord, chunk_spawn_channel) {
+            return false;
+        }
+        let coarse_positions = lod_morph_targets(
+            &vertices,
+            DensityField::FullRes(density_buffer),
+            SAMPLES_PER_CHUNK_DIM,
+        );
+        let mesh = prepare_bevy_mesh(vertices, normals, material_ids, indices);
+        let mesh = match coarse_positions {
+            Some(coarse_positions) => with_coarse_positions(mesh, coarse_positions),
+            None => mesh,
+        };
+        //a mesh rapier refuses is still shown, it just cant be stood on until it is meshed again
+        let collider = match mode {
+            FullLodMode::NoCollider => None,
+            FullLodMode::WithCollider | FullLodMode::AddColliderToExisting => {
+                match cached_chunk_collider(chunk_coord, density_buffer, &mesh.mesh) {
+                    Ok(collider) => Some(collider),
+                    Err(e) => {
+                        warn!("{e}, it is left without one");
+                        None
+                    }
+                }
+            }
+        };
+        match (mode, collider) {
+            (FullLodMode::NoCollider, _) | (FullLodMode::WithCollider, None) => {
                 if had_entity {
                     let _ = chunk_spawn_channel
                         .send(ChunkSpawnResult::ToChangeLod((chunk_coord, mesh)));
@@ -1426,12 +3283,7 @@ pub fn build_full_mesh_and_spawn(
                         chunk_spawn_channel.send(ChunkSpawnResult::ToSpawn((chunk_coord, mesh)));
                 }
             }
-            FullLodMode::WithCollider => {
-                let collider = Collider::from_bevy_mesh(
-                    &mesh,
-                    &ComputedColliderShape::TriMesh(TriMeshFlags::default()),
-                )
-                .unwrap();
+            (FullLodMode::WithCollider, Some(collider)) => {
                 if had_entity {
                     let _ = chunk_spawn_channel.send(ChunkSpawnResult::ToChangeLodAddCollider((
                         chunk_coord,
@@ -1446,12 +3298,7 @@ pub fn build_full_mesh_and_spawn(
                     )));
                 }
             }
-            FullLodMode::AddColliderToExisting => {
-                let collider = Collider::from_bevy_mesh(
-                    &mesh,
-                    &ComputedColliderShape::TriMesh(TriMeshFlags::default()),
-                )
-                .unwrap();
+            (FullLodMode::AddColliderToExisting, Some(collider)) => {
                 if had_entity {
                     let _ = chunk_spawn_channel
                         .send(ChunkSpawnResult::ToGiveCollider((chunk_coord, collider)));
@@ -1463,6 +3310,12 @@ pub fn build_full_mesh_and_spawn(
                     )));
                 }
             }
+            (FullLodMode::AddColliderToExisting, None) => {
+                if !had_entity {
+                    let _ =
+                        chunk_spawn_channel.send(ChunkSpawnResult::ToSpawn((chunk_coord, mesh)));
+                }
+            }
         }
         true
     }
@@ -1477,4 +3330,5 @@ pub(crate) fn info_print() {
     info!("fma: {}", std::is_x86_feature_detected!("fma"));
     info!("avx2: {}", std::is_x86_feature_detected!("avx2"));
     info!("sse2: {}", std::is_x86_feature_detected!("sse2"));
+    info!("world seed: {}", world_seed());
 }
@@ -5,11 +5,19 @@ use std::sync::{
     atomic::{AtomicUsize, Ordering},
 };
 
-use crate::deformable_terrain::driver::QUEUE_SIZE;
+use crate::deformable_terrain::{
+    chunk_budget::{TERRAIN_MAP_BYTES, TERRAIN_MAP_CHUNKS, TERRAIN_MAP_EVICTIONS},
+    collider_cache::{
+        COLLIDER_CACHE_HITS, COLLIDER_CACHE_MISSES, HEIGHTFIELD_COLLIDERS, collider_cache_hit_rate,
+    },
+    driver::QUEUE_SIZE,
+};
 
 pub static CHUNK_SPAWN_RECEIVER_QUEUE_SIZE: AtomicUsize = AtomicUsize::new(0);
-pub static INTERNAL_QUEUE_SIZES: OnceLock<Box<[AtomicUsize]>> = OnceLock::new();
+pub static INTERNAL_QUEUE_SIZES: OnceLock<Box<[AtomicUsize]>> = OnceLock::new(); //1 while a compute thread holds a cluster
 pub static CLUSTERS_PROCESSED: AtomicUsize = AtomicUsize::new(0);
+pub static EMPTY_MESHES_SUPPRESSED: AtomicUsize = AtomicUsize::new(0); //surface checks that meshed to nothing and were dropped
+pub static EMPTY_MESHES_SPAWNED: AtomicUsize = AtomicUsize::new(0); //empty meshes that still reached the spawn receiver, should stay 0
 
 #[derive(Component)]
 pub struct PriorityQueueSizeText;
@@ -20,6 +28,12 @@ pub struct ChunkSpawnReceiverText;
 #[derive(Component)]
 pub struct InternalQueueSizeText;
 
+#[derive(Component)]
+pub struct TerrainMapText;
+
+#[derive(Component)]
+pub struct ColliderCacheText;
+
 pub fn spawn_debug_texts(mut commands: Commands) {
     commands.spawn((
         PriorityQueueSizeText,
@@ -66,6 +80,36 @@ pub fn spawn_debug_texts(mut commands: Commands) {
             ..default()
         },
     ));
+    commands.spawn((
+        TerrainMapText,
+        Text::new("Terrain Map: 0 MB"),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(100.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+    ));
+    commands.spawn((
+        ColliderCacheText,
+        Text::new("Collider Cache: 0 hits"),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(130.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+    ));
 }
 
 pub fn update_debug_texts(
@@ -75,6 +119,8 @@ pub fn update_debug_texts(
             With<PriorityQueueSizeText>,
             Without<ChunkSpawnReceiverText>,
             Without<InternalQueueSizeText>,
+            Without<TerrainMapText>,
+            Without<ColliderCacheText>,
         ),
     >,
     mut spawn_receiver_text: Query<
@@ -83,6 +129,8 @@ pub fn update_debug_texts(
             With<ChunkSpawnReceiverText>,
             Without<PriorityQueueSizeText>,
             Without<InternalQueueSizeText>,
+            Without<TerrainMapText>,
+            Without<ColliderCacheText>,
         ),
     >,
     mut internal_queue_text: Query<
@@ -91,6 +139,28 @@ pub fn update_debug_texts(
             With<InternalQueueSizeText>,
             Without<PriorityQueueSizeText>,
             Without<ChunkSpawnReceiverText>,
+            Without<TerrainMapText>,
+            Without<ColliderCacheText>,
+        ),
+    >,
+    mut terrain_map_text: Query<
+        &mut Text,
+        (
+            With<TerrainMapText>,
+            Without<PriorityQueueSizeText>,
+            Without<ChunkSpawnReceiverText>,
+            Without<InternalQueueSizeText>,
+            Without<ColliderCacheText>,
+        ),
+    >,
+    mut collider_cache_text: Query<
+        &mut Text,
+        (
+            With<ColliderCacheText>,
+            Without<PriorityQueueSizeText>,
+            Without<ChunkSpawnReceiverText>,
+            Without<InternalQueueSizeText>,
+            Without<TerrainMapText>,
         ),
     >,
     mut rate_state: Local<(usize, f32, f32, bool)>,
@@ -122,14 +192,16 @@ pub fn update_debug_texts(
     }
     if let Ok(mut text) = spawn_receiver_text.single_mut() {
         text.0 = format!(
-            "Spawn Receiver Queue: {}",
-            CHUNK_SPAWN_RECEIVER_QUEUE_SIZE.load(Ordering::Relaxed)
+            "Spawn Receiver Queue: {} (empty meshes dropped: {}, spawned: {})",
+            CHUNK_SPAWN_RECEIVER_QUEUE_SIZE.load(Ordering::Relaxed),
+            EMPTY_MESHES_SUPPRESSED.load(Ordering::Relaxed),
+            EMPTY_MESHES_SPAWNED.load(Ordering::Relaxed)
         );
     }
     if let Ok(mut text) = internal_queue_text.single_mut() {
         if let Some(sizes) = INTERNAL_QUEUE_SIZES.get() {
             text.0 = format!(
-                "Compute Internal Queues: [{}]",
+                "Compute Threads Busy: [{}]",
                 sizes
                     .iter()
                     .map(|a| format!("{:>2}", a.load(Ordering::Relaxed)))
@@ -138,4 +210,21 @@ pub fn update_debug_texts(
             );
         }
     }
+    if let Ok(mut text) = terrain_map_text.single_mut() {
+        text.0 = format!(
+            "Terrain Map: {:.1} MB in {} chunks ({} evicted)",
+            TERRAIN_MAP_BYTES.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            TERRAIN_MAP_CHUNKS.load(Ordering::Relaxed),
+            TERRAIN_MAP_EVICTIONS.load(Ordering::Relaxed)
+        );
+    }
+    if let Ok(mut text) = collider_cache_text.single_mut() {
+        text.0 = format!(
+            "Collider Cache: {} hits, {} misses ({:.0}%), {} heightfields",
+            COLLIDER_CACHE_HITS.load(Ordering::Relaxed),
+            COLLIDER_CACHE_MISSES.load(Ordering::Relaxed),
+            collider_cache_hit_rate() * 100.0,
+            HEIGHTFIELD_COLLIDERS.load(Ordering::Relaxed)
+        );
+    }
 }
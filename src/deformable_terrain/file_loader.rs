@@ -1,136 +1,1049 @@
 use bevy::prelude::*;
+use lz4_flex::block::{compress_into, decompress_into, get_maximum_output_size};
+use memmap2::Mmap;
+use parking_lot::RwLock;
 use rustc_hash::FxHashMap;
 use std::collections::VecDeque;
-use std::fs::{File, create_dir_all};
+use std::collections::hash_map::Entry;
+use std::fs::{File, create_dir_all, read_dir, remove_file, rename};
 use std::io::{Read, Seek, SeekFrom, Write};
-use std::mem::transmute;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED};
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{
+    SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    SAMPLES_PER_CHUNK_PADDED,
+};
+use crate::conversions::flatten_index;
 use crate::deformable_terrain::chunk_entity_map::ChunkEntityMap;
 use crate::deformable_terrain::chunk_generator::MaterialCode;
+use crate::deformable_terrain::chunk_summary::{CHUNK_SUMMARY_SERIALIZED_SIZE, ChunkSummary};
 use crate::deformable_terrain::column_range_map::ColumnRangeMap;
+use crate::deformable_terrain::driver::DirtyRange;
 use crate::deformable_terrain::plugin::Uniformity;
+use crate::deformable_terrain::sdf_value::SdfValue;
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::terrain_error::TerrainError;
+use crate::deformable_terrain::world_header::validate_world_header;
 
 pub(crate) const CHUNK_SERIALIZED_SIZE: usize = SAMPLES_PER_CHUNK * std::mem::size_of::<u8>()
     + SAMPLES_PER_CHUNK_PADDED * std::mem::size_of::<i16>();
+const CHUNK_BLOB_HEADER_SIZE: usize = 9; //sizeof (u8, u32, u32)
+pub(crate) const CHUNK_CODEC: ChunkCodec = ChunkCodec::RunLength;
+const LITERAL_RUN_FLAG: u16 = 0x8000;
+const MAX_DENSITY_RUN: usize = 0x7FFF;
+const MIN_DENSITY_REPEAT: usize = 3; //a shorter repeat costs more as its own run than inside a literal
+pub(crate) const REGION_DIR: &str = "regions";
+const REGION_DIM: i16 = 16; //chunks per region edge, 32 would put a 400kb header in front of regions holding a few chunks
+const CHUNKS_PER_REGION: usize = (REGION_DIM as usize).pow(3);
+const SECTOR_SIZE: u64 = 4096;
+const HEADER_ENTRY_SIZE: usize = 14 + CHUNK_SUMMARY_SERIALIZED_SIZE; //sizeof (u32, u16, u64) + summary
+const HEADER_SIZE: usize = CHUNKS_PER_REGION * HEADER_ENTRY_SIZE;
+const HEADER_SECTORS: u32 = (HEADER_SIZE as u64).div_ceil(SECTOR_SIZE) as u32;
 const TOMBSTONE_BYTES: [u8; 6] = [0xFF; 6];
+pub(crate) const REMOVED_CHUNK_OFFSET: u64 = 0; //index delta entry for a chunk dropped from the region files this session, no chunk starts inside a header
+pub(crate) const REMOVED_DIRT_CHUNK_OFFSET: u64 = 1; //same for a chunk built up into uniform dirt
 
 // Binary format layout:
 // - SDF values: num_voxels * i16 (2 bytes each)
 // - Material values: num_voxels * u8 (1 byte each)
+// Stored chunk layout:
+// - codec: u8, uncompressed size: u32, payload size: u32, then the payload in that codec
+// Run length payload layout:
+// - density runs until every sample is covered, each a u16 header of length | literal flag
+//   then one i16 repeated over the length, or length i16s when the literal flag is set
+// - material runs until every sample is covered, each (material: u8, length: u16)
+// Region file layout:
+// - one file per REGION_DIM^3 block of chunks, regions/r.x.y.z.bin named by region coord
+// - header: one entry per chunk in x, y, z order
+//   first sector: u32 (0 = not stored), sector count: u16, last modified: u64 unix millis (0 = before timestamps), ChunkSummary
+// - chunks start on a sector boundary after the header, an update that outgrows its sectors moves the chunk
+// - index maps hold the byte offset of a chunk inside its own region file
 
-//serialize densities and materials into a byte buffer
-fn serialize_chunk_data(densities: &[i16], materials: &[MaterialCode], mut buffer: &mut [u8]) {
+//how the payload of a stored chunk is encoded
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChunkCodec {
+    Raw = 0,
+    Lz4 = 1, //most samples sit at the clamped air or solid value so chunks shrink several times over
+    RunLength = 2, //mostly air or dirt chunks collapse to their few rows that cross the surface
+}
+
+impl ChunkCodec {
+    fn from_u8(value: u8) -> Result<Self, TerrainError> {
+        match value {
+            0 => Ok(ChunkCodec::Raw),
+            1 => Ok(ChunkCodec::Lz4),
+            2 => Ok(ChunkCodec::RunLength),
+            _ => Err(TerrainError::CorruptChunk("unknown codec")),
+        }
+    }
+}
+
+//scratch for reading stored chunks, reused so loads dont allocate
+#[derive(Default)]
+pub struct ChunkReadBuffers {
+    blob: Vec<u8>,
+    raw: Vec<u8>,
+}
+
+//densities then materials, uncompressed
+fn write_raw_chunk_data(densities: &[i16], materials: &[MaterialCode], mut buffer: &mut [u8]) {
     for &d in densities.iter() {
         let (dst, rest) = buffer.split_at_mut(2);
         dst.copy_from_slice(&d.to_le_bytes());
         buffer = rest;
     }
     for &m in materials.iter() {
-        buffer[0] = unsafe { transmute::<MaterialCode, u8>(m) };
+        buffer[0] = m as u8;
         buffer = &mut buffer[1..];
     }
 }
 
-//read density and material data into provided buffers
-fn deserialize_chunk_data(
+fn read_raw_chunk_data(
     data: &[u8],
     density_buffer: &mut [i16],
     material_buffer: &mut [MaterialCode],
-) {
+) -> Result<(), TerrainError> {
     let (sdf_bytes, material_bytes) = data.split_at(SAMPLES_PER_CHUNK_PADDED * 2);
     for (chunk, dst) in sdf_bytes.chunks_exact(2).zip(density_buffer.iter_mut()) {
-        *dst = i16::from_le_bytes([chunk[0], chunk[1]]);
+        *dst = SdfValue::from_raw(i16::from_le_bytes([chunk[0], chunk[1]])).0;
     }
     for (&src, dst) in material_bytes.iter().zip(material_buffer.iter_mut()) {
-        *dst = unsafe { transmute::<u8, MaterialCode>(src) };
+        *dst = MaterialCode::try_from(src)
+            .map_err(|_| TerrainError::CorruptChunk("unknown material"))?;
+    }
+    Ok(())
+}
+
+//maximal runs of equal values, split so every length fits in max_len
+fn runs<T: Copy + PartialEq>(
+    values: &[T],
+    max_len: usize,
+) -> impl Iterator<Item = (T, usize)> + '_ {
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        let &value = values.get(i)?;
+        let len = values[i..]
+            .iter()
+            .take(max_len)
+            .take_while(|&&v| v == value)
+            .count();
+        i += len;
+        Some((value, len))
+    })
+}
+
+//appends into a fixed slice, None once it would run past the end
+struct SliceWriter<'a> {
+    out: &'a mut [u8],
+    len: usize,
+}
+
+impl SliceWriter<'_> {
+    fn put(&mut self, bytes: &[u8]) -> Option<()> {
+        let end = self.len + bytes.len();
+        self.out.get_mut(self.len..end)?.copy_from_slice(bytes);
+        self.len = end;
+        Some(())
+    }
+}
+
+fn write_density_literals(densities: &[i16], writer: &mut SliceWriter) -> Option<()> {
+    for literal in densities.chunks(MAX_DENSITY_RUN) {
+        writer.put(&(literal.len() as u16 | LITERAL_RUN_FLAG).to_le_bytes())?;
+        for d in literal.iter() {
+            writer.put(&d.to_le_bytes())?;
+        }
+    }
+    Some(())
+}
+
+//samples between repeats are gathered into literal runs so surface detail costs about what it does raw
+fn write_density_runs(densities: &[i16], writer: &mut SliceWriter) -> Option<()> {
+    let mut literal_start = 0;
+    let mut i = 0;
+    for (value, len) in runs(densities, MAX_DENSITY_RUN) {
+        if len >= MIN_DENSITY_REPEAT {
+            write_density_literals(&densities[literal_start..i], writer)?;
+            writer.put(&(len as u16).to_le_bytes())?;
+            writer.put(&value.to_le_bytes())?;
+            literal_start = i + len;
+        }
+        i += len;
     }
+    write_density_literals(&densities[literal_start..], writer)
+}
+
+//returns what follows the runs, None when the data ends or overruns the buffer partway through a run
+fn read_density_runs<'a>(mut data: &'a [u8], density_buffer: &mut [i16]) -> Option<&'a [u8]> {
+    let mut i = 0;
+    while i < density_buffer.len() {
+        let (run_header, data_after_header) = data.split_at_checked(2)?;
+        let run_header = u16::from_le_bytes([run_header[0], run_header[1]]);
+        let len = (run_header & !LITERAL_RUN_FLAG) as usize;
+        let literal = run_header & LITERAL_RUN_FLAG != 0;
+        let (values, rest) =
+            data_after_header.split_at_checked(if literal { len * 2 } else { 2 })?;
+        let dst = density_buffer.get_mut(i..i + len)?;
+        if literal {
+            for (bytes, dst) in values.chunks_exact(2).zip(dst.iter_mut()) {
+                *dst = SdfValue::from_raw(i16::from_le_bytes([bytes[0], bytes[1]])).0;
+            }
+        } else {
+            dst.fill(SdfValue::from_raw(i16::from_le_bytes([values[0], values[1]])).0);
+        }
+        i += len;
+        data = rest;
+    }
+    Some(data)
+}
+
+//length written, None when it would not fit in the output
+fn write_run_length_chunk_data(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    out: &mut [u8],
+) -> Option<usize> {
+    let mut writer = SliceWriter { out, len: 0 };
+    write_density_runs(densities, &mut writer)?;
+    for (material, len) in runs(materials, u16::MAX as usize) {
+        writer.put(&[material as u8])?;
+        writer.put(&(len as u16).to_le_bytes())?;
+    }
+    Some(writer.len)
+}
+
+fn read_run_length_chunk_data(
+    data: &[u8],
+    density_buffer: &mut [i16],
+    material_buffer: &mut [MaterialCode],
+) -> Result<(), TerrainError> {
+    let material_runs = read_density_runs(data, density_buffer)
+        .ok_or(TerrainError::CorruptChunk("run length payload cut short"))?;
+    let material_runs = material_runs.chunks_exact(3);
+    if !material_runs.remainder().is_empty() {
+        return Err(TerrainError::CorruptChunk("material run cut short"));
+    }
+    let mut i = 0;
+    for run in material_runs {
+        let len = u16::from_le_bytes([run[1], run[2]]) as usize;
+        let material = MaterialCode::try_from(run[0])
+            .map_err(|_| TerrainError::CorruptChunk("unknown material"))?;
+        material_buffer
+            .get_mut(i..i + len)
+            .ok_or(TerrainError::CorruptChunk(
+                "material runs overrun the chunk",
+            ))?
+            .fill(material);
+        i += len;
+    }
+    //anything short of the whole chunk would leave the pooled buffer's stale materials in place
+    if i != material_buffer.len() {
+        return Err(TerrainError::CorruptChunk(
+            "material runs do not cover the chunk",
+        ));
+    }
+    Ok(())
+}
+
+//serialize densities and materials into the buffer and return the bytes to store, header included
+//run length is tried per chunk and kept when it is no bigger than lz4, busy surface chunks fall back to lz4
+//a chunk that does not compress is stored raw so it never takes more space than before
+pub fn serialize_chunk_data<'a>(
+    densities: &[i16],
+    materials: &[MaterialCode],
+    codec: ChunkCodec,
+    buffer: &'a mut Vec<u8>,
+) -> &'a [u8] {
+    let max_payload = get_maximum_output_size(CHUNK_SERIALIZED_SIZE).max(CHUNK_SERIALIZED_SIZE);
+    buffer.resize(
+        2 * CHUNK_SERIALIZED_SIZE + CHUNK_BLOB_HEADER_SIZE + max_payload,
+        0,
+    );
+    let (raw, rest) = buffer.split_at_mut(CHUNK_SERIALIZED_SIZE);
+    let (run_length_scratch, blob) = rest.split_at_mut(CHUNK_SERIALIZED_SIZE);
+    write_raw_chunk_data(densities, materials, raw);
+    let (header, payload) = blob.split_at_mut(CHUNK_BLOB_HEADER_SIZE);
+    let compressed = match codec {
+        ChunkCodec::Raw => None,
+        ChunkCodec::Lz4 => compress_into(raw, payload)
+            .ok()
+            .map(|len| (ChunkCodec::Lz4, len)),
+        ChunkCodec::RunLength => {
+            let lz4_len = compress_into(raw, payload).ok();
+            match write_run_length_chunk_data(densities, materials, run_length_scratch) {
+                Some(len) if lz4_len.is_none_or(|lz4_len| len <= lz4_len) => {
+                    payload[..len].copy_from_slice(&run_length_scratch[..len]);
+                    Some((ChunkCodec::RunLength, len))
+                }
+                _ => lz4_len.map(|len| (ChunkCodec::Lz4, len)),
+            }
+        }
+    }
+    .filter(|(_, len)| *len < CHUNK_SERIALIZED_SIZE);
+    let (codec, payload_len) = match compressed {
+        Some(stored) => stored,
+        None => {
+            payload[..CHUNK_SERIALIZED_SIZE].copy_from_slice(raw);
+            (ChunkCodec::Raw, CHUNK_SERIALIZED_SIZE)
+        }
+    };
+    header[0] = codec as u8;
+    header[1..5].copy_from_slice(&(CHUNK_SERIALIZED_SIZE as u32).to_le_bytes());
+    header[5..9].copy_from_slice(&(payload_len as u32).to_le_bytes());
+    &blob[..CHUNK_BLOB_HEADER_SIZE + payload_len]
+}
+
+//read a stored chunk, header included, into provided buffers
+//a torn or corrupt chunk is an error, the buffers may be partly overwritten by then
+pub fn deserialize_chunk_data(
+    data: &[u8],
+    raw_buffer: &mut Vec<u8>,
+    density_buffer: &mut [i16],
+    material_buffer: &mut [MaterialCode],
+) -> Result<(), TerrainError> {
+    let (header, payload) = data
+        .split_at_checked(CHUNK_BLOB_HEADER_SIZE)
+        .ok_or(TerrainError::CorruptChunk("header cut short"))?;
+    let uncompressed_size = u32::from_le_bytes(header[1..5].try_into().unwrap()) as usize;
+    let payload_size = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+    let payload = payload
+        .get(..payload_size)
+        .ok_or(TerrainError::CorruptChunk("payload cut short"))?;
+    match ChunkCodec::from_u8(header[0])? {
+        ChunkCodec::Raw => {
+            if payload.len() < CHUNK_SERIALIZED_SIZE {
+                return Err(TerrainError::CorruptChunk("raw payload cut short"));
+            }
+            read_raw_chunk_data(payload, density_buffer, material_buffer)?;
+        }
+        ChunkCodec::Lz4 => {
+            //checked before resizing, a corrupt size could ask for gigabytes
+            if uncompressed_size != CHUNK_SERIALIZED_SIZE {
+                return Err(TerrainError::CorruptChunk("wrong uncompressed size"));
+            }
+            raw_buffer.resize(uncompressed_size, 0);
+            match decompress_into(payload, raw_buffer) {
+                Ok(len) if len == uncompressed_size => {}
+                _ => {
+                    return Err(TerrainError::CorruptChunk(
+                        "lz4 payload does not decompress",
+                    ));
+                }
+            }
+            read_raw_chunk_data(raw_buffer, density_buffer, material_buffer)?;
+        }
+        ChunkCodec::RunLength => {
+            read_run_length_chunk_data(payload, density_buffer, material_buffer)?;
+        }
+    }
+    Ok(())
+}
+
+fn sectors_for(byte_len: usize) -> u32 {
+    (byte_len as u64).div_ceil(SECTOR_SIZE) as u32
+}
+
+fn region_coord(chunk_coord: &ChunkCoord) -> (i16, i16, i16) {
+    (
+        chunk_coord.0.div_euclid(REGION_DIM),
+        chunk_coord.1.div_euclid(REGION_DIM),
+        chunk_coord.2.div_euclid(REGION_DIM),
+    )
+}
+
+//byte offset of the chunk's entry in its region header
+fn header_offset(chunk_coord: &ChunkCoord) -> u64 {
+    let x = chunk_coord.0.rem_euclid(REGION_DIM) as usize;
+    let y = chunk_coord.1.rem_euclid(REGION_DIM) as usize;
+    let z = chunk_coord.2.rem_euclid(REGION_DIM) as usize;
+    let dim = REGION_DIM as usize;
+    ((x + y * dim + z * dim * dim) * HEADER_ENTRY_SIZE) as u64
+}
+
+fn region_file_name(region: (i16, i16, i16)) -> String {
+    format!("r.{}.{}.{}.bin", region.0, region.1, region.2)
+}
+
+fn parse_region_file_name(name: &str) -> Option<(i16, i16, i16)> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".bin")?.split('.');
+    let region = (
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    );
+    parts.next().is_none().then_some(region)
+}
+
+//sector runs of a region that can take a chunk, rebuilt from the header the first time a session writes to the region
+//runs left behind by a chunk that moved or was removed are only reused next session since an io thread may still be reading them
+struct RegionSectors {
+    free: Vec<(u32, u32)>, //first sector, sector count
+    end: u32,
+}
+
+impl RegionSectors {
+    fn from_header(header: &[u8]) -> Self {
+        let mut used: Vec<(u32, u32)> = header
+            .chunks_exact(HEADER_ENTRY_SIZE)
+            .map(|entry| {
+                let first = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+                let count = u16::from_le_bytes([entry[4], entry[5]]) as u32;
+                (first, count)
+            })
+            .filter(|(first, _)| *first != 0)
+            .collect();
+        used.sort_unstable();
+        let mut free = Vec::new();
+        let mut end = HEADER_SECTORS;
+        for (first, count) in used {
+            if first > end {
+                free.push((end, first - end));
+            }
+            end = end.max(first + count);
+        }
+        RegionSectors { free, end }
+    }
+
+    //first fit, growing the file when no gap is big enough
+    fn allocate(&mut self, count: u32) -> u32 {
+        if let Some(i) = self.free.iter().position(|(_, free)| *free >= count) {
+            let (first, free) = self.free[i];
+            if free == count {
+                self.free.swap_remove(i);
+            } else {
+                self.free[i] = (first + count, free - count);
+            }
+            return first;
+        }
+        let first = self.end;
+        self.end += count;
+        first
+    }
+}
+
+//read only maps of the region files shared by every io thread, a hot chunk is copied straight out of the page cache
+//a map only covers the file as long as it was when mapped, reading a chunk appended past that maps the region again
+#[derive(Clone, Default)]
+pub struct RegionMaps(Arc<RwLock<FxHashMap<(i16, i16, i16), Arc<Mmap>>>>);
+
+impl RegionMaps {
+    //None when the region cant be mapped or is shorter than end even after remapping
+    fn covering(&self, dir: &Path, region: (i16, i16, i16), end: u64) -> Option<Arc<Mmap>> {
+        if let Some(map) = self.0.read().get(&region)
+            && map.len() as u64 >= end
+        {
+            return Some(Arc::clone(map));
+        }
+        let file = File::open(dir.join(region_file_name(region))).ok()?;
+        //safety: the write thread keeps writing the file while it is mapped, region files only ever grow while the game runs
+        //a chunk rewritten mid read comes back torn the same as it would through read, the bytes are only copied out
+        let map = Arc::new(unsafe { Mmap::map(&file) }.ok()?);
+        if (map.len() as u64) < end {
+            return None;
+        }
+        self.0.write().insert(region, Arc::clone(&map));
+        Some(map)
+    }
+}
+
+//region files of one world opened on first use, each io thread and the write thread own their own set
+pub struct RegionFiles {
+    dir: PathBuf,
+    writable: bool,
+    files: FxHashMap<(i16, i16, i16), File>,
+    sectors: FxHashMap<(i16, i16, i16), RegionSectors>, //only filled on the write side
+    maps: Option<RegionMaps>,                           //loads go through these when set
+    read_buffers: ChunkReadBuffers,
 }
 
+impl RegionFiles {
+    pub fn reader(data_dir: &Path) -> Self {
+        RegionFiles {
+            dir: data_dir.join(REGION_DIR),
+            writable: false,
+            files: FxHashMap::default(),
+            sectors: FxHashMap::default(),
+            maps: None,
+            read_buffers: ChunkReadBuffers::default(),
+        }
+    }
+
+    //reader that loads through maps shared with the other io threads, falls back to seek and read if mapping fails
+    pub fn mapped_reader(data_dir: &Path, maps: RegionMaps) -> Self {
+        RegionFiles {
+            maps: Some(maps),
+            ..RegionFiles::reader(data_dir)
+        }
+    }
+
+    pub fn writer(data_dir: &Path) -> Result<Self, TerrainError> {
+        let dir = data_dir.join(REGION_DIR);
+        create_dir_all(&dir).map_err(|e| TerrainError::Open(dir.clone(), e))?;
+        Ok(RegionFiles {
+            dir,
+            writable: true,
+            files: FxHashMap::default(),
+            sectors: FxHashMap::default(),
+            maps: None,
+            read_buffers: ChunkReadBuffers::default(),
+        })
+    }
+
+    //new regions start as an empty header so the first chunk lands on the first sector after it
+    //a region that failed to open is tried again on its next use
+    fn file(&mut self, region: (i16, i16, i16)) -> Result<&mut File, TerrainError> {
+        let (dir, writable) = (&self.dir, self.writable);
+        match self.files.entry(region) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let path = dir.join(region_file_name(region));
+                let open_err = |e| TerrainError::Open(path.clone(), e);
+                let file = StoragePaths::open_file(&path, writable).map_err(open_err)?;
+                if writable && file.metadata().map_err(open_err)?.len() == 0 {
+                    file.set_len(HEADER_SECTORS as u64 * SECTOR_SIZE)
+                        .map_err(open_err)?;
+                }
+                Ok(entry.insert(file))
+            }
+        }
+    }
+
+    //everything written so far is on the disk once this returns, the write journal is only cleared after it
+    pub fn sync_all(&self) -> Result<(), TerrainError> {
+        for file in self.files.values() {
+            file.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn allocate(&mut self, chunk_coord: &ChunkCoord, count: u32) -> Result<u32, TerrainError> {
+        let region = region_coord(chunk_coord);
+        if !self.sectors.contains_key(&region) {
+            let mut header = vec![0u8; HEADER_SIZE];
+            let region_file = self.file(region)?;
+            region_file.seek(SeekFrom::Start(0))?;
+            //a header cut short would have sectors handed out over chunks it lost track of
+            if let Err(e) = region_file.read_exact(&mut header) {
+                return Err(match e.kind() {
+                    std::io::ErrorKind::UnexpectedEof => {
+                        TerrainError::TruncatedRegionHeader(self.dir.join(region_file_name(region)))
+                    }
+                    _ => TerrainError::Io(e),
+                });
+            }
+            self.sectors
+                .insert(region, RegionSectors::from_header(&header));
+        }
+        Ok(self.sectors.get_mut(&region).unwrap().allocate(count))
+    }
+
+    fn allocated_sectors(&mut self, chunk_coord: &ChunkCoord) -> Result<u32, TerrainError> {
+        let mut count = [0u8; 2];
+        let region_file = self.file(region_coord(chunk_coord))?;
+        region_file.seek(SeekFrom::Start(header_offset(chunk_coord) + 4))?;
+        region_file.read_exact(&mut count)?;
+        Ok(u16::from_le_bytes(count) as u32)
+    }
+
+    fn write_blob(
+        &mut self,
+        chunk_coord: &ChunkCoord,
+        sector: u32,
+        blob: &[u8],
+    ) -> Result<(), TerrainError> {
+        let region_file = self.file(region_coord(chunk_coord))?;
+        region_file.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))?;
+        region_file.write_all(blob)?;
+        region_file.flush()?;
+        Ok(())
+    }
+
+    //rewrites the chunk's header entry in place
+    fn write_header_entry(
+        &mut self,
+        chunk_coord: &ChunkCoord,
+        sector: u32,
+        sector_count: u32,
+        summary: &ChunkSummary,
+        modified: u64,
+    ) -> Result<(), TerrainError> {
+        let mut entry = [0u8; HEADER_ENTRY_SIZE];
+        entry[..4].copy_from_slice(&sector.to_le_bytes());
+        entry[4..6].copy_from_slice(&(sector_count as u16).to_le_bytes());
+        entry[6..14].copy_from_slice(&modified.to_le_bytes());
+        entry[14..].copy_from_slice(&summary.to_bytes());
+        let region_file = self.file(region_coord(chunk_coord))?;
+        region_file.seek(SeekFrom::Start(header_offset(chunk_coord)))?;
+        region_file.write_all(&entry)?;
+        region_file.flush()?;
+        Ok(())
+    }
+
+    fn clear_header_entry(&mut self, chunk_coord: &ChunkCoord) -> Result<(), TerrainError> {
+        let region_file = self.file(region_coord(chunk_coord))?;
+        region_file.seek(SeekFrom::Start(header_offset(chunk_coord)))?;
+        region_file.write_all(&[0u8; HEADER_ENTRY_SIZE])?;
+        region_file.flush()?;
+        Ok(())
+    }
+}
+
+//writes the chunk into free sectors of its region and records it in the region header
 pub(crate) fn write_chunk(
     densities: &[i16],
     materials: &[MaterialCode],
-    chunk_coord: &(i16, i16, i16),
-    index_map_delta: &mut FxHashMap<(i16, i16, i16), u64>,
-    chunk_data_file: &mut File,
-    chunk_index_file: &mut File,
-    index_buffer_allocation: &mut Vec<u8>,
-    serial_buffer: &mut [u8],
-) {
-    index_buffer_allocation.clear();
-    let byte_offset = chunk_data_file.seek(SeekFrom::End(0)).unwrap();
-    serialize_chunk_data(densities, materials, serial_buffer);
-    chunk_data_file.write_all(serial_buffer).unwrap();
-    chunk_data_file.flush().unwrap();
-    chunk_index_file.seek(SeekFrom::End(0)).unwrap();
-    index_buffer_allocation.extend_from_slice(&chunk_coord.0.to_le_bytes());
-    index_buffer_allocation.extend_from_slice(&chunk_coord.1.to_le_bytes());
-    index_buffer_allocation.extend_from_slice(&chunk_coord.2.to_le_bytes());
-    index_buffer_allocation.extend_from_slice(&byte_offset.to_le_bytes());
-    chunk_index_file
-        .write_all(&index_buffer_allocation)
-        .unwrap();
-    chunk_index_file.flush().unwrap();
-    index_map_delta.insert(*chunk_coord, byte_offset);
+    summary: &ChunkSummary,
+    modified: u64,
+    chunk_coord: &ChunkCoord,
+    index_map_delta: &mut FxHashMap<ChunkCoord, u64>,
+    region_files: &mut RegionFiles,
+    serial_buffer: &mut Vec<u8>,
+) -> Result<(), TerrainError> {
+    let blob = serialize_chunk_data(densities, materials, CHUNK_CODEC, serial_buffer);
+    let sector_count = sectors_for(blob.len());
+    let sector = region_files.allocate(chunk_coord, sector_count)?;
+    region_files.write_blob(chunk_coord, sector, blob)?;
+    region_files.write_header_entry(chunk_coord, sector, sector_count, summary, modified)?;
+    index_map_delta.insert(*chunk_coord, sector as u64 * SECTOR_SIZE);
+    Ok(())
 }
 
+//rewrites the chunk in place while it fits its sectors, a chunk that compresses worse than before moves to new ones
+//returns the byte offset the chunk is stored at afterwards
 pub(crate) fn update_chunk(
+    chunk_coord: &ChunkCoord,
+    byte_offset: u64,
+    densities: &[i16],
+    materials: &[MaterialCode],
+    summary: &ChunkSummary,
+    modified: u64,
+    codec: ChunkCodec,
+    region_files: &mut RegionFiles,
+    serial_buffer: &mut Vec<u8>,
+) -> Result<u64, TerrainError> {
+    let blob = serialize_chunk_data(densities, materials, codec, serial_buffer);
+    let needed = sectors_for(blob.len());
+    let allocated = region_files.allocated_sectors(chunk_coord)?;
+    let (sector, sector_count) = if needed <= allocated {
+        ((byte_offset / SECTOR_SIZE) as u32, allocated)
+    } else {
+        (region_files.allocate(chunk_coord, needed)?, needed)
+    };
+    region_files.write_blob(chunk_coord, sector, blob)?;
+    region_files.write_header_entry(chunk_coord, sector, sector_count, summary, modified)?;
+    Ok(sector as u64 * SECTOR_SIZE)
+}
+
+//rewrites only the samples inside dirty, one density span and one material span per z slice
+//each span runs from the slice's first dirty row to its last
+//false when the stored chunk is compressed, those have to be rewritten whole
+pub(crate) fn patch_chunk(
+    chunk_coord: &ChunkCoord,
     byte_offset: u64,
     densities: &[i16],
     materials: &[MaterialCode],
-    chunk_data_file: &mut File,
-    serial_buffer: &mut [u8],
-) {
-    serialize_chunk_data(densities, materials, serial_buffer);
-    chunk_data_file.seek(SeekFrom::Start(byte_offset)).unwrap();
-    chunk_data_file.write_all(serial_buffer).unwrap();
-    chunk_data_file.flush().unwrap();
+    dirty: &DirtyRange,
+    summary: &ChunkSummary,
+    modified: u64,
+    region_files: &mut RegionFiles,
+) -> Result<bool, TerrainError> {
+    let region_file = region_files.file(region_coord(chunk_coord))?;
+    let mut codec = [0u8; 1];
+    region_file.seek(SeekFrom::Start(byte_offset))?;
+    region_file.read_exact(&mut codec)?;
+    if ChunkCodec::from_u8(codec[0])? != ChunkCodec::Raw {
+        return Ok(false);
+    }
+    let densities_start = byte_offset + CHUNK_BLOB_HEADER_SIZE as u64;
+    let mut span = Vec::new();
+    for z in dirty.min.2..=dirty.max.2 {
+        let first = flatten_index(
+            dirty.min.0 as u32,
+            dirty.min.1 as u32,
+            z as u32,
+            SAMPLES_PER_CHUNK_DIM_PADDED,
+        ) as usize;
+        let last = flatten_index(
+            dirty.max.0 as u32,
+            dirty.max.1 as u32,
+            z as u32,
+            SAMPLES_PER_CHUNK_DIM_PADDED,
+        ) as usize;
+        span.clear();
+        span.extend(densities[first..=last].iter().flat_map(|d| d.to_le_bytes()));
+        region_file.seek(SeekFrom::Start(densities_start + first as u64 * 2))?;
+        region_file.write_all(&span)?;
+    }
+    //materials have no padding, padded sample p is material p - 1 and the padding layers have none
+    let materials_start = densities_start + SAMPLES_PER_CHUNK_PADDED as u64 * 2;
+    let interior = |min: usize, max: usize| {
+        let (min, max) = (min.max(1) - 1, max.min(SAMPLES_PER_CHUNK_DIM) - 1);
+        (min <= max).then_some((min as u32, max as u32))
+    };
+    if let (Some((x0, x1)), Some((y0, y1)), Some((z0, z1))) = (
+        interior(dirty.min.0, dirty.max.0),
+        interior(dirty.min.1, dirty.max.1),
+        interior(dirty.min.2, dirty.max.2),
+    ) {
+        for z in z0..=z1 {
+            let first = flatten_index(x0, y0, z, SAMPLES_PER_CHUNK_DIM) as usize;
+            let last = flatten_index(x1, y1, z, SAMPLES_PER_CHUNK_DIM) as usize;
+            span.clear();
+            span.extend(materials[first..=last].iter().map(|m| *m as u8));
+            region_file.seek(SeekFrom::Start(materials_start + first as u64))?;
+            region_file.write_all(&span)?;
+        }
+    }
+    region_file.flush()?;
+    let sector = (byte_offset / SECTOR_SIZE) as u32;
+    let sector_count = region_files.allocated_sectors(chunk_coord)?;
+    region_files.write_header_entry(chunk_coord, sector, sector_count, summary, modified)?;
+    Ok(true)
+}
+
+//truncated after the write so a crash part way through never leaves an empty file
+pub(crate) fn replace_file_contents(mut file: &File, contents: &[u8]) -> std::io::Result<()> {
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(contents)?;
+    file.set_len(contents.len() as u64)?;
+    file.sync_data()
+}
+
+//reads a chunk back and stores it again with CHUNK_CODEC in the sectors it already has
+//only for chunks stored raw, anything else could come out bigger than its sectors
+pub(crate) fn recompress_chunk(
+    chunk_coord: &ChunkCoord,
+    byte_offset: u64,
+    density_buffer: &mut [i16],
+    material_buffer: &mut [MaterialCode],
+    region_files: &mut RegionFiles,
+    serial_buffer: &mut Vec<u8>,
+) -> Result<(), TerrainError> {
+    load_chunk(
+        region_files,
+        chunk_coord,
+        byte_offset,
+        density_buffer,
+        material_buffer,
+    )?;
+    let blob = serialize_chunk_data(density_buffer, material_buffer, CHUNK_CODEC, serial_buffer);
+    region_files.write_blob(chunk_coord, (byte_offset / SECTOR_SIZE) as u32, blob)
+}
+
+//drops the chunk from its region header, the delta entry tells readers it is gone and what it became until the next session
+pub(crate) fn remove_chunk(
+    chunk_coord: &ChunkCoord,
+    removed_as: Uniformity,
+    index_map_delta: &mut FxHashMap<ChunkCoord, u64>,
+    region_files: &mut RegionFiles,
+) -> Result<(), TerrainError> {
+    region_files.clear_header_entry(chunk_coord)?;
+    let marker = match removed_as {
+        Uniformity::Dirt => REMOVED_DIRT_CHUNK_OFFSET,
+        _ => REMOVED_CHUNK_OFFSET,
+    };
+    index_map_delta.insert(*chunk_coord, marker);
+    Ok(())
+}
+
+//Air or Dirt when the delta entry is a remove_chunk marker instead of a sector offset
+pub(crate) fn removed_as(offset: u64) -> Option<Uniformity> {
+    match offset {
+        REMOVED_CHUNK_OFFSET => Some(Uniformity::Air),
+        REMOVED_DIRT_CHUNK_OFFSET => Some(Uniformity::Dirt),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CompactReport {
+    pub regions: usize,
+    pub chunks: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+//rewrites every region whose unused bytes are more than min_waste of the file, chunks packed right after the header
+//regions left without chunks are deleted, each region is replaced whole by a rename so a failure leaves the rest intact
+//nothing else may have the region files open, the index map has to be loaded again afterwards
+pub fn compact_region_files(data_dir: &Path, min_waste: f32) -> Result<CompactReport, String> {
+    let mut report = CompactReport::default();
+    let Ok(entries) = read_dir(data_dir.join(REGION_DIR)) else {
+        return Ok(report);
+    };
+    for entry in entries.flatten() {
+        if entry
+            .file_name()
+            .to_str()
+            .and_then(parse_region_file_name)
+            .is_some()
+        {
+            compact_region_file(&entry.path(), min_waste, &mut report)?;
+        }
+    }
+    Ok(report)
+}
+
+fn compact_region_file(
+    path: &Path,
+    min_waste: f32,
+    report: &mut CompactReport,
+) -> Result<(), String> {
+    let io_err = |e: std::io::Error| format!("failed to compact {}: {e}", path.display());
+    let mut region_file = File::open(path).map_err(io_err)?;
+    let file_len = region_file.metadata().map_err(io_err)?.len();
+    let mut header = vec![0u8; HEADER_SIZE];
+    region_file.read_exact(&mut header).map_err(io_err)?;
+    let mut blobs = Vec::new(); //header entry, first sector, blob length
+    let mut used = HEADER_SECTORS as u64 * SECTOR_SIZE;
+    for (i, entry) in header.chunks_exact(HEADER_ENTRY_SIZE).enumerate() {
+        let sector = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+        if sector == 0 {
+            continue;
+        }
+        let mut blob_header = [0u8; CHUNK_BLOB_HEADER_SIZE];
+        region_file
+            .seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))
+            .and_then(|_| region_file.read_exact(&mut blob_header))
+            .map_err(io_err)?;
+        let payload_size = u32::from_le_bytes(blob_header[5..9].try_into().unwrap()) as usize;
+        let blob_len = CHUNK_BLOB_HEADER_SIZE + payload_size;
+        used += sectors_for(blob_len) as u64 * SECTOR_SIZE;
+        blobs.push((i, sector, blob_len));
+    }
+    if blobs.is_empty() {
+        drop(region_file);
+        remove_file(path).map_err(io_err)?;
+        report.regions += 1;
+        report.bytes_before += file_len;
+        return Ok(());
+    }
+    if file_len <= used || (file_len - used) as f32 <= min_waste * file_len as f32 {
+        return Ok(());
+    }
+    let tmp_path = path.with_extension("bin.tmp");
+    let mut compacted = File::create(&tmp_path).map_err(io_err)?;
+    let mut blob = Vec::new();
+    let mut next_sector = HEADER_SECTORS;
+    for (i, sector, blob_len) in &blobs {
+        blob.resize(*blob_len, 0);
+        region_file
+            .seek(SeekFrom::Start(*sector as u64 * SECTOR_SIZE))
+            .and_then(|_| region_file.read_exact(&mut blob))
+            .map_err(io_err)?;
+        compacted
+            .seek(SeekFrom::Start(next_sector as u64 * SECTOR_SIZE))
+            .and_then(|_| compacted.write_all(&blob))
+            .map_err(io_err)?;
+        let sector_count = sectors_for(*blob_len);
+        let entry = &mut header[i * HEADER_ENTRY_SIZE..(i + 1) * HEADER_ENTRY_SIZE];
+        entry[0..4].copy_from_slice(&next_sector.to_le_bytes());
+        entry[4..6].copy_from_slice(&(sector_count as u16).to_le_bytes());
+        next_sector += sector_count;
+    }
+    let compacted_len = next_sector as u64 * SECTOR_SIZE;
+    compacted
+        .set_len(compacted_len)
+        .and_then(|_| compacted.seek(SeekFrom::Start(0)))
+        .and_then(|_| compacted.write_all(&header))
+        .and_then(|_| compacted.sync_all())
+        .map_err(io_err)?;
+    drop(region_file);
+    rename(&tmp_path, path).map_err(io_err)?;
+    report.regions += 1;
+    report.chunks += blobs.len();
+    report.bytes_before += file_len;
+    report.bytes_after += compacted_len;
+    Ok(())
 }
 
 //loads chunk data into provided density and material buffers
-pub(crate) fn load_chunk(
-    chunk_data_file: &mut File,
+pub fn load_chunk(
+    region_files: &mut RegionFiles,
+    chunk_coord: &ChunkCoord,
     byte_offset: u64,
     density_buffer: &mut [i16],
     material_buffer: &mut [MaterialCode],
-) {
-    chunk_data_file.seek(SeekFrom::Start(byte_offset)).unwrap();
-    let mut buffer = [0u8; CHUNK_SERIALIZED_SIZE];
-    chunk_data_file.read_exact(&mut buffer).unwrap();
-    deserialize_chunk_data(&buffer, density_buffer, material_buffer);
+) -> Result<(), TerrainError> {
+    let mut read_buffers = std::mem::take(&mut region_files.read_buffers);
+    let region = region_coord(chunk_coord);
+    let loaded = match region_files
+        .maps
+        .as_ref()
+        .and_then(|maps| mapped_blob(maps, &region_files.dir, region, byte_offset))
+    {
+        Some((map, blob)) => deserialize_chunk_data(
+            &map[blob],
+            &mut read_buffers.raw,
+            density_buffer,
+            material_buffer,
+        ),
+        None => region_files.file(region).and_then(|region_file| {
+            read_chunk_at(
+                region_file,
+                byte_offset,
+                &mut read_buffers,
+                density_buffer,
+                material_buffer,
+            )
+        }),
+    };
+    region_files.read_buffers = read_buffers;
+    loaded
 }
 
-pub fn load_chunk_index_map(index_file: &mut File) -> FxHashMap<(i16, i16, i16), u64> {
+//the map holding a stored chunk and the range of its blob, header included
+fn mapped_blob(
+    maps: &RegionMaps,
+    dir: &Path,
+    region: (i16, i16, i16),
+    byte_offset: u64,
+) -> Option<(Arc<Mmap>, std::ops::Range<usize>)> {
+    let header_end = byte_offset + CHUNK_BLOB_HEADER_SIZE as u64;
+    let map = maps.covering(dir, region, header_end)?;
+    let start = byte_offset as usize;
+    let header = &map[start..header_end as usize];
+    let payload_size = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+    let end = start + CHUNK_BLOB_HEADER_SIZE + payload_size;
+    let map = maps.covering(dir, region, end as u64)?;
+    Some((map, start..end))
+}
+
+//one stored chunk at a byte offset, the header says how much to read
+pub fn read_chunk_at(
+    file: &mut File,
+    byte_offset: u64,
+    read_buffers: &mut ChunkReadBuffers,
+    density_buffer: &mut [i16],
+    material_buffer: &mut [MaterialCode],
+) -> Result<(), TerrainError> {
+    let mut header = [0u8; CHUNK_BLOB_HEADER_SIZE];
+    file.seek(SeekFrom::Start(byte_offset))?;
+    file.read_exact(&mut header)?;
+    let payload_size = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+    //no stored chunk comes close to twice its raw size, a bigger one is a corrupt header
+    if payload_size > 2 * CHUNK_SERIALIZED_SIZE {
+        return Err(TerrainError::CorruptChunk("payload size too large"));
+    }
+    read_buffers.blob.clear();
+    read_buffers.blob.extend_from_slice(&header);
+    read_buffers
+        .blob
+        .resize(CHUNK_BLOB_HEADER_SIZE + payload_size, 0);
+    file.read_exact(&mut read_buffers.blob[CHUNK_BLOB_HEADER_SIZE..])?;
+    deserialize_chunk_data(
+        &read_buffers.blob,
+        &mut read_buffers.raw,
+        density_buffer,
+        material_buffer,
+    )
+}
+
+//uncompressed chunk without a header, how the flat chunk_data file stored them before region files
+pub(crate) fn read_raw_chunk_at(
+    file: &mut File,
+    byte_offset: u64,
+    density_buffer: &mut [i16],
+    material_buffer: &mut [MaterialCode],
+) -> Result<(), TerrainError> {
+    file.seek(SeekFrom::Start(byte_offset))?;
+    let mut buffer = vec![0u8; CHUNK_SERIALIZED_SIZE];
+    file.read_exact(&mut buffer)?;
+    read_raw_chunk_data(&buffer, density_buffer, material_buffer)
+}
+
+//reads every region header under data_dir, a world without a region directory is empty
+//a region that cant be opened or whose header is cut short is skipped with a warning, its chunks are generated again
+pub fn load_chunk_index_map(
+    data_dir: &Path,
+    summaries: &mut FxHashMap<ChunkCoord, ChunkSummary>,
+    modified_times: &mut FxHashMap<ChunkCoord, u64>,
+) -> FxHashMap<ChunkCoord, u64> {
     let mut index_map = FxHashMap::default();
-    index_file.seek(SeekFrom::Start(0)).unwrap();
-    let mut buffer = [0u8; 14]; // sizeof (i16, i16, i16, u64)
-    while let Ok(_) = index_file.read_exact(&mut buffer) {
-        let x = i16::from_le_bytes([buffer[0], buffer[1]]);
-        let y = i16::from_le_bytes([buffer[2], buffer[3]]);
-        let z = i16::from_le_bytes([buffer[4], buffer[5]]);
-        let offset = u64::from_le_bytes([
-            buffer[6], buffer[7], buffer[8], buffer[9], buffer[10], buffer[11], buffer[12],
-            buffer[13],
-        ]);
-        index_map.insert((x, y, z), offset);
+    let Ok(entries) = read_dir(data_dir.join(REGION_DIR)) else {
+        return index_map;
+    };
+    let mut header = vec![0u8; HEADER_SIZE];
+    let dim = REGION_DIM as usize;
+    for entry in entries.flatten() {
+        let Some(region) = entry.file_name().to_str().and_then(parse_region_file_name) else {
+            continue;
+        };
+        let mut region_file = match File::open(entry.path()) {
+            Ok(region_file) => region_file,
+            Err(e) => {
+                warn!("{}", TerrainError::Open(entry.path(), e));
+                continue;
+            }
+        };
+        if region_file.read_exact(&mut header).is_err() {
+            warn!("{}", TerrainError::TruncatedRegionHeader(entry.path()));
+            continue;
+        }
+        for (i, record) in header.chunks_exact(HEADER_ENTRY_SIZE).enumerate() {
+            let sector = u32::from_le_bytes(record[0..4].try_into().unwrap());
+            if sector == 0 {
+                continue;
+            }
+            let chunk_coord = ChunkCoord(
+                region.0 * REGION_DIM + (i % dim) as i16,
+                region.1 * REGION_DIM + (i / dim % dim) as i16,
+                region.2 * REGION_DIM + (i / (dim * dim)) as i16,
+            );
+            let modified = u64::from_le_bytes(record[6..14].try_into().unwrap());
+            index_map.insert(chunk_coord, sector as u64 * SECTOR_SIZE);
+            modified_times.insert(chunk_coord, modified);
+            //a bad summary only loses the summary, the chunk data behind it is still read
+            match ChunkSummary::from_bytes(&record[14..]) {
+                Ok(summary) => {
+                    summaries.insert(chunk_coord, summary);
+                }
+                Err(e) => warn!(
+                    "summary of chunk {chunk_coord} in {}: {e}",
+                    entry.path().display()
+                ),
+            }
+        }
     }
     index_map
 }
 
-pub fn get_project_root() -> PathBuf {
-    let exe_path = std::env::current_exe().expect("Failed to get executable path");
-    exe_path
-        .parent()
-        .and_then(|p| p.parent())
-        .and_then(|p| p.parent())
-        .expect("Failed to get project root")
-        .to_path_buf()
+pub fn unix_millis_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
 }
 
+//bytes on disk of a save file, or of everything in a save directory
+pub(crate) fn disk_size(path: &Path) -> Option<u64> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return Some(metadata.len());
+    }
+    Some(
+        read_dir(path)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+    )
+}
+
+//creates the world directory and checks its header before anything reads or writes the world, migrating an old one
+pub fn open_world_dir(data_dir: &Path) -> Result<(), TerrainError> {
+    let latest = data_dir.join("latest");
+    create_dir_all(&latest).map_err(|e| TerrainError::Open(latest, e))?;
+    validate_world_header(data_dir).map_err(TerrainError::RejectedWorld)?;
+    Ok(())
+}
+
+//the plugin has already opened the world with open_world_dir
 pub fn setup_chunk_loading(mut commands: Commands) {
-    let root = get_project_root();
-    create_dir_all(root.join("data/latest")).expect("Failed to create data directory");
     commands.insert_resource(ChunkEntityMap::new());
 }
 
@@ -139,10 +1052,10 @@ pub fn load_uniform_chunks(
     f: &mut File,
     uniformity: Uniformity,
     column_range_map: &mut ColumnRangeMap,
-) -> VecDeque<u64> {
-    f.seek(SeekFrom::Start(0)).unwrap();
+) -> Result<VecDeque<u64>, TerrainError> {
+    f.seek(SeekFrom::Start(0))?;
     let mut data = Vec::new();
-    f.read_to_end(&mut data).unwrap();
+    f.read_to_end(&mut data)?;
     // let count = data.len() / 6;
     let mut free_slots = VecDeque::new();
     for (i, b) in data.chunks_exact(6).enumerate() {
@@ -150,7 +1063,7 @@ pub fn load_uniform_chunks(
         if b == TOMBSTONE_BYTES {
             free_slots.push_back(offset);
         } else {
-            let coord = (
+            let coord = ChunkCoord(
                 i16::from_le_bytes([b[0], b[1]]),
                 i16::from_le_bytes([b[2], b[3]]),
                 i16::from_le_bytes([b[4], b[5]]),
@@ -158,35 +1071,37 @@ pub fn load_uniform_chunks(
             column_range_map.insert(coord, uniformity);
         }
     }
-    free_slots
+    Ok(free_slots)
 }
 
 // Write either into a free slot or append
 pub fn write_uniform_chunk(
-    chunk_coord: &(i16, i16, i16),
+    chunk_coord: &ChunkCoord,
     f: &mut File,
     free_slots: &mut VecDeque<u64>,
-) {
+) -> Result<(), TerrainError> {
     let mut buffer = [0; 6];
     buffer[..2].copy_from_slice(&chunk_coord.0.to_le_bytes());
     buffer[2..4].copy_from_slice(&chunk_coord.1.to_le_bytes());
     buffer[4..6].copy_from_slice(&chunk_coord.2.to_le_bytes());
-    if let Some(pos) = free_slots.pop_front() {
-        f.seek(SeekFrom::Start(pos)).unwrap();
-    } else {
-        f.seek(SeekFrom::End(0)).unwrap();
-    }
-    f.write_all(&buffer).unwrap();
-    f.flush().unwrap();
+    //the slot only leaves the free list once the entry is in it, a failed write can take it again
+    match free_slots.front() {
+        Some(&pos) => f.seek(SeekFrom::Start(pos))?,
+        None => f.seek(SeekFrom::End(0))?,
+    };
+    f.write_all(&buffer)?;
+    f.flush()?;
+    free_slots.pop_front();
+    Ok(())
 }
 
 // Mark a chunk as deleted by overwriting with tombstone bytes
 pub fn remove_uniform_chunk(
-    chunk_coord: &(i16, i16, i16),
+    chunk_coord: &ChunkCoord,
     f: &mut File,
     free_uniform_slots: &mut VecDeque<u64>,
-) {
-    f.seek(SeekFrom::Start(0)).unwrap();
+) -> Result<(), TerrainError> {
+    f.seek(SeekFrom::Start(0))?;
     let mut buffer = [0; 6];
     let mut offset = 0;
     while let Ok(_) = f.read_exact(&mut buffer) {
@@ -195,13 +1110,14 @@ pub fn remove_uniform_chunk(
             let y = i16::from_le_bytes([buffer[2], buffer[3]]);
             let z = i16::from_le_bytes([buffer[4], buffer[5]]);
             if (x, y, z) == *chunk_coord {
-                f.seek(SeekFrom::Start(offset)).unwrap();
-                f.write_all(&TOMBSTONE_BYTES).unwrap();
+                f.seek(SeekFrom::Start(offset))?;
+                f.write_all(&TOMBSTONE_BYTES)?;
                 free_uniform_slots.push_back(offset);
                 break;
             }
         }
         offset += 6;
     }
-    f.flush().unwrap();
+    f.flush()?;
+    Ok(())
 }
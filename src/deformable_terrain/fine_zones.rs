@@ -0,0 +1,707 @@
+use std::sync::Arc;
+use std::thread;
+
+use bevy::{camera::primitives::MeshAabb, prelude::*};
+use bevy_rapier3d::prelude::{Collider, ColliderDisabled};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::{
+        HALF_CHUNK, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+        SAMPLES_PER_CHUNK_PADDED, VOXEL_WORLD_SIZE,
+    },
+    conversions::{flatten_index, world_pos_to_chunk_coord},
+    deformable_terrain::{
+        chunk_entity_map::ChunkEntityMap,
+        chunk_generator::MaterialCode,
+        chunk_iter::chunks_in_sphere,
+        chunk_summary::compute_chunk_summary,
+        digging::{TerrainDug, apply_brush, dig_sample},
+        driver::{MESHING_MODE, TerrainChunkMap, trimesh_collider},
+        file_loader::{
+            CHUNK_CODEC, RegionFiles, load_chunk, load_chunk_index_map, unix_millis_now,
+            update_chunk, write_chunk,
+        },
+        marching_cubes::{
+            greedy_cubes::greedy_cubes_mesh_generation,
+            mc::{MaterialResolution, mc_mesh_generation},
+        },
+        plugin::MeshingMode,
+        protected_regions::ProtectedRegions,
+        sdf_value::SdfValue,
+        storage_paths::StoragePaths,
+        terrain::{
+            NonUniformTerrainChunk, TerrainChunk, TerrainMaterialHandle, generate_bevy_mesh,
+        },
+    },
+    player::player::PlayerTag,
+    ui::console::{Console, ConsoleCommand},
+};
+
+//refined chunks are split into 2x2x2 octants, each a normal chunk grid at half the size, so voxels are half as wide
+//octants are stored like chunks in their own region files, keyed by fine coords where fine coord 2c + o is octant o of chunk c
+//a fine coord is a ChunkCoord on the half size grid, it is never passed to the chunk conversions
+//there is no stitching, where a refined chunk meets a coarse one the surfaces can be up to half a coarse voxel apart
+const FINE_DATA_DIR: &str = "fine"; //inside the world data dir
+const FINE_CHUNK_WORLD_SIZE: f32 = HALF_CHUNK;
+const FINE_VOXEL_WORLD_SIZE: f32 = VOXEL_WORLD_SIZE / 2.0;
+const MAX_REFINE_RADIUS: i16 = 2; //in chunks, every refined chunk keeps ~7mb of octants in memory once loaded
+const MAX_ACTIVATIONS_PER_FRAME: usize = 1; //loading and meshing 8 octants is a few ms
+
+#[derive(Component)]
+pub struct FineChunkTag;
+
+struct FineWrite {
+    fine_coord: ChunkCoord,
+    densities: Arc<[i16]>,
+    materials: Arc<[MaterialCode]>,
+}
+
+struct FineRemeshRequest {
+    fine_coord: ChunkCoord,
+    generation: u64,
+    octant: NonUniformTerrainChunk,
+}
+
+struct FineRemeshed {
+    fine_coord: ChunkCoord,
+    generation: u64,
+    mesh: Option<(Mesh, Option<Collider>)>,
+}
+
+//refined chunks are shown at fine scale while the coarse chunk is loaded with full detail, the coarse entity is hidden meanwhile
+//the coarse chunk is still dug alongside so it stays a close stand in for distant lods and for whoever reads the chunk map
+#[derive(Resource)]
+pub struct FineZones {
+    refined: FxHashSet<ChunkCoord>,
+    active: FxHashMap<ChunkCoord, Option<Entity>>, //refined chunk -> the coarse entity hidden for it
+    octants: FxHashMap<ChunkCoord, NonUniformTerrainChunk>, //kept once loaded so the disk copy is only ever written
+    entities: FxHashMap<ChunkCoord, (Entity, Handle<Mesh>)>,
+    index_map: FxHashMap<ChunkCoord, u64>, //octants stored before this session
+    reader: RegionFiles,
+    writer: Sender<FineWrite>,
+    remesh_sender: Sender<FineRemeshRequest>,
+    remesh_receiver: Receiver<FineRemeshed>,
+    pending_remeshes: FxHashMap<ChunkCoord, u64>, //dug octant -> generation of the latest remesh sent for it
+    remesh_generation: u64,
+}
+
+impl FineZones {
+    fn load_octants(&mut self, chunk_coord: ChunkCoord, coarse: Option<&TerrainChunk>) {
+        for fine_coord in octant_coords(chunk_coord) {
+            if self.octants.contains_key(&fine_coord) {
+                continue;
+            }
+            let stored = self.index_map.get(&fine_coord).and_then(|&offset| {
+                let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+                let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+                load_chunk(
+                    &mut self.reader,
+                    &fine_coord,
+                    offset,
+                    &mut densities,
+                    &mut materials,
+                )
+                .inspect_err(|e| {
+                    warn!("failed to read fine zone chunk {fine_coord}, it is resampled: {e}")
+                })
+                .ok()?;
+                Some(NonUniformTerrainChunk {
+                    densities: densities.into(),
+                    materials: materials.into(),
+                })
+            });
+            //one that cant be read is resampled from the coarse chunk the same as one never stored
+            let octant = match (stored, coarse) {
+                (Some(octant), _) => octant,
+                (None, Some(coarse)) => {
+                    let octant = resample_octant(coarse, fine_coord);
+                    self.write(fine_coord, &octant);
+                    octant
+                }
+                (None, None) => continue,
+            };
+            self.octants.insert(fine_coord, octant);
+        }
+    }
+
+    fn write(&self, fine_coord: ChunkCoord, octant: &NonUniformTerrainChunk) {
+        let _ = self.writer.send(FineWrite {
+            fine_coord,
+            densities: Arc::clone(&octant.densities),
+            materials: Arc::clone(&octant.materials),
+        });
+    }
+
+    //meshed on the remesh thread, the octant keeps showing its old mesh until apply_fine_remeshes swaps it
+    fn request_remesh(&mut self, fine_coord: ChunkCoord) {
+        let Some(octant) = self.octants.get(&fine_coord) else {
+            return;
+        };
+        self.remesh_generation += 1;
+        let _ = self.remesh_sender.send(FineRemeshRequest {
+            fine_coord,
+            generation: self.remesh_generation,
+            octant: octant.clone(),
+        });
+        self.pending_remeshes
+            .insert(fine_coord, self.remesh_generation);
+    }
+
+    //every dig so far has its new mesh in place
+    pub(crate) fn is_idle(&self) -> bool {
+        self.pending_remeshes.is_empty()
+    }
+}
+
+pub fn octant_coords(chunk_coord: ChunkCoord) -> impl Iterator<Item = ChunkCoord> {
+    (0..8).map(move |o| {
+        ChunkCoord(
+            chunk_coord.0 * 2 + (o & 1),
+            chunk_coord.1 * 2 + (o >> 1 & 1),
+            chunk_coord.2 * 2 + (o >> 2),
+        )
+    })
+}
+
+pub fn parent_chunk(fine_coord: ChunkCoord) -> ChunkCoord {
+    ChunkCoord(
+        fine_coord.0.div_euclid(2),
+        fine_coord.1.div_euclid(2),
+        fine_coord.2.div_euclid(2),
+    )
+}
+
+pub fn fine_coord_to_world_pos(fine_coord: &ChunkCoord) -> Vec3 {
+    Vec3::new(
+        fine_coord.0 as f32,
+        fine_coord.1 as f32,
+        fine_coord.2 as f32,
+    ) * FINE_CHUNK_WORLD_SIZE
+        - Vec3::splat(FINE_CHUNK_WORLD_SIZE / 2.0)
+}
+
+fn world_pos_to_fine_coord(world_pos: Vec3) -> ChunkCoord {
+    let fine = (world_pos / FINE_CHUNK_WORLD_SIZE + Vec3::ONE).floor();
+    ChunkCoord(fine.x as i16, fine.y as i16, fine.z as i16)
+}
+
+//fine padded sample i of octant o lands on coarse padded sample (63o + 1 + i) / 2, halfway to the next one when that is odd
+fn coarse_span(octant: i16, fine_padded_index: usize) -> (usize, usize) {
+    let n = 63 * octant as usize + 1 + fine_padded_index;
+    (n / 2, n / 2 + n % 2)
+}
+
+//trilinear densities and nearest materials from the coarse chunk the octant sits in
+fn resample_octant(coarse: &TerrainChunk, fine_coord: ChunkCoord) -> NonUniformTerrainChunk {
+    let coarse = match coarse {
+        TerrainChunk::UniformAir => {
+            return NonUniformTerrainChunk {
+                densities: Arc::new([SdfValue::AIR.0; SAMPLES_PER_CHUNK_PADDED]),
+                materials: Arc::new([MaterialCode::Air; SAMPLES_PER_CHUNK]),
+            };
+        }
+        TerrainChunk::UniformDirt => {
+            return NonUniformTerrainChunk {
+                densities: Arc::new([SdfValue::SOLID.0; SAMPLES_PER_CHUNK_PADDED]),
+                materials: Arc::new([MaterialCode::Dirt; SAMPLES_PER_CHUNK]),
+            };
+        }
+        TerrainChunk::NonUniformTerrainChunk(chunk) => chunk,
+    };
+    let octant = (
+        fine_coord.0.rem_euclid(2),
+        fine_coord.1.rem_euclid(2),
+        fine_coord.2.rem_euclid(2),
+    );
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        let zs = coarse_span(octant.2, z);
+        for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let ys = coarse_span(octant.1, y);
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let xs = coarse_span(octant.0, x);
+                let mut sum = 0.0;
+                for cz in [zs.0, zs.1] {
+                    for cy in [ys.0, ys.1] {
+                        for cx in [xs.0, xs.1] {
+                            sum += coarse.get_density(cx as u32, cy as u32, cz as u32) as f32;
+                        }
+                    }
+                }
+                let index =
+                    flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
+                densities[index as usize] = (sum / 8.0).round() as i16;
+            }
+        }
+    }
+    let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+    let material_index = |octant: i16, i: usize| coarse_span(octant, i + 1).0 - 1;
+    for z in 0..SAMPLES_PER_CHUNK_DIM {
+        let cz = material_index(octant.2, z);
+        for y in 0..SAMPLES_PER_CHUNK_DIM {
+            let cy = material_index(octant.1, y);
+            for x in 0..SAMPLES_PER_CHUNK_DIM {
+                let cx = material_index(octant.0, x);
+                let index = flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM);
+                let coarse_index =
+                    flatten_index(cx as u32, cy as u32, cz as u32, SAMPLES_PER_CHUNK_DIM);
+                materials[index as usize] = coarse.materials[coarse_index as usize];
+            }
+        }
+    }
+    NonUniformTerrainChunk {
+        densities: densities.into(),
+        materials: materials.into(),
+    }
+}
+
+//meshed as a full size chunk and scaled down, None when there is no surface
+fn mesh_octant(octant: &NonUniformTerrainChunk) -> Option<Mesh> {
+    let (mut vertices, normals, material_ids, indices) = match *MESHING_MODE.read() {
+        MeshingMode::MarchingCubes => mc_mesh_generation(
+            &octant.densities,
+            &octant.materials,
+            SAMPLES_PER_CHUNK_DIM,
+            true,
+            &octant.densities,
+            MaterialResolution::default(),
+        ),
+        MeshingMode::GreedyCubes => greedy_cubes_mesh_generation(
+            &octant.densities,
+            &octant.materials,
+            SAMPLES_PER_CHUNK_DIM,
+            true,
+        ),
+    };
+    if indices.is_empty() {
+        return None;
+    }
+    for vertex in &mut vertices {
+        *vertex *= 0.5;
+    }
+    Some(generate_bevy_mesh(vertices, normals, material_ids, indices))
+}
+
+//the mesh and its collider, a mesh rapier refuses is still shown, it just cant be stood on until the next dig remeshes it
+fn build_octant(
+    fine_coord: ChunkCoord,
+    octant: &NonUniformTerrainChunk,
+) -> Option<(Mesh, Option<Collider>)> {
+    let mesh = mesh_octant(octant)?;
+    let collider = trimesh_collider(fine_coord, &mesh)
+        .inspect_err(|e| warn!("fine zone {e}, it is left without one"))
+        .ok();
+    Some((mesh, collider))
+}
+
+//spawns, replaces or despawns the octant's entity to match its samples
+fn remesh_octant(
+    fine_zones: &mut FineZones,
+    fine_coord: ChunkCoord,
+    commands: &mut Commands,
+    mesh_handles: &mut Assets<Mesh>,
+    material_handle: &TerrainMaterialHandle,
+) {
+    let mesh = fine_zones
+        .octants
+        .get(&fine_coord)
+        .and_then(|octant| build_octant(fine_coord, octant));
+    place_octant(
+        fine_zones,
+        fine_coord,
+        mesh,
+        commands,
+        mesh_handles,
+        material_handle,
+    );
+}
+
+//spawns, replaces or despawns the octant's entity to match mesh
+fn place_octant(
+    fine_zones: &mut FineZones,
+    fine_coord: ChunkCoord,
+    mesh: Option<(Mesh, Option<Collider>)>,
+    commands: &mut Commands,
+    mesh_handles: &mut Assets<Mesh>,
+    material_handle: &TerrainMaterialHandle,
+) {
+    let existing = fine_zones.entities.remove(&fine_coord);
+    let Some((mesh, collider)) = mesh else {
+        if let Some((entity, mesh_handle)) = existing {
+            commands.entity(entity).despawn();
+            mesh_handles.remove(&mesh_handle);
+        }
+        return;
+    };
+    let aabb = mesh.compute_aabb();
+    let mesh_handle = mesh_handles.add(mesh);
+    let entity = match existing {
+        Some((entity, old_mesh_handle)) => {
+            mesh_handles.remove(&old_mesh_handle);
+            commands.entity(entity).insert(Mesh3d(mesh_handle.clone()));
+            entity
+        }
+        None => commands
+            .spawn((
+                Mesh3d(mesh_handle.clone()),
+                MeshMaterial3d(material_handle.0.clone()),
+                FineChunkTag,
+                Transform::from_translation(fine_coord_to_world_pos(&fine_coord)),
+            ))
+            .id(),
+    };
+    match collider {
+        Some(collider) => commands.entity(entity).insert(collider),
+        None => commands.entity(entity).remove::<Collider>(),
+    };
+    if let Some(aabb) = aabb {
+        commands.entity(entity).insert(aabb);
+    }
+    fine_zones
+        .entities
+        .insert(fine_coord, (entity, mesh_handle));
+}
+
+//assume duplicate writes are impossible, same as the main write thread
+//a failed write is only logged, octants are always written whole so the next dig to it writes it again
+fn fine_write_thread(
+    rx: Receiver<FineWrite>,
+    mut region_files: RegionFiles,
+    mut index_map: FxHashMap<ChunkCoord, u64>,
+) {
+    let mut serial_buffer = Vec::new();
+    while let Ok(FineWrite {
+        fine_coord,
+        densities,
+        materials,
+    }) = rx.recv()
+    {
+        let summary = compute_chunk_summary(&densities, &materials, parent_chunk(fine_coord));
+        let modified = unix_millis_now();
+        let written = match index_map.get(&fine_coord).copied() {
+            Some(offset) => update_chunk(
+                &fine_coord,
+                offset,
+                &densities,
+                &materials,
+                &summary,
+                modified,
+                CHUNK_CODEC,
+                &mut region_files,
+                &mut serial_buffer,
+            )
+            .map(|new_offset| {
+                index_map.insert(fine_coord, new_offset);
+            }),
+            None => write_chunk(
+                &densities,
+                &materials,
+                &summary,
+                modified,
+                &fine_coord,
+                &mut index_map,
+                &mut region_files,
+                &mut serial_buffer,
+            ),
+        };
+        if let Err(e) = written {
+            warn!("failed to write fine zone chunk {fine_coord}: {e}");
+        }
+    }
+}
+
+//rapier takes milliseconds over a fine trimesh, a big brush reaching a dozen octants would stall the frame on the main thread
+fn fine_remesh_thread(rx: Receiver<FineRemeshRequest>, tx: Sender<FineRemeshed>) {
+    while let Ok(FineRemeshRequest {
+        fine_coord,
+        generation,
+        octant,
+    }) = rx.recv()
+    {
+        let mesh = build_octant(fine_coord, &octant);
+        let _ = tx.send(FineRemeshed {
+            fine_coord,
+            generation,
+            mesh,
+        });
+    }
+}
+
+//the plugin checked the world header in open_world_dir before any system runs
+pub(crate) fn setup_fine_zones(mut commands: Commands, storage: Res<StoragePaths>) {
+    let data_dir = storage.world_dir().join(FINE_DATA_DIR);
+    let index_map = load_chunk_index_map(
+        &data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    let refined = index_map.keys().copied().map(parent_chunk).collect();
+    let (writer, rx) = unbounded();
+    match RegionFiles::writer(&data_dir) {
+        Ok(region_files) => {
+            let thread_index_map = index_map.clone();
+            thread::spawn(move || fine_write_thread(rx, region_files, thread_index_map));
+        }
+        //rx is dropped, fine zones still refine and dig but their edits are lost on exit
+        Err(e) => warn!("fine zone edits will not be saved: {e}"),
+    }
+    let (remesh_sender, remesh_rx) = unbounded();
+    let (remesh_tx, remesh_receiver) = unbounded();
+    thread::spawn(move || fine_remesh_thread(remesh_rx, remesh_tx));
+    commands.insert_resource(FineZones {
+        refined,
+        active: FxHashMap::default(),
+        octants: FxHashMap::default(),
+        entities: FxHashMap::default(),
+        index_map,
+        reader: RegionFiles::reader(&data_dir),
+        writer,
+        remesh_sender,
+        remesh_receiver,
+        pending_remeshes: FxHashMap::default(),
+        remesh_generation: 0,
+    });
+}
+
+//a refined chunk is shown fine while the coarse chunk is in the chunk map, which is everything in the simulation radius
+//outside of it the coarse lods take over again
+pub(crate) fn update_fine_zones(
+    mut commands: Commands,
+    mut fine_zones: ResMut<FineZones>,
+    terrain_chunk_map: Res<TerrainChunkMap>,
+    chunk_entity_map: Res<ChunkEntityMap>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+    material_handle: Res<TerrainMaterialHandle>,
+) {
+    if fine_zones.refined.is_empty() {
+        return;
+    }
+    let deactivated: Vec<ChunkCoord> = fine_zones
+        .active
+        .keys()
+        .filter(|chunk_coord| !terrain_chunk_map.0.contains_key(chunk_coord))
+        .copied()
+        .collect();
+    let mut activated = Vec::new();
+    for chunk_coord in &fine_zones.refined {
+        if !fine_zones.active.contains_key(chunk_coord)
+            && terrain_chunk_map.0.contains_key(chunk_coord)
+            && activated.len() < MAX_ACTIVATIONS_PER_FRAME
+        {
+            activated.push(*chunk_coord);
+        }
+    }
+    for chunk_coord in &activated {
+        fine_zones.load_octants(*chunk_coord, terrain_chunk_map.0.get(chunk_coord).as_ref());
+    }
+    for chunk_coord in deactivated {
+        let hidden = fine_zones.active.remove(&chunk_coord).flatten();
+        for fine_coord in octant_coords(chunk_coord) {
+            fine_zones.pending_remeshes.remove(&fine_coord);
+            if let Some((entity, mesh_handle)) = fine_zones.entities.remove(&fine_coord) {
+                commands.entity(entity).despawn();
+                mesh_handles.remove(&mesh_handle);
+            }
+        }
+        if let Some(entity) = hidden {
+            commands
+                .entity(entity)
+                .try_insert(Visibility::Inherited)
+                .try_remove::<ColliderDisabled>();
+        }
+    }
+    for chunk_coord in activated {
+        for fine_coord in octant_coords(chunk_coord) {
+            remesh_octant(
+                &mut fine_zones,
+                fine_coord,
+                &mut commands,
+                &mut mesh_handles,
+                &material_handle,
+            );
+        }
+        fine_zones.active.insert(chunk_coord, None);
+    }
+    //the driver and digging respawn coarse entities whenever they like, whichever is current gets hidden
+    for (chunk_coord, hidden) in fine_zones.active.iter_mut() {
+        let current = chunk_entity_map
+            .get_option(*chunk_coord)
+            .map(|(entity, _)| *entity);
+        if current != *hidden {
+            if let Some(entity) = current {
+                commands
+                    .entity(entity)
+                    .try_insert((Visibility::Hidden, ColliderDisabled));
+            }
+            *hidden = current;
+        }
+    }
+}
+
+//digs the same brush into every active octant it reaches, through the padding like brush_stroke
+pub(crate) fn dig_fine_zones(
+    mut dug_reader: MessageReader<TerrainDug>,
+    mut fine_zones: ResMut<FineZones>,
+    protected_regions: Res<ProtectedRegions>,
+) {
+    for dug in dug_reader.read() {
+        if fine_zones.active.is_empty() {
+            continue;
+        }
+        let reach = dug.shape.half_extents(dug.radius) + FINE_VOXEL_WORLD_SIZE;
+        let min_fine = world_pos_to_fine_coord(dug.center - reach);
+        let max_fine = world_pos_to_fine_coord(dug.center + reach);
+        let mut modified = Vec::new();
+        for z in min_fine.2..=max_fine.2 {
+            for y in min_fine.1..=max_fine.1 {
+                for x in min_fine.0..=max_fine.0 {
+                    let fine_coord = ChunkCoord(x, y, z);
+                    if !fine_zones.active.contains_key(&parent_chunk(fine_coord)) {
+                        continue;
+                    }
+                    let Some(octant) = fine_zones.octants.get_mut(&fine_coord) else {
+                        continue;
+                    };
+                    let padded_origin = fine_coord_to_world_pos(&fine_coord)
+                        - Vec3::splat(FINE_CHUNK_WORLD_SIZE / 2.0 + FINE_VOXEL_WORLD_SIZE);
+                    let padded_max = padded_origin
+                        + Vec3::splat(FINE_CHUNK_WORLD_SIZE + 2.0 * FINE_VOXEL_WORLD_SIZE);
+                    if !dug
+                        .shape
+                        .reaches(dug.center, dug.radius, &padded_origin, &padded_max)
+                    {
+                        continue;
+                    }
+                    if apply_brush(
+                        Arc::make_mut(&mut octant.densities),
+                        padded_origin,
+                        FINE_VOXEL_WORLD_SIZE,
+                        dug.center,
+                        dug.radius,
+                        dug.shape,
+                        &protected_regions,
+                        dig_sample(dug.strength),
+                    ) {
+                        modified.push(fine_coord);
+                    }
+                }
+            }
+        }
+        for fine_coord in modified {
+            fine_zones.write(fine_coord, &fine_zones.octants[&fine_coord]);
+            fine_zones.request_remesh(fine_coord);
+        }
+    }
+}
+
+//swaps in the meshes the remesh thread made for dug octants, one a later dig or a deactivation superseded is dropped
+pub(crate) fn apply_fine_remeshes(
+    mut commands: Commands,
+    mut fine_zones: ResMut<FineZones>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+    material_handle: Res<TerrainMaterialHandle>,
+) {
+    while let Ok(FineRemeshed {
+        fine_coord,
+        generation,
+        mesh,
+    }) = fine_zones.remesh_receiver.try_recv()
+    {
+        if fine_zones.pending_remeshes.get(&fine_coord) != Some(&generation) {
+            continue;
+        }
+        fine_zones.pending_remeshes.remove(&fine_coord);
+        place_octant(
+            &mut fine_zones,
+            fine_coord,
+            mesh,
+            &mut commands,
+            &mut mesh_handles,
+            &material_handle,
+        );
+    }
+}
+
+//`refine [radius]` resamples the chunks around the player at twice the voxel resolution, radius in chunks
+//refining is permanent for the world, the octants are saved as soon as they are made
+pub fn refine_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    player_query: Query<&Transform, With<PlayerTag>>,
+    mut fine_zones: ResMut<FineZones>,
+) {
+    for command in command_reader.read() {
+        if command.name != "refine" {
+            continue;
+        }
+        let radius = match command.args.first().map(|a| a.parse::<i16>()) {
+            None => 0,
+            Some(Ok(radius)) if (0..=MAX_REFINE_RADIUS).contains(&radius) => radius,
+            _ => {
+                console.print(format!("usage: refine [radius 0-{MAX_REFINE_RADIUS}]"));
+                continue;
+            }
+        };
+        let Ok(player) = player_query.single() else {
+            continue;
+        };
+        let center = world_pos_to_chunk_coord(&player.translation);
+        let mut refined = 0;
+        for chunk_coord in chunks_in_sphere(center, radius) {
+            if fine_zones.refined.insert(chunk_coord) {
+                refined += 1;
+            }
+        }
+        console.print(format!(
+            "refined {refined} chunks, they switch to fine voxels once loaded in the simulation radius"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octants_tile_their_parent_chunk() {
+        for chunk_coord in [ChunkCoord(0, 0, 0), ChunkCoord(-3, 1, 7)] {
+            let center = chunk_coord.to_world_center();
+            let mut sum = Vec3::ZERO;
+            for fine_coord in octant_coords(chunk_coord) {
+                assert_eq!(parent_chunk(fine_coord), chunk_coord);
+                let fine_center = fine_coord_to_world_pos(&fine_coord);
+                assert_eq!(world_pos_to_fine_coord(fine_center), fine_coord);
+                assert_eq!(world_pos_to_chunk_coord(&fine_center), chunk_coord);
+                sum += fine_center - center;
+            }
+            assert!(sum.length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn resampling_interpolates_between_coarse_samples() {
+        let densities: Vec<i16> = (0..SAMPLES_PER_CHUNK_PADDED)
+            .map(|i| (i % 1000) as i16 - 500)
+            .collect();
+        let coarse = NonUniformTerrainChunk {
+            densities: densities.into(),
+            materials: Arc::new([MaterialCode::Dirt; SAMPLES_PER_CHUNK]),
+        };
+        let fine_coord = ChunkCoord(1, 0, 1);
+        let octant = resample_octant(
+            &TerrainChunk::NonUniformTerrainChunk(coarse.clone()),
+            fine_coord,
+        );
+        //the upper octant lands exactly on a coarse sample at even i, the lower one at odd i
+        let exact = octant.densities[flatten_index(2, 1, 2, SAMPLES_PER_CHUNK_DIM_PADDED) as usize];
+        assert_eq!(exact, coarse.get_density(33, 1, 33));
+        let between =
+            octant.densities[flatten_index(2, 2, 2, SAMPLES_PER_CHUNK_DIM_PADDED) as usize];
+        let lower = coarse.get_density(33, 1, 33) as f32;
+        let upper = coarse.get_density(33, 2, 33) as f32;
+        assert_eq!(between, ((lower + upper) / 2.0).round() as i16);
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Collider;
+
+use crate::{
+    constants::{
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+        VOXEL_WORLD_SIZE,
+    },
+    conversions::flatten_index,
+};
+
+//chunks whose surface crosses every column once, solid below and air above, collide against a heightfield
+//rapier handles one far cheaper than the trimesh of the same surface. overhangs, caves and surfaces that leave
+//through the top or bottom of the chunk keep the trimesh
+//the heights are the crossings marching cubes puts on the vertical edges, so the two surfaces share those vertices
+//and differ only in how the cells between them are split into triangles
+pub fn heightfield_collider(densities: &[i16]) -> Option<Collider> {
+    let heights = surface_heights(densities)?;
+    Some(Collider::heightfield(
+        heights,
+        SAMPLES_PER_CHUNK_DIM,
+        SAMPLES_PER_CHUNK_DIM,
+        Vec3::new(CHUNK_WORLD_SIZE, 1.0, CHUNK_WORLD_SIZE),
+    ))
+}
+
+//chunk local height of every unpadded column, column major with rows along z and columns along x like rapier wants
+//None when any column is not solid at the bottom and air at the top with a single crossing between
+pub fn surface_heights(densities: &[i16]) -> Option<Vec<f32>> {
+    let mut heights = Vec::with_capacity(SAMPLES_PER_CHUNK_DIM * SAMPLES_PER_CHUNK_DIM);
+    for x in 1..=SAMPLES_PER_CHUNK_DIM {
+        for z in 1..=SAMPLES_PER_CHUNK_DIM {
+            heights.push(column_height(densities, x, z)?);
+        }
+    }
+    Some(heights)
+}
+
+//x and z are padded indices, only the unpadded samples of the column are looked at
+fn column_height(densities: &[i16], x: usize, z: usize) -> Option<f32> {
+    let sample = |y: usize| {
+        densities
+            [flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED) as usize]
+    };
+    let mut crossing = None;
+    let mut below = sample(1);
+    for y in 2..=SAMPLES_PER_CHUNK_DIM {
+        let above = sample(y);
+        match (below < 0, above < 0) {
+            //y - 2 is the unpadded index of the solid sample
+            (true, false) => crossing = Some((y - 2, below, above)),
+            //solid over air
+            (false, true) => return None,
+            _ => {}
+        }
+        below = above;
+    }
+    //no crossing is a column all solid or all air, the surface leaves through the top or bottom there
+    let (solid_y, solid, air) = crossing?;
+    let t = -(solid as f32) / (air as f32 - solid as f32); //same interpolation as the mesh, air - solid is at least 1
+    Some(-HALF_CHUNK + (solid_y as f32 + t) * VOXEL_WORLD_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SAMPLES_PER_CHUNK_PADDED;
+
+    //padded densities of the ground below height(x) in chunk local space
+    fn ground(height: impl Fn(f32) -> f32) -> Vec<i16> {
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        let local = |i: usize| -HALF_CHUNK + (i as f32 - 1.0) * VOXEL_WORLD_SIZE;
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                    let index =
+                        flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
+                    densities[index as usize] = ((local(y) - height(local(x))) * 1000.0) as i16;
+                }
+            }
+        }
+        densities
+    }
+
+    #[test]
+    fn heights_follow_a_slope_along_the_columns() {
+        let heights = surface_heights(&ground(|x| 0.25 * x + 0.5)).unwrap();
+        assert_eq!(heights.len(), SAMPLES_PER_CHUNK_DIM * SAMPLES_PER_CHUNK_DIM);
+        for column in 0..SAMPLES_PER_CHUNK_DIM {
+            let x = -HALF_CHUNK + column as f32 * VOXEL_WORLD_SIZE;
+            let rows =
+                &heights[column * SAMPLES_PER_CHUNK_DIM..(column + 1) * SAMPLES_PER_CHUNK_DIM];
+            for height in rows {
+                assert!((height - (0.25 * x + 0.5)).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn overhangs_and_surfaces_leaving_the_chunk_fall_back() {
+        let mut densities = ground(|_| 0.5);
+        let index = flatten_index(
+            10,
+            SAMPLES_PER_CHUNK_DIM as u32 - 2,
+            20,
+            SAMPLES_PER_CHUNK_DIM_PADDED,
+        );
+        densities[index as usize] = -100;
+        assert!(surface_heights(&densities).is_none());
+        assert!(surface_heights(&ground(|x| x * 2.0)).is_none());
+    }
+}
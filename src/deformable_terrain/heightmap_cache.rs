@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use std::sync::Arc;
+
+use crate::{
+    constants::SAMPLES_PER_CHUNK_2D_PADDED,
+    deformable_terrain::{
+        density_source::DensitySource, driver::ChunkBuffers, lru_cache::LruCache,
+    },
+};
+
+const HEIGHTMAP_CACHE_CAPACITY: usize = 256; //columns, about 75kb each
+
+//everything prepare_column writes, the same for every chunk stacked in a column
+struct ColumnHeights {
+    heightmap: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
+    dhdx: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
+    dhdz: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
+    path_mask: [bool; SAMPLES_PER_CHUNK_2D_PADDED],
+    water_level: [f32; SAMPLES_PER_CHUNK_2D_PADDED],
+}
+
+impl ColumnHeights {
+    fn copy_from(chunk_buffers: &ChunkBuffers) -> Arc<Self> {
+        Arc::new(ColumnHeights {
+            heightmap: chunk_buffers.heightmap,
+            dhdx: chunk_buffers.dhdx,
+            dhdz: chunk_buffers.dhdz,
+            path_mask: chunk_buffers.path_mask,
+            water_level: chunk_buffers.water_level,
+        })
+    }
+
+    fn copy_into(&self, chunk_buffers: &mut ChunkBuffers) {
+        chunk_buffers.heightmap = self.heightmap;
+        chunk_buffers.dhdx = self.dhdx;
+        chunk_buffers.dhdz = self.dhdz;
+        chunk_buffers.path_mask = self.path_mask;
+        chunk_buffers.water_level = self.water_level;
+    }
+}
+
+//prepared columns shared by all loader threads, keyed on chunk xz
+//a cluster spans several chunks vertically and stacked clusters land on different threads, so without this every one of them reruns the fbm
+pub struct HeightmapCache {
+    columns: LruCache<(i16, i16), Arc<ColumnHeights>>,
+}
+
+impl Default for HeightmapCache {
+    fn default() -> Self {
+        HeightmapCache {
+            columns: LruCache::new(HEIGHTMAP_CACHE_CAPACITY),
+        }
+    }
+}
+
+impl HeightmapCache {
+    //fills the column part of the buffers, from the cache if another chunk of the column was already prepared
+    //two threads missing on the same column both prepare it, the result is identical so the second insert is harmless
+    pub fn prepare_column<S: DensitySource + ?Sized>(
+        &self,
+        source: &S,
+        chunk_xz: (i16, i16),
+        chunk_start: &Vec3,
+        chunk_buffers: &mut ChunkBuffers,
+    ) {
+        if let Some(column) = self.columns.get(&chunk_xz) {
+            column.copy_into(chunk_buffers);
+            return;
+        }
+        source.prepare_column(chunk_start, chunk_buffers);
+        self.columns
+            .insert(chunk_xz, ColumnHeights::copy_from(chunk_buffers));
+    }
+}
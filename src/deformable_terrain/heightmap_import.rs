@@ -0,0 +1,338 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, ImageSampler, ImageType},
+    prelude::*,
+};
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::fs::read;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::{
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_DIM,
+        SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
+    },
+    deformable_terrain::{
+        chunk_generator::{MaterialCode, calculate_chunk_start, chunk_uniformity},
+        chunk_history::ChunkModifiedTimes,
+        chunk_iter::chunks_in_box,
+        chunk_summary::ChunkSummaries,
+        density_source::DensitySource,
+        driver::{ChunkBuffers, WriteCmd, dedicated_write_thread},
+        file_loader::{RegionFiles, load_chunk_index_map},
+        plugin::Uniformity,
+        sdf_value::SdfValue,
+        storage_paths::StoragePaths,
+        world_gen::world_gen_config,
+        world_header::WorldHeader,
+    },
+};
+
+const GRASS_DEPTH: f32 = 0.3; //world units of grass over the dirt
+const BEACH_HEIGHT: f32 = 1.5; //surfaces below this are sand
+const SNOW_LINE: f32 = 180.0;
+const WRITE_QUEUE_SIZE: usize = 64; //non uniform chunks are ~0.8mb each, keeps generation from running away from the disk
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeightmapImportSettings {
+    pub meters_per_pixel: f32,
+    pub height_scale: f32, //world units between black and white
+    pub base_height: f32,  //world height of black
+    pub origin: Vec2,      //world xz of the top left pixel, image rows run along +z
+}
+
+impl Default for HeightmapImportSettings {
+    fn default() -> Self {
+        HeightmapImportSettings {
+            meters_per_pixel: 1.0,
+            height_scale: 256.0,
+            base_height: -32.0,
+            origin: Vec2::ZERO,
+        }
+    }
+}
+
+//a grayscale image as terrain, png and tiff (including single band geotiffs, georeferencing is ignored)
+//the edge pixels extend outward so chunks on the border of the footprint close cleanly
+pub struct HeightmapTerrain {
+    width: usize,
+    height: usize,
+    heights: Vec<f32>, //world units, row major
+    settings: HeightmapImportSettings,
+}
+
+impl HeightmapTerrain {
+    pub fn load(path: &Path, settings: HeightmapImportSettings) -> Result<Self, String> {
+        let bytes = read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| format!("{} has no file extension", path.display()))?;
+        let image = Image::from_buffer(
+            &bytes,
+            ImageType::Extension(extension),
+            CompressedImageFormats::NONE,
+            false,
+            ImageSampler::Default,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+        .map_err(|e| format!("failed to decode {}: {e}", path.display()))?;
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let mut heights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                //16 bit and float bands come through as the red channel, 8 bit gray is expanded to rgba
+                let value = image
+                    .get_color_at(x as u32, y as u32)
+                    .map_err(|e| format!("unsupported pixel format in {}: {e}", path.display()))?
+                    .to_linear()
+                    .red;
+                heights.push(settings.base_height + value * settings.height_scale);
+            }
+        }
+        Ok(HeightmapTerrain {
+            width,
+            height,
+            heights,
+            settings,
+        })
+    }
+
+    //bilinear, clamped to the image edge
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let p = (Vec2::new(x, z) - self.settings.origin) / self.settings.meters_per_pixel;
+        let max = Vec2::new((self.width - 1) as f32, (self.height - 1) as f32);
+        let p = p.clamp(Vec2::ZERO, max);
+        let x0 = p.x as usize;
+        let z0 = p.y as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let z1 = (z0 + 1).min(self.height - 1);
+        let t = p - Vec2::new(x0 as f32, z0 as f32);
+        let h = |x: usize, z: usize| self.heights[z * self.width + x];
+        let near = h(x0, z0) + (h(x1, z0) - h(x0, z0)) * t.x;
+        let far = h(x0, z1) + (h(x1, z1) - h(x0, z1)) * t.x;
+        near + (far - near) * t.y
+    }
+
+    //world xz covered by the image
+    pub fn footprint(&self) -> (Vec2, Vec2) {
+        let size = Vec2::new((self.width - 1) as f32, (self.height - 1) as f32)
+            * self.settings.meters_per_pixel;
+        (self.settings.origin, self.settings.origin + size)
+    }
+
+    pub fn height_range(&self) -> (f32, f32) {
+        self.heights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), h| {
+                (lo.min(*h), hi.max(*h))
+            })
+    }
+}
+
+//grass over dirt, sand near sea level and snow above the snow line
+fn layered_material(depth: f32, surface_height: f32) -> MaterialCode {
+    if depth < 0.0 {
+        MaterialCode::Air
+    } else if depth > GRASS_DEPTH {
+        MaterialCode::Dirt
+    } else if surface_height < BEACH_HEIGHT {
+        MaterialCode::Sand
+    } else if surface_height > SNOW_LINE {
+        MaterialCode::Snow
+    } else {
+        MaterialCode::Grass
+    }
+}
+
+impl DensitySource for HeightmapTerrain {
+    fn sample(&self, world_pos: Vec3) -> (f32, MaterialCode) {
+        let h = self.height_at(world_pos.x, world_pos.z);
+        (world_pos.y - h, layered_material(h - world_pos.y, h))
+    }
+
+    fn prepare_column(&self, chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) {
+        let column_min = chunk_start.xz() - VOXEL_WORLD_SIZE;
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let i = z * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                let p = column_min + Vec2::new(x as f32, z as f32) * VOXEL_WORLD_SIZE;
+                let ddx = self.height_at(p.x + VOXEL_WORLD_SIZE, p.y)
+                    - self.height_at(p.x - VOXEL_WORLD_SIZE, p.y);
+                let ddz = self.height_at(p.x, p.y + VOXEL_WORLD_SIZE)
+                    - self.height_at(p.x, p.y - VOXEL_WORLD_SIZE);
+                chunk_buffers.heightmap[i] = self.height_at(p.x, p.y);
+                chunk_buffers.dhdx[i] = ddx / (2.0 * VOXEL_WORLD_SIZE);
+                chunk_buffers.dhdz[i] = ddz / (2.0 * VOXEL_WORLD_SIZE);
+            }
+        }
+        chunk_buffers.path_mask.fill(false);
+        chunk_buffers.water_level.fill(f32::NEG_INFINITY);
+    }
+
+    fn classify(&self, chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) -> Uniformity {
+        let (h_min, h_max) = chunk_buffers
+            .heightmap
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), h| {
+                (lo.min(*h), hi.max(*h))
+            });
+        let padded_min_y = chunk_start.y - VOXEL_WORLD_SIZE;
+        let padded_max_y = chunk_start.y + SAMPLES_PER_CHUNK_DIM as f32 * VOXEL_WORLD_SIZE;
+        if padded_min_y > h_max {
+            Uniformity::Air
+        } else if padded_max_y < h_min {
+            Uniformity::Dirt
+        } else {
+            Uniformity::Unknown
+        }
+    }
+
+    //same as the default but slope corrected, using the column prepared above
+    fn fill_chunk(&self, chunk_start: Vec3, chunk_buffers: &mut ChunkBuffers) {
+        let padded_start_y = chunk_start.y - VOXEL_WORLD_SIZE;
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let i = z * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                let h = chunk_buffers.heightmap[i];
+                let (gx, gz) = (chunk_buffers.dhdx[i], chunk_buffers.dhdz[i]);
+                let inv_slope = 1.0 / (1.0 + gx * gx + gz * gz).sqrt();
+                for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                    let world_y = padded_start_y + y as f32 * VOXEL_WORLD_SIZE;
+                    let idx =
+                        (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                    chunk_buffers.density[idx] = SdfValue::from_f32((world_y - h) * inv_slope).0;
+                    let interior = [x, y, z]
+                        .iter()
+                        .all(|p| (1..=SAMPLES_PER_CHUNK_DIM).contains(p));
+                    if interior {
+                        let mat_idx =
+                            (z - 1) * SAMPLES_PER_CHUNK_2D + (y - 1) * SAMPLES_PER_CHUNK_DIM + x
+                                - 1;
+                        chunk_buffers.material[mat_idx] = layered_material(h - world_y, h);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub surface_chunks: usize,
+    pub air_chunks: usize,
+    pub dirt_chunks: usize,
+}
+
+fn world_to_chunk(v: f32) -> i16 {
+    ((v + HALF_CHUNK) / CHUNK_WORLD_SIZE).floor() as i16
+}
+
+//bakes the heightmap into a fresh world through the same write thread the game uses
+//columns are written from below the lowest point up past the noise band, so nothing procedural shows through inside the footprint
+//outside the footprint the world stays procedural
+pub fn import_heightmap(
+    terrain: &HeightmapTerrain,
+    data_dir: &Path,
+) -> Result<ImportReport, String> {
+    let open = |name: &str| {
+        StoragePaths::open_file(&data_dir.join(name), true)
+            .map_err(|e| format!("failed to open {name}: {e}"))
+    };
+    let air_file = open("air_compression_data.txt")?;
+    let dirt_file = open("dirt_compression_data.txt")?;
+    let has_uniform_chunks = [&air_file, &dirt_file]
+        .iter()
+        .any(|file| file.metadata().map_or(true, |m| m.len() > 0));
+    let has_unmigrated_chunks =
+        std::fs::metadata(data_dir.join("chunk_data.txt")).is_ok_and(|m| m.len() > 0);
+    if has_uniform_chunks
+        || has_unmigrated_chunks
+        || !load_chunk_index_map(
+            data_dir,
+            &mut FxHashMap::default(),
+            &mut FxHashMap::default(),
+        )
+        .is_empty()
+    {
+        return Err(format!(
+            "{} already has chunks, import into an empty world",
+            data_dir.display()
+        ));
+    }
+    WorldHeader::current().write(data_dir)?;
+    let region_files =
+        RegionFiles::writer(data_dir).map_err(|e| format!("failed to open region files: {e}"))?;
+    let (write_tx, write_rx) = crossbeam_channel::bounded(WRITE_QUEUE_SIZE);
+    let writer = thread::spawn(move || {
+        dedicated_write_thread(
+            write_rx,
+            Arc::new(RwLock::new(FxHashMap::default())),
+            region_files,
+            air_file,
+            dirt_file,
+            VecDeque::new(),
+            VecDeque::new(),
+            Arc::new(FxHashMap::default()),
+            ChunkSummaries::default(),
+            ChunkModifiedTimes::default(),
+            None,
+        );
+    });
+    let (min, max) = terrain.footprint();
+    let (h_min, h_max) = terrain.height_range();
+    let noise_amplitude = world_gen_config().noise_amplitude;
+    let y_min = world_to_chunk(h_min.min(-noise_amplitude)) - 1;
+    let y_max = world_to_chunk(h_max.max(noise_amplitude)) + 1;
+    let mut chunk_buffers = ChunkBuffers::new();
+    let mut report = ImportReport::default();
+    let columns = chunks_in_box(
+        ChunkCoord(world_to_chunk(min.x), 0, world_to_chunk(min.y)),
+        ChunkCoord(world_to_chunk(max.x), 0, world_to_chunk(max.y)),
+    );
+    for column in columns {
+        let column_start = calculate_chunk_start(&column);
+        terrain.prepare_column(&column_start, &mut chunk_buffers);
+        for chunk_y in y_min..=y_max {
+            let chunk_coord = ChunkCoord(column.0, chunk_y, column.2);
+            let chunk_start = calculate_chunk_start(&chunk_coord);
+            let mut uniformity = terrain.classify(&chunk_start, &mut chunk_buffers);
+            if uniformity == Uniformity::Unknown {
+                terrain.fill_chunk(chunk_start, &mut chunk_buffers);
+                uniformity = chunk_uniformity(&chunk_buffers.density, &chunk_buffers.material);
+            }
+            let cmd = match uniformity {
+                Uniformity::Air => {
+                    report.air_chunks += 1;
+                    WriteCmd::WriteUniformAir { chunk_coord }
+                }
+                Uniformity::Dirt => {
+                    report.dirt_chunks += 1;
+                    WriteCmd::WriteUniformDirt { chunk_coord }
+                }
+                _ => {
+                    report.surface_chunks += 1;
+                    WriteCmd::UpdateNonUniform {
+                        densities: Arc::from(&chunk_buffers.density[..]),
+                        materials: Arc::from(&chunk_buffers.material[..]),
+                        chunk_coord,
+                    }
+                }
+            };
+            write_tx
+                .send(cmd)
+                .map_err(|_| "write thread stopped".to_string())?;
+        }
+    }
+    drop(write_tx);
+    writer
+        .join()
+        .map_err(|_| "write thread panicked".to_string())?;
+    Ok(report)
+}
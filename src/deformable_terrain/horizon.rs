@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::constants::{
+    CHUNK_WORLD_SIZE, CHUNKS_PER_CLUSTER, CHUNKS_PER_CLUSTER_DIM, CLUSTER_WORLD_LENGTH, HALF_CHUNK,
+    REDUCED_LOD_2_RADIUS_SQUARED,
+};
+use crate::conversions::{cluster_coord_to_min_chunk_coord, cluster_coord_to_world_pos};
+
+pub const HORIZON_CULL_MIN_DISTANCE_SQUARED: f32 = REDUCED_LOD_2_RADIUS_SQUARED; //nearer clusters are always requested
+const REVALIDATE_DISTANCE: f32 = CLUSTER_WORLD_LENGTH; //culled clusters are retested after the observer moves this far
+const OCCLUDER_MARGIN: f32 = CLUSTER_WORLD_LENGTH; //ignore ground this close to the target, rays to its corners fan out there
+const EYE_MARGIN: f32 = 4.0; //covers camera offsets above the tracked center
+
+//conservative horizon test for far cluster requests
+//ground holds a lower bound on the terrain height of each chunk column, taken from loaded clusters in the svo:
+//below the lowest chunk with a surface is buried dirt, so the surface can not be lower than that chunk's floor
+//a cluster is hidden when its highest point sits below the steepest ground slope between it and the observer
+pub struct HorizonCuller {
+    ground: FxHashMap<(i16, i16), f32>,
+    culled: FxHashSet<(i16, i16, i16)>,
+    validated_center: Vec3,
+}
+
+impl HorizonCuller {
+    pub fn new(center: Vec3) -> Self {
+        Self {
+            ground: FxHashMap::default(),
+            culled: FxHashSet::default(),
+            validated_center: center,
+        }
+    }
+
+    //has_entity is ordered x, z, y like the loader threads roll through a cluster
+    pub fn record_cluster(
+        &mut self,
+        cluster_coord: (i16, i16, i16),
+        has_entity: &[bool; CHUNKS_PER_CLUSTER],
+    ) {
+        let min_chunk = cluster_coord_to_min_chunk_coord(cluster_coord);
+        for (column_idx, column) in has_entity.chunks_exact(CHUNKS_PER_CLUSTER_DIM).enumerate() {
+            //when the surface reaches the bottom layer the chunk below is unknown so no bound can be claimed
+            let Some(lowest) = column.iter().position(|&e| e) else {
+                continue;
+            };
+            if lowest == 0 {
+                continue;
+            }
+            let column_coord = (
+                min_chunk.0 + (column_idx / CHUNKS_PER_CLUSTER_DIM) as i16,
+                min_chunk.2 + (column_idx % CHUNKS_PER_CLUSTER_DIM) as i16,
+            );
+            let floor = (min_chunk.1 + lowest as i16) as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK;
+            let ground = self.ground.entry(column_coord).or_insert(f32::MIN);
+            *ground = ground.max(floor);
+        }
+    }
+
+    //called once per fill pass, forgets old verdicts when the observer has moved enough to see over ridges
+    pub fn begin_pass(&mut self, center: Vec3, render_radius_squared: f32) {
+        if center.distance_squared(self.validated_center)
+            < REVALIDATE_DISTANCE * REVALIDATE_DISTANCE
+        {
+            return;
+        }
+        self.validated_center = center;
+        self.culled.clear();
+        self.ground.retain(|column, _| {
+            let column_center = Vec2::new(column.0 as f32, column.1 as f32) * CHUNK_WORLD_SIZE;
+            column_center.distance_squared(center.xz()) <= render_radius_squared
+        });
+    }
+
+    pub fn is_hidden(&mut self, center: &Vec3, cluster_coord: (i16, i16, i16)) -> bool {
+        if self.culled.contains(&cluster_coord) {
+            return true;
+        }
+        let hidden = self.test(center, cluster_coord);
+        if hidden {
+            self.culled.insert(cluster_coord);
+        }
+        hidden
+    }
+
+    fn test(&self, center: &Vec3, cluster_coord: (i16, i16, i16)) -> bool {
+        let cluster_min = cluster_coord_to_world_pos(&cluster_coord) - Vec3::splat(HALF_CHUNK);
+        let cluster_max = cluster_min + Vec3::splat(CLUSTER_WORLD_LENGTH);
+        let nearest = center.xz().clamp(cluster_min.xz(), cluster_max.xz());
+        let to_target = nearest - center.xz();
+        let target_distance = to_target.length();
+        if target_distance <= OCCLUDER_MARGIN {
+            return false;
+        }
+        //highest elevation any point of the cluster can have as seen from the observer
+        let farthest = target_distance + CLUSTER_WORLD_LENGTH * std::f32::consts::SQRT_2;
+        let eye_y = center.y + EYE_MARGIN;
+        let rise = cluster_max.y - eye_y;
+        let target_slope = if rise >= 0.0 {
+            rise / target_distance
+        } else {
+            rise / farthest
+        };
+        let direction = to_target / target_distance;
+        let mut distance = CHUNK_WORLD_SIZE;
+        while distance < target_distance - OCCLUDER_MARGIN {
+            let sample = center.xz() + direction * distance;
+            let column = (
+                (sample.x / CHUNK_WORLD_SIZE).round() as i16,
+                (sample.y / CHUNK_WORLD_SIZE).round() as i16,
+            );
+            if let Some(ground) = self.ground.get(&column) {
+                //measured from whichever column edge gives the shallower slope so it is never overestimated
+                let ground_rise = ground - eye_y;
+                let column_distance = if ground_rise >= 0.0 {
+                    distance + HALF_CHUNK * std::f32::consts::SQRT_2
+                } else {
+                    (distance - HALF_CHUNK * std::f32::consts::SQRT_2).max(HALF_CHUNK)
+                };
+                if ground_rise / column_distance > target_slope {
+                    return true;
+                }
+            }
+            distance += CHUNK_WORLD_SIZE;
+        }
+        false
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use fastnoise2::{
+    SafeNode,
+    generator::{Generator, GeneratorWrapper, simplex::opensimplex2},
+};
+
+use crate::{
+    constants::{
+        HALF_CHUNK, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+        VOXEL_WORLD_SIZE,
+    },
+    deformable_terrain::{
+        biomes::smoothstep,
+        chunk_generator::{MaterialCode, quantize_f32_to_i16},
+        driver::ChunkBuffers,
+        world_gen::channel_seed,
+    },
+};
+
+const RIVER_FREQUENCY: f32 = 0.0012;
+const RIVER_WIDTH: f32 = 0.03; //noise units either side of the river noise's zero line that are channel
+const LAKE_FREQUENCY: f32 = 0.002;
+const LAKE_THRESHOLD: f32 = 0.55; //lake noise above this is basin, higher is rarer
+const LAKE_BLEND: f32 = 0.1;
+const CHANNEL_DEPTH: f32 = 4.0; //world units a channel or basin is carved at its deepest
+const WATER_FILL: f32 = 0.5; //fraction of the carved depth that is filled back up with water
+
+thread_local! {
+    static HYDROLOGY_NOISE: GeneratorWrapper<SafeNode> = opensimplex2().build();
+}
+
+//0 on dry land, 1 in the middle of a river or lake
+//rivers follow the zero lines of a low frequency noise so they always join up into a network
+fn wetness(river: f32, lake: f32) -> f32 {
+    let river = 1.0 - smoothstep(0.0, RIVER_WIDTH, river.abs());
+    let lake = smoothstep(LAKE_THRESHOLD, LAKE_THRESHOLD + LAKE_BLEND, lake);
+    river.max(lake)
+}
+
+//single point version for placement and spawning, matches apply_hydrology up to its interpolation
+pub fn wetness_at(x: f32, z: f32) -> f32 {
+    HYDROLOGY_NOISE.with(|noise| {
+        wetness(
+            noise.gen_single_2d(x * RIVER_FREQUENCY, z * RIVER_FREQUENCY, channel_seed(20)),
+            noise.gen_single_2d(x * LAKE_FREQUENCY, z * LAKE_FREQUENCY, channel_seed(21)),
+        )
+    })
+}
+
+//whether a point sits under a river or lake surface
+pub fn has_water_at(x: f32, z: f32) -> bool {
+    wetness_at(x, z) > WATER_FILL
+}
+
+//how far the hydrology pass lowers the terrain at a point
+pub fn channel_depth_at(x: f32, z: f32) -> f32 {
+    CHANNEL_DEPTH * wetness_at(x, z)
+}
+
+//carves river channels and lake basins into the heightmap and records the water surface above them
+//noise comes from the 5x5 lattice the chunk heights are sampled on so neighboring chunks agree on their padding
+//the water surface sits a fixed depth below the uncarved terrain, so it follows the land downhill
+pub fn apply_hydrology(chunk_start: &Vec3, chunk_buffers: &mut ChunkBuffers) {
+    chunk_buffers.water_level.fill(f32::NEG_INFINITY);
+    let x_start = ((chunk_start.x - HALF_CHUNK) / HALF_CHUNK) as i32;
+    let z_start = ((chunk_start.z - HALF_CHUNK) / HALF_CHUNK) as i32;
+    let mut river = [0.0; 25];
+    let mut lake = [0.0; 25];
+    HYDROLOGY_NOISE.with(|noise| {
+        noise.gen_uniform_grid_2d(
+            &mut river,
+            x_start,
+            z_start,
+            5,
+            5,
+            RIVER_FREQUENCY * HALF_CHUNK,
+            channel_seed(20),
+        );
+        noise.gen_uniform_grid_2d(
+            &mut lake,
+            x_start,
+            z_start,
+            5,
+            5,
+            LAKE_FREQUENCY * HALF_CHUNK,
+            channel_seed(21),
+        );
+    });
+    //bilinear blends of same signed values past the width never reach the channel
+    let no_river =
+        river.iter().all(|&r| r >= RIVER_WIDTH) || river.iter().all(|&r| r <= -RIVER_WIDTH);
+    if no_river && lake.iter().all(|&l| l <= LAKE_THRESHOLD) {
+        return;
+    }
+    //bilinear over the lattice, offsets are in world units from the padded origin
+    let lattice_origin = chunk_start.xz() - HALF_CHUNK;
+    let depth_at = |p: Vec2| {
+        let g = (p - lattice_origin) / HALF_CHUNK;
+        let g0 = g.floor().clamp(Vec2::ZERO, Vec2::splat(3.0));
+        let t = g - g0;
+        let i = g0.y as usize * 5 + g0.x as usize;
+        let lerp2 = |v: &[f32; 25]| {
+            let near = v[i] + (v[i + 1] - v[i]) * t.x;
+            let far = v[i + 5] + (v[i + 6] - v[i + 5]) * t.x;
+            near + (far - near) * t.y
+        };
+        CHANNEL_DEPTH * wetness(lerp2(&river), lerp2(&lake))
+    };
+    let column_min = chunk_start.xz() - VOXEL_WORLD_SIZE;
+    let step = VOXEL_WORLD_SIZE;
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let i = z * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+            let p = column_min + Vec2::new(x as f32, z as f32) * step;
+            let depth = depth_at(p);
+            if depth <= 0.0 {
+                continue;
+            }
+            let h = chunk_buffers.heightmap[i];
+            let ddx = depth_at(p + Vec2::X * step) - depth_at(p - Vec2::X * step);
+            let ddz = depth_at(p + Vec2::Y * step) - depth_at(p - Vec2::Y * step);
+            chunk_buffers.heightmap[i] = h - depth;
+            chunk_buffers.dhdx[i] -= ddx / (2.0 * step);
+            chunk_buffers.dhdz[i] -= ddz / (2.0 * step);
+            if depth > CHANNEL_DEPTH * WATER_FILL {
+                chunk_buffers.water_level[i] = h - CHANNEL_DEPTH * WATER_FILL;
+            }
+        }
+    }
+}
+
+//turns the air between a carved bed and its water surface into water, runs right after fill_voxel_densities
+//the bed and the water are both solid so only the water surface gets meshed
+pub fn fill_water(chunk_buffers: &mut ChunkBuffers, chunk_start: &Vec3) {
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let water_level = chunk_buffers.water_level[z * SAMPLES_PER_CHUNK_DIM_PADDED + x];
+            if water_level == f32::NEG_INFINITY {
+                continue;
+            }
+            for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let world_y = chunk_start.y + (y as f32 - 1.0) * VOXEL_WORLD_SIZE;
+                let q = quantize_f32_to_i16(world_y - water_level);
+                let idx = (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                //union so the air just above the water measures to the water, not to the bed below it
+                let previous = chunk_buffers.density[idx];
+                if q >= previous {
+                    continue;
+                }
+                chunk_buffers.density[idx] = q;
+                let interior = [x, y, z]
+                    .iter()
+                    .all(|p| (1..=SAMPLES_PER_CHUNK_DIM).contains(p));
+                if interior && previous >= 0 && q < 0 {
+                    let mat_idx = (z - 1) * SAMPLES_PER_CHUNK_2D + (y - 1) * SAMPLES_PER_CHUNK_DIM;
+                    chunk_buffers.material[mat_idx + x - 1] = MaterialCode::Water;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::f32::consts::TAU;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use fastnoise2::{SafeNode, generator::GeneratorWrapper};
+
+use crate::deformable_terrain::biomes::biome_at;
+use crate::deformable_terrain::chunk_generator::{MaterialCode, sample_terrain_height};
+use crate::deformable_terrain::driver::{NoiseGenerator, RENDER_RADIUS_SQUARED};
+use crate::deformable_terrain::hydrology::has_water_at;
+use crate::deformable_terrain::plugin::MoveableCenter;
+
+const IMPOSTER_SECTORS: usize = 32; //flat quads in the ring around the player
+const IMPOSTER_COLUMNS: usize = 64; //texels across one sector
+const IMPOSTER_ROWS: usize = 128;
+const IMPOSTER_MIN_PITCH: f32 = -0.2; //radians below the eye the strips cover
+const IMPOSTER_MAX_PITCH: f32 = 0.35;
+const IMPOSTER_MAX_DISTANCE: f32 = 8000.0; //how far the strips march, effectively the horizon
+const IMPOSTER_QUAD_MARGIN: f32 = 1.1; //quads sit just past the meshed radius so real terrain always depth tests in front
+const IMPOSTER_REFRESH_DISTANCE: f32 = 250.0; //world units the player moves before the ring is baked again
+const MARCH_MIN_STEP: f32 = 8.0;
+const MARCH_STEP_PER_DISTANCE: f32 = 0.01; //far samples cover more ground, the texels there are wider too
+const HAZE_DISTANCE: f32 = 3000.0; //distance fog does not reach the ring, the haze is baked in from the depth instead
+const HAZE_COLOR: Vec3 = Vec3::new(0.8, 0.8, 0.9); //matches the camera's distance fog
+
+struct ImposterBake {
+    center: Vec3,
+    inner_radius: f32,
+    strips: Vec<Vec<u8>>,
+}
+
+//ring of heightmap imposters past the meshed radius, baked on a background thread and swapped in whole
+#[derive(Resource)]
+pub struct Imposters {
+    sectors: Vec<(
+        Entity,
+        Handle<Mesh>,
+        Handle<StandardMaterial>,
+        Handle<Image>,
+    )>,
+    baked_center: Option<Vec3>,
+    baked_radius: f32,
+    baking: bool,
+    bake_sender: Sender<ImposterBake>,
+    bake_receiver: Receiver<ImposterBake>,
+}
+
+impl Default for Imposters {
+    fn default() -> Self {
+        let (bake_sender, bake_receiver) = unbounded();
+        Imposters {
+            sectors: Vec::new(),
+            baked_center: None,
+            baked_radius: 0.0,
+            baking: false,
+            bake_sender,
+            bake_receiver,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ImposterTag;
+
+pub fn update_imposters(
+    mut commands: Commands,
+    moveable_center: Res<MoveableCenter>,
+    noise_generator: Option<Res<NoiseGenerator>>,
+    mut imposters: ResMut<Imposters>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut transforms: Query<&mut Transform, With<ImposterTag>>,
+) {
+    let Some(noise_generator) = noise_generator else {
+        return;
+    };
+    let center = moveable_center.read();
+    //the ring follows the player so only the parallax inside the strips goes stale between bakes
+    if let Some(baked_center) = imposters.baked_center {
+        let offset = Vec3::new(center.x - baked_center.x, 0.0, center.z - baked_center.z);
+        for (entity, ..) in imposters.sectors.iter() {
+            if let Ok(mut transform) = transforms.get_mut(*entity) {
+                transform.translation = offset;
+            }
+        }
+    }
+    if let Ok(bake) = imposters.bake_receiver.try_recv() {
+        imposters.baking = false;
+        for (entity, mesh, material, image) in imposters.sectors.drain(..) {
+            commands.entity(entity).despawn();
+            meshes.remove(&mesh);
+            materials.remove(&material);
+            images.remove(&image);
+        }
+        let quad_distance = bake.inner_radius * IMPOSTER_QUAD_MARGIN;
+        let offset = Vec3::new(center.x - bake.center.x, 0.0, center.z - bake.center.z);
+        for (sector, strip) in bake.strips.into_iter().enumerate() {
+            let image = images.add(Image::new(
+                Extent3d {
+                    width: IMPOSTER_COLUMNS as u32,
+                    height: IMPOSTER_ROWS as u32,
+                    ..default()
+                },
+                TextureDimension::D2,
+                strip,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            ));
+            let material = materials.add(StandardMaterial {
+                base_color_texture: Some(image.clone()),
+                alpha_mode: AlphaMode::Mask(0.5),
+                unlit: true,
+                fog_enabled: false,
+                cull_mode: None,
+                ..default()
+            });
+            let mesh = meshes.add(build_sector_mesh(sector, bake.center, quad_distance));
+            let entity = commands
+                .spawn((
+                    ImposterTag,
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(material.clone()),
+                    Transform::from_translation(offset),
+                    NoFrustumCulling, //the ring sits past the camera's far plane, depth is infinite reverse z
+                ))
+                .id();
+            imposters.sectors.push((entity, mesh, material, image));
+        }
+        imposters.baked_center = Some(bake.center);
+        imposters.baked_radius = bake.inner_radius;
+    }
+    let inner_radius = f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed))
+        .sqrt()
+        .max(MARCH_MIN_STEP);
+    let stale = inner_radius != imposters.baked_radius
+        || imposters.baked_center.is_none_or(|baked_center| {
+            Vec2::new(center.x - baked_center.x, center.z - baked_center.z).length()
+                > IMPOSTER_REFRESH_DISTANCE
+        });
+    if stale && !imposters.baking {
+        imposters.baking = true;
+        let fbm = noise_generator.0.clone();
+        let sender = imposters.bake_sender.clone();
+        thread::spawn(move || {
+            let strips = (0..IMPOSTER_SECTORS)
+                .map(|sector| bake_strip(sector, center, inner_radius, &fbm))
+                .collect();
+            let _ = sender.send(ImposterBake {
+                center,
+                inner_radius,
+                strips,
+            });
+        });
+    }
+}
+
+//columns are spaced evenly in tangent space so the strip maps onto a flat quad
+//rows are too but measured along each column's own ray, that is off by under a percent at the sector edges
+fn column_angle(sector: usize, column: f32) -> f32 {
+    let half_width = (TAU / IMPOSTER_SECTORS as f32 / 2.0).tan();
+    let sector_angle = (sector as f32 + 0.5) * TAU / IMPOSTER_SECTORS as f32;
+    sector_angle + ((column / IMPOSTER_COLUMNS as f32 * 2.0 - 1.0) * half_width).atan()
+}
+
+//rgba8 strip for one sector, alpha 0 above the silhouette
+//marches outward per column and fills rows bottom up whenever the terrain rises above everything nearer
+//the marched distance of each texel is its depth, it only goes into the haze since the quad itself is flat
+pub fn bake_strip(
+    sector: usize,
+    center: Vec3,
+    inner_radius: f32,
+    fbm: &GeneratorWrapper<SafeNode>,
+) -> Vec<u8> {
+    let mut color = vec![0u8; IMPOSTER_COLUMNS * IMPOSTER_ROWS * 4];
+    let (min_tan, max_tan) = (IMPOSTER_MIN_PITCH.tan(), IMPOSTER_MAX_PITCH.tan());
+    for column in 0..IMPOSTER_COLUMNS {
+        let angle = column_angle(sector, column as f32 + 0.5);
+        let direction = Vec2::new(angle.cos(), angle.sin());
+        let mut filled_rows = 0;
+        let mut distance = inner_radius;
+        let mut previous_height = None;
+        while distance < IMPOSTER_MAX_DISTANCE && filled_rows < IMPOSTER_ROWS {
+            let step = MARCH_MIN_STEP.max(distance * MARCH_STEP_PER_DISTANCE);
+            let position = Vec2::new(center.x, center.z) + direction * distance;
+            let height = sample_terrain_height(position.x, position.y, fbm);
+            let pitch_tan = (height - center.y) / distance;
+            let covered_rows = (((pitch_tan - min_tan) / (max_tan - min_tan))
+                * IMPOSTER_ROWS as f32)
+                .round()
+                .clamp(0.0, IMPOSTER_ROWS as f32) as usize;
+            if covered_rows > filled_rows {
+                //rising away from the viewer faces the sky more, a cheap stand in for lighting
+                let slope = previous_height.map_or(0.0, |h| (height - h) / step);
+                let shade = (0.75 + slope * 0.5).clamp(0.45, 1.1);
+                let material = surface_color(position, height) * shade;
+                let haze = 1.0 - (-distance / HAZE_DISTANCE).exp();
+                let rgb = material.lerp(HAZE_COLOR, haze).clamp(Vec3::ZERO, Vec3::ONE);
+                let rgba = [
+                    (rgb.x * 255.0) as u8,
+                    (rgb.y * 255.0) as u8,
+                    (rgb.z * 255.0) as u8,
+                    255,
+                ];
+                for row in filled_rows..covered_rows {
+                    //image rows go top down, filled rows count from the bottom
+                    let texel = (IMPOSTER_ROWS - 1 - row) * IMPOSTER_COLUMNS + column;
+                    color[texel * 4..texel * 4 + 4].copy_from_slice(&rgba);
+                }
+                filled_rows = covered_rows;
+            }
+            previous_height = Some(height);
+            distance += step;
+        }
+    }
+    color
+}
+
+fn surface_color(position: Vec2, height: f32) -> Vec3 {
+    let material = if has_water_at(position.x, position.y) {
+        MaterialCode::Water
+    } else if height < 0.0 {
+        MaterialCode::Sand
+    } else {
+        biome_at(Vec3::new(position.x, height, position.y))
+            .surface_stack()
+            .top
+    };
+    match material {
+        MaterialCode::Grass => Vec3::new(0.28, 0.42, 0.17),
+        MaterialCode::Sand => Vec3::new(0.74, 0.67, 0.48),
+        MaterialCode::Snow => Vec3::new(0.9, 0.92, 0.96),
+        MaterialCode::Water => Vec3::new(0.16, 0.32, 0.4),
+        _ => Vec3::new(0.4, 0.31, 0.22),
+    }
+}
+
+//flat quad facing the bake center, vertices in world space around the center at bake time
+fn build_sector_mesh(sector: usize, center: Vec3, quad_distance: f32) -> Mesh {
+    let (left, right) = (
+        column_angle(sector, 0.0),
+        column_angle(sector, IMPOSTER_COLUMNS as f32),
+    );
+    let sector_angle = (sector as f32 + 0.5) * TAU / IMPOSTER_SECTORS as f32;
+    //edge rays hit the quad plane further out than its center
+    let edge_distance = quad_distance / (left - sector_angle).cos();
+    let corner = |angle: f32, pitch: f32| {
+        Vec3::new(
+            center.x + angle.cos() * edge_distance,
+            center.y + pitch.tan() * quad_distance,
+            center.z + angle.sin() * edge_distance,
+        )
+    };
+    let vertices = vec![
+        corner(left, IMPOSTER_MAX_PITCH),
+        corner(right, IMPOSTER_MAX_PITCH),
+        corner(right, IMPOSTER_MIN_PITCH),
+        corner(left, IMPOSTER_MIN_PITCH),
+    ];
+    let normal = -Vec3::new(sector_angle.cos(), 0.0, sector_angle.sin());
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![normal; 4]);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+    );
+    mesh.insert_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]));
+    mesh
+}
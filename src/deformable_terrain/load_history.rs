@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use parking_lot::Mutex;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::ui::console::{Console, ConsoleCommand};
+
+const LOAD_HISTORY_LEN: usize = 512; //a few seconds of streaming at full speed
+const DEFAULT_REPORTED_LOADS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadStage {
+    Disk,     //reading and decompressing the stored chunk on an io thread
+    Generate, //heightmap column, classification and filling the density buffers
+    Mesh,     //surface check, mesh and collider building
+}
+
+impl LoadStage {
+    pub const ALL: [LoadStage; 3] = [LoadStage::Disk, LoadStage::Generate, LoadStage::Mesh];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoadStage::Disk => "disk",
+            LoadStage::Generate => "generate",
+            LoadStage::Mesh => "mesh",
+        }
+    }
+}
+
+//one non uniform chunk passing through the loaders, uniform chunks are cache hits and never show up here
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkLoadEvent {
+    pub chunk_coord: ChunkCoord,
+    pub stages: [Duration; 3], //indexed by LoadStage
+    pub thread_idx: usize,     //which chunk_loader thread finished it
+}
+
+impl ChunkLoadEvent {
+    pub fn total(&self) -> Duration {
+        self.stages.iter().sum()
+    }
+
+    pub fn dominant_stage(&self) -> LoadStage {
+        LoadStage::ALL
+            .into_iter()
+            .max_by_key(|stage| self.stages[*stage as usize])
+            .unwrap()
+    }
+}
+
+//the last LOAD_HISTORY_LEN chunk loads, written by the loader threads and read by `why-slow`
+#[derive(Resource, Clone, Default)]
+pub struct ChunkLoadHistory(Arc<Mutex<VecDeque<ChunkLoadEvent>>>);
+
+impl ChunkLoadHistory {
+    pub fn record(&self, event: ChunkLoadEvent) {
+        let mut events = self.0.lock();
+        if events.len() == LOAD_HISTORY_LEN {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    //most expensive first
+    pub fn slowest(&self, count: usize) -> Vec<ChunkLoadEvent> {
+        let mut events: Vec<ChunkLoadEvent> = self.0.lock().iter().copied().collect();
+        events.sort_by_key(|event| std::cmp::Reverse(event.total()));
+        events.truncate(count);
+        events
+    }
+
+    //summed over the whole buffer, the stage that hitches usually also dominates overall
+    pub fn stage_totals(&self) -> (usize, [Duration; 3]) {
+        let events = self.0.lock();
+        let mut totals = [Duration::ZERO; 3];
+        for event in events.iter() {
+            for (total, stage) in totals.iter_mut().zip(event.stages) {
+                *total += stage;
+            }
+        }
+        (events.len(), totals)
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+//`why-slow [count]` prints the slowest recent chunk loads with their stage breakdown, meant to be pasted into bug reports
+pub fn why_slow_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    history: Res<ChunkLoadHistory>,
+) {
+    for command in command_reader.read() {
+        if command.name != "why-slow" {
+            continue;
+        }
+        let count = match command.args.first().map(|arg| arg.parse::<usize>()) {
+            None => DEFAULT_REPORTED_LOADS,
+            Some(Ok(count)) => count,
+            Some(Err(_)) => {
+                console.print("usage: why-slow [count]");
+                continue;
+            }
+        };
+        let (recorded, totals) = history.stage_totals();
+        if recorded == 0 {
+            console.print("no chunk loads recorded yet");
+            continue;
+        }
+        for event in history.slowest(count) {
+            let breakdown: Vec<String> = LoadStage::ALL
+                .iter()
+                .map(|stage| {
+                    format!(
+                        "{} {:.2}",
+                        stage.name(),
+                        millis(event.stages[*stage as usize])
+                    )
+                })
+                .collect();
+            console.print(format!(
+                "chunk {} {:.2} ms on loader {}, mostly {} ({})",
+                event.chunk_coord,
+                millis(event.total()),
+                event.thread_idx,
+                event.dominant_stage().name(),
+                breakdown.join(", ")
+            ));
+        }
+        let busiest = LoadStage::ALL
+            .into_iter()
+            .max_by_key(|stage| totals[*stage as usize])
+            .unwrap();
+        let all_stages: Duration = totals.iter().sum();
+        console.print(format!(
+            "over the last {recorded} loads {} took {:.0}% of the time",
+            busiest.name(),
+            100.0 * totals[busiest as usize].as_secs_f64() / all_stages.as_secs_f64().max(1e-9)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_newest_loads_and_ranks_them() {
+        let history = ChunkLoadHistory::default();
+        for i in 0..LOAD_HISTORY_LEN + 10 {
+            history.record(ChunkLoadEvent {
+                chunk_coord: (i as i16, 0, 0),
+                stages: [
+                    Duration::from_micros(10),
+                    Duration::from_micros(i as u64),
+                    Duration::from_micros(20),
+                ],
+                thread_idx: 0,
+            });
+        }
+        let (recorded, _) = history.stage_totals();
+        assert_eq!(recorded, LOAD_HISTORY_LEN);
+        let slowest = history.slowest(2);
+        assert_eq!(slowest[0].chunk_coord.0 as usize, LOAD_HISTORY_LEN + 9);
+        assert_eq!(slowest[1].chunk_coord.0 as usize, LOAD_HISTORY_LEN + 8);
+        assert_eq!(slowest[0].dominant_stage(), LoadStage::Generate);
+        //the oldest ten were pushed out
+        assert!(
+            history
+                .slowest(LOAD_HISTORY_LEN)
+                .iter()
+                .all(|e| e.chunk_coord.0 >= 10)
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::pbr::ExtendedMaterial;
+use bevy::prelude::*;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::conversions::chunk_coord_to_world_pos;
+use crate::deformable_terrain::terrain::TerrainMaterialHandle;
+use crate::deformable_terrain::terrain_material::TerrainMaterialExtension;
+
+type TerrainMaterial = ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>;
+
+const LOD_FADE_SECS: f32 = 0.3;
+const LOD_FADE_STEPS: usize = 8; //materials per direction, every fading chunk shares them instead of owning one
+
+//chunk showing its new lod mesh through more of the dither every step
+#[derive(Component)]
+pub struct LodFadeIn {
+    started: f32,
+}
+
+//the mesh a chunk had before its lod changed, drawn where the new one is not until the fade ends
+#[derive(Component)]
+pub struct LodFadeOut {
+    started: f32,
+    mesh: Handle<Mesh>,
+}
+
+//copies of the terrain material that only differ in dither_fade, only present when lod fading is on
+#[derive(Resource)]
+pub struct LodFadeMaterials {
+    fade_in: Vec<Handle<TerrainMaterial>>,
+    fade_out: Vec<Handle<TerrainMaterial>>,
+}
+
+impl LodFadeMaterials {
+    //the chunk entity takes the new mesh and a ghost keeps the old one, the dithers are complementary so each pixel draws one of them
+    pub(crate) fn begin(
+        &self,
+        commands: &mut Commands,
+        now: f32,
+        entity: Entity,
+        chunk_coord: ChunkCoord,
+        old_mesh: Handle<Mesh>,
+        new_mesh: Handle<Mesh>,
+    ) {
+        commands.entity(entity).insert((
+            Mesh3d(new_mesh),
+            MeshMaterial3d(self.fade_in[0].clone()),
+            LodFadeIn { started: now },
+        ));
+        commands.spawn((
+            Mesh3d(old_mesh.clone()),
+            Transform::from_translation(chunk_coord_to_world_pos(&chunk_coord)),
+            MeshMaterial3d(self.fade_out[0].clone()),
+            LodFadeOut {
+                started: now,
+                mesh: old_mesh,
+            },
+        ));
+    }
+}
+
+//step i of a fade draws the fraction (i + 0.5) / steps of the new mesh, negative values tell the shader to draw the rest
+fn fade_progress(step: usize) -> f32 {
+    (step as f32 + 0.5) / LOD_FADE_STEPS as f32
+}
+
+fn fade_step(started: f32, now: f32) -> Option<usize> {
+    let t = (now - started) / LOD_FADE_SECS;
+    (t < 1.0).then(|| ((t.max(0.0) * LOD_FADE_STEPS as f32) as usize).min(LOD_FADE_STEPS - 1))
+}
+
+pub(crate) fn setup_lod_fade_materials(
+    mut commands: Commands,
+    material_handle: Res<TerrainMaterialHandle>,
+    mut materials: ResMut<Assets<TerrainMaterial>>,
+) {
+    let base = materials.get(&material_handle.0).unwrap().clone();
+    let mut with_fade = |dither_fade: f32| {
+        let mut material = base.clone();
+        material.extension.dither_fade = dither_fade;
+        materials.add(material)
+    };
+    let fade_in = (0..LOD_FADE_STEPS)
+        .map(|step| with_fade(fade_progress(step)))
+        .collect();
+    let fade_out = (0..LOD_FADE_STEPS)
+        .map(|step| with_fade(-fade_progress(step)))
+        .collect();
+    commands.insert_resource(LodFadeMaterials { fade_in, fade_out });
+}
+
+pub(crate) fn update_lod_fades(
+    mut commands: Commands,
+    time: Res<Time>,
+    fade_materials: Res<LodFadeMaterials>,
+    material_handle: Res<TerrainMaterialHandle>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+    fading_in: Query<(Entity, &LodFadeIn)>,
+    fading_out: Query<(Entity, &LodFadeOut)>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, fade) in &fading_in {
+        let material = match fade_step(fade.started, now) {
+            Some(step) => fade_materials.fade_in[step].clone(),
+            None => {
+                commands.entity(entity).remove::<LodFadeIn>();
+                material_handle.0.clone()
+            }
+        };
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+    for (entity, fade) in &fading_out {
+        match fade_step(fade.started, now) {
+            Some(step) => {
+                commands
+                    .entity(entity)
+                    .insert(MeshMaterial3d(fade_materials.fade_out[step].clone()));
+            }
+            None => {
+                mesh_handles.remove(&fade.mesh);
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+//the cutaway and lod morphing write the shared material, the fade copies follow it
+pub(crate) fn sync_lod_fade_materials(
+    fade_materials: Res<LodFadeMaterials>,
+    material_handle: Res<TerrainMaterialHandle>,
+    mut materials: ResMut<Assets<TerrainMaterial>>,
+) {
+    let Some(base) = materials.get(&material_handle.0) else {
+        return;
+    };
+    let (clip_plane, morph_center, cull_mode) = (
+        base.extension.clip_plane,
+        base.extension.morph_center,
+        base.base.cull_mode,
+    );
+    for handle in fade_materials
+        .fade_in
+        .iter()
+        .chain(&fade_materials.fade_out)
+    {
+        let Some(material) = materials.get(handle) else {
+            continue;
+        };
+        if material.extension.clip_plane == clip_plane
+            && material.extension.morph_center == morph_center
+            && material.base.cull_mode == cull_mode
+        {
+            continue; //get_mut would reupload it every frame
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            material.extension.clip_plane = clip_plane;
+            material.extension.morph_center = morph_center;
+            material.base.cull_mode = cull_mode;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_step_through_every_material_and_end() {
+        assert_eq!(fade_step(0.0, 0.0), Some(0));
+        assert_eq!(
+            fade_step(0.0, LOD_FADE_SECS * 0.99),
+            Some(LOD_FADE_STEPS - 1)
+        );
+        assert_eq!(fade_step(0.0, LOD_FADE_SECS), None);
+        let progress: Vec<f32> = (0..LOD_FADE_STEPS).map(fade_progress).collect();
+        assert!(progress.windows(2).all(|w| w[0] < w[1]));
+        assert!(progress[0] > 0.0 && progress[LOD_FADE_STEPS - 1] < 1.0);
+    }
+}
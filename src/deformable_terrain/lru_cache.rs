@@ -0,0 +1,75 @@
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::hash::Hash;
+
+struct CacheEntries<K, V> {
+    values: FxHashMap<K, (V, u64)>, //last use stamp for eviction
+    clock: u64,
+}
+
+//the caches shared by every loader thread: heightmap columns, colliders, roads and structures
+//values are cheap to clone handles (Arc) so a hit never holds the lock while the caller uses it
+pub struct LruCache<K, V> {
+    entries: Mutex<CacheEntries<K, V>>,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Copy, V: Clone> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            entries: Mutex::new(CacheEntries {
+                values: FxHashMap::default(),
+                clock: 0,
+            }),
+            capacity,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.values.get_mut(key).map(|(value, last_used)| {
+            *last_used = clock;
+            value.clone()
+        })
+    }
+
+    //another thread may have raced us here, both results are identical so keep whichever landed first
+    pub fn insert(&self, key: K, value: V) -> V {
+        let mut entries = self.entries.lock();
+        if let Some((existing, _)) = entries.values.get(&key) {
+            return existing.clone();
+        }
+        if entries.values.len() >= self.capacity {
+            let oldest = entries
+                .values
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.values.remove(&oldest);
+            }
+        }
+        let clock = entries.clock;
+        entries.values.insert(key, (value.clone(), clock));
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_within_capacity_and_keeps_recent_entries() {
+        let cache = LruCache::new(2);
+        cache.insert(1, 'a');
+        cache.insert(2, 'b');
+        assert_eq!(cache.get(&1), Some('a')); //2 is now the oldest
+        cache.insert(3, 'c');
+        assert_eq!(cache.get(&1), Some('a'));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.insert(3, 'x'), 'c'); //the first insert wins a race
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::{
+    constants::{
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    },
+    deformable_terrain::{
+        chunk_generator::{dequantize_i16_to_f32, quantize_f32_to_i16, sample_trilinear_density},
+        driver::{
+            RF1_SAMPLES_PER_CHUNK_DIM, RF2_SAMPLES_PER_CHUNK_DIM, RF3_SAMPLES_PER_CHUNK_DIM,
+            RF4_SAMPLES_PER_CHUNK_DIM, STREAMING_CONFIG,
+        },
+        marching_cubes::mc::DensityField,
+    },
+};
+
+const MAX_MORPH_STEP: f32 = 1.0; //in coarse voxels, a vertex further than this from the coarse surface has nothing there to match
+const FACE_EPSILON: f32 = 1e-3; //in coarse voxels, vertices this close to a chunk face count as on it
+
+//past this radius a mesh with this many samples is swapped for the next coarser lod, None for the coarsest
+//baked into the mesh, one built before the streaming config changed morphs to the old radius until it is remeshed
+pub fn morph_end_radius(samples_per_chunk_dim: usize) -> Option<f32> {
+    let lod = match samples_per_chunk_dim {
+        SAMPLES_PER_CHUNK_DIM => 0,
+        RF1_SAMPLES_PER_CHUNK_DIM => 1,
+        RF2_SAMPLES_PER_CHUNK_DIM => 2,
+        RF3_SAMPLES_PER_CHUNK_DIM => 3,
+        RF4_SAMPLES_PER_CHUNK_DIM => 4,
+        _ => return None,
+    };
+    Some(STREAMING_CONFIG.read().lod_radii[lod])
+}
+
+//where each vertex would sit on the surface of the next coarser lod, w is the radius that lod takes over at
+//the shader slides vertices towards these as they near that radius so the swap itself barely moves anything
+//field is what the coarse lod is downscaled from, the full res chunk or the grid this lod was generated at
+pub fn coarse_positions(
+    vertices: &[Vec3],
+    field: DensityField,
+    samples_per_chunk_dim: usize,
+) -> Option<Vec<[f32; 4]>> {
+    let end_radius = morph_end_radius(samples_per_chunk_dim)?;
+    let coarse_dim = samples_per_chunk_dim / 2;
+    let coarse = coarse_densities(field, coarse_dim);
+    let to_lattice = (coarse_dim - 1) as f32 / CHUNK_WORLD_SIZE;
+    let positions = vertices
+        .iter()
+        .map(|vertex| {
+            let lattice = (*vertex + Vec3::splat(HALF_CHUNK)) * to_lattice;
+            let moved = step_to_surface(&coarse, coarse_dim, lattice) / to_lattice;
+            let local = moved - Vec3::splat(HALF_CHUNK);
+            [local.x, local.y, local.z, end_radius]
+        })
+        .collect();
+    Some(positions)
+}
+
+//the samples downscale takes for a chunk of coarse_dim, quantized the same so this is the field the coarse lod meshes
+//a generated coarse lod samples the terrain itself, taking them from the finer grid is close to that
+fn coarse_densities(field: DensityField, coarse_dim: usize) -> Vec<f32> {
+    let (densities, dim, in_max, first) = match field {
+        DensityField::FullRes(densities_full_res) => (
+            densities_full_res,
+            SAMPLES_PER_CHUNK_DIM_PADDED,
+            SAMPLES_PER_CHUNK_DIM - 1,
+            1.0,
+        ),
+        DensityField::Downsampled(densities, samples_per_chunk_dim) => (
+            densities,
+            samples_per_chunk_dim,
+            samples_per_chunk_dim - 1,
+            0.0,
+        ),
+    };
+    let out_max = (coarse_dim - 1) as f32;
+    let high_sample = |target: usize| first + target as f32 / out_max * in_max as f32;
+    let mut coarse = Vec::with_capacity(coarse_dim.pow(3));
+    for z in 0..coarse_dim {
+        for y in 0..coarse_dim {
+            for x in 0..coarse_dim {
+                let density = sample_trilinear_density(
+                    densities,
+                    dim,
+                    high_sample(x),
+                    high_sample(y),
+                    high_sample(z),
+                );
+                coarse.push(dequantize_i16_to_f32(quantize_f32_to_i16(density)));
+            }
+        }
+    }
+    coarse
+}
+
+//value and gradient of the trilinear field, p is in coarse voxels
+fn sample_with_gradient(coarse: &[f32], dim: usize, p: Vec3) -> (f32, Vec3) {
+    let max_cell = (dim - 2) as f32;
+    let cell = p.floor().clamp(Vec3::ZERO, Vec3::splat(max_cell));
+    let t = p - cell;
+    let (x0, y0, z0) = (cell.x as usize, cell.y as usize, cell.z as usize);
+    let d = |x: usize, y: usize, z: usize| coarse[((z0 + z) * dim + y0 + y) * dim + x0 + x];
+    let (d000, d100, d010, d110) = (d(0, 0, 0), d(1, 0, 0), d(0, 1, 0), d(1, 1, 0));
+    let (d001, d101, d011, d111) = (d(0, 0, 1), d(1, 0, 1), d(0, 1, 1), d(1, 1, 1));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let c00 = lerp(d000, d100, t.x);
+    let c10 = lerp(d010, d110, t.x);
+    let c01 = lerp(d001, d101, t.x);
+    let c11 = lerp(d011, d111, t.x);
+    let c0 = lerp(c00, c10, t.y);
+    let c1 = lerp(c01, c11, t.y);
+    let gradient = Vec3::new(
+        lerp(
+            lerp(d100 - d000, d110 - d010, t.y),
+            lerp(d101 - d001, d111 - d011, t.y),
+            t.z,
+        ),
+        lerp(c10 - c00, c11 - c01, t.z),
+        c1 - c0,
+    );
+    (lerp(c0, c1, t.z), gradient)
+}
+
+//one newton step onto the coarse surface, good enough once a vertex is within a coarse voxel of it
+fn step_to_surface(coarse: &[f32], dim: usize, p: Vec3) -> Vec3 {
+    let max = (dim - 1) as f32;
+    let (value, mut gradient) = sample_with_gradient(coarse, dim, p);
+    //a vertex on a chunk face stays on it, face samples are shared with the neighbor so both sides move it the same
+    for axis in 0..3 {
+        if p[axis] <= FACE_EPSILON || p[axis] >= max - FACE_EPSILON {
+            gradient[axis] = 0.0;
+        }
+    }
+    let length_squared = gradient.length_squared();
+    if length_squared <= f32::EPSILON {
+        return p;
+    }
+    let step = (-value * gradient / length_squared).clamp_length_max(MAX_MORPH_STEP);
+    (p + step).clamp(Vec3::ZERO, Vec3::splat(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{SAMPLES_PER_CHUNK_PADDED, VOXEL_WORLD_SIZE};
+
+    #[test]
+    fn vertices_move_onto_the_coarse_surface() {
+        //a tilted plane is linear so the coarse field matches it up to quantization
+        let plane = |p: Vec3| p.y - 0.3 * p.x - 0.1 * p.z - 0.7;
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                    let local = Vec3::new(x as f32, y as f32, z as f32) * VOXEL_WORLD_SIZE
+                        - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+                    let idx =
+                        (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                    densities[idx] = quantize_f32_to_i16(plane(local));
+                }
+            }
+        }
+        let vertices = [
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(2.0, 0.9, -3.0),
+            Vec3::new(-HALF_CHUNK, -0.7, 1.0), //on the -x face
+        ];
+        let coarse = coarse_positions(
+            &vertices,
+            DensityField::FullRes(&densities),
+            SAMPLES_PER_CHUNK_DIM,
+        )
+        .unwrap();
+        let coarse_voxel = CHUNK_WORLD_SIZE / (RF1_SAMPLES_PER_CHUNK_DIM - 1) as f32;
+        for (vertex, target) in vertices.iter().zip(coarse.iter()) {
+            let target = Vec3::new(target[0], target[1], target[2]);
+            assert!(plane(target).abs() < 0.01, "{target} is off the plane");
+            assert!(vertex.distance(target) <= coarse_voxel + 1e-4);
+        }
+        assert_eq!(coarse[2][0], -HALF_CHUNK);
+        assert_eq!(coarse[0][3], STREAMING_CONFIG.read().lod_radii[0]);
+        assert!(coarse_positions(&vertices, DensityField::FullRes(&densities), 2).is_none());
+    }
+}
@@ -1,2 +1,5 @@
-pub mod mc;
-mod tables;
+//the meshers live in marching_cubes_core, the lod morphing and bevy mesh building stay here
+pub use marching_cubes_core::meshing::{greedy_cubes, mc, simplify, surface_nets, transvoxel};
+
+pub mod geomorph;
+pub mod sdf_mesh;
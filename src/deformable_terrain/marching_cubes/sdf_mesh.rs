@@ -0,0 +1,111 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    math::bounding::Aabb3d,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::{
+    constants::{CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM},
+    deformable_terrain::{
+        chunk_generator::MaterialCode,
+        marching_cubes::mc::{MaterialResolution, mc_mesh_generation},
+        sdf_value::SdfValue,
+    },
+};
+
+//meshes any signed distance function without the terrain, negative is inside
+//resolution is samples per axis across the bounds, normals always come from a 64 sample grid of the same function
+pub fn mesh_sdf(sdf: impl Fn(Vec3) -> f32, bounds: Aabb3d, resolution: usize) -> Mesh {
+    let (vertices, normals, indices) = mesh_sdf_buffers(sdf, bounds, resolution);
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+//positions, normals and indices in the space of the bounds
+//the mesher works on a chunk sized cube, so the bounds are mapped onto it and the result mapped back
+pub fn mesh_sdf_buffers(
+    sdf: impl Fn(Vec3) -> f32,
+    bounds: Aabb3d,
+    resolution: usize,
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>) {
+    assert!(
+        (2..=u16::MAX as usize).contains(&resolution),
+        "resolution must be between 2 and {}",
+        u16::MAX
+    );
+    let min = Vec3::from(bounds.min);
+    let scale = (Vec3::from(bounds.max) - min) / CHUNK_WORLD_SIZE;
+    let to_bounds = |local: Vec3| min + (local + HALF_CHUNK) * scale;
+    let unit = scale.max_element(); //keeps the distances in chunk units so the +-10 range means the same thing
+    let densities = sample_padded(&sdf, &to_bounds, unit, resolution);
+    let densities_full_res = if resolution == SAMPLES_PER_CHUNK_DIM {
+        densities.clone()
+    } else {
+        sample_padded(&sdf, &to_bounds, unit, SAMPLES_PER_CHUNK_DIM)
+    };
+    let materials = vec![MaterialCode::Dirt; resolution.pow(3)];
+    let (mut vertices, mut normals, _, indices) = mc_mesh_generation(
+        &densities,
+        &materials,
+        resolution,
+        true,
+        &densities_full_res,
+        MaterialResolution::DominantCorner,
+    );
+    for vertex in vertices.iter_mut() {
+        *vertex = to_bounds(*vertex);
+    }
+    for normal in normals.iter_mut() {
+        *normal = (*normal / scale).normalize_or(Vec3::Y);
+    }
+    (vertices, normals, indices)
+}
+
+//samples_per_dim plus a one sample apron on every side, laid out the way the chunk mesher reads padded densities
+fn sample_padded(
+    sdf: &impl Fn(Vec3) -> f32,
+    to_bounds: &impl Fn(Vec3) -> Vec3,
+    unit: f32,
+    samples_per_dim: usize,
+) -> Vec<i16> {
+    let padded_dim = samples_per_dim + 2;
+    let voxel_size = CHUNK_WORLD_SIZE / (samples_per_dim - 1) as f32;
+    let mut densities = Vec::with_capacity(padded_dim.pow(3));
+    for z in 0..padded_dim {
+        for y in 0..padded_dim {
+            for x in 0..padded_dim {
+                let local = Vec3::new(x as f32, y as f32, z as f32) * voxel_size
+                    - (HALF_CHUNK + voxel_size);
+                densities.push(SdfValue::from_f32(sdf(to_bounds(local)) / unit).0);
+            }
+        }
+    }
+    densities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_vertices_lie_on_the_sphere() {
+        let center = Vec3::new(5.0, -3.0, 2.0);
+        let radius = 4.0;
+        let bounds = Aabb3d::new(center, Vec3::splat(radius + 1.0));
+        let (vertices, normals, indices) =
+            mesh_sdf_buffers(|p| p.distance(center) - radius, bounds, 32);
+        assert!(!indices.is_empty());
+        let cell = 2.0 * (radius + 1.0) / 31.0;
+        for (vertex, normal) in vertices.iter().zip(normals.iter()) {
+            assert!((vertex.distance(center) - radius).abs() < cell);
+            assert!(normal.dot((*vertex - center).normalize()) > 0.9);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::conversions::{chunk_coord_to_world_pos, cluster_coord_to_world_center};
+use crate::deformable_terrain::terrain::{PreparedMesh, prepare_bevy_mesh};
+
+#[derive(Component)]
+pub struct MergedClusterTag;
+
+//one entity per cluster past MERGED_LOD_RADIUS instead of one per chunk, keyed by cluster coord
+//merged clusters have no entries in the chunk entity map, their chunks are stored in the svo without entities
+#[derive(Resource, Default)]
+pub struct MergedClusterMap(pub(crate) FxHashMap<(i16, i16, i16), (Entity, Handle<Mesh>)>);
+
+//the reduced meshes of a cluster's chunks appended into one, positioned relative to the cluster center
+pub(crate) struct MergedClusterMesh {
+    cluster_center: Vec3,
+    vertices: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    material_ids: Vec<u32>,
+    indices: Vec<u32>,
+}
+
+impl MergedClusterMesh {
+    pub(crate) fn new(cluster_coord: (i16, i16, i16)) -> Self {
+        MergedClusterMesh {
+            cluster_center: cluster_coord_to_world_center(&cluster_coord),
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            material_ids: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    //the chunk mesh is in chunk local space like the ones spawned on their own
+    pub(crate) fn append(
+        &mut self,
+        chunk_coord: ChunkCoord,
+        (vertices, normals, material_ids, indices): (Vec<Vec3>, Vec<Vec3>, Vec<u32>, Vec<u32>),
+    ) {
+        let offset = chunk_coord_to_world_pos(&chunk_coord) - self.cluster_center;
+        let base = self.vertices.len() as u32;
+        self.vertices
+            .extend(vertices.into_iter().map(|v| v + offset));
+        self.normals.extend(normals);
+        self.material_ids.extend(material_ids);
+        self.indices.extend(indices.into_iter().map(|i| i + base));
+    }
+
+    //None when no chunk of the cluster had a surface
+    pub(crate) fn into_prepared(self) -> Option<PreparedMesh> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        Some(prepare_bevy_mesh(
+            self.vertices,
+            self.normals,
+            self.material_ids,
+            self.indices,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHUNKS_PER_CLUSTER_DIM;
+
+    #[test]
+    fn appended_chunks_keep_their_world_positions() {
+        let triangle = || {
+            (
+                vec![Vec3::ZERO, Vec3::X, Vec3::Z],
+                vec![Vec3::Y; 3],
+                vec![0; 3],
+                vec![0, 1, 2],
+            )
+        };
+        let mut merged = MergedClusterMesh::new((1, 0, -1));
+        let first = ChunkCoord(5, 0, -5);
+        let last = first.offset(CHUNKS_PER_CLUSTER_DIM as i16 - 1, 0, 1);
+        merged.append(first, triangle());
+        merged.append(last, triangle());
+        assert_eq!(merged.indices, vec![0, 1, 2, 3, 4, 5]);
+        let world = |i: usize| merged.vertices[i] + merged.cluster_center;
+        assert_eq!(world(0), chunk_coord_to_world_pos(&first));
+        assert_eq!(world(4), chunk_coord_to_world_pos(&last) + Vec3::X);
+        assert!(merged.into_prepared().is_some());
+        assert!(MergedClusterMesh::new((0, 0, 0)).into_prepared().is_none());
+    }
+}
@@ -0,0 +1,303 @@
+use std::fs::{File, create_dir_all, read_dir, remove_dir, remove_dir_all, rename};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED};
+use crate::deformable_terrain::chunk_generator::MaterialCode;
+use crate::deformable_terrain::chunk_summary::{
+    CHUNK_SUMMARY_SERIALIZED_SIZE, compute_chunk_summary,
+};
+use crate::deformable_terrain::file_loader::{
+    REGION_DIR, RegionFiles, disk_size, load_chunk, load_chunk_index_map, read_raw_chunk_at,
+    write_chunk,
+};
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::terrain_error::TerrainError;
+
+const CHUNK_DATA_FILE: &str = "chunk_data.txt";
+const CHUNK_INDEX_FILE: &str = "chunk_index_data.txt";
+const STAGING_DIR: &str = "migration_staging";
+const LEGACY_INDEX_RECORD_SIZE: usize = 14; //sizeof (i16, i16, i16, u64)
+const FIVE_MATERIAL_INDEX_RECORD_SIZE: usize = 21; //summary had coverage for 5 materials, before snow
+const SIX_MATERIAL_INDEX_RECORD_SIZE: usize = 22; //summary had coverage for 6 materials, before water
+const PLAIN_INDEX_RECORD_SIZE: usize = 14 + CHUNK_SUMMARY_SERIALIZED_SIZE;
+
+//every on disk layout the non uniform chunk store has had, oldest first
+//uniform chunk files have not changed and are left alone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageFormat {
+    LegacyIndex,         //index records without a chunk summary
+    FiveMaterialSummary, //index records carry a ChunkSummary from before MaterialCode::Snow
+    SixMaterialSummary,  //index records carry a ChunkSummary from before MaterialCode::Water
+    Plain,               //index records carry a ChunkSummary, chunks appended to one flat data file
+    Region,              //chunks and their summaries in per region files, see file_loader
+}
+
+//where chunk data is read from while migrating
+enum ChunkReader {
+    Flat(File),
+    Region(RegionFiles),
+}
+
+impl ChunkReader {
+    fn read(
+        &mut self,
+        chunk_coord: &ChunkCoord,
+        byte_offset: u64,
+        densities: &mut [i16],
+        materials: &mut [MaterialCode],
+    ) -> Result<(), TerrainError> {
+        match self {
+            ChunkReader::Flat(chunk_data_file) => {
+                read_raw_chunk_at(chunk_data_file, byte_offset, densities, materials)
+            }
+            ChunkReader::Region(region_files) => {
+                load_chunk(region_files, chunk_coord, byte_offset, densities, materials)
+            }
+        }
+    }
+}
+
+impl StorageFormat {
+    pub const ALL: [StorageFormat; 5] = [
+        StorageFormat::LegacyIndex,
+        StorageFormat::FiveMaterialSummary,
+        StorageFormat::SixMaterialSummary,
+        StorageFormat::Plain,
+        StorageFormat::Region,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageFormat::LegacyIndex => "legacy-index",
+            StorageFormat::FiveMaterialSummary => "five-material-summary",
+            StorageFormat::SixMaterialSummary => "six-material-summary",
+            StorageFormat::Plain => "plain",
+            StorageFormat::Region => "region",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    //region files win when present, flat formats are guessed from the index record size
+    //None when there is nothing stored or the size fits several formats and the caller has to say
+    pub fn detect(data_dir: &Path) -> Option<Self> {
+        if read_dir(data_dir.join(REGION_DIR)).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Some(StorageFormat::Region);
+        }
+        let len = std::fs::metadata(data_dir.join(CHUNK_INDEX_FILE))
+            .ok()?
+            .len() as usize;
+        match (
+            len % PLAIN_INDEX_RECORD_SIZE == 0,
+            len % SIX_MATERIAL_INDEX_RECORD_SIZE == 0,
+            len % FIVE_MATERIAL_INDEX_RECORD_SIZE == 0,
+            len % LEGACY_INDEX_RECORD_SIZE == 0,
+        ) {
+            (true, false, false, false) => Some(StorageFormat::Plain),
+            (false, true, false, false) => Some(StorageFormat::SixMaterialSummary),
+            (false, false, true, false) => Some(StorageFormat::FiveMaterialSummary),
+            (false, false, false, true) => Some(StorageFormat::LegacyIndex),
+            _ => None,
+        }
+    }
+
+    //everything the format keeps in the data dir, moved as a whole into the backup
+    fn files(&self) -> &'static [&'static str] {
+        match self {
+            StorageFormat::LegacyIndex
+            | StorageFormat::FiveMaterialSummary
+            | StorageFormat::SixMaterialSummary
+            | StorageFormat::Plain => &[CHUNK_DATA_FILE, CHUNK_INDEX_FILE],
+            StorageFormat::Region => &[REGION_DIR],
+        }
+    }
+
+    fn stored_bytes(&self, data_dir: &Path) -> u64 {
+        self.files()
+            .iter()
+            .filter_map(|name| disk_size(&data_dir.join(name)))
+            .sum()
+    }
+
+    fn open(&self, data_dir: &Path) -> Result<(FxHashMap<ChunkCoord, u64>, ChunkReader), String> {
+        let open_read = |name: &str| {
+            StoragePaths::open_file(&data_dir.join(name), false)
+                .map_err(|e| format!("failed to open {name}: {e}"))
+        };
+        let record_size = match self {
+            StorageFormat::LegacyIndex => LEGACY_INDEX_RECORD_SIZE,
+            StorageFormat::FiveMaterialSummary => FIVE_MATERIAL_INDEX_RECORD_SIZE,
+            StorageFormat::SixMaterialSummary => SIX_MATERIAL_INDEX_RECORD_SIZE,
+            StorageFormat::Plain => PLAIN_INDEX_RECORD_SIZE,
+            StorageFormat::Region => {
+                let index_map = load_chunk_index_map(
+                    data_dir,
+                    &mut FxHashMap::default(),
+                    &mut FxHashMap::default(),
+                );
+                let region_files = RegionFiles::reader(data_dir);
+                return Ok((index_map, ChunkReader::Region(region_files)));
+            }
+        };
+        let index_map = load_flat_chunk_index_map(&mut open_read(CHUNK_INDEX_FILE)?, record_size);
+        Ok((index_map, ChunkReader::Flat(open_read(CHUNK_DATA_FILE)?)))
+    }
+
+    //old formats are only ever migrated from
+    pub fn is_writable(&self) -> bool {
+        matches!(self, StorageFormat::Region)
+    }
+
+    fn write_chunk(
+        &self,
+        densities: &[i16],
+        materials: &[MaterialCode],
+        chunk_coord: &ChunkCoord,
+        region_files: &mut RegionFiles,
+        serial_buffer: &mut Vec<u8>,
+    ) -> Result<(), TerrainError> {
+        match self {
+            StorageFormat::LegacyIndex
+            | StorageFormat::FiveMaterialSummary
+            | StorageFormat::SixMaterialSummary
+            | StorageFormat::Plain => unreachable!(),
+            StorageFormat::Region => write_chunk(
+                densities,
+                materials,
+                &compute_chunk_summary(densities, materials, *chunk_coord),
+                0, //flat formats never recorded when a chunk was written
+                chunk_coord,
+                &mut FxHashMap::default(),
+                region_files,
+                serial_buffer,
+            ),
+        }
+    }
+}
+
+//summaries in flat index files are dropped, they are recomputed when the chunk is written in the new format
+fn load_flat_chunk_index_map(
+    index_file: &mut File,
+    record_size: usize,
+) -> FxHashMap<ChunkCoord, u64> {
+    let mut index_map = FxHashMap::default();
+    index_file.seek(SeekFrom::Start(0)).unwrap();
+    let mut buffer = vec![0u8; record_size];
+    while let Ok(_) = index_file.read_exact(&mut buffer) {
+        let x = i16::from_le_bytes([buffer[0], buffer[1]]);
+        let y = i16::from_le_bytes([buffer[2], buffer[3]]);
+        let z = i16::from_le_bytes([buffer[4], buffer[5]]);
+        let offset = u64::from_le_bytes(buffer[6..14].try_into().unwrap());
+        index_map.insert(ChunkCoord(x, y, z), offset);
+    }
+    index_map
+}
+
+fn hash_chunk(densities: &[i16], materials: &[MaterialCode]) -> u64 {
+    let mut hasher = FxHasher::default();
+    densities.hash(&mut hasher);
+    for &material in materials {
+        (material as u8).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[derive(Debug)]
+pub struct MigrationReport {
+    pub chunks: usize,
+    pub source_bytes: u64,
+    pub destination_bytes: u64,
+    pub backup_dir: String,
+}
+
+//copies every stored chunk into the new format in a staging directory, reads the copy back and compares
+//record counts and per chunk hashes, and only then swaps the files in, keeping the originals as a backup
+pub fn migrate_world(
+    data_dir: &Path,
+    from: StorageFormat,
+    to: StorageFormat,
+) -> Result<MigrationReport, String> {
+    if !to.is_writable() {
+        return Err(format!("{} can only be migrated from", to.name()));
+    }
+    if from == to {
+        return Err(format!("world is already {}", to.name()));
+    }
+    let (index_map, mut source) = from.open(data_dir)?;
+    let source_bytes = from.stored_bytes(data_dir);
+    let staging_dir = data_dir.join(STAGING_DIR);
+    if staging_dir.exists() {
+        //left over from a migration that failed verification
+        remove_dir_all(&staging_dir).map_err(|e| format!("failed to clear staging dir: {e}"))?;
+    }
+    create_dir_all(&staging_dir).map_err(|e| format!("failed to create staging dir: {e}"))?;
+    let mut staged = RegionFiles::writer(&staging_dir)
+        .map_err(|e| format!("failed to open staging region files: {e}"))?;
+    //read in file order so a flat source is streamed front to back
+    let mut chunks: Vec<(ChunkCoord, u64)> = index_map.into_iter().collect();
+    chunks.sort_unstable_by_key(|(_, offset)| *offset);
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+    let mut serial_buffer = Vec::new();
+    let mut hashes = FxHashMap::default();
+    for (chunk_coord, offset) in chunks.iter() {
+        source
+            .read(chunk_coord, *offset, &mut densities, &mut materials)
+            .map_err(|e| format!("failed to read chunk {chunk_coord}: {e}"))?;
+        hashes.insert(*chunk_coord, hash_chunk(&densities, &materials));
+        to.write_chunk(
+            &densities,
+            &materials,
+            chunk_coord,
+            &mut staged,
+            &mut serial_buffer,
+        )
+        .map_err(|e| format!("failed to write chunk {chunk_coord}: {e}"))?;
+    }
+    drop(staged);
+    let (staged_map, mut staged) = to.open(&staging_dir)?;
+    if staged_map.len() != hashes.len() {
+        return Err(format!(
+            "record count mismatch: {} in source, {} after migration",
+            hashes.len(),
+            staged_map.len()
+        ));
+    }
+    for (chunk_coord, offset) in staged_map.iter() {
+        staged
+            .read(chunk_coord, *offset, &mut densities, &mut materials)
+            .map_err(|e| format!("failed to read back chunk {chunk_coord}: {e}"))?;
+        if hashes.get(chunk_coord) != Some(&hash_chunk(&densities, &materials)) {
+            return Err(format!(
+                "chunk {chunk_coord} does not match after migration"
+            ));
+        }
+    }
+    let destination_bytes = to.stored_bytes(&staging_dir);
+    drop((source, staged));
+    let backup_dir = data_dir.join(format!("backup_{}", from.name()));
+    create_dir_all(&backup_dir).map_err(|e| format!("failed to create backup dir: {e}"))?;
+    for name in from.files() {
+        rename(data_dir.join(name), backup_dir.join(name))
+            .map_err(|e| format!("failed to back up {name}: {e}"))?;
+    }
+    for name in to.files() {
+        let _ = remove_dir(data_dir.join(name)); //an empty region dir would block the rename
+        rename(staging_dir.join(name), data_dir.join(name))
+            .map_err(|e| format!("failed to move migrated {name} into place: {e}"))?;
+    }
+    let _ = remove_dir(&staging_dir);
+    Ok(MigrationReport {
+        chunks: hashes.len(),
+        source_bytes,
+        destination_bytes,
+        backup_dir: backup_dir.display().to_string(),
+    })
+}
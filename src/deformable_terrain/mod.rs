@@ -1,6 +1,22 @@
+pub mod backup;
+pub mod biomes;
+pub mod chunk_budget;
 pub mod chunk_entity_map;
+pub mod chunk_events;
 pub mod chunk_generator;
+pub mod chunk_history;
+pub mod chunk_iter;
+#[cfg(feature = "debug")]
+pub mod chunk_overlay;
+pub mod chunk_pool;
+pub mod chunk_store;
+pub mod chunk_summary;
+pub mod cluster_occupancy;
+pub mod collider_cache;
+pub mod collider_culling;
 pub mod column_range_map;
+pub mod cutaway;
+pub mod density_source;
 #[cfg(feature = "debug")]
 pub mod debug_lines;
 pub mod digging;
@@ -8,8 +24,40 @@ pub mod driver;
 #[cfg(feature = "debug")]
 pub mod driver_debug_ui;
 pub mod file_loader;
+pub mod fine_zones;
+pub mod heightfield_collider;
+pub mod heightmap_cache;
+pub mod heightmap_import;
+mod horizon;
+pub mod hydrology;
+pub mod imposters;
+pub mod load_history;
+pub mod lod_fade;
+mod lru_cache;
 pub mod marching_cubes;
+pub mod merged_clusters;
+pub mod migrate;
+pub mod observers;
+pub mod orbit_stress;
+pub mod placeholders;
 pub mod plugin;
+pub mod protected_regions;
+pub mod roads;
+pub mod scatter;
+pub mod schematic;
+pub mod simulation;
 mod sparse_voxel_octree;
+pub mod storage_paths;
+pub mod structures;
 mod terrain;
+pub mod terrain_edit;
+pub mod terrain_error;
 pub mod terrain_material;
+pub mod terrain_query;
+pub mod world_gen;
+pub mod world_gen_compare;
+pub mod world_header;
+pub mod world_stats;
+pub mod write_journal;
+
+pub use marching_cubes_core::sdf_value; //kept at its old path
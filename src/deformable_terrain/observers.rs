@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use parking_lot::Mutex;
+
+use crate::deformable_terrain::sparse_voxel_octree::RenderBounds;
+
+//a point terrain streams in around, the svo manager always puts the player first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainObserver {
+    pub position: Vec3,
+    pub render_radius_squared: f32,
+    pub vertical_render_radius_squared: f32, //0 keeps the bounds a sphere
+}
+
+impl TerrainObserver {
+    pub(crate) fn bounds(&self) -> RenderBounds {
+        RenderBounds::new(
+            self.render_radius_squared,
+            self.vertical_render_radius_squared,
+        )
+    }
+}
+
+//handed out by TerrainObservers::add, the observer keeps terrain loaded until it is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u32);
+
+#[derive(Default)]
+struct ObserverList {
+    next_id: u32,
+    observers: Vec<(ObserverId, TerrainObserver)>,
+}
+
+//observers besides the player, other players on a server, a second split screen camera, a map render job
+//the svo manager loads the union of their regions and every cluster gets the lod of the observer nearest to it
+#[derive(Resource, Clone, Default)]
+pub struct TerrainObservers(Arc<Mutex<ObserverList>>);
+
+impl TerrainObservers {
+    pub fn add(&self, observer: TerrainObserver) -> ObserverId {
+        let mut list = self.0.lock();
+        let id = ObserverId(list.next_id);
+        list.next_id += 1;
+        list.observers.push((id, observer));
+        id
+    }
+
+    pub fn set_position(&self, id: ObserverId, position: Vec3) {
+        if let Some((_, observer)) = self.0.lock().observers.iter_mut().find(|(i, _)| *i == id) {
+            observer.position = position;
+        }
+    }
+
+    //its clusters are dropped on the next pass unless another observer still covers them
+    pub fn remove(&self, id: ObserverId) -> bool {
+        let mut list = self.0.lock();
+        let len = list.observers.len();
+        list.observers.retain(|(i, _)| *i != id);
+        list.observers.len() != len
+    }
+
+    //the manager copies them out once per pass instead of holding the lock through it
+    pub(crate) fn copy_into(&self, observers: &mut Vec<TerrainObserver>) {
+        observers.extend(
+            self.0
+                .lock()
+                .observers
+                .iter()
+                .map(|(_, observer)| *observer),
+        );
+    }
+}
+
+pub(crate) fn nearest_distance_squared(observers: &[TerrainObserver], point: Vec3) -> f32 {
+    observers
+        .iter()
+        .map(|observer| observer.position.distance_squared(point))
+        .fold(f32::INFINITY, f32::min)
+}
+
+//a pass is due once any observer moved past the threshold, changed radius, or one was added or removed
+pub(crate) fn observers_changed(
+    last: &[TerrainObserver],
+    current: &[TerrainObserver],
+    move_threshold: f32,
+) -> bool {
+    last.len() != current.len()
+        || last.iter().zip(current).any(|(last, current)| {
+            last.position.distance_squared(current.position) >= move_threshold * move_threshold
+                || last.render_radius_squared != current.render_radius_squared
+                || last.vertical_render_radius_squared != current.vertical_render_radius_squared
+        })
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use std::sync::atomic::Ordering;
+
+use crate::deformable_terrain::driver::{RENDER_RADIUS_SQUARED, VERTICAL_RENDER_RADIUS_SQUARED};
+use crate::deformable_terrain::observers::{ObserverId, TerrainObserver, TerrainObservers};
+use crate::deformable_terrain::plugin::MoveableCenter;
+use crate::ui::console::{Console, ConsoleCommand};
+
+const DEFAULT_ORBIT_RADIUS: f32 = 1500.0;
+const DEFAULT_ORBIT_SPEED: f32 = 250.0; //world units per second along the circle
+
+//invisible observer circling the player's position with the player's render radius, the player keeps its own region
+//reproduces load/unload churn, svo manager spinning and channel backlogs on demand
+#[derive(Resource, Default)]
+pub struct OrbitObserver {
+    id: Option<ObserverId>, //registered with TerrainObservers while it runs
+    center: Vec3,
+    radius: f32,
+    speed: f32,
+    angle: f32,
+}
+
+//orbit [radius] [speed] starts around the player, orbit again or orbit stop removes the observer
+pub fn orbit_stress_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    moveable_center: Res<MoveableCenter>,
+    terrain_observers: Res<TerrainObservers>,
+    mut orbit: ResMut<OrbitObserver>,
+) {
+    for command in command_reader.read() {
+        if command.name != "orbit" {
+            continue;
+        }
+        let stop = command.args.first().is_some_and(|a| a == "stop");
+        if let Some(id) = orbit.id
+            && (stop || command.args.is_empty())
+        {
+            terrain_observers.remove(id);
+            orbit.id = None;
+            console.print("orbit stopped, its terrain unloads unless the player still covers it");
+            continue;
+        }
+        if stop {
+            console.print("orbit is not running");
+            continue;
+        }
+        let parse = |idx: usize, default: f32| {
+            command.args.get(idx).map_or(Some(default), |a| {
+                a.parse::<f32>().ok().filter(|v| *v > 0.0)
+            })
+        };
+        let (Some(radius), Some(speed)) = (
+            parse(0, DEFAULT_ORBIT_RADIUS),
+            parse(1, DEFAULT_ORBIT_SPEED),
+        ) else {
+            console.print("usage: orbit [radius] [speed] | orbit stop");
+            continue;
+        };
+        if let Some(id) = orbit.id {
+            terrain_observers.remove(id); //restarting with new settings
+        }
+        let center = moveable_center.read();
+        let id = terrain_observers.add(TerrainObserver {
+            position: center,
+            render_radius_squared: f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)),
+            vertical_render_radius_squared: f32::from_bits(
+                VERTICAL_RENDER_RADIUS_SQUARED.load(Ordering::Relaxed),
+            ),
+        });
+        *orbit = OrbitObserver {
+            id: Some(id),
+            center,
+            radius,
+            speed,
+            angle: 0.0,
+        };
+        console.print(format!(
+            "orbiting r={radius:.0} at {speed:.0}/s, one lap every {:.1}s",
+            std::f32::consts::TAU * radius / speed
+        ));
+    }
+}
+
+pub fn update_orbit_observer(
+    time: Res<Time>,
+    mut orbit: ResMut<OrbitObserver>,
+    terrain_observers: Res<TerrainObservers>,
+) {
+    let Some(id) = orbit.id else {
+        return;
+    };
+    orbit.angle =
+        (orbit.angle + orbit.speed / orbit.radius * time.delta_secs()) % std::f32::consts::TAU;
+    let offset = Vec3::new(orbit.angle.cos() - 1.0, 0.0, orbit.angle.sin()) * orbit.radius;
+    terrain_observers.set_position(id, orbit.center + offset);
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{CHUNK_WORLD_SIZE, HALF_CHUNK};
+use crate::deformable_terrain::chunk_entity_map::ChunkEntityMap;
+use crate::deformable_terrain::chunk_generator::{MaterialCode, generate_noise_height_grid};
+use crate::deformable_terrain::driver::{NoiseGenerator, TerrainChunkMap};
+use crate::deformable_terrain::plugin::MoveableCenter;
+use crate::deformable_terrain::terrain::{PreparedMesh, TerrainMaterialHandle, prepare_bevy_mesh};
+
+const PLACEHOLDER_GRID_DIM: usize = 3; //noise samples per column edge, lines up with the heightmap control points
+const PLACEHOLDER_RADIUS: i16 = 3; //in chunk columns around the predicted position
+const PLACEHOLDER_LOOKAHEAD: f32 = 0.75; //seconds of movement to predict ahead
+const PLACEHOLDER_MIN_SPEED: f32 = 15.0; //world units per second, walking never outruns the loaders
+const MAX_PLACEHOLDER_SPAWNS_PER_FRAME: usize = 16;
+
+#[derive(Component)]
+pub struct PlaceholderTag;
+
+//keyed by chunk column, the y range holds every chunk the placeholder surface passes through
+#[derive(Resource, Default)]
+pub struct Placeholders(FxHashMap<(i16, i16), (Entity, Handle<Mesh>, (i16, i16))>);
+
+//heightmap only quad patch so fast movement never looks into a hole while the real mesh is still queued
+//placeholders have no collider, they are removed as soon as any chunk they cover is loaded or the player moves on
+pub fn update_placeholder_meshes(
+    mut commands: Commands,
+    moveable_center: Res<MoveableCenter>,
+    time: Res<Time>,
+    noise_generator: Option<Res<NoiseGenerator>>,
+    chunk_entity_map: Res<ChunkEntityMap>,
+    terrain_chunk_map: Option<Res<TerrainChunkMap>>,
+    standard_material: Option<Res<TerrainMaterialHandle>>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+    mut placeholders: ResMut<Placeholders>,
+    mut last_center: Local<Option<Vec3>>,
+) {
+    let (Some(noise_generator), Some(terrain_chunk_map), Some(standard_material)) =
+        (noise_generator, terrain_chunk_map, standard_material)
+    else {
+        return;
+    };
+    let center = moveable_center.read();
+    let velocity = match *last_center {
+        Some(last) if time.delta_secs() > 0.0 => (center - last) / time.delta_secs(),
+        _ => Vec3::ZERO,
+    };
+    *last_center = Some(center);
+    let predicted = center + velocity * PLACEHOLDER_LOOKAHEAD;
+    let predicted_column = (
+        (predicted.x / CHUNK_WORLD_SIZE).round() as i16,
+        (predicted.z / CHUNK_WORLD_SIZE).round() as i16,
+    );
+    let center_column = (
+        (center.x / CHUNK_WORLD_SIZE).round() as i16,
+        (center.z / CHUNK_WORLD_SIZE).round() as i16,
+    );
+    let is_loaded = |column: (i16, i16), y_range: (i16, i16)| {
+        (y_range.0..=y_range.1).any(|y| {
+            let chunk_coord = ChunkCoord(column.0, y, column.1);
+            chunk_entity_map.get_option(chunk_coord).is_some()
+                || terrain_chunk_map.0.contains_key(&chunk_coord)
+        })
+    };
+    let near = |column: (i16, i16), target: (i16, i16)| {
+        (column.0 - target.0).abs() <= PLACEHOLDER_RADIUS + 1
+            && (column.1 - target.1).abs() <= PLACEHOLDER_RADIUS + 1
+    };
+    placeholders
+        .0
+        .retain(|column, (entity, mesh_handle, y_range)| {
+            if is_loaded(*column, *y_range)
+                || !(near(*column, center_column) || near(*column, predicted_column))
+            {
+                mesh_handles.remove(&*mesh_handle);
+                commands.entity(*entity).despawn();
+                return false;
+            }
+            true
+        });
+    if velocity.length() < PLACEHOLDER_MIN_SPEED {
+        return;
+    }
+    let mut spawned = 0;
+    for column_x in
+        predicted_column.0 - PLACEHOLDER_RADIUS..=predicted_column.0 + PLACEHOLDER_RADIUS
+    {
+        for column_z in
+            predicted_column.1 - PLACEHOLDER_RADIUS..=predicted_column.1 + PLACEHOLDER_RADIUS
+        {
+            let column = (column_x, column_z);
+            if spawned == MAX_PLACEHOLDER_SPAWNS_PER_FRAME {
+                return;
+            }
+            if placeholders.0.contains_key(&column) {
+                continue;
+            }
+            let column_start = Vec2::new(
+                column_x as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK,
+                column_z as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK,
+            );
+            let heights = generate_noise_height_grid(
+                (column_start.x / HALF_CHUNK) as i32,
+                (column_start.y / HALF_CHUNK) as i32,
+                PLACEHOLDER_GRID_DIM,
+                PLACEHOLDER_GRID_DIM,
+                HALF_CHUNK,
+                &noise_generator.0,
+            );
+            let (min_height, max_height) = heights
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+            let y_range = (
+                (min_height / CHUNK_WORLD_SIZE).round() as i16,
+                (max_height / CHUNK_WORLD_SIZE).round() as i16,
+            );
+            if is_loaded(column, y_range) {
+                continue;
+            }
+            let prepared = build_placeholder_mesh(column_start, &heights);
+            let mesh_handle = mesh_handles.add(prepared.mesh);
+            let entity = commands
+                .spawn((
+                    PlaceholderTag,
+                    Mesh3d(mesh_handle.clone()),
+                    prepared.aabb,
+                    Transform::default(),
+                    MeshMaterial3d(standard_material.0.clone()),
+                ))
+                .id();
+            placeholders
+                .0
+                .insert(column, (entity, mesh_handle, y_range));
+            spawned += 1;
+        }
+    }
+}
+
+//vertices are in world space, the grid is row major in z like the noise output
+fn build_placeholder_mesh(column_start: Vec2, heights: &[f32]) -> PreparedMesh {
+    let mut vertices = Vec::with_capacity(heights.len());
+    let mut normals = Vec::with_capacity(heights.len());
+    let height_at = |x: usize, z: usize| heights[z * PLACEHOLDER_GRID_DIM + x];
+    for z in 0..PLACEHOLDER_GRID_DIM {
+        for x in 0..PLACEHOLDER_GRID_DIM {
+            vertices.push(Vec3::new(
+                column_start.x + x as f32 * HALF_CHUNK,
+                height_at(x, z),
+                column_start.y + z as f32 * HALF_CHUNK,
+            ));
+            let (x0, x1) = (x.saturating_sub(1), (x + 1).min(PLACEHOLDER_GRID_DIM - 1));
+            let (z0, z1) = (z.saturating_sub(1), (z + 1).min(PLACEHOLDER_GRID_DIM - 1));
+            let dhdx = (height_at(x1, z) - height_at(x0, z)) / ((x1 - x0) as f32 * HALF_CHUNK);
+            let dhdz = (height_at(x, z1) - height_at(x, z0)) / ((z1 - z0) as f32 * HALF_CHUNK);
+            normals.push(Vec3::new(-dhdx, 1.0, -dhdz).normalize());
+        }
+    }
+    let mut indices = Vec::with_capacity((PLACEHOLDER_GRID_DIM - 1).pow(2) * 6);
+    for z in 0..PLACEHOLDER_GRID_DIM - 1 {
+        for x in 0..PLACEHOLDER_GRID_DIM - 1 {
+            let i00 = (z * PLACEHOLDER_GRID_DIM + x) as u32;
+            let i10 = i00 + 1;
+            let i01 = i00 + PLACEHOLDER_GRID_DIM as u32;
+            let i11 = i01 + 1;
+            indices.extend_from_slice(&[i00, i01, i10, i10, i01, i11]);
+        }
+    }
+    let material_ids = vec![MaterialCode::Grass as u32; vertices.len()];
+    prepare_bevy_mesh(vertices, normals, material_ids, indices)
+}
@@ -1,19 +1,57 @@
 use std::sync::{Arc, Mutex, atomic::Ordering};
 
+use std::time::Instant;
+
 use bevy::{
-    app::{App, Plugin, Startup, Update},
-    ecs::{component::Component, resource::Resource},
-    math::Vec3,
+    app::{App, AppExit, First, Last, Plugin, Startup, Update},
+    ecs::{
+        change_detection::DetectChanges, component::Component, resource::Resource,
+        schedule::IntoScheduleConfigs, system::Res,
+    },
+    log::{error, warn},
+    math::{Dir3, Vec3},
+    pbr::{ExtendedMaterial, MaterialPlugin, StandardMaterial},
 };
 use fastnoise2::{SafeNode, generator::GeneratorWrapper};
 use serde::{Deserialize, Serialize};
 
+use crate::constants::{
+    MERGED_LOD_RADIUS, REDUCED_LOD_1_RADIUS, REDUCED_LOD_2_RADIUS, REDUCED_LOD_3_RADIUS,
+    REDUCED_LOD_4_RADIUS, REDUCED_LOD_5_RADIUS, SIMULATION_RADIUS,
+};
 use crate::deformable_terrain::{
-    driver::{Lods, RENDER_RADIUS_SQUARED, chunk_spawn_reciever, info_print, setup_chunk_driver},
-    file_loader::setup_chunk_loading,
-    terrain::setup_map,
+    chunk_budget::TerrainMemoryBudget,
+    chunk_events::{ChunkEdited, ChunkLoaded, ChunkMeshed, ChunkUnloaded},
+    chunk_generator::get_fbm,
+    cluster_occupancy::save_cluster_occupancy_on_exit,
+    collider_culling::cull_far_colliders,
+    digging::{TerrainDug, apply_remesh_results},
+    driver::{
+        FrameStart, InitialLoadComplete, InitialLoadProgress, LOD_MORPHING, Lods, MEMORY_BUDGET,
+        MESH_SIMPLIFICATION, MESHING_MODE, RENDER_RADIUS_SQUARED, STREAMING_CONFIG,
+        VERTICAL_RENDER_RADIUS_SQUARED, chunk_spawn_reciever, info_print, record_frame_start,
+        setup_chunk_driver, update_initial_load_progress,
+    },
+    file_loader::{open_world_dir, setup_chunk_loading},
+    fine_zones::{apply_fine_remeshes, dig_fine_zones, setup_fine_zones, update_fine_zones},
+    imposters::{Imposters, update_imposters},
+    lod_fade::{setup_lod_fade_materials, sync_lod_fade_materials, update_lod_fades},
+    marching_cubes::simplify::MeshSimplification,
+    merged_clusters::MergedClusterMap,
+    observers::TerrainObservers,
+    placeholders::{Placeholders, update_placeholder_meshes},
+    protected_regions::{EditRejected, ProtectedRegions},
+    storage_paths::StoragePaths,
+    terrain::{setup_map, update_morph_center},
+    terrain_material::TerrainMaterialExtension,
+    world_gen::{
+        CaveSettings, WorldBounds, WorldGenConfig, WorldSeed, load_world_gen_config,
+        load_world_gen_config_or, set_world_gen_config,
+    },
 };
 
+type TerrainMaterial = ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>;
+
 #[derive(Resource)]
 pub struct NoiseFunction(pub GeneratorWrapper<SafeNode>);
 
@@ -29,6 +67,14 @@ pub enum Uniformity {
     Unknown,
 }
 
+//picked once per world, every chunk of the world has to be meshed the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MeshingMode {
+    #[default]
+    MarchingCubes,
+    GreedyCubes, //blocky, the sdf is thresholded per cell
+}
+
 #[derive(Resource)]
 pub struct DeformableTerrainConfig {
     pub lods: bool,
@@ -39,19 +85,91 @@ impl DeformableTerrainConfig {
         RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)
     }
 
+    //f32 bits of the squared radius, the svo manager polls it and its next pass loads or drops clusters to match
     pub fn set_render_radius(radius: u32) {
         RENDER_RADIUS_SQUARED.store(radius, Ordering::Relaxed);
     }
 
+    //squared like the render radius, past it or 0 the render distance stays a sphere
+    pub fn set_vertical_render_radius(radius: u32) {
+        VERTICAL_RENDER_RADIUS_SQUARED.store(radius, Ordering::Relaxed);
+    }
+
+    //applies to lod meshes built after the call
+    pub fn set_mesh_simplification(simplification: Option<MeshSimplification>) {
+        *MESH_SIMPLIFICATION.write() = simplification;
+    }
+
+    //checked on every svo pass, lowering it evicts on the next one
+    pub fn set_memory_budget(budget: TerrainMemoryBudget) {
+        *MEMORY_BUDGET.write() = budget;
+    }
+
     pub fn default() -> Self {
         DeformableTerrainConfig { lods: false }
     }
 }
 
+//the radii the lods change at, world units from the nearest observer, each larger than the one before
+//the svo manager reads them every loop, a change starts a pass that moves every cluster whose lod changed
+//the render radius past them is DeformableTerrainConfig::set_render_radius
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct StreamingConfig {
+    pub lod_radii: [f32; 5], //past lod_radii[i] clusters load at lod i + 1
+    pub merged_radius: f32,  //past it whole clusters are one mesh at lod5 resolution
+}
+
+impl StreamingConfig {
+    pub const DEFAULT: StreamingConfig = StreamingConfig {
+        lod_radii: [
+            REDUCED_LOD_1_RADIUS,
+            REDUCED_LOD_2_RADIUS,
+            REDUCED_LOD_3_RADIUS,
+            REDUCED_LOD_4_RADIUS,
+            REDUCED_LOD_5_RADIUS,
+        ],
+        merged_radius: MERGED_LOD_RADIUS,
+    };
+
+    //raises every radius to at least the one before it, starting from the simulation radius
+    //a lod1 radius inside the simulation radius would take colliders away from the player, one out of order skips lods
+    pub fn clamped(mut self) -> StreamingConfig {
+        let mut floor = SIMULATION_RADIUS;
+        for radius in &mut self.lod_radii {
+            *radius = radius.max(floor);
+            floor = *radius;
+        }
+        self.merged_radius = self.merged_radius.max(floor);
+        self
+    }
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        StreamingConfig::DEFAULT
+    }
+}
+
+//hands the resource to the svo manager and the meshers, also runs on the first frame so one inserted before the plugin applies
+fn apply_streaming_config(streaming: Res<StreamingConfig>) {
+    if streaming.is_changed() {
+        let clamped = streaming.clamped();
+        if clamped != *streaming {
+            warn!(
+                "Streaming radii have to grow outward from the simulation radius {SIMULATION_RADIUS}, using {clamped:?} instead of {:?}.",
+                *streaming
+            );
+        }
+        *STREAMING_CONFIG.write() = clamped;
+    }
+}
+
 #[derive(Resource)]
 pub struct MoveableCenter {
     pub(crate) center_mutex: Arc<Mutex<Vec3>>,
     last_center: Vec3,
+    pub(crate) view_mutex: Arc<Mutex<Vec3>>, //camera forward, zero until the camera sets it and loads ignore it until then
+    last_view: Vec3,
 }
 
 impl MoveableCenter {
@@ -60,32 +178,185 @@ impl MoveableCenter {
         self.last_center = new_position
     }
 
+    pub fn update_view(&mut self, forward: Dir3) {
+        *(self.view_mutex.lock().unwrap()) = *forward;
+        self.last_view = *forward;
+    }
+
+    pub fn read_view(&self) -> Vec3 {
+        self.last_view
+    }
+
     pub fn read(&self) -> Vec3 {
         self.last_center
     }
 }
 
+//everything the terrain needs to load, mesh, page and save chunks around MoveableCenter, the config is its fields
+//apps that drive the center themselves only need this one, PlayerPlugin and TerrainEditPlugin build on top of it
 pub struct DeformableTerrainPlugin {
     pub lods: bool,
+    pub meshing_mode: MeshingMode,
+    pub caves: CaveSettings, //for a world with nothing saved yet, an existing world keeps the caves it was generated with
+    pub imposters: bool,     //heightmap imposter ring past the render radius
+    pub lod_morphing: bool, //vertices slide towards the next coarser lod before it swaps in, only with lods
+    pub lod_fade: bool, //lod mesh swaps cross fade through a dither instead of popping, only with lods
+    pub new_world_seed: Option<i32>, //seed for a world with nothing saved yet, None rolls a random one
+    pub memory_budget: TerrainMemoryBudget, //for the chunk samples kept in the simulation radius
+    pub storage: StoragePaths,       //where the world is saved, the platform data dir unless set
+}
+
+pub type TerrainPlugin = DeformableTerrainPlugin;
+
+impl Default for DeformableTerrainPlugin {
+    fn default() -> Self {
+        DeformableTerrainPlugin {
+            lods: false,
+            meshing_mode: MeshingMode::default(),
+            caves: CaveSettings::default(),
+            imposters: true,
+            lod_morphing: true,
+            lod_fade: true,
+            new_world_seed: None,
+            memory_budget: TerrainMemoryBudget::Unlimited,
+            storage: StoragePaths::default(),
+        }
+    }
 }
 
 impl Plugin for DeformableTerrainPlugin {
     fn build(&self, app: &mut App) {
+        *MESHING_MODE.write() = self.meshing_mode;
+        *MEMORY_BUDGET.write() = self.memory_budget;
+        let lod_morphing = self.lods && self.lod_morphing;
+        LOD_MORPHING.store(lod_morphing, Ordering::Relaxed);
+        let data_dir = self.storage.world_dir();
+        if let Err(e) = open_world_dir(&data_dir) {
+            //nothing has touched the world yet, so exit before the first frame instead of running on a world that isnt there
+            error!("{e}");
+            app.set_runner(|_| AppExit::error());
+            return;
+        }
+        let new_world = WorldGenConfig {
+            caves: self.caves,
+            ..default()
+        };
+        let world_gen = match self.new_world_seed {
+            Some(seed) => {
+                let world_gen =
+                    load_world_gen_config_or(&data_dir, WorldGenConfig { seed, ..new_world });
+                if world_gen.seed != seed {
+                    warn!(
+                        "World was generated with seed {}, ignoring seed {seed}.",
+                        world_gen.seed
+                    );
+                }
+                world_gen
+            }
+            None => load_world_gen_config(&data_dir, new_world),
+        };
+        if world_gen.caves != self.caves {
+            warn!(
+                "World was generated with {:?}, ignoring {:?}.",
+                world_gen.caves, self.caves
+            );
+        }
+        set_world_gen_config(world_gen);
+        app.insert_resource(self.storage.clone())
+            .insert_resource(WorldSeed(world_gen.seed))
+            .insert_resource(WorldBounds::from_radius(world_gen.world_radius_clusters()))
+            .insert_resource(world_gen);
+        if !app.is_plugin_added::<MaterialPlugin<TerrainMaterial>>() {
+            app.add_plugins(MaterialPlugin::<TerrainMaterial>::default());
+        }
         app.insert_resource(MoveableCenter {
             center_mutex: Arc::new(Mutex::new(Vec3::ZERO)),
             last_center: Vec3::ZERO,
+            view_mutex: Arc::new(Mutex::new(Vec3::ZERO)),
+            last_view: Vec3::ZERO,
         })
         .insert_resource(DeformableTerrainConfig::default())
         .insert_resource(Lods(self.lods))
+        .insert_resource(NoiseFunction(get_fbm()))
+        .insert_resource(FrameStart(Instant::now()))
+        .init_resource::<Placeholders>()
+        .init_resource::<MergedClusterMap>()
+        .init_resource::<InitialLoadProgress>()
+        .init_resource::<TerrainObservers>()
+        .init_resource::<ProtectedRegions>()
+        .init_resource::<StreamingConfig>()
+        .add_message::<TerrainDug>()
+        .add_message::<InitialLoadComplete>()
+        .add_message::<EditRejected>()
+        .add_message::<ChunkLoaded>()
+        .add_message::<ChunkMeshed>()
+        .add_message::<ChunkUnloaded>()
+        .add_message::<ChunkEdited>()
         .add_systems(
             Startup,
             (
                 info_print,
                 setup_chunk_loading,
-                setup_chunk_driver,
+                setup_chunk_driver.after(setup_chunk_loading),
+                setup_fine_zones.after(setup_chunk_loading),
                 setup_map,
             ),
         )
-        .add_systems(Update, chunk_spawn_reciever);
+        .add_systems(First, record_frame_start)
+        .add_systems(
+            Update,
+            (
+                apply_streaming_config,
+                update_initial_load_progress.before(chunk_spawn_reciever),
+                chunk_spawn_reciever,
+                apply_remesh_results.after(chunk_spawn_reciever),
+                cull_far_colliders.after(chunk_spawn_reciever),
+                update_placeholder_meshes.after(chunk_spawn_reciever),
+                update_fine_zones.after(chunk_spawn_reciever),
+                dig_fine_zones.after(update_fine_zones),
+                apply_fine_remeshes.after(dig_fine_zones),
+            ),
+        );
+        if lod_morphing {
+            app.add_systems(Update, update_morph_center);
+        }
+        if self.lods {
+            app.add_systems(Last, save_cluster_occupancy_on_exit);
+        }
+        if self.lods && self.lod_fade {
+            app.add_systems(Startup, setup_lod_fade_materials.after(setup_map))
+                .add_systems(
+                    Update,
+                    (
+                        update_lod_fades.after(chunk_spawn_reciever),
+                        sync_lod_fade_materials,
+                    ),
+                );
+        }
+        if self.imposters {
+            app.init_resource::<Imposters>()
+                .add_systems(Update, update_imposters);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_radii_are_clamped_to_grow_from_the_simulation_radius() {
+        assert_eq!(StreamingConfig::DEFAULT.clamped(), StreamingConfig::DEFAULT);
+        let config = StreamingConfig {
+            lod_radii: [10.0, 200.0, 150.0, 400.0, 300.0],
+            merged_radius: 100.0,
+        };
+        assert_eq!(
+            config.clamped(),
+            StreamingConfig {
+                lod_radii: [SIMULATION_RADIUS, 200.0, 200.0, 400.0, 400.0],
+                merged_radius: 400.0,
+            }
+        );
     }
 }
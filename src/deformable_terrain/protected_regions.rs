@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::{
+    deformable_terrain::{
+        digging::screen_to_world_ray,
+        driver::{TerrainChunkMap, TerrainSvo},
+    },
+    player::player::MainCameraTag,
+    ui::console::{Console, ConsoleCommand},
+};
+
+const REJECTION_REPORT_INTERVAL: f32 = 1.0; // seconds, holding the dig button on a protected area reports it once a second
+const MAX_PROTECT_HALF_SIZE: f32 = 256.0; //world space
+
+//a named box of world space no edit can change, such as the spawn area or a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtectedRegion {
+    pub name: String,
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+//every brush sample inside one of these is left as it is, whole schematic pastes that overlap one are refused
+#[derive(Resource, Debug, Default)]
+pub struct ProtectedRegions {
+    regions: Vec<ProtectedRegion>,
+}
+
+impl ProtectedRegions {
+    //a region with the same name is replaced
+    pub fn protect(&mut self, name: impl Into<String>, min: Vec3, max: Vec3) {
+        let name = name.into();
+        self.regions.retain(|region| region.name != name);
+        self.regions.push(ProtectedRegion {
+            name,
+            min: min.min(max),
+            max: min.max(max),
+        });
+    }
+
+    pub fn unprotect(&mut self, name: &str) -> bool {
+        let before = self.regions.len();
+        self.regions.retain(|region| region.name != name);
+        self.regions.len() != before
+    }
+
+    pub fn regions(&self) -> &[ProtectedRegion] {
+        &self.regions
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.regions
+            .iter()
+            .any(|region| point.cmpge(region.min).all() && point.cmple(region.max).all())
+    }
+
+    //the first region the box min..max touches
+    pub fn overlapping(&self, min: Vec3, max: Vec3) -> Option<&ProtectedRegion> {
+        self.regions
+            .iter()
+            .find(|region| min.cmple(region.max).all() && max.cmpge(region.min).all())
+    }
+}
+
+//an edit reached into a protected region, the samples inside it were left alone
+#[derive(Message, Clone, Debug)]
+pub struct EditRejected {
+    pub center: Vec3,
+    pub region: String,
+}
+
+//rejections come every dig tick while the button is held, only the first of each burst is printed
+pub fn report_rejected_edits(
+    mut rejected_reader: MessageReader<EditRejected>,
+    console: Res<Console>,
+    time: Res<Time>,
+    mut last_report: Local<Option<f32>>,
+) {
+    let Some(rejected) = rejected_reader.read().last() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    if last_report.is_some_and(|last| now - last < REJECTION_REPORT_INTERVAL) {
+        return;
+    }
+    *last_report = Some(now);
+    console.print(format!("{} is protected", rejected.region));
+}
+
+//`protect <name> <half size>` protects a box centered where the crosshair hits the terrain
+//`protect remove <name>` and `protect list` manage the ones there are
+pub fn protect_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
+    window: Query<&Window>,
+    terrain_chunk_map: Res<TerrainChunkMap>,
+    terrain_svo: Res<TerrainSvo>,
+    mut protected_regions: ResMut<ProtectedRegions>,
+) {
+    for command in command_reader.read() {
+        if command.name != "protect" {
+            continue;
+        }
+        let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            ["list"] => {
+                if protected_regions.regions().is_empty() {
+                    console.print("nothing is protected");
+                }
+                for region in protected_regions.regions() {
+                    console.print(format!(
+                        "{}: {:.1} to {:.1}",
+                        region.name, region.min, region.max
+                    ));
+                }
+            }
+            ["remove", name] => {
+                if protected_regions.unprotect(name) {
+                    console.print(format!("{name} is no longer protected"));
+                } else {
+                    console.print(format!("no protected region named {name}"));
+                }
+            }
+            [name, half_size] => {
+                let half_size = match half_size.parse::<f32>() {
+                    Ok(half_size) if half_size > 0.0 && half_size <= MAX_PROTECT_HALF_SIZE => {
+                        half_size
+                    }
+                    _ => {
+                        console.print(format!(
+                            "half size is a number of world units up to {MAX_PROTECT_HALF_SIZE}"
+                        ));
+                        continue;
+                    }
+                };
+                let target = window
+                    .iter()
+                    .next()
+                    .and_then(|window| window.cursor_position())
+                    .zip(camera.iter().next())
+                    .and_then(|(cursor_pos, (camera, camera_transform))| {
+                        screen_to_world_ray(
+                            cursor_pos,
+                            camera,
+                            camera_transform,
+                            &terrain_chunk_map,
+                            &terrain_svo,
+                        )
+                    });
+                let Some(center) = target else {
+                    console.print("no terrain under the crosshair");
+                    continue;
+                };
+                protected_regions.protect(
+                    *name,
+                    center - Vec3::splat(half_size),
+                    center + Vec3::splat(half_size),
+                );
+                console.print(format!("protected {name} around {center:.1}"));
+            }
+            _ => console.print("usage: protect <name> <half size> | remove <name> | list"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_is_inclusive_of_the_box_faces() {
+        let mut regions = ProtectedRegions::default();
+        regions.protect("spawn", Vec3::splat(-2.0), Vec3::splat(2.0));
+        assert!(regions.contains(Vec3::ZERO));
+        assert!(regions.contains(Vec3::new(2.0, -2.0, 0.0)));
+        assert!(!regions.contains(Vec3::new(2.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn protecting_a_name_again_replaces_it() {
+        let mut regions = ProtectedRegions::default();
+        regions.protect("spawn", Vec3::ZERO, Vec3::ONE);
+        regions.protect("spawn", Vec3::splat(10.0), Vec3::splat(5.0));
+        assert_eq!(regions.regions().len(), 1);
+        assert!(!regions.contains(Vec3::splat(0.5)));
+        assert!(regions.contains(Vec3::splat(7.0)));
+        assert!(regions.unprotect("spawn"));
+        assert!(!regions.unprotect("spawn"));
+    }
+
+    #[test]
+    fn overlapping_finds_boxes_that_only_touch() {
+        let mut regions = ProtectedRegions::default();
+        regions.protect("tower", Vec3::ZERO, Vec3::ONE);
+        assert!(regions.overlapping(Vec3::ONE, Vec3::splat(3.0)).is_some());
+        assert!(
+            regions
+                .overlapping(Vec3::splat(1.5), Vec3::splat(3.0))
+                .is_none()
+        );
+    }
+}
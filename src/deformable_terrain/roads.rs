@@ -0,0 +1,287 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, LazyLock};
+
+use bevy::prelude::*;
+use fastnoise2::{SafeNode, generator::GeneratorWrapper};
+
+use crate::{
+    constants::{SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE},
+    deformable_terrain::{
+        chunk_generator::generate_noise_height_grid,
+        driver::ChunkBuffers,
+        lru_cache::LruCache,
+        world_gen::{world_gen_key, world_seed},
+    },
+};
+
+pub const SITE_CELL_SIZE: f32 = 384.0; //world units per site cell, each cell holds at most one structure site
+const SITE_CHANCE: f32 = 0.6;
+const ROAD_GRID_STEP: f32 = 8.0; //resolution of the least cost search, must be an integer
+const ROAD_SEARCH_MARGIN: f32 = 64.0; //how far a road may wander outside the box spanned by its endpoints
+const SLOPE_PENALTY: f32 = 40.0;
+const ROAD_HALF_WIDTH: f32 = 2.5;
+const ROAD_BLEND_WIDTH: f32 = 4.0;
+const ROAD_REACH: f32 = ROAD_HALF_WIDTH + ROAD_BLEND_WIDTH;
+const MAX_ROAD_CUT: f32 = 3.0; //roads only nudge the terrain, they never tunnel through a hill
+const GRADE_SMOOTHING_PASSES: usize = 6;
+const ROAD_CACHE_CAPACITY: usize = 256; //site cells, about 100 km² of roads around wherever the loaders are working
+
+//roads are computed lazily per site cell and shared by every loader thread
+//a cell owns the roads to its +x and +z neighbors so every road is computed by exactly one cell
+//keyed on the generation settings too, a world_gen_compare thread generates another world's roads next to the loaders
+//least recently used cells are dropped past ROAD_CACHE_CAPACITY and computed again if the loaders come back
+static ROAD_CACHE: LazyLock<LruCache<(u64, (i32, i32)), Arc<[Road]>>> =
+    LazyLock::new(|| LruCache::new(ROAD_CACHE_CAPACITY));
+
+pub struct Road {
+    pub points: Vec<Vec3>, //x and z are the world position, y is the graded road height
+    min: Vec2,
+    max: Vec2,
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    cost: f32,
+    index: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        //reverse to make the max heap a min heap
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn hash_cell(cell: (i32, i32), salt: u32) -> u32 {
+    let mut h = (cell.0 as u32).wrapping_mul(0x8da6_b343)
+        ^ (cell.1 as u32).wrapping_mul(0xd816_3841)
+        ^ (world_seed() as u32).wrapping_mul(0xcb1a_b31f)
+        ^ salt.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+fn hash_to_unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+//deterministic structure site for a cell, jittered inside the middle of the cell so neighboring sites never touch
+pub fn structure_site(cell: (i32, i32)) -> Option<Vec2> {
+    if hash_to_unit(hash_cell(cell, 0)) > SITE_CHANCE {
+        return None;
+    }
+    let jitter = Vec2::new(
+        hash_to_unit(hash_cell(cell, 1)) - 0.5,
+        hash_to_unit(hash_cell(cell, 2)) - 0.5,
+    ) * 0.6;
+    Some((Vec2::new(cell.0 as f32, cell.1 as f32) + 0.5 + jitter) * SITE_CELL_SIZE)
+}
+
+pub fn roads_for_cell(cell: (i32, i32), fbm: &GeneratorWrapper<SafeNode>) -> Arc<[Road]> {
+    let key = (world_gen_key(), cell);
+    if let Some(roads) = ROAD_CACHE.get(&key) {
+        return roads;
+    }
+    let mut roads = Vec::new();
+    if let Some(start) = structure_site(cell) {
+        for neighbor in [(cell.0 + 1, cell.1), (cell.0, cell.1 + 1)] {
+            if let Some(end) = structure_site(neighbor) {
+                roads.push(find_road(start, end, fbm));
+            }
+        }
+    }
+    ROAD_CACHE.insert(key, Arc::from(roads))
+}
+
+//least cost path over a coarse height grid, steep steps are penalized so roads follow contours
+fn find_road(start: Vec2, end: Vec2, fbm: &GeneratorWrapper<SafeNode>) -> Road {
+    let grid_min = ((start.min(end) - ROAD_SEARCH_MARGIN) / ROAD_GRID_STEP)
+        .floor()
+        .as_ivec2();
+    let grid_max = ((start.max(end) + ROAD_SEARCH_MARGIN) / ROAD_GRID_STEP)
+        .ceil()
+        .as_ivec2();
+    let width = (grid_max.x - grid_min.x + 1) as usize;
+    let depth = (grid_max.y - grid_min.y + 1) as usize;
+    let heights =
+        generate_noise_height_grid(grid_min.x, grid_min.y, width, depth, ROAD_GRID_STEP, fbm);
+    let to_index = |p: Vec2| {
+        let g = (p / ROAD_GRID_STEP).round().as_ivec2() - grid_min;
+        g.y as usize * width + g.x as usize
+    };
+    let to_world = |index: usize| {
+        Vec2::new(
+            (grid_min.x + (index % width) as i32) as f32,
+            (grid_min.y + (index / width) as i32) as f32,
+        ) * ROAD_GRID_STEP
+    };
+    let start_index = to_index(start);
+    let end_index = to_index(end);
+    let end_world = to_world(end_index);
+    let mut best_cost = vec![f32::INFINITY; width * depth];
+    let mut came_from = vec![usize::MAX; width * depth];
+    let mut open = BinaryHeap::new();
+    best_cost[start_index] = 0.0;
+    open.push(OpenNode {
+        cost: 0.0,
+        index: start_index,
+    });
+    while let Some(OpenNode { index, .. }) = open.pop() {
+        if index == end_index {
+            break;
+        }
+        let x = (index % width) as i32;
+        let z = (index / width) as i32;
+        for (dx, dz) in [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ] {
+            let nx = x + dx;
+            let nz = z + dz;
+            if nx < 0 || nz < 0 || nx >= width as i32 || nz >= depth as i32 {
+                continue;
+            }
+            let neighbor = nz as usize * width + nx as usize;
+            let run = ROAD_GRID_STEP * ((dx * dx + dz * dz) as f32).sqrt();
+            let grade = (heights[neighbor] - heights[index]) / run;
+            let cost = best_cost[index] + run * (1.0 + SLOPE_PENALTY * grade * grade);
+            if cost < best_cost[neighbor] {
+                best_cost[neighbor] = cost;
+                came_from[neighbor] = index;
+                //straight line distance never overestimates since every step costs at least its run
+                let heuristic = to_world(neighbor).distance(end_world);
+                open.push(OpenNode {
+                    cost: cost + heuristic,
+                    index: neighbor,
+                });
+            }
+        }
+    }
+    let mut path = vec![end_index];
+    while let Some(&last) = path.last()
+        && came_from[last] != usize::MAX
+    {
+        path.push(came_from[last]);
+    }
+    path.reverse();
+    let mut grades: Vec<f32> = path.iter().map(|&i| heights[i]).collect();
+    for _ in 0..GRADE_SMOOTHING_PASSES {
+        let previous = grades.clone();
+        for i in 1..grades.len().saturating_sub(1) {
+            grades[i] = (previous[i - 1] + previous[i] + previous[i + 1]) / 3.0;
+        }
+    }
+    let points: Vec<Vec3> = path
+        .iter()
+        .zip(grades)
+        .map(|(&i, grade)| {
+            let p = to_world(i);
+            Vec3::new(p.x, grade, p.y)
+        })
+        .collect();
+    let (min, max) = points.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.min(p.xz()), max.max(p.xz())),
+    );
+    Road { points, min, max }
+}
+
+//returns how far the terrain at p should move toward the road grade and whether p is on the road surface
+fn road_offset(segments: &[(Vec3, Vec3)], p: Vec2, height: f32) -> (f32, bool) {
+    let mut best_distance = f32::INFINITY;
+    let mut best_grade = 0.0;
+    for (a, b) in segments {
+        let ab = b.xz() - a.xz();
+        let t = ((p - a.xz()).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+        let distance = p.distance(a.xz() + ab * t);
+        if distance < best_distance {
+            best_distance = distance;
+            best_grade = a.y + (b.y - a.y) * t;
+        }
+    }
+    if best_distance >= ROAD_REACH {
+        return (0.0, false);
+    }
+    let weight = if best_distance <= ROAD_HALF_WIDTH {
+        1.0
+    } else {
+        let t = 1.0 - (best_distance - ROAD_HALF_WIDTH) / ROAD_BLEND_WIDTH;
+        t * t * (3.0 - 2.0 * t)
+    };
+    (
+        (best_grade - height).clamp(-MAX_ROAD_CUT, MAX_ROAD_CUT) * weight,
+        best_distance <= ROAD_HALF_WIDTH,
+    )
+}
+
+//flattens the heightmap along any road crossing this chunk column and marks the road surface for the path material
+//everything is a function of world position only, so neighboring chunks agree on their shared padding
+pub fn apply_roads(
+    chunk_start: &Vec3,
+    chunk_buffers: &mut ChunkBuffers,
+    fbm: &GeneratorWrapper<SafeNode>,
+) {
+    chunk_buffers.path_mask.fill(false);
+    let column_min = chunk_start.xz() - VOXEL_WORLD_SIZE;
+    let column_max = column_min + (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32 * VOXEL_WORLD_SIZE;
+    let reach_min = column_min - ROAD_REACH - VOXEL_WORLD_SIZE;
+    let reach_max = column_max + ROAD_REACH + VOXEL_WORLD_SIZE;
+    let cell_min = (column_min / SITE_CELL_SIZE).floor().as_ivec2() - 1;
+    let cell_max = (column_max / SITE_CELL_SIZE).floor().as_ivec2();
+    let mut segments = Vec::new();
+    for cell_x in cell_min.x..=cell_max.x {
+        for cell_z in cell_min.y..=cell_max.y {
+            for road in roads_for_cell((cell_x, cell_z), fbm).iter() {
+                if road.max.cmplt(reach_min).any() || road.min.cmpgt(reach_max).any() {
+                    continue;
+                }
+                for pair in road.points.windows(2) {
+                    let (a, b) = (pair[0].xz(), pair[1].xz());
+                    if a.max(b).cmpge(reach_min).all() && a.min(b).cmple(reach_max).all() {
+                        segments.push((pair[0], pair[1]));
+                    }
+                }
+            }
+        }
+    }
+    if segments.is_empty() {
+        return;
+    }
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            let i = z * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+            let p = column_min + Vec2::new(x as f32, z as f32) * VOXEL_WORLD_SIZE;
+            let h = chunk_buffers.heightmap[i];
+            let gx = chunk_buffers.dhdx[i];
+            let gz = chunk_buffers.dhdz[i];
+            let (offset, on_road) = road_offset(&segments, p, h);
+            let step = VOXEL_WORLD_SIZE;
+            let (offset_px, _) = road_offset(&segments, p + Vec2::X * step, h + gx * step);
+            let (offset_nx, _) = road_offset(&segments, p - Vec2::X * step, h - gx * step);
+            let (offset_pz, _) = road_offset(&segments, p + Vec2::Y * step, h + gz * step);
+            let (offset_nz, _) = road_offset(&segments, p - Vec2::Y * step, h - gz * step);
+            chunk_buffers.heightmap[i] = h + offset;
+            chunk_buffers.dhdx[i] = gx + (offset_px - offset_nx) / (2.0 * step);
+            chunk_buffers.dhdz[i] = gz + (offset_pz - offset_nz) / (2.0 * step);
+            chunk_buffers.path_mask[i] = on_road;
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use rand::RngCore;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::deformable_terrain::world_gen::world_seed;
+
+//one salt per consumer so their streams stay independent, adding a consumer never shifts another one's rolls
+pub const DECORATION_SALT: u64 = 1;
+pub const MOB_SPAWN_SALT: u64 = 2;
+pub const ORE_SALT: u64 = 3;
+
+//splitmix64, the whole algorithm is below so the rolls dont change with the rand version, platform or thread
+//implements RngCore so rand's ranges and distributions can be used on top, those are only as stable as rand itself
+#[derive(Debug, Clone)]
+pub struct ChunkRng {
+    state: u64,
+}
+
+impl ChunkRng {
+    pub fn from_seed(seed: u64) -> Self {
+        ChunkRng { state: seed }
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl RngCore for ChunkRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+//deterministic stream for a chunk, the same coord, salt and world seed always give the same rolls
+pub fn chunk_rng(chunk_coord: ChunkCoord, salt: u64) -> ChunkRng {
+    let mut seeder = ChunkRng::from_seed(world_seed() as u64);
+    let mut seed = seeder.next_u64();
+    for word in [
+        chunk_coord.0 as u16 as u64,
+        chunk_coord.1 as u16 as u64,
+        chunk_coord.2 as u16 as u64,
+        salt,
+    ] {
+        seed = ChunkRng::from_seed(seed ^ word).next_u64();
+    }
+    ChunkRng::from_seed(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_inputs_give_same_stream() {
+        let mut a = chunk_rng(ChunkCoord(3, -2, 7), ORE_SALT);
+        let mut b = chunk_rng(ChunkCoord(3, -2, 7), ORE_SALT);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn neighbors_and_salts_diverge() {
+        let first = chunk_rng(ChunkCoord(0, 0, 0), DECORATION_SALT).next_u64();
+        assert_ne!(
+            first,
+            chunk_rng(ChunkCoord(1, 0, 0), DECORATION_SALT).next_u64()
+        );
+        assert_ne!(
+            first,
+            chunk_rng(ChunkCoord(0, 1, 0), DECORATION_SALT).next_u64()
+        );
+        assert_ne!(
+            first,
+            chunk_rng(ChunkCoord(0, 0, 1), DECORATION_SALT).next_u64()
+        );
+        assert_ne!(
+            first,
+            chunk_rng(ChunkCoord(0, 0, 0), MOB_SPAWN_SALT).next_u64()
+        );
+    }
+
+    //pins the algorithm, if this changes every saved world scatters differently
+    #[test]
+    fn stream_is_stable() {
+        let mut rng = chunk_rng(ChunkCoord(0, 0, 0), DECORATION_SALT);
+        assert_eq!(rng.next_u64(), 0xb6b3_a499_8898_096e);
+        assert_eq!(rng.next_u64(), 0xdc48_9847_7b9e_7593);
+    }
+}
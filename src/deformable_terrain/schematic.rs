@@ -0,0 +1,428 @@
+use std::fs::{create_dir_all, read, write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    constants::{SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED},
+    conversions::{
+        chunk_coord_to_first_padded_sample, flatten_index, sample_to_padded_index,
+        sample_to_world_pos, world_pos_to_sample,
+    },
+    deformable_terrain::{
+        chunk_generator::MaterialCode,
+        chunk_iter::chunks_in_box,
+        digging::{ModifiedChunk, TerrainEditor, copy_chunks_in_box, screen_to_world_ray},
+        driver::TerrainChunkMap,
+        sdf_value::SdfValue,
+        terrain::TerrainChunk,
+    },
+    player::player::MainCameraTag,
+    ui::console::{Console, ConsoleCommand},
+};
+
+const SCHEMATICS_DIR: &str = "data/schematics";
+const SCHEMATIC_EXTENSION: &str = "voxschem";
+const SCHEMATIC_MAGIC: [u8; 4] = *b"VXSC";
+const SCHEMATIC_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 4 + 1 + 3 * 4; //magic, version, size
+const MAX_SCHEMATIC_SAMPLES: u32 = 256; //per axis, about 48 world units
+
+// Binary format layout:
+// - magic "VXSC", version: u8, size: 3 * u32
+// - SDF values: x fastest then y then z, i16 each
+// - Material values: same order, u8 each
+//a box of samples on the global sample grid, copied out of the terrain and stamped back in whole
+//the binary format above is what is saved, serde is for dumping one as ron or json
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schematic {
+    pub size: UVec3,
+    pub densities: Vec<i16>,
+    pub materials: Vec<MaterialCode>,
+}
+
+impl Schematic {
+    fn index(&self, local: UVec3) -> usize {
+        ((local.z * self.size.y + local.y) * self.size.x + local.x) as usize
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.densities.len() * 3);
+        bytes.extend_from_slice(&SCHEMATIC_MAGIC);
+        bytes.push(SCHEMATIC_VERSION);
+        for axis in self.size.to_array() {
+            bytes.extend_from_slice(&axis.to_le_bytes());
+        }
+        for density in &self.densities {
+            bytes.extend_from_slice(&density.to_le_bytes());
+        }
+        bytes.extend(self.materials.iter().map(|material| *material as u8));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_SIZE || bytes[..4] != SCHEMATIC_MAGIC {
+            return Err("not a schematic file".to_string());
+        }
+        if bytes[4] != SCHEMATIC_VERSION {
+            return Err(format!("unsupported schematic version {}", bytes[4]));
+        }
+        let axis = |i: usize| u32::from_le_bytes(bytes[5 + i * 4..9 + i * 4].try_into().unwrap());
+        let size = UVec3::new(axis(0), axis(1), axis(2));
+        if size.cmpeq(UVec3::ZERO).any() || size.max_element() > MAX_SCHEMATIC_SAMPLES {
+            return Err(format!("schematic size {size} is out of range"));
+        }
+        let count = (size.x * size.y * size.z) as usize;
+        if bytes.len() != HEADER_SIZE + count * 3 {
+            return Err("schematic file is truncated".to_string());
+        }
+        let (density_bytes, material_bytes) = bytes[HEADER_SIZE..].split_at(count * 2);
+        let densities = density_bytes
+            .chunks_exact(2)
+            .map(|pair| SdfValue::from_raw(i16::from_le_bytes([pair[0], pair[1]])).0)
+            .collect();
+        let materials = material_bytes
+            .iter()
+            .map(|&byte| MaterialCode::try_from(byte))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("{e} in schematic"))?;
+        Ok(Schematic {
+            size,
+            densities,
+            materials,
+        })
+    }
+
+    //quarter turns about +y, each takes +x onto +z so clockwise seen from above
+    pub fn rotated(&self, quarter_turns: u8) -> Schematic {
+        let mut rotated = self.clone();
+        for _ in 0..quarter_turns % 4 {
+            let source = rotated;
+            rotated = Schematic {
+                size: UVec3::new(source.size.z, source.size.y, source.size.x),
+                densities: source.densities.clone(),
+                materials: source.materials.clone(),
+            };
+            for z in 0..source.size.z {
+                for y in 0..source.size.y {
+                    for x in 0..source.size.x {
+                        let from = source.index(UVec3::new(x, y, z));
+                        let to = rotated.index(UVec3::new(source.size.z - 1 - z, y, x));
+                        rotated.densities[to] = source.densities[from];
+                        rotated.materials[to] = source.materials[from];
+                    }
+                }
+            }
+        }
+        rotated
+    }
+}
+
+//the samples between two corners, both included, every chunk they touch has to be loaded
+fn copy_selection(
+    min: IVec3,
+    max: IVec3,
+    terrain_chunk_map: &TerrainChunkMap,
+) -> Result<Schematic, String> {
+    let size = (max - min + IVec3::ONE).as_uvec3();
+    if size.max_element() > MAX_SCHEMATIC_SAMPLES {
+        return Err(format!(
+            "the selection is {size} samples, at most {MAX_SCHEMATIC_SAMPLES} per axis fit"
+        ));
+    }
+    let mut chunks = FxHashMap::default();
+    let mut schematic = Schematic {
+        size,
+        densities: Vec::with_capacity((size.x * size.y * size.z) as usize),
+        materials: Vec::with_capacity((size.x * size.y * size.z) as usize),
+    };
+    for z in min.z..=max.z {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let (chunk_coord, padded) = sample_to_padded_index(IVec3::new(x, y, z));
+                if !chunks.contains_key(&chunk_coord) {
+                    let Some(chunk) = terrain_chunk_map.0.get(&chunk_coord) else {
+                        return Err(format!("chunk {chunk_coord} is not loaded"));
+                    };
+                    chunks.insert(chunk_coord, chunk);
+                }
+                let (density, material) = match &chunks[&chunk_coord] {
+                    TerrainChunk::UniformAir => (SdfValue::AIR.0, MaterialCode::Air),
+                    TerrainChunk::UniformDirt => (SdfValue::SOLID.0, MaterialCode::Dirt),
+                    TerrainChunk::NonUniformTerrainChunk(chunk) => (
+                        chunk.densities[flatten_index(
+                            padded.x,
+                            padded.y,
+                            padded.z,
+                            SAMPLES_PER_CHUNK_DIM_PADDED,
+                        ) as usize],
+                        chunk.materials[flatten_index(
+                            padded.x - 1,
+                            padded.y - 1,
+                            padded.z - 1,
+                            SAMPLES_PER_CHUNK_DIM,
+                        ) as usize],
+                    ),
+                };
+                schematic.densities.push(density);
+                schematic.materials.push(material);
+            }
+        }
+    }
+    Ok(schematic)
+}
+
+//replaces every sample in the box at origin with the schematic's, padding included so chunk faces stay seamless
+//the chunks the box touches have to be loaded so a stamp is never cut off at the edge of what is loaded
+fn stamp_schematic(
+    schematic: &Schematic,
+    origin: IVec3,
+    terrain_chunk_map: &TerrainChunkMap,
+) -> Result<Vec<ModifiedChunk>, String> {
+    let max = origin + schematic.size.as_ivec3() - IVec3::ONE;
+    let (min_world, max_world) = (sample_to_world_pos(origin), sample_to_world_pos(max));
+    let (min_chunk, _) = sample_to_padded_index(origin - IVec3::ONE);
+    let (max_chunk, _) = sample_to_padded_index(max + IVec3::ONE);
+    if let Some(chunk_coord) = chunks_in_box(min_chunk, max_chunk)
+        .find(|chunk_coord| !terrain_chunk_map.0.contains_key(chunk_coord))
+    {
+        return Err(format!("chunk {chunk_coord} is not loaded"));
+    }
+    let mut modified_chunks =
+        copy_chunks_in_box(min_world, max_world, terrain_chunk_map, |_, _| true);
+    modified_chunks.retain_mut(|(chunk_coord, densities, materials, _, _)| {
+        let first_sample = chunk_coord_to_first_padded_sample(chunk_coord);
+        let mut modified = false;
+        for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED as u32 {
+            for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED as u32 {
+                for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED as u32 {
+                    let local = first_sample + IVec3::new(x as i32, y as i32, z as i32) - origin;
+                    if local.cmplt(IVec3::ZERO).any()
+                        || local.cmpge(schematic.size.as_ivec3()).any()
+                    {
+                        continue;
+                    }
+                    let from = schematic.index(local.as_uvec3());
+                    let density_index =
+                        flatten_index(x, y, z, SAMPLES_PER_CHUNK_DIM_PADDED) as usize;
+                    if densities[density_index] != schematic.densities[from] {
+                        Arc::make_mut(densities)[density_index] = schematic.densities[from];
+                        modified = true;
+                    }
+                    let interior = 1..=SAMPLES_PER_CHUNK_DIM as u32;
+                    if !(interior.contains(&x) && interior.contains(&y) && interior.contains(&z)) {
+                        continue; //materials have no padding
+                    }
+                    let material_index =
+                        flatten_index(x - 1, y - 1, z - 1, SAMPLES_PER_CHUNK_DIM) as usize;
+                    if materials[material_index] != schematic.materials[from] {
+                        Arc::make_mut(materials)[material_index] = schematic.materials[from];
+                        modified = true;
+                    }
+                }
+            }
+        }
+        modified
+    });
+    Ok(modified_chunks)
+}
+
+fn schematic_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("schematic names are letters, digits, - and _".to_string());
+    }
+    Ok(PathBuf::from(SCHEMATICS_DIR).join(format!("{name}.{SCHEMATIC_EXTENSION}")))
+}
+
+//`schem pos1`, `schem pos2` mark the corners of the selection at the crosshair
+//`schem copy <name>` saves the selection to data/schematics, `schem paste <name> [quarter turns]` stamps it with
+//its min corner at the crosshair
+pub fn schematic_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
+    window: Query<&Window>,
+    mut selection: Local<[Option<IVec3>; 2]>,
+    mut terrain_editor: TerrainEditor,
+) {
+    for command in command_reader.read() {
+        if command.name != "schem" {
+            continue;
+        }
+        let target = window
+            .iter()
+            .next()
+            .and_then(|window| window.cursor_position())
+            .zip(camera.iter().next())
+            .and_then(|(cursor_pos, (camera, camera_transform))| {
+                screen_to_world_ray(
+                    cursor_pos,
+                    camera,
+                    camera_transform,
+                    &terrain_editor.terrain_io.terrain_chunk_map,
+                    &terrain_editor.terrain_io.terrain_svo,
+                )
+            })
+            .map(world_pos_to_sample);
+        let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            [corner @ ("pos1" | "pos2")] => {
+                let Some(target) = target else {
+                    console.print("no terrain under the crosshair");
+                    continue;
+                };
+                selection[(*corner == "pos2") as usize] = Some(target);
+                console.print(format!("{corner} set to sample {target}"));
+            }
+            ["copy", name] => {
+                let [Some(a), Some(b)] = *selection else {
+                    console.print("mark both corners with schem pos1 and schem pos2 first");
+                    continue;
+                };
+                let result = schematic_path(name).and_then(|path| {
+                    let schematic = copy_selection(
+                        a.min(b),
+                        a.max(b),
+                        &terrain_editor.terrain_io.terrain_chunk_map,
+                    )?;
+                    create_dir_all(SCHEMATICS_DIR).map_err(|e| e.to_string())?;
+                    write(&path, schematic.to_bytes()).map_err(|e| e.to_string())?;
+                    Ok(format!(
+                        "saved {} samples to {}",
+                        schematic.size,
+                        path.display()
+                    ))
+                });
+                console.print(result.unwrap_or_else(|e| format!("copy failed: {e}")));
+            }
+            ["paste", name, rest @ ..] => {
+                let quarter_turns = match rest {
+                    [] => 0,
+                    [turns] => match turns.parse::<u8>() {
+                        Ok(turns) if turns < 4 => turns,
+                        _ => {
+                            console.print("quarter turns are 0-3");
+                            continue;
+                        }
+                    },
+                    _ => {
+                        console.print("usage: schem paste <name> [quarter turns 0-3]");
+                        continue;
+                    }
+                };
+                let Some(target) = target else {
+                    console.print("no terrain under the crosshair");
+                    continue;
+                };
+                let result = schematic_path(name).and_then(|path| {
+                    let bytes = read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+                    let schematic = Schematic::from_bytes(&bytes)?.rotated(quarter_turns);
+                    //a paste is all or nothing, cutting it off at a protected face would leave half a build
+                    let max = target + schematic.size.as_ivec3() - IVec3::ONE;
+                    let (min_world, max_world) =
+                        (sample_to_world_pos(target), sample_to_world_pos(max));
+                    if terrain_editor.reject_protected(
+                        (min_world + max_world) / 2.0,
+                        min_world,
+                        max_world,
+                    ) {
+                        return Err("it overlaps a protected region".to_string());
+                    }
+                    stamp_schematic(
+                        &schematic,
+                        target,
+                        &terrain_editor.terrain_io.terrain_chunk_map,
+                    )
+                });
+                match result {
+                    Ok(modified_chunks) => {
+                        let count = modified_chunks.len();
+                        terrain_editor.apply(modified_chunks);
+                        console.print(format!("stamped {name} into {count} chunks"));
+                    }
+                    Err(e) => console.print(format!("paste failed: {e}")),
+                }
+            }
+            _ => console
+                .print("usage: schem pos1 | pos2 | copy <name> | paste <name> [quarter turns 0-3]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_coord::ChunkCoord;
+
+    fn numbered(size: UVec3) -> Schematic {
+        let count = (size.x * size.y * size.z) as usize;
+        Schematic {
+            size,
+            densities: (0..count as i16).collect(),
+            materials: (0..count)
+                .map(|i| {
+                    if i % 2 == 0 {
+                        MaterialCode::Grass
+                    } else {
+                        MaterialCode::Air
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn schematics_survive_a_round_trip_through_bytes() {
+        let schematic = numbered(UVec3::new(3, 2, 4));
+        assert_eq!(Schematic::from_bytes(&schematic.to_bytes()), Ok(schematic));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unknown_material_bytes_are_rejected() {
+        let mut bytes = numbered(UVec3::new(2, 2, 2)).to_bytes();
+        *bytes.last_mut().unwrap() = 200;
+        assert_eq!(
+            Schematic::from_bytes(&bytes),
+            Err("unknown material 200 in schematic".to_string())
+        );
+    }
+
+    #[test]
+    fn schematics_survive_a_round_trip_through_json() {
+        let schematic = numbered(UVec3::new(2, 3, 2));
+        let json = serde_json::to_string(&schematic).unwrap();
+        assert_eq!(serde_json::from_str::<Schematic>(&json).unwrap(), schematic);
+    }
+
+    #[test]
+    fn a_quarter_turn_moves_the_x_axis_onto_z() {
+        let schematic = numbered(UVec3::new(3, 1, 2));
+        let rotated = schematic.rotated(1);
+        assert_eq!(rotated.size, UVec3::new(2, 1, 3));
+        //the sample at x 2 on the first row ends up at z 2 on the last column
+        let from = schematic.index(UVec3::new(2, 0, 0));
+        let to = rotated.index(UVec3::new(1, 0, 2));
+        assert_eq!(rotated.densities[to], schematic.densities[from]);
+        assert_eq!(schematic.rotated(4), schematic);
+        assert_eq!(schematic.rotated(1).rotated(3), schematic);
+    }
+
+    #[test]
+    fn neighboring_chunks_share_their_border_samples() {
+        let (chunk_coord, padded) = sample_to_padded_index(IVec3::new(63, 0, -1));
+        assert_eq!(chunk_coord, ChunkCoord(1, 0, -1));
+        assert_eq!(padded, UVec3::new(1, 1, 63));
+        let first = chunk_coord_to_first_padded_sample(&chunk_coord);
+        assert_eq!(first + padded.as_ivec3(), IVec3::new(63, 0, -1));
+        let world = sample_to_world_pos(IVec3::new(5, -7, 130));
+        assert_eq!(world_pos_to_sample(world), IVec3::new(5, -7, 130));
+    }
+}
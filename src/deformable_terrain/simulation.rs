@@ -0,0 +1,202 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::{
+    ecs::system::RunSystemOnce,
+    prelude::*,
+    render::{RenderPlugin, settings::WgpuSettings},
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::SIMULATION_RADIUS,
+    deformable_terrain::{
+        digging::TerrainEditor,
+        driver::{InitialLoadProgress, RemeshQueue, TerrainChunkMap, WriteCmd, WriteCmdSender},
+        fine_zones::FineZones,
+        plugin::{DeformableTerrainConfig, DeformableTerrainPlugin, MoveableCenter},
+        storage_paths::StoragePaths,
+        terrain_edit::explode,
+        terrain_query::TerrainQuery,
+    },
+    ui::configurable_settings::ConfigurableSettings,
+};
+
+const SIMULATION_STEP: Duration = Duration::from_micros(16_667); //what Time advances by every update, 60 updates per simulated second
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(120); //wall clock, a step that takes longer than this failed
+const SETTLE_POLL: Duration = Duration::from_millis(1); //between updates while waiting on the worker threads
+
+//one thing the scripted player does, every step waits until the pipeline has caught up with it
+#[derive(Debug, Clone, Copy)]
+pub enum SimulationStep {
+    //moves the terrain center, done once the chunks around it are in the chunk map
+    MoveTo(Vec3),
+    //an explosion without debris, done once its remesh is in place
+    Dig { center: Vec3, radius: f32 },
+    //done once everything sent to the write thread is on disk
+    Flush,
+}
+
+//the whole chunk pipeline, svo manager, loader threads and write thread, without a window or a gpu
+//updated by hand, time moves a fixed step per update and each step waits on what it started instead of a frame count,
+//so with the seed fixed a script ends in the same world every run however the loaders were scheduled
+//where the world is saved belongs to the app, but the render radius, meshing mode and world gen config are still
+//process wide, run one simulation per process at a time
+pub struct HeadlessSimulation {
+    app: App,
+}
+
+impl HeadlessSimulation {
+    //set terrain.storage to a TempDirStorage and terrain.new_world_seed for a world that starts the same every run
+    //returns once the initial load around the origin is done
+    pub fn new(terrain: DeformableTerrainPlugin) -> Self {
+        //the smallest radius that still loads everything the simulation needs
+        DeformableTerrainConfig::set_render_radius(SIMULATION_RADIUS.powi(2).to_bits());
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None, //no render app at all, meshes are built but never uploaded
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<WinitPlugin>(),
+        )
+        .insert_resource(TimeUpdateStrategy::ManualDuration(SIMULATION_STEP))
+        .insert_resource(ConfigurableSettings::default())
+        .add_plugins(terrain);
+        app.finish();
+        app.cleanup();
+        let mut simulation = HeadlessSimulation { app };
+        simulation.settle("the initial load", |world| {
+            world.resource::<InitialLoadProgress>().complete
+        });
+        simulation
+    }
+
+    pub fn run(&mut self, script: &[SimulationStep]) {
+        for step in script {
+            self.step(*step);
+        }
+    }
+
+    pub fn step(&mut self, step: SimulationStep) {
+        match step {
+            SimulationStep::MoveTo(position) => {
+                self.app
+                    .world_mut()
+                    .resource_mut::<MoveableCenter>()
+                    .update(position);
+                self.wait_for_chunks_around(position);
+            }
+            SimulationStep::Dig { center, radius } => {
+                self.wait_for_chunks_around(center);
+                self.app
+                    .world_mut()
+                    .run_system_once(move |mut terrain_editor: TerrainEditor| {
+                        explode(&mut terrain_editor, center, radius, 1.0);
+                    })
+                    .expect("the terrain editor is set up by the terrain plugin");
+                self.settle("a dig to be remeshed", |world| {
+                    world.resource::<RemeshQueue>().is_idle()
+                        && world.resource::<FineZones>().is_idle()
+                });
+            }
+            SimulationStep::Flush => {
+                let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+                let sent = self
+                    .app
+                    .world()
+                    .resource::<WriteCmdSender>()
+                    .0
+                    .send(WriteCmd::Sync {
+                        done: Some(done_tx),
+                    });
+                assert!(
+                    sent.is_ok() && done_rx.recv_timeout(SETTLE_TIMEOUT).is_ok(),
+                    "the write thread did not flush within {SETTLE_TIMEOUT:?}"
+                );
+            }
+        }
+    }
+
+    //one frame
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    //None when the chunk holding world_pos is not loaded
+    pub fn density_at(&mut self, world_pos: Vec3) -> Option<f32> {
+        self.app
+            .world_mut()
+            .run_system_once(move |terrain_query: TerrainQuery| terrain_query.density_at(world_pos))
+            .expect("the terrain query is set up by the terrain plugin")
+    }
+
+    pub fn ground_height_at(&mut self, xz: Vec2) -> Option<f32> {
+        self.app
+            .world_mut()
+            .run_system_once(move |terrain_query: TerrainQuery| terrain_query.ground_height_at(xz))
+            .expect("the terrain query is set up by the terrain plugin")
+    }
+
+    pub fn storage(&self) -> &StoragePaths {
+        self.app.world().resource::<StoragePaths>()
+    }
+
+    //for anything a test needs that the simulation does not wrap
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    //updates until the chunk holding world_pos has been dropped from the chunk map, unloading trails the center moving away
+    pub fn wait_for_unload(&mut self, world_pos: Vec3) {
+        let chunk_coord = ChunkCoord::from_world_pos(world_pos);
+        self.settle("a chunk to unload", move |world| {
+            !world
+                .resource::<TerrainChunkMap>()
+                .0
+                .contains_key(&chunk_coord)
+        });
+    }
+
+    //the chunk holding position and the 26 around it
+    fn wait_for_chunks_around(&mut self, position: Vec3) {
+        let center = ChunkCoord::from_world_pos(position);
+        self.settle("the chunks around the center to load", move |world| {
+            let terrain_chunk_map = world.resource::<TerrainChunkMap>();
+            (-1..=1).all(|x| {
+                (-1..=1).all(|y| {
+                    (-1..=1).all(|z| terrain_chunk_map.0.contains_key(&center.offset(x, y, z)))
+                })
+            })
+        });
+    }
+
+    //updates until done says the pipeline caught up, panics with what it was waiting for past SETTLE_TIMEOUT
+    fn settle(&mut self, waiting_for: &str, mut done: impl FnMut(&World) -> bool) {
+        let started = Instant::now();
+        loop {
+            self.app.update();
+            if done(self.app.world()) {
+                return;
+            }
+            assert!(
+                started.elapsed() < SETTLE_TIMEOUT,
+                "gave up waiting for {waiting_for} after {SETTLE_TIMEOUT:?}"
+            );
+            thread::sleep(SETTLE_POLL);
+        }
+    }
+}
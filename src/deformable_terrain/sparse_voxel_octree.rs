@@ -1,48 +1,67 @@
-use std::sync::atomic::Ordering;
-
-use crate::deformable_terrain::driver::RENDER_RADIUS_SQUARED;
+use crate::deformable_terrain::horizon::{HORIZON_CULL_MIN_DISTANCE_SQUARED, HorizonCuller};
+use crate::deformable_terrain::observers::{TerrainObserver, nearest_distance_squared};
 use crate::{
+    chunk_coord::ChunkCoord,
     constants::{
         CHUNK_WORLD_SIZE, CHUNKS_PER_CLUSTER, CHUNKS_PER_CLUSTER_DIM, CLUSTER_WORLD_LENGTH,
-        REDUCED_LOD_1_RADIUS_SQUARED, REDUCED_LOD_2_RADIUS_SQUARED, REDUCED_LOD_3_RADIUS_SQUARED,
-        REDUCED_LOD_4_RADIUS_SQUARED, REDUCED_LOD_5_RADIUS_SQUARED, SIMULATION_RADIUS_SQUARED,
+        HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, SIMULATION_RADIUS_SQUARED,
+    },
+    conversions::{
+        chunk_coord_to_cluster_coord, chunk_coord_to_world_pos, cluster_coord_to_min_chunk_coord,
+        cluster_coord_to_world_center, cluster_coord_to_world_pos,
+    },
+    deformable_terrain::{
+        driver::{ClusterRequest, LoadState, LoadStateTransition},
+        plugin::StreamingConfig,
     },
-    conversions::{cluster_coord_to_world_center, cluster_coord_to_world_pos},
-    deformable_terrain::driver::{ClusterRequest, LoadState, LoadStateTransition},
 };
 use bevy::prelude::*;
 use rustc_hash::FxHashSet;
 
-const MAX_WORLD_SIZE: i16 = 512; //in chunks
+const LOD_HYSTERESIS: f32 = 6.0; //world units a cluster center has to pass a lod radius by before a loaded cluster changes lod
+
+const NO_NODE: u32 = u32::MAX; //empty child slot
+const ROOT: u32 = 0;
+
+//a cluster the svo was asked to store past the world edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvoError {
+    OutOfBounds((i16, i16, i16)),
+}
+
+//every node of the tree in one vec, children are indices into it so a pass walks one allocation instead of a box per level
+//nodes dropped by delete go on a free list and are handed out again before the vec grows
+#[derive(Debug)]
+pub struct Svo {
+    nodes: Vec<SvoNode>,
+    free: Vec<u32>,
+}
 
 #[derive(Debug)]
 pub struct SvoNode {
     pub lower_cluster_coord: (i16, i16, i16), // cluster coord of lower corner (RENAMED)
     pub size: i16,                            // region size in clusters (power of 2)
-    pub children: Option<Box<[Option<SvoNode>; 8]>>,
+    pub children: [u32; 8], //indices into the arena, NO_NODE where there is no child
     pub chunk: Option<([bool; CHUNKS_PER_CLUSTER], LoadState)>,
     pub node_min: Vec3,
     pub node_max: Vec3,
+    pub edited_tick: u32, //newest edit anywhere below, 0 when nothing below was edited
+    pub chunk_edit_ticks: Option<Box<[u32; CHUNKS_PER_CLUSTER]>>, //leaves only, allocated on the first edit
 }
 
 impl SvoNode {
-    pub fn world_root() -> Self {
-        SvoNode::new(
-            (-MAX_WORLD_SIZE, -MAX_WORLD_SIZE, -MAX_WORLD_SIZE),
-            2 * MAX_WORLD_SIZE,
-        )
-    }
-
     fn new(lower_cluster_coord: (i16, i16, i16), size: i16) -> Self {
         let node_min = cluster_coord_to_world_pos(&lower_cluster_coord);
         let node_max = node_min + Vec3::splat(size as f32 * CLUSTER_WORLD_LENGTH);
         Self {
             lower_cluster_coord,
             size,
-            children: None,
+            children: [NO_NODE; 8],
             chunk: None,
             node_min,
             node_max,
+            edited_tick: 0,
+            chunk_edit_ticks: None,
         }
     }
 
@@ -62,302 +81,688 @@ impl SvoNode {
         index
     }
 
+    fn has_children(&self) -> bool {
+        self.children.iter().any(|child| *child != NO_NODE)
+    }
+
+    fn child_nodes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.children
+            .iter()
+            .copied()
+            .filter(|child| *child != NO_NODE)
+    }
+}
+
+impl Svo {
+    //clusters from -world_radius up to world_radius - 1 on every axis, world_radius is a power of two
+    pub fn world_root(world_radius: i16) -> Self {
+        Svo {
+            nodes: vec![SvoNode::new(
+                (-world_radius, -world_radius, -world_radius),
+                2 * world_radius,
+            )],
+            free: Vec::new(),
+        }
+    }
+
+    //walking down from the root only finds the right slot for clusters the root covers
+    fn contains(&self, cluster_coord: (i16, i16, i16)) -> bool {
+        let root = self.node(ROOT);
+        let lower = root.lower_cluster_coord;
+        let in_range = |coord: i16, lower: i16| coord >= lower && coord - lower < root.size;
+        in_range(cluster_coord.0, lower.0)
+            && in_range(cluster_coord.1, lower.1)
+            && in_range(cluster_coord.2, lower.2)
+    }
+
+    #[inline(always)]
+    fn node(&self, index: u32) -> &SvoNode {
+        &self.nodes[index as usize]
+    }
+
+    #[inline(always)]
+    fn node_mut(&mut self, index: u32) -> &mut SvoNode {
+        &mut self.nodes[index as usize]
+    }
+
+    fn allocate(&mut self, lower_cluster_coord: (i16, i16, i16), size: i16) -> u32 {
+        let node = SvoNode::new(lower_cluster_coord, size);
+        match self.free.pop() {
+            Some(index) => {
+                *self.node_mut(index) = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                (self.nodes.len() - 1) as u32
+            }
+        }
+    }
+
+    //the leaf holding the cluster, None when it was never inserted or was deleted since
+    fn leaf(&self, cluster_coord: (i16, i16, i16)) -> Option<u32> {
+        if !self.contains(cluster_coord) {
+            return None;
+        }
+        let mut index = ROOT;
+        while self.node(index).size > 1 {
+            let node = self.node(index);
+            index = node.children[node.child_index(&cluster_coord)];
+            if index == NO_NODE {
+                return None;
+            }
+        }
+        Some(index)
+    }
+
     pub fn insert(
         &mut self,
         coord: (i16, i16, i16),
         has_entity: [bool; CHUNKS_PER_CLUSTER],
         load_state: LoadState,
-    ) {
-        if self.size == 1 {
-            self.chunk = Some((has_entity, load_state));
-            return;
+    ) -> Result<(), SvoError> {
+        if !self.contains(coord) {
+            return Err(SvoError::OutOfBounds(coord));
         }
-        let index = self.child_index(&coord);
-        if self.children.is_none() {
-            self.children = Some(Box::new([None, None, None, None, None, None, None, None]));
-        }
-        let children = self.children.as_mut().unwrap();
-        if children[index].is_none() {
-            let half = self.size / 2;
-            let child_pos = (
-                self.lower_cluster_coord.0 + if (index & 1) != 0 { half } else { 0 },
-                self.lower_cluster_coord.1 + if (index & 2) != 0 { half } else { 0 },
-                self.lower_cluster_coord.2 + if (index & 4) != 0 { half } else { 0 },
-            );
-            children[index] = Some(SvoNode::new(child_pos, self.size / 2));
+        let mut index = ROOT;
+        while self.node(index).size > 1 {
+            let node = self.node(index);
+            let child_slot = node.child_index(&coord);
+            let mut child = node.children[child_slot];
+            if child == NO_NODE {
+                let half = node.size / 2;
+                let child_pos = child_lower_coord(node.lower_cluster_coord, half, child_slot);
+                child = self.allocate(child_pos, half);
+                self.node_mut(index).children[child_slot] = child;
+            }
+            index = child;
         }
-        children[index]
-            .as_mut()
-            .unwrap()
-            .insert(coord, has_entity, load_state);
+        self.node_mut(index).chunk = Some((has_entity, load_state));
+        Ok(())
     }
 
     pub fn delete(&mut self, coord: (i16, i16, i16)) -> bool {
-        if self.size == 1 {
-            let had_chunk = self.chunk.take().is_some();
+        self.contains(coord) && self.delete_below(ROOT, coord)
+    }
+
+    fn delete_below(&mut self, index: u32, coord: (i16, i16, i16)) -> bool {
+        let node = self.node_mut(index);
+        if node.size == 1 {
+            node.chunk_edit_ticks = None;
+            let had_chunk = node.chunk.take().is_some();
             return had_chunk;
         }
-        let child_index = self.child_index(&coord);
-        if let Some(children) = self.children.as_mut() {
-            if let Some(child) = children[child_index].as_mut() {
-                if child.delete(coord) {
-                    if child.children.is_none() && child.chunk.is_none() {
-                        children[child_index] = None;
-                    }
-                    if children.iter().all(|c| c.is_none()) {
-                        self.children = None;
-                    }
-                    return true;
-                }
-            }
+        let child_slot = node.child_index(&coord);
+        let child = node.children[child_slot];
+        if child == NO_NODE || !self.delete_below(child, coord) {
+            return false;
+        }
+        let child_node = self.node(child);
+        if !child_node.has_children() && child_node.chunk.is_none() {
+            self.node_mut(index).children[child_slot] = NO_NODE;
+            self.free.push(child);
         }
-        false
+        true
     }
 
-    pub fn lod_fill_missing_chunks_in_radius(
-        &mut self,
-        center: &Vec3,
-        radius_squared: f32,
+    //the resolution each face neighbor of a cluster is meshed at or is about to be, -x, +x, -y, +y, -z, +z
+    //stored neighbors keep their state unless they are past the hysteresis, missing ones get what they would be requested at
+    fn lod_neighbor_samples_per_chunk_dim(
+        &self,
+        observers: &[TerrainObserver],
+        cluster_coord: (i16, i16, i16),
+        streaming: &StreamingConfig,
+    ) -> [usize; 6] {
+        std::array::from_fn(|face| {
+            let mut neighbor = [cluster_coord.0, cluster_coord.1, cluster_coord.2];
+            neighbor[face / 2] += if face % 2 == 1 { 1 } else { -1 };
+            let neighbor = (neighbor[0], neighbor[1], neighbor[2]);
+            let distance_squared =
+                nearest_distance_squared(observers, cluster_coord_to_world_center(&neighbor));
+            let state = match self.leaf(neighbor).and_then(|index| self.node(index).chunk) {
+                Some((_, current)) => {
+                    lod_get_desired_state_with_hysteresis(distance_squared, current, streaming)
+                }
+                None => lod_get_desired_state(distance_squared, streaming),
+            };
+            state.samples_per_chunk_dim()
+        })
+    }
+
+    //every observer's region is walked in turn, a cluster gets the lod of the observer nearest to it
+    //so whichever pass reaches it asks for the same state and the passes only add clusters to each other
+    pub fn lod_fill_missing_chunks(
+        &self,
+        observers: &[TerrainObserver],
         chunks_being_loaded: &FxHashSet<(i16, i16, i16)>,
+        visited: &mut ClusterVisitMask,
         request_buffer: &mut Vec<ClusterRequest>,
+        horizon: &mut HorizonCuller,
+        streaming: &StreamingConfig,
     ) {
-        if !sphere_intersects_aabb(center, radius_squared, &self.node_min, &self.node_max) {
-            return;
-        }
-        if self.children.is_none() {
-            if self.size == 1 {
-                if self.chunk.is_none() && !chunks_being_loaded.contains(&self.lower_cluster_coord)
-                {
-                    //chunk did not already exist
-                    let distance_squared = center
-                        .distance_squared(cluster_coord_to_world_center(&self.lower_cluster_coord));
-                    if distance_squared
-                        > f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed))
-                    {
-                        return; //skip chunks where the sphere intersects but chunk center is outside max radius
+        for (observer_index, observer) in observers.iter().enumerate() {
+            let center = &observer.position;
+            let render_bounds = observer.bounds();
+            visited.begin_pass(center, observer.render_radius_squared, chunks_being_loaded);
+            self.visit_clusters_in_radius(
+                ROOT,
+                center,
+                observer.render_radius_squared,
+                &mut |cluster_coord, chunk| {
+                    if !visited.insert(cluster_coord) {
+                        return; //being loaded or already handled this pass
                     }
-                    let load_state_transition = lod_get_load_state_transition(
-                        None,
-                        lod_get_desired_state(distance_squared),
-                    );
-                    request_buffer.push(ClusterRequest {
-                        position: self.lower_cluster_coord,
-                        distance_squared,
-                        load_state_transition,
-                        prev_has_entity: None,
-                        prev_in_simulation_radius: false,
-                    });
-                } else if !chunks_being_loaded.contains(&self.lower_cluster_coord) {
-                    //chunk already existed
-                    let current_load_state = self.chunk.as_ref().unwrap().1;
-                    let distance_squared = center
-                        .distance_squared(cluster_coord_to_world_center(&self.lower_cluster_coord));
-                    let desired_load_state = lod_get_desired_state(distance_squared);
-                    if desired_load_state != current_load_state {
-                        let load_state_transition = lod_get_load_state_transition(
-                            Some(current_load_state),
-                            desired_load_state,
-                        );
-                        let prev_has_entity = self.chunk.as_ref().unwrap().0;
-                        let prev_in_simulation_radius =
-                            current_load_state == LoadState::FullWithCollider;
-                        request_buffer.push(ClusterRequest {
-                            position: self.lower_cluster_coord,
-                            distance_squared,
-                            load_state_transition,
-                            prev_has_entity: Some(prev_has_entity),
-                            prev_in_simulation_radius,
-                        });
+                    let cluster_center = cluster_coord_to_world_center(&cluster_coord);
+                    let distance_squared = nearest_distance_squared(observers, cluster_center);
+                    match chunk {
+                        None => {
+                            //chunk did not already exist
+                            if !render_bounds.contains(center, cluster_center) {
+                                return; //skip chunks where the sphere intersects but chunk center is outside max radius
+                            }
+                            //the horizon is built from the player's view, other observers load what they cover
+                            if observer_index == 0
+                                && center.distance_squared(cluster_center)
+                                    > HORIZON_CULL_MIN_DISTANCE_SQUARED
+                                && horizon.is_hidden(center, cluster_coord)
+                            {
+                                return; //behind nearer terrain, requested again once the observer moves
+                            }
+                            let load_state_transition = lod_get_load_state_transition(
+                                None,
+                                lod_get_desired_state(distance_squared, streaming),
+                            );
+                            request_buffer.push(ClusterRequest {
+                                position: cluster_coord,
+                                distance_squared,
+                                load_state_transition,
+                                prev_has_entity: None,
+                                prev_in_simulation_radius: false,
+                                prev_merged: false,
+                                queued_secs: 0.0,
+                                neighbor_samples_per_chunk_dim: self
+                                    .lod_neighbor_samples_per_chunk_dim(
+                                        observers,
+                                        cluster_coord,
+                                        streaming,
+                                    ),
+                            });
+                        }
+                        Some((prev_has_entity, current_load_state)) => {
+                            //chunk already existed
+                            let desired_load_state = lod_get_desired_state_with_hysteresis(
+                                distance_squared,
+                                current_load_state,
+                                streaming,
+                            );
+                            if desired_load_state != current_load_state {
+                                let load_state_transition = lod_get_load_state_transition(
+                                    Some(current_load_state),
+                                    desired_load_state,
+                                );
+                                request_buffer.push(ClusterRequest {
+                                    position: cluster_coord,
+                                    distance_squared,
+                                    load_state_transition,
+                                    prev_has_entity: Some(prev_has_entity),
+                                    prev_in_simulation_radius: current_load_state
+                                        == LoadState::FullWithCollider,
+                                    prev_merged: current_load_state == LoadState::Merged,
+                                    queued_secs: 0.0,
+                                    neighbor_samples_per_chunk_dim: self
+                                        .lod_neighbor_samples_per_chunk_dim(
+                                            observers,
+                                            cluster_coord,
+                                            streaming,
+                                        ),
+                                });
+                            }
+                        }
                     }
-                }
-                return;
-            } else {
-                self.children = Some(Box::new([None, None, None, None, None, None, None, None]));
-            }
+                },
+            );
         }
-        if let Some(children) = &mut self.children {
-            let half = self.size / 2;
-            for i in 0..8 {
-                let child_pos = (
-                    self.lower_cluster_coord.0 + if (i & 1) != 0 { half } else { 0 },
-                    self.lower_cluster_coord.1 + if (i & 2) != 0 { half } else { 0 },
-                    self.lower_cluster_coord.2 + if (i & 4) != 0 { half } else { 0 },
-                );
-                if children[i].is_none() {
-                    let child_center = cluster_coord_to_world_pos(&child_pos);
-                    let cluster_size_world = CHUNK_WORLD_SIZE * CHUNKS_PER_CLUSTER_DIM as f32;
-                    let half_cluster = cluster_size_world * 0.5;
-                    let child_min = child_center - Vec3::splat(half_cluster);
-                    let child_max = child_min + Vec3::splat(half as f32 * cluster_size_world);
-                    if sphere_intersects_aabb(center, radius_squared, &child_min, &child_max) {
-                        children[i] = Some(SvoNode::new(child_pos, half));
+        dedup_overlapping_requests(observers, request_buffer);
+    }
+
+    pub(crate) fn fill_missing_chunks(
+        &self,
+        observers: &[TerrainObserver],
+        chunks_being_loaded: &FxHashSet<(i16, i16, i16)>,
+        visited: &mut ClusterVisitMask,
+        request_buffer: &mut Vec<ClusterRequest>,
+    ) {
+        for observer in observers {
+            let center = &observer.position;
+            let render_bounds = observer.bounds();
+            visited.begin_pass(center, observer.render_radius_squared, chunks_being_loaded);
+            self.visit_clusters_in_radius(
+                ROOT,
+                center,
+                observer.render_radius_squared,
+                &mut |cluster_coord, chunk| {
+                    if !visited.insert(cluster_coord) {
+                        return; //being loaded or already handled this pass
                     }
-                }
-                if let Some(child) = &mut children[i] {
-                    child.fill_missing_chunks_in_radius(
-                        center,
-                        radius_squared,
-                        chunks_being_loaded,
-                        request_buffer,
-                    );
-                }
-            }
+                    let cluster_center = cluster_coord_to_world_center(&cluster_coord);
+                    let distance_squared = nearest_distance_squared(observers, cluster_center);
+                    match chunk {
+                        None => {
+                            //chunk did not already exist
+                            if !render_bounds.contains(center, cluster_center) {
+                                return; //skip chunks where the sphere intersects but chunk center is outside max radius
+                            }
+                            let load_state_transition = get_load_state_transition(
+                                None,
+                                get_desired_state(distance_squared),
+                            );
+                            request_buffer.push(ClusterRequest {
+                                position: cluster_coord,
+                                distance_squared,
+                                load_state_transition,
+                                prev_has_entity: None,
+                                prev_in_simulation_radius: false,
+                                prev_merged: false,
+                                queued_secs: 0.0,
+                                neighbor_samples_per_chunk_dim: [SAMPLES_PER_CHUNK_DIM; 6],
+                            });
+                        }
+                        Some((prev_has_entity, current_load_state)) => {
+                            //chunk already existed
+                            let desired_load_state = get_desired_state(distance_squared);
+                            if desired_load_state != current_load_state {
+                                let load_state_transition = get_load_state_transition(
+                                    Some(current_load_state),
+                                    desired_load_state,
+                                );
+                                request_buffer.push(ClusterRequest {
+                                    position: cluster_coord,
+                                    distance_squared,
+                                    load_state_transition,
+                                    prev_has_entity: Some(prev_has_entity),
+                                    prev_in_simulation_radius: current_load_state
+                                        == LoadState::FullWithCollider,
+                                    prev_merged: current_load_state == LoadState::Merged,
+                                    queued_secs: 0.0,
+                                    neighbor_samples_per_chunk_dim: [SAMPLES_PER_CHUNK_DIM; 6],
+                                });
+                            }
+                        }
+                    }
+                },
+            );
         }
+        dedup_overlapping_requests(observers, request_buffer);
     }
 
-    pub(crate) fn fill_missing_chunks_in_radius(
-        &mut self,
+    //calls visit for every cluster slot whose bounds touch the sphere, with the stored chunk if there is one
+    //regions without nodes are walked virtually so a pass never allocates, nodes only appear on insert
+    fn visit_clusters_in_radius(
+        &self,
+        index: u32,
         center: &Vec3,
         radius_squared: f32,
-        chunks_being_loaded: &FxHashSet<(i16, i16, i16)>,
-        request_buffer: &mut Vec<ClusterRequest>,
+        visit: &mut impl FnMut((i16, i16, i16), Option<([bool; CHUNKS_PER_CLUSTER], LoadState)>),
     ) {
-        if !sphere_intersects_aabb(center, radius_squared, &self.node_min, &self.node_max) {
+        let node = self.node(index);
+        if !sphere_intersects_aabb(center, radius_squared, &node.node_min, &node.node_max) {
             return;
         }
-        if self.children.is_none() {
-            if self.size == 1 {
-                if self.chunk.is_none() && !chunks_being_loaded.contains(&self.lower_cluster_coord)
-                {
-                    //chunk did not already exist
-                    let distance_squared = center
-                        .distance_squared(cluster_coord_to_world_center(&self.lower_cluster_coord));
-                    if distance_squared
-                        > f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed))
-                    {
-                        return; //skip chunks where the sphere intersects but chunk center is outside max radius
-                    }
-                    let load_state_transition =
-                        get_load_state_transition(None, get_desired_state(distance_squared));
-                    request_buffer.push(ClusterRequest {
-                        position: self.lower_cluster_coord,
-                        distance_squared,
-                        load_state_transition,
-                        prev_has_entity: None,
-                        prev_in_simulation_radius: false,
-                    });
-                } else if !chunks_being_loaded.contains(&self.lower_cluster_coord) {
-                    //chunk already existed
-                    let current_load_state = self.chunk.as_ref().unwrap().1;
-                    let distance_squared = center
-                        .distance_squared(cluster_coord_to_world_center(&self.lower_cluster_coord));
-                    let desired_load_state = get_desired_state(distance_squared);
-                    if desired_load_state != current_load_state {
-                        let load_state_transition =
-                            get_load_state_transition(Some(current_load_state), desired_load_state);
-                        let prev_has_entity = self.chunk.as_ref().unwrap().0;
-                        let prev_in_simulation_radius =
-                            current_load_state == LoadState::FullWithCollider;
-                        request_buffer.push(ClusterRequest {
-                            position: self.lower_cluster_coord,
-                            distance_squared,
-                            load_state_transition,
-                            prev_has_entity: Some(prev_has_entity),
-                            prev_in_simulation_radius,
-                        });
-                    }
-                }
-                return;
-            } else {
-                self.children = Some(Box::new([None, None, None, None, None, None, None, None]));
-            }
+        if node.size == 1 {
+            visit(node.lower_cluster_coord, node.chunk);
+            return;
         }
-        if let Some(children) = &mut self.children {
-            let half = self.size / 2;
-            for i in 0..8 {
-                let child_pos = (
-                    self.lower_cluster_coord.0 + if (i & 1) != 0 { half } else { 0 },
-                    self.lower_cluster_coord.1 + if (i & 2) != 0 { half } else { 0 },
-                    self.lower_cluster_coord.2 + if (i & 4) != 0 { half } else { 0 },
+        let half = node.size / 2;
+        for (i, child) in node.children.iter().enumerate() {
+            if *child == NO_NODE {
+                visit_unallocated_region(
+                    child_lower_coord(node.lower_cluster_coord, half, i),
+                    half,
+                    center,
+                    radius_squared,
+                    visit,
                 );
-                if children[i].is_none() {
-                    let child_center = cluster_coord_to_world_pos(&child_pos);
-                    let cluster_size_world = CHUNK_WORLD_SIZE * CHUNKS_PER_CLUSTER_DIM as f32;
-                    let half_cluster = cluster_size_world * 0.5;
-                    let child_min = child_center - Vec3::splat(half_cluster);
-                    let child_max = child_min + Vec3::splat(half as f32 * cluster_size_world);
-                    if sphere_intersects_aabb(center, radius_squared, &child_min, &child_max) {
-                        children[i] = Some(SvoNode::new(child_pos, half));
-                    }
-                }
-                if let Some(child) = &mut children[i] {
-                    child.fill_missing_chunks_in_radius(
-                        center,
-                        radius_squared,
-                        chunks_being_loaded,
-                        request_buffer,
-                    );
-                }
+            } else {
+                self.visit_clusters_in_radius(*child, center, radius_squared, visit);
             }
         }
     }
 
-    /// Query all chunks that are completely outside the given sphere.
-    /// Returns coordinates and entity IDs.
+    /// Query all chunks that are completely outside the deallocation bounds of every observer.
+    /// Returns coordinates, entity IDs and load state.
     /// This may need to change to base on distance instead of intersection
-    pub fn query_chunks_outside_sphere(
+    pub fn query_chunks_outside_observers(
         &self,
-        center: &Vec3,
-        results: &mut Vec<((i16, i16, i16), [bool; CHUNKS_PER_CLUSTER])>,
+        observers: &[TerrainObserver],
+        results: &mut Vec<((i16, i16, i16), [bool; CHUNKS_PER_CLUSTER], LoadState)>,
     ) {
-        // Quick prune: if the node’s nearest point is still beyond MAX_RENDER_RADIUS, skip this node entirely.
-        let node_center_to_sphere = {
-            let mut sq_dist = 0.0;
-            let mut v = center.x;
-            if v < self.node_min.x {
-                sq_dist += (self.node_min.x - v) * (self.node_min.x - v);
-            } else if v > self.node_max.x {
-                sq_dist += (v - self.node_max.x) * (v - self.node_max.x);
-            }
-            v = center.y;
-            if v < self.node_min.y {
-                sq_dist += (self.node_min.y - v) * (self.node_min.y - v);
-            } else if v > self.node_max.y {
-                sq_dist += (v - self.node_max.y) * (v - self.node_max.y);
+        let bounds: Vec<(Vec3, RenderBounds)> = observers
+            .iter()
+            .map(|observer| (observer.position, observer.bounds().deallocation_bounds()))
+            .collect();
+        self.query_below_outside_bounds(ROOT, &bounds, results);
+    }
+
+    fn query_below_outside_bounds(
+        &self,
+        index: u32,
+        bounds: &[(Vec3, RenderBounds)],
+        results: &mut Vec<((i16, i16, i16), [bool; CHUNKS_PER_CLUSTER], LoadState)>,
+    ) {
+        let node = self.node(index);
+        //if this entire node is outside the bounds, collect all chunks inside it
+        if !bounds
+            .iter()
+            .any(|(center, bounds)| bounds.intersects_aabb(center, &node.node_min, &node.node_max))
+        {
+            self.collect_all_chunks(index, results);
+            return;
+        }
+        if node.size == 1 {
+            if let Some((has_entity, load_state)) = &node.chunk {
+                let chunk_center = cluster_coord_to_world_center(&node.lower_cluster_coord);
+                if !bounds
+                    .iter()
+                    .any(|(center, bounds)| bounds.contains(center, chunk_center))
+                {
+                    results.push((node.lower_cluster_coord, *has_entity, *load_state));
+                }
             }
-            v = center.z;
-            if v < self.node_min.z {
-                sq_dist += (self.node_min.z - v) * (self.node_min.z - v);
-            } else if v > self.node_max.z {
-                sq_dist += (v - self.node_max.z) * (v - self.node_max.z);
+            return;
+        }
+        for child in node.child_nodes() {
+            self.query_below_outside_bounds(child, bounds, results);
+        }
+    }
+
+    //first chunk along the ray whose cluster could hold a surface there and how far along the ray it starts
+    //empty space is skipped a node at a time, clusters that are not loaded are passed through
+    //merged clusters keep no flags for their chunks so each of them could
+    pub(crate) fn raycast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<(ChunkCoord, f32)> {
+        let inverse_direction = direction.recip();
+        let root = self.node(ROOT);
+        ray_entry(
+            origin,
+            inverse_direction,
+            root.node_min - HALF_CHUNK,
+            root.node_max - HALF_CHUNK,
+            max_distance,
+        )?;
+        self.raycast_node(ROOT, origin, inverse_direction, max_distance)
+    }
+
+    //callers already know the ray enters this node
+    fn raycast_node(
+        &self,
+        index: u32,
+        origin: Vec3,
+        inverse_direction: Vec3,
+        max_distance: f32,
+    ) -> Option<(ChunkCoord, f32)> {
+        let node = self.node(index);
+        if node.size == 1 {
+            return raycast_cluster(node, origin, inverse_direction, max_distance);
+        }
+        //children are disjoint so the first one entered with a hit has the nearest hit
+        let mut entered = [(0.0, 0); 8];
+        let mut entered_count = 0;
+        for child in node.child_nodes() {
+            let child_node = self.node(child);
+            if let Some(entry) = ray_entry(
+                origin,
+                inverse_direction,
+                child_node.node_min - HALF_CHUNK,
+                child_node.node_max - HALF_CHUNK,
+                max_distance,
+            ) {
+                entered[entered_count] = (entry, child);
+                entered_count += 1;
             }
-            sq_dist
+        }
+        let entered = &mut entered[..entered_count];
+        entered.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        entered.iter().find_map(|(_, child)| {
+            self.raycast_node(*child, origin, inverse_direction, max_distance)
+        })
+    }
+
+    //digs spawn and despawn chunk entities without the loaders, their clusters are kept in step here
+    pub(crate) fn set_has_entity(&mut self, chunk_coord: ChunkCoord, has_entity: bool) {
+        let Some(leaf) = self.leaf(chunk_coord_to_cluster_coord(&chunk_coord)) else {
+            return;
         };
-        //if this entire node is beyond MAX_RENDER_RADIUS, collect all chunks inside it
-        if node_center_to_sphere > f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)) {
-            self.collect_all_chunks(results);
+        if let Some((flags, _)) = self.node_mut(leaf).chunk.as_mut() {
+            flags[index_in_cluster(chunk_coord)] = has_entity;
+        }
+    }
+
+    //digs stamp the chunks they change with the frame they changed in, every node on the way down keeps the newest stamp
+    //ancestors are stamped even when the cluster is not loaded, that only costs a query a wasted descent
+    pub(crate) fn mark_edited(&mut self, chunk_coord: ChunkCoord, tick: u32) {
+        let tick = tick.max(1); //0 means never edited
+        let cluster_coord = chunk_coord_to_cluster_coord(&chunk_coord);
+        if !self.contains(cluster_coord) {
             return;
         }
-        if self.size == 1 {
-            if let Some((has_entity, _)) = &self.chunk {
-                let chunk_center = cluster_coord_to_world_center(&self.lower_cluster_coord);
-                let dist_sq = center.distance_squared(chunk_center);
-                if dist_sq > f32::from_bits(RENDER_RADIUS_SQUARED.load(Ordering::Relaxed)) {
-                    results.push((self.lower_cluster_coord, *has_entity));
-                }
+        let mut index = ROOT;
+        loop {
+            let node = self.node_mut(index);
+            node.edited_tick = node.edited_tick.max(tick);
+            if node.size == 1 {
+                break;
             }
+            index = node.children[node.child_index(&cluster_coord)];
+            if index == NO_NODE {
+                return;
+            }
+        }
+        self.node_mut(index)
+            .chunk_edit_ticks
+            .get_or_insert_with(|| Box::new([0; CHUNKS_PER_CLUSTER]))
+            [index_in_cluster(chunk_coord)] = tick;
+    }
+
+    //chunks edited at or after since in the clusters from min to max inclusive, with the tick of their last edit
+    //subtrees with nothing that new are skipped whole. edits are forgotten with their cluster once it unloads,
+    //the chunk modified times cover what was written before that
+    pub(crate) fn chunks_edited_since(
+        &self,
+        since: u32,
+        min_cluster: (i16, i16, i16),
+        max_cluster: (i16, i16, i16),
+        results: &mut Vec<(ChunkCoord, u32)>,
+    ) {
+        self.edited_below_since(ROOT, since, min_cluster, max_cluster, results);
+    }
+
+    fn edited_below_since(
+        &self,
+        index: u32,
+        since: u32,
+        min_cluster: (i16, i16, i16),
+        max_cluster: (i16, i16, i16),
+        results: &mut Vec<(ChunkCoord, u32)>,
+    ) {
+        let node = self.node(index);
+        let lower = node.lower_cluster_coord;
+        let upper = (
+            lower.0 + node.size - 1,
+            lower.1 + node.size - 1,
+            lower.2 + node.size - 1,
+        );
+        if node.edited_tick == 0
+            || node.edited_tick < since
+            || upper.0 < min_cluster.0
+            || upper.1 < min_cluster.1
+            || upper.2 < min_cluster.2
+            || lower.0 > max_cluster.0
+            || lower.1 > max_cluster.1
+            || lower.2 > max_cluster.2
+        {
             return;
         }
-        if let Some(children) = &self.children {
-            for child in children.iter().filter_map(|c| c.as_ref()) {
-                child.query_chunks_outside_sphere(center, results);
+        if let Some(ticks) = &node.chunk_edit_ticks {
+            let min_chunk = cluster_coord_to_min_chunk_coord(lower);
+            let mut roller = 0;
+            for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                    for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                        let tick = ticks[roller];
+                        if tick != 0 && tick >= since {
+                            results.push((ChunkCoord(chunk_x, chunk_y, chunk_z), tick));
+                        }
+                        roller += 1;
+                    }
+                }
             }
         }
+        for child in node.child_nodes() {
+            self.edited_below_since(child, since, min_cluster, max_cluster, results);
+        }
     }
 
-    fn collect_all_chunks(&self, results: &mut Vec<((i16, i16, i16), [bool; CHUNKS_PER_CLUSTER])>) {
-        if self.size == 1 {
-            if let Some((has_entity, _)) = &self.chunk {
-                results.push((self.lower_cluster_coord, *has_entity));
+    fn collect_all_chunks(
+        &self,
+        index: u32,
+        results: &mut Vec<((i16, i16, i16), [bool; CHUNKS_PER_CLUSTER], LoadState)>,
+    ) {
+        let node = self.node(index);
+        if node.size == 1 {
+            if let Some((has_entity, load_state)) = &node.chunk {
+                results.push((node.lower_cluster_coord, *has_entity, *load_state));
             }
             return;
         }
-        if let Some(children) = &self.children {
-            for child in children.iter().filter_map(|c| c.as_ref()) {
-                child.collect_all_chunks(results);
+        for child in node.child_nodes() {
+            self.collect_all_chunks(child, results);
+        }
+    }
+}
+
+fn raycast_cluster(
+    leaf: &SvoNode,
+    origin: Vec3,
+    inverse_direction: Vec3,
+    max_distance: f32,
+) -> Option<(ChunkCoord, f32)> {
+    let (has_entity, load_state) = leaf.chunk.as_ref()?;
+    let merged = *load_state == LoadState::Merged;
+    let min_chunk = cluster_coord_to_min_chunk_coord(leaf.lower_cluster_coord);
+    let mut nearest: Option<(ChunkCoord, f32)> = None;
+    let mut roller = 0;
+    for chunk_x in min_chunk.0..min_chunk.0 + CHUNKS_PER_CLUSTER_DIM as i16 {
+        for chunk_z in min_chunk.2..min_chunk.2 + CHUNKS_PER_CLUSTER_DIM as i16 {
+            for chunk_y in min_chunk.1..min_chunk.1 + CHUNKS_PER_CLUSTER_DIM as i16 {
+                if merged || has_entity[roller] {
+                    let chunk_coord = ChunkCoord(chunk_x, chunk_y, chunk_z);
+                    let chunk_min = chunk_coord_to_world_pos(&chunk_coord) - HALF_CHUNK;
+                    if let Some(entry) = ray_entry(
+                        origin,
+                        inverse_direction,
+                        chunk_min,
+                        chunk_min + CHUNK_WORLD_SIZE,
+                        max_distance,
+                    ) && nearest.is_none_or(|(_, nearest_entry)| entry < nearest_entry)
+                    {
+                        nearest = Some((chunk_coord, entry));
+                    }
+                }
+                roller += 1;
             }
         }
     }
+    nearest
+}
+
+//the loaded region, an ellipsoid as wide as the render radius and as tall as the vertical render radius
+//heights are stretched by vertical_scale so every test is the sphere test against the render radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RenderBounds {
+    radius_squared: f32,
+    vertical_scale: f32, //render radius over vertical render radius, at least 1
+}
+
+impl RenderBounds {
+    //a vertical radius of zero, or one past the render radius, leaves the bounds a sphere
+    pub(crate) fn new(radius_squared: f32, vertical_radius_squared: f32) -> Self {
+        let vertical_scale = if vertical_radius_squared > 0.0 {
+            (radius_squared / vertical_radius_squared).sqrt().max(1.0)
+        } else {
+            1.0
+        };
+        RenderBounds {
+            radius_squared,
+            vertical_scale,
+        }
+    }
+
+    //loaded clusters are kept until they are LOD_HYSTERESIS outside the bounds, new ones are requested inside them
+    pub(crate) fn deallocation_bounds(self) -> Self {
+        let radius = self.radius_squared.sqrt();
+        let grown = radius + LOD_HYSTERESIS;
+        let grown_vertical = radius / self.vertical_scale + LOD_HYSTERESIS;
+        RenderBounds::new(grown * grown, grown_vertical * grown_vertical)
+    }
+
+    fn stretch(&self, point: &Vec3) -> Vec3 {
+        Vec3::new(point.x, point.y * self.vertical_scale, point.z)
+    }
+
+    pub(crate) fn contains(&self, center: &Vec3, point: Vec3) -> bool {
+        self.stretch(center).distance_squared(self.stretch(&point)) <= self.radius_squared
+    }
+
+    fn intersects_aabb(&self, center: &Vec3, min: &Vec3, max: &Vec3) -> bool {
+        sphere_intersects_aabb(
+            &self.stretch(center),
+            self.radius_squared,
+            &self.stretch(min),
+            &self.stretch(max),
+        )
+    }
+}
+
+//where the ray enters the box, 0 when it starts inside, None when it misses it within max_distance
+//the direction is passed inverted so the slab test is only multiplies, axes it does not move along come out infinite
+fn ray_entry(
+    origin: Vec3,
+    inverse_direction: Vec3,
+    min: Vec3,
+    max: Vec3,
+    max_distance: f32,
+) -> Option<f32> {
+    let to_min = (min - origin) * inverse_direction;
+    let to_max = (max - origin) * inverse_direction;
+    let entry = to_min.min(to_max).max_element().max(0.0);
+    let exit = to_max.max(to_min).min_element().min(max_distance);
+    (entry <= exit).then_some(entry)
+}
+
+//same order as the has_entity flags, x then z then y
+fn index_in_cluster(chunk_coord: ChunkCoord) -> usize {
+    let dim = CHUNKS_PER_CLUSTER_DIM as i16;
+    let (x, y, z) = (
+        chunk_coord.0.rem_euclid(dim) as usize,
+        chunk_coord.1.rem_euclid(dim) as usize,
+        chunk_coord.2.rem_euclid(dim) as usize,
+    );
+    (x * CHUNKS_PER_CLUSTER_DIM + z) * CHUNKS_PER_CLUSTER_DIM + y
+}
+
+//a cluster inside several observers' regions is pushed by each of their passes, the copies are identical
+fn dedup_overlapping_requests(
+    observers: &[TerrainObserver],
+    request_buffer: &mut Vec<ClusterRequest>,
+) {
+    if observers.len() > 1 {
+        request_buffer.sort_unstable_by_key(|request| request.position);
+        request_buffer.dedup_by_key(|request| request.position);
+    }
 }
 
 pub fn sphere_intersects_aabb(center: &Vec3, radius_squared: f32, min: &Vec3, max: &Vec3) -> bool {
@@ -383,6 +788,101 @@ pub fn sphere_intersects_aabb(center: &Vec3, radius_squared: f32, min: &Vec3, ma
     d <= radius_squared
 }
 
+//every cluster in a region without nodes is missing, so only the bounds need checking on the way down
+fn visit_unallocated_region(
+    lower_cluster_coord: (i16, i16, i16),
+    size: i16,
+    center: &Vec3,
+    radius_squared: f32,
+    visit: &mut impl FnMut((i16, i16, i16), Option<([bool; CHUNKS_PER_CLUSTER], LoadState)>),
+) {
+    let region_min = cluster_coord_to_world_pos(&lower_cluster_coord);
+    let region_max = region_min + Vec3::splat(size as f32 * CLUSTER_WORLD_LENGTH);
+    if !sphere_intersects_aabb(center, radius_squared, &region_min, &region_max) {
+        return;
+    }
+    if size == 1 {
+        visit(lower_cluster_coord, None);
+        return;
+    }
+    let half = size / 2;
+    for i in 0..8 {
+        visit_unallocated_region(
+            child_lower_coord(lower_cluster_coord, half, i),
+            half,
+            center,
+            radius_squared,
+            visit,
+        );
+    }
+}
+
+#[inline(always)]
+fn child_lower_coord(
+    lower_cluster_coord: (i16, i16, i16),
+    half: i16,
+    index: usize,
+) -> (i16, i16, i16) {
+    (
+        lower_cluster_coord.0 + if (index & 1) != 0 { half } else { 0 },
+        lower_cluster_coord.1 + if (index & 2) != 0 { half } else { 0 },
+        lower_cluster_coord.2 + if (index & 4) != 0 { half } else { 0 },
+    )
+}
+
+//one bit per cluster in the cube around the candidate sphere, rebuilt every fill pass
+//starts out with the clusters already being loaded so the walk never touches the hash set
+#[derive(Default)]
+pub struct ClusterVisitMask {
+    lower_cluster_coord: (i16, i16, i16),
+    dim: usize,
+    bits: Vec<u64>,
+}
+
+impl ClusterVisitMask {
+    fn begin_pass(
+        &mut self,
+        center: &Vec3,
+        radius_squared: f32,
+        chunks_being_loaded: &FxHashSet<(i16, i16, i16)>,
+    ) {
+        //one cluster of margin, a leaf touches the sphere when any part of it does
+        let radius = radius_squared.sqrt();
+        let lower = ((*center - Vec3::splat(radius)) / CLUSTER_WORLD_LENGTH).floor() - Vec3::ONE;
+        let upper = ((*center + Vec3::splat(radius)) / CLUSTER_WORLD_LENGTH).floor() + Vec3::ONE;
+        self.lower_cluster_coord = (lower.x as i16, lower.y as i16, lower.z as i16);
+        self.dim = (upper.x - lower.x) as usize + 1;
+        self.bits.clear();
+        self.bits.resize(self.dim.pow(3).div_ceil(64), 0);
+        for cluster_coord in chunks_being_loaded {
+            self.insert(*cluster_coord);
+        }
+    }
+
+    #[inline(always)]
+    fn index(&self, cluster_coord: (i16, i16, i16)) -> Option<usize> {
+        let x = (cluster_coord.0 - self.lower_cluster_coord.0) as usize;
+        let y = (cluster_coord.1 - self.lower_cluster_coord.1) as usize;
+        let z = (cluster_coord.2 - self.lower_cluster_coord.2) as usize;
+        if x >= self.dim || y >= self.dim || z >= self.dim {
+            return None;
+        }
+        Some((z * self.dim + y) * self.dim + x)
+    }
+
+    //false when the cluster was already set
+    #[inline(always)]
+    fn insert(&mut self, cluster_coord: (i16, i16, i16)) -> bool {
+        let Some(index) = self.index(cluster_coord) else {
+            return true; //outside the sphere, nothing there gets visited
+        };
+        let (word, bit) = (index / 64, 1u64 << (index % 64));
+        let was_set = self.bits[word] & bit != 0;
+        self.bits[word] |= bit;
+        !was_set
+    }
+}
+
 #[inline(always)]
 fn lod_get_load_state_transition(
     current: Option<LoadState>,
@@ -399,6 +899,7 @@ fn lod_get_load_state_transition(
         (_, LoadState::Lod3) => LoadStateTransition::ToLod3,
         (_, LoadState::Lod4) => LoadStateTransition::ToLod4,
         (_, LoadState::Lod5) => LoadStateTransition::ToLod5,
+        (_, LoadState::Merged) => LoadStateTransition::ToMerged,
     }
 }
 
@@ -418,16 +919,20 @@ fn get_load_state_transition(
 }
 
 #[inline(always)]
-fn lod_get_desired_state(distance_squared: f32) -> LoadState {
-    if distance_squared > REDUCED_LOD_5_RADIUS_SQUARED {
+fn lod_get_desired_state(distance_squared: f32, streaming: &StreamingConfig) -> LoadState {
+    let past = |radius: f32| distance_squared > radius * radius;
+    let [lod_1, lod_2, lod_3, lod_4, lod_5] = streaming.lod_radii;
+    if past(streaming.merged_radius) {
+        LoadState::Merged
+    } else if past(lod_5) {
         LoadState::Lod5
-    } else if distance_squared > REDUCED_LOD_4_RADIUS_SQUARED {
+    } else if past(lod_4) {
         LoadState::Lod4
-    } else if distance_squared > REDUCED_LOD_3_RADIUS_SQUARED {
+    } else if past(lod_3) {
         LoadState::Lod3
-    } else if distance_squared > REDUCED_LOD_2_RADIUS_SQUARED {
+    } else if past(lod_2) {
         LoadState::Lod2
-    } else if distance_squared > REDUCED_LOD_1_RADIUS_SQUARED {
+    } else if past(lod_1) {
         LoadState::Lod1
     } else if distance_squared <= SIMULATION_RADIUS_SQUARED {
         LoadState::FullWithCollider
@@ -437,6 +942,33 @@ fn lod_get_desired_state(distance_squared: f32) -> LoadState {
     }
 }
 
+//a loaded cluster keeps its state while it is within LOD_HYSTERESIS of where that state applies,
+//so strafing along a lod radius does not remesh the clusters on it every pass.
+//the simulation radius has no band, the chunk map and colliders follow it exactly
+fn lod_get_desired_state_with_hysteresis(
+    distance_squared: f32,
+    current: LoadState,
+    streaming: &StreamingConfig,
+) -> LoadState {
+    let desired = lod_get_desired_state(distance_squared, streaming);
+    if desired == current
+        || desired == LoadState::FullWithCollider
+        || current == LoadState::FullWithCollider
+    {
+        return desired;
+    }
+    let distance = distance_squared.sqrt();
+    let nearer = (distance - LOD_HYSTERESIS).max(0.0);
+    let further = distance + LOD_HYSTERESIS;
+    if lod_get_desired_state(nearer * nearer, streaming) == current
+        || lod_get_desired_state(further * further, streaming) == current
+    {
+        current
+    } else {
+        desired
+    }
+}
+
 #[inline(always)]
 fn get_desired_state(distance_squared: f32) -> LoadState {
     if distance_squared <= SIMULATION_RADIUS_SQUARED {
@@ -446,3 +978,237 @@ fn get_desired_state(distance_squared: f32) -> LoadState {
         LoadState::Full
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{DEFAULT_WORLD_RADIUS, REDUCED_LOD_2_RADIUS};
+
+    #[test]
+    fn lods_only_change_once_past_the_band() {
+        let squared = |distance: f32| distance * distance;
+        let streaming = &StreamingConfig::DEFAULT;
+        let just_outside = squared(REDUCED_LOD_2_RADIUS + LOD_HYSTERESIS * 0.5);
+        let well_outside = squared(REDUCED_LOD_2_RADIUS + LOD_HYSTERESIS * 1.5);
+        assert_eq!(
+            lod_get_desired_state(just_outside, streaming),
+            LoadState::Lod2
+        );
+        assert_eq!(
+            lod_get_desired_state_with_hysteresis(just_outside, LoadState::Lod1, streaming),
+            LoadState::Lod1
+        );
+        assert_eq!(
+            lod_get_desired_state_with_hysteresis(well_outside, LoadState::Lod1, streaming),
+            LoadState::Lod2
+        );
+        let just_inside = squared(REDUCED_LOD_2_RADIUS - LOD_HYSTERESIS * 0.5);
+        assert_eq!(
+            lod_get_desired_state_with_hysteresis(just_inside, LoadState::Lod2, streaming),
+            LoadState::Lod2
+        );
+        assert_eq!(
+            lod_get_desired_state_with_hysteresis(just_inside, LoadState::Lod3, streaming),
+            LoadState::Lod1
+        );
+        let inside_simulation = squared(SIMULATION_RADIUS_SQUARED.sqrt() - 1.0);
+        assert_eq!(
+            lod_get_desired_state_with_hysteresis(inside_simulation, LoadState::Lod1, streaming),
+            LoadState::FullWithCollider
+        );
+    }
+
+    #[test]
+    fn lod_radii_follow_the_streaming_config() {
+        let near = StreamingConfig {
+            lod_radii: [10.0, 20.0, 30.0, 40.0, 50.0],
+            merged_radius: 60.0,
+        };
+        let distance_squared = 45.0 * 45.0;
+        assert_eq!(
+            lod_get_desired_state(distance_squared, &near),
+            LoadState::Lod4
+        );
+        assert_eq!(
+            lod_get_desired_state(distance_squared, &StreamingConfig::DEFAULT),
+            lod_get_desired_state(0.0, &StreamingConfig::DEFAULT)
+        );
+    }
+
+    #[test]
+    fn rays_stop_at_the_first_chunk_with_a_surface() {
+        let mut svo = Svo::world_root(DEFAULT_WORLD_RADIUS);
+        let mut has_entity = [false; CHUNKS_PER_CLUSTER];
+        has_entity[index_in_cluster(ChunkCoord(3, 1, 2))] = true;
+        svo.insert((0, 0, 0), has_entity, LoadState::FullWithCollider)
+            .unwrap();
+        svo.insert((2, 0, 0), [false; CHUNKS_PER_CLUSTER], LoadState::Merged)
+            .unwrap();
+        let row = |x: f32| Vec3::new(x, 1.0, 2.0) * CHUNK_WORLD_SIZE;
+        assert_eq!(
+            svo.raycast(row(-10.0), Vec3::X, 1000.0),
+            Some((
+                ChunkCoord(3, 1, 2),
+                10.0 * CHUNK_WORLD_SIZE + 2.5 * CHUNK_WORLD_SIZE
+            ))
+        );
+        //past the flagged chunk the cluster is empty, the merged cluster two over is not
+        assert_eq!(
+            svo.raycast(row(4.0), Vec3::X, 1000.0).map(|hit| hit.0),
+            Some(ChunkCoord(10, 1, 2))
+        );
+        assert_eq!(svo.raycast(row(-10.0), Vec3::X, 100.0), None);
+        assert_eq!(svo.raycast(row(-10.0), Vec3::NEG_X, 1000.0), None);
+        svo.set_has_entity(ChunkCoord(3, 1, 2), false);
+        svo.set_has_entity(ChunkCoord(1, 1, 2), true);
+        assert_eq!(
+            svo.raycast(row(-10.0), Vec3::X, 1000.0).map(|hit| hit.0),
+            Some(ChunkCoord(1, 1, 2))
+        );
+    }
+
+    #[test]
+    fn deleted_nodes_are_reused() {
+        let mut svo = Svo::world_root(DEFAULT_WORLD_RADIUS);
+        svo.insert((3, -2, 9), [false; CHUNKS_PER_CLUSTER], LoadState::Lod1)
+            .unwrap();
+        let allocated = svo.nodes.len();
+        assert!(svo.delete((3, -2, 9)));
+        assert!(!svo.delete((3, -2, 9)));
+        assert!(!svo.node(ROOT).has_children());
+        assert_eq!(svo.free.len(), allocated - 1);
+        svo.insert((-40, 7, 1), [true; CHUNKS_PER_CLUSTER], LoadState::Lod4)
+            .unwrap();
+        assert_eq!(svo.nodes.len(), allocated);
+        let mut all = Vec::new();
+        svo.collect_all_chunks(ROOT, &mut all);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].0, (-40, 7, 1));
+        assert_eq!(all[0].2, LoadState::Lod4);
+    }
+
+    #[test]
+    fn clusters_past_the_world_edge_are_refused() {
+        let mut svo = Svo::world_root(4);
+        let empty = [false; CHUNKS_PER_CLUSTER];
+        assert!(svo.insert((-4, 3, 0), empty, LoadState::Lod1).is_ok());
+        assert_eq!(
+            svo.insert((4, 0, 0), empty, LoadState::Lod1),
+            Err(SvoError::OutOfBounds((4, 0, 0)))
+        );
+        assert_eq!(
+            svo.insert((0, -5, 0), empty, LoadState::Lod1),
+            Err(SvoError::OutOfBounds((0, -5, 0)))
+        );
+        assert!(!svo.delete((0, 0, 9)));
+        svo.mark_edited(ChunkCoord(100, 0, 0), 1);
+        let mut all = Vec::new();
+        svo.collect_all_chunks(ROOT, &mut all);
+        assert_eq!(all.len(), 1);
+    }
+
+    #[test]
+    fn observers_load_and_keep_the_union_of_their_regions() {
+        let mut svo = Svo::world_root(DEFAULT_WORLD_RADIUS);
+        let observer = |x: f32| TerrainObserver {
+            position: Vec3::new(x * CLUSTER_WORLD_LENGTH, 0.0, 0.0),
+            render_radius_squared: (2.0 * CLUSTER_WORLD_LENGTH).powi(2),
+            vertical_render_radius_squared: 0.0,
+        };
+        //the far one twice, overlapping regions ask for each cluster once
+        let observers = [observer(0.0), observer(20.0), observer(20.0)];
+        let mut requests = Vec::new();
+        svo.fill_missing_chunks(
+            &observers,
+            &FxHashSet::default(),
+            &mut ClusterVisitMask::default(),
+            &mut requests,
+        );
+        let mut positions: Vec<_> = requests.iter().map(|request| request.position).collect();
+        positions.dedup();
+        assert_eq!(positions.len(), requests.len());
+        for cluster_coord in [(0, 0, 0), (20, 0, 0)] {
+            let request = requests
+                .iter()
+                .find(|request| request.position == cluster_coord)
+                .unwrap();
+            assert!(matches!(
+                request.load_state_transition,
+                LoadStateTransition::ToFullWithCollider
+            ));
+        }
+        assert!(!positions.contains(&(10, 0, 0)));
+        for request in &requests {
+            svo.insert(
+                request.position,
+                [false; CHUNKS_PER_CLUSTER],
+                LoadState::FullWithCollider,
+            )
+            .unwrap();
+        }
+        let mut outside = Vec::new();
+        svo.query_chunks_outside_observers(&observers, &mut outside);
+        assert!(outside.is_empty());
+        svo.query_chunks_outside_observers(&observers[..1], &mut outside);
+        assert!(outside.iter().any(|cluster| cluster.0 == (20, 0, 0)));
+        assert!(outside.iter().all(|cluster| cluster.0.0 > 10));
+    }
+
+    #[test]
+    fn edits_are_found_by_region_and_tick() {
+        let mut svo = Svo::world_root(DEFAULT_WORLD_RADIUS);
+        svo.insert(
+            (0, 0, 0),
+            [false; CHUNKS_PER_CLUSTER],
+            LoadState::FullWithCollider,
+        )
+        .unwrap();
+        svo.insert((-3, 1, 7), [false; CHUNKS_PER_CLUSTER], LoadState::Lod2)
+            .unwrap();
+        svo.mark_edited(ChunkCoord(2, 3, 4), 10);
+        svo.mark_edited(ChunkCoord(-11, 9, 36), 20);
+        svo.mark_edited(ChunkCoord(2, 3, 4), 30);
+        let edited = |since, min, max| {
+            let mut results = Vec::new();
+            svo.chunks_edited_since(since, min, max, &mut results);
+            results.sort();
+            results
+        };
+        let everywhere = ((-100, -100, -100), (100, 100, 100));
+        assert_eq!(
+            edited(0, everywhere.0, everywhere.1),
+            vec![(ChunkCoord(-11, 9, 36), 20), (ChunkCoord(2, 3, 4), 30)]
+        );
+        assert_eq!(
+            edited(25, everywhere.0, everywhere.1),
+            vec![(ChunkCoord(2, 3, 4), 30)]
+        );
+        assert_eq!(
+            edited(0, (-3, 1, 7), (-3, 1, 7)),
+            vec![(ChunkCoord(-11, 9, 36), 20)]
+        );
+        assert_eq!(edited(31, everywhere.0, everywhere.1), vec![]);
+        svo.delete((0, 0, 0));
+        assert_eq!(edited(25, everywhere.0, everywhere.1), vec![]);
+    }
+
+    #[test]
+    fn vertical_radius_flattens_the_bounds() {
+        let bounds = RenderBounds::new(400.0 * 400.0, 100.0 * 100.0);
+        let center = Vec3::new(10.0, -20.0, 30.0);
+        assert!(bounds.contains(&center, center + Vec3::new(390.0, 0.0, 0.0)));
+        assert!(bounds.contains(&center, center + Vec3::new(0.0, -95.0, 0.0)));
+        assert!(!bounds.contains(&center, center + Vec3::new(0.0, 105.0, 0.0)));
+        assert!(!bounds.intersects_aabb(
+            &center,
+            &(center + Vec3::new(-50.0, 110.0, -50.0)),
+            &(center + Vec3::new(50.0, 200.0, 50.0)),
+        ));
+        let kept = bounds.deallocation_bounds();
+        assert!(kept.contains(&center, center + Vec3::new(0.0, 105.0, 0.0)));
+        assert_eq!(
+            RenderBounds::new(400.0 * 400.0, 0.0),
+            RenderBounds::new(400.0 * 400.0, 900.0 * 900.0)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs::{File, OpenOptions, create_dir_all, remove_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::ecs::resource::Resource;
+use directories::ProjectDirs;
+
+const APPLICATION: &str = "marching_cubes";
+const NAMED_WORLDS_DIR: &str = "worlds";
+const CRASH_DIR: &str = "crash_reports";
+const SHARED_MEMORY_DIR: &str = "/dev/shm"; //a tmpfs on linux, files there never reach the disk
+
+static TEMP_WORLDS: AtomicUsize = AtomicUsize::new(0); //keeps two worlds made by one process apart
+
+//where saved worlds live, the default world is data_dir itself and named ones are in data_dir/worlds/<name>
+//defaults to the platform data dir, ~/.local/share/marching_cubes on linux
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct StoragePaths {
+    pub data_dir: PathBuf,
+    pub world: Option<String>, //None is the default world
+}
+
+impl Default for StoragePaths {
+    fn default() -> Self {
+        //no home dir to put it under, fall back to the working directory
+        let data_dir = ProjectDirs::from("", "", APPLICATION).map_or_else(
+            || PathBuf::from("data"),
+            |dirs| dirs.data_dir().to_path_buf(),
+        );
+        StoragePaths::new(data_dir)
+    }
+}
+
+impl StoragePaths {
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        StoragePaths {
+            data_dir: data_dir.into(),
+            world: None,
+        }
+    }
+
+    pub fn with_world(mut self, world: Option<String>) -> Self {
+        self.world = world;
+        self
+    }
+
+    //everything belonging to the world, chunk files, config and spawn points
+    pub fn world_dir(&self) -> PathBuf {
+        match self.world.as_deref() {
+            Some(name) => self.data_dir.join(NAMED_WORLDS_DIR).join(name),
+            None => self.data_dir.clone(),
+        }
+    }
+
+    //shared by every world
+    pub fn crash_dir(&self) -> PathBuf {
+        self.data_dir.join(CRASH_DIR)
+    }
+
+    //a file directly in the world dir, created if missing
+    pub fn open(&self, name: &str) -> io::Result<File> {
+        StoragePaths::open_file(&self.world_dir().join(name), true)
+    }
+
+    //every world file is opened through here, a writable file is created if missing and a read only one has to exist
+    pub fn open_file(path: &Path, writable: bool) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(writable)
+            .create(writable)
+            .open(path)
+    }
+}
+
+//a world that is gone once this is dropped, for tests and the headless simulation
+//it is a plain dir going through the same file code as a saved world, on a tmpfs where the os has one
+//and in the temp dir otherwise, which can be on disk
+pub struct TempDirStorage(StoragePaths);
+
+impl TempDirStorage {
+    pub fn new(name: &str) -> Self {
+        let root = Path::new(SHARED_MEMORY_DIR);
+        let root = if root.is_dir() {
+            root.to_path_buf()
+        } else {
+            std::env::temp_dir()
+        };
+        let data_dir = root.join(format!(
+            "{APPLICATION}_{name}_{}_{}",
+            std::process::id(),
+            TEMP_WORLDS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = remove_dir_all(&data_dir); //left over from a process with the same id that did not clean up
+        create_dir_all(&data_dir).expect("failed to create temporary world dir");
+        TempDirStorage(StoragePaths::new(data_dir))
+    }
+
+    pub fn paths(&self) -> &StoragePaths {
+        &self.0
+    }
+}
+
+impl Drop for TempDirStorage {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0.data_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_worlds_live_under_the_data_dir() {
+        let paths = StoragePaths::new("saves");
+        assert_eq!(paths.world_dir(), PathBuf::from("saves"));
+        let paths = paths.with_world(Some("island".to_string()));
+        assert_eq!(
+            paths.world_dir(),
+            Path::new("saves").join("worlds").join("island")
+        );
+        assert_eq!(paths.crash_dir(), Path::new("saves").join("crash_reports"));
+    }
+
+    #[test]
+    fn temp_dir_worlds_are_removed_on_drop() {
+        let first = TempDirStorage::new("storage_paths_test");
+        let second = TempDirStorage::new("storage_paths_test");
+        assert_ne!(first.paths(), second.paths());
+        let data_dir = first.paths().data_dir.clone();
+        first.paths().open("air_compression_data.txt").unwrap();
+        assert!(data_dir.join("air_compression_data.txt").exists());
+        drop(first);
+        assert!(!data_dir.exists());
+    }
+}
@@ -0,0 +1,396 @@
+use std::sync::{Arc, LazyLock};
+
+use bevy::prelude::*;
+use fastnoise2::{SafeNode, generator::GeneratorWrapper};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::{
+        CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_2D, SAMPLES_PER_CHUNK_DIM,
+        SAMPLES_PER_CHUNK_DIM_PADDED, VOXEL_WORLD_SIZE,
+    },
+    deformable_terrain::{
+        biomes::{Biome, biome_at},
+        chunk_generator::{MaterialCode, quantize_f32_to_i16, sample_terrain_height},
+        driver::ChunkBuffers,
+        hydrology::has_water_at,
+        lru_cache::LruCache,
+        roads::{SITE_CELL_SIZE, structure_site},
+        scatter::{ChunkRng, DECORATION_SALT, chunk_rng},
+        world_gen::world_gen_key,
+    },
+};
+
+//structures are anchored to the chunk column their base sits in and never reach further than this from it
+//so a chunk only has to look at its own column and the eight around it
+const MAX_STRUCTURE_REACH: f32 = HALF_CHUNK - 0.5;
+const TREE_ATTEMPTS: usize = 3;
+const ROCK_ATTEMPTS: usize = 1;
+const SINK_DEPTH: f32 = 1.5; //bases start below the sampled height, sample_terrain_height is only close to the real surface
+//samples further than this from a structure keep the terrain density, keeps shared padding identical between
+//a chunk that stamps a structure and a neighbor whose bounds only just miss it
+const STAMP_BAND: f32 = VOXEL_WORLD_SIZE * 3.0;
+const STRUCTURE_CACHE_CAPACITY: usize = 4096; //chunk columns, a 64 by 64 column square around wherever the loaders are working
+
+//one entry per chunk column and seed, filled on first use
+//the generation key keeps a world_gen_compare thread's structures apart from the live world's
+//least recently used columns are dropped past STRUCTURE_CACHE_CAPACITY and rolled again if the loaders come back
+static STRUCTURE_CACHE: LazyLock<LruCache<(u64, (i16, i16)), Arc<[Structure]>>> =
+    LazyLock::new(|| LruCache::new(STRUCTURE_CACHE_CAPACITY));
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StructureKind {
+    Tree,
+    Rock,
+    Ruin,
+}
+
+//signed distance primitives in world space, negative inside
+#[derive(Debug, Clone, Copy)]
+pub enum SdfShape {
+    Sphere { center: Vec3, radius: f32 },
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+    Box { center: Vec3, half_extents: Vec3 },
+}
+
+impl SdfShape {
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match *self {
+            SdfShape::Sphere { center, radius } => p.distance(center) - radius,
+            SdfShape::Capsule { a, b, radius } => {
+                let ab = b - a;
+                let t = ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+                p.distance(a + ab * t) - radius
+            }
+            SdfShape::Box {
+                center,
+                half_extents,
+            } => {
+                let q = (p - center).abs() - half_extents;
+                q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+            }
+        }
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        match *self {
+            SdfShape::Sphere { center, radius } => (center - radius, center + radius),
+            SdfShape::Capsule { a, b, radius } => (a.min(b) - radius, a.max(b) + radius),
+            SdfShape::Box {
+                center,
+                half_extents,
+            } => (center - half_extents, center + half_extents),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stamp {
+    pub shape: SdfShape,
+    pub material: MaterialCode,
+}
+
+//union of stamps, later stamps win the material where they overlap
+#[derive(Debug, Clone)]
+pub struct Structure {
+    pub kind: StructureKind,
+    pub stamps: Vec<Stamp>,
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Structure {
+    fn new(kind: StructureKind, stamps: Vec<Stamp>) -> Self {
+        let (mut min, mut max) = (Vec3::MAX, Vec3::MIN);
+        for stamp in &stamps {
+            let (stamp_min, stamp_max) = stamp.shape.bounds();
+            min = min.min(stamp_min);
+            max = max.max(stamp_max);
+        }
+        Structure {
+            kind,
+            stamps,
+            min,
+            max,
+        }
+    }
+
+    pub fn distance(&self, p: Vec3) -> (f32, MaterialCode) {
+        let mut best = (f32::MAX, MaterialCode::Air);
+        for stamp in &self.stamps {
+            let d = stamp.shape.distance(p);
+            if d <= best.0 {
+                best = (d, stamp.material);
+            }
+        }
+        best
+    }
+
+    fn overlaps(&self, min: Vec3, max: Vec3) -> bool {
+        (self.max + STAMP_BAND).cmpge(min).all() && (self.min - STAMP_BAND).cmple(max).all()
+    }
+}
+
+fn tree(base: Vec3, rng: &mut ChunkRng) -> Structure {
+    let height = 4.0 + rng.next_f32() * 3.0;
+    let crown_radius = 2.0 + rng.next_f32();
+    let top = base + Vec3::Y * height;
+    Structure::new(
+        StructureKind::Tree,
+        vec![
+            Stamp {
+                shape: SdfShape::Capsule {
+                    a: base - Vec3::Y * SINK_DEPTH,
+                    b: top,
+                    radius: 0.35 + rng.next_f32() * 0.15,
+                },
+                material: MaterialCode::Dirt,
+            },
+            Stamp {
+                shape: SdfShape::Sphere {
+                    center: top + Vec3::Y * crown_radius * 0.5,
+                    radius: crown_radius,
+                },
+                material: MaterialCode::Grass,
+            },
+        ],
+    )
+}
+
+fn rock(base: Vec3, material: MaterialCode, rng: &mut ChunkRng) -> Structure {
+    let radius = 0.8 + rng.next_f32() * 1.2;
+    Structure::new(
+        StructureKind::Rock,
+        vec![Stamp {
+            shape: SdfShape::Sphere {
+                center: base + Vec3::Y * radius * (rng.next_f32() * 0.4),
+                radius,
+            },
+            material,
+        }],
+    )
+}
+
+//four broken walls around the site, one of them split by a doorway
+fn ruin(base: Vec3, rng: &mut ChunkRng) -> Structure {
+    let half_size = 3.0 + rng.next_f32() * 1.5;
+    let thickness = 0.4;
+    let doorway = (rng.next_u32() % 4) as usize;
+    let mut stamps = Vec::new();
+    for side in 0..4 {
+        let height = 1.0 + rng.next_f32() * 2.5;
+        let (along, across) = if side % 2 == 0 {
+            (Vec3::X, Vec3::Z)
+        } else {
+            (Vec3::Z, Vec3::X)
+        };
+        let sign = if side < 2 { 1.0 } else { -1.0 };
+        let wall_center = base + across * half_size * sign + Vec3::Y * (height - SINK_DEPTH) * 0.5;
+        let half_height = (height + SINK_DEPTH) * 0.5;
+        let wall = |center: Vec3, half_length: f32| Stamp {
+            shape: SdfShape::Box {
+                center,
+                half_extents: along * half_length + across * thickness + Vec3::Y * half_height,
+            },
+            material: MaterialCode::Path,
+        };
+        if side == doorway {
+            let half_length = (half_size - 0.75) * 0.5;
+            let offset = along * (0.75 + half_length);
+            stamps.push(wall(wall_center + offset, half_length));
+            stamps.push(wall(wall_center - offset, half_length));
+        } else {
+            stamps.push(wall(wall_center, half_size + thickness));
+        }
+    }
+    Structure::new(StructureKind::Ruin, stamps)
+}
+
+fn column_of(world_x: f32, world_z: f32) -> (i16, i16) {
+    (
+        ((world_x + HALF_CHUNK) / CHUNK_WORLD_SIZE).floor() as i16,
+        ((world_z + HALF_CHUNK) / CHUNK_WORLD_SIZE).floor() as i16,
+    )
+}
+
+//everything anchored in one chunk column, only a function of the column and the world seed
+pub fn structures_for_column(
+    column: (i16, i16),
+    fbm: &GeneratorWrapper<SafeNode>,
+) -> Arc<[Structure]> {
+    let key = (world_gen_key(), column);
+    if let Some(structures) = STRUCTURE_CACHE.get(&key) {
+        return structures;
+    }
+    let column_min = Vec2::new(column.0 as f32, column.1 as f32) * CHUNK_WORLD_SIZE - HALF_CHUNK;
+    let mut rng = chunk_rng(ChunkCoord(column.0, 0, column.1), DECORATION_SALT);
+    let roll_anchor = |rng: &mut ChunkRng| {
+        let x = column_min.x + rng.next_f32() * CHUNK_WORLD_SIZE;
+        let z = column_min.y + rng.next_f32() * CHUNK_WORLD_SIZE;
+        Vec3::new(x, sample_terrain_height(x, z, fbm), z)
+    };
+    let mut structures = Vec::new();
+    let column_center = column_min + HALF_CHUNK;
+    let cell = (column_center / SITE_CELL_SIZE).floor().as_ivec2();
+    if let Some(site) = structure_site((cell.x, cell.y))
+        && column_of(site.x, site.y) == column
+    {
+        let base = Vec3::new(site.x, sample_terrain_height(site.x, site.y, fbm), site.y);
+        if base.y > 0.0 && !has_water_at(base.x, base.z) {
+            structures.push(ruin(base, &mut rng));
+        }
+    }
+    let biome = biome_at(Vec3::new(column_center.x, 0.0, column_center.y));
+    let (tree_chance, rock_chance) = match biome {
+        Biome::Plains => (0.35, 0.15),
+        Biome::Desert => (0.0, 0.3),
+        Biome::SnowPeaks => (0.15, 0.3),
+    };
+    for _ in 0..TREE_ATTEMPTS {
+        //always consume the rolls so changing a chance doesnt reshuffle everything after it
+        let roll = rng.next_f32();
+        let base = roll_anchor(&mut rng);
+        if roll < tree_chance && base.y > 1.0 && !has_water_at(base.x, base.z) {
+            structures.push(tree(base, &mut rng));
+        }
+    }
+    for _ in 0..ROCK_ATTEMPTS {
+        let roll = rng.next_f32();
+        let base = roll_anchor(&mut rng);
+        if roll < rock_chance && base.y > 0.0 && !has_water_at(base.x, base.z) {
+            let material = if biome == Biome::Desert {
+                MaterialCode::Sand
+            } else {
+                MaterialCode::Dirt
+            };
+            structures.push(rock(base, material, &mut rng));
+        }
+    }
+    debug_assert!(structures.iter().all(|structure| {
+        let anchor_min = column_min - MAX_STRUCTURE_REACH;
+        let anchor_max = column_min + CHUNK_WORLD_SIZE + MAX_STRUCTURE_REACH;
+        structure.min.xz().cmpge(anchor_min).all() && structure.max.xz().cmple(anchor_max).all()
+    }));
+    STRUCTURE_CACHE.insert(key, Arc::from(structures))
+}
+
+//world space box covered by a chunk's padded samples
+fn padded_bounds(chunk_start: &Vec3) -> (Vec3, Vec3) {
+    let min = chunk_start - VOXEL_WORLD_SIZE;
+    (
+        min,
+        min + (SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32 * VOXEL_WORLD_SIZE,
+    )
+}
+
+//structures from this column and its neighbors that overlap the chunk
+//a structure crossing a border is stamped by every chunk it touches when that chunk generates,
+//so nothing ever has to be written back into a neighbor that was already saved
+fn structures_overlapping(
+    chunk_start: &Vec3,
+    fbm: &GeneratorWrapper<SafeNode>,
+) -> Vec<(Arc<[Structure]>, usize)> {
+    let (min, max) = padded_bounds(chunk_start);
+    let center = column_of(chunk_start.x + HALF_CHUNK, chunk_start.z + HALF_CHUNK);
+    let mut overlapping = Vec::new();
+    for column_x in center.0 - 1..=center.0 + 1 {
+        for column_z in center.1 - 1..=center.1 + 1 {
+            let structures = structures_for_column((column_x, column_z), fbm);
+            for i in 0..structures.len() {
+                if structures[i].overlaps(min, max) {
+                    overlapping.push((Arc::clone(&structures), i));
+                }
+            }
+        }
+    }
+    overlapping
+}
+
+//used to promote chunks fast_get_uniformity called air, a tree crown can poke into a chunk the terrain never reaches
+pub fn structures_reach_chunk(chunk_start: &Vec3, fbm: &GeneratorWrapper<SafeNode>) -> bool {
+    !structures_overlapping(chunk_start, fbm).is_empty()
+}
+
+//unions every overlapping structure into the chunk, runs after the caves so caves never cut through them
+pub fn stamp_structures(
+    chunk_buffers: &mut ChunkBuffers,
+    chunk_start: &Vec3,
+    fbm: &GeneratorWrapper<SafeNode>,
+) {
+    let sample_range = |min: f32, max: f32, start: f32| {
+        let first = ((min - start) / VOXEL_WORLD_SIZE).ceil() as i32 + 1;
+        let last = ((max - start) / VOXEL_WORLD_SIZE).floor() as i32 + 1;
+        first.max(0) as usize..=last.min(SAMPLES_PER_CHUNK_DIM_PADDED as i32 - 1) as usize
+    };
+    for (structures, i) in structures_overlapping(chunk_start, fbm) {
+        let structure = &structures[i];
+        let (min, max) = (structure.min - STAMP_BAND, structure.max + STAMP_BAND);
+        for z in sample_range(min.z, max.z, chunk_start.z) {
+            for y in sample_range(min.y, max.y, chunk_start.y) {
+                for x in sample_range(min.x, max.x, chunk_start.x) {
+                    let p = *chunk_start
+                        + (Vec3::new(x as f32, y as f32, z as f32) - 1.0) * VOXEL_WORLD_SIZE;
+                    let (d, material) = structure.distance(p);
+                    if d > STAMP_BAND {
+                        continue;
+                    }
+                    let q = quantize_f32_to_i16(d);
+                    let idx =
+                        (z * SAMPLES_PER_CHUNK_DIM_PADDED + y) * SAMPLES_PER_CHUNK_DIM_PADDED + x;
+                    if q >= chunk_buffers.density[idx] {
+                        continue;
+                    }
+                    chunk_buffers.density[idx] = q;
+                    let interior = [x, y, z]
+                        .iter()
+                        .all(|p| (1..=SAMPLES_PER_CHUNK_DIM).contains(p));
+                    if interior && q < 0 {
+                        let mat_idx =
+                            (z - 1) * SAMPLES_PER_CHUNK_2D + (y - 1) * SAMPLES_PER_CHUNK_DIM;
+                        chunk_buffers.material[mat_idx + x - 1] = material;
+                    }
+                }
+            }
+        }
+    }
+}
+
+//stamp_structures for a far lod generated at out_dim samples per dim, unpadded and spread evenly over the chunk
+pub fn stamp_structures_downsampled(
+    densities_out: &mut [i16],
+    materials_out: &mut [MaterialCode],
+    out_dim: usize,
+    chunk_start: &Vec3,
+    fbm: &GeneratorWrapper<SafeNode>,
+) {
+    let spacing = CHUNK_WORLD_SIZE / (out_dim - 1) as f32;
+    let sample_range = |min: f32, max: f32, start: f32| {
+        let first = ((min - start) / spacing).ceil() as i32;
+        let last = ((max - start) / spacing).floor() as i32;
+        first.max(0) as usize..=last.clamp(0, out_dim as i32 - 1) as usize
+    };
+    for (structures, i) in structures_overlapping(chunk_start, fbm) {
+        let structure = &structures[i];
+        let (min, max) = (structure.min - STAMP_BAND, structure.max + STAMP_BAND);
+        for z in sample_range(min.z, max.z, chunk_start.z) {
+            for y in sample_range(min.y, max.y, chunk_start.y) {
+                for x in sample_range(min.x, max.x, chunk_start.x) {
+                    let p = *chunk_start + Vec3::new(x as f32, y as f32, z as f32) * spacing;
+                    let (d, material) = structure.distance(p);
+                    if d > STAMP_BAND {
+                        continue;
+                    }
+                    let q = quantize_f32_to_i16(d);
+                    let idx = (z * out_dim + y) * out_dim + x;
+                    if q >= densities_out[idx] {
+                        continue;
+                    }
+                    densities_out[idx] = q;
+                    if q < 0 {
+                        materials_out[idx] = material;
+                    }
+                }
+            }
+        }
+    }
+}
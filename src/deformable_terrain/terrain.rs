@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use bevy::{
     asset::RenderAssetUsages,
+    camera::primitives::Aabb,
     image::{ImageLoaderSettings, ImageSampler},
     mesh::{Indices, MeshVertexAttribute, PrimitiveTopology},
     pbr::ExtendedMaterial,
@@ -14,13 +15,16 @@ use crate::{
     constants::SAMPLES_PER_CHUNK_DIM_PADDED,
     conversions::flatten_index,
     deformable_terrain::{
-        chunk_generator::MaterialCode, file_loader::get_project_root,
+        chunk_generator::MaterialCode, plugin::MoveableCenter,
         terrain_material::TerrainMaterialExtension,
     },
 };
 
 pub(crate) const ATTRIBUTE_MATERIAL_ID: MeshVertexAttribute =
     MeshVertexAttribute::new("MaterialId", 988540918, VertexFormat::Uint32);
+//xyz is where the next coarser lod puts the vertex and w the radius it takes over at, see geomorph
+pub(crate) const ATTRIBUTE_COARSE_POSITION: MeshVertexAttribute =
+    MeshVertexAttribute::new("CoarsePosition", 988540919, VertexFormat::Float32x4);
 
 #[derive(Resource)]
 pub struct TerrainMaterialHandle(
@@ -28,11 +32,14 @@ pub struct TerrainMaterialHandle(
 );
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct NonUniformTerrainChunk {
     pub(crate) densities: Arc<[i16]>, //arc, so the write thread can read them
     pub(crate) materials: Arc<[MaterialCode]>,
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum TerrainChunk {
     UniformDirt,
     UniformAir,
@@ -66,24 +73,20 @@ pub(crate) fn setup_map(
     mut materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>>>,
     asset_server: Res<AssetServer>,
 ) {
-    let root = get_project_root();
     let texture_array_handle: Handle<Image> = asset_server
-        .load_with_settings::<Image, ImageLoaderSettings>(
-            root.join("assets/texture_array.ktx2"),
-            |settings| {
-                settings.sampler = ImageSampler::Descriptor(
-                    SamplerDescriptor {
-                        address_mode_u: AddressMode::ClampToEdge,
-                        address_mode_v: AddressMode::ClampToEdge,
-                        address_mode_w: AddressMode::ClampToEdge,
-                        lod_min_clamp: 0.0,
-                        lod_max_clamp: 5.0,
-                        ..Default::default()
-                    }
-                    .into(),
-                );
-            },
-        );
+        .load_with_settings::<Image, ImageLoaderSettings>("texture_array.ktx2", |settings| {
+            settings.sampler = ImageSampler::Descriptor(
+                SamplerDescriptor {
+                    address_mode_u: AddressMode::ClampToEdge,
+                    address_mode_v: AddressMode::ClampToEdge,
+                    address_mode_w: AddressMode::ClampToEdge,
+                    lod_min_clamp: 0.0,
+                    lod_max_clamp: 5.0,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        });
     let standard_terrain_material_handle = materials.add(ExtendedMaterial {
         base: StandardMaterial {
             perceptual_roughness: 0.8,
@@ -92,11 +95,42 @@ pub(crate) fn setup_map(
         extension: TerrainMaterialExtension {
             base_texture: texture_array_handle.clone(),
             scale: 1.5,
+            clip_plane: Vec4::ZERO,
+            morph_center: Vec3::ZERO,
+            dither_fade: 0.0,
         },
     });
     commands.insert_resource(TerrainMaterialHandle(standard_terrain_material_handle));
 }
 
+//lod morphing measures from the same center the driver picks lods by, every chunk shares the material so this is one write
+pub(crate) fn update_morph_center(
+    moveable_center: Res<MoveableCenter>,
+    material_handle: Option<Res<TerrainMaterialHandle>>,
+    mut materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>>>,
+) {
+    let Some(material_handle) = material_handle else {
+        return;
+    };
+    let center = moveable_center.read();
+    let Some(material) = materials.get(&material_handle.0) else {
+        return;
+    };
+    if material.extension.morph_center == center {
+        return; //get_mut would mark the material changed and reupload it every frame
+    }
+    if let Some(material) = materials.get_mut(&material_handle.0) {
+        material.extension.morph_center = center;
+    }
+}
+
+//mesh built on a worker thread with its bounds already computed so the main thread only has to add the asset
+pub(crate) struct PreparedMesh {
+    pub(crate) mesh: Mesh,
+    pub(crate) aabb: Aabb,
+}
+
+//RENDER_WORLD only so the cpu side copy is dropped once uploaded, nothing reads chunk meshes back on the main thread
 pub(crate) fn generate_bevy_mesh(
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
@@ -105,7 +139,7 @@ pub(crate) fn generate_bevy_mesh(
 ) -> Mesh {
     let mut mesh = Mesh::new(
         PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
+        RenderAssetUsages::RENDER_WORLD,
     );
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
@@ -113,3 +147,38 @@ pub(crate) fn generate_bevy_mesh(
     mesh.insert_attribute(ATTRIBUTE_MATERIAL_ID, material_ids);
     mesh
 }
+
+//computes the aabb from the raw vertices before they are moved into the mesh so bevy's calculate_bounds never has to touch it
+pub(crate) fn prepare_bevy_mesh(
+    vertices: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    material_ids: Vec<u32>,
+    indices: Vec<u32>,
+) -> PreparedMesh {
+    let aabb = Aabb::enclosing(&vertices).unwrap_or_default();
+    PreparedMesh {
+        mesh: generate_bevy_mesh(vertices, normals, material_ids, indices),
+        aabb,
+    }
+}
+
+//the shader can put a vertex anywhere between its two positions, so the bounds have to hold both
+pub(crate) fn with_coarse_positions(
+    mut prepared: PreparedMesh,
+    coarse_positions: Vec<[f32; 4]>,
+) -> PreparedMesh {
+    let coarse_points: Vec<Vec3> = coarse_positions
+        .iter()
+        .map(|p| Vec3::new(p[0], p[1], p[2]))
+        .collect();
+    if let Some(coarse_aabb) = Aabb::enclosing(&coarse_points) {
+        prepared.aabb = Aabb::from_min_max(
+            prepared.aabb.min().min(coarse_aabb.min()).into(),
+            prepared.aabb.max().max(coarse_aabb.max()).into(),
+        );
+    }
+    prepared
+        .mesh
+        .insert_attribute(ATTRIBUTE_COARSE_POSITION, coarse_positions);
+    prepared
+}
@@ -0,0 +1,398 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RigidBody, Velocity};
+
+use crate::{
+    constants::{HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, VOXEL_WORLD_SIZE},
+    conversions::{chunk_coord_to_world_pos, flatten_index},
+    deformable_terrain::{
+        chunk_generator::{MATERIAL_COUNT, MaterialCode},
+        digging::{
+            BrushShape, TerrainBrush, TerrainDug, TerrainEditor, adjust_brush, apply_brush,
+            brush_command, copy_chunks_in_box, handle_digging_input, paint_sample,
+            screen_to_world_ray,
+        },
+        driver::{TerrainChunkMap, TerrainSvo},
+        fine_zones::dig_fine_zones,
+        protected_regions::{protect_command, report_rejected_edits},
+        scatter::ChunkRng,
+        schematic::schematic_command,
+        sdf_value::SdfValue,
+    },
+    player::player::{KeyBindings, MainCameraTag, load_key_bindings},
+    ui::{
+        console::{Console, ConsoleCommand, ConsolePlugin},
+        paint_palette::{spawn_paint_palette, swatch_color, update_paint_palette},
+    },
+};
+
+const MAX_EXPLOSION_RADIUS: f32 = 12.0; //world space, bounds how many chunks a single blast copies and remeshes
+const CRATER_ROUGHNESS: f32 = 0.3; //how far the lobes push the crater wall in or out, as a fraction of the radius
+const CRATER_LOBES: usize = 6;
+const RIM_REACH: f32 = 1.6; //the rim ends this many crater radii from the center
+const RIM_HEIGHT: f32 = 0.2; //in crater radii at power 1
+const MAX_DEBRIS: usize = 24; //per explosion, picked evenly from the samples blown out
+const DEBRIS_SIZE: f32 = 0.3; //world space edge length
+const DEBRIS_SPEED: f32 = 6.0; //world units per second at power 1
+const DEBRIS_LIFETIME: f32 = 8.0; // seconds
+
+//blows a crater into the terrain, for gameplay systems that only want to send a message
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TerrainExplosion {
+    pub center: Vec3,
+    pub radius: f32, //world space, clamped to MAX_EXPLOSION_RADIUS
+    pub power: f32,  //scales the rim and how fast debris is thrown, 1 is a plain blast
+    pub debris: bool,
+}
+
+//where the material that was blown out came from, a few samples of it rather than all of it
+pub struct Crater {
+    pub debris: Vec<(Vec3, MaterialCode)>,
+    pub rim_material: MaterialCode, //what most of the blown out samples were made of
+}
+
+//thrown out of a crater, despawned by despawn_debris once it has lived DEBRIS_LIFETIME
+#[derive(Component)]
+pub struct Debris {
+    despawn_at: f32, //elapsed seconds
+}
+
+#[derive(Resource)]
+pub struct DebrisAssets {
+    mesh: Handle<Mesh>,
+    materials: [Option<Handle<StandardMaterial>>; MATERIAL_COUNT], //made the first time a material is thrown
+}
+
+//mouse digging and building with the terrain brush, explosions, schematics, protected regions and their console commands
+//needs DeformableTerrainPlugin, key bindings are loaded here when PlayerPlugin has not already
+pub struct TerrainEditPlugin;
+
+impl Plugin for TerrainEditPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ConsolePlugin>() {
+            app.add_plugins(ConsolePlugin);
+        }
+        if !app.world().contains_resource::<KeyBindings>() {
+            app.insert_resource(load_key_bindings());
+        }
+        app.init_resource::<TerrainBrush>()
+            .add_message::<TerrainExplosion>()
+            .add_systems(Startup, (setup_debris_assets, spawn_paint_palette))
+            .add_systems(
+                Update,
+                (
+                    (adjust_brush, handle_digging_input.after(adjust_brush)),
+                    explode_terrain.before(dig_fine_zones),
+                    despawn_debris,
+                    (update_paint_palette, report_rejected_edits),
+                    (
+                        brush_command,
+                        schematic_command,
+                        explode_command,
+                        protect_command,
+                    ),
+                ),
+            );
+    }
+}
+
+//random lobes on a sphere, the crater wall is pushed out where one points and pulled in where a negative one does
+//seeded from the center so the same blast in the same place always leaves the same crater
+struct CraterShape {
+    lobes: [(Vec3, f32); CRATER_LOBES],
+}
+
+impl CraterShape {
+    fn new(rng: &mut ChunkRng) -> Self {
+        let lobes = std::array::from_fn(|_| {
+            let direction = Vec3::new(
+                rng.next_f32() * 2.0 - 1.0,
+                rng.next_f32() * 2.0 - 1.0,
+                rng.next_f32() * 2.0 - 1.0,
+            )
+            .normalize_or(Vec3::Y);
+            (direction, rng.next_f32() * 2.0 - 1.0)
+        });
+        CraterShape { lobes }
+    }
+
+    fn radius(&self, radius: f32, direction: Vec3) -> f32 {
+        let bulge: f32 = self
+            .lobes
+            .iter()
+            .map(|(lobe, amplitude)| amplitude * direction.dot(*lobe).max(0.0).powi(2))
+            .sum();
+        radius * (1.0 + CRATER_ROUGHNESS * bulge.clamp(-1.0, 1.0))
+    }
+}
+
+//carves a noise perturbed sphere out of the loaded terrain and heaps what it removed around it as a rim
+//the rim takes the material most of the crater was made of, the returned crater says where to throw debris from
+pub fn explode(
+    terrain_editor: &mut TerrainEditor,
+    center: Vec3,
+    radius: f32,
+    power: f32,
+) -> Crater {
+    let radius = radius.clamp(VOXEL_WORLD_SIZE, MAX_EXPLOSION_RADIUS);
+    let bits = center.to_array().map(|c| c.to_bits() as u64);
+    let mut rng = ChunkRng::from_seed(bits[0] ^ bits[1] << 21 ^ bits[2] << 42);
+    let shape = CraterShape::new(&mut rng);
+    let reach = radius * (1.0 + CRATER_ROUGHNESS) * RIM_REACH;
+    terrain_editor.reject_protected(
+        center,
+        center - Vec3::splat(reach),
+        center + Vec3::splat(reach),
+    );
+    let mut modified_chunks = copy_chunks_in_box(
+        center - Vec3::splat(reach),
+        center + Vec3::splat(reach),
+        &terrain_editor.terrain_io.terrain_chunk_map,
+        |node_min, node_max| BrushShape::Sphere.reaches(center, reach, node_min, node_max),
+    );
+    let mut removed_counts: Vec<(MaterialCode, usize)> = Vec::new();
+    let mut debris = Vec::with_capacity(MAX_DEBRIS);
+    let mut removed = 0;
+    let mut carved_chunks = vec![false; modified_chunks.len()];
+    for ((chunk_coord, densities, materials, _, _), carved) in
+        modified_chunks.iter_mut().zip(&mut carved_chunks)
+    {
+        let padded_origin =
+            chunk_coord_to_world_pos(chunk_coord) - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+        *carved = apply_brush(
+            Arc::make_mut(densities),
+            padded_origin,
+            VOXEL_WORLD_SIZE,
+            center,
+            reach,
+            BrushShape::Sphere,
+            &terrain_editor.protected_regions,
+            |padded, world, current, _| {
+                let offset = world - center;
+                let crater_radius = shape.radius(radius, offset.normalize_or(Vec3::Y));
+                let distance = offset.length();
+                if distance >= crater_radius {
+                    return current;
+                }
+                let blasted = SdfValue::from_f32(crater_radius - distance);
+                if blasted.to_f32() <= current.to_f32() {
+                    return current;
+                }
+                if current.is_solid()
+                    && !blasted.is_solid()
+                    && let Some(material) = interior_material(materials, padded)
+                {
+                    match removed_counts.iter_mut().find(|(m, _)| *m == material) {
+                        Some((_, count)) => *count += 1,
+                        None => removed_counts.push((material, 1)),
+                    }
+                    //reservoir sampling, every blown out sample is equally likely to become debris
+                    removed += 1;
+                    if debris.len() < MAX_DEBRIS {
+                        debris.push((world, material));
+                    } else {
+                        let slot = (rng.next_f32() * removed as f32) as usize;
+                        if slot < MAX_DEBRIS {
+                            debris[slot] = (world, material);
+                        }
+                    }
+                }
+                blasted
+            },
+        );
+    }
+    let rim_material = removed_counts
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or(MaterialCode::Dirt, |(material, _)| *material);
+    let rim_height = radius * RIM_HEIGHT * power;
+    let mut chunk_index = 0;
+    modified_chunks.retain_mut(|(chunk_coord, densities, materials, _, _)| {
+        let padded_origin =
+            chunk_coord_to_world_pos(chunk_coord) - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+        let rimmed = apply_brush(
+            Arc::make_mut(densities),
+            padded_origin,
+            VOXEL_WORLD_SIZE,
+            center,
+            reach,
+            BrushShape::Sphere,
+            &terrain_editor.protected_regions,
+            |padded, world, current, _| {
+                let offset = world - center;
+                let crater_radius = shape.radius(radius, offset.normalize_or(Vec3::Y));
+                let rim_radius = crater_radius * RIM_REACH;
+                let distance = offset.length();
+                if distance < crater_radius || distance >= rim_radius {
+                    return current;
+                }
+                //highest at the crater wall and gone by the outer edge
+                let t = (distance - crater_radius) / (rim_radius - crater_radius);
+                let filled = current.saturating_add(-rim_height * (1.0 - t).powi(2));
+                if filled.is_solid() && !current.is_solid() {
+                    paint_sample(materials, padded, rim_material, true);
+                }
+                filled
+            },
+        );
+        let keep = carved_chunks[chunk_index] || rimmed;
+        chunk_index += 1;
+        keep
+    });
+    terrain_editor.apply(modified_chunks);
+    Crater {
+        debris,
+        rim_material,
+    }
+}
+
+//the material of a padded sample, None for padding which belongs to the neighbor
+fn interior_material(materials: &[MaterialCode], padded: UVec3) -> Option<MaterialCode> {
+    let interior = 1..=SAMPLES_PER_CHUNK_DIM as u32;
+    if !(interior.contains(&padded.x)
+        && interior.contains(&padded.y)
+        && interior.contains(&padded.z))
+    {
+        return None;
+    }
+    let index = flatten_index(
+        padded.x - 1,
+        padded.y - 1,
+        padded.z - 1,
+        SAMPLES_PER_CHUNK_DIM,
+    );
+    Some(materials[index as usize]).filter(|material| *material != MaterialCode::Air)
+}
+
+pub fn setup_debris_assets(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(DebrisAssets {
+        mesh: meshes.add(Cuboid::from_length(DEBRIS_SIZE)),
+        materials: Default::default(),
+    });
+}
+
+//fine zones are dug like a plain sphere, they are unrefined once the player leaves so the lobes are not worth it there
+pub fn explode_terrain(
+    mut explosion_reader: MessageReader<TerrainExplosion>,
+    mut terrain_editor: TerrainEditor,
+    mut dug_writer: MessageWriter<TerrainDug>,
+    mut commands: Commands,
+    mut debris_assets: ResMut<DebrisAssets>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    for explosion in explosion_reader.read() {
+        let crater = explode(
+            &mut terrain_editor,
+            explosion.center,
+            explosion.radius,
+            explosion.power,
+        );
+        dug_writer.write(TerrainDug {
+            center: explosion.center,
+            radius: explosion.radius.min(MAX_EXPLOSION_RADIUS),
+            strength: explosion.radius.min(MAX_EXPLOSION_RADIUS),
+            shape: BrushShape::Sphere,
+        });
+        if !explosion.debris {
+            continue;
+        }
+        let despawn_at = time.elapsed_secs() + DEBRIS_LIFETIME;
+        for (position, material) in crater.debris {
+            let material_handle = debris_assets.materials[material as usize]
+                .get_or_insert_with(|| standard_materials.add(swatch_color(material)))
+                .clone();
+            let velocity = ((position - explosion.center).normalize_or(Vec3::Y) + Vec3::Y)
+                * DEBRIS_SPEED
+                * explosion.power;
+            commands.spawn((
+                Debris { despawn_at },
+                Mesh3d(debris_assets.mesh.clone()),
+                MeshMaterial3d(material_handle),
+                Transform::from_translation(position),
+                RigidBody::Dynamic,
+                Collider::cuboid(DEBRIS_SIZE / 2.0, DEBRIS_SIZE / 2.0, DEBRIS_SIZE / 2.0),
+                Velocity::linear(velocity),
+            ));
+        }
+    }
+}
+
+pub fn despawn_debris(
+    mut commands: Commands,
+    debris_query: Query<(Entity, &Debris)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, debris) in &debris_query {
+        if now >= debris.despawn_at {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+//`explode [radius] [power] [nodebris]` blows a crater where the crosshair hits the terrain
+pub fn explode_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
+    window: Query<&Window>,
+    terrain_chunk_map: Res<TerrainChunkMap>,
+    terrain_svo: Res<TerrainSvo>,
+    mut explosion_writer: MessageWriter<TerrainExplosion>,
+) {
+    for command in command_reader.read() {
+        if command.name != "explode" {
+            continue;
+        }
+        let mut explosion = TerrainExplosion {
+            center: Vec3::ZERO,
+            radius: 3.0,
+            power: 1.0,
+            debris: true,
+        };
+        let mut numbers = 0;
+        let mut valid = true;
+        for arg in &command.args {
+            match (arg.as_str(), numbers, arg.parse::<f32>()) {
+                ("nodebris", ..) => explosion.debris = false,
+                (_, 0, Ok(radius)) if radius > 0.0 && radius <= MAX_EXPLOSION_RADIUS => {
+                    explosion.radius = radius;
+                    numbers += 1;
+                }
+                (_, 1, Ok(power)) if power >= 0.0 => {
+                    explosion.power = power;
+                    numbers += 1;
+                }
+                _ => valid = false,
+            }
+        }
+        if !valid {
+            console.print(format!(
+                "usage: explode [radius up to {MAX_EXPLOSION_RADIUS}] [power] [nodebris]"
+            ));
+            continue;
+        }
+        let target = window
+            .iter()
+            .next()
+            .and_then(|window| window.cursor_position())
+            .zip(camera.iter().next())
+            .and_then(|(cursor_pos, (camera, camera_transform))| {
+                screen_to_world_ray(
+                    cursor_pos,
+                    camera,
+                    camera_transform,
+                    &terrain_chunk_map,
+                    &terrain_svo,
+                )
+            });
+        let Some(center) = target else {
+            console.print("no terrain under the crosshair");
+            continue;
+        };
+        explosion.center = center;
+        explosion_writer.write(explosion);
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::chunk_coord::ChunkCoord;
+
+//what can go wrong reading or writing the world or building a chunk's collider
+//the threads that hit these log them and recover, none of them take the thread down
+#[derive(Debug)]
+pub enum TerrainError {
+    Io(io::Error),
+    Open(PathBuf, io::Error),
+    CorruptChunk(&'static str), //a stored chunk whose bytes dont decode, the reason says which part
+    TruncatedRegionHeader(PathBuf),
+    Collider(ChunkCoord), //the chunk's mesh could not be made into a trimesh
+    Migration(String), //converting an old world to region files failed, the reason comes from migrate_world
+    //the world header is from a newer build or another chunk layout
+    RejectedWorld(String),
+}
+
+impl fmt::Display for TerrainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TerrainError::Io(e) => write!(f, "{e}"),
+            TerrainError::Open(path, e) => write!(f, "failed to open {}: {e}", path.display()),
+            TerrainError::CorruptChunk(reason) => write!(f, "corrupt chunk data, {reason}"),
+            TerrainError::TruncatedRegionHeader(path) => {
+                write!(f, "{} is shorter than its header", path.display())
+            }
+            TerrainError::Collider(chunk_coord) => {
+                write!(
+                    f,
+                    "chunk {chunk_coord} mesh could not be made into a collider"
+                )
+            }
+            TerrainError::Migration(reason) => {
+                write!(f, "failed to migrate the world to region files: {reason}")
+            }
+            TerrainError::RejectedWorld(reason) => write!(f, "refusing to load world: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for TerrainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TerrainError::Io(e) | TerrainError::Open(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TerrainError {
+    fn from(e: io::Error) -> Self {
+        TerrainError::Io(e)
+    }
+}
@@ -10,7 +10,9 @@ use bevy::{
     shader::ShaderRef,
 };
 
-use crate::deformable_terrain::{file_loader::get_project_root, terrain::ATTRIBUTE_MATERIAL_ID};
+use crate::deformable_terrain::terrain::{ATTRIBUTE_COARSE_POSITION, ATTRIBUTE_MATERIAL_ID};
+
+const TRIPLANAR_SHADER: &str = "shaders/triplanar.wgsl"; //relative to the asset dir, so it is found wherever the app is installed
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 pub struct TerrainMaterialExtension {
@@ -19,19 +21,21 @@ pub struct TerrainMaterialExtension {
     pub base_texture: Handle<Image>,
     #[uniform(105)]
     pub scale: f32,
+    #[uniform(106)]
+    pub clip_plane: Vec4, //xyz normal and w offset, fragments with dot(normal, pos) > w are dropped, zero disables it
+    #[uniform(107)]
+    pub morph_center: Vec3, //lod morphing distances are measured from here, only read by meshes with coarse positions
+    #[uniform(108)]
+    pub dither_fade: f32, //fraction of a lod fade, positive draws that much of the dither and negative the rest, zero draws everything
 }
 
 impl MaterialExtension for TerrainMaterialExtension {
     fn vertex_shader() -> ShaderRef {
-        let root = get_project_root();
-        let path = root.join("assets/shaders/triplanar.wgsl");
-        ShaderRef::Path(AssetPath::from(path))
+        ShaderRef::Path(AssetPath::from(TRIPLANAR_SHADER))
     }
 
     fn fragment_shader() -> ShaderRef {
-        let root = get_project_root();
-        let path = root.join("assets/shaders/triplanar.wgsl");
-        ShaderRef::Path(AssetPath::from(path))
+        ShaderRef::Path(AssetPath::from(TRIPLANAR_SHADER))
     }
 
     fn specialize(
@@ -40,11 +44,17 @@ impl MaterialExtension for TerrainMaterialExtension {
         layout: &MeshVertexBufferLayoutRef,
         _key: MaterialExtensionKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
-        let vertex_layout = layout.0.get_layout(&[
+        let mut attributes = vec![
             Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
             Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
             ATTRIBUTE_MATERIAL_ID.at_shader_location(2),
-        ])?;
+        ];
+        //meshes built without lod morphing, or rebuilt by digging, keep the plain pipeline
+        if layout.0.contains(ATTRIBUTE_COARSE_POSITION) {
+            attributes.push(ATTRIBUTE_COARSE_POSITION.at_shader_location(3));
+            descriptor.vertex.shader_defs.push("LOD_MORPH".into());
+        }
+        let vertex_layout = layout.0.get_layout(&attributes)?;
         descriptor.vertex.buffers = vec![vertex_layout];
         Ok(())
     }
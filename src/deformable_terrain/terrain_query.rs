@@ -0,0 +1,223 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{
+    CHUNK_WORLD_SIZE, HALF_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    VOXEL_WORLD_SIZE,
+};
+use crate::conversions::{
+    flatten_index, sample_to_padded_index, world_pos_to_chunk_coord, world_pos_to_sample,
+};
+use crate::deformable_terrain::biomes::{Biome, biome_at};
+use crate::deformable_terrain::chunk_generator::{
+    MaterialCode, sample_terrain_height, sample_trilinear_density,
+};
+use crate::deformable_terrain::chunk_summary::{ChunkSummaries, ChunkSummary};
+use crate::deformable_terrain::driver::TerrainChunkMap;
+use crate::deformable_terrain::plugin::NoiseFunction;
+use crate::deformable_terrain::sdf_value::SdfValue;
+use crate::deformable_terrain::terrain::TerrainChunk;
+
+const GROUND_SEARCH_CHUNKS_ABOVE: i16 = 4; //builds and structures can stand this far above the generated surface
+const GROUND_SEARCH_DEPTH: i16 = 32; //in chunks, well past anything the simulation radius keeps loaded
+
+//read only view of terrain data for gameplay systems
+//voxel reads only see chunks in the chunk map, which is everything in the simulation radius
+//each lookup locks the one shard holding its chunk, never the whole map
+#[derive(SystemParam)]
+pub struct TerrainQuery<'w> {
+    chunk_summaries: Res<'w, ChunkSummaries>,
+    terrain_chunk_map: Res<'w, TerrainChunkMap>,
+    noise_function: Res<'w, NoiseFunction>,
+}
+
+impl TerrainQuery<'_> {
+    //uniform chunks and chunks that have never been generated have no summary
+    pub fn chunk_summary(&self, chunk_coord: ChunkCoord) -> Option<ChunkSummary> {
+        self.chunk_summaries.0.read().get(&chunk_coord).copied()
+    }
+
+    //straight from the climate noise, works anywhere whether or not the chunk is loaded
+    pub fn biome_at(&self, world_pos: Vec3) -> Biome {
+        biome_at(world_pos)
+    }
+
+    //distance to the surface in world units, negative inside solid terrain, None when the chunk is not loaded
+    //trilinear between the samples around the point, so it agrees with the meshed surface
+    pub fn density_at(&self, world_pos: Vec3) -> Option<f32> {
+        let sample = (world_pos + HALF_CHUNK) / VOXEL_WORLD_SIZE;
+        let (chunk_coord, padded) = sample_to_padded_index(sample.floor().as_ivec3());
+        let chunk = self.terrain_chunk_map.0.get(&chunk_coord)?;
+        Some(density_in(
+            &chunk,
+            padded.as_vec3() + (sample - sample.floor()),
+        ))
+    }
+
+    //material of the nearest sample, None when the chunk is not loaded
+    pub fn material_at(&self, world_pos: Vec3) -> Option<MaterialCode> {
+        let (chunk_coord, padded) = sample_to_padded_index(world_pos_to_sample(world_pos));
+        let chunk = self.terrain_chunk_map.0.get(&chunk_coord)?;
+        Some(material_in(&chunk, padded))
+    }
+
+    //height of the highest surface in the column, walked down from a little above the generated surface
+    //unloaded chunks above the loaded ones are passed over, a gap below them or a column that starts in solid ground gives None
+    pub fn ground_height_at(&self, xz: Vec2) -> Option<f32> {
+        let noise_height = sample_terrain_height(xz.x, xz.y, &self.noise_function.0);
+        let top = world_pos_to_chunk_coord(&Vec3::new(xz.x, noise_height, xz.y));
+        let sample = (xz + HALF_CHUNK) / VOXEL_WORLD_SIZE;
+        let (column, padded) = sample_to_padded_index(IVec3::new(
+            sample.x.floor() as i32,
+            0,
+            sample.y.floor() as i32,
+        ));
+        let padded_xz = Vec2::new(padded.x as f32, padded.z as f32) + (sample - sample.floor());
+        let mut above = None;
+        for chunk_y in (top.1 - GROUND_SEARCH_DEPTH..=top.1 + GROUND_SEARCH_CHUNKS_ABOVE).rev() {
+            let chunk_coord = ChunkCoord(column.0, chunk_y, column.2);
+            let Some(chunk) = self.terrain_chunk_map.0.get(&chunk_coord) else {
+                if above.is_some() {
+                    return None;
+                }
+                continue;
+            };
+            match ground_in_chunk(&chunk, chunk_y, padded_xz, &mut above) {
+                Ground::Found(height) => return Some(height),
+                Ground::StartsSolid => return None,
+                Ground::Below => {}
+            }
+        }
+        None
+    }
+}
+
+enum Ground {
+    Found(f32),
+    StartsSolid, //the first sample walked was already solid, the surface is somewhere above what is loaded
+    Below,
+}
+
+//padded is a position in the chunk's padded sample grid, the eight samples around it are always inside the grid
+fn density_in(chunk: &TerrainChunk, padded: Vec3) -> f32 {
+    match chunk {
+        TerrainChunk::UniformAir => SdfValue::AIR.to_f32(),
+        TerrainChunk::UniformDirt => SdfValue::SOLID.to_f32(),
+        TerrainChunk::NonUniformTerrainChunk(chunk) => sample_trilinear_density(
+            &chunk.densities,
+            SAMPLES_PER_CHUNK_DIM_PADDED,
+            padded.x,
+            padded.y,
+            padded.z,
+        ),
+    }
+}
+
+//materials have no padding, interior padded index i is material index i - 1
+fn material_in(chunk: &TerrainChunk, padded: UVec3) -> MaterialCode {
+    match chunk {
+        TerrainChunk::UniformAir => MaterialCode::Air,
+        TerrainChunk::UniformDirt => MaterialCode::Dirt,
+        TerrainChunk::NonUniformTerrainChunk(chunk) => {
+            let local = padded - UVec3::ONE;
+            chunk.materials
+                [flatten_index(local.x, local.y, local.z, SAMPLES_PER_CHUNK_DIM) as usize]
+        }
+    }
+}
+
+//walks the chunk's interior samples top down, above carries the last air sample as (height, density) between chunks
+//the top interior sample is the same one the chunk above ended on, so the walk is continuous across chunk faces
+fn ground_in_chunk(
+    chunk: &TerrainChunk,
+    chunk_y: i16,
+    padded_xz: Vec2,
+    above: &mut Option<(f32, f32)>,
+) -> Ground {
+    let chunk_min_y = chunk_y as f32 * CHUNK_WORLD_SIZE - HALF_CHUNK;
+    for y in (1..=SAMPLES_PER_CHUNK_DIM).rev() {
+        let height = chunk_min_y + (y - 1) as f32 * VOXEL_WORLD_SIZE;
+        let density = density_in(chunk, Vec3::new(padded_xz.x, y as f32, padded_xz.y));
+        if density >= 0.0 {
+            *above = Some((height, density));
+            continue;
+        }
+        let Some((above_height, above_density)) = *above else {
+            return Ground::StartsSolid;
+        };
+        //the surface is where the density crosses zero between the two samples
+        let t = above_density / (above_density - density);
+        return Ground::Found(above_height + (height - above_height) * t);
+    }
+    Ground::Below
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED};
+    use crate::deformable_terrain::terrain::NonUniformTerrainChunk;
+
+    //a flat surface at ground_height in chunk 0, grass on top of dirt
+    fn flat_chunk(ground_height: f32) -> TerrainChunk {
+        let dim = SAMPLES_PER_CHUNK_DIM_PADDED;
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        for (i, density) in densities.iter_mut().enumerate() {
+            let height = (i / dim % dim) as f32 * VOXEL_WORLD_SIZE - VOXEL_WORLD_SIZE - HALF_CHUNK;
+            *density = SdfValue::from_f32(height - ground_height).0;
+        }
+        let mut materials = vec![MaterialCode::Dirt; SAMPLES_PER_CHUNK];
+        for (i, material) in materials.iter_mut().enumerate() {
+            let y = i / SAMPLES_PER_CHUNK_DIM % SAMPLES_PER_CHUNK_DIM;
+            let height = y as f32 * VOXEL_WORLD_SIZE - HALF_CHUNK;
+            if height > ground_height {
+                *material = MaterialCode::Air;
+            } else if height > ground_height - VOXEL_WORLD_SIZE {
+                *material = MaterialCode::Grass;
+            }
+        }
+        TerrainChunk::NonUniformTerrainChunk(NonUniformTerrainChunk {
+            densities: Arc::from(densities),
+            materials: Arc::from(materials),
+        })
+    }
+
+    #[test]
+    fn ground_is_found_between_samples() {
+        let chunk = flat_chunk(1.3);
+        let mut above = Some((HALF_CHUNK, SdfValue::AIR.to_f32()));
+        let Ground::Found(height) = ground_in_chunk(&chunk, 0, Vec2::new(10.4, 20.7), &mut above)
+        else {
+            panic!("flat chunk has no ground");
+        };
+        assert!((height - 1.3).abs() < 1e-3, "{height}");
+        //a walk that starts inside the ground cannot say where its surface is
+        assert!(matches!(
+            ground_in_chunk(&flat_chunk(HALF_CHUNK + 1.0), 0, Vec2::ONE, &mut None),
+            Ground::StartsSolid
+        ));
+        assert!(matches!(
+            ground_in_chunk(&TerrainChunk::UniformAir, 0, Vec2::ONE, &mut None),
+            Ground::Below
+        ));
+    }
+
+    #[test]
+    fn density_and_material_follow_the_samples() {
+        let chunk = flat_chunk(0.2); //just above the sample nearest the origin, so that sample is the grass
+        let padded = |world_pos: Vec3| (world_pos + HALF_CHUNK) / VOXEL_WORLD_SIZE + Vec3::ONE;
+        assert!(density_in(&chunk, padded(Vec3::new(1.0, 0.5, 1.0))) > 0.0);
+        assert!(density_in(&chunk, padded(Vec3::new(1.0, -0.5, 1.0))) < 0.0);
+        let (chunk_coord, index) = sample_to_padded_index(world_pos_to_sample(Vec3::ZERO));
+        assert_eq!(chunk_coord, ChunkCoord::ORIGIN);
+        assert_eq!(material_in(&chunk, index), MaterialCode::Grass);
+        let (_, index) = sample_to_padded_index(world_pos_to_sample(Vec3::new(0.0, 2.0, 0.0)));
+        assert_eq!(material_in(&chunk, index), MaterialCode::Air);
+        assert_eq!(
+            material_in(&TerrainChunk::UniformDirt, index),
+            MaterialCode::Dirt
+        );
+    }
+}
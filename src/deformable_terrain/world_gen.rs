@@ -0,0 +1,290 @@
+use bevy::prelude::*;
+use parking_lot::RwLock;
+use rustc_hash::FxBuildHasher;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string_pretty};
+use std::cell::Cell;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use crate::constants::{
+    DEFAULT_WORLD_RADIUS, DEFAULT_WORLD_SEED, HALF_CHUNK, MAX_WORLD_RADIUS, NOISE_AMPLITUDE,
+    NOISE_FREQUENCY,
+};
+use crate::conversions::cluster_coord_to_world_pos;
+use crate::deformable_terrain::file_loader::REGION_DIR;
+
+//lives next to the chunk files, a save has to be regenerated with the config it was created with
+pub(crate) const WORLD_GEN_CONFIG_FILE: &str = "world_gen.json";
+const CHUNK_INDEX_FILE: &str = "chunk_index_data.txt";
+
+//both set by the plugin through set_world_gen_config before any chunk is generated
+//the seed is read for every noise call so it gets its own atomic
+static WORLD_SEED: AtomicI32 = AtomicI32::new(DEFAULT_WORLD_SEED);
+static WORLD_GEN_CONFIG: RwLock<WorldGenConfig> = RwLock::new(WorldGenConfig::DEFAULT);
+
+thread_local! {
+    //set for the length of with_world_gen_config, the loader threads never set it and always see the world's config
+    static CONFIG_OVERRIDE: Cell<Option<WorldGenConfig>> = const { Cell::new(None) };
+}
+
+//chunks already on disk keep the caves they were generated with
+//frequency is in 1/world units and 0 turns carving off, radius and min_depth are in world units
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct CaveSettings {
+    pub frequency: f32,
+    pub radius: f32, //tunnel radius, caverns open up wider where the second channel peaks
+    pub min_depth: f32, //below the terrain surface, carving fades out above this
+}
+
+impl CaveSettings {
+    pub const DEFAULT: CaveSettings = CaveSettings {
+        frequency: 0.01,
+        radius: 3.0,
+        min_depth: 12.0,
+    };
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        CaveSettings::DEFAULT
+    }
+}
+
+//everything chunk generation reads besides the chunk's position, a save has to be regenerated with the one it was created with
+#[derive(Serialize, Deserialize, Resource, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct WorldGenConfig {
+    pub seed: i32,
+    pub world_radius: i16, //in clusters along each axis, worlds saved before it was configurable get the default
+    pub caves: CaveSettings,
+    pub noise_frequency: f32, //of the height noise, in 1/world units
+    pub noise_amplitude: f32, //height noise is scaled to +-this before the biomes shape it
+}
+
+impl WorldGenConfig {
+    pub const DEFAULT: WorldGenConfig = WorldGenConfig {
+        seed: DEFAULT_WORLD_SEED,
+        world_radius: DEFAULT_WORLD_RADIUS,
+        caves: CaveSettings::DEFAULT,
+        noise_frequency: NOISE_FREQUENCY,
+        noise_amplitude: NOISE_AMPLITUDE,
+    };
+}
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        WorldGenConfig::DEFAULT
+    }
+}
+
+impl WorldGenConfig {
+    //the svo root halves down to single clusters so its width is rounded up to a power of two
+    pub fn world_radius_clusters(&self) -> i16 {
+        (self.world_radius.max(1) as u16)
+            .next_power_of_two()
+            .min(MAX_WORLD_RADIUS as u16) as i16
+    }
+}
+
+//the edge of the world in world units, the svo holds no clusters past it and the player is stopped at it
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct WorldBounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl WorldBounds {
+    pub fn from_radius(world_radius: i16) -> Self {
+        WorldBounds {
+            min: cluster_coord_to_world_pos(&(-world_radius, -world_radius, -world_radius))
+                - HALF_CHUNK,
+            max: cluster_coord_to_world_pos(&(world_radius, world_radius, world_radius))
+                - HALF_CHUNK,
+        }
+    }
+
+    //movement that would take a position past the edge stops at it, movement along the edge is kept
+    pub fn clamp_movement(&self, position: Vec3, movement: Vec3) -> Vec3 {
+        let mut clamped = movement;
+        for axis in 0..3 {
+            let target = position[axis] + movement[axis];
+            if movement[axis] > 0.0 && target > self.max[axis] {
+                clamped[axis] = (self.max[axis] - position[axis]).max(0.0);
+            } else if movement[axis] < 0.0 && target < self.min[axis] {
+                clamped[axis] = (self.min[axis] - position[axis]).min(0.0);
+            }
+        }
+        clamped
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldSeed(pub i32);
+
+pub fn set_world_gen_config(config: WorldGenConfig) {
+    *WORLD_GEN_CONFIG.write() = config;
+    WORLD_SEED.store(config.seed, Ordering::Relaxed);
+}
+
+//the config chunks generated on this thread follow
+pub fn world_gen_config() -> WorldGenConfig {
+    CONFIG_OVERRIDE
+        .get()
+        .unwrap_or_else(|| *WORLD_GEN_CONFIG.read())
+}
+
+pub fn world_seed() -> i32 {
+    CONFIG_OVERRIDE
+        .get()
+        .map_or_else(|| WORLD_SEED.load(Ordering::Relaxed), |config| config.seed)
+}
+
+//tells apart results of different configs in the generation caches shared with a world_gen_compare thread
+//world_radius only bounds the svo so it is left out, fx hashing is the same on every run
+pub fn world_gen_key() -> u64 {
+    let WorldGenConfig {
+        seed,
+        world_radius: _,
+        caves,
+        noise_frequency,
+        noise_amplitude,
+    } = world_gen_config();
+    FxBuildHasher.hash_one((
+        seed,
+        caves.frequency.to_bits(),
+        caves.radius.to_bits(),
+        caves.min_depth.to_bits(),
+        noise_frequency.to_bits(),
+        noise_amplitude.to_bits(),
+    ))
+}
+
+//runs generate with everything generated on this thread following config instead of the world's
+//for generating a region of the world the way another config would have, see world_gen_compare
+pub fn with_world_gen_config<R>(config: &WorldGenConfig, generate: impl FnOnce() -> R) -> R {
+    let previous = CONFIG_OVERRIDE.replace(Some(*config));
+    let result = generate();
+    CONFIG_OVERRIDE.set(previous);
+    result
+}
+
+//every noise channel offsets the world seed by its own number so the channels stay uncorrelated
+pub fn channel_seed(channel: i32) -> i32 {
+    world_seed().wrapping_add(channel)
+}
+
+//a fresh world rolls a random seed, the rest of new_world is kept
+pub fn load_world_gen_config(data_dir: &Path, new_world: WorldGenConfig) -> WorldGenConfig {
+    load_world_gen_config_or(
+        data_dir,
+        WorldGenConfig {
+            seed: rand::random(),
+            ..new_world
+        },
+    )
+}
+
+//new_world only applies to a world with nothing saved yet and is written out immediately
+//an existing world keeps the config it was generated with, fields missing from an older file get their defaults
+//worlds saved before the seed was configurable have chunk files but no config
+//those were generated with the default seed and radius, their caves are whatever the app asks for now
+pub fn load_world_gen_config_or(data_dir: &Path, new_world: WorldGenConfig) -> WorldGenConfig {
+    if let Some(config) = read_to_string(data_dir.join(WORLD_GEN_CONFIG_FILE))
+        .ok()
+        .and_then(|s| from_str(&s).ok())
+    {
+        return config;
+    }
+    let config = if data_dir.join(CHUNK_INDEX_FILE).exists() || data_dir.join(REGION_DIR).exists() {
+        WorldGenConfig {
+            seed: DEFAULT_WORLD_SEED,
+            world_radius: DEFAULT_WORLD_RADIUS,
+            ..new_world
+        }
+    } else {
+        new_world
+    };
+    save_world_gen_config(data_dir, &config);
+    config
+}
+
+pub fn save_world_gen_config(data_dir: &Path, config: &WorldGenConfig) {
+    let _ = create_dir_all(data_dir);
+    if let Ok(json) = to_string_pretty(config) {
+        let _ = write(data_dir.join(WORLD_GEN_CONFIG_FILE), json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_override_is_scoped_to_the_call() {
+        let world = world_gen_config();
+        let world_key = world_gen_key();
+        let config = WorldGenConfig {
+            seed: world.seed.wrapping_add(1),
+            caves: CaveSettings {
+                frequency: 0.0,
+                ..world.caves
+            },
+            noise_amplitude: world.noise_amplitude * 2.0,
+            ..world
+        };
+        let inner = with_world_gen_config(&config, || {
+            assert_eq!(channel_seed(3), world.seed.wrapping_add(4));
+            assert_ne!(world_gen_key(), world_key);
+            world_gen_config()
+        });
+        assert_eq!(inner, config);
+        assert_eq!(world_gen_config(), world);
+        assert_eq!(world_seed(), world.seed);
+        assert_eq!(world_gen_key(), world_key);
+    }
+
+    #[test]
+    fn existing_world_keeps_its_saved_generation_settings() {
+        let dir = std::env::temp_dir().join(format!("world_gen_config_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let created = WorldGenConfig {
+            seed: 7,
+            caves: CaveSettings {
+                frequency: 0.02,
+                radius: 5.0,
+                min_depth: 20.0,
+            },
+            noise_amplitude: 150.0,
+            ..default()
+        };
+        assert_eq!(load_world_gen_config_or(&dir, created), created);
+        let reopened = load_world_gen_config_or(
+            &dir,
+            WorldGenConfig {
+                seed: 8,
+                ..default()
+            },
+        );
+        assert_eq!(reopened, created);
+        //a config written before the caves and noise were saved loads with their defaults
+        write(
+            dir.join(WORLD_GEN_CONFIG_FILE),
+            r#"{ "seed": 7, "world_radius": 16 }"#,
+        )
+        .unwrap();
+        let old = load_world_gen_config_or(&dir, created);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            old,
+            WorldGenConfig {
+                seed: 7,
+                world_radius: 16,
+                ..default()
+            }
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::thread;
+
+use bevy::{camera::primitives::MeshAabb, prelude::*};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+use crate::{
+    chunk_coord::ChunkCoord,
+    constants::SAMPLES_PER_CHUNK_DIM,
+    conversions::{chunk_coord_to_world_pos, world_pos_to_chunk_coord},
+    deformable_terrain::{
+        chunk_entity_map::ChunkEntityMap,
+        chunk_generator::{calculate_chunk_start, get_fbm},
+        chunk_iter::chunks_in_sphere,
+        density_source::{DensitySource, NoiseTerrain},
+        driver::ChunkBuffers,
+        marching_cubes::mc::{MaterialResolution, mc_mesh_generation},
+        terrain::{TerrainMaterialHandle, generate_bevy_mesh},
+        world_gen::{WorldGenConfig, with_world_gen_config},
+    },
+    player::player::PlayerTag,
+    ui::console::{Console, ConsoleCommand},
+};
+
+const DEFAULT_COMPARE_RADIUS: i16 = 2; //in chunks, 33 chunks generated on one thread in well under a second
+const MAX_COMPARE_RADIUS: i16 = 4;
+
+#[derive(Component)]
+pub struct ComparisonChunkTag;
+
+//the region around the player generated a second time with another WorldGenConfig, B next to the world's own A
+//B is only meshes, it has no colliders, is never saved and ignores digs, the player keeps standing on A
+//B can change the seed, the caves and the height noise, world_radius has no effect on a region this small
+#[derive(Resource, Default)]
+pub struct WorldGenComparison {
+    b: Option<WorldGenConfig>,
+    region: Vec<ChunkCoord>,
+    showing_b: bool,
+    entities: Vec<(Entity, Handle<Mesh>)>,
+    hidden: Vec<Entity>, //A chunk entities hidden while B is shown
+    receiver: Option<Receiver<(ChunkCoord, Option<Mesh>)>>,
+}
+
+impl WorldGenComparison {
+    fn clear(&mut self, commands: &mut Commands, mesh_handles: &mut Assets<Mesh>) {
+        for (entity, mesh_handle) in self.entities.drain(..) {
+            commands.entity(entity).despawn();
+            mesh_handles.remove(&mesh_handle);
+        }
+        self.show_a(commands);
+        self.b = None;
+        self.region.clear();
+        self.receiver = None; //the generation thread stops once its sends start failing
+    }
+
+    fn show_a(&mut self, commands: &mut Commands) {
+        for entity in self.hidden.drain(..) {
+            commands.entity(entity).try_insert(Visibility::Inherited);
+        }
+        for (entity, _) in &self.entities {
+            commands.entity(*entity).insert(Visibility::Hidden);
+        }
+        self.showing_b = false;
+    }
+}
+
+//same padded buffers the loader fills, meshed at full resolution
+fn mesh_chunk(chunk_buffers: &ChunkBuffers) -> Option<Mesh> {
+    let (vertices, normals, material_ids, indices) = mc_mesh_generation(
+        &chunk_buffers.density,
+        &chunk_buffers.material,
+        SAMPLES_PER_CHUNK_DIM,
+        true,
+        &chunk_buffers.density,
+        MaterialResolution::default(),
+    );
+    (!indices.is_empty()).then(|| generate_bevy_mesh(vertices, normals, material_ids, indices))
+}
+
+fn comparison_thread(
+    config: WorldGenConfig,
+    region: Vec<ChunkCoord>,
+    tx: Sender<(ChunkCoord, Option<Mesh>)>,
+) {
+    with_world_gen_config(&config, || {
+        let source = NoiseTerrain::new(get_fbm());
+        let mut chunk_buffers = ChunkBuffers::new();
+        for chunk_coord in region {
+            let chunk_start = calculate_chunk_start(&chunk_coord);
+            source.prepare_column(&chunk_start, &mut chunk_buffers);
+            source.fill_chunk(chunk_start, &mut chunk_buffers);
+            if tx.send((chunk_coord, mesh_chunk(&chunk_buffers))).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+//sets one `knob=value` on B, false for an unknown knob or a value that doesnt parse
+fn apply_knob(config: &mut WorldGenConfig, arg: &str) -> bool {
+    let Some((knob, value)) = arg.split_once('=') else {
+        return false;
+    };
+    let Ok(value) = value.parse::<f32>() else {
+        return false;
+    };
+    if !value.is_finite() {
+        return false;
+    }
+    match knob {
+        "cave_frequency" => config.caves.frequency = value,
+        "cave_radius" => config.caves.radius = value,
+        "cave_min_depth" => config.caves.min_depth = value,
+        "noise_frequency" => config.noise_frequency = value,
+        "noise_amplitude" => config.noise_amplitude = value,
+        _ => return false,
+    }
+    true
+}
+
+//`compare <seed> [radius] [knob=value ...]` generates the chunks around the player again with the world's config,
+//that seed and any knobs changed, the knobs are cave_frequency, cave_radius, cave_min_depth, noise_frequency and noise_amplitude
+//`compare` flips the region between the world (A) and the comparison (B), `compare off` drops the comparison
+pub fn compare_command(
+    mut commands: Commands,
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    player_query: Query<&Transform, With<PlayerTag>>,
+    world_gen: Res<WorldGenConfig>,
+    mut comparison: ResMut<WorldGenComparison>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+) {
+    for command in command_reader.read() {
+        if command.name != "compare" {
+            continue;
+        }
+        match command.args.first().map(String::as_str) {
+            None if comparison.b.is_some() => {
+                if comparison.showing_b {
+                    comparison.show_a(&mut commands);
+                    console.print("compare: showing A, the world");
+                } else {
+                    for (entity, _) in &comparison.entities {
+                        commands.entity(*entity).insert(Visibility::Inherited);
+                    }
+                    comparison.showing_b = true;
+                    let seed = comparison.b.map_or(0, |b| b.seed);
+                    console.print(format!("compare: showing B, seed {seed}"));
+                }
+                continue;
+            }
+            Some("off") => {
+                comparison.clear(&mut commands, &mut mesh_handles);
+                console.print("compare off");
+                continue;
+            }
+            _ => {}
+        }
+        let seed = command.args.first().and_then(|a| a.parse::<i32>().ok());
+        let mut knobs = command.args.iter().skip(1).peekable();
+        let radius = match knobs
+            .next_if(|a| !a.contains('='))
+            .map(|a| a.parse::<i16>())
+        {
+            None => Some(DEFAULT_COMPARE_RADIUS),
+            Some(Ok(radius)) if (0..=MAX_COMPARE_RADIUS).contains(&radius) => Some(radius),
+            _ => None,
+        };
+        let mut config = WorldGenConfig {
+            seed: seed.unwrap_or(world_gen.seed),
+            ..*world_gen
+        };
+        let knobs_ok = knobs.all(|arg| apply_knob(&mut config, arg));
+        let (Some(seed), Some(radius), true, Ok(player)) =
+            (seed, radius, knobs_ok, player_query.single())
+        else {
+            console.print(format!(
+                "usage: compare <seed> [radius 0-{MAX_COMPARE_RADIUS}] [knob=value ...] | compare | compare off"
+            ));
+            console.print(
+                "knobs: cave_frequency cave_radius cave_min_depth noise_frequency noise_amplitude",
+            );
+            continue;
+        };
+        comparison.clear(&mut commands, &mut mesh_handles);
+        let center = world_pos_to_chunk_coord(&player.translation);
+        let region: Vec<ChunkCoord> = chunks_in_sphere(center, radius).collect();
+        let (tx, rx) = unbounded();
+        let thread_region = region.clone();
+        thread::spawn(move || comparison_thread(config, thread_region, tx));
+        comparison.b = Some(config);
+        comparison.region = region;
+        comparison.receiver = Some(rx);
+        console.print(format!(
+            "compare: generating {} chunks with seed {seed} against seed {}, run compare to flip",
+            comparison.region.len(),
+            world_gen.seed
+        ));
+        if command.args.iter().any(|a| a.contains('=')) {
+            console.print(format!("compare: B {config:?}"));
+        }
+    }
+}
+
+//spawns B meshes as they arrive and keeps A hidden while B is shown, the driver respawns chunk entities whenever it likes
+pub fn update_world_gen_comparison(
+    mut commands: Commands,
+    mut comparison: ResMut<WorldGenComparison>,
+    mut mesh_handles: ResMut<Assets<Mesh>>,
+    material_handle: Res<TerrainMaterialHandle>,
+    chunk_entity_map: Res<ChunkEntityMap>,
+) {
+    let arrived: Vec<(ChunkCoord, Option<Mesh>)> = comparison
+        .receiver
+        .as_ref()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default();
+    for (chunk_coord, mesh) in arrived {
+        let Some(mesh) = mesh else {
+            continue;
+        };
+        let aabb = mesh.compute_aabb();
+        let mesh_handle = mesh_handles.add(mesh);
+        let visibility = if comparison.showing_b {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let entity = commands
+            .spawn((
+                Mesh3d(mesh_handle.clone()),
+                MeshMaterial3d(material_handle.0.clone()),
+                ComparisonChunkTag,
+                Transform::from_translation(chunk_coord_to_world_pos(&chunk_coord)),
+                visibility,
+            ))
+            .id();
+        if let Some(aabb) = aabb {
+            commands.entity(entity).insert(aabb);
+        }
+        comparison.entities.push((entity, mesh_handle));
+    }
+    if !comparison.showing_b {
+        return;
+    }
+    let current: Vec<Entity> = comparison
+        .region
+        .iter()
+        .filter_map(|chunk_coord| chunk_entity_map.get_option(*chunk_coord))
+        .map(|(entity, _)| *entity)
+        .filter(|entity| !comparison.hidden.contains(entity))
+        .collect();
+    for entity in current {
+        commands.entity(entity).try_insert(Visibility::Hidden);
+        comparison.hidden.push(entity);
+    }
+}
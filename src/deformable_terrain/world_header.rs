@@ -0,0 +1,196 @@
+use std::fs::{File, read, rename};
+use std::io::Write;
+use std::path::Path;
+
+use crate::constants::{CHUNK_WORLD_SIZE, SAMPLES_PER_CHUNK_DIM};
+use crate::deformable_terrain::migrate::StorageFormat;
+use crate::deformable_terrain::sdf_value::SdfValue;
+
+pub const WORLD_HEADER_FILE: &str = "world_header.bin";
+const WORLD_HEADER_MAGIC: [u8; 4] = *b"MCWD";
+const WORLD_HEADER_SIZE: usize = 20; //magic + sizeof (u32, u32, f32, f32)
+pub const WORLD_FORMAT_VERSION: u32 = 2; //bump when stored chunks change meaning and add a step to migrate_world_version
+const UNIFORM_CHUNK_FILES: [&str; 2] = ["air_compression_data.txt", "dirt_compression_data.txt"];
+
+//what the stored chunks were written with, a world saved with a different sample grid or quantization reads back as garbage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldHeader {
+    pub version: u32,
+    pub samples_per_chunk_dim: u32,
+    pub chunk_world_size: f32,
+    pub quantization_scale: f32, //SdfValue::SCALE, quantized units per world unit
+}
+
+impl WorldHeader {
+    pub fn current() -> Self {
+        WorldHeader {
+            version: WORLD_FORMAT_VERSION,
+            samples_per_chunk_dim: SAMPLES_PER_CHUNK_DIM as u32,
+            chunk_world_size: CHUNK_WORLD_SIZE,
+            quantization_scale: SdfValue::SCALE,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; WORLD_HEADER_SIZE] {
+        let mut bytes = [0; WORLD_HEADER_SIZE];
+        bytes[..4].copy_from_slice(&WORLD_HEADER_MAGIC);
+        bytes[4..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.samples_per_chunk_dim.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.chunk_world_size.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.quantization_scale.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != WORLD_HEADER_SIZE || bytes[..4] != WORLD_HEADER_MAGIC {
+            return Err(format!("{WORLD_HEADER_FILE} is not a world header"));
+        }
+        let word = |i: usize| bytes[i..i + 4].try_into().unwrap();
+        Ok(WorldHeader {
+            version: u32::from_le_bytes(word(4)),
+            samples_per_chunk_dim: u32::from_le_bytes(word(8)),
+            chunk_world_size: f32::from_le_bytes(word(12)),
+            quantization_scale: f32::from_le_bytes(word(16)),
+        })
+    }
+
+    //None when the world has no header yet
+    pub fn read(data_dir: &Path) -> Result<Option<Self>, String> {
+        match read(data_dir.join(WORLD_HEADER_FILE)) {
+            Ok(bytes) => Self::from_bytes(&bytes).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("failed to read {WORLD_HEADER_FILE}: {e}")),
+        }
+    }
+
+    //written beside and renamed over so a crash never leaves half a header
+    pub fn write(&self, data_dir: &Path) -> Result<(), String> {
+        let path = data_dir.join(WORLD_HEADER_FILE);
+        let tmp_path = path.with_extension("bin.tmp");
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&self.to_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| rename(&tmp_path, &path))
+            .map_err(|e| format!("failed to write {WORLD_HEADER_FILE}: {e}"))
+    }
+
+    //the fields no migration can fix, chunks would have to be resampled
+    fn check_layout(&self) -> Result<(), String> {
+        let current = WorldHeader::current();
+        if self.samples_per_chunk_dim != current.samples_per_chunk_dim
+            || self.chunk_world_size != current.chunk_world_size
+            || self.quantization_scale != current.quantization_scale
+        {
+            return Err(format!(
+                "world was saved with {} samples per chunk edge, chunk size {} and quantization scale {}, this build uses {}, {} and {}",
+                self.samples_per_chunk_dim,
+                self.chunk_world_size,
+                self.quantization_scale,
+                current.samples_per_chunk_dim,
+                current.chunk_world_size,
+                current.quantization_scale
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn has_stored_chunks(data_dir: &Path) -> bool {
+    StorageFormat::detect(data_dir).is_some()
+        || UNIFORM_CHUNK_FILES.iter().any(|name| {
+            std::fs::metadata(data_dir.join(name)).is_ok_and(|metadata| metadata.len() > 0)
+        })
+}
+
+//one step per version, each brings a world from from_version to from_version + 1
+//version 0 is every world saved before the header existed, those were all written with the current layout
+//version 2 adds run length chunks, older chunks still read as they are but older builds cannot read the new ones
+fn migrate_world_version(_data_dir: &Path, from_version: u32) -> Result<(), String> {
+    match from_version {
+        0 | 1 => Ok(()),
+        _ => Err(format!("no migration from world version {from_version}")),
+    }
+}
+
+//fresh worlds get the current header, older ones are migrated a version at a time and stamped after every step
+//fails when the world is newer than this build or its chunk layout differs, loading either would corrupt the save
+pub fn validate_world_header(data_dir: &Path) -> Result<WorldHeader, String> {
+    let mut header = match WorldHeader::read(data_dir)? {
+        Some(header) => header,
+        None if has_stored_chunks(data_dir) => WorldHeader {
+            version: 0,
+            ..WorldHeader::current()
+        },
+        None => {
+            let header = WorldHeader::current();
+            header.write(data_dir)?;
+            return Ok(header);
+        }
+    };
+    if header.version > WORLD_FORMAT_VERSION {
+        return Err(format!(
+            "world version {} is newer than this build, which reads up to {WORLD_FORMAT_VERSION}",
+            header.version
+        ));
+    }
+    header.check_layout()?;
+    while header.version < WORLD_FORMAT_VERSION {
+        migrate_world_version(data_dir, header.version)?;
+        header.version += 1;
+        header.write(data_dir)?;
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn headerless_world_is_migrated_and_stamped() {
+        let dir = TempDir::new("world_header_legacy");
+        std::fs::write(dir.0.join(UNIFORM_CHUNK_FILES[0]), [0u8; 6]).unwrap();
+        assert_eq!(validate_world_header(&dir.0), Ok(WorldHeader::current()));
+        assert_eq!(WorldHeader::read(&dir.0), Ok(Some(WorldHeader::current())));
+    }
+
+    #[test]
+    fn mismatched_layout_and_newer_versions_are_rejected() {
+        let dir = TempDir::new("world_header_mismatch");
+        WorldHeader {
+            samples_per_chunk_dim: WorldHeader::current().samples_per_chunk_dim + 1,
+            ..WorldHeader::current()
+        }
+        .write(&dir.0)
+        .unwrap();
+        assert!(validate_world_header(&dir.0).is_err());
+        WorldHeader {
+            version: WORLD_FORMAT_VERSION + 1,
+            ..WorldHeader::current()
+        }
+        .write(&dir.0)
+        .unwrap();
+        assert!(validate_world_header(&dir.0).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+use std::thread;
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::constants::{
+    CHUNK_WORLD_SIZE, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    SAMPLES_PER_CHUNK_PADDED, VOXEL_WORLD_SIZE,
+};
+use crate::conversions::flatten_index;
+use crate::deformable_terrain::chunk_generator::{MATERIAL_COUNT, MaterialCode};
+use crate::deformable_terrain::cluster_occupancy::CLUSTER_OCCUPANCY_FILE;
+use crate::deformable_terrain::column_range_map::ColumnRangeMap;
+use crate::deformable_terrain::file_loader::{
+    REGION_DIR, RegionFiles, disk_size, load_chunk, load_chunk_index_map, load_uniform_chunks,
+};
+use crate::deformable_terrain::plugin::Uniformity;
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::world_gen::WORLD_GEN_CONFIG_FILE;
+use crate::deformable_terrain::world_header::WORLD_HEADER_FILE;
+use crate::deformable_terrain::write_journal::WRITE_JOURNAL_FILE;
+use crate::player::physics_tuning::PHYSICS_TUNING_FILE;
+use crate::player::spawn_points::SPAWN_POINTS_FILE;
+use crate::ui::console::{Console, ConsoleCommand};
+
+const MATERIAL_NAMES: [&str; MATERIAL_COUNT] =
+    ["air", "dirt", "grass", "sand", "path", "snow", "water"];
+//everything a world writes into its dir, paths relative to it
+pub(crate) const SAVE_FILES: [(&str, &str); 11] = [
+    ("region", REGION_DIR),
+    ("uniform air", "air_compression_data.txt"),
+    ("uniform dirt", "dirt_compression_data.txt"),
+    ("write journal", WRITE_JOURNAL_FILE),
+    ("world header", WORLD_HEADER_FILE),
+    ("world gen", WORLD_GEN_CONFIG_FILE),
+    ("cluster occupancy", CLUSTER_OCCUPANCY_FILE),
+    ("fine zone region", "fine/regions"), //FINE_DATA_DIR/REGION_DIR
+    ("player", "player_data.txt"),
+    ("spawn points", SPAWN_POINTS_FILE),
+    ("physics tuning", PHYSICS_TUNING_FILE),
+];
+
+#[derive(Debug, Default)]
+pub struct WorldStats {
+    pub non_uniform_chunks: usize,
+    pub uniform_air_chunks: usize,
+    pub uniform_dirt_chunks: usize,
+    pub explored_volume: f32,
+    pub surface_area: f32,
+    pub material_volumes: [f32; MATERIAL_COUNT], //solid volume indexed by MaterialCode
+    pub file_sizes: Vec<(&'static str, u64)>,
+}
+
+impl WorldStats {
+    pub fn explored_chunks(&self) -> usize {
+        self.non_uniform_chunks + self.uniform_air_chunks + self.uniform_dirt_chunks
+    }
+
+    pub fn report_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "explored chunks: {} ({} non uniform, {} air, {} dirt)",
+                self.explored_chunks(),
+                self.non_uniform_chunks,
+                self.uniform_air_chunks,
+                self.uniform_dirt_chunks
+            ),
+            format!("explored volume: {:.0} m^3", self.explored_volume),
+            format!("surface area: ~{:.0} m^2", self.surface_area),
+        ];
+        for (name, volume) in MATERIAL_NAMES.iter().zip(self.material_volumes) {
+            if volume > 0.0 {
+                lines.push(format!("{name}: {volume:.0} m^3"));
+            }
+        }
+        for (name, size) in self.file_sizes.iter() {
+            lines.push(format!("{name} file: {:.2} MB", *size as f64 / 1_000_000.0));
+        }
+        lines
+    }
+}
+
+//walks every stored chunk, only reads so it is safe to run while the write thread is writing
+pub fn compute_world_stats(data_dir: &Path) -> WorldStats {
+    let mut stats = WorldStats::default();
+    for (name, path) in SAVE_FILES {
+        let size = disk_size(&data_dir.join(path)).unwrap_or(0);
+        stats.file_sizes.push((name, size));
+    }
+    let mut column_range_map = ColumnRangeMap::new();
+    stats.uniform_air_chunks = count_uniform_chunks(
+        &data_dir.join("air_compression_data.txt"),
+        Uniformity::Air,
+        &mut column_range_map,
+    );
+    stats.uniform_dirt_chunks = count_uniform_chunks(
+        &data_dir.join("dirt_compression_data.txt"),
+        Uniformity::Dirt,
+        &mut column_range_map,
+    );
+    let voxel_volume = VOXEL_WORLD_SIZE.powi(3);
+    stats.material_volumes[MaterialCode::Dirt as usize] +=
+        (stats.uniform_dirt_chunks * SAMPLES_PER_CHUNK) as f32 * voxel_volume;
+    let index_map = load_chunk_index_map(
+        &data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    if !index_map.is_empty() {
+        let mut region_files = RegionFiles::reader(&data_dir);
+        stats.non_uniform_chunks = index_map.len();
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+        let mut surface_crossings = 0;
+        let mut solid_samples = [0usize; MATERIAL_COUNT];
+        for (chunk_coord, &offset) in index_map.iter() {
+            //stats are only a rough picture, a chunk that cant be read is left out of them
+            if let Err(e) = load_chunk(
+                &mut region_files,
+                chunk_coord,
+                offset,
+                &mut densities,
+                &mut materials,
+            ) {
+                warn!("world stats skipped chunk {chunk_coord}: {e}");
+                continue;
+            }
+            surface_crossings += count_surface_crossings(&densities);
+            for z in 0..SAMPLES_PER_CHUNK_DIM {
+                for y in 0..SAMPLES_PER_CHUNK_DIM {
+                    for x in 0..SAMPLES_PER_CHUNK_DIM {
+                        let density = densities[flatten_index(
+                            x as u32 + 1,
+                            y as u32 + 1,
+                            z as u32 + 1,
+                            SAMPLES_PER_CHUNK_DIM_PADDED,
+                        ) as usize];
+                        if density < 0 {
+                            let material = materials[flatten_index(
+                                x as u32,
+                                y as u32,
+                                z as u32,
+                                SAMPLES_PER_CHUNK_DIM,
+                            ) as usize];
+                            solid_samples[material as usize] += 1;
+                        }
+                    }
+                }
+            }
+        }
+        //each sign change between neighboring samples is roughly one voxel face of surface
+        stats.surface_area = surface_crossings as f32 * VOXEL_WORLD_SIZE * VOXEL_WORLD_SIZE;
+        for (volume, count) in stats.material_volumes.iter_mut().zip(solid_samples) {
+            *volume += count as f32 * voxel_volume;
+        }
+    }
+    stats.explored_volume = stats.explored_chunks() as f32 * CHUNK_WORLD_SIZE.powi(3);
+    stats
+}
+
+fn count_uniform_chunks(
+    path: &Path,
+    uniformity: Uniformity,
+    column_range_map: &mut ColumnRangeMap,
+) -> usize {
+    let Ok(mut file) = StoragePaths::open_file(path, false) else {
+        return 0;
+    };
+    let Ok(metadata) = file.metadata() else {
+        return 0;
+    };
+    let records = metadata.len() as usize / 6;
+    let Ok(free_slots) = load_uniform_chunks(&mut file, uniformity, column_range_map) else {
+        return 0;
+    };
+    records - free_slots.len()
+}
+
+//only counts pairs inside the unpadded region so shared borders are not counted twice by neighboring chunks
+fn count_surface_crossings(densities: &[i16]) -> usize {
+    let solid = |x: usize, y: usize, z: usize| {
+        densities
+            [flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED) as usize]
+            < 0
+    };
+    let mut crossings = 0;
+    for z in 1..=SAMPLES_PER_CHUNK_DIM {
+        for y in 1..=SAMPLES_PER_CHUNK_DIM {
+            for x in 1..=SAMPLES_PER_CHUNK_DIM {
+                let here = solid(x, y, z);
+                if x < SAMPLES_PER_CHUNK_DIM && here != solid(x + 1, y, z) {
+                    crossings += 1;
+                }
+                if y < SAMPLES_PER_CHUNK_DIM && here != solid(x, y + 1, z) {
+                    crossings += 1;
+                }
+                if z < SAMPLES_PER_CHUNK_DIM && here != solid(x, y, z + 1) {
+                    crossings += 1;
+                }
+            }
+        }
+    }
+    crossings
+}
+
+//`world_stats` walks the save on a background thread and prints the report when done
+pub fn world_stats_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    storage: Res<StoragePaths>,
+) {
+    for command in command_reader.read() {
+        if command.name != "world_stats" {
+            continue;
+        }
+        console.print("computing world stats...");
+        let output = console.sender();
+        let data_dir = storage.world_dir();
+        thread::spawn(move || {
+            let stats = compute_world_stats(&data_dir);
+            for line in stats.report_lines() {
+                let _ = output.send(line);
+            }
+        });
+    }
+}
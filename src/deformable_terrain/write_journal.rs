@@ -0,0 +1,226 @@
+use std::fs::{File, read};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::{SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_PADDED};
+use crate::deformable_terrain::chunk_generator::MaterialCode;
+use crate::deformable_terrain::chunk_history::ChunkModifiedTimes;
+use crate::deformable_terrain::chunk_summary::ChunkSummaries;
+use crate::deformable_terrain::column_range_map::ColumnRangeMap;
+use crate::deformable_terrain::driver::{WriteCmd, dedicated_write_thread};
+use crate::deformable_terrain::file_loader::{
+    ChunkCodec, RegionFiles, deserialize_chunk_data, load_chunk_index_map, load_uniform_chunks,
+    serialize_chunk_data,
+};
+use crate::deformable_terrain::plugin::Uniformity;
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::deformable_terrain::terrain_error::TerrainError;
+
+pub const WRITE_JOURNAL_FILE: &str = "write_journal.bin";
+const RECORD_HEADER_SIZE: usize = 14; //sizeof (i16, i16, i16, u32, u32)
+const CHECKPOINT_BYTES: u64 = 32 * 1024 * 1024; //past this the data files are synced and the journal starts over
+
+// Journal layout:
+// - records back to back, each chunk coord: 3 * i16, blob length: u32, checksum of coord and blob: u32
+//   then the chunk as serialize_chunk_data stores it
+// - a record that is cut short or fails its checksum ends the journal, it was being appended when the game died
+
+//fnv-1a, only has to catch a record that never finished writing
+fn checksum(parts: &[&[u8]]) -> u32 {
+    let mut hash = 0x811c9dc5u32;
+    for &b in parts.iter().flat_map(|part| part.iter()) {
+        hash = (hash ^ b as u32).wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+//every chunk edit is appended and synced here before the write thread touches the region files
+//so a crash part way through an update is finished on the next start instead of leaving a torn chunk
+pub struct WriteJournal {
+    file: File,
+    len: u64,
+    record: Vec<u8>,
+}
+
+impl WriteJournal {
+    pub fn open(data_dir: &Path) -> Result<Self, TerrainError> {
+        let path = data_dir.join(WRITE_JOURNAL_FILE);
+        let file = StoragePaths::open_file(&path, true).map_err(|e| TerrainError::Open(path, e))?;
+        let len = file.metadata()?.len();
+        Ok(WriteJournal {
+            file,
+            len,
+            record: Vec::new(),
+        })
+    }
+
+    //one sync for the whole batch, the write thread drains its queue before calling this
+    //on an error the batch may be partly in the file past len, the next append writes over it
+    pub fn append<'a>(
+        &mut self,
+        batch: impl IntoIterator<Item = &'a WriteCmd>,
+        serial_buffer: &mut Vec<u8>,
+    ) -> Result<(), TerrainError> {
+        self.record.clear();
+        for cmd in batch {
+            //a patch is journaled as the whole chunk, replay rewrites it in full
+            let (WriteCmd::UpdateNonUniform {
+                densities,
+                materials,
+                chunk_coord,
+            }
+            | WriteCmd::PatchNonUniform {
+                densities,
+                materials,
+                chunk_coord,
+                ..
+            }) = cmd
+            else {
+                continue; //uniform writes are a single 6 byte slot, they cannot tear
+            };
+            let blob = serialize_chunk_data(densities, materials, ChunkCodec::Lz4, serial_buffer);
+            let mut coord = [0u8; 6];
+            coord[..2].copy_from_slice(&chunk_coord.0.to_le_bytes());
+            coord[2..4].copy_from_slice(&chunk_coord.1.to_le_bytes());
+            coord[4..].copy_from_slice(&chunk_coord.2.to_le_bytes());
+            self.record.extend_from_slice(&coord);
+            self.record
+                .extend_from_slice(&(blob.len() as u32).to_le_bytes());
+            self.record
+                .extend_from_slice(&checksum(&[&coord, blob]).to_le_bytes());
+            self.record.extend_from_slice(blob);
+        }
+        if self.record.is_empty() {
+            return Ok(());
+        }
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&self.record)?;
+        self.file.sync_data()?;
+        self.len += self.record.len() as u64;
+        Ok(())
+    }
+
+    pub fn needs_checkpoint(&self) -> bool {
+        self.len >= CHECKPOINT_BYTES
+    }
+
+    //only after every data file the journaled edits went to has been synced
+    //on an error the records are still there and get replayed, replaying them again is harmless
+    pub fn clear(&mut self) -> Result<(), TerrainError> {
+        self.file.set_len(0)?;
+        self.len = 0;
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
+
+//the complete records in the journal, in the order they were written
+fn read_journal(data_dir: &Path) -> Vec<WriteCmd> {
+    let Ok(data) = read(data_dir.join(WRITE_JOURNAL_FILE)) else {
+        return Vec::new();
+    };
+    let mut cmds = Vec::new();
+    let mut raw_buffer = Vec::new();
+    let mut rest = &data[..];
+    while rest.len() >= RECORD_HEADER_SIZE {
+        let (header, body) = rest.split_at(RECORD_HEADER_SIZE);
+        let blob_len = u32::from_le_bytes(header[6..10].try_into().unwrap()) as usize;
+        let expected = u32::from_le_bytes(header[10..14].try_into().unwrap());
+        let Some(blob) = body.get(..blob_len) else {
+            break;
+        };
+        if checksum(&[&header[..6], blob]) != expected {
+            break;
+        }
+        let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+        let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+        //passed its checksum but does not decode, nothing after it can be trusted either
+        if deserialize_chunk_data(blob, &mut raw_buffer, &mut densities, &mut materials).is_err() {
+            break;
+        }
+        cmds.push(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(densities),
+            materials: Arc::from(materials),
+            chunk_coord: ChunkCoord(
+                i16::from_le_bytes([header[0], header[1]]),
+                i16::from_le_bytes([header[2], header[3]]),
+                i16::from_le_bytes([header[4], header[5]]),
+            ),
+        });
+        rest = &body[blob_len..];
+    }
+    cmds
+}
+
+//runs the journaled edits through a write thread of their own before anything reads the region files
+//records hold whole chunks so applying one that already landed changes nothing, a crash during replay just replays again
+//the journal is only cleared once the replay is through, on an error it is left for the next start
+pub fn replay_write_journal(data_dir: &Path) -> Result<usize, TerrainError> {
+    let cmds = read_journal(data_dir);
+    if cmds.is_empty() {
+        return Ok(0);
+    }
+    let replayed = cmds.len();
+    let open = |name: &str| {
+        let path = data_dir.join(name);
+        StoragePaths::open_file(&path, true).map_err(|e| TerrainError::Open(path, e))
+    };
+    let (mut air_file, mut dirt_file) = (
+        open("air_compression_data.txt")?,
+        open("dirt_compression_data.txt")?,
+    );
+    let mut column_range_map = ColumnRangeMap::new();
+    let air_offsets = load_uniform_chunks(&mut air_file, Uniformity::Air, &mut column_range_map)?;
+    let dirt_offsets =
+        load_uniform_chunks(&mut dirt_file, Uniformity::Dirt, &mut column_range_map)?;
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    for cmd in cmds {
+        //a journaled chunk still listed in a uniform file has to leave it first
+        //the uniform removes of an edit batch are not journaled, and the loader checks the uniform files before the regions,
+        //so a chunk that is non uniform now would stay hidden behind its old listing
+        //one that is uniform again would be appended to the file twice
+        if let WriteCmd::UpdateNonUniform { chunk_coord, .. } = &cmd {
+            let listed = column_range_map
+                .get_column(chunk_coord.0, chunk_coord.2)
+                .uniformity_at_y(chunk_coord.1);
+            let chunk_coord = *chunk_coord;
+            match listed {
+                Uniformity::Air => {
+                    let _ = write_tx.send(WriteCmd::RemoveUniformAir { chunk_coord });
+                }
+                Uniformity::Dirt => {
+                    let _ = write_tx.send(WriteCmd::RemoveUniformDirt { chunk_coord });
+                }
+                _ => {}
+            }
+        }
+        let _ = write_tx.send(cmd);
+    }
+    drop(write_tx);
+    let mut summaries = FxHashMap::default();
+    let mut modified_times = FxHashMap::default();
+    let index_map = load_chunk_index_map(data_dir, &mut summaries, &mut modified_times);
+    //without a journal of its own the write thread syncs and returns as soon as the queue is empty
+    dedicated_write_thread(
+        write_rx,
+        Arc::new(RwLock::new(FxHashMap::default())),
+        RegionFiles::writer(data_dir)?,
+        air_file,
+        dirt_file,
+        air_offsets,
+        dirt_offsets,
+        Arc::new(index_map),
+        ChunkSummaries(Arc::new(RwLock::new(summaries))),
+        ChunkModifiedTimes::new(modified_times),
+        None,
+    );
+    WriteJournal::open(data_dir)?.clear()?;
+    info!("Replayed {replayed} chunk writes from the write journal.");
+    Ok(replayed)
+}
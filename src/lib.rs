@@ -1,8 +1,9 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-pub mod constants;
-pub mod conversions;
+pub use marching_cubes_core::{chunk_coord, constants, conversions};
+
+pub mod crash_report;
 pub mod deformable_terrain;
 pub mod lighting;
 pub mod player;
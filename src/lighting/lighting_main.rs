@@ -11,10 +11,18 @@ use bevy::{
 };
 
 use crate::{
-    constants::CAMERA_FIRST_PERSON_OFFSET, player::player::MainCameraTag,
+    constants::CAMERA_FIRST_PERSON_OFFSET,
+    deformable_terrain::{chunk_generator::sample_terrain_height, plugin::NoiseFunction},
+    player::player::MainCameraTag,
     ui::configurable_settings::ConfigurableSettings,
 };
 
+const SURFACE_EV100: f32 = 13.0;
+const UNDERGROUND_EV100: f32 = 7.5; //lower lets more light in, enough to see cave walls by bounce light
+const EXPOSURE_FADE_START: f32 = 2.0; //depth below the surface height before exposure starts opening up
+const EXPOSURE_FADE_DEPTH: f32 = 30.0; //depth over which it reaches the underground value
+const EXPOSURE_ADAPT_RATE: f32 = 1.5; //per second, eyes adjust instead of snapping at cave mouths
+
 #[derive(Component)]
 pub struct SunLightTag;
 
@@ -76,6 +84,32 @@ pub fn apply_settings_changes(
     }
 }
 
+//target exposure for a camera this far below the terrain surface, negative depth is above ground
+fn target_ev100(depth: f32) -> f32 {
+    let t = ((depth - EXPOSURE_FADE_START) / EXPOSURE_FADE_DEPTH).clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    SURFACE_EV100 + (UNDERGROUND_EV100 - SURFACE_EV100) * t
+}
+
+//depth comes from the generated surface height, so dug tunnels and natural caves both darken with how far down they are
+pub fn update_exposure(
+    time: Res<Time>,
+    fbm: Res<NoiseFunction>,
+    mut camera_query: Query<(&GlobalTransform, &mut Exposure), With<MainCameraTag>>,
+) {
+    let Ok((camera_transform, mut exposure)) = camera_query.single_mut() else {
+        return;
+    };
+    let position = camera_transform.translation();
+    let depth = sample_terrain_height(position.x, position.z, &fbm.0) - position.y;
+    let target = target_ev100(depth);
+    let blend = 1.0 - (-EXPOSURE_ADAPT_RATE * time.delta_secs()).exp();
+    let ev100 = exposure.ev100 + (target - exposure.ev100) * blend;
+    if (ev100 - exposure.ev100).abs() > 0.001 {
+        exposure.ev100 = ev100;
+    }
+}
+
 pub fn setup_camera(
     mut commands: Commands,
     mut scattering_mediums: ResMut<Assets<ScatteringMedium>>,
@@ -100,7 +134,9 @@ pub fn setup_camera(
             medium: scattering_mediums.add(ScatteringMedium::default()),
         },
         AtmosphereSettings::default(),
-        Exposure { ev100: 13.0 },
+        Exposure {
+            ev100: SURFACE_EV100,
+        },
         Tonemapping::AcesFitted,
         Bloom::NATURAL,
         AtmosphereEnvironmentMapLight::default(),
@@ -1,112 +1,172 @@
-use std::sync::atomic::Ordering;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::asset::UnapprovedPathMode;
 use bevy::diagnostic::{
     EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, SystemInformationDiagnosticsPlugin,
 };
 use bevy::image::ImageSamplerDescriptor;
-use bevy::pbr::{ExtendedMaterial, PbrPlugin};
+use bevy::log::LogPlugin;
+use bevy::pbr::PbrPlugin;
 use bevy::prelude::*;
 // use bevy::render::diagnostic::RenderDiagnosticsPlugin;
-use bevy::window::{PresentMode, WindowMode};
-use bevy::winit::{UpdateMode, WinitSettings};
-use bevy_rapier3d::plugin::{NoUserData, PhysicsSet, RapierPhysicsPlugin};
+use bevy::window::{ExitCondition, PresentMode, PrimaryWindow, WindowMode};
+use bevy::winit::{UpdateMode, WinitPlugin, WinitSettings};
+use bevy_rapier3d::plugin::{NoUserData, RapierPhysicsPlugin};
 // use bevy_rapier3d::render::RapierDebugRenderPlugin;
-use iyes_perf_ui::PerfUiPlugin;
-use iyes_perf_ui::prelude::PerfUiDefaultEntries;
+#[cfg(feature = "perf_ui")]
+use iyes_perf_ui::{PerfUiPlugin, prelude::PerfUiDefaultEntries};
 
-use marching_cubes::deformable_terrain::chunk_generator::get_fbm;
+use marching_cubes::crash_report::{crash_log_layer, install_crash_reporter, record_crash_context};
+use marching_cubes::deformable_terrain::backup::backup_command;
+#[cfg(feature = "debug")]
+use marching_cubes::deformable_terrain::chunk_overlay::{
+    draw_chunk_overlay, record_chunk_load_times, setup_chunk_overlay,
+};
+use marching_cubes::deformable_terrain::cutaway::{
+    Cutaway, apply_cutaway, cutaway_command, nudge_cutaway,
+};
 #[cfg(feature = "debug")]
 use marching_cubes::deformable_terrain::debug_lines::{
     draw_cluster_debug, draw_collider_debug, draw_lod_debug, draw_voxel_surface_debug,
 };
-use marching_cubes::deformable_terrain::digging::handle_digging_input;
-use marching_cubes::deformable_terrain::driver::{
-    FrameStart, INITIAL_CHUNKS_LOADED, record_frame_start,
-};
 #[cfg(feature = "debug")]
 use marching_cubes::deformable_terrain::driver_debug_ui::{spawn_debug_texts, update_debug_texts};
-use marching_cubes::deformable_terrain::file_loader::setup_chunk_loading;
+use marching_cubes::deformable_terrain::fine_zones::refine_command;
+use marching_cubes::deformable_terrain::load_history::why_slow_command;
+use marching_cubes::deformable_terrain::orbit_stress::{
+    OrbitObserver, orbit_stress_command, update_orbit_observer,
+};
 use marching_cubes::deformable_terrain::plugin::{
-    DeformableTerrainConfig, DeformableTerrainPlugin, NoiseFunction,
+    DeformableTerrainConfig, DeformableTerrainPlugin,
 };
-use marching_cubes::deformable_terrain::terrain_material::TerrainMaterialExtension;
-use marching_cubes::lighting::lighting_main::{
-    apply_settings_changes, setup_camera, setup_lighting,
+use marching_cubes::deformable_terrain::storage_paths::StoragePaths;
+use marching_cubes::deformable_terrain::terrain_edit::TerrainEditPlugin;
+use marching_cubes::deformable_terrain::world_gen_compare::{
+    WorldGenComparison, compare_command, update_world_gen_comparison,
 };
-use marching_cubes::player::player::{
-    CameraController, KeyBindings, camera_look, camera_zoom, free_cam_movement, grab_on_click,
-    handle_focus_change, initial_grab_cursor, player_movement, spawn_free_cam_root, spawn_player,
-    sync_player_rotation, sync_terrain_center, toggle_first_person, toggle_fly_mode,
-    toggle_free_cam, validate_player_spawn,
+use marching_cubes::deformable_terrain::world_stats::world_stats_command;
+use marching_cubes::lighting::lighting_main::{
+    apply_settings_changes, setup_camera, setup_lighting, update_exposure,
 };
+use marching_cubes::player::player::{player_movement, sync_terrain_center};
+use marching_cubes::player::plugin::PlayerPlugin;
 use marching_cubes::settings::settings_driver::{load_settings, save_monitor_on_move};
+use marching_cubes::settings::startup_args::{StartupArgs, USAGE, exit_when_pregenerated};
 use marching_cubes::ui::configurable_settings::{
-    FpsLimit, MenuFocus, MenuTab, load_configurable_settings,
+    FpsLimit, MenuFocus, MenuTab, RenderRadiusSquared, load_configurable_settings,
 };
+use marching_cubes::ui::console::ConsolePlugin;
 use marching_cubes::ui::crosshair::spawn_crosshair;
+use marching_cubes::ui::loading_bar::{spawn_loading_bar, update_loading_bar};
 use marching_cubes::ui::menu::{SettingsState, menu_toggle, menu_update};
+use marching_cubes::ui::save_indicator::{spawn_save_indicator, update_save_indicator};
 
-fn main() {
+fn main() -> AppExit {
+    let args = match StartupArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("{USAGE}");
+            return AppExit::error();
+        }
+    };
+    let storage = StoragePaths::default().with_world(args.world.clone());
+    install_crash_reporter(storage.clone());
     let settings = load_settings(); //automatically saved state
-    let configurable_settings = load_configurable_settings(); //user saved state
+    let mut configurable_settings = load_configurable_settings(); //user saved state
+    if let Some(radius) = args.render_radius() {
+        configurable_settings.render_radius_squared = RenderRadiusSquared(radius * radius);
+    }
     DeformableTerrainConfig::set_render_radius(
         configurable_settings.render_radius_squared.0.to_bits(),
     );
+    DeformableTerrainConfig::set_vertical_render_radius(
+        configurable_settings
+            .vertical_render_radius
+            .powi(2)
+            .to_bits(),
+    );
     let window_centered_position = settings.window_centered_position;
     let update_mode = match configurable_settings.fps_limit {
         FpsLimit::Fps60 => UpdateMode::reactive_low_power(Duration::from_secs_f64(1.0 / 60.0)),
         FpsLimit::Fps120 => UpdateMode::reactive_low_power(Duration::from_secs_f64(1.0 / 120.0)),
         FpsLimit::Unlimited => UpdateMode::Continuous,
     };
-    App::new()
-        .insert_resource(settings)
+    let window_plugin = if args.headless {
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        }
+    } else {
+        WindowPlugin {
+            primary_window: Some(Window {
+                present_mode: PresentMode::AutoNoVsync,
+                mode: WindowMode::BorderlessFullscreen(MonitorSelection::Primary),
+                position: window_centered_position
+                    .map(WindowPosition::At)
+                    .unwrap_or(WindowPosition::Automatic),
+                ..default()
+            }),
+            ..default()
+        }
+    };
+    let mut default_plugins = DefaultPlugins
+        .set(window_plugin)
+        .set(ImagePlugin {
+            default_sampler: ImageSamplerDescriptor {
+                anisotropy_clamp: 16,
+                ..ImageSamplerDescriptor::linear()
+            },
+        })
+        .set(PbrPlugin { ..default() })
+        .set(AssetPlugin {
+            unapproved_path_mode: UnapprovedPathMode::Allow,
+            ..default()
+        })
+        .set(LogPlugin {
+            custom_layer: crash_log_layer,
+            ..default()
+        });
+    let mut app = App::new();
+    if args.headless {
+        //still renders, just to nothing, so the gpu side of meshing is part of what gets measured
+        default_plugins = default_plugins.disable::<WinitPlugin>();
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+    }
+    if args.pregen.is_some() {
+        app.add_systems(Update, exit_when_pregenerated);
+    }
+    app.insert_resource(settings)
         .insert_resource(SettingsState {
             current_tab: MenuTab::General,
             current_focus: MenuFocus::Tabs,
         })
-        .insert_resource(FrameStart(Instant::now()))
         .insert_resource(configurable_settings)
-        .insert_resource(KeyBindings::default())
-        .insert_resource(CameraController::default())
         .insert_resource(WinitSettings {
             focused_mode: update_mode,
             unfocused_mode: update_mode,
         })
-        .insert_resource(NoiseFunction(get_fbm()))
+        .init_resource::<OrbitObserver>()
+        .init_resource::<Cutaway>()
+        .init_resource::<WorldGenComparison>()
         .add_plugins((
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        present_mode: PresentMode::AutoNoVsync,
-                        mode: WindowMode::BorderlessFullscreen(MonitorSelection::Primary),
-                        position: window_centered_position
-                            .map(WindowPosition::At)
-                            .unwrap_or(WindowPosition::Automatic),
-                        ..default()
-                    }),
-                    ..default()
-                })
-                .set(ImagePlugin {
-                    default_sampler: ImageSamplerDescriptor {
-                        anisotropy_clamp: 16,
-                        ..ImageSamplerDescriptor::linear()
-                    },
-                })
-                .set(PbrPlugin { ..default() })
-                .set(AssetPlugin {
-                    unapproved_path_mode: UnapprovedPathMode::Allow,
-                    ..default()
-                }),
+            default_plugins,
             FrameTimeDiagnosticsPlugin::default(),
             EntityCountDiagnosticsPlugin::default(),
             SystemInformationDiagnosticsPlugin,
+            #[cfg(feature = "perf_ui")]
             PerfUiPlugin,
             RapierPhysicsPlugin::<NoUserData>::default(),
-            DeformableTerrainPlugin { lods: false },
-            MaterialPlugin::<ExtendedMaterial<StandardMaterial, TerrainMaterialExtension>>::default(
-            ),
+            ConsolePlugin,
+            DeformableTerrainPlugin {
+                new_world_seed: args.seed,
+                storage,
+                ..default()
+            },
+            TerrainEditPlugin,
+            PlayerPlugin,
             // LogDiagnosticsPlugin::default(),
             // RapierDebugRenderPlugin::default(),
         ))
@@ -114,32 +174,24 @@ fn main() {
         .add_systems(
             Startup,
             (
-                setup,
+                #[cfg(feature = "perf_ui")]
+                spawn_perf_ui,
                 spawn_crosshair,
-                spawn_player.after(setup_chunk_loading).after(setup_camera),
+                spawn_save_indicator,
+                spawn_loading_bar,
                 // spawn_minimap.after(spawn_player),
-                initial_grab_cursor,
                 setup_lighting,
                 setup_camera,
-                spawn_free_cam_root,
                 #[cfg(feature = "debug")]
                 spawn_debug_texts,
+                #[cfg(feature = "debug")]
+                setup_chunk_overlay,
             ),
         )
-        .add_systems(First, record_frame_start)
         .add_systems(
             Update,
             (
-                handle_digging_input,
-                toggle_first_person,
-                camera_zoom,
-                camera_look,
-                player_movement,
-                sync_terrain_center.after(player_movement),
-                validate_player_spawn
-                    .after(PhysicsSet::SyncBackend)
-                    .run_if(|| !INITIAL_CHUNKS_LOADED.load(Ordering::Relaxed)),
-                save_monitor_on_move,
+                save_monitor_on_move.run_if(any_with_component::<PrimaryWindow>),
                 // #[cfg(feature = "debug")]
                 // update_debug_sphere_positions,
                 #[cfg(feature = "debug")]
@@ -152,25 +204,39 @@ fn main() {
                 draw_voxel_surface_debug,
                 menu_toggle,
                 menu_update.after(menu_toggle),
-                handle_focus_change,
-                grab_on_click,
-                toggle_fly_mode,
                 apply_settings_changes,
+                world_stats_command,
+                backup_command,
+                update_save_indicator,
+                update_loading_bar,
+                orbit_stress_command,
+                update_orbit_observer.after(orbit_stress_command),
+                cutaway_command,
+                refine_command,
+                why_slow_command,
+                compare_command,
             ),
         )
         .add_systems(
             Update,
             (
-                toggle_free_cam,
-                free_cam_movement,
-                sync_player_rotation,
+                nudge_cutaway.after(cutaway_command),
+                apply_cutaway.after(nudge_cutaway),
+                update_world_gen_comparison.after(compare_command),
+                record_crash_context.after(sync_terrain_center),
+                update_exposure.after(player_movement),
                 #[cfg(feature = "debug")]
                 update_debug_texts,
+                #[cfg(feature = "debug")]
+                record_chunk_load_times,
+                #[cfg(feature = "debug")]
+                draw_chunk_overlay.after(record_chunk_load_times),
             ),
         )
-        .run();
+        .run()
 }
 
-fn setup(mut commands: Commands) {
+#[cfg(feature = "perf_ui")]
+fn spawn_perf_ui(mut commands: Commands) {
     commands.spawn(PerfUiDefaultEntries::default());
 }
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::deformable_terrain::driver::{WriteCmd, WriteCmdSender};
+use crate::player::player::{
+    CameraController, PlayerDataFile, PlayerSaveData, PlayerTag, player_data_contents,
+};
+use crate::ui::configurable_settings::ConfigurableSettings;
+
+const EXIT_FLUSH_TIMEOUT: Duration = Duration::from_secs(10); //past this the write journal finishes the job on the next start
+
+//queued ahead of the Sync that follows it, so the player is saved by the time that Sync is done
+fn save_player(
+    write_cmd_sender: &WriteCmdSender,
+    player_data_file: Option<&PlayerDataFile>, //missing when spawn_player could not open it
+    player_query: &Query<&Transform, With<PlayerTag>>,
+    camera_controller: &CameraController,
+) {
+    let (Some(player_data_file), Ok(transform)) = (player_data_file, player_query.single()) else {
+        return;
+    };
+    let contents = player_data_contents(&PlayerSaveData {
+        position: transform.translation,
+        yaw: camera_controller.player_yaw,
+        pitch: camera_controller.player_pitch,
+        camera_distance: camera_controller.distance,
+        first_person: camera_controller.is_first_person,
+    });
+    let sent = write_cmd_sender.0.send(WriteCmd::ReplaceFile {
+        name: "player data",
+        file: Arc::clone(&player_data_file.0),
+        contents,
+    });
+    if sent.is_err() {
+        warn!("The write thread is gone, player data not saved.");
+    }
+}
+
+//chunk edits are already written as they happen, autosave only makes them durable and stores the player
+//the write thread does the syncing so a slow disk never holds up the frame
+pub fn autosave(
+    time: Res<Time>,
+    settings: Res<ConfigurableSettings>,
+    mut since_last_save: Local<f32>,
+    write_cmd_sender: Res<WriteCmdSender>,
+    player_data_file: Option<Res<PlayerDataFile>>,
+    player_query: Query<&Transform, With<PlayerTag>>,
+    camera_controller: Res<CameraController>,
+) {
+    *since_last_save += time.delta_secs();
+    if *since_last_save < settings.autosave_interval_secs {
+        return;
+    }
+    *since_last_save = 0.0;
+    save_player(
+        &write_cmd_sender,
+        player_data_file.as_deref(),
+        &player_query,
+        &camera_controller,
+    );
+    let _ = write_cmd_sender.0.send(WriteCmd::Sync { done: None });
+}
+
+//the loader threads keep the write channel open until the process dies, so exit waits for the queue to drain instead
+pub fn save_on_exit(
+    mut exit_reader: MessageReader<AppExit>,
+    write_cmd_sender: Res<WriteCmdSender>,
+    player_data_file: Option<Res<PlayerDataFile>>,
+    player_query: Query<&Transform, With<PlayerTag>>,
+    camera_controller: Res<CameraController>,
+) {
+    if exit_reader.read().last().is_none() {
+        return;
+    }
+    save_player(
+        &write_cmd_sender,
+        player_data_file.as_deref(),
+        &player_query,
+        &camera_controller,
+    );
+    let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+    let flushed = write_cmd_sender
+        .0
+        .send(WriteCmd::Sync {
+            done: Some(done_tx),
+        })
+        .is_ok()
+        && done_rx.recv_timeout(EXIT_FLUSH_TIMEOUT).is_ok();
+    if flushed {
+        info!("World saved.");
+    } else {
+        warn!("Write queue did not drain before exit, the write journal will replay the rest.");
+    }
+}
@@ -1 +1,5 @@
+pub mod autosave;
+pub mod physics_tuning;
 pub mod player;
+pub mod plugin;
+pub mod spawn_points;
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string_pretty};
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+
+//lives next to the chunk files so every world keeps its own feel
+pub(crate) const PHYSICS_TUNING_FILE: &str = "physics_tuning.json";
+
+#[derive(Serialize, Deserialize, Resource, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct PhysicsTuning {
+    pub gravity: f32,
+    pub jump_impulse: f32,
+    pub player_speed: f32,
+}
+
+impl Default for PhysicsTuning {
+    fn default() -> Self {
+        PhysicsTuning {
+            gravity: -9.81,
+            jump_impulse: 7.0,
+            player_speed: 5.0,
+        }
+    }
+}
+
+//load tuning from the world's json file, missing fields fall back to the defaults
+pub fn load_physics_tuning(data_dir: &Path) -> PhysicsTuning {
+    read_to_string(data_dir.join(PHYSICS_TUNING_FILE))
+        .ok()
+        .and_then(|s| from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_physics_tuning(data_dir: &Path, tuning: &PhysicsTuning) {
+    let _ = create_dir_all(data_dir);
+    if let Ok(json) = to_string_pretty(tuning) {
+        let _ = write(data_dir.join(PHYSICS_TUNING_FILE), json);
+    }
+}
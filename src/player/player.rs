@@ -1,7 +1,7 @@
 use std::{
-    fs::{File, OpenOptions, create_dir_all},
-    io::{Read, Seek, SeekFrom, Write},
-    sync::atomic::Ordering,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    sync::{Arc, atomic::Ordering},
 };
 
 use bevy::{
@@ -10,28 +10,32 @@ use bevy::{
     window::{CursorGrabMode, CursorOptions, PrimaryWindow, WindowFocused},
 };
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::{
-    constants::{
-        CAMERA_FIRST_PERSON_OFFSET, NOISE_AMPLITUDE, NOISE_FREQUENCY, PLAYER_CUBOID_SIZE,
-        PLAYER_SPAWN, WORLD_SEED,
-    },
+    chunk_coord::ChunkCoord,
+    constants::{CAMERA_FIRST_PERSON_OFFSET, PLAYER_CUBOID_SIZE},
     conversions::world_pos_to_chunk_coord,
     deformable_terrain::{
         chunk_entity_map::ChunkEntityMap,
         driver::INITIAL_CHUNKS_LOADED,
-        file_loader::get_project_root,
-        plugin::{ChunkTag, MoveableCenter, NoiseFunction},
+        plugin::{ChunkTag, MoveableCenter},
+        storage_paths::StoragePaths,
+        world_gen::WorldBounds,
     },
+    player::{physics_tuning::PhysicsTuning, spawn_points::SpawnPoints},
+    settings::schema::{VersionedSettings, load_versioned},
     ui::menu::MenuRoot,
 };
 
+const KEY_BINDINGS_PATH: &str = "data/key_bindings.json";
+
 const CAMERA_3RD_PERSON_OFFSET: Vec3 = Vec3 {
     x: 0.0,
     y: 5.0,
     z: 10.0,
 };
-const PLAYER_SPEED: f32 = 5.0;
 const MIN_ZOOM_DISTANCE: f32 = 4.0;
 const MAX_ZOOM_DISTANCE: f32 = 5000.0;
 const MIN_ZOOM_SPEED: f32 = 0.5;
@@ -39,18 +43,18 @@ const MAX_ZOOM_SPEED: f32 = 180.0;
 const MOUSE_SENSITIVITY: f32 = 0.002;
 const MIN_PITCH: f32 = -1.5;
 const MAX_PITCH: f32 = 1.5;
-const BASE_GRAVITY: f32 = -9.81;
-const JUMP_IMPULSE: f32 = 7.0;
 const FLY_SPEED: f32 = 20.0;
 const FLY_FAST_MULTIPLIER: f32 = 4.0;
 
 #[derive(Resource)]
-pub struct PlayerDataFile(pub File);
+pub struct PlayerDataFile(pub Arc<File>); //shared with the write thread, which does every write to it
 
 pub struct PlayerSaveData {
     pub position: Vec3,
     pub yaw: f32,
     pub pitch: f32,
+    pub camera_distance: f32,
+    pub first_person: bool,
 }
 
 #[derive(Component)]
@@ -115,8 +119,11 @@ impl Default for FreeCamMode {
     }
 }
 
-#[derive(Resource)]
+#[derive(Serialize, Deserialize, Resource)]
+#[serde(default)]
 pub struct KeyBindings {
+    #[serde(default)]
+    pub version: u32,
     pub move_forward: KeyCode,
     pub move_backward: KeyCode,
     pub move_left: KeyCode,
@@ -128,11 +135,35 @@ pub struct KeyBindings {
     pub fly_fast: KeyCode,
     pub toggle_first_person: KeyCode,
     pub toggle_free_cam: KeyCode,
+    pub cycle_brush: KeyCode,
+    pub brush_resize: KeyCode, //held while scrolling
+    pub brush_stronger: KeyCode,
+    pub brush_weaker: KeyCode,
+    pub toggle_paint: KeyCode,
+    #[serde(flatten)]
+    unknown: Map<String, Value>,
+}
+
+impl VersionedSettings for KeyBindings {
+    const VERSION: u32 = 1;
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u32) {
+        self.version = version;
+    }
+}
+
+pub fn load_key_bindings() -> KeyBindings {
+    load_versioned(KEY_BINDINGS_PATH)
 }
 
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             move_forward: KeyCode::KeyW,
             move_backward: KeyCode::KeyS,
             move_left: KeyCode::KeyA,
@@ -144,6 +175,12 @@ impl Default for KeyBindings {
             fly_fast: KeyCode::ShiftLeft,
             toggle_first_person: KeyCode::KeyC,
             toggle_free_cam: KeyCode::KeyR,
+            cycle_brush: KeyCode::KeyB,
+            brush_resize: KeyCode::ControlLeft,
+            brush_stronger: KeyCode::Equal,
+            brush_weaker: KeyCode::Minus,
+            toggle_paint: KeyCode::KeyP,
+            unknown: Map::new(),
         }
     }
 }
@@ -152,39 +189,35 @@ pub fn spawn_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    fbm: Res<NoiseFunction>,
+    spawn_points: Res<SpawnPoints>,
+    storage: Res<StoragePaths>,
     main_camera: Query<Entity, With<MainCameraTag>>,
     mut camera_controller: ResMut<CameraController>,
     mut camera_transform: Query<&mut Transform, With<MainCameraTag>>,
 ) {
-    let root = get_project_root();
-    create_dir_all(root.join("data/latest")).expect("Failed to create data directory");
-    let mut player_data_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(root.join("data/player_data.txt"))
-        .unwrap();
-    let save_data = read_player_data(&mut player_data_file);
-    commands.insert_resource(PlayerDataFile(player_data_file));
+    let save_data = match storage.open("player_data.txt") {
+        Ok(mut player_data_file) => {
+            let save_data = read_player_data(&mut player_data_file);
+            commands.insert_resource(PlayerDataFile(Arc::new(player_data_file)));
+            save_data
+        }
+        //without the resource autosave skips the player, the terrain is still saved
+        Err(e) => {
+            warn!("failed to open player data, the player will not be saved: {e}");
+            None
+        }
+    };
     let player_spawn = match &save_data {
         Some(data) => {
             camera_controller.yaw = data.yaw;
             camera_controller.pitch = data.pitch;
             camera_controller.player_yaw = data.yaw;
             camera_controller.player_pitch = data.pitch;
+            camera_controller.distance = data.camera_distance;
+            camera_controller.is_first_person = data.first_person;
             data.position
         }
-        None => Vec3::new(
-            PLAYER_SPAWN.x,
-            fbm.0.gen_single_2d(
-                PLAYER_SPAWN.x * NOISE_FREQUENCY,
-                PLAYER_SPAWN.z * NOISE_FREQUENCY,
-                WORLD_SEED,
-            ) * NOISE_AMPLITUDE
-                + 20.0,
-            PLAYER_SPAWN.z,
-        ),
+        None => spawn_points.world_spawn(),
     };
     let player_mesh = Cuboid::new(
         PLAYER_CUBOID_SIZE.x,
@@ -219,7 +252,11 @@ pub fn spawn_player(
             Mesh3d(player_mesh_handle),
             MeshMaterial3d(material),
             Transform::default(),
-            Visibility::Hidden,
+            if camera_controller.is_first_person {
+                Visibility::Hidden
+            } else {
+                Visibility::Visible
+            },
             PlayerMeshTag,
         ))
         .id();
@@ -228,7 +265,11 @@ pub fn spawn_player(
         .entity(player)
         .add_child(main_camera.iter().next().unwrap());
     if let Ok(mut transform) = camera_transform.single_mut() {
-        update_first_person_camera(&mut transform, &camera_controller);
+        if camera_controller.is_first_person {
+            update_first_person_camera(&mut transform, &camera_controller);
+        } else {
+            update_camera_position(&mut transform, &camera_controller);
+        }
     }
     //add a huge baseplate to hide the atmosphere edge. Idk if this is intended but it kinda works
     let baseplate = commands
@@ -279,7 +320,14 @@ pub fn camera_zoom(
     mut camera_transform_query: Query<&mut Transform, With<MainCameraTag>>,
     mut camera_controller: ResMut<CameraController>,
     free_cam: Res<FreeCamMode>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
 ) {
+    //scrolling resizes the terrain brush while its key is held
+    if keyboard.pressed(key_bindings.brush_resize) {
+        scroll_events.clear();
+        return;
+    }
     if camera_controller.is_first_person
         || !camera_controller.is_cursor_grabbed
         || free_cam.is_active
@@ -383,6 +431,7 @@ pub fn player_movement(
             &mut VerticalVelocity,
             &FlyMode,
             Option<&KinematicCharacterControllerOutput>,
+            &Transform,
         ),
         With<PlayerTag>,
     >,
@@ -391,8 +440,10 @@ pub fn player_movement(
     camera_controller: Res<CameraController>,
     menu_root_query: Query<&MenuRoot>,
     free_cam: Res<FreeCamMode>,
+    physics_tuning: Res<PhysicsTuning>,
+    world_bounds: Res<WorldBounds>,
 ) {
-    let Ok((mut controller, mut vertical_velocity, fly_mode, controller_output)) =
+    let Ok((mut controller, mut vertical_velocity, fly_mode, controller_output, transform)) =
         player_query.single_mut()
     else {
         return;
@@ -436,15 +487,15 @@ pub fn player_movement(
             }
             vertical_velocity.y = 0.0;
         } else {
-            movement_vec += horizontal * PLAYER_SPEED;
+            movement_vec += horizontal * physics_tuning.player_speed;
             if keyboard.just_pressed(key_bindings.jump) && is_grounded {
-                vertical_velocity.y = JUMP_IMPULSE;
+                vertical_velocity.y = physics_tuning.jump_impulse;
             }
         }
     }
     if !fly_mode.active {
         if !is_grounded {
-            vertical_velocity.y += BASE_GRAVITY
+            vertical_velocity.y += physics_tuning.gravity
                 * time.delta_secs()
                 * INITIAL_CHUNKS_LOADED.load(Ordering::Relaxed) as u8 as f32;
         } else if vertical_velocity.y < 0.0 {
@@ -452,36 +503,26 @@ pub fn player_movement(
         }
         movement_vec.y = vertical_velocity.y;
     }
-    controller.translation = Some(movement_vec * time.delta_secs());
+    //nothing loads past the world edge so the player is held at it
+    controller.translation =
+        Some(world_bounds.clamp_movement(transform.translation, movement_vec * time.delta_secs()));
 }
 
+//the player is saved by autosave and on exit, not every frame it moves
 pub fn sync_terrain_center(
     mut moveable_center: ResMut<MoveableCenter>,
     player_transform_query: Query<&Transform, With<PlayerTag>>,
-    mut player_data_file: ResMut<PlayerDataFile>,
-    camera_controller: Res<CameraController>,
-    mut last_saved_yaw: Local<f32>,
-    mut last_saved_pitch: Local<f32>,
+    camera_transform_query: Query<&GlobalTransform, With<MainCameraTag>>,
 ) {
     let player_translation = player_transform_query.iter().next().unwrap().translation;
-    let current_position = moveable_center.read();
-    let translation_changed = current_position != player_translation;
-    let angles_changed = *last_saved_yaw != camera_controller.player_yaw
-        || *last_saved_pitch != camera_controller.player_pitch;
-    if translation_changed || angles_changed {
-        if translation_changed {
-            moveable_center.update(player_translation);
+    if moveable_center.read() != player_translation {
+        moveable_center.update(player_translation);
+    }
+    if let Some(camera_transform) = camera_transform_query.iter().next() {
+        let forward = camera_transform.forward();
+        if moveable_center.read_view() != *forward {
+            moveable_center.update_view(forward);
         }
-        *last_saved_yaw = camera_controller.player_yaw;
-        *last_saved_pitch = camera_controller.player_pitch;
-        write_player_data(
-            &mut player_data_file.0,
-            &PlayerSaveData {
-                position: player_translation,
-                yaw: camera_controller.player_yaw,
-                pitch: camera_controller.player_pitch,
-            },
-        );
     }
 }
 
@@ -661,7 +702,7 @@ pub fn validate_player_spawn(
     let player_chunk = world_pos_to_chunk_coord(&player_position.translation);
     for chunk_y in (player_chunk.1 - 10..=player_chunk.1).rev() {
         if let Some((entity, _)) =
-            chunk_entity_map.get_option((player_chunk.0, chunk_y, player_chunk.2))
+            chunk_entity_map.get_option(ChunkCoord(player_chunk.0, chunk_y, player_chunk.2))
         {
             if spawned_chunks_query.get(*entity).is_ok() {
                 INITIAL_CHUNKS_LOADED.store(true, Ordering::Relaxed);
@@ -685,19 +726,23 @@ pub fn sync_player_rotation(
     mesh_transform.rotation = Quat::from_rotation_y(camera_controller.player_yaw);
 }
 
-pub fn write_player_data(f: &mut File, data: &PlayerSaveData) {
-    f.set_len(0).unwrap();
-    f.seek(SeekFrom::Start(0)).unwrap();
-    let s = format!(
-        "{} {} {} {} {}",
-        data.position.x, data.position.y, data.position.z, data.yaw, data.pitch
-    );
-    f.write_all(s.as_bytes()).unwrap();
-    f.flush().unwrap();
+//the write thread rewrites the file with these and syncs it, see autosave
+pub fn player_data_contents(data: &PlayerSaveData) -> Vec<u8> {
+    format!(
+        "{} {} {} {} {} {} {}",
+        data.position.x,
+        data.position.y,
+        data.position.z,
+        data.yaw,
+        data.pitch,
+        data.camera_distance,
+        data.first_person
+    )
+    .into_bytes()
 }
 
 pub fn read_player_data(f: &mut File) -> Option<PlayerSaveData> {
-    f.seek(SeekFrom::Start(0)).unwrap();
+    f.seek(SeekFrom::Start(0)).ok()?;
     let mut buf = String::new();
     if f.read_to_string(&mut buf).is_err() {
         return None;
@@ -708,9 +753,20 @@ pub fn read_player_data(f: &mut File) -> Option<PlayerSaveData> {
     let z = it.next()?.parse::<f32>().ok()?;
     let yaw = it.next()?.parse::<f32>().ok()?;
     let pitch = it.next()?.parse::<f32>().ok()?;
+    //files from before the camera was saved end here
+    let camera_distance = it
+        .next()
+        .and_then(|s| s.parse::<f32>().ok())
+        .unwrap_or(CAMERA_3RD_PERSON_OFFSET.length());
+    let first_person = it
+        .next()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or(true);
     Some(PlayerSaveData {
         position: Vec3::new(x, y, z),
         yaw,
         pitch,
+        camera_distance,
+        first_person,
     })
 }
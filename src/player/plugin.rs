@@ -0,0 +1,79 @@
+use std::sync::atomic::Ordering;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_rapier3d::plugin::PhysicsSet;
+
+use crate::{
+    deformable_terrain::{
+        driver::INITIAL_CHUNKS_LOADED, file_loader::setup_chunk_loading,
+        storage_paths::StoragePaths,
+    },
+    lighting::lighting_main::setup_camera,
+    player::{
+        autosave::{autosave, save_on_exit},
+        physics_tuning::load_physics_tuning,
+        player::{
+            CameraController, KeyBindings, camera_look, camera_zoom, free_cam_movement,
+            grab_on_click, handle_focus_change, initial_grab_cursor, load_key_bindings,
+            player_movement, spawn_free_cam_root, spawn_player, sync_player_rotation,
+            sync_terrain_center, toggle_first_person, toggle_fly_mode, toggle_free_cam,
+            validate_player_spawn,
+        },
+        spawn_points::{setup_spawn_points, spawn_points_command},
+    },
+    ui::console::ConsolePlugin,
+};
+
+//the walking, flying and free cam player, spawn points and saving the player on autosave and exit
+//needs DeformableTerrainPlugin, RapierPhysicsPlugin, the ConfigurableSettings resource and the camera setup_camera spawns
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ConsolePlugin>() {
+            app.add_plugins(ConsolePlugin);
+        }
+        if !app.world().contains_resource::<KeyBindings>() {
+            app.insert_resource(load_key_bindings());
+        }
+        //per world state, a rejected world never inserts StoragePaths and never runs a frame
+        let physics_tuning = app
+            .world()
+            .get_resource::<StoragePaths>()
+            .map(|storage| load_physics_tuning(&storage.world_dir()))
+            .unwrap_or_default();
+        app.insert_resource(physics_tuning)
+            .insert_resource(CameraController::default())
+            .add_systems(
+                Startup,
+                (
+                    setup_spawn_points.after(setup_chunk_loading),
+                    spawn_player.after(setup_spawn_points).after(setup_camera),
+                    initial_grab_cursor.run_if(any_with_component::<PrimaryWindow>),
+                    spawn_free_cam_root,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_first_person,
+                    camera_zoom,
+                    camera_look,
+                    player_movement,
+                    sync_terrain_center.after(player_movement),
+                    validate_player_spawn
+                        .after(PhysicsSet::SyncBackend)
+                        .run_if(|| !INITIAL_CHUNKS_LOADED.load(Ordering::Relaxed)),
+                    handle_focus_change.run_if(any_with_component::<PrimaryWindow>),
+                    grab_on_click.run_if(any_with_component::<PrimaryWindow>),
+                    toggle_fly_mode,
+                    toggle_free_cam,
+                    free_cam_movement,
+                    sync_player_rotation,
+                    spawn_points_command,
+                    autosave,
+                ),
+            )
+            .add_systems(Last, save_on_exit);
+    }
+}
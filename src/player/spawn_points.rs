@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{from_str, to_string_pretty};
+
+use crate::chunk_coord::ChunkCoord;
+use crate::constants::PLAYER_SPAWN;
+use crate::conversions::{chunk_coord_to_world_pos, world_pos_to_chunk_coord};
+use crate::deformable_terrain::chunk_generator::sample_terrain_height;
+use crate::deformable_terrain::driver::INITIAL_CHUNKS_LOADED;
+use crate::deformable_terrain::plugin::NoiseFunction;
+use crate::deformable_terrain::storage_paths::StoragePaths;
+use crate::player::player::{PlayerTag, VerticalVelocity};
+use crate::ui::console::{Console, ConsoleCommand};
+
+pub const SPAWN_POINTS_FILE: &str = "spawn_points.json";
+pub const WORLD_SPAWN: &str = "world";
+const WORLD_SPAWN_CLEARANCE: f32 = 20.0; //dropped from above the surface, the terrain may not be meshed yet
+
+//stored as the chunk it sits in plus an offset from that chunk's center
+//so a point keeps its precision and always names the chunk streaming has to bring in before it is safe to stand on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SpawnPoint {
+    #[serde(with = "chunk_as_array")]
+    pub chunk: ChunkCoord,
+    pub offset: Vec3,
+}
+
+impl SpawnPoint {
+    pub fn at(position: Vec3) -> Self {
+        let chunk = world_pos_to_chunk_coord(&position);
+        SpawnPoint {
+            chunk,
+            offset: position - chunk_coord_to_world_pos(&chunk),
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        chunk_coord_to_world_pos(&self.chunk) + self.offset
+    }
+}
+
+//written as [x, y, z] like ChunkCoord's own derive, which only exists with the serde feature
+mod chunk_as_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(chunk: &ChunkCoord, serializer: S) -> Result<S::Ok, S::Error> {
+        (chunk.0, chunk.1, chunk.2).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ChunkCoord, D::Error> {
+        let (x, y, z) = <(i16, i16, i16)>::deserialize(deserializer)?;
+        Ok(ChunkCoord(x, y, z))
+    }
+}
+
+//named places the player can return to, world is the default spawn and is always present
+//saved beside the chunks so each world keeps its own beds and teleporters
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SpawnPoints {
+    points: BTreeMap<String, SpawnPoint>,
+}
+
+impl SpawnPoints {
+    pub fn get(&self, name: &str) -> Option<&SpawnPoint> {
+        self.points.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, position: Vec3) {
+        self.points
+            .insert(name.to_string(), SpawnPoint::at(position));
+    }
+
+    //the world spawn can be moved but not removed
+    pub fn remove(&mut self, name: &str) -> bool {
+        name != WORLD_SPAWN && self.points.remove(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SpawnPoint)> {
+        self.points
+            .iter()
+            .map(|(name, point)| (name.as_str(), point))
+    }
+
+    pub fn world_spawn(&self) -> Vec3 {
+        self.points[WORLD_SPAWN].position()
+    }
+
+    //missing or unreadable files start over with only the world spawn
+    pub fn load(data_dir: &Path, world_spawn: Vec3) -> Self {
+        let mut spawn_points: SpawnPoints = read_to_string(data_dir.join(SPAWN_POINTS_FILE))
+            .ok()
+            .and_then(|s| from_str(&s).ok())
+            .unwrap_or_default();
+        spawn_points
+            .points
+            .entry(WORLD_SPAWN.to_string())
+            .or_insert_with(|| SpawnPoint::at(world_spawn));
+        spawn_points
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        let _ = create_dir_all(data_dir);
+        let json =
+            to_string_pretty(self).map_err(|e| format!("failed to encode spawn points: {e}"))?;
+        write(data_dir.join(SPAWN_POINTS_FILE), json)
+            .map_err(|e| format!("failed to write {SPAWN_POINTS_FILE}: {e}"))
+    }
+}
+
+pub fn setup_spawn_points(
+    mut commands: Commands,
+    fbm: Res<NoiseFunction>,
+    storage: Res<StoragePaths>,
+) {
+    let data_dir = storage.world_dir();
+    let world_spawn = Vec3::new(
+        PLAYER_SPAWN.x,
+        sample_terrain_height(PLAYER_SPAWN.x, PLAYER_SPAWN.z, &fbm.0) + WORLD_SPAWN_CLEARANCE,
+        PLAYER_SPAWN.z,
+    );
+    let spawn_points = SpawnPoints::load(&data_dir, world_spawn);
+    if let Err(e) = spawn_points.save(&data_dir) {
+        warn!("{e}");
+    }
+    commands.insert_resource(spawn_points);
+}
+
+//spawn lists, spawn set <name> places one at the player, spawn tp <name> goes there, spawn remove <name> forgets it
+//a teleport hands the player back to the spawn check so gravity waits until the target chunk has a collider
+pub fn spawn_points_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    console: Res<Console>,
+    storage: Res<StoragePaths>,
+    mut spawn_points: ResMut<SpawnPoints>,
+    mut player_query: Query<(&mut Transform, &mut VerticalVelocity), With<PlayerTag>>,
+) {
+    for command in command_reader.read() {
+        if command.name != "spawn" {
+            continue;
+        }
+        let Ok((mut transform, mut vertical_velocity)) = player_query.single_mut() else {
+            continue;
+        };
+        let name = command.args.get(1).map(String::as_str);
+        match (command.args.first().map(String::as_str), name) {
+            (None, _) => {
+                for (name, point) in spawn_points.iter() {
+                    let p = point.position();
+                    console.print(format!(
+                        "{name}: ({:.1}, {:.1}, {:.1}) in chunk {}",
+                        p.x, p.y, p.z, point.chunk
+                    ));
+                }
+                continue;
+            }
+            (Some("set"), Some(name)) => {
+                spawn_points.set(name, transform.translation);
+                console.print(format!("spawn point {name} set"));
+            }
+            (Some("remove"), Some(name)) => {
+                if !spawn_points.remove(name) {
+                    console.print(format!("cannot remove spawn point {name}"));
+                    continue;
+                }
+                console.print(format!("spawn point {name} removed"));
+            }
+            (Some("tp"), Some(name)) => {
+                let Some(point) = spawn_points.get(name) else {
+                    console.print(format!("no spawn point named {name}"));
+                    continue;
+                };
+                transform.translation = point.position();
+                vertical_velocity.y = 0.0;
+                INITIAL_CHUNKS_LOADED.store(false, Ordering::Relaxed);
+                console.print(format!("teleported to {name}"));
+                continue;
+            }
+            _ => {
+                console.print("usage: spawn | spawn set|tp|remove <name>");
+                continue;
+            }
+        }
+        if let Err(e) = spawn_points.save(&storage.world_dir()) {
+            console.print(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_points_round_trip_through_their_chunk() {
+        let position = Vec3::new(-1234.5, 67.25, 8901.75);
+        let point = SpawnPoint::at(position);
+        assert_eq!(point.chunk, world_pos_to_chunk_coord(&position));
+        assert!(point.position().distance(position) < 1e-3);
+        let mut spawn_points = SpawnPoints::default();
+        spawn_points.set(WORLD_SPAWN, Vec3::ZERO);
+        spawn_points.set("bed", position);
+        let json = to_string_pretty(&spawn_points).unwrap();
+        assert_eq!(from_str::<SpawnPoints>(&json).unwrap(), spawn_points);
+        assert!(!spawn_points.remove(WORLD_SPAWN));
+        assert!(spawn_points.remove("bed"));
+    }
+}
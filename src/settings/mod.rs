@@ -1 +1,3 @@
-pub mod settings_driver;
\ No newline at end of file
+pub mod schema;
+pub mod settings_driver;
+pub mod startup_args;
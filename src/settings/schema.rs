@@ -0,0 +1,142 @@
+use bevy::log::warn;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{from_str, to_string_pretty};
+use std::fs::{create_dir_all, read_to_string, rename, write};
+use std::path::PathBuf;
+
+//user editable json files that have to survive fields being added between builds
+//types use #[serde(default)] so missing fields fall back to Default, and a flattened map so unknown fields are written back untouched
+pub trait VersionedSettings: Serialize + DeserializeOwned + Default {
+    const VERSION: u32; //bump when an existing field changes meaning, new fields only need a default
+
+    fn version(&self) -> u32;
+
+    fn set_version(&mut self, version: u32);
+
+    //runs once for files older than VERSION, before the new version is written back
+    fn migrate(&mut self, _from_version: u32) {}
+}
+
+//a missing or outdated file is written back right away so it shows every current field
+//a file that no longer parses is moved aside instead of being overwritten by the next save
+pub fn load_versioned<T: VersionedSettings>(path: &str) -> T {
+    let Ok(contents) = read_to_string(path) else {
+        let settings = T::default();
+        save_versioned(path, &settings);
+        return settings;
+    };
+    match from_str::<T>(&contents) {
+        Ok(mut settings) => {
+            let version = settings.version();
+            if version < T::VERSION {
+                settings.migrate(version);
+                settings.set_version(T::VERSION);
+                save_versioned(path, &settings);
+            }
+            settings
+        }
+        Err(err) => {
+            let backup = format!("{path}.bak");
+            warn!("{path} could not be read ({err}), moved to {backup} and using defaults");
+            let _ = rename(path, backup);
+            T::default()
+        }
+    }
+}
+
+//writes to a temporary file first so a crash mid save never leaves a truncated file behind
+pub fn save_versioned<T: VersionedSettings>(path: &str, settings: &T) {
+    let path = PathBuf::from(path);
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent);
+    }
+    let Ok(json) = to_string_pretty(settings) else {
+        return;
+    };
+    let tmp_path = path.with_extension("json.tmp");
+    if write(&tmp_path, json).is_ok() {
+        let _ = rename(&tmp_path, &path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::{Map, Value};
+    use std::path::Path;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(default)]
+    struct TestSettings {
+        #[serde(default)]
+        version: u32,
+        volume: f32,
+        fov: f32,
+        #[serde(flatten)]
+        unknown: Map<String, Value>,
+    }
+
+    impl Default for TestSettings {
+        fn default() -> Self {
+            TestSettings {
+                version: Self::VERSION,
+                volume: 1.0,
+                fov: 90.0,
+                unknown: Map::new(),
+            }
+        }
+    }
+
+    impl VersionedSettings for TestSettings {
+        const VERSION: u32 = 2;
+
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn set_version(&mut self, version: u32) {
+            self.version = version;
+        }
+
+        fn migrate(&mut self, from_version: u32) {
+            if from_version < 2 {
+                self.volume = self.volume.min(0.5);
+            }
+        }
+    }
+
+    fn temp_path(name: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("settings_schema_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("settings.json").to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn old_file_gets_defaults_and_keeps_unknown_fields() {
+        let path = temp_path("old_file");
+        create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        write(&path, r#"{ "volume": 0.8, "audio_device": "usb" }"#).unwrap();
+        let settings: TestSettings = load_versioned(&path);
+        assert_eq!(settings.version, TestSettings::VERSION);
+        assert_eq!(settings.volume, 0.5);
+        assert_eq!(settings.fov, 90.0);
+        let saved: Value = from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["audio_device"], "usb");
+        assert_eq!(saved["version"], TestSettings::VERSION);
+        let _ = std::fs::remove_dir_all(Path::new(&path).parent().unwrap());
+    }
+
+    #[test]
+    fn unreadable_file_is_moved_aside() {
+        let path = temp_path("unreadable");
+        create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        write(&path, "{ not json").unwrap();
+        let settings: TestSettings = load_versioned(&path);
+        assert_eq!(settings, TestSettings::default());
+        assert!(Path::new(&format!("{path}.bak")).exists());
+        let _ = std::fs::remove_dir_all(Path::new(&path).parent().unwrap());
+    }
+}
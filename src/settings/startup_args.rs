@@ -0,0 +1,121 @@
+use std::sync::atomic::Ordering;
+
+use bevy::prelude::*;
+
+use crate::constants::SIMULATION_RADIUS;
+use crate::deformable_terrain::driver::{INITIAL_CHUNKS_LOADED, QUEUE_SIZE};
+
+pub const USAGE: &str = "usage: marching_cubes [--world <name>] [--seed <n>] [--radius <m>] [--headless] [--pregen <r>]";
+const PREGEN_SETTLE_SECS: f32 = 3.0; //the queue has to stay empty this long, clusters already handed to a worker arent counted in it
+
+//command line overrides for benchmarks and automated runs, nothing here is written back to the settings files
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StartupArgs {
+    pub world: Option<String>, //saved in worlds/<name> under the data dir, the default world is the data dir itself
+    pub seed: Option<i32>,     //only used if the world has nothing saved yet
+    pub radius: Option<f32>,   //render radius in world units
+    pub headless: bool,        //no window, terrain still loads, meshes and saves
+    pub pregen: Option<f32>,   //loads everything this far out, then saves and exits
+}
+
+impl StartupArgs {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = StartupArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--world" => {
+                    let name = value()?;
+                    //a plain directory name, so a world cant be written outside the worlds dir
+                    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\'])
+                    {
+                        return Err(format!("{name:?} is not a valid world name"));
+                    }
+                    parsed.world = Some(name);
+                }
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(seed.parse().map_err(|_| format!("bad seed {seed:?}"))?);
+                }
+                "--radius" => parsed.radius = Some(parse_radius(&arg, &value()?)?),
+                "--pregen" => parsed.pregen = Some(parse_radius(&arg, &value()?)?),
+                "--headless" => parsed.headless = true,
+                _ => return Err(format!("unknown argument {arg:?}")),
+            }
+        }
+        Ok(parsed)
+    }
+
+    //pregen loads out to its own radius, so it wins over --radius
+    pub fn render_radius(&self) -> Option<f32> {
+        self.pregen.or(self.radius)
+    }
+}
+
+//below the simulation radius the player could stand on ground that never loads
+fn parse_radius(flag: &str, value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(radius) if radius.is_finite() && radius >= SIMULATION_RADIUS => Ok(radius),
+        _ => Err(format!(
+            "{flag} takes a radius in world units of at least {SIMULATION_RADIUS}, got {value:?}"
+        )),
+    }
+}
+
+//the driver has no notion of done, so pregen waits until the player has landed and the load queue stays empty
+pub fn exit_when_pregenerated(
+    time: Res<Time>,
+    mut idle_secs: Local<f32>,
+    mut exit_writer: MessageWriter<AppExit>,
+) {
+    if !INITIAL_CHUNKS_LOADED.load(Ordering::Relaxed) || QUEUE_SIZE.load(Ordering::Relaxed) > 0 {
+        *idle_secs = 0.0;
+        return;
+    }
+    *idle_secs += time.delta_secs();
+    if *idle_secs >= PREGEN_SETTLE_SECS {
+        info!("Pregeneration finished.");
+        exit_writer.write(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<StartupArgs, String> {
+        StartupArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn flags_parse_and_bad_values_are_rejected() {
+        assert_eq!(parse(&[]), Ok(StartupArgs::default()));
+        let args = parse(&[
+            "--world",
+            "bench",
+            "--seed",
+            "-7",
+            "--radius",
+            "600",
+            "--headless",
+        ])
+        .unwrap();
+        assert_eq!(args.world.as_deref(), Some("bench"));
+        assert_eq!(args.seed, Some(-7));
+        assert_eq!(args.render_radius(), Some(600.0));
+        assert!(args.headless);
+        let pregen = parse(&["--radius", "600", "--pregen", "300"]).unwrap();
+        assert_eq!(pregen.render_radius(), Some(300.0));
+        for bad in [
+            &["--world", "../elsewhere"][..],
+            &["--world"],
+            &["--seed", "lots"],
+            &["--radius", "10"],
+            &["--pregen", "nan"],
+            &["--fast"],
+        ] {
+            assert!(parse(bad).is_err(), "{bad:?} parsed");
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::{from_str, to_string_pretty};
-use std::fs::{create_dir_all, read_to_string, write};
-use std::path::PathBuf;
+use serde_json::{Map, Value};
 
-use crate::constants::SIMULATION_RADIUS;
+use crate::constants::{CHUNK_WORLD_SIZE, SIMULATION_RADIUS};
+use crate::player::physics_tuning::PhysicsTuning;
+use crate::settings::schema::{VersionedSettings, load_versioned, save_versioned};
 
 const CONFIG_PATH: &str = "data/configurable_settings.json";
 const RENDER_RADIUS_STEPS: &[f32] = &[
@@ -26,6 +26,17 @@ const RENDER_RADIUS_STEPS: &[f32] = &[
 ];
 const _: () = assert!(RENDER_RADIUS_STEPS[0] as u64 >= SIMULATION_RADIUS as u64);
 pub const DEFAULT_RENDER_RADIUS_SQUARED: f32 = 1000.0 * 1000.0;
+pub const DEFAULT_VERTICAL_RENDER_RADIUS: f32 = 600.0;
+const MIN_VERTICAL_RENDER_RADIUS: f32 = 200.0; //the smallest render radius step, well past the simulation radius
+const MAX_VERTICAL_RENDER_RADIUS: f32 = 3000.0; //the largest render radius step, at or past the render radius it is a sphere again
+const VERTICAL_RENDER_RADIUS_STEP: f32 = 100.0;
+pub const DEFAULT_PHYSICS_RADIUS: f32 = 48.0;
+const MIN_PHYSICS_RADIUS: f32 = 3.0 * CHUNK_WORLD_SIZE; //the ground the player spawns above always keeps its colliders
+const PHYSICS_RADIUS_STEP: f32 = 8.0;
+pub const DEFAULT_AUTOSAVE_INTERVAL_SECS: f32 = 60.0;
+const MIN_AUTOSAVE_INTERVAL_SECS: f32 = 15.0;
+const MAX_AUTOSAVE_INTERVAL_SECS: f32 = 600.0;
+const AUTOSAVE_INTERVAL_STEP_SECS: f32 = 15.0;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RenderRadiusSquared(pub f32);
@@ -119,17 +130,25 @@ pub enum SettingsType {
     Lod5Toggle,
     ShowChunksToggle,
     ShowVoxelsToggle,
+    ChunkLabelsToggle,
+    ChunkHeatmapToggle,
     FpsChange,
     ShadowsToggle,
     RenderRadiusChange,
+    VerticalRenderRadiusChange,
+    PhysicsRadiusChange,
+    AutosaveIntervalChange,
     FogStartMultiplier,
     FogEndMultiplier,
     DistanceFogToggle,
     OcclusionCullingToggle,
+    GravityChange,
+    JumpImpulseChange,
+    PlayerSpeedChange,
 }
 
 impl SettingsType {
-    pub fn text(&self, s: &ConfigurableSettings) -> String {
+    pub fn text(&self, s: &ConfigurableSettings, physics: &PhysicsTuning) -> String {
         const fn on_off(b: bool) -> &'static str {
             if b { "ON" } else { "OFF" }
         }
@@ -141,12 +160,25 @@ impl SettingsType {
             SettingsType::Lod5Toggle => format!("LOD 5: {}", on_off(s.debug_lod_5)),
             SettingsType::ShowChunksToggle => format!("Show Chunks: {}", on_off(s.show_chunks)),
             SettingsType::ShowVoxelsToggle => format!("Show Voxels: {}", on_off(s.show_voxels)),
+            SettingsType::ChunkLabelsToggle => format!("Chunk Labels: {}", on_off(s.chunk_labels)),
+            SettingsType::ChunkHeatmapToggle => {
+                format!("Chunk Heatmap: {}", on_off(s.chunk_heatmap))
+            }
             SettingsType::FpsChange => format!("FPS Limit: {}", s.fps_limit.to_display_string()),
             SettingsType::ShadowsToggle => format!("Shadows: {}", on_off(s.shadows)),
             SettingsType::RenderRadiusChange => format!(
                 "Render Radius: {}",
                 s.render_radius_squared.to_display_string()
             ),
+            SettingsType::VerticalRenderRadiusChange => {
+                format!("Vertical Render Radius: {:.0}", s.vertical_render_radius)
+            }
+            SettingsType::PhysicsRadiusChange => {
+                format!("Physics Radius: {:.0}", s.physics_radius)
+            }
+            SettingsType::AutosaveIntervalChange => {
+                format!("Autosave Every: {:.0}s", s.autosave_interval_secs)
+            }
             SettingsType::FogStartMultiplier => {
                 format!("Fog Start Multiplier: {:.2}", s.fog_start_multiplier)
             }
@@ -157,10 +189,21 @@ impl SettingsType {
             SettingsType::OcclusionCullingToggle => {
                 format!("Occlusion Culling: {}", on_off(s.occlusion_culling))
             }
+            SettingsType::GravityChange => format!("Gravity: {:.2}", physics.gravity),
+            SettingsType::JumpImpulseChange => format!("Jump Impulse: {:.1}", physics.jump_impulse),
+            SettingsType::PlayerSpeedChange => format!("Player Speed: {:.1}", physics.player_speed),
         }
     }
 
-    pub fn cycle(&self, settings: &mut ConfigurableSettings, dir_next: bool) {
+    pub fn cycle(
+        &self,
+        settings: &mut ConfigurableSettings,
+        physics: &mut PhysicsTuning,
+        dir_next: bool,
+    ) {
+        let step = |value: f32, step: f32, min: f32, max: f32| {
+            (value + if dir_next { step } else { -step }).clamp(min, max)
+        };
         match self {
             SettingsType::FpsChange => {
                 settings.fps_limit = if dir_next {
@@ -176,6 +219,8 @@ impl SettingsType {
             SettingsType::Lod5Toggle => settings.debug_lod_5 = !settings.debug_lod_5,
             SettingsType::ShowChunksToggle => settings.show_chunks = !settings.show_chunks,
             SettingsType::ShowVoxelsToggle => settings.show_voxels = !settings.show_voxels,
+            SettingsType::ChunkLabelsToggle => settings.chunk_labels = !settings.chunk_labels,
+            SettingsType::ChunkHeatmapToggle => settings.chunk_heatmap = !settings.chunk_heatmap,
             SettingsType::ShadowsToggle => settings.shadows = !settings.shadows,
             SettingsType::RenderRadiusChange => {
                 settings.render_radius_squared = if dir_next {
@@ -184,6 +229,30 @@ impl SettingsType {
                     settings.render_radius_squared.prev_step()
                 };
             }
+            SettingsType::VerticalRenderRadiusChange => {
+                settings.vertical_render_radius = step(
+                    settings.vertical_render_radius,
+                    VERTICAL_RENDER_RADIUS_STEP,
+                    MIN_VERTICAL_RENDER_RADIUS,
+                    MAX_VERTICAL_RENDER_RADIUS,
+                )
+            }
+            SettingsType::PhysicsRadiusChange => {
+                settings.physics_radius = step(
+                    settings.physics_radius,
+                    PHYSICS_RADIUS_STEP,
+                    MIN_PHYSICS_RADIUS,
+                    SIMULATION_RADIUS,
+                )
+            }
+            SettingsType::AutosaveIntervalChange => {
+                settings.autosave_interval_secs = step(
+                    settings.autosave_interval_secs,
+                    AUTOSAVE_INTERVAL_STEP_SECS,
+                    MIN_AUTOSAVE_INTERVAL_SECS,
+                    MAX_AUTOSAVE_INTERVAL_SECS,
+                )
+            }
             SettingsType::FogStartMultiplier => {
                 let new = settings.fog_start_multiplier + if dir_next { 0.05 } else { -0.05 };
                 let new = new.clamp(0.0, settings.fog_end_multiplier - 0.05);
@@ -198,14 +267,36 @@ impl SettingsType {
             SettingsType::OcclusionCullingToggle => {
                 settings.occlusion_culling = !settings.occlusion_culling
             }
+            SettingsType::GravityChange => physics.gravity = step(physics.gravity, 0.5, -50.0, 0.0),
+            SettingsType::JumpImpulseChange => {
+                physics.jump_impulse = step(physics.jump_impulse, 0.5, 0.0, 30.0)
+            }
+            SettingsType::PlayerSpeedChange => {
+                physics.player_speed = step(physics.player_speed, 0.5, 0.5, 50.0)
+            }
         }
     }
+
+    //physics settings are stored per world instead of with the user settings
+    pub fn is_physics(&self) -> bool {
+        matches!(
+            self,
+            SettingsType::GravityChange
+                | SettingsType::JumpImpulseChange
+                | SettingsType::PlayerSpeedChange
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Resource, Debug)]
+#[serde(default)]
 pub struct ConfigurableSettings {
+    #[serde(default)] //files from before the schema was versioned read as 0
+    pub version: u32,
     pub show_chunks: bool,
     pub show_voxels: bool,
+    pub chunk_labels: bool,
+    pub chunk_heatmap: bool,
     pub fps_limit: FpsLimit,
     pub debug_lod_1: bool,
     pub debug_lod_2: bool,
@@ -214,24 +305,41 @@ pub struct ConfigurableSettings {
     pub debug_lod_5: bool,
     pub shadows: bool,
     pub render_radius_squared: RenderRadiusSquared,
+    pub vertical_render_radius: f32, //how far above and below the player terrain loads, the render radius still bounds it
+    pub physics_radius: f32, //chunks past this keep their mesh but drop their collider, at most SIMULATION_RADIUS
+    pub autosave_interval_secs: f32, //how often the player is saved and the world files are synced
     pub fog_start_multiplier: f32,
     pub fog_end_multiplier: f32,
     pub distance_fog: bool,
     pub occlusion_culling: bool,
+    #[serde(flatten)]
+    unknown: Map<String, Value>, //fields this build doesnt know, written back as is
+}
+
+impl VersionedSettings for ConfigurableSettings {
+    const VERSION: u32 = 1;
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u32) {
+        self.version = version;
+    }
 }
 
 pub fn load_configurable_settings() -> ConfigurableSettings {
-    read_to_string(CONFIG_PATH)
-        .ok()
-        .and_then(|s| from_str(&s).ok())
-        .unwrap_or_default()
+    load_versioned(CONFIG_PATH)
 }
 
 impl Default for ConfigurableSettings {
     fn default() -> Self {
         ConfigurableSettings {
+            version: Self::VERSION,
             show_chunks: false,
             show_voxels: false,
+            chunk_labels: false,
+            chunk_heatmap: false,
             fps_limit: FpsLimit::default(),
             debug_lod_1: false,
             debug_lod_2: false,
@@ -240,20 +348,18 @@ impl Default for ConfigurableSettings {
             debug_lod_5: false,
             shadows: true,
             render_radius_squared: RenderRadiusSquared::default(),
+            vertical_render_radius: DEFAULT_VERTICAL_RENDER_RADIUS,
+            physics_radius: DEFAULT_PHYSICS_RADIUS,
+            autosave_interval_secs: DEFAULT_AUTOSAVE_INTERVAL_SECS,
             fog_start_multiplier: 0.7,
             fog_end_multiplier: 0.8,
             distance_fog: true,
             occlusion_culling: true,
+            unknown: Map::new(),
         }
     }
 }
 
 pub fn save_configurable_settings(settings: &ConfigurableSettings) {
-    let path = PathBuf::from(CONFIG_PATH);
-    if let Some(parent) = path.parent() {
-        let _ = create_dir_all(parent);
-    }
-    if let Ok(json) = to_string_pretty(settings) {
-        let _ = write(path, json);
-    }
+    save_versioned(CONFIG_PATH, settings);
 }
@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    input::{
+        ButtonState, InputSystems,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+const CONSOLE_TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const MAX_CONSOLE_LINES: usize = 24;
+const FONT_SIZE: f32 = 18.0;
+const BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.1, 0.85);
+
+//a typed command split on whitespace, systems that own a command read these and match on name
+#[derive(Message, Clone, Debug)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+//output goes through a channel so worker threads can print long running results back to the console
+#[derive(Resource)]
+pub struct Console {
+    pub is_open: bool,
+    input: String,
+    lines: VecDeque<String>,
+    output_tx: Sender<String>,
+    output_rx: Receiver<String>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        let (output_tx, output_rx) = unbounded();
+        Self {
+            is_open: false,
+            input: String::new(),
+            lines: VecDeque::with_capacity(MAX_CONSOLE_LINES),
+            output_tx,
+            output_rx,
+        }
+    }
+
+    pub fn print(&self, line: impl Into<String>) {
+        let _ = self.output_tx.send(line.into());
+    }
+
+    pub fn sender(&self) -> Sender<String> {
+        self.output_tx.clone()
+    }
+}
+
+//the console and the message every command system reads, added by the plugins that register commands if it is missing
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Console::new())
+            .add_message::<ConsoleCommand>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(PreUpdate, console_input.after(InputSystems))
+            .add_systems(Update, update_console_text);
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleRoot;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+pub fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            ConsoleRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(BACKGROUND_COLOR),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ConsoleText,
+                Text::new(""),
+                TextFont {
+                    font_size: FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+//runs after input is collected so keys typed into the console can be swallowed before gameplay systems see them
+pub fn console_input(
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut keyboard_events: MessageReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+    mut command_writer: MessageWriter<ConsoleCommand>,
+) {
+    if keyboard.just_pressed(CONSOLE_TOGGLE_KEY) {
+        console.is_open = !console.is_open;
+        console.input.clear();
+        keyboard.reset_all();
+        keyboard_events.clear();
+        return;
+    }
+    if !console.is_open {
+        keyboard_events.clear();
+        return;
+    }
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                let mut words = line.split_whitespace().map(str::to_string);
+                if let Some(name) = words.next() {
+                    console.print(format!("> {line}"));
+                    command_writer.write(ConsoleCommand {
+                        name,
+                        args: words.collect(),
+                    });
+                }
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            _ => {
+                if let Some(text) = &event.text {
+                    console
+                        .input
+                        .extend(text.chars().filter(|c| !c.is_control() && *c != '`'));
+                }
+            }
+        }
+    }
+    keyboard.reset_all();
+}
+
+pub fn update_console_text(
+    mut console: ResMut<Console>,
+    mut root_query: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    while let Ok(line) = console.output_rx.try_recv() {
+        if console.lines.len() == MAX_CONSOLE_LINES {
+            console.lines.pop_front();
+        }
+        console.lines.push_back(line);
+    }
+    let Ok(mut visibility) = root_query.single_mut() else {
+        return;
+    };
+    if !console.is_open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Visible;
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+    let mut contents = String::new();
+    for line in console.lines.iter() {
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    contents.push_str("> ");
+    contents.push_str(&console.input);
+    text.0 = contents;
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::deformable_terrain::driver::{InitialLoadComplete, InitialLoadProgress};
+
+const BAR_WIDTH: f32 = 320.0;
+const BAR_HEIGHT: f32 = 8.0;
+const BACK_COLOR: Color = Color::srgba(0.1, 0.1, 0.12, 0.7);
+const FILL_COLOR: Color = Color::srgba(0.85, 0.85, 0.9, 0.8);
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+#[derive(Component)]
+pub struct LoadingBarFill;
+
+pub fn spawn_loading_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            LoadingBar,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                bottom: Val::Percent(30.0),
+                margin: UiRect::left(Val::Px(-BAR_WIDTH / 2.0)),
+                width: Val::Px(BAR_WIDTH),
+                height: Val::Px(BAR_HEIGHT),
+                ..default()
+            },
+            BackgroundColor(BACK_COLOR),
+        ))
+        .with_child((
+            LoadingBarFill,
+            Node {
+                width: Val::Percent(0.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(FILL_COLOR),
+        ));
+}
+
+//fills with the startup load and goes away for good once it is done
+pub fn update_loading_bar(
+    mut commands: Commands,
+    progress: Res<InitialLoadProgress>,
+    mut initial_load_complete: MessageReader<InitialLoadComplete>,
+    bar_query: Query<Entity, With<LoadingBar>>,
+    mut fill_query: Query<&mut Node, With<LoadingBarFill>>,
+) {
+    if initial_load_complete.read().count() > 0 {
+        for bar in &bar_query {
+            commands.entity(bar).despawn();
+        }
+        return;
+    }
+    if !progress.is_changed() {
+        return;
+    }
+    for mut fill in &mut fill_query {
+        fill.width = Val::Percent(progress.fraction() * 100.0);
+    }
+}
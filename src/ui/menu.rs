@@ -6,7 +6,8 @@ use bevy::{
 };
 
 use crate::{
-    deformable_terrain::plugin::DeformableTerrainConfig,
+    deformable_terrain::{plugin::DeformableTerrainConfig, storage_paths::StoragePaths},
+    player::physics_tuning::{PhysicsTuning, save_physics_tuning},
     ui::configurable_settings::{
         ConfigurableSettings, FpsLimit, MenuFocus, MenuTab, SettingsType,
         save_configurable_settings,
@@ -21,17 +22,20 @@ const INACTIVE_BORDER_COLOR: Color = Color::srgba(0.5, 0.5, 0.7, 1.0);
 const FONT_SIZE: f32 = 24.0;
 const SETTINGS_ROW_HEIGHT: f32 = 40.0;
 const SETTINGS_ROW_BORDER_SIZE: f32 = 3.0;
-const GENERAL_SETTINGS: [SettingsType; 7] = [
+const GENERAL_SETTINGS: [SettingsType; 10] = [
     SettingsType::FpsChange,
     SettingsType::ShadowsToggle,
     SettingsType::RenderRadiusChange,
+    SettingsType::VerticalRenderRadiusChange,
+    SettingsType::PhysicsRadiusChange,
+    SettingsType::AutosaveIntervalChange,
     SettingsType::DistanceFogToggle,
     SettingsType::FogStartMultiplier,
     SettingsType::FogEndMultiplier,
     SettingsType::OcclusionCullingToggle,
 ];
 #[cfg(feature = "debug")]
-const DEBUG_SETTINGS: [SettingsType; 7] = [
+const DEBUG_SETTINGS: [SettingsType; 12] = [
     SettingsType::Lod1Toggle,
     SettingsType::Lod2Toggle,
     SettingsType::Lod3Toggle,
@@ -39,6 +43,11 @@ const DEBUG_SETTINGS: [SettingsType; 7] = [
     SettingsType::Lod5Toggle,
     SettingsType::ShowChunksToggle,
     SettingsType::ShowVoxelsToggle,
+    SettingsType::ChunkLabelsToggle,
+    SettingsType::ChunkHeatmapToggle,
+    SettingsType::GravityChange,
+    SettingsType::JumpImpulseChange,
+    SettingsType::PlayerSpeedChange,
 ];
 
 #[derive(Component)]
@@ -70,6 +79,7 @@ pub fn menu_toggle(
     menu_root_query: Query<Entity, With<MenuRoot>>,
     mut commands: Commands,
     settings: Res<ConfigurableSettings>,
+    physics_tuning: Res<PhysicsTuning>,
     mut settings_state: ResMut<SettingsState>,
 ) {
     if keyboard.just_pressed(KeyCode::Escape) {
@@ -80,7 +90,7 @@ pub fn menu_toggle(
             None => {
                 settings_state.current_focus = MenuFocus::Tabs;
                 settings_state.current_tab = MenuTab::General;
-                spawn_menu(&mut commands, &settings);
+                spawn_menu(&mut commands, &settings, &physics_tuning);
             }
         }
     }
@@ -90,6 +100,8 @@ pub fn menu_update(
     keyboard: Res<ButtonInput<KeyCode>>,
     menu_query: Query<&MenuRoot>,
     mut settings: ResMut<ConfigurableSettings>,
+    mut physics_tuning: ResMut<PhysicsTuning>,
+    storage: Res<StoragePaths>,
     mut winit_settings: ResMut<WinitSettings>,
     mut tab_button_query: Query<
         (&TabButton, &mut BackgroundColor, &mut BorderColor),
@@ -160,8 +172,12 @@ pub fn menu_update(
             }
             MenuFocus::Setting(index) => {
                 let setting = settings_list[index];
-                setting.cycle(&mut settings, dir_next);
-                save_configurable_settings(&settings);
+                setting.cycle(&mut settings, &mut physics_tuning, dir_next);
+                if setting.is_physics() {
+                    save_physics_tuning(&storage.world_dir(), &physics_tuning);
+                } else {
+                    save_configurable_settings(&settings);
+                }
                 if setting == SettingsType::FpsChange {
                     apply_fps_limit(&settings.fps_limit, &mut winit_settings);
                 }
@@ -170,9 +186,14 @@ pub fn menu_update(
                         settings.render_radius_squared.0.to_bits(),
                     )
                 }
+                if setting == SettingsType::VerticalRenderRadiusChange {
+                    DeformableTerrainConfig::set_vertical_render_radius(
+                        settings.vertical_render_radius.powi(2).to_bits(),
+                    )
+                }
                 for (SettingLabel(setting_type), mut text) in text_query.iter_mut() {
                     if *setting_type == setting {
-                        text.0 = setting_type.text(&settings);
+                        text.0 = setting_type.text(&settings, &physics_tuning);
                         break;
                     }
                 }
@@ -197,7 +218,11 @@ pub fn menu_update(
     }
 }
 
-fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
+fn spawn_menu(
+    commands: &mut Commands,
+    settings: &ConfigurableSettings,
+    physics_tuning: &PhysicsTuning,
+) {
     commands
         .spawn((
             Node {
@@ -330,7 +355,10 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                         .with_children(|parent| {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::FpsChange),
-                                                Text(SettingsType::FpsChange.text(settings)),
+                                                Text(
+                                                    SettingsType::FpsChange
+                                                        .text(settings, physics_tuning),
+                                                ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
                                                     ..default()
@@ -356,7 +384,10 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                         .with_children(|parent| {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::ShadowsToggle),
-                                                Text(SettingsType::ShadowsToggle.text(settings)),
+                                                Text(
+                                                    SettingsType::ShadowsToggle
+                                                        .text(settings, physics_tuning),
+                                                ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
                                                     ..default()
@@ -383,7 +414,66 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::RenderRadiusChange),
                                                 Text(
-                                                    SettingsType::RenderRadiusChange.text(settings),
+                                                    SettingsType::RenderRadiusChange
+                                                        .text(settings, physics_tuning),
+                                                ),
+                                                TextFont {
+                                                    font_size: FONT_SIZE,
+                                                    ..default()
+                                                },
+                                                TextColor(Color::WHITE),
+                                            ));
+                                        });
+                                    parent
+                                        .spawn((
+                                            Node {
+                                                width: Val::Percent(100.0),
+                                                height: Val::Px(SETTINGS_ROW_HEIGHT),
+                                                justify_content: JustifyContent::Center,
+                                                align_items: AlignItems::Center,
+                                                border: UiRect::all(Val::Px(
+                                                    SETTINGS_ROW_BORDER_SIZE,
+                                                )),
+                                                ..default()
+                                            },
+                                            BorderColor::all(INACTIVE_BORDER_COLOR),
+                                            SettingRow(SettingsType::PhysicsRadiusChange),
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                SettingLabel(SettingsType::PhysicsRadiusChange),
+                                                Text(
+                                                    SettingsType::PhysicsRadiusChange
+                                                        .text(settings, physics_tuning),
+                                                ),
+                                                TextFont {
+                                                    font_size: FONT_SIZE,
+                                                    ..default()
+                                                },
+                                                TextColor(Color::WHITE),
+                                            ));
+                                        });
+                                    parent
+                                        .spawn((
+                                            Node {
+                                                width: Val::Percent(100.0),
+                                                height: Val::Px(SETTINGS_ROW_HEIGHT),
+                                                justify_content: JustifyContent::Center,
+                                                align_items: AlignItems::Center,
+                                                border: UiRect::all(Val::Px(
+                                                    SETTINGS_ROW_BORDER_SIZE,
+                                                )),
+                                                ..default()
+                                            },
+                                            BorderColor::all(INACTIVE_BORDER_COLOR),
+                                            SettingRow(SettingsType::AutosaveIntervalChange),
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                SettingLabel(SettingsType::AutosaveIntervalChange),
+                                                Text(
+                                                    SettingsType::AutosaveIntervalChange
+                                                        .text(settings, physics_tuning),
                                                 ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
@@ -411,7 +501,8 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::DistanceFogToggle),
                                                 Text(
-                                                    SettingsType::DistanceFogToggle.text(settings),
+                                                    SettingsType::DistanceFogToggle
+                                                        .text(settings, physics_tuning),
                                                 ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
@@ -439,7 +530,8 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::FogStartMultiplier),
                                                 Text(
-                                                    SettingsType::FogStartMultiplier.text(settings),
+                                                    SettingsType::FogStartMultiplier
+                                                        .text(settings, physics_tuning),
                                                 ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
@@ -466,7 +558,10 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                         .with_children(|parent| {
                                             parent.spawn((
                                                 SettingLabel(SettingsType::FogEndMultiplier),
-                                                Text(SettingsType::FogEndMultiplier.text(settings)),
+                                                Text(
+                                                    SettingsType::FogEndMultiplier
+                                                        .text(settings, physics_tuning),
+                                                ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
                                                     ..default()
@@ -494,7 +589,7 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                                 SettingLabel(SettingsType::OcclusionCullingToggle),
                                                 Text(
                                                     SettingsType::OcclusionCullingToggle
-                                                        .text(settings),
+                                                        .text(settings, physics_tuning),
                                                 ),
                                                 TextFont {
                                                     font_size: FONT_SIZE,
@@ -520,7 +615,8 @@ fn spawn_menu(commands: &mut Commands, settings: &ConfigurableSettings) {
                                 ))
                                 .with_children(|parent| {
                                     for &setting_type in DEBUG_SETTINGS.iter() {
-                                        let settings_text = setting_type.text(settings);
+                                        let settings_text =
+                                            setting_type.text(settings, physics_tuning);
                                         parent
                                             .spawn((
                                                 Node {
@@ -1,4 +1,8 @@
 pub mod configurable_settings;
+pub mod console;
 pub mod crosshair;
+pub mod loading_bar;
 pub mod menu;
 pub mod minimap;
+pub mod paint_palette;
+pub mod save_indicator;
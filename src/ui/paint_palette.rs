@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::deformable_terrain::chunk_generator::MaterialCode;
+use crate::deformable_terrain::digging::{PAINT_PALETTE, TerrainBrush};
+
+const SWATCH_SIZE: f32 = 28.0;
+const SWATCH_GAP: f32 = 6.0;
+const BORDER_WIDTH: f32 = 2.0;
+const SELECTED_BORDER_COLOR: Color = Color::srgba(0.85, 0.85, 0.9, 0.9);
+const UNSELECTED_BORDER_COLOR: Color = Color::srgba(0.1, 0.1, 0.12, 0.7);
+const FONT_SIZE: f32 = 12.0;
+const TEXT_COLOR: Color = Color::srgba(0.85, 0.85, 0.9, 0.8);
+
+#[derive(Component)]
+pub struct PaintPalette;
+
+#[derive(Component)]
+pub struct PaletteSwatch(MaterialCode);
+
+//one swatch per palette material along the bottom of the screen, numbered like the keys that pick them
+pub fn spawn_paint_palette(mut commands: Commands) {
+    commands
+        .spawn((
+            PaintPalette,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(12.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(SWATCH_GAP),
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_children(|palette| {
+            for (i, material) in PAINT_PALETTE.into_iter().enumerate() {
+                palette
+                    .spawn((
+                        PaletteSwatch(material),
+                        Node {
+                            width: Val::Px(SWATCH_SIZE),
+                            height: Val::Px(SWATCH_SIZE),
+                            border: UiRect::all(Val::Px(BORDER_WIDTH)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(swatch_color(material)),
+                        BorderColor::all(UNSELECTED_BORDER_COLOR),
+                    ))
+                    .with_child((
+                        Text::new((i + 1).to_string()),
+                        TextFont {
+                            font_size: FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ));
+            }
+        });
+}
+
+//only shown while painting, the material the brush paints with is outlined
+pub fn update_paint_palette(
+    brush: Res<TerrainBrush>,
+    mut palette_query: Query<&mut Visibility, With<PaintPalette>>,
+    mut swatch_query: Query<(&PaletteSwatch, &mut BorderColor)>,
+) {
+    if !brush.is_changed() {
+        return;
+    }
+    for mut visibility in &mut palette_query {
+        visibility.set_if_neq(if brush.painting {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    for (swatch, mut border) in &mut swatch_query {
+        *border = BorderColor::all(if swatch.0 == brush.material {
+            SELECTED_BORDER_COLOR
+        } else {
+            UNSELECTED_BORDER_COLOR
+        });
+    }
+}
+
+pub(crate) fn swatch_color(material: MaterialCode) -> Color {
+    match material {
+        MaterialCode::Grass => Color::srgb(0.28, 0.42, 0.17),
+        MaterialCode::Sand => Color::srgb(0.74, 0.67, 0.48),
+        MaterialCode::Path => Color::srgb(0.55, 0.47, 0.36),
+        MaterialCode::Snow => Color::srgb(0.9, 0.92, 0.96),
+        _ => Color::srgb(0.4, 0.31, 0.22),
+    }
+}
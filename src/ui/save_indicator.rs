@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::deformable_terrain::chunk_history::ChunkModifiedTimes;
+use crate::deformable_terrain::driver::WriteCmdSender;
+use crate::deformable_terrain::file_loader::unix_millis_now;
+
+const SAVED_DISPLAY_MILLIS: u64 = 1500; //how long "saved" stays up after the last write
+const FONT_SIZE: f32 = 16.0;
+const TEXT_COLOR: Color = Color::srgba(0.85, 0.85, 0.9, 0.8);
+
+#[derive(Component)]
+pub struct SaveIndicatorText;
+
+pub fn spawn_save_indicator(mut commands: Commands) {
+    commands.spawn((
+        SaveIndicatorText,
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            ..default()
+        },
+        Text::new(""),
+        TextFont {
+            font_size: FONT_SIZE,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        Visibility::Hidden,
+    ));
+}
+
+//edits are written as they happen, this only shows it
+//saving while the write thread has commands queued, saved for a moment after the newest chunk hit the disk
+pub fn update_save_indicator(
+    write_cmd_sender: Res<WriteCmdSender>,
+    chunk_modified_times: Res<ChunkModifiedTimes>,
+    mut indicator_query: Query<(&mut Text, &mut Visibility), With<SaveIndicatorText>>,
+) {
+    let Ok((mut text, mut visibility)) = indicator_query.single_mut() else {
+        return;
+    };
+    let since_last_write = unix_millis_now().saturating_sub(chunk_modified_times.latest());
+    let label = if !write_cmd_sender.0.is_empty() {
+        Some("saving...")
+    } else if since_last_write < SAVED_DISPLAY_MILLIS {
+        Some("saved")
+    } else {
+        None
+    };
+    match label {
+        Some(label) => {
+            if text.0 != label {
+                text.0 = label.to_string();
+            }
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        None => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//chunk content must not depend on which loader thread generated it or in what order
+use std::sync::Arc;
+use std::thread;
+
+use marching_cubes::chunk_coord::ChunkCoord;
+use marching_cubes::constants::CHUNK_WORLD_SIZE;
+use marching_cubes::deformable_terrain::chunk_generator::{
+    MaterialCode, calculate_chunk_start, chunk_uniformity, get_fbm, sample_terrain_height,
+};
+use marching_cubes::deformable_terrain::density_source::{DensitySource, NoiseTerrain};
+use marching_cubes::deformable_terrain::driver::ChunkBuffers;
+use marching_cubes::deformable_terrain::heightmap_cache::HeightmapCache;
+use marching_cubes::deformable_terrain::plugin::Uniformity;
+use rustc_hash::FxHashMap;
+
+const REGION_RADIUS: i16 = 1; //columns either side of the origin
+const REGION_HALF_HEIGHT: i16 = 2; //chunks above and below the surface chunk
+const THREADS: usize = 4;
+
+type GeneratedChunk = (Uniformity, Vec<i16>, Vec<MaterialCode>);
+
+fn region(terrain: &NoiseTerrain) -> Vec<ChunkCoord> {
+    let surface_y =
+        (sample_terrain_height(0.0, 0.0, &terrain.fbm) / CHUNK_WORLD_SIZE).round() as i16;
+    let mut coords = Vec::new();
+    for x in -REGION_RADIUS..=REGION_RADIUS {
+        for z in -REGION_RADIUS..=REGION_RADIUS {
+            for y in surface_y - REGION_HALF_HEIGHT..=surface_y + REGION_HALF_HEIGHT {
+                coords.push(ChunkCoord(x, y, z));
+            }
+        }
+    }
+    coords
+}
+
+//the same steps a loader thread takes for a chunk that is not on disk, buffers are reused as they come
+fn generate(
+    terrain: &NoiseTerrain,
+    heightmap_cache: &HeightmapCache,
+    chunk_coord: ChunkCoord,
+    chunk_buffers: &mut ChunkBuffers,
+) -> GeneratedChunk {
+    let chunk_start = calculate_chunk_start(&chunk_coord);
+    heightmap_cache.prepare_column(
+        terrain,
+        (chunk_coord.0, chunk_coord.2),
+        &chunk_start,
+        chunk_buffers,
+    );
+    let mut uniformity = terrain.classify(&chunk_start, chunk_buffers);
+    if matches!(uniformity, Uniformity::Air | Uniformity::Dirt) {
+        return (uniformity, Vec::new(), Vec::new());
+    }
+    terrain.fill_chunk(chunk_start, chunk_buffers);
+    if uniformity == Uniformity::Unknown {
+        uniformity = chunk_uniformity(&chunk_buffers.density, &chunk_buffers.material);
+    }
+    (
+        uniformity,
+        chunk_buffers.density.to_vec(),
+        chunk_buffers.material.to_vec(),
+    )
+}
+
+#[test]
+fn region_generates_the_same_on_one_thread_and_many() {
+    let terrain = Arc::new(NoiseTerrain::new(get_fbm()));
+    let coords = region(&terrain);
+    //threads first so the road and structure caches are filled by racing threads rather than in order
+    let heightmap_cache = Arc::new(HeightmapCache::default());
+    let workers: Vec<_> = (0..THREADS)
+        .map(|thread_idx| {
+            //each thread walks its share backwards so neighbors come in a different order than below
+            let share: Vec<_> = coords
+                .iter()
+                .rev()
+                .skip(thread_idx)
+                .step_by(THREADS)
+                .copied()
+                .collect();
+            let terrain = Arc::clone(&terrain);
+            let heightmap_cache = Arc::clone(&heightmap_cache);
+            thread::spawn(move || {
+                let mut chunk_buffers = ChunkBuffers::new();
+                share
+                    .into_iter()
+                    .map(|coord| {
+                        (
+                            coord,
+                            generate(&terrain, &heightmap_cache, coord, &mut chunk_buffers),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let threaded: FxHashMap<_, _> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap())
+        .collect();
+    let heightmap_cache = HeightmapCache::default();
+    let mut chunk_buffers = ChunkBuffers::new();
+    let mut non_uniform = 0;
+    for coord in coords.iter() {
+        let sequential = generate(&terrain, &heightmap_cache, *coord, &mut chunk_buffers);
+        if sequential.0 == Uniformity::NonUniform {
+            non_uniform += 1;
+        }
+        //compared whole rather than with assert_eq so a failure doesnt print every sample
+        assert!(threaded[coord] == sequential, "chunk {coord:?} differs");
+    }
+    assert!(non_uniform > 0, "the region never crossed the surface");
+}
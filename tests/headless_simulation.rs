@@ -0,0 +1,81 @@
+//streams, digs and reloads a world through the whole pipeline, one test since the pipeline is process wide
+use bevy::math::{Vec2, Vec3};
+use marching_cubes::chunk_coord::ChunkCoord;
+use marching_cubes::constants::{
+    HALF_CHUNK, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM_PADDED, SAMPLES_PER_CHUNK_PADDED,
+    VOXEL_WORLD_SIZE,
+};
+use marching_cubes::conversions::flatten_index;
+use marching_cubes::deformable_terrain::chunk_generator::MaterialCode;
+use marching_cubes::deformable_terrain::file_loader::{
+    RegionFiles, load_chunk, load_chunk_index_map,
+};
+use marching_cubes::deformable_terrain::plugin::DeformableTerrainPlugin;
+use marching_cubes::deformable_terrain::sdf_value::SdfValue;
+use marching_cubes::deformable_terrain::simulation::{HeadlessSimulation, SimulationStep};
+use marching_cubes::deformable_terrain::storage_paths::TempDirStorage;
+use rustc_hash::FxHashMap;
+
+const SEED: i32 = 1234;
+const DIG_RADIUS: f32 = 4.0;
+const AWAY: f32 = 400.0; //far enough that the dig site leaves the simulation radius
+
+//the stored density of the padded sample nearest world_pos
+fn stored_density(world: &TempDirStorage, world_pos: Vec3) -> SdfValue {
+    let dir = world.paths().world_dir();
+    let index_map =
+        load_chunk_index_map(&dir, &mut FxHashMap::default(), &mut FxHashMap::default());
+    let chunk_coord = ChunkCoord::from_world_pos(world_pos);
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+    load_chunk(
+        &mut RegionFiles::reader(&dir),
+        &chunk_coord,
+        index_map[&chunk_coord],
+        &mut densities,
+        &mut materials,
+    )
+    .unwrap();
+    let padded_origin = chunk_coord.to_world_center() - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+    let sample = ((world_pos - padded_origin) / VOXEL_WORLD_SIZE)
+        .round()
+        .as_uvec3();
+    assert!(sample.max_element() < SAMPLES_PER_CHUNK_DIM_PADDED as u32);
+    let index = flatten_index(sample.x, sample.y, sample.z, SAMPLES_PER_CHUNK_DIM_PADDED);
+    SdfValue::from_raw(densities[index as usize])
+}
+
+#[test]
+fn dig_survives_streaming_out_and_back() {
+    let world = TempDirStorage::new("headless_simulation");
+    let mut simulation = HeadlessSimulation::new(DeformableTerrainPlugin {
+        new_world_seed: Some(SEED),
+        imposters: false,
+        storage: world.paths().clone(),
+        ..Default::default()
+    });
+    let ground = simulation
+        .ground_height_at(Vec2::ZERO)
+        .expect("the spawn column is loaded by the initial load");
+    let dig_site = Vec3::new(0.0, ground - 1.0, 0.0);
+    assert!(simulation.density_at(dig_site).unwrap() < 0.0);
+    simulation.run(&[
+        SimulationStep::Dig {
+            center: dig_site,
+            radius: DIG_RADIUS,
+        },
+        SimulationStep::Flush,
+    ]);
+    assert!(simulation.density_at(dig_site).unwrap() > 0.0);
+    //the write thread put the dug chunk on disk, not just the chunk map
+    assert!(!stored_density(&world, dig_site).is_solid());
+    let away = Vec3::new(AWAY, ground, 0.0);
+    simulation.run(&[SimulationStep::MoveTo(away)]);
+    assert!(simulation.density_at(away).is_some());
+    //loading around the new center can finish before the old chunks are dropped
+    simulation.wait_for_unload(dig_site);
+    assert!(simulation.density_at(dig_site).is_none());
+    //coming back reads the dug chunk from disk again
+    simulation.run(&[SimulationStep::MoveTo(Vec3::new(0.0, ground, 0.0))]);
+    assert!(simulation.density_at(dig_site).unwrap() > 0.0);
+}
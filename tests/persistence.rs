@@ -0,0 +1,1083 @@
+//dig -> unload -> reload round trip through the real write thread, region files and index delta
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::Arc;
+use std::thread;
+
+use bevy::math::Vec3;
+use marching_cubes::chunk_coord::ChunkCoord;
+use marching_cubes::constants::{
+    HALF_CHUNK, SAMPLES_PER_CHUNK, SAMPLES_PER_CHUNK_DIM, SAMPLES_PER_CHUNK_DIM_PADDED,
+    SAMPLES_PER_CHUNK_PADDED, VOXEL_WORLD_SIZE,
+};
+use marching_cubes::conversions::flatten_index;
+use marching_cubes::deformable_terrain::chunk_generator::{
+    MaterialCode, calculate_chunk_start, chunk_contains_surface, compute_heightmap_gradients,
+    generate_chunk_into_buffers, generate_noise_height_samples, generate_terrain_heights, get_fbm,
+    quantize_f32_to_i16,
+};
+use marching_cubes::deformable_terrain::chunk_history::ChunkModifiedTimes;
+use marching_cubes::deformable_terrain::chunk_summary::ChunkSummaries;
+use marching_cubes::deformable_terrain::column_range_map::ColumnRangeMap;
+use marching_cubes::deformable_terrain::driver::{
+    ChunkBuffers, DirtyRange, WriteCmd, dedicated_write_thread, try_load_chunk,
+};
+use marching_cubes::deformable_terrain::file_loader::{
+    ChunkCodec, RegionFiles, RegionMaps, compact_region_files, deserialize_chunk_data, load_chunk,
+    load_chunk_index_map, load_uniform_chunks, serialize_chunk_data, unix_millis_now,
+};
+use marching_cubes::deformable_terrain::hydrology::apply_hydrology;
+use marching_cubes::deformable_terrain::marching_cubes::mc::{
+    MaterialResolution, mc_mesh_generation,
+};
+use marching_cubes::deformable_terrain::plugin::Uniformity;
+use marching_cubes::deformable_terrain::roads::apply_roads;
+use marching_cubes::deformable_terrain::sdf_value::SdfValue;
+use marching_cubes::deformable_terrain::storage_paths::{StoragePaths, TempDirStorage};
+use marching_cubes::deformable_terrain::terrain_error::TerrainError;
+use marching_cubes::deformable_terrain::write_journal::{
+    WRITE_JOURNAL_FILE, WriteJournal, replay_write_journal,
+};
+use parking_lot::RwLock;
+use rustc_hash::{FxHashMap, FxHasher};
+
+//same steps the loader threads take for a chunk that is not on disk
+fn generate_surface_chunk() -> (ChunkCoord, Box<ChunkBuffers>) {
+    let fbm = get_fbm();
+    let mut chunk_buffers = ChunkBuffers::new();
+    for chunk_y in -40..40 {
+        let chunk_coord = ChunkCoord(0, chunk_y, 0);
+        let chunk_start = calculate_chunk_start(&chunk_coord);
+        let noise_samples = generate_noise_height_samples(chunk_start.x, chunk_start.z, &fbm);
+        generate_terrain_heights(&mut chunk_buffers.heightmap, &noise_samples);
+        compute_heightmap_gradients(
+            &mut chunk_buffers.dhdx,
+            &mut chunk_buffers.dhdz,
+            &noise_samples,
+        );
+        apply_hydrology(&chunk_start, &mut chunk_buffers);
+        apply_roads(&chunk_start, &mut chunk_buffers, &fbm);
+        generate_chunk_into_buffers(chunk_start, &mut chunk_buffers, &fbm);
+        if chunk_contains_surface(&chunk_buffers.density) {
+            return (chunk_coord, chunk_buffers);
+        }
+    }
+    panic!("no surface chunk in the column at the origin");
+}
+
+//carves a sphere of air around a point given in local chunk space, padding included
+fn dig(densities: &mut [i16], center: Vec3, radius: f32) {
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let local = Vec3::new(x as f32, y as f32, z as f32) * VOXEL_WORLD_SIZE
+                    - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+                let carved = quantize_f32_to_i16(radius - local.distance(center));
+                let index =
+                    flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
+                let density = &mut densities[index as usize];
+                *density = (*density).max(carved);
+            }
+        }
+    }
+}
+
+fn mesh_hash(densities: &[i16], materials: &[MaterialCode]) -> u64 {
+    let (vertices, normals, material_ids, indices) = mc_mesh_generation(
+        densities,
+        materials,
+        SAMPLES_PER_CHUNK_DIM,
+        true,
+        densities,
+        MaterialResolution::default(),
+    );
+    let mut hasher = FxHasher::default();
+    for v in vertices.iter().chain(normals.iter()) {
+        v.to_array().map(f32::to_bits).hash(&mut hasher);
+    }
+    material_ids.hash(&mut hasher);
+    indices.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn dug_chunk_survives_unload_and_reload() {
+    let storage = TempDirStorage::new("persistence");
+    let world = storage.paths();
+    let (chunk_coord, mut chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let chunk_modified_times = ChunkModifiedTimes::default();
+    let started = unix_millis_now();
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let chunk_modified_times = chunk_modified_times.clone();
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                chunk_modified_times,
+                None,
+            )
+        })
+    };
+    //first dig appends the chunk, the second one has to find it through the delta and update in place
+    let mut densities = chunk_buffers.density.to_vec();
+    let materials: Arc<[MaterialCode]> = Arc::from(&chunk_buffers.material[..]);
+    dig(&mut densities, Vec3::ZERO, 3.0);
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::clone(&materials),
+            chunk_coord,
+        })
+        .unwrap();
+    dig(&mut densities, Vec3::new(2.0, 1.0, -2.0), 2.5);
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::clone(&materials),
+            chunk_coord,
+        })
+        .unwrap();
+    //same data in a region on the other side of the origin, negative coords must not alias the first one
+    let mirrored_coord = ChunkCoord(-chunk_coord.0 - 1, -chunk_coord.1 - 1, -chunk_coord.2 - 1);
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::clone(&materials),
+            chunk_coord: mirrored_coord,
+        })
+        .unwrap();
+    let expected_mesh = mesh_hash(&densities, &materials);
+    //unload, dropping the sender lets the write thread drain and exit
+    drop(write_tx);
+    write_thread.join().unwrap();
+    //reload in the same session, only the delta knows the chunk
+    chunk_buffers.density.fill(0);
+    let mut region_files_read = RegionFiles::reader(&world.data_dir);
+    let uniformity = try_load_chunk(
+        chunk_coord,
+        &index_map_read,
+        &index_map_delta,
+        &mut region_files_read,
+        &mut chunk_buffers,
+    )
+    .unwrap();
+    assert_eq!(uniformity, Uniformity::NonUniform);
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    assert_eq!(&chunk_buffers.material[..], &materials[..]);
+    assert_eq!(
+        mesh_hash(&chunk_buffers.density, &chunk_buffers.material),
+        expected_mesh
+    );
+    //reload in a new session, the chunks must come back from the region headers alone
+    let mut summaries = FxHashMap::default();
+    let mut modified_times = FxHashMap::default();
+    let index_map = load_chunk_index_map(&world.data_dir, &mut summaries, &mut modified_times);
+    assert_eq!(index_map.len(), 2);
+    //both chunks count as changed since the session started and the headers agree with the write thread
+    let changed: FxHashMap<ChunkCoord, u64> = chunk_modified_times
+        .iter_chunks_modified_since(started)
+        .collect();
+    assert_eq!(changed, modified_times);
+    assert_eq!(changed.len(), 2);
+    assert!(
+        chunk_modified_times
+            .iter_chunks_modified_since(chunk_modified_times.latest() + 1)
+            .next()
+            .is_none()
+    );
+    let mut region_files_read = RegionFiles::reader(&world.data_dir);
+    for coord in [chunk_coord, mirrored_coord] {
+        assert!(summaries.contains_key(&coord));
+        let mut reloaded_densities = vec![0i16; densities.len()];
+        let mut reloaded_materials = vec![MaterialCode::Air; materials.len()];
+        load_chunk(
+            &mut region_files_read,
+            &coord,
+            index_map[&coord],
+            &mut reloaded_densities,
+            &mut reloaded_materials,
+        )
+        .unwrap();
+        assert_eq!(reloaded_densities, densities);
+        assert_eq!(&reloaded_materials[..], &materials[..]);
+        assert_eq!(
+            mesh_hash(&reloaded_densities, &reloaded_materials),
+            expected_mesh
+        );
+    }
+}
+
+#[test]
+fn dug_out_chunk_is_removed_and_its_sectors_compacted_away() {
+    let storage = TempDirStorage::new("compaction");
+    let world = storage.paths();
+    let (chunk_coord, chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                None,
+            )
+        })
+    };
+    //three neighbors in one region, the middle one is dug out so the last one has to move down
+    let densities: Arc<[i16]> = Arc::from(&chunk_buffers.density[..]);
+    let materials: Arc<[MaterialCode]> = Arc::from(&chunk_buffers.material[..]);
+    let coords = [0, 1, 2].map(|i| (chunk_coord.0 + i, chunk_coord.1, chunk_coord.2));
+    for coord in coords {
+        write_tx
+            .send(WriteCmd::UpdateNonUniform {
+                densities: Arc::clone(&densities),
+                materials: Arc::clone(&materials),
+                chunk_coord: coord,
+            })
+            .unwrap();
+    }
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(vec![SdfValue::AIR.0; SAMPLES_PER_CHUNK_PADDED]),
+            materials: Arc::clone(&materials),
+            chunk_coord: coords[1],
+        })
+        .unwrap();
+    drop(write_tx);
+    write_thread.join().unwrap();
+    //same session, the delta says it is air now
+    let mut chunk_buffers = ChunkBuffers::new();
+    let uniformity = try_load_chunk(
+        coords[1],
+        &index_map_read,
+        &index_map_delta,
+        &mut RegionFiles::reader(&world.data_dir),
+        &mut chunk_buffers,
+    )
+    .unwrap();
+    assert_eq!(uniformity, Uniformity::Air);
+    assert_eq!(
+        std::fs::metadata(world.data_dir.join("air_compression_data.txt"))
+            .unwrap()
+            .len(),
+        6
+    );
+    //next session, only the headers and the air file know about it
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    assert_eq!(index_map.len(), 2);
+    assert!(!index_map.contains_key(&coords[1]));
+    let report = compact_region_files(&world.data_dir, 0.0).unwrap();
+    assert_eq!(report.regions, 1);
+    assert_eq!(report.chunks, 2);
+    assert!(report.bytes_after < report.bytes_before);
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    let mut region_files_read = RegionFiles::reader(&world.data_dir);
+    for coord in [coords[0], coords[2]] {
+        let mut reloaded_densities = vec![0i16; densities.len()];
+        let mut reloaded_materials = vec![MaterialCode::Air; materials.len()];
+        load_chunk(
+            &mut region_files_read,
+            &coord,
+            index_map[&coord],
+            &mut reloaded_densities,
+            &mut reloaded_materials,
+        )
+        .unwrap();
+        assert_eq!(&reloaded_densities[..], &densities[..]);
+        assert_eq!(&reloaded_materials[..], &materials[..]);
+    }
+    //already packed, a second pass leaves it alone
+    assert_eq!(
+        compact_region_files(&world.data_dir, 0.0).unwrap().regions,
+        0
+    );
+}
+
+#[test]
+fn mostly_uniform_chunk_is_run_length_encoded_and_surface_chunk_round_trips() {
+    let (_, chunk_buffers) = generate_surface_chunk();
+    //a flat floor near the bottom, every row of samples is one repeat
+    let floor = -HALF_CHUNK + 2.0;
+    let mut floor_densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    for (i, density) in floor_densities.iter_mut().enumerate() {
+        let y = (i / SAMPLES_PER_CHUNK_DIM_PADDED) % SAMPLES_PER_CHUNK_DIM_PADDED;
+        let local_y = y as f32 * VOXEL_WORLD_SIZE - HALF_CHUNK - VOXEL_WORLD_SIZE;
+        *density = quantize_f32_to_i16(local_y - floor);
+    }
+    let mut floor_materials = vec![MaterialCode::Air; chunk_buffers.material.len()];
+    for (i, material) in floor_materials.iter_mut().enumerate() {
+        let y = (i / SAMPLES_PER_CHUNK_DIM) % SAMPLES_PER_CHUNK_DIM;
+        if (y as f32) * VOXEL_WORLD_SIZE - HALF_CHUNK < floor {
+            *material = MaterialCode::Dirt;
+        }
+    }
+    let mut serial_buffer = Vec::new();
+    let mut raw_buffer = Vec::new();
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    let mut materials = vec![MaterialCode::Air; chunk_buffers.material.len()];
+    for (source_densities, source_materials, mostly_uniform) in [
+        (&floor_densities[..], &floor_materials[..], true),
+        (
+            &chunk_buffers.density[..],
+            &chunk_buffers.material[..],
+            false,
+        ),
+    ] {
+        let blob = serialize_chunk_data(
+            source_densities,
+            source_materials,
+            ChunkCodec::RunLength,
+            &mut serial_buffer,
+        );
+        if mostly_uniform {
+            assert_eq!(blob[0], ChunkCodec::RunLength as u8);
+            assert!(blob.len() < source_densities.len() / 8);
+        }
+        deserialize_chunk_data(blob, &mut raw_buffer, &mut densities, &mut materials).unwrap();
+        assert_eq!(&densities[..], source_densities);
+        assert_eq!(&materials[..], source_materials);
+    }
+}
+
+#[test]
+fn run_length_chunk_with_material_runs_cut_or_overrun_is_corrupt() {
+    //half dirt half air, the material runs end the payload
+    let densities: Vec<i16> = (0..SAMPLES_PER_CHUNK_PADDED)
+        .map(|i| {
+            if i < SAMPLES_PER_CHUNK_PADDED / 2 {
+                -1
+            } else {
+                1
+            }
+        })
+        .collect();
+    let materials: Vec<MaterialCode> = (0..SAMPLES_PER_CHUNK)
+        .map(|i| {
+            if i < SAMPLES_PER_CHUNK / 2 {
+                MaterialCode::Dirt
+            } else {
+                MaterialCode::Air
+            }
+        })
+        .collect();
+    let blob = serialize_chunk_data(
+        &densities,
+        &materials,
+        ChunkCodec::RunLength,
+        &mut Vec::new(),
+    )
+    .to_vec();
+    assert_eq!(blob[0], ChunkCodec::RunLength as u8);
+    //payload_len in the header has to follow the payload or the blob fails before the runs are read
+    let with_payload = |payload: &[u8]| {
+        let mut edited = blob[..9].to_vec();
+        edited[5..9].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        edited.extend_from_slice(payload);
+        edited
+    };
+    let payload = &blob[9..];
+    let mut overrun = payload.to_vec();
+    overrun.extend_from_slice(&[MaterialCode::Dirt as u8, 1, 0]);
+    let mut densities_out = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    for (corrupt, what) in [
+        (
+            with_payload(&payload[..payload.len() - 1]),
+            "a run cut short",
+        ),
+        (
+            with_payload(&payload[..payload.len() - 3]),
+            "the last run missing",
+        ),
+        (with_payload(&overrun), "a run past the end"),
+    ] {
+        let mut materials_out = vec![MaterialCode::Snow; SAMPLES_PER_CHUNK];
+        assert!(
+            matches!(
+                deserialize_chunk_data(
+                    &corrupt,
+                    &mut Vec::new(),
+                    &mut densities_out,
+                    &mut materials_out
+                ),
+                Err(TerrainError::CorruptChunk(_))
+            ),
+            "{what} loaded"
+        );
+    }
+}
+
+#[test]
+fn journaled_write_is_replayed_after_a_crash() {
+    let storage = TempDirStorage::new("write_journal");
+    let world = storage.paths();
+    let (chunk_coord, mut chunk_buffers) = generate_surface_chunk();
+    let mut densities = chunk_buffers.density.to_vec();
+    dig(&mut densities, Vec3::ZERO, 3.0);
+    let materials: Arc<[MaterialCode]> = Arc::from(&chunk_buffers.material[..]);
+    //the game died after the journal sync, before the region files were touched
+    WriteJournal::open(&world.data_dir)
+        .unwrap()
+        .append(
+            &[WriteCmd::UpdateNonUniform {
+                densities: Arc::from(&densities[..]),
+                materials: Arc::clone(&materials),
+                chunk_coord,
+            }],
+            &mut Vec::new(),
+        )
+        .unwrap();
+    //and a second record was only half appended
+    let mut journal_file = world.open(WRITE_JOURNAL_FILE).unwrap();
+    journal_file.seek(SeekFrom::End(0)).unwrap();
+    journal_file.write_all(&[7; 20]).unwrap();
+    assert_eq!(replay_write_journal(&world.data_dir).unwrap(), 1);
+    assert_eq!(
+        std::fs::metadata(world.data_dir.join(WRITE_JOURNAL_FILE))
+            .unwrap()
+            .len(),
+        0
+    );
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    load_chunk(
+        &mut RegionFiles::reader(&world.data_dir),
+        &chunk_coord,
+        index_map[&chunk_coord],
+        &mut chunk_buffers.density,
+        &mut chunk_buffers.material,
+    )
+    .unwrap();
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    assert_eq!(&chunk_buffers.material[..], &materials[..]);
+    //nothing left to replay on the next start
+    assert_eq!(replay_write_journal(&world.data_dir).unwrap(), 0);
+}
+
+#[test]
+fn replayed_write_takes_the_chunk_out_of_its_uniform_file() {
+    let storage = TempDirStorage::new("journal_uniform");
+    let world = storage.paths();
+    let (chunk_coord, chunk_buffers) = generate_surface_chunk();
+    //the chunk was stored as uniform air before a build gave it a surface
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    write_tx
+        .send(WriteCmd::WriteUniformAir { chunk_coord })
+        .unwrap();
+    drop(write_tx);
+    dedicated_write_thread(
+        write_rx,
+        Arc::new(RwLock::new(FxHashMap::default())),
+        RegionFiles::writer(&world.data_dir).unwrap(),
+        world.open("air_compression_data.txt").unwrap(),
+        world.open("dirt_compression_data.txt").unwrap(),
+        VecDeque::new(),
+        VecDeque::new(),
+        Arc::new(FxHashMap::default()),
+        ChunkSummaries::default(),
+        ChunkModifiedTimes::default(),
+        None,
+    );
+    //the game died after the journal sync, the RemoveUniformAir sent with the build never landed
+    WriteJournal::open(&world.data_dir)
+        .unwrap()
+        .append(
+            &[WriteCmd::UpdateNonUniform {
+                densities: Arc::from(&chunk_buffers.density[..]),
+                materials: Arc::from(&chunk_buffers.material[..]),
+                chunk_coord,
+            }],
+            &mut Vec::new(),
+        )
+        .unwrap();
+    assert_eq!(replay_write_journal(&world.data_dir).unwrap(), 1);
+    let mut column_range_map = ColumnRangeMap::new();
+    load_uniform_chunks(
+        &mut world.open("air_compression_data.txt").unwrap(),
+        Uniformity::Air,
+        &mut column_range_map,
+    )
+    .unwrap();
+    assert_eq!(
+        column_range_map
+            .get_column(chunk_coord.0, chunk_coord.2)
+            .uniformity_at_y(chunk_coord.1),
+        Uniformity::Unknown
+    );
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    let mut densities = vec![0i16; SAMPLES_PER_CHUNK_PADDED];
+    let mut materials = vec![MaterialCode::Air; SAMPLES_PER_CHUNK];
+    load_chunk(
+        &mut RegionFiles::reader(&world.data_dir),
+        &chunk_coord,
+        index_map[&chunk_coord],
+        &mut densities,
+        &mut materials,
+    )
+    .unwrap();
+    assert_eq!(&densities[..], &chunk_buffers.density[..]);
+}
+
+#[test]
+fn patched_chunk_reloads_equal_to_a_full_write() {
+    let storage = TempDirStorage::new("patch");
+    let world = storage.paths();
+    let (chunk_coord, chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                None,
+            )
+        })
+    };
+    let materials: Arc<[MaterialCode]> = Arc::from(&chunk_buffers.material[..]);
+    let mut densities = chunk_buffers.density.to_vec();
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::clone(&materials),
+            chunk_coord,
+        })
+        .unwrap();
+    //the first dig rewrites the compressed chunk raw, the ones after it only patch their rows
+    for center in [
+        Vec3::ZERO,
+        Vec3::new(2.0, 1.0, -3.0),
+        Vec3::new(-4.5, 0.5, 4.0),
+    ] {
+        let radius = 2.0;
+        dig(&mut densities, center, radius);
+        let to_sample = |p: Vec3| {
+            let sample = ((p + Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE)) / VOXEL_WORLD_SIZE)
+                .clamp(
+                    Vec3::ZERO,
+                    Vec3::splat((SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32),
+                );
+            (sample.x as usize, sample.y as usize, sample.z as usize)
+        };
+        write_tx
+            .send(WriteCmd::PatchNonUniform {
+                densities: Arc::from(&densities[..]),
+                materials: Arc::clone(&materials),
+                chunk_coord,
+                dirty: DirtyRange {
+                    min: to_sample((center - radius - VOXEL_WORLD_SIZE).floor()),
+                    max: to_sample((center + radius + VOXEL_WORLD_SIZE).ceil()),
+                },
+            })
+            .unwrap();
+    }
+    drop(write_tx);
+    write_thread.join().unwrap();
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    let mut reloaded_densities = vec![0i16; densities.len()];
+    let mut reloaded_materials = vec![MaterialCode::Air; materials.len()];
+    load_chunk(
+        &mut RegionFiles::reader(&world.data_dir),
+        &chunk_coord,
+        index_map[&chunk_coord],
+        &mut reloaded_densities,
+        &mut reloaded_materials,
+    )
+    .unwrap();
+    assert_eq!(&reloaded_densities[..], &densities[..]);
+    assert_eq!(&reloaded_materials[..], &materials[..]);
+}
+
+//fills a sphere around a point in local chunk space and gives the samples it turned solid material
+//overwrite like paint_sample, the build brush overwrites, flatten and smooth only fill samples without a material
+fn build(
+    densities: &mut [i16],
+    materials: &mut [MaterialCode],
+    center: Vec3,
+    radius: f32,
+    material: MaterialCode,
+    overwrite: bool,
+) {
+    for z in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+        for y in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+            for x in 0..SAMPLES_PER_CHUNK_DIM_PADDED {
+                let local = Vec3::new(x as f32, y as f32, z as f32) * VOXEL_WORLD_SIZE
+                    - Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE);
+                let filled = quantize_f32_to_i16(local.distance(center) - radius);
+                let index =
+                    flatten_index(x as u32, y as u32, z as u32, SAMPLES_PER_CHUNK_DIM_PADDED);
+                let density = &mut densities[index as usize];
+                let was_solid = SdfValue::from_raw(*density).is_solid();
+                *density = (*density).min(filled);
+                let interior = 1..=SAMPLES_PER_CHUNK_DIM;
+                if !was_solid
+                    && SdfValue::from_raw(*density).is_solid()
+                    && [x, y, z].iter().all(|i| interior.contains(i))
+                {
+                    let material_index = flatten_index(
+                        x as u32 - 1,
+                        y as u32 - 1,
+                        z as u32 - 1,
+                        SAMPLES_PER_CHUNK_DIM,
+                    );
+                    let current = &mut materials[material_index as usize];
+                    if overwrite || *current == MaterialCode::Air {
+                        *current = material;
+                    }
+                }
+            }
+        }
+    }
+}
+
+//padded samples a sphere around a point in local chunk space can touch
+fn sphere_dirty_range(center: Vec3, radius: f32) -> DirtyRange {
+    let to_sample = |p: Vec3| {
+        let sample = ((p + Vec3::splat(HALF_CHUNK + VOXEL_WORLD_SIZE)) / VOXEL_WORLD_SIZE).clamp(
+            Vec3::ZERO,
+            Vec3::splat((SAMPLES_PER_CHUNK_DIM_PADDED - 1) as f32),
+        );
+        (sample.x as usize, sample.y as usize, sample.z as usize)
+    };
+    DirtyRange {
+        min: to_sample((center - radius - VOXEL_WORLD_SIZE).floor()),
+        max: to_sample((center + radius + VOXEL_WORLD_SIZE).ceil()),
+    }
+}
+
+#[test]
+fn material_built_into_a_hot_chunk_survives_a_reload() {
+    let storage = TempDirStorage::new("build");
+    let world = storage.paths();
+    let (chunk_coord, chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                None,
+            )
+        })
+    };
+    let mut materials = chunk_buffers.material.to_vec();
+    let mut densities = chunk_buffers.density.to_vec();
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&materials[..]),
+            chunk_coord,
+        })
+        .unwrap();
+    //the dig rewrites the compressed chunk raw, the build into the hole only patches the hot chunk
+    dig(&mut densities, Vec3::ZERO, 3.0);
+    write_tx
+        .send(WriteCmd::PatchNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&materials[..]),
+            chunk_coord,
+            dirty: sphere_dirty_range(Vec3::ZERO, 3.0),
+        })
+        .unwrap();
+    build(
+        &mut densities,
+        &mut materials,
+        Vec3::ZERO,
+        2.0,
+        MaterialCode::Snow,
+        true,
+    );
+    write_tx
+        .send(WriteCmd::PatchNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&materials[..]),
+            chunk_coord,
+            dirty: sphere_dirty_range(Vec3::ZERO, 2.0),
+        })
+        .unwrap();
+    //flatten and smooth raise the surface into open air, those samples had no material before
+    let open_air = (0..SAMPLES_PER_CHUNK)
+        .find(|&i| {
+            let (x, y, z) = (
+                i % SAMPLES_PER_CHUNK_DIM,
+                i / SAMPLES_PER_CHUNK_DIM % SAMPLES_PER_CHUNK_DIM,
+                i / (SAMPLES_PER_CHUNK_DIM * SAMPLES_PER_CHUNK_DIM),
+            );
+            let padded = flatten_index(
+                x as u32 + 1,
+                y as u32 + 1,
+                z as u32 + 1,
+                SAMPLES_PER_CHUNK_DIM_PADDED,
+            );
+            materials[i] == MaterialCode::Air
+                && !SdfValue::from_raw(densities[padded as usize]).is_solid()
+                && [x, y, z]
+                    .iter()
+                    .all(|c| (2..SAMPLES_PER_CHUNK_DIM - 2).contains(c))
+        })
+        .map(|i| {
+            let sample = Vec3::new(
+                (i % SAMPLES_PER_CHUNK_DIM) as f32,
+                (i / SAMPLES_PER_CHUNK_DIM % SAMPLES_PER_CHUNK_DIM) as f32,
+                (i / (SAMPLES_PER_CHUNK_DIM * SAMPLES_PER_CHUNK_DIM)) as f32,
+            );
+            sample * VOXEL_WORLD_SIZE - Vec3::splat(HALF_CHUNK)
+        })
+        .expect("a surface chunk has open air");
+    build(
+        &mut densities,
+        &mut materials,
+        open_air,
+        VOXEL_WORLD_SIZE,
+        MaterialCode::Sand,
+        false,
+    );
+    write_tx
+        .send(WriteCmd::PatchNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&materials[..]),
+            chunk_coord,
+            dirty: sphere_dirty_range(open_air, VOXEL_WORLD_SIZE),
+        })
+        .unwrap();
+    drop(write_tx);
+    write_thread.join().unwrap();
+    let index_map = load_chunk_index_map(
+        &world.data_dir,
+        &mut FxHashMap::default(),
+        &mut FxHashMap::default(),
+    );
+    let mut reloaded_densities = vec![0i16; densities.len()];
+    let mut reloaded_materials = vec![MaterialCode::Air; materials.len()];
+    load_chunk(
+        &mut RegionFiles::reader(&world.data_dir),
+        &chunk_coord,
+        index_map[&chunk_coord],
+        &mut reloaded_densities,
+        &mut reloaded_materials,
+    )
+    .unwrap();
+    assert_eq!(&reloaded_densities[..], &densities[..]);
+    assert_eq!(&reloaded_materials[..], &materials[..]);
+    assert!(reloaded_materials.contains(&MaterialCode::Snow));
+    assert!(reloaded_materials.contains(&MaterialCode::Sand));
+}
+
+#[test]
+fn sync_acks_once_earlier_writes_are_on_disk_while_the_channel_stays_open() {
+    let storage = TempDirStorage::new("sync");
+    let world = storage.paths();
+    let (chunk_coord, mut chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        let journal = WriteJournal::open(&world.data_dir).unwrap();
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                Some(journal),
+            )
+        })
+    };
+    let densities = chunk_buffers.density.to_vec();
+    let materials = chunk_buffers.material.to_vec();
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&materials[..]),
+            chunk_coord,
+        })
+        .unwrap();
+    let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+    write_tx
+        .send(WriteCmd::Sync {
+            done: Some(done_tx),
+        })
+        .unwrap();
+    done_rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .unwrap();
+    //like the game at exit, the sender is still alive
+    let offset = index_map_delta.read()[&chunk_coord];
+    load_chunk(
+        &mut RegionFiles::reader(&world.data_dir),
+        &chunk_coord,
+        offset,
+        &mut chunk_buffers.density,
+        &mut chunk_buffers.material,
+    )
+    .unwrap();
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    assert_eq!(&chunk_buffers.material[..], &materials[..]);
+    assert_eq!(
+        std::fs::metadata(world.data_dir.join(WRITE_JOURNAL_FILE))
+            .unwrap()
+            .len(),
+        0
+    );
+    drop(write_tx);
+    write_thread.join().unwrap();
+}
+
+#[test]
+fn mapped_reader_sees_chunks_appended_after_the_region_was_mapped() {
+    let storage = TempDirStorage::new("mapped_reads");
+    let world = storage.paths();
+    let (chunk_coord, mut chunk_buffers) = generate_surface_chunk();
+    //flipping the lowest bit stays in the same region
+    let neighbor_coord = (chunk_coord.0 ^ 1, chunk_coord.1, chunk_coord.2);
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                None,
+            )
+        })
+    };
+    let write_and_wait = |densities: &[i16], materials: &[MaterialCode], chunk_coord| {
+        write_tx
+            .send(WriteCmd::UpdateNonUniform {
+                densities: Arc::from(densities),
+                materials: Arc::from(materials),
+                chunk_coord,
+            })
+            .unwrap();
+        let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+        write_tx
+            .send(WriteCmd::Sync {
+                done: Some(done_tx),
+            })
+            .unwrap();
+        done_rx
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .unwrap();
+    };
+    let densities = chunk_buffers.density.to_vec();
+    let materials = chunk_buffers.material.to_vec();
+    write_and_wait(&densities, &materials, chunk_coord);
+    let mut region_files_read = RegionFiles::mapped_reader(&world.data_dir, RegionMaps::default());
+    let mut load = |chunk_coord, chunk_buffers: &mut ChunkBuffers| {
+        let uniformity = try_load_chunk(
+            chunk_coord,
+            &index_map_read,
+            &index_map_delta,
+            &mut region_files_read,
+            chunk_buffers,
+        )
+        .unwrap();
+        assert_eq!(uniformity, Uniformity::NonUniform);
+    };
+    chunk_buffers.density.fill(0);
+    load(chunk_coord, &mut chunk_buffers);
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    //the neighbor lands past the end of the mapping the first load made
+    let mut dug_densities = densities.clone();
+    dig(&mut dug_densities, Vec3::ZERO, 3.0);
+    write_and_wait(&dug_densities, &materials, neighbor_coord);
+    load(neighbor_coord, &mut chunk_buffers);
+    assert_eq!(&chunk_buffers.density[..], &dug_densities[..]);
+    assert_eq!(&chunk_buffers.material[..], &materials[..]);
+    load(chunk_coord, &mut chunk_buffers);
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    drop(write_tx);
+    write_thread.join().unwrap();
+}
+
+#[test]
+fn unreadable_chunk_stays_unloaded_and_only_a_corrupt_one_is_generated_again() {
+    let storage = TempDirStorage::new("failed_reads");
+    let world = storage.paths();
+    let (chunk_coord, mut chunk_buffers) = generate_surface_chunk();
+    let index_map_read = Arc::new(FxHashMap::default());
+    let index_map_delta = Arc::new(RwLock::new(FxHashMap::default()));
+    let (write_tx, write_rx) = crossbeam_channel::unbounded();
+    let write_thread = {
+        let index_map_read = Arc::clone(&index_map_read);
+        let index_map_delta = Arc::clone(&index_map_delta);
+        let (region_files, air, dirt) = (
+            RegionFiles::writer(&world.data_dir).unwrap(),
+            world.open("air_compression_data.txt").unwrap(),
+            world.open("dirt_compression_data.txt").unwrap(),
+        );
+        thread::spawn(move || {
+            dedicated_write_thread(
+                write_rx,
+                index_map_delta,
+                region_files,
+                air,
+                dirt,
+                VecDeque::new(),
+                VecDeque::new(),
+                index_map_read,
+                ChunkSummaries::default(),
+                ChunkModifiedTimes::default(),
+                None,
+            )
+        })
+    };
+    let mut densities = chunk_buffers.density.to_vec();
+    dig(&mut densities, Vec3::ZERO, 3.0);
+    write_tx
+        .send(WriteCmd::UpdateNonUniform {
+            densities: Arc::from(&densities[..]),
+            materials: Arc::from(&chunk_buffers.material[..]),
+            chunk_coord,
+        })
+        .unwrap();
+    drop(write_tx);
+    write_thread.join().unwrap();
+    let offset = index_map_delta.read()[&chunk_coord];
+    let region_path = std::fs::read_dir(world.data_dir.join("regions"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let load = |chunk_buffers: &mut ChunkBuffers| {
+        try_load_chunk(
+            chunk_coord,
+            &index_map_read,
+            &index_map_delta,
+            &mut RegionFiles::reader(&world.data_dir),
+            chunk_buffers,
+        )
+    };
+    //a region that cant be opened right now may be back later, the dug chunk must not be replaced by noise
+    let moved_path = region_path.with_extension("moved");
+    std::fs::rename(&region_path, &moved_path).unwrap();
+    let read = load(&mut chunk_buffers);
+    assert!(
+        matches!(read, Err(TerrainError::Open(..))),
+        "expected the open error, got {read:?}"
+    );
+    std::fs::rename(&moved_path, &region_path).unwrap();
+    assert_eq!(load(&mut chunk_buffers).unwrap(), Uniformity::NonUniform);
+    assert_eq!(&chunk_buffers.density[..], &densities[..]);
+    //an unknown codec byte reads the same every time, so that chunk is generated again
+    let mut region_file = StoragePaths::open_file(&region_path, true).unwrap();
+    region_file.seek(SeekFrom::Start(offset)).unwrap();
+    region_file.write_all(&[0xFF]).unwrap();
+    drop(region_file);
+    assert_eq!(load(&mut chunk_buffers).unwrap(), Uniformity::Unknown);
+}